@@ -1,6 +1,10 @@
 use ebi_arithmetic::{
     Fraction, MaybeExact, One, Round, Signed, Zero,
     anyhow::{self, anyhow},
+    malachite::base::{
+        num::{conversion::traits::RoundingFrom, logic::traits::SignificantBits},
+        rounding_modes::RoundingMode,
+    },
 };
 use std::{
     cmp::Ordering,
@@ -9,6 +13,8 @@ use std::{
     ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign},
 };
 
+use crate::error::ArithmeticError;
+
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum AbnormalFraction {
     Normal(Fraction),
@@ -53,12 +59,50 @@ impl AbnormalFraction {
         }
     }
 
-    pub(crate) fn both_normal(&self, rhs: &Self) -> bool {
-        match (self, rhs) {
-            (AbnormalFraction::Normal(_), AbnormalFraction::Normal(_)) => true,
-            _ => false,
+    /// The nearest approximate number, or `None` if approximate fractions are not available.
+    pub fn to_approx(&self) -> Option<Self> {
+        match self {
+            AbnormalFraction::Normal(f) => {
+                let approx = match f.approx_ref() {
+                    Ok(approx) => *approx,
+                    Err(_) => f64::rounding_from(f.exact_ref().ok()?, RoundingMode::Nearest).0,
+                };
+                Fraction::try_to_approx(approx).ok().map(Self::Normal)
+            }
+            AbnormalFraction::Infinite | AbnormalFraction::NegInfinite | AbnormalFraction::NaN => {
+                Some(self.clone())
+            }
+        }
+    }
+
+    /// The number of significant bits of the numerator and the denominator together, for an
+    /// exact value; 0 otherwise.
+    pub fn complexity(&self) -> u64 {
+        match self {
+            AbnormalFraction::Normal(f) => {
+                f.exact_ref().map_or(0, |exact| exact.significant_bits())
+            }
+            AbnormalFraction::Infinite | AbnormalFraction::NegInfinite | AbnormalFraction::NaN => 0,
+        }
+    }
+
+    /// The exact value, like [`MaybeExact::exact_ref`] but with a typed error: the number
+    /// must be finite and exact.
+    pub fn exact_value(&self) -> Result<&Rational, ArithmeticError> {
+        match self {
+            AbnormalFraction::Normal(f) => f.exact_ref().map_err(|_| ArithmeticError::NotExact),
+            AbnormalFraction::Infinite | AbnormalFraction::NegInfinite | AbnormalFraction::NaN => {
+                Err(ArithmeticError::NotFinite)
+            }
         }
     }
+
+    pub(crate) fn both_normal(&self, rhs: &Self) -> bool {
+        matches!(
+            (self, rhs),
+            (AbnormalFraction::Normal(_), AbnormalFraction::Normal(_))
+        )
+    }
 }
 
 impl Display for AbnormalFraction {
@@ -76,8 +120,7 @@ impl Signed for AbnormalFraction {
     fn abs(self) -> Self {
         match self {
             AbnormalFraction::Normal(f) => AbnormalFraction::Normal(f.abs()),
-            AbnormalFraction::Infinite => Self::NegInfinite,
-            AbnormalFraction::NegInfinite => Self::Infinite,
+            AbnormalFraction::Infinite | AbnormalFraction::NegInfinite => Self::Infinite,
             AbnormalFraction::NaN => Self::NaN,
         }
     }
@@ -235,18 +278,26 @@ impl MaybeExact for AbnormalFraction {
 
 impl PartialOrd for AbnormalFraction {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A total order, consistent with equality: negative infinity, the normal numbers, infinity,
+/// and NaN last. Infinity equals itself, so that infinite costs can be compared, e.g. in
+/// [`astar`](crate::astar::astar).
+impl Ord for AbnormalFraction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let rank = |f: &AbnormalFraction| match f {
+            AbnormalFraction::NegInfinite => 0,
+            AbnormalFraction::Normal(_) => 1,
+            AbnormalFraction::Infinite => 2,
+            AbnormalFraction::NaN => 3,
+        };
         match (self, other) {
-            (AbnormalFraction::Normal(f1), AbnormalFraction::Normal(f2)) => f1.partial_cmp(f2),
-            (AbnormalFraction::Normal(_), AbnormalFraction::Infinite) => Some(Ordering::Less),
-            (AbnormalFraction::Normal(_), AbnormalFraction::NegInfinite) => Some(Ordering::Greater),
-            (AbnormalFraction::Infinite, AbnormalFraction::Normal(_)) => Some(Ordering::Greater),
-            (AbnormalFraction::Infinite, AbnormalFraction::Infinite) => None,
-            (AbnormalFraction::Infinite, AbnormalFraction::NegInfinite) => Some(Ordering::Greater),
-            (AbnormalFraction::NegInfinite, AbnormalFraction::Normal(_)) => Some(Ordering::Less),
-            (AbnormalFraction::NegInfinite, AbnormalFraction::Infinite) => Some(Ordering::Less),
-            (AbnormalFraction::NegInfinite, AbnormalFraction::NegInfinite) => None,
-            (_, AbnormalFraction::NaN) => None,
-            (AbnormalFraction::NaN, _) => None,
+            (AbnormalFraction::Normal(f1), AbnormalFraction::Normal(f2)) => {
+                f1.partial_cmp(f2).unwrap_or(Ordering::Equal)
+            }
+            _ => rank(self).cmp(&rank(other)),
         }
     }
 }
@@ -301,8 +352,7 @@ impl Add for AbnormalFraction {
     type Output = AbnormalFraction;
 
     fn add(self, rhs: Self) -> Self::Output {
-        print!("add {} + {}", self, rhs);
-        let x = match (self, rhs) {
+        match (self, rhs) {
             (AbnormalFraction::Normal(f1), AbnormalFraction::Normal(f2)) => Self::Normal(f1 + f2),
             (AbnormalFraction::Normal(_), AbnormalFraction::Infinite) => AbnormalFraction::Infinite,
             (AbnormalFraction::Normal(_), AbnormalFraction::NegInfinite) => {
@@ -320,9 +370,7 @@ impl Add for AbnormalFraction {
             }
             (_, AbnormalFraction::NaN) => AbnormalFraction::NaN,
             (AbnormalFraction::NaN, _) => AbnormalFraction::NaN,
-        };
-        println!(" = {}", x);
-        x
+        }
     }
 }
 
@@ -330,7 +378,7 @@ impl Add for &AbnormalFraction {
     type Output = AbnormalFraction;
 
     fn add(self, rhs: Self) -> Self::Output {
-        let x = match (self, rhs) {
+        match (self, rhs) {
             (AbnormalFraction::Normal(f1), AbnormalFraction::Normal(f2)) => {
                 AbnormalFraction::Normal(f1 + f2)
             }
@@ -350,20 +398,15 @@ impl Add for &AbnormalFraction {
             }
             (_, AbnormalFraction::NaN) => AbnormalFraction::NaN,
             (AbnormalFraction::NaN, _) => AbnormalFraction::NaN,
-        };
-        println!("add {} + {} = {}", self, rhs, x);
-        x
+        }
     }
 }
 
 impl AddAssign for AbnormalFraction {
     fn add_assign(&mut self, rhs: Self) {
-        print!("add_assign {} + {}", self, rhs);
         if self.both_normal(&rhs) {
             if let (AbnormalFraction::Normal(f1), AbnormalFraction::Normal(f2)) = (self, rhs) {
                 *f1 += f2;
-
-                println!(" = {}", f1);
             } else {
                 unreachable!()
             }
@@ -389,7 +432,6 @@ impl AddAssign for AbnormalFraction {
                 (_, AbnormalFraction::NaN) => *self = AbnormalFraction::NaN,
                 (AbnormalFraction::NaN, _) => {}
             };
-            println!(" = {}", self);
         }
     }
 }
@@ -398,7 +440,6 @@ impl Sub for AbnormalFraction {
     type Output = AbnormalFraction;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        println!("sub {} - {}", self, rhs);
         match (&self, &rhs) {
             (AbnormalFraction::Normal(f1), AbnormalFraction::Normal(f2)) => {
                 AbnormalFraction::Normal(f1 - f2)
@@ -431,7 +472,6 @@ impl Sub for &AbnormalFraction {
     type Output = AbnormalFraction;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        println!("sub {} - {}", self, rhs);
         match (&self, &rhs) {
             (AbnormalFraction::Normal(f1), AbnormalFraction::Normal(f2)) => {
                 AbnormalFraction::Normal(f1 - f2)
@@ -462,7 +502,6 @@ impl Sub for &AbnormalFraction {
 
 impl SubAssign for AbnormalFraction {
     fn sub_assign(&mut self, rhs: Self) {
-        println!("sub_assign {} - {}", self, rhs);
         if self.both_normal(&rhs) {
             if let (AbnormalFraction::Normal(f1), AbnormalFraction::Normal(f2)) = (self, rhs) {
                 *f1 -= f2;
@@ -499,8 +538,7 @@ impl Mul for AbnormalFraction {
     type Output = AbnormalFraction;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        print!("mul {} * {}", self, rhs);
-        let x = match (&self, &rhs) {
+        match (&self, &rhs) {
             (AbnormalFraction::Normal(f1), AbnormalFraction::Normal(f2)) => {
                 AbnormalFraction::Normal(f1 * f2)
             }
@@ -549,9 +587,7 @@ impl Mul for AbnormalFraction {
             }
             (_, AbnormalFraction::NaN) => AbnormalFraction::NaN,
             (AbnormalFraction::NaN, _) => AbnormalFraction::NaN,
-        };
-        println!(" = {}", x);
-        x
+        }
     }
 }
 
@@ -559,8 +595,7 @@ impl Mul for &AbnormalFraction {
     type Output = AbnormalFraction;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        print!("mul {} * {}", self, rhs);
-        let x = match (&self, &rhs) {
+        match (&self, &rhs) {
             (AbnormalFraction::Normal(f1), AbnormalFraction::Normal(f2)) => {
                 AbnormalFraction::Normal(f1 * f2)
             }
@@ -609,9 +644,7 @@ impl Mul for &AbnormalFraction {
             }
             (_, AbnormalFraction::NaN) => AbnormalFraction::NaN,
             (AbnormalFraction::NaN, _) => AbnormalFraction::NaN,
-        };
-        println!(" = {}", x);
-        x
+        }
     }
 }
 
@@ -619,8 +652,7 @@ impl Div for AbnormalFraction {
     type Output = AbnormalFraction;
 
     fn div(self, rhs: Self) -> Self::Output {
-        print!("div {} / {}", self, rhs);
-        let x = match (&self, &rhs) {
+        match (&self, &rhs) {
             (AbnormalFraction::Normal(f1), AbnormalFraction::Normal(f2)) if !f2.is_zero() => {
                 AbnormalFraction::Normal(f1 / f2)
             }
@@ -655,9 +687,7 @@ impl Div for AbnormalFraction {
             (AbnormalFraction::NegInfinite, AbnormalFraction::NegInfinite) => AbnormalFraction::NaN,
             (_, AbnormalFraction::NaN) => AbnormalFraction::NaN,
             (AbnormalFraction::NaN, _) => AbnormalFraction::NaN,
-        };
-        println!(" = {}", x);
-        x
+        }
     }
 }
 
@@ -665,8 +695,7 @@ impl Div for &AbnormalFraction {
     type Output = AbnormalFraction;
 
     fn div(self, rhs: Self) -> Self::Output {
-        print!("div {} / {}", self, rhs);
-        let x = match (&self, &rhs) {
+        match (&self, &rhs) {
             (AbnormalFraction::Normal(f1), AbnormalFraction::Normal(f2)) if !f2.is_zero() => {
                 AbnormalFraction::Normal(f1 / f2)
             }
@@ -701,9 +730,7 @@ impl Div for &AbnormalFraction {
             (AbnormalFraction::NegInfinite, AbnormalFraction::NegInfinite) => AbnormalFraction::NaN,
             (_, AbnormalFraction::NaN) => AbnormalFraction::NaN,
             (AbnormalFraction::NaN, _) => AbnormalFraction::NaN,
-        };
-        println!(" = {}", x);
-        x
+        }
     }
 }
 
@@ -771,4 +798,28 @@ mod tests {
         assert!(AbnormalFraction::infinity().is_infinite());
         assert!(!AbnormalFraction::infinity().is_finite());
     }
+
+    #[test]
+    fn abnormal_fraction_order() {
+        let mut values = vec![
+            AbnormalFraction::NaN,
+            AbnormalFraction::infinity(),
+            AbnormalFraction::from((1, 2)),
+            AbnormalFraction::neg_infinity(),
+            AbnormalFraction::from((1, 3)),
+        ];
+        values.sort();
+        assert_eq!(
+            values,
+            vec![
+                AbnormalFraction::neg_infinity(),
+                AbnormalFraction::from((1, 3)),
+                AbnormalFraction::from((1, 2)),
+                AbnormalFraction::infinity(),
+                AbnormalFraction::NaN,
+            ]
+        );
+        assert!(AbnormalFraction::infinity() <= AbnormalFraction::infinity());
+        assert!(AbnormalFraction::from(1) < AbnormalFraction::infinity());
+    }
 }
@@ -3,36 +3,85 @@
 // The original code has been modified to support the Fraction type in this project.
 // For more information, see https://github.com/evenfurther/pathfinding?tab=readme-ov-file#license
 
+#[cfg(feature = "linear_programming")]
+use crate::abnormal_fraction::AbnormalFraction;
+use crate::astar_options::{Reopening, SearchOptions, TieBreak};
+use crate::astar_progress::{ProgressCallback, ProgressInfo};
+use crate::astar_search_graph::{GraphRecorder, SearchGraph};
+use crate::astar_stats::SearchStats;
+use ebi_arithmetic::Fraction;
 use ebi_arithmetic::ebi_number::Zero;
+use indexmap::IndexMap;
 use indexmap::map::Entry::{Occupied, Vacant};
+use log::debug;
+use rustc_hash::FxHasher;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
+use std::convert::Infallible;
+use std::fmt::Debug;
 use std::hash::Hash;
-use std::hash::BuildHasherDefault;
+use std::hash::{BuildHasher, BuildHasherDefault};
 use std::ops::AddAssign;
-use indexmap::IndexMap;
-use rustc_hash::FxHasher;
+use std::time::{Duration, Instant};
 
-type FxIndexMap<K, V> = IndexMap<K, V, BuildHasherDefault<FxHasher>>;
+/// The hasher of the nodes in the searches, unless set otherwise in the
+/// [options](SearchOptions::hasher).
+pub type DefaultSearchHasher = BuildHasherDefault<FxHasher>;
 
+pub(crate) type FxIndexMap<K, V> = IndexMap<K, V, DefaultSearchHasher>;
 
-#[allow(clippy::needless_collect)]
-fn reverse_path<N, V, F>(parents: &FxIndexMap<N, V>, mut parent: F, start: usize) -> Vec<N>
-where
-    N: Eq + Hash + Clone,
-    F: FnMut(&V) -> usize,
-{
-    let mut i = start;
-    let path = std::iter::from_fn(|| {
-        parents.get_index(i).map(|(node, value)| {
-            i = parent(value);
-            node
-        })
-    })
-    .collect::<Vec<&N>>();
-    // Collecting the going through the vector is needed to revert the path because the
-    // unfold iterator is not double-ended due to its iterative nature.
-    path.into_iter().rev().cloned().collect()
+/// The cost of a move or a path in [`astar`]: non-negative, totally ordered, and summed from
+/// zero. Implemented for the common integer types, [`Fraction`], and, with the
+/// `linear_programming` feature, [`AbnormalFraction`](crate::abnormal_fraction::AbnormalFraction).
+pub trait Cost: Zero + Ord + Clone + AddAssign + Debug {
+    /// Whether a move of this cost is forbidden: the search never takes it. Only
+    /// [`AbnormalFraction::Infinite`](crate::abnormal_fraction::AbnormalFraction::Infinite) is.
+    fn is_forbidden(&self) -> bool {
+        false
+    }
+
+    /// This cost times `numerator` / `denominator`, rounded down for integers. For the weights
+    /// of [`astar_weighted`].
+    fn scale(&self, numerator: usize, denominator: usize) -> Self;
+
+    /// The index of this cost in a bucket queue, see
+    /// [`astar_indexed`](crate::astar_indexed::astar_indexed), if it is a non-negative integer.
+    /// `None` by default.
+    fn bucket(&self) -> Option<usize> {
+        None
+    }
+}
+
+macro_rules! impl_cost {
+    ($($t:ty),*) => {
+        $(impl Cost for $t {
+            fn scale(&self, numerator: usize, denominator: usize) -> Self {
+                (*self as i128 * numerator as i128 / denominator as i128) as $t
+            }
+
+            fn bucket(&self) -> Option<usize> {
+                usize::try_from(*self).ok()
+            }
+        })*
+    };
+}
+impl_cost!(u32, u64, usize, i32, i64);
+
+impl Cost for Fraction {
+    fn scale(&self, numerator: usize, denominator: usize) -> Self {
+        self.clone() * Fraction::from((numerator, denominator))
+    }
+}
+
+#[cfg(feature = "linear_programming")]
+impl Cost for AbnormalFraction {
+    fn is_forbidden(&self) -> bool {
+        matches!(self, AbnormalFraction::Infinite)
+    }
+
+    fn scale(&self, numerator: usize, denominator: usize) -> Self {
+        self * &AbnormalFraction::from((numerator, denominator))
+    }
 }
 
 /// Compute a shortest path using the [A* search
@@ -44,7 +93,8 @@ where
 ///
 /// - `start` is the starting node.
 /// - `successors` returns a list of successors for a given node, along with the cost for moving
-///   from the node to the successor. This cost must be non-negative.
+///   from the node to the successor. This cost must be non-negative. Moves of a forbidden cost
+///   (see [`Cost::is_forbidden`]) are skipped.
 /// - `heuristic` returns an approximation of the cost from a given node to the goal. The
 ///   approximation must not be greater than the real cost, or a wrong shortest path may be returned.
 /// - `success` checks whether the goal has been reached. It is not a node as some problems require
@@ -102,96 +152,928 @@ where
 /// assert_eq!(result.expect("no path found").1, 4);
 /// ```
 #[allow(clippy::missing_panics_doc)]
-#[allow(clippy::missing_panics_doc)]
-pub fn astar<'a, N, C, FN, IN, FH, FS>(
+pub fn astar<N, C, FN, IN, FH, FS>(
+    start: &N,
+    successors: FN,
+    heuristic: FH,
+    success: FS,
+) -> Option<(Vec<N>, C)>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    Searcher::new().search(start, successors, heuristic, success)
+}
+
+/// Like [`astar`], for `successors` and `heuristic` that may fail: the search stops at the
+/// first error, which is returned as is.
+pub fn try_astar<N, C, E, FN, IN, FH, FS>(
+    start: &N,
+    successors: FN,
+    heuristic: FH,
+    success: FS,
+) -> Result<Option<(Vec<N>, C)>, E>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> Result<IN, E>,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> Result<C, E>,
+    FS: FnMut(&N) -> bool,
+{
+    Searcher::new().try_search(start, successors, heuristic, success)
+}
+
+/// Like [`astar`], with `options`, and also return the counters of the search.
+pub fn astar_with_options<N, C, S, FN, IN, FH, FS>(
     start: &N,
     mut successors: FN,
     mut heuristic: FH,
     mut success: FS,
-) -> Option<(Vec<N>, C)>
+    options: &SearchOptions<C, S>,
+) -> (SearchOutcome<N, C>, SearchStats)
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    S: BuildHasher + Clone,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    if let Some(max_path_len) = options.max_path_len {
+        return astar_max_path_len(start, successors, heuristic, success, options, max_path_len);
+    }
+    let mut searcher = Searcher::with_options(options);
+    searcher.core.reset(start);
+    let outcome = (searcher.core).run(&mut successors, &mut heuristic, &mut success, None);
+    (outcome, searcher.core.stats)
+}
+
+/// [`astar_with_options`] for paths of at most `max_path_len` moves: the search is over pairs
+/// of a node and the number of moves to it, so that a dearer path to a node with fewer moves
+/// is not left out for a cheaper one with more.
+fn astar_max_path_len<N, C, S, FN, IN, FH, FS>(
+    start: &N,
+    mut successors: FN,
+    mut heuristic: FH,
+    mut success: FS,
+    options: &SearchOptions<C, S>,
+    max_path_len: usize,
+) -> (SearchOutcome<N, C>, SearchStats)
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    S: BuildHasher + Clone,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    let capacity = options.expected_states.unwrap_or(0);
+    let parents: IndexMap<(N, usize), (usize, C), S> =
+        IndexMap::with_capacity_and_hasher(capacity, options.hasher.clone());
+    let mut search =
+        SearchCore::with_options(parents, BinaryHeap::with_capacity(capacity), options);
+    search.max_path_len = Some(max_path_len);
+    search.reset(&(start.clone(), 0));
+    let outcome = search.run(
+        &mut |(node, moves): &(N, usize)| {
+            let moves = moves + 1;
+            (successors(node).into_iter()).map(move |(successor, cost)| ((successor, moves), cost))
+        },
+        &mut |(node, _): &(N, usize)| heuristic(node),
+        &mut |(node, _): &(N, usize)| success(node),
+        None,
+    );
+    let outcome = match outcome {
+        SearchOutcome::Found { path, cost } => SearchOutcome::Found {
+            path: path.into_iter().map(|(node, _)| node).collect(),
+            cost,
+        },
+        SearchOutcome::NoPath => SearchOutcome::NoPath,
+        SearchOutcome::NoPathWithinBound { lower_bound } => {
+            SearchOutcome::NoPathWithinBound { lower_bound }
+        }
+        SearchOutcome::Aborted {
+            best_f_lower_bound,
+            expanded,
+            frontier_size,
+        } => SearchOutcome::Aborted {
+            best_f_lower_bound,
+            expanded,
+            frontier_size,
+        },
+    };
+    (outcome, search.stats)
+}
+
+/// The result of [`astar_with_options`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SearchOutcome<N, C> {
+    /// A shortest path, comprising both the start and end node, and its cost.
+    Found { path: Vec<N>, cost: C },
+    /// There is no path.
+    NoPath,
+    /// There is no path within the [upper bound](SearchOptions::upper_bound) or the
+    /// [path length](SearchOptions::max_path_len) of the search. A path, if any, costs at
+    /// least `lower_bound`: the smallest estimated cost of the nodes that were left out for
+    /// exceeding the bound, or whose successors were.
+    NoPathWithinBound { lower_bound: C },
+    /// The search reached its [expansion limit](SearchOptions::max_expansions) or its
+    /// [time limit](SearchOptions::time_limit) first, or its
+    /// [progress callback](SearchOptions::on_progress) cancelled it. With an admissible
+    /// heuristic, a path costs at least `best_f_lower_bound`, the smallest estimated cost on the
+    /// open list, which held `frontier_size` entries after `expanded` expansions.
+    Aborted {
+        best_f_lower_bound: C,
+        expanded: usize,
+        frontier_size: usize,
+    },
+}
+
+impl<N, C> SearchOutcome<N, C> {
+    /// The path and its cost if one was found.
+    pub fn found(self) -> Option<(Vec<N>, C)> {
+        match self {
+            SearchOutcome::Found { path, cost } => Some((path, cost)),
+            SearchOutcome::NoPath
+            | SearchOutcome::NoPathWithinBound { .. }
+            | SearchOutcome::Aborted { .. } => None,
+        }
+    }
+}
+
+/// A path found by [`astar_weighted`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightedPath<N, C> {
+    /// The path, comprising both the start and end node.
+    pub path: Vec<N>,
+    /// The true cost of the path.
+    pub cost: C,
+    /// A lower bound on the cost of a shortest path: the smallest sum of the cost and the
+    /// (uninflated) heuristic of the nodes left to expand, or `cost` if that is smaller. The
+    /// gap between `cost` and `bound` is at most ε times `bound`.
+    pub bound: C,
+    /// The counters of the search.
+    pub stats: SearchStats,
+}
+
+/// Weighted A*: like [`astar`], but the priority of a node is its cost plus (1 + `epsilon`)
+/// times its heuristic, which drives the search towards the goal and expands fewer nodes.
+/// With an admissible heuristic, the path costs at most (1 + `epsilon`) times as much as a
+/// shortest path, and [`WeightedPath::bound`] tells how far from a shortest path it actually
+/// is. With an `epsilon` of 0, the search is that of [`astar`].
+///
+/// # Panics
+///
+/// If `epsilon` is negative or not finite.
+pub fn astar_weighted<N, C, FN, IN, FH, FS>(
+    start: &N,
+    mut successors: FN,
+    mut heuristic: FH,
+    mut success: FS,
+    epsilon: f64,
+) -> Option<WeightedPath<N, C>>
 where
     N: Eq + Hash + Clone,
-    C: Zero + Ord + Clone + AddAssign,
+    C: Cost,
     FN: FnMut(&N) -> IN,
     IN: IntoIterator<Item = (N, C)>,
     FH: FnMut(&N) -> C,
     FS: FnMut(&N) -> bool,
 {
-    let mut to_see = BinaryHeap::new();
-    to_see.push(SmallestCostHolder {
-        estimated_cost: Zero::zero(),
-        cost: Zero::zero(),
-        index: 0,
-    });
-    let mut parents: FxIndexMap<N, (usize, C)> = FxIndexMap::default();
-    parents.insert(start.clone(), (usize::MAX, Zero::zero()));
-    while let Some(SmallestCostHolder { cost, index, .. }) = to_see.pop() {
-        let successors = {
-            let (node, &(_, ref c)) = parents.get_index(index).unwrap(); // Cannot fail
-            if success(node) {
-                let path = reverse_path(&parents, |&(p, _)| p, index);
-                return Some((path, cost));
-            }
-            // We may have inserted a node several time into the binary heap if we found
-            // a better way to access it. Ensure that we are currently dealing with the
-            // best path and discard the others.
-            if &cost > c {
-                continue;
+    assert!(
+        epsilon >= 0.0 && epsilon.is_finite(),
+        "epsilon must be a non-negative number, not {}",
+        epsilon
+    );
+    let weight = weight_ratio(1.0 + epsilon);
+
+    let mut search = Searcher::new().core;
+    search.reset(start);
+    let (path, cost) = search
+        .run(&mut successors, &mut heuristic, &mut success, weight)
+        .found()?;
+    let mut bound = cost.clone();
+    for holder in &search.to_see {
+        let (node, _) = search.parents.get_index(holder.index).unwrap(); // Cannot fail
+        let mut f = holder.cost.clone();
+        f += heuristic(node);
+        if f < bound {
+            bound = f;
+        }
+    }
+    Some(WeightedPath {
+        path,
+        cost,
+        bound,
+        stats: search.stats,
+    })
+}
+
+/// `weight` as a ratio with a power of 2 as denominator, which is exact for the weights one
+/// would choose, such as 1.5 or 1.25; `None` for a weight of 1.
+pub(crate) fn weight_ratio(weight: f64) -> Option<(usize, usize)> {
+    const WEIGHT_DENOMINATOR: usize = 1 << 20;
+    let numerator = (weight * WEIGHT_DENOMINATOR as f64).round() as usize;
+    (numerator != WEIGHT_DENOMINATOR).then_some((numerator, WEIGHT_DENOMINATOR))
+}
+
+/// The successors in the synchronous product of two labeled transition systems, such as a
+/// process model and a trace for an alignment, to search with [`astar`]. The states of the
+/// product are pairs of a model state and a trace state. `model_successors` and
+/// `trace_successors` return the moves of each side as pairs of a label and a next state, and
+/// `move_cost` returns the cost of a move of the product, or `None` if it is not allowed:
+///
+/// - `move_cost(Some(m), None)`: a move on the model only, with label `m`;
+/// - `move_cost(None, Some(t))`: a move on the trace only, with label `t`;
+/// - `move_cost(Some(m), Some(t))`: a synchronous move of both sides.
+///
+/// ```
+/// # use ebi_optimisation::astar::{astar, product_successors};
+/// // The model a b, the trace a c: a synchronous move, then a model move and a log move.
+/// let model = |&m: &usize| ["a", "b"].get(m).map(|&label| (label, m + 1));
+/// let trace = |&t: &usize| ["a", "c"].get(t).map(|&label| (label, t + 1));
+/// let cost = |m: Option<&&str>, t: Option<&&str>| match (m, t) {
+///     (Some(m), Some(t)) => (m == t).then_some(0u32),
+///     _ => Some(1),
+/// };
+/// let successors = product_successors(model, trace, cost);
+/// let (_, cost) = astar(&(0, 0), successors, |_| 0, |&state| state == (2, 2)).unwrap();
+/// assert_eq!(cost, 2);
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn product_successors<M, T, LM, LT, C, FM, IM, FT, IT, FC>(
+    mut model_successors: FM,
+    mut trace_successors: FT,
+    mut move_cost: FC,
+) -> impl FnMut(&(M, T)) -> Vec<((M, T), C)>
+where
+    M: Clone,
+    T: Clone,
+    FM: FnMut(&M) -> IM,
+    IM: IntoIterator<Item = (LM, M)>,
+    FT: FnMut(&T) -> IT,
+    IT: IntoIterator<Item = (LT, T)>,
+    FC: FnMut(Option<&LM>, Option<&LT>) -> Option<C>,
+{
+    move |(model, trace)| {
+        let model_moves = model_successors(model).into_iter().collect::<Vec<_>>();
+        let trace_moves = trace_successors(trace).into_iter().collect::<Vec<_>>();
+        let mut successors = vec![];
+        for (label, next) in &model_moves {
+            if let Some(cost) = move_cost(Some(label), None) {
+                successors.push(((next.clone(), trace.clone()), cost));
             }
-            successors(node)
+        }
+        for (label, next) in &trace_moves {
+            if let Some(cost) = move_cost(None, Some(label)) {
+                successors.push(((model.clone(), next.clone()), cost));
+            }
+        }
+        for (model_label, model_next) in &model_moves {
+            for (trace_label, trace_next) in &trace_moves {
+                if let Some(cost) = move_cost(Some(model_label), Some(trace_label)) {
+                    successors.push(((model_next.clone(), trace_next.clone()), cost));
+                }
+            }
+        }
+        successors
+    }
+}
+
+/// The heuristic of the states of a [product](product_successors): the largest of
+/// `model_heuristic` of the model state and `trace_heuristic` of the trace state, which is
+/// admissible if both are.
+pub fn product_heuristic<M, T, C, FM, FT>(
+    mut model_heuristic: FM,
+    mut trace_heuristic: FT,
+) -> impl FnMut(&(M, T)) -> C
+where
+    C: Ord,
+    FM: FnMut(&M) -> C,
+    FT: FnMut(&T) -> C,
+{
+    move |(model, trace)| model_heuristic(model).max(trace_heuristic(trace))
+}
+
+/// The storage of A* searches: the open list, for each node seen its parent and the cost of
+/// the best path to it found so far, and a buffer for the path. A searcher keeps that storage
+/// from one search to the next, so once it has grown to the size of the searches, a search
+/// only allocates the path it returns; [`astar`] allocates it anew on every call.
+///
+/// ```
+/// # use ebi_optimisation::astar::Searcher;
+/// let mut searcher = Searcher::new();
+/// for goal in 1..10u32 {
+///     let (path, cost) = searcher
+///         .search(&0, |&n| [(n + 1, 1u32)], |&n| goal.saturating_sub(n), |&n| n == goal)
+///         .unwrap();
+///     assert_eq!(path.len(), goal as usize + 1);
+///     assert_eq!(cost, goal);
+/// }
+/// ```
+pub struct Searcher<N, C, S = DefaultSearchHasher> {
+    core: HashedSearchCore<N, C, S>,
+}
+
+/// The loop of the searches of a [`Searcher`], over a hash map of the nodes and a binary heap.
+type HashedSearchCore<N, C, S> =
+    SearchCore<C, IndexMap<N, (usize, C), S>, BinaryHeap<SmallestCostHolder<C>>>;
+
+impl<N, C> Default for Searcher<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N, C> Searcher<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+{
+    /// A searcher with the default options.
+    pub fn new() -> Self {
+        Self::with_options(&SearchOptions::default())
+    }
+}
+
+impl<N, C, S> Searcher<N, C, S>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    S: BuildHasher + Clone,
+{
+    /// A searcher whose searches use `options`.
+    pub fn with_options(options: &SearchOptions<C, S>) -> Self {
+        let capacity = options.expected_states.unwrap_or(0);
+        Searcher {
+            core: SearchCore::with_options(
+                IndexMap::with_capacity_and_hasher(capacity, options.hasher.clone()),
+                BinaryHeap::with_capacity(capacity),
+                options,
+            ),
+        }
+    }
+
+    /// Search a shortest path like [`astar`], reusing the storage of the earlier searches.
+    /// Returns `None` as well if there is no path within the
+    /// [upper bound](SearchOptions::upper_bound) of the searcher, or if the search reached
+    /// one of its limits.
+    pub fn search<IN, FN, FH, FS>(
+        &mut self,
+        start: &N,
+        mut successors: FN,
+        mut heuristic: FH,
+        mut success: FS,
+    ) -> Option<(Vec<N>, C)>
+    where
+        FN: FnMut(&N) -> IN,
+        IN: IntoIterator<Item = (N, C)>,
+        FH: FnMut(&N) -> C,
+        FS: FnMut(&N) -> bool,
+    {
+        self.core.reset(start);
+        (self.core)
+            .run(&mut successors, &mut heuristic, &mut success, None)
+            .found()
+    }
+
+    /// Like [`Self::search`], with `successors` and `heuristic` that may fail, see
+    /// [`try_astar`]. After an error, the searcher holds nothing of the search but its
+    /// counters.
+    pub fn try_search<E, IN, FN, FH, FS>(
+        &mut self,
+        start: &N,
+        mut successors: FN,
+        mut heuristic: FH,
+        mut success: FS,
+    ) -> Result<Option<(Vec<N>, C)>, E>
+    where
+        FN: FnMut(&N) -> Result<IN, E>,
+        IN: IntoIterator<Item = (N, C)>,
+        FH: FnMut(&N) -> Result<C, E>,
+        FS: FnMut(&N) -> bool,
+    {
+        self.core.reset(start);
+        let outcome = (self.core).try_run(&mut successors, &mut heuristic, &mut success, None)?;
+        Ok(outcome.found())
+    }
+
+    /// The counters of the last search.
+    pub fn stats(&self) -> &SearchStats {
+        &self.core.stats
+    }
+
+    /// The nodes and moves of the last search, if the searcher
+    /// [records them](SearchOptions::record_search_graph).
+    pub fn search_graph(&self) -> Option<SearchGraph<N, C>> {
+        (self.core.graph.as_ref()).map(|graph| graph.build(&self.core.parents))
+    }
+}
+
+/// The bookkeeping of the nodes seen in a search: per node, an index, its parent and the
+/// cost of the best path to it found so far.
+pub(crate) trait NodeStorage<C> {
+    type Node;
+
+    /// Forget all nodes.
+    fn clear(&mut self);
+
+    /// Forget all nodes but `start`, which has no parent and cost zero. Returns its index.
+    fn reset(&mut self, start: &Self::Node) -> usize;
+
+    /// The index of `node`, if it was seen.
+    fn index_of(&self, node: &Self::Node) -> Option<usize>;
+
+    /// The node with index `index`.
+    fn node<'a>(&'a self, index: &'a usize) -> &'a Self::Node;
+
+    /// The index of the parent of the node with index `index`, `usize::MAX` for the start,
+    /// and the cost of the path to it.
+    fn parent_and_cost(&self, index: usize) -> (usize, &C);
+
+    /// Record the path to `node` from the node with index `parent` if it costs less than
+    /// the best one known. Returns the index of `node`, as `Err` if a path to it of at most
+    /// the same cost was known.
+    fn relax(&mut self, node: Self::Node, parent: usize, cost: C) -> Result<usize, usize>;
+}
+
+impl<N, C, S> NodeStorage<C> for IndexMap<N, (usize, C), S>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    S: BuildHasher,
+{
+    type Node = N;
+
+    fn clear(&mut self) {
+        IndexMap::clear(self);
+    }
+
+    fn reset(&mut self, start: &N) -> usize {
+        IndexMap::clear(self);
+        self.insert(start.clone(), (usize::MAX, Zero::zero()));
+        0
+    }
+
+    fn index_of(&self, node: &N) -> Option<usize> {
+        self.get_index_of(node)
+    }
+
+    fn node<'a>(&'a self, index: &'a usize) -> &'a N {
+        self.get_index(*index).unwrap().0 // Cannot fail
+    }
+
+    fn parent_and_cost(&self, index: usize) -> (usize, &C) {
+        let (_, (parent, cost)) = self.get_index(index).unwrap(); // Cannot fail
+        (*parent, cost)
+    }
+
+    fn relax(&mut self, node: N, parent: usize, cost: C) -> Result<usize, usize> {
+        match self.entry(node) {
+            Vacant(e) => {
+                let index = e.index();
+                e.insert((parent, cost));
+                Ok(index)
+            }
+            Occupied(mut e) => {
+                if e.get().1 > cost {
+                    e.insert((parent, cost));
+                    Ok(e.index())
+                } else {
+                    Err(e.index())
+                }
+            }
+        }
+    }
+}
+
+/// The open list of a search.
+pub(crate) trait OpenList<C> {
+    fn push(&mut self, holder: SmallestCostHolder<C>);
+
+    /// Take the entry that comes first.
+    fn pop(&mut self) -> Option<SmallestCostHolder<C>>;
+
+    fn len(&self) -> usize;
+
+    fn clear(&mut self);
+}
+
+impl<C: Ord> OpenList<C> for BinaryHeap<SmallestCostHolder<C>> {
+    fn push(&mut self, holder: SmallestCostHolder<C>) {
+        BinaryHeap::push(self, holder);
+    }
+
+    fn pop(&mut self) -> Option<SmallestCostHolder<C>> {
+        BinaryHeap::pop(self)
+    }
+
+    fn len(&self) -> usize {
+        BinaryHeap::len(self)
+    }
+
+    fn clear(&mut self) {
+        BinaryHeap::clear(self);
+    }
+}
+
+/// The path from the start to the node with index `end` in `parents`.
+pub(crate) fn path_to<C, M>(parents: &M, end: usize) -> Vec<M::Node>
+where
+    M: NodeStorage<C>,
+    M::Node: Clone,
+{
+    let indices = || {
+        std::iter::successors(Some(end), |&i| {
+            Some(parents.parent_and_cost(i).0).filter(|&parent| parent != usize::MAX)
+        })
+    };
+    // Walk the parents twice rather than growing the path, which allocates only once.
+    let mut path = Vec::with_capacity(indices().count());
+    path.extend(indices().map(|i| parents.node(&i).clone()));
+    path.reverse();
+    path
+}
+
+/// The loop of an A* search, over the storage of the nodes `M` and the open list `Q`, and the
+/// options of the search.
+pub(crate) struct SearchCore<C, M, Q> {
+    pub(crate) to_see: Q,
+    pub(crate) parents: M,
+    pub(crate) stats: SearchStats,
+    log_every: Option<u64>,
+    upper_bound: Option<C>,
+    tie_break: TieBreak,
+    max_expansions: Option<u64>,
+    time_limit: Option<Duration>,
+    /// The number of moves beyond which paths are left out.
+    pub(crate) max_path_len: Option<usize>,
+    reopening: Reopening,
+    /// The number of times each node was expanded, by index.
+    times_expanded: Vec<usize>,
+    on_progress: Option<ProgressCallback<C>>,
+    /// The search graph, if recorded.
+    pub(crate) graph: Option<GraphRecorder<C>>,
+}
+
+impl<C, M, Q> SearchCore<C, M, Q>
+where
+    C: Cost,
+    M: NodeStorage<C>,
+    M::Node: Clone,
+    Q: OpenList<C>,
+{
+    pub(crate) fn with_options<S>(parents: M, to_see: Q, options: &SearchOptions<C, S>) -> Self {
+        SearchCore {
+            to_see,
+            parents,
+            stats: SearchStats::default(),
+            log_every: options.log_every,
+            upper_bound: options.upper_bound.clone(),
+            tie_break: options.tie_break,
+            max_expansions: options.max_expansions,
+            time_limit: options.time_limit,
+            max_path_len: None,
+            reopening: options.reopening,
+            times_expanded: vec![],
+            on_progress: options.on_progress.clone(),
+            graph: options.record_search_graph.then(GraphRecorder::default),
+        }
+    }
+
+    /// Forget the last search and start one from `start`.
+    pub(crate) fn reset(&mut self, start: &M::Node) {
+        let index = self.parents.reset(start);
+        self.to_see.clear();
+        self.to_see.push(SmallestCostHolder {
+            estimated_cost: Zero::zero(),
+            cost: Zero::zero(),
+            index,
+            depth: 0,
+            seq: 0,
+            tie_break: self.tie_break,
+        });
+        self.times_expanded.clear();
+        self.stats = SearchStats {
+            max_open_len: 1,
+            queue_operations: 1,
+            ..SearchStats::default()
         };
-        for (successor, mut move_cost) in successors {
-            move_cost += cost.clone();
-            let new_cost = move_cost;
-            let h; // heuristic(&successor)
-            let n; // index for successor
-            match parents.entry(successor) {
-                Vacant(e) => {
-                    h = heuristic(e.key());
-                    n = e.index();
-                    e.insert((index, new_cost.clone()));
-                }
-                Occupied(mut e) => {
-                    if e.get().1 > new_cost {
-                        h = heuristic(e.key());
-                        n = e.index();
-                        e.insert((index, new_cost.clone()));
-                    } else {
+        if let Some(graph) = &mut self.graph {
+            graph.reset(index);
+        }
+    }
+
+    /// Search until a node for which `success` holds is taken from the open list, with the
+    /// heuristic scaled by the ratio `weight` in the priorities if set. Successors whose cost
+    /// plus heuristic exceeds the upper bound are left out, and so are the nodes at the
+    /// maximum path length, which is only exact if the nodes count their moves. The search is
+    /// aborted before an expansion beyond the limits.
+    pub(crate) fn run<FN, IN, FH, FS>(
+        &mut self,
+        successors: &mut FN,
+        heuristic: &mut FH,
+        success: &mut FS,
+        weight: Option<(usize, usize)>,
+    ) -> SearchOutcome<M::Node, C>
+    where
+        FN: FnMut(&M::Node) -> IN,
+        IN: IntoIterator<Item = (M::Node, C)>,
+        FH: FnMut(&M::Node) -> C,
+        FS: FnMut(&M::Node) -> bool,
+    {
+        let Ok(outcome) = self.try_run(
+            &mut |node: &M::Node| Ok::<_, Infallible>(successors(node)),
+            &mut |node: &M::Node| Ok::<_, Infallible>(heuristic(node)),
+            success,
+            weight,
+        );
+        outcome
+    }
+
+    /// Like [`Self::run`], with fallible `successors` and `heuristic`. The first error ends
+    /// the search and empties the storage.
+    pub(crate) fn try_run<E, FN, IN, FH, FS>(
+        &mut self,
+        successors: &mut FN,
+        heuristic: &mut FH,
+        success: &mut FS,
+        weight: Option<(usize, usize)>,
+    ) -> Result<SearchOutcome<M::Node, C>, E>
+    where
+        FN: FnMut(&M::Node) -> Result<IN, E>,
+        IN: IntoIterator<Item = (M::Node, C)>,
+        FH: FnMut(&M::Node) -> Result<C, E>,
+        FS: FnMut(&M::Node) -> bool,
+    {
+        let start_time = Instant::now();
+        let res = self.run_until_success(successors, heuristic, success, weight);
+        self.stats.time += start_time.elapsed();
+        match &res {
+            Ok(_) => debug!("A*: done, {}", self.stats),
+            Err(_) => {
+                debug!("A*: failed, {}", self.stats);
+                self.to_see.clear();
+                self.parents.clear();
+                if let Some(graph) = &mut self.graph {
+                    graph.clear();
+                }
+            }
+        }
+        res
+    }
+
+    fn run_until_success<E, FN, IN, FH, FS>(
+        &mut self,
+        successors: &mut FN,
+        heuristic: &mut FH,
+        success: &mut FS,
+        weight: Option<(usize, usize)>,
+    ) -> Result<SearchOutcome<M::Node, C>, E>
+    where
+        FN: FnMut(&M::Node) -> Result<IN, E>,
+        IN: IntoIterator<Item = (M::Node, C)>,
+        FH: FnMut(&M::Node) -> Result<C, E>,
+        FS: FnMut(&M::Node) -> bool,
+    {
+        // The smallest cost plus heuristic of a successor left out for exceeding the bound, or
+        // of a node not expanded for being at the maximum path length.
+        let mut lowest_pruned: Option<C> = None;
+        let start_time = Instant::now();
+        let deadline = (self.time_limit).and_then(|limit| start_time.checked_add(limit));
+        while let Some(SmallestCostHolder {
+            estimated_cost,
+            cost,
+            index,
+            depth,
+            ..
+        }) = self.to_see.pop()
+        {
+            let SearchCore {
+                to_see,
+                parents,
+                stats,
+                log_every,
+                upper_bound,
+                tie_break,
+                max_expansions,
+                max_path_len,
+                reopening,
+                times_expanded,
+                on_progress,
+                graph,
+                ..
+            } = self;
+            stats.queue_operations += 1;
+            let successors = {
+                let node = parents.node(&index);
+                if success(node) {
+                    if let Some(graph) = graph.as_mut() {
+                        graph.found(index);
+                    }
+                    return Ok(SearchOutcome::Found {
+                        path: path_to(&self.parents, index),
+                        cost,
+                    });
+                }
+                // We may have inserted a node several time into the binary heap if we found
+                // a better way to access it. Ensure that we are currently dealing with the
+                // best path and discard the others.
+                if &cost > parents.parent_and_cost(index).1 {
+                    stats.duplicates_skipped += 1;
+                    continue;
+                }
+                if max_path_len.is_some_and(|max| depth >= max) {
+                    let f = match weight {
+                        Some((numerator, denominator)) => {
+                            estimated_cost.scale(denominator, numerator)
+                        }
+                        None => estimated_cost,
+                    };
+                    if lowest_pruned.as_ref().is_none_or(|lowest| &f < lowest) {
+                        lowest_pruned = Some(f);
+                    }
+                    continue;
+                }
+                let limit_reached = max_expansions.is_some_and(|max| stats.expanded as u64 >= max)
+                    || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+                let progress = on_progress
+                    .as_ref()
+                    .filter(|on_progress| on_progress.due(stats.expanded));
+                if limit_reached || progress.is_some() {
+                    // The weighted estimate is at most `weight` times the true one.
+                    let mut best_f_lower_bound = match weight {
+                        Some((numerator, denominator)) => {
+                            estimated_cost.scale(denominator, numerator)
+                        }
+                        None => estimated_cost.clone(),
+                    };
+                    if let Some(lowest) = &lowest_pruned {
+                        best_f_lower_bound = best_f_lower_bound.min(lowest.clone());
+                    }
+                    let cancelled = progress.is_some_and(|on_progress| {
+                        let info = ProgressInfo {
+                            expanded: stats.expanded,
+                            open_len: to_see.len() + 1,
+                            min_f: best_f_lower_bound.clone(),
+                            incumbent: upper_bound.clone(),
+                            elapsed: stats.time + start_time.elapsed(),
+                        };
+                        on_progress.call(&info).is_break()
+                    });
+                    if limit_reached || cancelled {
+                        return Ok(SearchOutcome::Aborted {
+                            best_f_lower_bound,
+                            expanded: stats.expanded,
+                            frontier_size: to_see.len() + 1,
+                        });
+                    }
+                }
+                stats.expanded += 1;
+                stats.max_depth = stats.max_depth.max(depth);
+                if times_expanded.len() <= index {
+                    times_expanded.resize(index + 1, 0);
+                }
+                if times_expanded[index] > 0 {
+                    stats.reexpanded += 1;
+                }
+                times_expanded[index] += 1;
+                if let Some(graph) = graph.as_mut() {
+                    graph.expanded(index);
+                }
+                if log_every.is_some_and(|every| (stats.expanded as u64).is_multiple_of(every)) {
+                    debug!(
+                        "A*: {}, open list: {}, f: {:?}",
+                        stats,
+                        to_see.len(),
+                        estimated_cost
+                    );
+                }
+                successors(node)?
+            };
+            for (successor, mut move_cost) in successors {
+                if move_cost.is_forbidden() {
+                    continue;
+                }
+                stats.generated += 1;
+                // The expanded nodes that may not be expanded again keep their path.
+                if let Some(max) = reopening.max_reexpansions() {
+                    let closed = (parents.index_of(&successor))
+                        .and_then(|n| times_expanded.get(n))
+                        .is_some_and(|&times| times > max);
+                    if closed {
+                        stats.duplicates_skipped += 1;
                         continue;
                     }
                 }
-            }
+                let recorded_cost = graph.is_some().then(|| move_cost.clone());
+                move_cost += cost.clone();
+                let new_cost = move_cost;
+                let (n, h) = match parents.relax(successor, index, new_cost.clone()) {
+                    Ok(n) => (n, Some(heuristic(parents.node(&n))?)),
+                    Err(n) => (n, None),
+                };
+                if let (Some(graph), Some(move_cost)) = (graph.as_mut(), recorded_cost) {
+                    graph.edge(index, n, move_cost, new_cost.clone(), h.clone());
+                }
+                let Some(h) = h else {
+                    stats.duplicates_skipped += 1;
+                    continue;
+                };
 
-            let mut estimated_cost = new_cost.clone();
-            estimated_cost += h;
-            to_see.push(SmallestCostHolder {
-                estimated_cost: estimated_cost,
-                cost: new_cost,
-                index: n,
-            });
+                if let Some(upper_bound) = upper_bound.as_ref() {
+                    let mut f = new_cost.clone();
+                    f += h.clone();
+                    if &f > upper_bound {
+                        if lowest_pruned.as_ref().is_none_or(|lowest| &f < lowest) {
+                            lowest_pruned = Some(f);
+                        }
+                        continue;
+                    }
+                }
+
+                let mut estimated_cost = new_cost.clone();
+                match weight {
+                    Some((numerator, denominator)) => {
+                        estimated_cost += h.scale(numerator, denominator)
+                    }
+                    None => estimated_cost += h,
+                }
+                to_see.push(SmallestCostHolder {
+                    estimated_cost,
+                    cost: new_cost,
+                    index: n,
+                    depth: depth + 1,
+                    seq: stats.generated,
+                    tie_break: *tie_break,
+                });
+                stats.queue_operations += 1;
+            }
+            stats.max_open_len = stats.max_open_len.max(to_see.len());
         }
+        Ok(match lowest_pruned {
+            Some(lower_bound) => SearchOutcome::NoPathWithinBound { lower_bound },
+            None => SearchOutcome::NoPath,
+        })
     }
-    None
 }
+
+/// [`astar`] with exact costs, which may be
+/// [`AbnormalFraction::Infinite`](crate::abnormal_fraction::AbnormalFraction::Infinite) for a
+/// forbidden move.
+#[cfg(feature = "linear_programming")]
+pub fn astar_ab<N, FN, IN, FH, FS>(
+    start: &N,
+    successors: FN,
+    heuristic: FH,
+    success: FS,
+) -> Option<(Vec<N>, AbnormalFraction)>
+where
+    N: Eq + Hash + Clone,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, AbnormalFraction)>,
+    FH: FnMut(&N) -> AbnormalFraction,
+    FS: FnMut(&N) -> bool,
+{
+    astar(start, successors, heuristic, success)
+}
+
 /// This structure is used to implement Rust's max-heap as a min-heap
 /// version for A*. The smallest `estimated_cost` (which is the sum of
 /// the `cost` and the heuristic) is preferred. For the same
-/// `estimated_cost`, the highest `cost` will be favored, as it may
-/// indicate that the goal is nearer, thereby requiring fewer
-/// exploration steps.
-struct SmallestCostHolder<K> {
-    estimated_cost: K,
-    cost: K,
-    index: usize,
+/// `estimated_cost`, the `tie_break` policy decides, and the `seq`uence
+/// numbers break the remaining ties, so that the order does not depend
+/// on the layout of the heap.
+pub(crate) struct SmallestCostHolder<K> {
+    pub(crate) estimated_cost: K,
+    pub(crate) cost: K,
+    pub(crate) index: usize,
+    /// Number of moves on the path to the node.
+    pub(crate) depth: usize,
+    /// The order in which the entries were added to the open list: increasing, and unique
+    /// within a search.
+    pub(crate) seq: usize,
+    pub(crate) tie_break: TieBreak,
 }
 
-impl<K: PartialEq> PartialEq for SmallestCostHolder<K> {
+impl<K: Ord> PartialEq for SmallestCostHolder<K> {
     fn eq(&self, other: &Self) -> bool {
-        self.estimated_cost.eq(&other.estimated_cost) && self.cost.eq(&other.cost)
+        self.cmp(other) == Ordering::Equal
     }
 }
 
-impl<K: PartialEq> Eq for SmallestCostHolder<K> {}
+impl<K: Ord> Eq for SmallestCostHolder<K> {}
 
 impl<K: Ord> PartialOrd for SmallestCostHolder<K> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
@@ -202,8 +1084,599 @@ impl<K: Ord> PartialOrd for SmallestCostHolder<K> {
 impl<K: Ord> Ord for SmallestCostHolder<K> {
     fn cmp(&self, other: &Self) -> Ordering {
         match other.estimated_cost.cmp(&self.estimated_cost) {
-            Ordering::Equal => self.cost.cmp(&other.cost),
+            Ordering::Equal => {
+                let by_cost = match self.tie_break {
+                    TieBreak::PreferHighG => self.cost.cmp(&other.cost),
+                    TieBreak::PreferLowG => other.cost.cmp(&self.cost),
+                    TieBreak::Fifo | TieBreak::Lifo => Ordering::Equal,
+                };
+                by_cost.then_with(|| match self.tie_break {
+                    TieBreak::Lifo => self.seq.cmp(&other.seq),
+                    _ => other.seq.cmp(&self.seq),
+                })
+            }
             s => s,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+    use parking_lot::Mutex;
+    use std::{ops::ControlFlow, sync::Arc};
+
+    /// A random directed graph of 2 to 199 nodes, with up to 4 moves from each node, of a
+    /// cost from 0 to 5, and a random goal; the searches start at node 0.
+    pub(crate) struct RandomGraph {
+        pub(crate) edges: Vec<Vec<(usize, u32)>>,
+        pub(crate) goal: usize,
+    }
+
+    impl RandomGraph {
+        pub(crate) fn successors(&self, node: &usize) -> Vec<(usize, u32)> {
+            self.edges[*node].clone()
+        }
+
+        /// The cost of a path along the cheapest moves between its nodes.
+        pub(crate) fn path_cost(&self, path: &[usize]) -> u32 {
+            (path.windows(2))
+                .map(|step| {
+                    (self.edges[step[0]].iter())
+                        .filter(|(next, _)| *next == step[1])
+                        .map(|(_, cost)| *cost)
+                        .min()
+                        .unwrap()
+                })
+                .sum()
+        }
+    }
+
+    pub(crate) fn random_graph(seed: u64) -> RandomGraph {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let n = rng.random_range(2..200);
+        let edges = (0..n)
+            .map(|_| {
+                (0..rng.random_range(0..5))
+                    .map(|_| (rng.random_range(0..n), rng.random_range(0..6u32)))
+                    .collect()
+            })
+            .collect();
+        let goal = rng.random_range(0..n);
+        RandomGraph { edges, goal }
+    }
+
+    #[test]
+    fn astar_integer_costs() {
+        // A chain 0 -> 1 -> ... -> 5 with a shortcut 0 -> 5 that costs more.
+        let successors = |&n: &u32| {
+            let mut succs = vec![];
+            if n < 5 {
+                succs.push((n + 1, 1u32));
+            }
+            if n == 0 {
+                succs.push((5, 6));
+            }
+            succs
+        };
+        let (path, cost) = astar(&0, successors, |&n| 5 - n, |&n| n == 5).unwrap();
+        assert_eq!(path, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(cost, 5);
+    }
+
+    #[test]
+    fn astar_stats() {
+        // A chain with a dead end at each node; the heuristic is the exact distance, so only
+        // the nodes on the path are expanded.
+        let successors = |&n: &u32| {
+            if n < 10 {
+                vec![(n + 1, 1u32), (100 + n, 1)]
+            } else {
+                vec![]
+            }
+        };
+        let heuristic = |&n: &u32| if n <= 10 { 10 - n } else { 100 };
+        let options = SearchOptions::new().log_every(Some(3));
+        let (res, stats) = astar_with_options(&0, successors, heuristic, |&n| n == 10, &options);
+        let (path, cost) = res.found().unwrap();
+        assert_eq!(cost, 10);
+        assert_eq!(stats.expanded, path.len() - 1);
+        assert_eq!(stats.generated, 20);
+        assert!(stats.expanded <= stats.generated);
+        assert_eq!(stats.max_depth, 9);
+        assert!(stats.max_open_len >= 10);
+
+        let (res, stats) =
+            astar_with_options(&0, successors, |_| 0, |&n| n == 1000, &SearchOptions::new());
+        assert_eq!(res, SearchOutcome::NoPath);
+        assert_eq!(stats.expanded, 21);
+        assert!(stats.expanded <= stats.generated + 1);
+    }
+
+    #[test]
+    fn astar_upper_bound() {
+        // The shortest path 0 -> 1 -> 2 costs 10, the shortcut 0 -> 2 costs 12.
+        let successors = |&n: &u32| match n {
+            0 => vec![(1, 4u32), (2, 12)],
+            1 => vec![(2, 6)],
+            _ => vec![],
+        };
+        let search = |bound| {
+            let options = SearchOptions::new().upper_bound(Some(bound));
+            astar_with_options(&0, successors, |_| 0, |&n| n == 2, &options).0
+        };
+        assert_eq!(
+            search(9),
+            SearchOutcome::NoPathWithinBound { lower_bound: 10 }
+        );
+        assert_eq!(
+            search(10),
+            SearchOutcome::Found {
+                path: vec![0, 1, 2],
+                cost: 10
+            }
+        );
+    }
+
+    #[test]
+    fn astar_fallible() {
+        #[derive(Debug, PartialEq)]
+        struct ModelError(usize);
+
+        // An endless chain, whose successors fail at the 100th expansion.
+        let mut expansions = 0;
+        let successors = |&n: &u32| {
+            expansions += 1;
+            if expansions == 100 {
+                Err(ModelError(expansions))
+            } else {
+                Ok([(n + 1, 1u32)])
+            }
+        };
+        let res = try_astar(&0, successors, |_| Ok(0), |_| false);
+        assert_eq!(res, Err(ModelError(100)));
+
+        let mut searcher = Searcher::new();
+        let res = searcher.try_search(
+            &0,
+            |&n: &u32| Ok([(n + 1, 1u32)]),
+            |&n| {
+                if n < 50 {
+                    Ok(0)
+                } else {
+                    Err(ModelError(n as usize))
+                }
+            },
+            |&n| n == 100,
+        );
+        assert_eq!(res, Err(ModelError(50)));
+        assert!(searcher.core.to_see.is_empty());
+        assert!(searcher.core.parents.is_empty());
+        assert_eq!(searcher.stats().expanded, 50);
+
+        let res = searcher.try_search(
+            &0,
+            |&n: &u32| Ok::<_, ModelError>([(n + 1, 1u32)]),
+            |_| Ok(0),
+            |&n| n == 100,
+        );
+        assert_eq!(res.unwrap().unwrap().1, 100);
+    }
+
+    #[test]
+    fn astar_tie_break() {
+        // On an open grid, all the cells between the start and the goal have the same
+        // estimated cost; preferring the highest cost goes straight to the goal.
+        let successors = grid_successors(20);
+        let goal = (19, 19);
+        let heuristic = |&(x, y): &(u64, u64)| 3 * (goal.0 - x + goal.1 - y);
+        let search = |tie_break| {
+            let mut expansions = vec![];
+            let options = SearchOptions::new().tie_break(tie_break);
+            let (res, stats) = astar_with_options(
+                &(0, 0),
+                |node: &(u64, u64)| {
+                    expansions.push(*node);
+                    let mut successors = successors;
+                    successors(node)
+                },
+                heuristic,
+                |&node| node == goal,
+                &options,
+            );
+            assert_eq!(res.found().unwrap().1, 3 * 38);
+            assert_eq!(stats.expanded, expansions.len());
+            expansions
+        };
+
+        let high_g = search(TieBreak::PreferHighG);
+        let low_g = search(TieBreak::PreferLowG);
+        assert!(high_g.len() < low_g.len());
+        assert_eq!(high_g.len(), 38);
+        for tie_break in [
+            TieBreak::PreferHighG,
+            TieBreak::PreferLowG,
+            TieBreak::Fifo,
+            TieBreak::Lifo,
+        ] {
+            assert_eq!(search(tie_break), search(tie_break));
+        }
+    }
+
+    #[test]
+    fn astar_hasher() {
+        // SipHash with fixed keys, which orders the nodes in its table differently.
+        type SipHasher = BuildHasherDefault<std::collections::hash_map::DefaultHasher>;
+
+        let successors = grid_successors(20);
+        let heuristic = |&(x, y): &(u64, u64)| 3 * (19 - x).max(19 - y);
+        let success = |&node: &(u64, u64)| node == (19, 19);
+        let options = SearchOptions::new().expected_states(Some(400));
+        let (default, default_stats) =
+            astar_with_options(&(0, 0), successors, heuristic, success, &options);
+        let options = options.hasher(SipHasher::default());
+        let (sip, sip_stats) =
+            astar_with_options(&(0, 0), successors, heuristic, success, &options);
+        assert_eq!(default, sip);
+        assert_eq!(default_stats.expanded, sip_stats.expanded);
+        assert_eq!(default_stats.generated, sip_stats.generated);
+    }
+
+    #[test]
+    fn astar_expansion_limit() {
+        let successors = grid_successors(20);
+        let heuristic = |&(x, y): &(u64, u64)| 3 * (19 - x).max(19 - y);
+        let success = |&node: &(u64, u64)| node == (19, 19);
+        let (optimum, _) = astar_with_options(
+            &(0, 0),
+            successors,
+            heuristic,
+            success,
+            &SearchOptions::new(),
+        );
+        let (_, optimum) = optimum.found().unwrap();
+        for limit in [1, 10, 100] {
+            let options = SearchOptions::new().max_expansions(Some(limit));
+            let (outcome, stats) =
+                astar_with_options(&(0, 0), successors, heuristic, success, &options);
+            let SearchOutcome::Aborted {
+                best_f_lower_bound,
+                expanded,
+                frontier_size,
+            } = outcome
+            else {
+                panic!("not aborted: {:?}", outcome);
+            };
+            assert_eq!(expanded, limit as usize);
+            assert_eq!(stats.expanded, expanded);
+            assert!(frontier_size > 0);
+            assert!(best_f_lower_bound <= optimum);
+        }
+    }
+
+    #[test]
+    fn astar_reopening() {
+        // The heuristic is admissible but not consistent: h(2) = 5 > 1 + h(3), so 3 is expanded
+        // through 1 before the cheaper path through 2 is found.
+        let successors = |&n: &u32| match n {
+            0 => vec![(1, 1u32), (2, 2)],
+            1 => vec![(3, 5)],
+            2 => vec![(3, 1)],
+            3 => vec![(4, 4)],
+            _ => vec![],
+        };
+        let heuristic = |&n: &u32| if n == 2 { 5 } else { 0 };
+        let search = |reopening| {
+            let options = SearchOptions::new().reopening(reopening);
+            astar_with_options(&0, successors, heuristic, |&n| n == 4, &options)
+        };
+        let optimal = SearchOutcome::Found {
+            path: vec![0, 2, 3, 4],
+            cost: 7,
+        };
+
+        let (outcome, stats) = search(Reopening::Forbid);
+        assert_eq!(
+            outcome,
+            SearchOutcome::Found {
+                path: vec![0, 1, 3, 4],
+                cost: 10
+            }
+        );
+        assert_eq!((stats.expanded, stats.reexpanded), (4, 0));
+
+        let (outcome, stats) = search(Reopening::Allow);
+        assert_eq!(outcome, optimal);
+        assert_eq!((stats.expanded, stats.reexpanded), (5, 1));
+
+        assert_eq!(
+            search(Reopening::BoundedBy(0)).0,
+            search(Reopening::Forbid).0
+        );
+        let (outcome, stats) = search(Reopening::BoundedBy(1));
+        assert_eq!(outcome, optimal);
+        assert_eq!(stats.reexpanded, 1);
+    }
+
+    #[test]
+    fn astar_progress() {
+        // A chain 0 -> 1 -> ... of cost 1 per move, so that the n-th expanded node has f = n.
+        let successors = |&n: &u32| vec![(n + 1, 1u32)];
+        let reported = Arc::new(Mutex::new(vec![]));
+        let options = SearchOptions::new().on_progress(2, {
+            let reported = Arc::clone(&reported);
+            move |info: &ProgressInfo<u32>| {
+                reported
+                    .lock()
+                    .push((info.expanded, info.min_f, info.open_len));
+                if info.min_f > 10 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            }
+        });
+        let (outcome, stats) = astar_with_options(&0, successors, |_| 0, |&n| n == 100, &options);
+        let SearchOutcome::Aborted {
+            best_f_lower_bound,
+            expanded,
+            ..
+        } = outcome
+        else {
+            panic!("expected an aborted search, got {:?}", outcome);
+        };
+        assert!(best_f_lower_bound >= 10);
+        assert_eq!((best_f_lower_bound, expanded), (12, 12));
+        assert_eq!(stats.expanded, 12);
+        assert_eq!(
+            *reported.lock(),
+            (1..=6)
+                .map(|i| (2 * i, 2 * i as u32, 1))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn astar_max_path_len() {
+        // A chain 0 -> 1 -> ... -> 6 of cost 1 per move, and the moves 0 -> 3 of cost 4 and
+        // 3 -> 6 of cost 5.
+        let successors = |&n: &u32| {
+            let mut succs = vec![];
+            if n < 6 {
+                succs.push((n + 1, 1u32));
+            }
+            match n {
+                0 => succs.push((3, 4)),
+                3 => succs.push((6, 5)),
+                _ => {}
+            }
+            succs
+        };
+        let search = |max_path_len| {
+            let options = SearchOptions::new().max_path_len(max_path_len);
+            astar_with_options(&0, successors, |_| 0, |&n| n == 6, &options).0
+        };
+        let unbounded = SearchOutcome::Found {
+            path: vec![0, 1, 2, 3, 4, 5, 6],
+            cost: 6,
+        };
+        assert_eq!(search(None), unbounded);
+        assert_eq!(search(Some(6)), unbounded);
+        // The cheapest path to 3 has too many moves to go on along the chain.
+        assert_eq!(
+            search(Some(4)),
+            SearchOutcome::Found {
+                path: vec![0, 3, 4, 5, 6],
+                cost: 7
+            }
+        );
+        assert_eq!(
+            search(Some(2)),
+            SearchOutcome::Found {
+                path: vec![0, 3, 6],
+                cost: 9
+            }
+        );
+        assert_eq!(
+            search(Some(1)),
+            SearchOutcome::NoPathWithinBound { lower_bound: 1 }
+        );
+    }
+
+    #[test]
+    fn astar_time_limit() {
+        let mut successors = grid_successors(20);
+        let slow_successors = |node: &(u64, u64)| {
+            std::thread::sleep(Duration::from_millis(1));
+            successors(node)
+        };
+        let heuristic = |&(x, y): &(u64, u64)| 3 * (19 - x).max(19 - y);
+        let success = |&node: &(u64, u64)| node == (19, 19);
+        let options = SearchOptions::new().time_limit(Some(Duration::from_millis(20)));
+        let (outcome, stats) =
+            astar_with_options(&(0, 0), slow_successors, heuristic, success, &options);
+        let SearchOutcome::Aborted {
+            best_f_lower_bound,
+            expanded,
+            ..
+        } = outcome
+        else {
+            panic!("not aborted: {:?}", outcome);
+        };
+        assert!(expanded < 400);
+        assert!(stats.time >= Duration::from_millis(20));
+        let optimum = astar(&(0, 0), successors, heuristic, success).unwrap().1;
+        assert!(best_f_lower_bound <= optimum);
+    }
+
+    #[test]
+    fn astar_product() {
+        // The model a d c, whose a d may be skipped by a silent move τ, with 4 states, and the
+        // trace a b c. Either b is a log move and d a model move, or a and b are log moves.
+        let model = |&m: &usize| match m {
+            0 => vec![(Some('a'), 1), (None, 2)],
+            1 => vec![(Some('d'), 2)],
+            2 => vec![(Some('c'), 3)],
+            _ => vec![],
+        };
+        let events = ['a', 'b', 'c'];
+        let trace = |&t: &usize| events.get(t).map(|&event| (event, t + 1));
+        // The standard costs: 1 for a model or log move, 0 for a silent or synchronous one.
+        let cost = |m: Option<&Option<char>>, t: Option<&char>| match (m, t) {
+            (Some(None), None) => Some(0u32),
+            (Some(Some(_)), None) | (None, Some(_)) => Some(1),
+            (Some(Some(m)), Some(t)) if m == t => Some(0),
+            _ => None,
+        };
+        let success = |&state: &(usize, usize)| state == (3, 3);
+
+        let (path, cost_zero) = astar(
+            &(0, 0),
+            product_successors(model, trace, cost),
+            |_| 0,
+            success,
+        )
+        .unwrap();
+        assert_eq!(cost_zero, 2);
+        assert_eq!(path.len(), 5);
+
+        // Lower bounds: d is not in the trace, and b is not in the model.
+        let heuristic = product_heuristic(
+            |&m: &usize| u32::from(m == 1),
+            |&t: &usize| u32::from(t < 2),
+        );
+        let (_, cost) = astar(
+            &(0, 0),
+            product_successors(model, trace, cost),
+            heuristic,
+            success,
+        )
+        .unwrap();
+        assert_eq!(cost, cost_zero);
+    }
+
+    #[test]
+    fn searcher_reuses_storage() {
+        use crate::counting_allocator::count_allocations;
+
+        // Moves between the neighbouring cells of a 30 × 30 grid, without allocating.
+        let side = 30u32;
+        let successors = move |&(x, y): &(u32, u32)| {
+            [
+                (x > 0).then(|| ((x - 1, y), 1u32)),
+                (x + 1 < side).then(|| ((x + 1, y), 1)),
+                (y > 0).then(|| ((x, y - 1), 1)),
+                (y + 1 < side).then(|| ((x, y + 1), 1)),
+            ]
+            .into_iter()
+            .flatten()
+        };
+        let goal = (side - 1, side / 2);
+        let heuristic = move |&(x, y): &(u32, u32)| goal.0.abs_diff(x) + goal.1.abs_diff(y);
+
+        let mut searcher = Searcher::new();
+        // A search of the whole grid grows the storage to about its final size.
+        assert!(
+            searcher
+                .search(&(0, 0), successors, |_| 0, |_| false)
+                .is_none()
+        );
+        let mut allocations = 0;
+        for i in 0..1000 {
+            let start = (i % side, (i / side) % side);
+            let expected = astar(&start, successors, heuristic, |&p| p == goal);
+            let mut res = None;
+            allocations += count_allocations(|| {
+                res = searcher.search(&start, successors, heuristic, |&p| p == goal);
+            });
+            assert_eq!(res, expected);
+        }
+        // The paths, and a few times growing the storage further.
+        assert!(allocations <= 1000 + 10, "{} allocations", allocations);
+    }
+
+    type Cell = (u64, u64);
+
+    /// The moves between the neighbouring cells of a `side` × `side` grid, of cost 3.
+    fn grid_successors(side: u64) -> impl FnMut(&Cell) -> Vec<(Cell, u64)> + Copy {
+        move |&(x, y)| {
+            let mut succs = vec![];
+            if x > 0 {
+                succs.push(((x - 1, y), 3));
+            }
+            if x + 1 < side {
+                succs.push(((x + 1, y), 3));
+            }
+            if y > 0 {
+                succs.push(((x, y - 1), 3));
+            }
+            if y + 1 < side {
+                succs.push(((x, y + 1), 3));
+            }
+            succs
+        }
+    }
+
+    #[test]
+    fn astar_weighted_grid() {
+        // Twice the Manhattan distance is two thirds of the true cost, so exact A* expands
+        // nearly every cell, while weighting the heuristic by 3/2 makes it exact.
+        let side = 20;
+        let goal = (side - 1, side - 1);
+        let heuristic = |&(x, y): &(u64, u64)| 2 * (goal.0 - x + goal.1 - y);
+        let optimum = 3 * 2 * (side - 1);
+
+        let exact = astar_weighted(
+            &(0, 0),
+            grid_successors(side),
+            heuristic,
+            |&p| p == goal,
+            0.0,
+        )
+        .unwrap();
+        assert_eq!(exact.cost, optimum);
+        assert_eq!(exact.bound, optimum);
+        let (path, cost) =
+            astar(&(0, 0), grid_successors(side), heuristic, |&p| p == goal).unwrap();
+        assert_eq!(exact.path, path);
+        assert_eq!(exact.cost, cost);
+
+        let weighted = astar_weighted(
+            &(0, 0),
+            grid_successors(side),
+            heuristic,
+            |&p| p == goal,
+            0.5,
+        )
+        .unwrap();
+        assert!(weighted.bound <= optimum && optimum <= weighted.cost);
+        assert!(2 * weighted.cost <= 3 * weighted.bound);
+        assert!(
+            weighted.stats.expanded < exact.stats.expanded,
+            "weighted: {}, exact: {}",
+            weighted.stats.expanded,
+            exact.stats.expanded
+        );
+    }
+
+    #[cfg(feature = "linear_programming")]
+    #[test]
+    fn astar_exact_costs() {
+        use crate::{f_ab, f0_ab};
+
+        // 0 -> 1 -> 2 costs 1/3 + 1/2; the direct move 0 -> 2 is forbidden, and so is the only
+        // move to 3.
+        let successors = |&n: &u32| match n {
+            0 => vec![(1, f_ab!(1, 3)), (2, AbnormalFraction::infinity())],
+            1 => vec![(2, f_ab!(1, 2))],
+            2 => vec![(3, AbnormalFraction::infinity())],
+            _ => vec![],
+        };
+        let (path, cost) = astar_ab(&0, successors, |_| f0_ab!(), |&n| n == 2).unwrap();
+        assert_eq!(path, vec![0, 1, 2]);
+        assert_eq!(cost, f_ab!(5, 6));
+
+        assert!(astar_ab(&0, successors, |_| f0_ab!(), |&n| n == 3).is_none());
+    }
+}
@@ -0,0 +1,261 @@
+//! All the shortest paths to a goal, kept as a graph of the parents of the nodes rather
+//! than as separate paths.
+
+use std::{collections::BinaryHeap, hash::Hash};
+
+use ebi_arithmetic::ebi_number::Zero;
+use indexmap::map::Entry::{Occupied, Vacant};
+
+use crate::{
+    astar::{Cost, FxIndexMap, SmallestCostHolder},
+    astar_options::TieBreak,
+};
+
+/// Compute all the shortest paths from `start` to nodes for which `success` holds, with the
+/// arguments of [`astar`](crate::astar::astar). Returns an iterator over the paths and their
+/// cost, or `None` if there is no path.
+///
+/// The search keeps, per node, all its parents on a shortest path to it, and goes on until
+/// every node that may lie on a shortest path to a goal is expanded, which requires an
+/// admissible heuristic. The iterator then walks the graph of the parents: the paths share
+/// their prefixes until they are returned. As the number of paths may grow exponentially with
+/// their length, `max_paths` limits the paths returned; [`AllOptimalPaths::capped`] tells
+/// whether there were more.
+///
+/// Paths end at their first node for which `success` holds.
+pub fn astar_all_optimal<N, C, FN, IN, FH, FS>(
+    start: &N,
+    mut successors: FN,
+    mut heuristic: FH,
+    mut success: FS,
+    max_paths: Option<usize>,
+) -> Option<(AllOptimalPaths<N>, C)>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    let mut to_see = BinaryHeap::from([SmallestCostHolder {
+        estimated_cost: Zero::zero(),
+        cost: Zero::zero(),
+        index: 0,
+        depth: 0,
+        seq: 0,
+        tie_break: TieBreak::default(),
+    }]);
+    // Per node seen: the indices of its parents on the shortest paths to it, and their cost.
+    let mut parents: FxIndexMap<N, (Vec<usize>, C)> = FxIndexMap::default();
+    parents.insert(start.clone(), (vec![], Zero::zero()));
+    let mut goals = vec![];
+    let mut best_cost: Option<C> = None;
+    let mut seq = 0;
+
+    while let Some(SmallestCostHolder {
+        estimated_cost,
+        cost,
+        index,
+        depth,
+        ..
+    }) = to_see.pop()
+    {
+        if best_cost
+            .as_ref()
+            .is_some_and(|best_cost| &estimated_cost > best_cost)
+        {
+            break;
+        }
+        let successors = {
+            let (node, (_, c)) = parents.get_index(index).unwrap(); // Cannot fail
+            if &cost > c {
+                continue;
+            }
+            if success(node) {
+                best_cost = Some(cost);
+                goals.push(index);
+                continue;
+            }
+            successors(node)
+        };
+        for (successor, mut new_cost) in successors {
+            if new_cost.is_forbidden() {
+                continue;
+            }
+            new_cost += cost.clone();
+            match parents.entry(successor) {
+                Vacant(e) => {
+                    let n = e.index();
+                    let h = heuristic(e.key());
+                    e.insert((vec![index], new_cost.clone()));
+                    let mut estimated_cost = new_cost.clone();
+                    estimated_cost += h;
+                    seq += 1;
+                    to_see.push(SmallestCostHolder {
+                        estimated_cost,
+                        cost: new_cost,
+                        index: n,
+                        depth: depth + 1,
+                        seq,
+                        tie_break: TieBreak::default(),
+                    });
+                }
+                Occupied(mut e) => {
+                    let (node_parents, node_cost) = e.get_mut();
+                    if *node_cost == new_cost {
+                        // Another shortest path; a node may be returned several times by
+                        // `successors`.
+                        if node_parents.last() != Some(&index) {
+                            node_parents.push(index);
+                        }
+                    } else if *node_cost > new_cost {
+                        *node_parents = vec![index];
+                        *node_cost = new_cost.clone();
+                        let n = e.index();
+                        let h = heuristic(e.key());
+                        let mut estimated_cost = new_cost.clone();
+                        estimated_cost += h;
+                        seq += 1;
+                        to_see.push(SmallestCostHolder {
+                            estimated_cost,
+                            cost: new_cost,
+                            index: n,
+                            depth: depth + 1,
+                            seq,
+                            tie_break: TieBreak::default(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    let cost = best_cost?;
+    Some((
+        AllOptimalPaths {
+            parents: parents
+                .into_iter()
+                .map(|(node, (node_parents, _))| (node, node_parents))
+                .collect(),
+            goals,
+            next_goal: 0,
+            stack: vec![],
+            remaining: max_paths,
+            capped: false,
+        },
+        cost,
+    ))
+}
+
+/// The iterator over the shortest paths returned by [`astar_all_optimal`], each comprising
+/// both the start and end node.
+pub struct AllOptimalPaths<N> {
+    /// Per node, the indices of its parents on the shortest paths to it.
+    parents: Vec<(N, Vec<usize>)>,
+    goals: Vec<usize>,
+    next_goal: usize,
+    /// The path last returned, from its goal: per node, its index and the position among its
+    /// parents of the next node.
+    stack: Vec<(usize, usize)>,
+    /// The number of paths that may still be returned.
+    remaining: Option<usize>,
+    capped: bool,
+}
+
+impl<N> AllOptimalPaths<N> {
+    /// Whether the iterator ended because the maximum number of paths was returned, while there
+    /// were more.
+    pub fn capped(&self) -> bool {
+        self.capped
+    }
+}
+
+impl<N: Clone> Iterator for AllOptimalPaths<N> {
+    type Item = Vec<N>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Go back to the last node with another parent.
+        if self.stack.pop().is_some() {
+            while let Some((node, position)) = self.stack.last_mut() {
+                if *position + 1 < self.parents[*node].1.len() {
+                    *position += 1;
+                    break;
+                }
+                self.stack.pop();
+            }
+        }
+        if self.remaining == Some(0) {
+            self.capped |= !self.stack.is_empty() || self.next_goal < self.goals.len();
+            return None;
+        }
+
+        // Follow the parents up to the start.
+        loop {
+            match self.stack.last() {
+                None => {
+                    let &goal = self.goals.get(self.next_goal)?;
+                    self.next_goal += 1;
+                    self.stack.push((goal, 0));
+                }
+                Some(&(node, position)) => match self.parents[node].1.get(position) {
+                    Some(&parent) => self.stack.push((parent, 0)),
+                    None => break,
+                },
+            }
+        }
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= 1;
+        }
+        Some(
+            (self.stack.iter().rev())
+                .map(|&(node, _)| self.parents[node].0.clone())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two diamonds in a row, so four paths of cost 4, and a path of cost 5.
+    fn diamonds(n: &u32) -> Vec<(u32, u32)> {
+        match n {
+            0 => vec![(1, 1), (2, 1), (6, 5)],
+            1 | 2 => vec![(3, 1)],
+            3 => vec![(4, 1), (5, 1), (5, 1)],
+            4 | 5 => vec![(6, 1)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn all_optimal_paths() {
+        let (paths, cost) = astar_all_optimal(&0, diamonds, |_| 0, |&n| n == 6, None).unwrap();
+        assert_eq!(cost, 4);
+        let mut paths = paths.collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                vec![0, 1, 3, 4, 6],
+                vec![0, 1, 3, 5, 6],
+                vec![0, 2, 3, 4, 6],
+                vec![0, 2, 3, 5, 6]
+            ]
+        );
+
+        let (mut paths, _) = astar_all_optimal(&0, diamonds, |_| 0, |&n| n == 6, Some(3)).unwrap();
+        assert_eq!(paths.by_ref().count(), 3);
+        assert!(paths.capped());
+        let (mut paths, _) = astar_all_optimal(&0, diamonds, |_| 0, |&n| n == 6, Some(4)).unwrap();
+        assert_eq!(paths.by_ref().count(), 4);
+        assert!(!paths.capped());
+
+        let (paths, cost) = astar_all_optimal(&0, diamonds, |_| 0, |&n| n == 0, None).unwrap();
+        assert_eq!(cost, 0);
+        assert_eq!(paths.collect::<Vec<_>>(), vec![vec![0]]);
+        assert!(astar_all_optimal(&0, diamonds, |_| 0, |&n| n == 7, None).is_none());
+    }
+}
@@ -0,0 +1,409 @@
+//! Anytime Repairing A* (ARA*): a series of weighted A* searches with decreasing weights,
+//! each of which reuses the work of the previous ones, reporting ever better paths.
+
+use std::{
+    collections::BinaryHeap,
+    hash::Hash,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+use ebi_arithmetic::ebi_number::Zero;
+use indexmap::map::Entry::{Occupied, Vacant};
+use log::debug;
+
+use crate::{
+    astar::{Cost, FxIndexMap, SmallestCostHolder, WeightedPath, path_to, weight_ratio},
+    astar_options::TieBreak,
+    astar_stats::SearchStats,
+};
+
+/// When [`arastar`] stops before it has searched with all its weights.
+#[derive(Clone, Copy, Debug)]
+pub enum AnytimeStop<'a> {
+    /// Search with all the weights.
+    Never,
+    /// Stop at this moment.
+    Deadline(Instant),
+    /// Stop as soon as the flag is set, for instance from another thread.
+    Flag(&'a AtomicBool),
+}
+
+impl AnytimeStop<'_> {
+    fn reached(&self) -> bool {
+        match self {
+            AnytimeStop::Never => false,
+            AnytimeStop::Deadline(deadline) => Instant::now() >= *deadline,
+            AnytimeStop::Flag(flag) => flag.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The state of a node in [`arastar`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NodeState {
+    /// In the open list.
+    Open,
+    /// Expanded in the current search.
+    Closed,
+    /// Reached cheaper after it was expanded in the current search; to be expanded in the
+    /// next one.
+    Inconsistent,
+    /// Expanded in an earlier search, and not reached cheaper since.
+    Done,
+}
+
+/// Search paths from `start` to a node for which `success` holds like
+/// [`astar_weighted`](crate::astar::astar_weighted), with each weight of `schedule` in turn.
+/// After each search that found a path, report the cheapest path found so far to `on_path`,
+/// with a lower bound on the cost of a shortest path, and the weight of the search. The
+/// weights must be non-increasing and at least 1; the last one is typically 1, which makes the
+/// last path a shortest one if the heuristic is consistent.
+///
+/// A search does not start from scratch: it continues from the open list of the previous one,
+/// and only expands again the nodes that were reached cheaper after they were expanded. The
+/// searches stop when `stop` is reached, even during a search.
+///
+/// Returns the cheapest path found, if any, with in [`WeightedPath::bound`] a lower bound on
+/// the cost of a shortest path, and the counters of all the searches together.
+///
+/// # Panics
+///
+/// If a weight is less than 1 or not finite, or greater than the weight before it.
+pub fn arastar<N, C, FN, IN, FH, FS, FP>(
+    start: &N,
+    mut successors: FN,
+    mut heuristic: FH,
+    mut success: FS,
+    schedule: &[f64],
+    stop: AnytimeStop<'_>,
+    mut on_path: FP,
+) -> Option<WeightedPath<N, C>>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+    FP: FnMut(&WeightedPath<N, C>, f64),
+{
+    for &weight in schedule {
+        assert!(
+            weight >= 1.0 && weight.is_finite(),
+            "weights must be numbers of at least 1, not {}",
+            weight
+        );
+    }
+    for pair in schedule.windows(2) {
+        assert!(
+            pair[1] <= pair[0],
+            "weights must be non-increasing, not {} after {}",
+            pair[1],
+            pair[0]
+        );
+    }
+    let start_time = Instant::now();
+    let mut stats = SearchStats::default();
+    // Per node seen: its parent and the cost of the best path to it found so far, its
+    // heuristic, whether it is a goal, and its state.
+    let mut parents: FxIndexMap<N, (usize, C)> = FxIndexMap::default();
+    parents.insert(start.clone(), (usize::MAX, Zero::zero()));
+    let mut heuristics = vec![heuristic(start)];
+    let mut goals = vec![success(start)];
+    let mut states = vec![NodeState::Open];
+    // The cheapest goal found.
+    let mut goal = goals[0].then_some(0);
+    let mut best: Option<WeightedPath<N, C>> = None;
+
+    for &weight in schedule {
+        if stop.reached() {
+            break;
+        }
+        let ratio = weight_ratio(weight);
+        let priority = |cost: &C, h: &C| {
+            let mut priority = cost.clone();
+            match ratio {
+                Some((numerator, denominator)) => priority += h.scale(numerator, denominator),
+                None => priority += h.clone(),
+            }
+            priority
+        };
+
+        // The open list holds the open and the inconsistent nodes, with the new weight.
+        let mut to_see = BinaryHeap::new();
+        for (index, state) in states.iter_mut().enumerate() {
+            match state {
+                NodeState::Open | NodeState::Inconsistent => {
+                    *state = NodeState::Open;
+                    let (_, (_, cost)) = parents.get_index(index).unwrap(); // Cannot fail
+                    to_see.push(SmallestCostHolder {
+                        estimated_cost: priority(cost, &heuristics[index]),
+                        cost: cost.clone(),
+                        index,
+                        depth: 0,
+                        seq: index,
+                        tie_break: TieBreak::default(),
+                    });
+                }
+                NodeState::Closed => *state = NodeState::Done,
+                NodeState::Done => {}
+            }
+        }
+
+        // Expand until no node in the open list can lead to a goal cheaper than the one found,
+        // up to the weight.
+        let mut stopped = false;
+        while let Some(holder) = to_see.peek() {
+            if goal.is_some_and(|goal| parents[goal].1 <= holder.estimated_cost) {
+                break;
+            }
+            if stop.reached() {
+                stopped = true;
+                break;
+            }
+            let SmallestCostHolder {
+                cost, index, depth, ..
+            } = to_see.pop().unwrap(); // Cannot fail
+            let successors = {
+                let (node, (_, c)) = parents.get_index(index).unwrap(); // Cannot fail
+                if &cost > c || states[index] != NodeState::Open {
+                    stats.duplicates_skipped += 1;
+                    continue;
+                }
+                states[index] = NodeState::Closed;
+                stats.expanded += 1;
+                stats.max_depth = stats.max_depth.max(depth);
+                successors(node)
+            };
+            for (successor, mut new_cost) in successors {
+                if new_cost.is_forbidden() {
+                    continue;
+                }
+                stats.generated += 1;
+                new_cost += cost.clone();
+                let n;
+                match parents.entry(successor) {
+                    Vacant(e) => {
+                        n = e.index();
+                        heuristics.push(heuristic(e.key()));
+                        goals.push(success(e.key()));
+                        states.push(NodeState::Open);
+                        e.insert((index, new_cost.clone()));
+                    }
+                    Occupied(mut e) => {
+                        if e.get().1 > new_cost {
+                            n = e.index();
+                            e.insert((index, new_cost.clone()));
+                        } else {
+                            stats.duplicates_skipped += 1;
+                            continue;
+                        }
+                    }
+                }
+                if goals[n] && goal.is_none_or(|goal| parents[goal].1 >= new_cost) {
+                    goal = Some(n);
+                }
+                match states[n] {
+                    NodeState::Closed | NodeState::Inconsistent => {
+                        states[n] = NodeState::Inconsistent;
+                    }
+                    NodeState::Open | NodeState::Done => {
+                        states[n] = NodeState::Open;
+                        to_see.push(SmallestCostHolder {
+                            estimated_cost: priority(&new_cost, &heuristics[n]),
+                            cost: new_cost,
+                            index: n,
+                            depth: depth + 1,
+                            seq: stats.generated,
+                            tie_break: TieBreak::default(),
+                        });
+                    }
+                }
+            }
+            stats.max_open_len = stats.max_open_len.max(to_see.len());
+        }
+
+        // Report the cheapest path.
+        if let Some(goal) = goal {
+            let cost = &parents[goal].1;
+            if best.as_ref().is_none_or(|best| cost < &best.cost) {
+                best = Some(WeightedPath {
+                    path: path_to(&parents, goal),
+                    cost: cost.clone(),
+                    bound: Zero::zero(),
+                    stats: SearchStats::default(),
+                });
+            }
+            if let Some(best) = best.as_mut() {
+                best.bound = lower_bound(&parents, &heuristics, &states, &best.cost);
+                best.stats = SearchStats {
+                    time: start_time.elapsed(),
+                    ..stats.clone()
+                };
+                debug!(
+                    "ARA*: weight {}, cost {:?}, bound {:?}, {}",
+                    weight, best.cost, best.bound, best.stats
+                );
+                on_path(best, weight);
+            }
+        }
+        if stopped {
+            break;
+        }
+    }
+    best
+}
+
+/// The smallest cost plus heuristic of the nodes in the open list or inconsistent, or `cost`
+/// if that is smaller: a lower bound on the cost of a shortest path.
+fn lower_bound<N, C: Cost>(
+    parents: &FxIndexMap<N, (usize, C)>,
+    heuristics: &[C],
+    states: &[NodeState],
+    cost: &C,
+) -> C {
+    let mut bound = cost.clone();
+    for (index, state) in states.iter().enumerate() {
+        if matches!(state, NodeState::Open | NodeState::Inconsistent) {
+            let mut f = parents[index].1.clone();
+            f += heuristics[index].clone();
+            if f < bound {
+                bound = f;
+            }
+        }
+    }
+    bound
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::astar;
+
+    type Cell = (u32, u32);
+
+    /// A `side` × `side` maze of rooms, each 3 cells wide, joined at alternating ends, with
+    /// moves of cost 3.
+    fn maze(side: u32) -> impl Fn(&Cell) -> Vec<(Cell, u32)> + Copy {
+        let open = move |(x, y): (u32, u32)| {
+            x % 4 != 3 || y == if (x / 4) % 2 == 0 { side - 1 } else { 0 }
+        };
+        move |&(x, y)| {
+            [
+                (x > 0).then(|| (x - 1, y)),
+                (x + 1 < side).then(|| (x + 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (y + 1 < side).then(|| (x, y + 1)),
+            ]
+            .into_iter()
+            .flatten()
+            .filter(|&cell| open(cell))
+            .map(|cell| (cell, 3))
+            .collect()
+        }
+    }
+
+    #[test]
+    fn arastar_improves() {
+        let side = 23;
+        let successors = maze(side);
+        let goal = (side - 1, side - 1);
+        let heuristic = |&(x, y): &(u32, u32)| 3 * (goal.0 - x + goal.1 - y);
+        let (_, optimal) = astar(&(0, 0), successors, heuristic, |&p| p == goal).unwrap();
+
+        let mut reported: Vec<(u32, u32, f64)> = vec![];
+        let best = arastar(
+            &(0, 0),
+            successors,
+            heuristic,
+            |&p| p == goal,
+            &[5.0, 3.0, 2.0, 1.5, 1.0],
+            AnytimeStop::Never,
+            |path, weight| reported.push((path.cost, path.bound, weight)),
+        )
+        .unwrap();
+        assert!(!reported.is_empty());
+        for (cost, bound, weight) in &reported {
+            assert!(bound <= cost);
+            assert!(*bound <= optimal);
+            assert!(*cost as f64 <= weight * optimal as f64);
+        }
+        for pair in reported.windows(2) {
+            assert!(pair[1].0 <= pair[0].0);
+        }
+        assert_eq!(best.cost, optimal);
+        assert_eq!(best.bound, optimal);
+        assert_eq!(reported.last().unwrap().2, 1.0);
+        assert_eq!(best.path.first(), Some(&(0, 0)));
+        assert_eq!(best.path.last(), Some(&goal));
+    }
+
+    #[test]
+    fn arastar_stops() {
+        let side = 23;
+        let successors = maze(side);
+        let goal = (side - 1, side - 1);
+        let heuristic = |&(x, y): &(u32, u32)| 3 * (goal.0 - x + goal.1 - y);
+
+        // Cancelled after the first path.
+        let cancel = AtomicBool::new(false);
+        let mut reported = 0;
+        let best = arastar(
+            &(0, 0),
+            successors,
+            heuristic,
+            |&p| p == goal,
+            &[5.0, 3.0, 2.0, 1.5, 1.0],
+            AnytimeStop::Flag(&cancel),
+            |_, _| {
+                reported += 1;
+                cancel.store(true, Ordering::Relaxed);
+            },
+        )
+        .unwrap();
+        assert_eq!(reported, 1);
+        assert!(best.bound <= best.cost);
+
+        // Cancelled before the first path.
+        let best = arastar(
+            &(0, 0),
+            successors,
+            heuristic,
+            |&p| p == goal,
+            &[5.0, 1.0],
+            AnytimeStop::Deadline(Instant::now()),
+            |_, _| panic!("no path expected"),
+        );
+        assert!(best.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "weights must be non-increasing, not 3 after 2")]
+    fn arastar_increasing_weights() {
+        // Checked before the search, which would stop at once.
+        arastar(
+            &0,
+            |&n: &u32| [(n + 1, 1)],
+            |_| 0,
+            |&n| n == 10,
+            &[2.0, 3.0, 1.0],
+            AnytimeStop::Deadline(Instant::now()),
+            |_, _| {},
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "weights must be numbers of at least 1, not 0.5")]
+    fn arastar_small_weight() {
+        arastar(
+            &0,
+            |&n: &u32| [(n + 1, 1)],
+            |_| 0,
+            |&n| n == 10,
+            &[2.0, 1.0, 0.5],
+            AnytimeStop::Deadline(Instant::now()),
+            |_, _| {},
+        );
+    }
+}
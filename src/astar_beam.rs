@@ -0,0 +1,223 @@
+//! Beam search: a search by depth that only keeps the most promising nodes of each layer,
+//! within a fixed amount of memory.
+
+use std::{hash::Hash, time::Instant};
+
+use ebi_arithmetic::ebi_number::Zero;
+use indexmap::map::Entry::{Occupied, Vacant};
+use log::debug;
+
+use crate::{
+    astar::{Cost, FxIndexMap, path_to},
+    astar_stats::SearchStats,
+};
+
+/// The result of [`beam_search`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BeamOutcome<N, C> {
+    /// The cheapest path found to a node for which `success` holds, comprising both the start
+    /// and end node, and its cost.
+    pub best: Option<(Vec<N>, C)>,
+    /// Whether nodes were left out of a layer for exceeding the width. If so, there may be a
+    /// cheaper path than `best`, or a path even if none was found; if not, `best` is a
+    /// shortest path.
+    pub pruned: bool,
+    /// The counters of the search.
+    pub stats: SearchStats,
+}
+
+/// Search a path from `start` to a node for which `success` holds like
+/// [`astar`](crate::astar::astar), layer by layer of the number of moves from the start,
+/// keeping only the `width` nodes of the smallest cost plus `heuristic` of each layer. This
+/// holds at most `width` nodes per layer, however large the graph, but is neither complete nor
+/// optimal: the pruned nodes may have led to a cheaper path, or to the only one.
+/// [`BeamOutcome::pruned`] tells whether that happened.
+///
+/// The search goes on after a path is found, for a cheaper one, but leaves out the nodes
+/// whose cost plus heuristic is not below the cost of the best path so far. It ends when a
+/// layer is empty; on an infinite graph without a path, it does not end.
+///
+/// # Panics
+///
+/// If `width` is zero.
+pub fn beam_search<N, C, FN, IN, FH, FS>(
+    start: &N,
+    mut successors: FN,
+    mut heuristic: FH,
+    mut success: FS,
+    width: usize,
+) -> BeamOutcome<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    assert!(width > 0, "the width of a beam search must be positive");
+    let start_time = Instant::now();
+    let mut stats = SearchStats {
+        max_open_len: 1,
+        ..SearchStats::default()
+    };
+    // The nodes kept in a layer, with their parent and cost.
+    let mut parents: FxIndexMap<N, (usize, C)> = FxIndexMap::default();
+    parents.insert(start.clone(), (usize::MAX, Zero::zero()));
+    let mut layer = vec![0];
+    // The successors of a layer, with their parent, cost and estimated cost.
+    let mut candidates: FxIndexMap<N, (usize, C, C)> = FxIndexMap::default();
+    let mut best: Option<(Vec<N>, C)> = None;
+    let mut pruned = false;
+    let mut depth = 0;
+    while !layer.is_empty() {
+        for &index in &layer {
+            let (node, (_, cost)) = parents.get_index(index).unwrap(); // Cannot fail
+            if success(node) {
+                if best.as_ref().is_none_or(|(_, best)| cost < best) {
+                    best = Some((path_to(&parents, index), cost.clone()));
+                }
+                continue;
+            }
+            stats.expanded += 1;
+            stats.max_depth = depth;
+            for (successor, move_cost) in successors(node) {
+                if move_cost.is_forbidden() {
+                    continue;
+                }
+                stats.generated += 1;
+                let mut new_cost = cost.clone();
+                new_cost += move_cost;
+                if parents.get(&successor).is_some_and(|(_, c)| c <= &new_cost)
+                    || candidates
+                        .get(&successor)
+                        .is_some_and(|(_, c, _)| c <= &new_cost)
+                {
+                    stats.duplicates_skipped += 1;
+                    continue;
+                }
+                let mut estimated_cost = new_cost.clone();
+                estimated_cost += heuristic(&successor);
+                if best
+                    .as_ref()
+                    .is_some_and(|(_, best)| &estimated_cost >= best)
+                {
+                    continue;
+                }
+                candidates.insert(successor, (index, new_cost, estimated_cost));
+            }
+        }
+
+        // The best candidates by estimated cost, then the highest cost like
+        // `TieBreak::PreferHighG`, then in the order they were generated.
+        let mut next = candidates.drain(..).collect::<Vec<_>>();
+        next.sort_by(|(_, (_, cost_a, f_a)), (_, (_, cost_b, f_b))| {
+            f_a.cmp(f_b).then_with(|| cost_b.cmp(cost_a))
+        });
+        if next.len() > width {
+            pruned = true;
+            next.truncate(width);
+        }
+        layer.clear();
+        for (node, (parent, cost, _)) in next {
+            let index = match parents.entry(node) {
+                Occupied(mut e) => {
+                    e.insert((parent, cost));
+                    e.index()
+                }
+                Vacant(e) => {
+                    let index = e.index();
+                    e.insert((parent, cost));
+                    index
+                }
+            };
+            layer.push(index);
+        }
+        stats.max_open_len = stats.max_open_len.max(layer.len());
+        depth += 1;
+    }
+    stats.time = start_time.elapsed();
+    debug!("beam search: done, pruned: {}, {}", pruned, stats);
+    BeamOutcome {
+        best,
+        pruned,
+        stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        astar::astar,
+        rand::{Rng, SeedableRng, rngs::StdRng},
+    };
+
+    #[test]
+    fn beam_width() {
+        // The cheapest first move leads to the dearest path: 0 -> 1 -> 3 costs 11, and
+        // 0 -> 2 -> 3 costs 3.
+        let successors = |&n: &u32| match n {
+            0 => vec![(1, 1u32), (2, 2)],
+            1 => vec![(3, 10)],
+            2 => vec![(3, 1)],
+            _ => vec![],
+        };
+        let narrow = beam_search(&0, successors, |_| 0, |&n| n == 3, 1);
+        assert_eq!(narrow.best, Some((vec![0, 1, 3], 11)));
+        assert!(narrow.pruned);
+        let wide = beam_search(&0, successors, |_| 0, |&n| n == 3, 2);
+        assert_eq!(wide.best, Some((vec![0, 2, 3], 3)));
+        assert!(!wide.pruned);
+    }
+
+    #[test]
+    fn beam_budget() {
+        // A 30 × 30 grid whose cells cost from 1 to 9 to enter.
+        let side = 30;
+        let mut rng = StdRng::seed_from_u64(702);
+        let costs = (0..side * side)
+            .map(|_| rng.random_range(1..10u32))
+            .collect::<Vec<_>>();
+        let successors = |&(x, y): &(usize, usize)| {
+            let mut succs = vec![];
+            if x > 0 {
+                succs.push((x - 1, y));
+            }
+            if x + 1 < side {
+                succs.push((x + 1, y));
+            }
+            if y > 0 {
+                succs.push((x, y - 1));
+            }
+            if y + 1 < side {
+                succs.push((x, y + 1));
+            }
+            succs
+                .into_iter()
+                .map(|(x, y)| ((x, y), costs[y * side + x]))
+                .collect::<Vec<_>>()
+        };
+        let goal = (side - 1, side - 1);
+        let heuristic = |&(x, y): &(usize, usize)| (goal.0 - x + goal.1 - y) as u32;
+        let success = |&cell: &(usize, usize)| cell == goal;
+        let optimum = astar(&(0, 0), successors, heuristic, success).unwrap().1;
+
+        for width in [3, 10, side * side] {
+            let outcome = beam_search(&(0, 0), successors, heuristic, success, width);
+            let (_, cost) = outcome.best.unwrap();
+            assert!(cost >= optimum);
+            if !outcome.pruned {
+                assert_eq!(cost, optimum);
+            }
+            let stats = outcome.stats;
+            assert!(stats.max_open_len <= width);
+            assert!(stats.expanded <= width * (stats.max_depth + 1));
+        }
+        let narrow = beam_search(&(0, 0), successors, heuristic, success, 3);
+        assert!(narrow.pruned);
+        let wide = beam_search(&(0, 0), successors, heuristic, success, side * side);
+        assert!(!wide.pruned);
+        assert_eq!(wide.best.unwrap().1, optimum);
+    }
+}
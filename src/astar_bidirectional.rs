@@ -0,0 +1,352 @@
+//! Bidirectional A*: a search from the start and a search backwards from the goal, which
+//! meet in the middle.
+
+use std::{cmp::Reverse, collections::BinaryHeap, hash::Hash, time::Instant};
+
+use ebi_arithmetic::ebi_number::Zero;
+use indexmap::map::Entry::{Occupied, Vacant};
+use log::debug;
+
+use crate::{
+    astar::{Cost, FxIndexMap, SmallestCostHolder},
+    astar_options::TieBreak,
+    astar_stats::SearchStats,
+};
+
+/// Compute a shortest path from `start` to `goal` with two A* searches, one forwards from
+/// `start` and one backwards from `goal`, which expand a node in turn until they meet. When
+/// both searches have to explore a wide area around their root, as with a weak heuristic,
+/// each of them only explores about half of the distance.
+///
+/// Unlike [`astar`](crate::astar::astar), this needs an explicit goal node instead of a
+/// predicate, and the moves in both directions:
+/// - `successors_fwd` returns the successors of a node, with the cost of each move, like for
+///   [`astar`](crate::astar::astar);
+/// - `successors_bwd` returns the predecessors of a node, with the cost of the move from the
+///   predecessor to the node; they must be the same moves as those of `successors_fwd`;
+/// - `heuristic_fwd` estimates the cost from a node to `goal`, and `heuristic_bwd` the cost
+///   from `start` to a node.
+///
+/// The path is a shortest one if both heuristics are consistent: the estimate of a node is
+/// at most the cost of a move to a neighbour plus the estimate of the neighbour. The search
+/// stops as soon as the cost of the best path through a node that both searches reached is
+/// at most the smallest estimated total cost of one of the directions, or at most the
+/// smallest cost of an open node of the forward search plus that of the backward search.
+///
+/// Returns the path, comprising both `start` and `goal`, and its cost, or `None` if there is
+/// no path; and the counters of both searches together.
+pub fn astar_bidirectional<N, C, FF, IF, FB, IB, HF, HB>(
+    start: &N,
+    goal: &N,
+    mut successors_fwd: FF,
+    mut successors_bwd: FB,
+    mut heuristic_fwd: HF,
+    mut heuristic_bwd: HB,
+) -> (Option<(Vec<N>, C)>, SearchStats)
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FF: FnMut(&N) -> IF,
+    IF: IntoIterator<Item = (N, C)>,
+    FB: FnMut(&N) -> IB,
+    IB: IntoIterator<Item = (N, C)>,
+    HF: FnMut(&N) -> C,
+    HB: FnMut(&N) -> C,
+{
+    let start_time = Instant::now();
+    let mut stats = SearchStats {
+        max_open_len: 2,
+        ..SearchStats::default()
+    };
+    if start == goal {
+        stats.time = start_time.elapsed();
+        return (Some((vec![start.clone()], Zero::zero())), stats);
+    }
+
+    let mut fwd = Side::new(start);
+    let mut bwd = Side::new(goal);
+    // The cost of the best path found so far, and the indices of its meeting node in the
+    // forward and the backward search.
+    let mut best: Option<(C, usize, usize)> = None;
+    let mut forward = true;
+    while let (Some(fwd_top), Some(bwd_top)) = (fwd.to_see.peek(), bwd.to_see.peek()) {
+        if let Some((cost, _, _)) = &best {
+            // Every path that is not known yet costs at least the smallest estimate of either
+            // direction, and at least the smallest costs to reach an open node from both ends.
+            if &fwd_top.estimated_cost >= cost || &bwd_top.estimated_cost >= cost {
+                break;
+            }
+            if let (Some(mut lower_bound), Some(bwd_cost)) =
+                (fwd.min_open_cost(), bwd.min_open_cost())
+            {
+                lower_bound += bwd_cost;
+                if &lower_bound >= cost {
+                    break;
+                }
+            }
+        }
+
+        if forward {
+            for (index, other_index, cost) in
+                fwd.expand(&bwd, &mut successors_fwd, &mut heuristic_fwd, &mut stats)
+            {
+                if best
+                    .as_ref()
+                    .is_none_or(|(best_cost, _, _)| &cost < best_cost)
+                {
+                    best = Some((cost, index, other_index));
+                }
+            }
+        } else {
+            for (index, other_index, cost) in
+                bwd.expand(&fwd, &mut successors_bwd, &mut heuristic_bwd, &mut stats)
+            {
+                if best
+                    .as_ref()
+                    .is_none_or(|(best_cost, _, _)| &cost < best_cost)
+                {
+                    best = Some((cost, other_index, index));
+                }
+            }
+        }
+        stats.max_open_len = stats.max_open_len.max(fwd.to_see.len() + bwd.to_see.len());
+        forward = !forward;
+    }
+    stats.time = start_time.elapsed();
+    debug!("bidirectional A*: done, {}", stats);
+
+    let res = best.map(|(cost, fwd_index, bwd_index)| {
+        let mut path = fwd.path_from_root(fwd_index);
+        path.reverse();
+        let mut bwd_path = bwd.path_from_root(bwd_index);
+        bwd_path.remove(0);
+        path.extend(bwd_path);
+        (path, cost)
+    });
+    (res, stats)
+}
+
+/// One direction of a bidirectional search.
+struct Side<N, C> {
+    to_see: BinaryHeap<SmallestCostHolder<C>>,
+    parents: FxIndexMap<N, (usize, C)>,
+    /// The open nodes by their cost from the root; entries of expanded nodes and of nodes
+    /// that were reached cheaper since are removed lazily.
+    by_cost: BinaryHeap<Reverse<(C, usize)>>,
+    /// Per node index, whether the node was expanded with its current cost.
+    expanded: Vec<bool>,
+}
+
+impl<N, C> Side<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+{
+    fn new(root: &N) -> Self {
+        let mut to_see = BinaryHeap::new();
+        to_see.push(SmallestCostHolder {
+            estimated_cost: Zero::zero(),
+            cost: Zero::zero(),
+            index: 0,
+            depth: 0,
+            seq: 0,
+            tie_break: TieBreak::default(),
+        });
+        let mut parents = FxIndexMap::default();
+        parents.insert(root.clone(), (usize::MAX, Zero::zero()));
+        Side {
+            to_see,
+            parents,
+            by_cost: BinaryHeap::from([Reverse((Zero::zero(), 0))]),
+            expanded: vec![false],
+        }
+    }
+
+    /// The smallest cost from the root of an open node.
+    fn min_open_cost(&mut self) -> Option<C> {
+        while let Some(Reverse((cost, index))) = self.by_cost.peek() {
+            if !self.expanded[*index] && cost <= &self.parents[*index].1 {
+                return Some(cost.clone());
+            }
+            self.by_cost.pop();
+        }
+        None
+    }
+
+    /// Expand the first node of the open list. Returns the paths through the successors that
+    /// `other` reached: the index of the successor in this and in the other search, and the
+    /// cost of the path.
+    fn expand<FN, IN, FH>(
+        &mut self,
+        other: &Side<N, C>,
+        successors: &mut FN,
+        heuristic: &mut FH,
+        stats: &mut SearchStats,
+    ) -> Vec<(usize, usize, C)>
+    where
+        FN: FnMut(&N) -> IN,
+        IN: IntoIterator<Item = (N, C)>,
+        FH: FnMut(&N) -> C,
+    {
+        let mut meetings = vec![];
+        let Some(SmallestCostHolder {
+            cost, index, depth, ..
+        }) = self.to_see.pop()
+        else {
+            return meetings;
+        };
+        let successors = {
+            let (node, (_, c)) = self.parents.get_index(index).unwrap(); // Cannot fail
+            if &cost > c {
+                stats.duplicates_skipped += 1;
+                return meetings;
+            }
+            stats.expanded += 1;
+            self.expanded[index] = true;
+            stats.max_depth = stats.max_depth.max(depth);
+            successors(node)
+        };
+        for (successor, mut move_cost) in successors {
+            if move_cost.is_forbidden() {
+                continue;
+            }
+            stats.generated += 1;
+            move_cost += cost.clone();
+            let new_cost = move_cost;
+            let other_entry = (other.parents)
+                .get_full(&successor)
+                .map(|(other_index, _, (_, other_cost))| (other_index, other_cost.clone()));
+            let h;
+            let n;
+            match self.parents.entry(successor) {
+                Vacant(e) => {
+                    h = heuristic(e.key());
+                    n = e.index();
+                    e.insert((index, new_cost.clone()));
+                    self.expanded.push(false);
+                }
+                Occupied(mut e) => {
+                    if e.get().1 > new_cost {
+                        h = heuristic(e.key());
+                        n = e.index();
+                        e.insert((index, new_cost.clone()));
+                        self.expanded[n] = false;
+                    } else {
+                        stats.duplicates_skipped += 1;
+                        continue;
+                    }
+                }
+            }
+            if let Some((other_index, mut other_cost)) = other_entry {
+                other_cost += new_cost.clone();
+                meetings.push((n, other_index, other_cost));
+            }
+
+            self.by_cost.push(Reverse((new_cost.clone(), n)));
+            let mut estimated_cost = new_cost.clone();
+            estimated_cost += h;
+            self.to_see.push(SmallestCostHolder {
+                estimated_cost,
+                cost: new_cost,
+                index: n,
+                depth: depth + 1,
+                seq: stats.generated,
+                tie_break: TieBreak::default(),
+            });
+        }
+        meetings
+    }
+
+    /// The path from the node with index `index` back to the root.
+    fn path_from_root(&self, index: usize) -> Vec<N> {
+        let mut path = vec![];
+        let mut i = index;
+        while i != usize::MAX {
+            let (node, &(parent, _)) = self.parents.get_index(i).unwrap(); // Cannot fail
+            path.push(node.clone());
+            i = parent;
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar::astar, astar_options::SearchOptions};
+
+    type Cell = (u32, u32);
+
+    /// The moves between the neighbouring cells of a `side` × `side` grid without the cells
+    /// of `wall`; the moves are the same in both directions.
+    fn grid_moves(side: u32, wall: &[Cell]) -> impl Fn(&Cell) -> Vec<(Cell, u32)> + Copy {
+        move |&(x, y)| {
+            [
+                (x > 0).then(|| (x - 1, y)),
+                (x + 1 < side).then(|| (x + 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (y + 1 < side).then(|| (x, y + 1)),
+            ]
+            .into_iter()
+            .flatten()
+            .filter(|cell| !wall.contains(cell))
+            .map(|cell| (cell, 1))
+            .collect()
+        }
+    }
+
+    #[test]
+    fn bidirectional_grid() {
+        let side = 20;
+        let wall = (0..side - 1).map(|y| (10, y)).collect::<Vec<_>>();
+        let moves = grid_moves(side, &wall);
+        let (start, goal): ((u32, u32), (u32, u32)) = ((0, 0), (side - 1, 0));
+        let to_goal = |&(x, y): &(u32, u32)| goal.0.abs_diff(x) + goal.1.abs_diff(y);
+        let to_start = |&(x, y): &(u32, u32)| start.0.abs_diff(x) + start.1.abs_diff(y);
+
+        let (path, cost) = astar(&start, moves, to_goal, |&p| p == goal).unwrap();
+        let (res, _) = astar_bidirectional(&start, &goal, moves, moves, to_goal, to_start);
+        let (bidirectional_path, bidirectional_cost) = res.unwrap();
+        assert_eq!(bidirectional_cost, cost);
+        assert_eq!(bidirectional_path.len(), path.len());
+        assert_eq!(bidirectional_path.first(), Some(&start));
+        assert_eq!(bidirectional_path.last(), Some(&goal));
+        for step in bidirectional_path.windows(2) {
+            assert!(moves(&step[0]).iter().any(|(cell, _)| *cell == step[1]));
+        }
+
+        // Walled in.
+        let wall = (0..side).map(|y| (10, y)).collect::<Vec<_>>();
+        let moves = grid_moves(side, &wall);
+        let (res, _) = astar_bidirectional(&start, &goal, moves, moves, to_goal, to_start);
+        assert!(res.is_none());
+    }
+
+    #[test]
+    fn bidirectional_expands_less() {
+        // Without a heuristic, the unidirectional search explores every cell closer to the
+        // start than the goal, and each direction only the cells up to about half of that.
+        let side = 41;
+        let moves = grid_moves(side, &[]);
+        let (start, goal) = ((0, side / 2), (side - 1, side / 2));
+
+        let (res, stats) = crate::astar::astar_with_options(
+            &start,
+            moves,
+            |_| 0,
+            |&p| p == goal,
+            &SearchOptions::new(),
+        );
+        let (_, cost) = res.found().unwrap();
+        let (res, bidirectional_stats) =
+            astar_bidirectional(&start, &goal, moves, moves, |_| 0, |_| 0);
+        let (_, bidirectional_cost) = res.unwrap();
+        assert_eq!(bidirectional_cost, cost);
+        assert!(
+            4 * bidirectional_stats.expanded < 3 * stats.expanded,
+            "bidirectional: {}, unidirectional: {}",
+            bidirectional_stats.expanded,
+            stats.expanded
+        );
+    }
+}
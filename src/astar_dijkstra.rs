@@ -0,0 +1,193 @@
+//! Dijkstra's algorithm with Dial's bucket queue, for small integer costs.
+
+use std::{
+    collections::{BinaryHeap, VecDeque},
+    hash::Hash,
+};
+
+use crate::{
+    astar::{Cost, FxIndexMap, OpenList, SearchCore, SearchOutcome, SmallestCostHolder},
+    astar_options::SearchOptions,
+    astar_stats::SearchStats,
+};
+
+/// Compute a shortest path like [`astar`](crate::astar::astar) without heuristic, that is
+/// with Dijkstra's algorithm. If the costs are integers (see [`Cost::bucket`]), the open list
+/// is a ring of `max_move_cost` + 1 buckets of the entries per cost, which is faster than a
+/// binary heap: as the costs of the entries taken from the open list only grow, and no entry
+/// costs more than `max_move_cost` above the last one taken, each has its bucket in the ring.
+/// Moves of a higher cost are still correct, but their entries are kept in a binary heap.
+/// Otherwise, the open list is a binary heap.
+///
+/// The nodes are expanded in the same order as by [`astar`](crate::astar::astar) with a zero
+/// heuristic, so the path is the same. Also returns the counters of the search, whose
+/// [`SearchStats::queue_operations`] compare the open lists.
+///
+/// ```
+/// # use ebi_optimisation::astar_dijkstra::dijkstra_buckets;
+/// let successors = |&n: &u32| [(n + 1, 1u32), (n + 3, 2)];
+/// let (found, _) = dijkstra_buckets(&0, successors, |&n| n == 9, 2);
+/// assert_eq!(found.unwrap().1, 6);
+/// ```
+pub fn dijkstra_buckets<N, C, FN, IN, FS>(
+    start: &N,
+    mut successors: FN,
+    mut success: FS,
+    max_move_cost: usize,
+) -> (Option<(Vec<N>, C)>, SearchStats)
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FS: FnMut(&N) -> bool,
+{
+    let parents: FxIndexMap<N, (usize, C)> = FxIndexMap::default();
+    let mut heuristic = |_: &N| C::zero();
+    if C::zero().bucket().is_some() {
+        let queue = DialQueue::new(max_move_cost);
+        let mut search = SearchCore::with_options(parents, queue, &SearchOptions::new());
+        search.reset(start);
+        let outcome = search.run(&mut successors, &mut heuristic, &mut success, None);
+        (outcome.found(), search.stats)
+    } else {
+        let mut search =
+            SearchCore::with_options(parents, BinaryHeap::new(), &SearchOptions::new());
+        search.reset(start);
+        let outcome: SearchOutcome<N, C> =
+            search.run(&mut successors, &mut heuristic, &mut success, None);
+        (outcome.found(), search.stats)
+    }
+}
+
+/// Dial's bucket queue: a ring of buckets of the entries per estimated cost, for integer
+/// costs whose entries are taken in order of estimated cost. The entries beyond the ring are
+/// kept in a binary heap. Unlike the [`BucketQueue`](crate::astar_indexed::BucketQueue), it
+/// needs no more buckets than the range of the estimated costs on the open list at once, and
+/// within a bucket, the first entry added comes first.
+pub(crate) struct DialQueue<C> {
+    buckets: Vec<VecDeque<SmallestCostHolder<C>>>,
+    /// The estimated cost of the last entry taken. The entries of the buckets have an
+    /// estimated cost from it to below it plus the number of buckets, and are in the bucket
+    /// of their estimated cost modulo the number of buckets.
+    current: usize,
+    /// The number of entries in the buckets.
+    ring_len: usize,
+    overflow: BinaryHeap<SmallestCostHolder<C>>,
+}
+
+impl<C> DialQueue<C> {
+    /// A queue for moves of a cost of at most `max_move_cost`.
+    pub(crate) fn new(max_move_cost: usize) -> Self {
+        DialQueue {
+            buckets: (0..=max_move_cost).map(|_| VecDeque::new()).collect(),
+            current: 0,
+            ring_len: 0,
+            overflow: BinaryHeap::new(),
+        }
+    }
+}
+
+impl<C: Cost> OpenList<C> for DialQueue<C> {
+    fn push(&mut self, holder: SmallestCostHolder<C>) {
+        let width = self.buckets.len();
+        match holder.estimated_cost.bucket() {
+            Some(key) if key >= self.current && key - self.current < width => {
+                self.buckets[key % width].push_back(holder);
+                self.ring_len += 1;
+            }
+            _ => self.overflow.push(holder),
+        }
+    }
+
+    fn pop(&mut self) -> Option<SmallestCostHolder<C>> {
+        let width = self.buckets.len();
+        let offset = (self.ring_len > 0)
+            .then(|| {
+                (0..width).find(|offset| !self.buckets[(self.current + offset) % width].is_empty())
+            })
+            .flatten();
+        let Some(offset) = offset else {
+            let holder = self.overflow.pop()?;
+            self.current = holder.estimated_cost.bucket().unwrap_or(self.current);
+            return Some(holder);
+        };
+        let bucket = (self.current + offset) % width;
+        let front = self.buckets[bucket].front().unwrap(); // Cannot fail
+        // The entry of the heap comes first if the heap would take it before the bucket's.
+        if self.overflow.peek().is_some_and(|top| top > front) {
+            let holder = self.overflow.pop().unwrap(); // Cannot fail
+            self.current = holder.estimated_cost.bucket().unwrap_or(self.current);
+            return Some(holder);
+        }
+        self.current += offset;
+        self.ring_len -= 1;
+        self.buckets[bucket].pop_front()
+    }
+
+    fn len(&self) -> usize {
+        self.ring_len + self.overflow.len()
+    }
+
+    fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            bucket.clear();
+        }
+        self.current = 0;
+        self.ring_len = 0;
+        self.overflow.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::{astar_with_options, tests::random_graph};
+
+    #[test]
+    fn dijkstra_random_graphs() {
+        for seed in 0..20 {
+            let graph = random_graph(seed);
+            let successors = |v: &usize| graph.successors(v);
+            let success = |&v: &usize| v == graph.goal;
+
+            let (generic, generic_stats) =
+                astar_with_options(&0, successors, |_| 0, success, &SearchOptions::new());
+            // Moves of a cost of 3 to 5 exceed the ring of 3 buckets.
+            for max_move_cost in [2, 5] {
+                let (buckets, stats) = dijkstra_buckets(&0, successors, success, max_move_cost);
+                assert_eq!(buckets, generic.clone().found());
+                assert_eq!(stats.expanded, generic_stats.expanded);
+                assert_eq!(stats.queue_operations, generic_stats.queue_operations);
+            }
+        }
+    }
+
+    #[test]
+    fn dijkstra_large_grid() {
+        // A 500 × 500 grid with moves of cost 1 between neighbours: a million moves.
+        let side = 500;
+        let successors = move |&v: &usize| {
+            let (x, y) = (v % side, v / side);
+            [
+                (x > 0).then(|| (v - 1, 1u32)),
+                (x + 1 < side).then(|| (v + 1, 1)),
+                (y > 0).then(|| (v - side, 1)),
+                (y + 1 < side).then(|| (v + side, 1)),
+            ]
+            .into_iter()
+            .flatten()
+        };
+        let goal = side * side - 1;
+        let success = |&v: &usize| v == goal;
+
+        let (generic, generic_stats) =
+            astar_with_options(&0, successors, |_| 0, success, &SearchOptions::new());
+        let (buckets, stats) = dijkstra_buckets(&0, successors, success, 1);
+
+        let (path, cost) = buckets.unwrap();
+        assert_eq!(cost, 2 * (side as u32 - 1));
+        assert_eq!(Some((path, cost)), generic.found());
+        assert_eq!(stats.queue_operations, generic_stats.queue_operations);
+    }
+}
@@ -0,0 +1,409 @@
+//! Focal search: paths within a factor of the shortest, chosen by a secondary criterion.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, BinaryHeap},
+    hash::Hash,
+    ops::Bound::{Excluded, Unbounded},
+    time::Instant,
+};
+
+use ebi_arithmetic::ebi_number::Zero;
+use log::debug;
+
+use crate::{
+    astar::{Cost, FxIndexMap, NodeStorage, weight_ratio},
+    astar_labeled::LabeledPath,
+    astar_stats::SearchStats,
+};
+
+/// A node reached by [`focal_search`], for its secondary criterion.
+pub struct FocalNode<'a, N, C, L> {
+    pub node: &'a N,
+    /// The cost of the path to the node.
+    pub cost: &'a C,
+    /// The number of moves on the path to the node.
+    pub depth: usize,
+    label_counts: &'a [(L, usize)],
+}
+
+impl<N, C, L: PartialEq> FocalNode<'_, N, C, L> {
+    /// The number of moves with `label` on the path to the node.
+    pub fn label_count(&self, label: &L) -> usize {
+        (self.label_counts.iter())
+            .find(|(l, _)| l == label)
+            .map_or(0, |(_, count)| *count)
+    }
+}
+
+/// A path found by [`focal_search`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FocalPath<N, C, L> {
+    pub path: LabeledPath<N, L>,
+    /// The cost of `path`.
+    pub cost: C,
+    /// A lower bound on the cost of a shortest path: the smallest cost plus heuristic of the
+    /// open list when the path was found.
+    pub lower_bound: C,
+    /// The weight times `lower_bound`, which `cost` does not exceed.
+    pub bound: C,
+    /// The counters of the search.
+    pub stats: SearchStats,
+}
+
+/// What focal search knows of a node, by index in the map of the parents.
+struct FocalData<C, L> {
+    heuristic: C,
+    /// The label of the move to the node on the best path to it.
+    label: Option<L>,
+    /// The cost of that move.
+    move_cost: C,
+    depth: usize,
+    label_counts: Vec<(L, usize)>,
+    /// The estimated cost of the node if it is on the open list.
+    open: Option<C>,
+    expanded: bool,
+    /// The stamp of the entry of the node on the focal list that is not stale.
+    stamp: usize,
+}
+
+/// Focal search (Pearl and Kim, 1982): search a path from `start` to a node for which
+/// `success` holds, whose cost is at most `weight` times that of a shortest path, and that is
+/// preferred by a secondary criterion among those. Like
+/// [`astar_labeled`](crate::astar_labeled::astar_labeled), `successors` return triples of a
+/// successor, the cost of the move and its label, and `heuristic` must be admissible.
+///
+/// Besides the open list by cost plus heuristic, the search keeps the focal list of the open
+/// nodes whose cost plus heuristic is at most `weight` times the smallest one, and expands the
+/// node of the focal list with the smallest `secondary` key, then the smallest cost plus
+/// heuristic. The key of a node is computed when a path to it is found, from its
+/// [`FocalNode`]: the node, the cost and the number of moves of the path, and the number of
+/// moves per label of the path, for instance to prefer alignments with fewer model moves.
+/// A node reached by a cheaper path is opened again, even if it was expanded.
+///
+/// With a `weight` of 1, the path is a shortest one, chosen among those by `secondary`. The
+/// [`FocalPath::lower_bound`] tells how far from a shortest path the path actually is.
+///
+/// # Panics
+///
+/// If `weight` is less than 1 or not finite.
+///
+/// ```
+/// # use ebi_optimisation::astar_focal::focal_search;
+/// // Two goals: 3 at a cost of 4 with two 'm' moves, and 4 at a cost of 5 with one.
+/// let successors = |&n: &usize| match n {
+///     0 => vec![(1, 2u32, 'm'), (2, 1, 's')],
+///     1 => vec![(3, 2, 'm')],
+///     2 => vec![(4, 4, 'm')],
+///     _ => vec![],
+/// };
+/// let heuristic = |&n: &usize| [4, 2, 4, 0, 0][n];
+/// let found = focal_search(&0, successors, heuristic, |&n| n >= 3, 1.5, |node| {
+///     node.label_count(&'m')
+/// })
+/// .unwrap();
+/// assert_eq!(found.cost, 5);
+/// assert_eq!(found.path.labels().collect::<String>(), "sm");
+/// assert!(found.cost <= found.bound);
+/// ```
+pub fn focal_search<N, C, L, K, FN, IN, FH, FS, FK>(
+    start: &N,
+    mut successors: FN,
+    mut heuristic: FH,
+    mut success: FS,
+    weight: f64,
+    mut secondary: FK,
+) -> Option<FocalPath<N, C, L>>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    L: PartialEq + Clone,
+    K: Ord,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C, L)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+    FK: FnMut(&FocalNode<N, C, L>) -> K,
+{
+    assert!(
+        weight >= 1.0 && weight.is_finite(),
+        "the weight of a focal search must be at least 1, not {}",
+        weight
+    );
+    let ratio = weight_ratio(weight);
+    let scale = |cost: &C| match ratio {
+        Some((numerator, denominator)) => cost.scale(numerator, denominator),
+        None => cost.clone(),
+    };
+
+    let start_time = Instant::now();
+    let mut stats = SearchStats {
+        max_open_len: 1,
+        ..SearchStats::default()
+    };
+    let mut parents: FxIndexMap<N, (usize, C)> = FxIndexMap::default();
+    let start_index = parents.reset(start);
+    let start_f = heuristic(start);
+    let mut nodes = vec![FocalData {
+        heuristic: start_f.clone(),
+        label: None,
+        move_cost: Zero::zero(),
+        depth: 0,
+        label_counts: vec![],
+        open: Some(start_f.clone()),
+        expanded: false,
+        stamp: 0,
+    }];
+    // The open nodes by estimated cost, and the entries of the focal list by key, estimated
+    // cost and index, with their stamp.
+    let mut open = BTreeSet::from([(start_f, start_index)]);
+    let mut focal = BinaryHeap::new();
+    let mut focal_bound: Option<C> = None;
+    loop {
+        let (f_min, _) = open.first()?;
+        let f_min = f_min.clone();
+        let bound = scale(&f_min);
+        // The open nodes within the raised bound join the focal list. Those beyond a lowered
+        // bound are left out when they are taken from it.
+        let lower = match &focal_bound {
+            Some(focal_bound) => Excluded((focal_bound.clone(), usize::MAX)),
+            None => Unbounded,
+        };
+        if focal_bound
+            .as_ref()
+            .is_none_or(|focal_bound| &bound > focal_bound)
+        {
+            for (f, index) in open.range((lower, Unbounded)) {
+                if f > &bound {
+                    break;
+                }
+                let node = &nodes[*index];
+                let (_, cost) = parents.parent_and_cost(*index);
+                let key = secondary(&FocalNode {
+                    node: parents.node(index),
+                    cost,
+                    depth: node.depth,
+                    label_counts: &node.label_counts,
+                });
+                focal.push(Reverse((key, f.clone(), *index, node.stamp)));
+            }
+        }
+        focal_bound = Some(bound.clone());
+
+        // The node to expand: the first valid entry of the focal list, which holds the node
+        // of the smallest estimated cost at least.
+        let index = loop {
+            let Reverse((_, f, index, stamp)) = focal.pop()?;
+            let node = &nodes[index];
+            if node.stamp == stamp && node.open.as_ref() == Some(&f) && f <= bound {
+                break index;
+            }
+        };
+        let node = &mut nodes[index];
+        let f = node.open.take().unwrap(); // Cannot fail
+        open.remove(&(f, index));
+        node.stamp += 1;
+        let cost = parents.parent_and_cost(index).1.clone();
+        if success(parents.node(&index)) {
+            stats.time = start_time.elapsed();
+            debug!("focal search: found, {}", stats);
+            let (path, cost) = labeled_path_to(&parents, &mut nodes, index);
+            return Some(FocalPath {
+                path,
+                cost,
+                lower_bound: f_min,
+                bound,
+                stats,
+            });
+        }
+
+        if node.expanded {
+            stats.reexpanded += 1;
+        }
+        node.expanded = true;
+        let depth = node.depth + 1;
+        let label_counts = node.label_counts.clone();
+        stats.expanded += 1;
+        stats.max_depth = stats.max_depth.max(depth - 1);
+        for (successor, move_cost, label) in successors(parents.node(&index)) {
+            if move_cost.is_forbidden() {
+                continue;
+            }
+            stats.generated += 1;
+            let mut new_cost = cost.clone();
+            new_cost += move_cost.clone();
+            let successor = match parents.relax(successor, index, new_cost.clone()) {
+                Ok(successor) => successor,
+                Err(_) => {
+                    stats.duplicates_skipped += 1;
+                    continue;
+                }
+            };
+            if successor == nodes.len() {
+                nodes.push(FocalData {
+                    heuristic: heuristic(parents.node(&successor)),
+                    label: None,
+                    move_cost: Zero::zero(),
+                    depth: 0,
+                    label_counts: vec![],
+                    open: None,
+                    expanded: false,
+                    stamp: 0,
+                });
+            }
+            let node = &mut nodes[successor];
+            if let Some(f) = node.open.take() {
+                open.remove(&(f, successor));
+            }
+            let mut counts = label_counts.clone();
+            match counts.iter_mut().find(|(l, _)| l == &label) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((label.clone(), 1)),
+            }
+            node.label = Some(label);
+            node.move_cost = move_cost;
+            node.depth = depth;
+            node.label_counts = counts;
+            node.stamp += 1;
+            let mut f = new_cost.clone();
+            f += node.heuristic.clone();
+            node.open = Some(f.clone());
+            open.insert((f.clone(), successor));
+            if f <= bound {
+                let key = secondary(&FocalNode {
+                    node: parents.node(&successor),
+                    cost: &new_cost,
+                    depth,
+                    label_counts: &node.label_counts,
+                });
+                focal.push(Reverse((key, f, successor, node.stamp)));
+            }
+        }
+        stats.max_open_len = stats.max_open_len.max(open.len());
+    }
+}
+
+/// The path from the start to the node with index `end` in `parents`, with the labels of its
+/// moves, which are taken from `nodes`, and its cost. The cost can be less than the one that
+/// the search recorded for `end`, as a node on the path may have been reached cheaper after
+/// the path was extended beyond it.
+fn labeled_path_to<N, C, L>(
+    parents: &FxIndexMap<N, (usize, C)>,
+    nodes: &mut [FocalData<C, L>],
+    end: usize,
+) -> (LabeledPath<N, L>, C)
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+{
+    let mut steps = vec![];
+    let mut cost: C = Zero::zero();
+    let mut i = end;
+    while let Some(label) = nodes[i].label.take() {
+        steps.push((label, parents.node(&i).clone()));
+        cost += nodes[i].move_cost.clone();
+        i = parents.parent_and_cost(i).0;
+    }
+    steps.reverse();
+    let path = LabeledPath {
+        start: parents.node(&i).clone(),
+        steps,
+    };
+    (path, cost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::{astar, tests::random_graph};
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Move {
+        Model,
+        Log,
+        Sync,
+    }
+
+    type Cell = (u32, u32);
+
+    #[test]
+    fn focal_fewer_model_moves() {
+        // Three rows of 20 cells: moves along the first row are model moves, along the other
+        // rows log moves, and between rows synchronous moves, all of cost 1. The shortest
+        // path from one end of the first row to the other takes 19 model moves; one through
+        // the second row costs 21 and takes none.
+        let successors = |&(x, y): &Cell| {
+            let along = if y == 0 { Move::Model } else { Move::Log };
+            [
+                (x > 0).then(|| ((x - 1, y), along)),
+                (x < 19).then(|| ((x + 1, y), along)),
+                (y > 0).then(|| ((x, y - 1), Move::Sync)),
+                (y < 2).then(|| ((x, y + 1), Move::Sync)),
+            ]
+            .into_iter()
+            .flatten()
+            .map(|(cell, label)| (cell, 1u32, label))
+        };
+        let goal: Cell = (19, 0);
+        let heuristic = |&(x, y): &Cell| goal.0.abs_diff(x) + goal.1.abs_diff(y);
+        let model_moves = |node: &FocalNode<Cell, u32, Move>| node.label_count(&Move::Model);
+
+        let optimal =
+            focal_search(&(0, 0), successors, heuristic, |&c| c == goal, 1.0, |_| 0).unwrap();
+        assert_eq!(optimal.cost, 19);
+        let optimal_model_moves = optimal.path.labels().filter(|&&l| l == Move::Model).count();
+        assert_eq!(optimal_model_moves, 19);
+
+        let found = focal_search(
+            &(0, 0),
+            successors,
+            heuristic,
+            |&c| c == goal,
+            1.2,
+            model_moves,
+        )
+        .unwrap();
+        assert!(found.cost <= found.bound);
+        assert!(found.lower_bound <= 19);
+        assert!(found.cost * 5 <= 19 * 6, "cost {}", found.cost);
+        let model = found.path.labels().filter(|&&l| l == Move::Model).count();
+        assert!(model < optimal_model_moves, "{} model moves", model);
+        assert_eq!(found.path.start, (0, 0));
+        assert_eq!(found.path.steps.len(), found.cost as usize);
+    }
+
+    #[test]
+    fn focal_random_graphs() {
+        // With a weight of 1, the path is a shortest one, whatever the secondary criterion.
+        for seed in 0..200 {
+            let graph = random_graph(seed);
+            let goal = graph.goal;
+            let labeled = |&u: &usize| graph.edges[u].iter().map(|&(v, cost)| (v, cost, v % 2));
+            let expected = astar(&0, |u| graph.successors(u), |_| 0, |&u| u == goal);
+            for weight in [1.0, 1.5] {
+                let found = focal_search(
+                    &0,
+                    labeled,
+                    |_| 0,
+                    |&u| u == goal,
+                    weight,
+                    |node| Reverse(node.label_count(&1)),
+                );
+                match (&found, &expected) {
+                    (Some(found), Some((_, cost))) => {
+                        assert!(found.lower_bound <= *cost);
+                        assert!(found.cost <= found.bound);
+                        assert!(found.cost as f64 <= weight * *cost as f64, "seed {}", seed);
+                        if weight == 1.0 {
+                            assert_eq!(found.cost, *cost);
+                        }
+                        let path = found.path.nodes().copied().collect::<Vec<_>>();
+                        assert_eq!(graph.path_cost(&path), found.cost, "seed {}", seed);
+                    }
+                    (None, None) => {}
+                    _ => panic!("focal search: {:?}, A*: {:?}", found, expected),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! Fringe search: A* without an ordered open list, by rounds of a limit on the estimated
+//! cost like iterative deepening.
+
+use std::{hash::Hash, time::Instant};
+
+use log::debug;
+
+use crate::{
+    astar::{Cost, FxIndexMap, NodeStorage, SearchOutcome, path_to},
+    astar_stats::SearchStats,
+};
+
+/// What fringe search knows of a node, by index in the map of the parents.
+struct FringeNode<C> {
+    heuristic: C,
+    /// The number of moves on the path to the node.
+    depth: usize,
+    expanded: bool,
+    /// The stamp of the only entry of the node on the fringe that is not stale; entries of
+    /// other stamps were superseded by a cheaper path, or the node was expanded since.
+    stamp: usize,
+}
+
+/// Compute a shortest path like [`astar`](crate::astar::astar) with fringe search
+/// (Björnsson et al., 2005), which holds the nodes left to expand in two unordered lists
+/// instead of a priority queue. Each round takes the nodes of the first list in turn: those
+/// whose cost plus `heuristic` exceeds the limit of the round go to the second list, the
+/// others are expanded, and their successors are taken next, depth-first. The next round goes
+/// over the second list with the smallest estimated cost that exceeded the limit. With an
+/// admissible heuristic, the path is a shortest one, and with a consistent one, nodes are
+/// rarely expanded again.
+///
+/// As it has no heap operations, it is faster than [`astar`](crate::astar::astar) when the
+/// estimated costs take few distinct values, as on grids with small integer costs and an
+/// accurate heuristic: there are then few rounds, and nodes are seldom taken from the lists
+/// more than once. With many distinct estimated costs, as with fractional costs, each round
+/// expands few nodes and goes over the deferred ones again, and A* is faster. Also returns
+/// the counters of the search, with the rounds as [`SearchStats::iterations`]; it counts no
+/// [queue operations](SearchStats::queue_operations).
+///
+/// ```
+/// # use ebi_optimisation::{astar::SearchOutcome, astar_fringe::fringe_search};
+/// let successors = |&n: &u32| [(n + 1, 1u32), (n + 3, 2)];
+/// let (outcome, _) = fringe_search(&0, successors, |&n| 9u32.saturating_sub(n) / 3, |&n| n == 9);
+/// assert!(matches!(outcome, SearchOutcome::Found { cost: 6, .. }));
+/// ```
+pub fn fringe_search<N, C, FN, IN, FH, FS>(
+    start: &N,
+    mut successors: FN,
+    mut heuristic: FH,
+    mut success: FS,
+) -> (SearchOutcome<N, C>, SearchStats)
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    let start_time = Instant::now();
+    let mut stats = SearchStats {
+        max_open_len: 1,
+        ..SearchStats::default()
+    };
+    let mut parents: FxIndexMap<N, (usize, C)> = FxIndexMap::default();
+    let start_index = parents.reset(start);
+    let mut nodes = vec![FringeNode {
+        heuristic: heuristic(start),
+        depth: 0,
+        expanded: false,
+        stamp: 0,
+    }];
+    // The entries of the round, taken from the back, and those deferred to the next round.
+    let mut now = vec![(start_index, 0)];
+    let mut later = vec![];
+    let mut f_limit = nodes[start_index].heuristic.clone();
+    let mut children = vec![];
+    loop {
+        stats.iterations += 1;
+        let mut f_min: Option<C> = None;
+        while let Some((index, stamp)) = now.pop() {
+            if nodes[index].stamp != stamp {
+                stats.duplicates_skipped += 1;
+                continue;
+            }
+            let cost = parents.parent_and_cost(index).1.clone();
+            let mut f = cost.clone();
+            f += nodes[index].heuristic.clone();
+            if f > f_limit {
+                if f_min.as_ref().is_none_or(|f_min| &f < f_min) {
+                    f_min = Some(f);
+                }
+                later.push((index, stamp));
+                continue;
+            }
+            if success(parents.node(&index)) {
+                stats.time = start_time.elapsed();
+                debug!("fringe search: found, {}", stats);
+                let path = path_to(&parents, index);
+                return (SearchOutcome::Found { path, cost }, stats);
+            }
+
+            let node = &mut nodes[index];
+            node.stamp += 1;
+            if node.expanded {
+                stats.reexpanded += 1;
+            }
+            node.expanded = true;
+            let depth = node.depth + 1;
+            stats.expanded += 1;
+            stats.max_depth = stats.max_depth.max(node.depth);
+            for (successor, move_cost) in successors(parents.node(&index)) {
+                if move_cost.is_forbidden() {
+                    continue;
+                }
+                stats.generated += 1;
+                let mut new_cost = cost.clone();
+                new_cost += move_cost;
+                match parents.relax(successor, index, new_cost) {
+                    Ok(successor) => {
+                        if successor == nodes.len() {
+                            nodes.push(FringeNode {
+                                heuristic: heuristic(parents.node(&successor)),
+                                depth,
+                                expanded: false,
+                                stamp: 0,
+                            });
+                        } else {
+                            let node = &mut nodes[successor];
+                            node.depth = depth;
+                            node.stamp += 1;
+                        }
+                        children.push((successor, nodes[successor].stamp));
+                    }
+                    Err(_) => stats.duplicates_skipped += 1,
+                }
+            }
+            // The first successor is taken next.
+            now.extend(children.drain(..).rev());
+            stats.max_open_len = stats.max_open_len.max(now.len() + later.len());
+        }
+
+        let Some(f_min) = f_min else {
+            stats.time = start_time.elapsed();
+            debug!("fringe search: no path, {}", stats);
+            return (SearchOutcome::NoPath, stats);
+        };
+        f_limit = f_min;
+        // The deferred entries, in the order they were deferred.
+        later.reverse();
+        std::mem::swap(&mut now, &mut later);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        astar::astar_with_options,
+        astar_options::SearchOptions,
+        rand::{Rng, SeedableRng, rngs::StdRng},
+    };
+
+    type Cell = (u32, u32);
+
+    /// The moves between the free neighbouring cells of a `side` × `side` grid, of cost 1 to
+    /// 3 by cell entered.
+    fn grid(side: u32, rng: &mut StdRng) -> impl Fn(&Cell) -> Vec<(Cell, u32)> + Clone + use<> {
+        let cells = (0..side * side)
+            .map(|_| match rng.random_range(0..10) {
+                0..2 => None,
+                2..8 => Some(1),
+                weight => Some(weight - 6),
+            })
+            .collect::<Vec<_>>();
+        move |&(x, y)| {
+            [
+                (x > 0).then(|| (x - 1, y)),
+                (x + 1 < side).then(|| (x + 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (y + 1 < side).then(|| (x, y + 1)),
+            ]
+            .into_iter()
+            .flatten()
+            .filter_map(|(x, y)| Some(((x, y), cells[(y * side + x) as usize]?)))
+            .collect()
+        }
+    }
+
+    #[test]
+    fn fringe_random_grids() {
+        let mut rng = StdRng::seed_from_u64(714);
+        let mut found = 0;
+        for _ in 0..200 {
+            let side = rng.random_range(2..30);
+            let successors = grid(side, &mut rng);
+            let start = (rng.random_range(0..side), rng.random_range(0..side));
+            let goal = (rng.random_range(0..side), rng.random_range(0..side));
+            let heuristic = |&(x, y): &Cell| goal.0.abs_diff(x) + goal.1.abs_diff(y);
+
+            let (outcome, stats) =
+                fringe_search(&start, successors.clone(), heuristic, |&cell| cell == goal);
+            let (expected, astar_stats) = astar_with_options(
+                &start,
+                successors.clone(),
+                heuristic,
+                |&cell| cell == goal,
+                &SearchOptions::new(),
+            );
+            match (&outcome, &expected) {
+                (
+                    SearchOutcome::Found { path, cost },
+                    SearchOutcome::Found {
+                        cost: expected_cost,
+                        ..
+                    },
+                ) => {
+                    found += 1;
+                    assert_eq!(cost, expected_cost);
+                    assert_eq!(path[0], start);
+                    assert_eq!(path[path.len() - 1], goal);
+                    let path_cost = path
+                        .windows(2)
+                        .map(|step| {
+                            let moves = successors(&step[0]);
+                            moves.iter().find(|(cell, _)| *cell == step[1]).unwrap().1
+                        })
+                        .sum::<u32>();
+                    assert_eq!(path_cost, *cost);
+                }
+                (SearchOutcome::NoPath, SearchOutcome::NoPath) => {}
+                _ => panic!("fringe search: {:?}, A*: {:?}", outcome, expected),
+            }
+            assert_eq!(stats.queue_operations, 0);
+            assert!(astar_stats.queue_operations >= astar_stats.expanded);
+        }
+        assert!(found > 100, "{} paths", found);
+    }
+
+    #[test]
+    fn fringe_open_grid() {
+        // Without obstacles and with unit costs, the Manhattan distance is exact: one round
+        // expands the nodes of a path and no other.
+        let side = 100;
+        let successors = move |&(x, y): &Cell| {
+            [
+                (x > 0).then(|| (x - 1, y)),
+                (x + 1 < side).then(|| (x + 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (y + 1 < side).then(|| (x, y + 1)),
+            ]
+            .into_iter()
+            .flatten()
+            .map(|cell| (cell, 1u32))
+        };
+        let goal = (side - 1, side - 1);
+        let heuristic = |&(x, y): &Cell| goal.0.abs_diff(x) + goal.1.abs_diff(y);
+        let (outcome, stats) = fringe_search(&(0, 0), successors, heuristic, |&c| c == goal);
+        assert_eq!(outcome.found().unwrap().1, 198);
+        assert_eq!(stats.iterations, 1);
+        assert_eq!(stats.expanded, 198);
+        assert_eq!(stats.reexpanded, 0);
+    }
+}
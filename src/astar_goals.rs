@@ -0,0 +1,161 @@
+//! A* searches towards a set of goals: the nearest of them, or each of them.
+
+use std::{
+    collections::{BinaryHeap, HashSet},
+    hash::Hash,
+};
+
+use indexmap::IndexMap;
+
+use crate::{
+    astar::{Cost, DefaultSearchHasher, FxIndexMap, NodeStorage, SearchCore, path_to},
+    astar_options::SearchOptions,
+};
+
+/// The goals of [`astar_goals`].
+pub enum GoalMode<'a, N> {
+    /// The nearest node for which the predicate holds, as in [`astar`](crate::astar::astar).
+    Matching(&'a mut dyn FnMut(&N) -> bool),
+    /// The nearest node of the set.
+    AnyOf(&'a HashSet<N>),
+    /// Each node of the slice.
+    AllOf(&'a [N]),
+}
+
+/// The result of [`astar_goals`].
+#[derive(Clone, Debug)]
+pub enum GoalOutcome<N, C> {
+    /// For [`GoalMode::Matching`] and [`GoalMode::AnyOf`]: a shortest path to the nearest
+    /// goal, which is its last node, and its cost, or `None` if no goal can be reached.
+    Nearest(Option<(Vec<N>, C)>),
+    /// For [`GoalMode::AllOf`]: per goal, in the order given, its cost and a shortest path to
+    /// it, or `None` if it cannot be reached.
+    All(IndexMap<N, Option<(C, Vec<N>)>, DefaultSearchHasher>),
+}
+
+impl<N: Eq + Hash, C: PartialEq> PartialEq for GoalOutcome<N, C> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (GoalOutcome::Nearest(a), GoalOutcome::Nearest(b)) => a == b,
+            (GoalOutcome::All(a), GoalOutcome::All(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<N: Eq + Hash, C: Eq> Eq for GoalOutcome<N, C> {}
+
+/// Search shortest paths from `start` to `goals`, with the other arguments of
+/// [`astar`](crate::astar::astar).
+///
+/// For [`GoalMode::AllOf`], the search goes on until every goal is taken from the open list,
+/// or until there is nothing left to expand, so it explores the whole graph if a goal cannot be
+/// reached. The heuristic must then not exceed the cost to any of the goals, such as the
+/// smallest estimate over the goals, or zero.
+///
+/// ```
+/// # use ebi_optimisation::astar_goals::{GoalMode, GoalOutcome, astar_goals};
+/// let successors = |&n: &u32| [(n + 1, 1u32), (n + 2, 3)];
+/// let outcome = astar_goals(&0, successors, |_| 0, GoalMode::AllOf(&[2, 5]));
+/// let GoalOutcome::All(goals) = outcome else { unreachable!() };
+/// assert_eq!(goals[&2], Some((2, vec![0, 1, 2])));
+/// assert_eq!(goals[&5].as_ref().unwrap().0, 5);
+/// ```
+pub fn astar_goals<N, C, FN, IN, FH>(
+    start: &N,
+    mut successors: FN,
+    mut heuristic: FH,
+    goals: GoalMode<N>,
+) -> GoalOutcome<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+{
+    let parents: FxIndexMap<N, (usize, C)> = FxIndexMap::default();
+    let mut search = SearchCore::with_options(parents, BinaryHeap::new(), &SearchOptions::new());
+    search.reset(start);
+    match goals {
+        GoalMode::Matching(mut success) => GoalOutcome::Nearest(
+            search
+                .run(&mut successors, &mut heuristic, &mut success, None)
+                .found(),
+        ),
+        GoalMode::AnyOf(goals) => GoalOutcome::Nearest(
+            search
+                .run(
+                    &mut successors,
+                    &mut heuristic,
+                    &mut |node: &N| goals.contains(node),
+                    None,
+                )
+                .found(),
+        ),
+        GoalMode::AllOf(goals) => {
+            if !goals.is_empty() {
+                // The goals not taken from the open list yet. A goal is taken first with the
+                // cost of a shortest path to it, and is expanded for the others.
+                let mut left: HashSet<&N, DefaultSearchHasher> = goals.iter().collect();
+                let mut success = |node: &N| left.remove(node) && left.is_empty();
+                search.run(&mut successors, &mut heuristic, &mut success, None);
+            }
+            let all = (goals.iter())
+                .map(|goal| {
+                    let found = search.parents.get_index_of(goal).map(|index| {
+                        let cost = search.parents.parent_and_cost(index).1.clone();
+                        (cost, path_to(&search.parents, index))
+                    });
+                    (goal.clone(), found)
+                })
+                .collect();
+            GoalOutcome::All(all)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chain 0 -> 1 -> ... -> 10 with a shortcut 0 -> 6, all of cost 1.
+    fn successors(&n: &u32) -> Vec<(u32, u32)> {
+        let mut succs = vec![];
+        if n < 10 {
+            succs.push((n + 1, 1));
+        }
+        if n == 0 {
+            succs.push((6, 1));
+        }
+        succs
+    }
+
+    #[test]
+    fn goals_nearest() {
+        let goals = HashSet::from([4, 7, 12]);
+        let outcome = astar_goals(&0, successors, |_| 0, GoalMode::AnyOf(&goals));
+        assert_eq!(outcome, GoalOutcome::Nearest(Some((vec![0, 6, 7], 2))));
+
+        let mut success = |&n: &u32| n > 0 && n % 4 == 0;
+        let outcome = astar_goals(&0, successors, |_| 0, GoalMode::Matching(&mut success));
+        assert_eq!(outcome, GoalOutcome::Nearest(Some((vec![0, 6, 7, 8], 3))));
+
+        let goals = HashSet::from([11, 12]);
+        let outcome = astar_goals(&0, successors, |_| 0, GoalMode::AnyOf(&goals));
+        assert_eq!(outcome, GoalOutcome::Nearest(None));
+    }
+
+    #[test]
+    fn goals_all() {
+        let outcome = astar_goals(&0, successors, |_| 0, GoalMode::AllOf(&[4, 7, 12, 9]));
+        let GoalOutcome::All(goals) = outcome else {
+            panic!("expected all goals, got {:?}", outcome);
+        };
+        assert_eq!(goals.keys().copied().collect::<Vec<_>>(), vec![4, 7, 12, 9]);
+        assert_eq!(goals[&4], Some((4, vec![0, 1, 2, 3, 4])));
+        assert_eq!(goals[&7], Some((2, vec![0, 6, 7])));
+        assert_eq!(goals[&12], None);
+        assert_eq!(goals[&9], Some((4, vec![0, 6, 7, 8, 9])));
+    }
+}
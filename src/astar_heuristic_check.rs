@@ -0,0 +1,214 @@
+//! A check of the heuristic of an A* search on the moves the search generates.
+
+use std::{cell::RefCell, fmt::Debug};
+
+use crate::astar::Cost;
+
+/// A violation of the requirements on a heuristic, found by [`HeuristicCheck`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HeuristicViolation<N, C> {
+    /// The estimate of `from` exceeds the cost of the move to `to` plus the estimate of `to`.
+    Inconsistent {
+        from: N,
+        to: N,
+        move_cost: C,
+        h_from: C,
+        h_to: C,
+    },
+    /// The estimate of a goal is not zero.
+    NonZeroAtGoal { goal: N, h: C },
+}
+
+/// Checks a heuristic during a search: wrap the successors and the success function of the
+/// search with [`HeuristicCheck::successors`] and [`HeuristicCheck::success`], and pass
+/// [`HeuristicCheck::heuristic`] as its heuristic. Then the heuristic must be consistent on
+/// every move that the search generates: the estimate of a node is at most the cost of the
+/// move plus the estimate of the successor; and the estimate of a goal must be zero. Together,
+/// they make the heuristic admissible on the paths the search explored.
+///
+/// With [`HeuristicCheck::new`], a violation panics in debug builds and is recorded in
+/// release builds; [`HeuristicCheck::recording`] always records them. With exact costs, such
+/// as [`Fraction`](ebi_arithmetic::Fraction), the check has no rounding margin.
+///
+/// The check evaluates the heuristic once more per expanded node and once for every generated
+/// move, and collects the successors of each node in a vector.
+///
+/// ```
+/// # use ebi_optimisation::{astar::astar, astar_heuristic_check::HeuristicCheck};
+/// let check = HeuristicCheck::new(|&n: &u32| 10 - n);
+/// let result = astar(
+///     &0,
+///     check.successors(|&n: &u32| [(n + 1, 1u32)]),
+///     check.heuristic(),
+///     check.success(|&n| n == 10),
+/// );
+/// assert_eq!(result.unwrap().1, 10);
+/// assert!(check.violations().is_empty());
+/// ```
+pub struct HeuristicCheck<N, C, FH> {
+    heuristic: RefCell<FH>,
+    panic: bool,
+    violations: RefCell<Vec<HeuristicViolation<N, C>>>,
+}
+
+impl<N, C, FH> HeuristicCheck<N, C, FH>
+where
+    N: Clone + Debug,
+    C: Cost,
+    FH: FnMut(&N) -> C,
+{
+    /// Check `heuristic`, panicking on a violation in debug builds.
+    pub fn new(heuristic: FH) -> Self {
+        HeuristicCheck {
+            heuristic: RefCell::new(heuristic),
+            panic: cfg!(debug_assertions),
+            violations: RefCell::new(vec![]),
+        }
+    }
+
+    /// Check `heuristic`, only recording the violations.
+    pub fn recording(heuristic: FH) -> Self {
+        HeuristicCheck {
+            panic: false,
+            ..Self::new(heuristic)
+        }
+    }
+
+    /// The checked heuristic, for the search.
+    pub fn heuristic(&self) -> impl FnMut(&N) -> C {
+        |node: &N| self.estimate(node)
+    }
+
+    /// `successors`, checking the heuristic on every move.
+    pub fn successors<FN, IN>(&self, mut successors: FN) -> impl FnMut(&N) -> Vec<(N, C)>
+    where
+        FN: FnMut(&N) -> IN,
+        IN: IntoIterator<Item = (N, C)>,
+    {
+        move |node: &N| {
+            let h_from = self.estimate(node);
+            let moves = successors(node).into_iter().collect::<Vec<_>>();
+            for (successor, move_cost) in &moves {
+                if move_cost.is_forbidden() {
+                    continue;
+                }
+                let h_to = self.estimate(successor);
+                let mut bound = move_cost.clone();
+                bound += h_to.clone();
+                if h_from > bound {
+                    self.violation(HeuristicViolation::Inconsistent {
+                        from: node.clone(),
+                        to: successor.clone(),
+                        move_cost: move_cost.clone(),
+                        h_from: h_from.clone(),
+                        h_to,
+                    });
+                }
+            }
+            moves
+        }
+    }
+
+    /// `success`, checking that the heuristic is zero at the goals.
+    pub fn success<FS>(&self, mut success: FS) -> impl FnMut(&N) -> bool
+    where
+        FS: FnMut(&N) -> bool,
+    {
+        move |node: &N| {
+            let is_goal = success(node);
+            if is_goal {
+                let h = self.estimate(node);
+                if h != C::zero() {
+                    self.violation(HeuristicViolation::NonZeroAtGoal {
+                        goal: node.clone(),
+                        h,
+                    });
+                }
+            }
+            is_goal
+        }
+    }
+
+    /// The violations recorded so far.
+    pub fn violations(&self) -> Vec<HeuristicViolation<N, C>> {
+        self.violations.borrow().clone()
+    }
+
+    fn estimate(&self, node: &N) -> C {
+        (self.heuristic.borrow_mut())(node)
+    }
+
+    fn violation(&self, violation: HeuristicViolation<N, C>) {
+        if self.panic {
+            panic!("heuristic violation: {:?}", violation);
+        }
+        self.violations.borrow_mut().push(violation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::astar;
+
+    /// A chain 0 -> 1 -> 2 -> 3 of unit moves, with a heuristic that overestimates at 1.
+    fn chain(n: &u32) -> Vec<(u32, u32)> {
+        if *n < 3 { vec![(n + 1, 1)] } else { vec![] }
+    }
+
+    fn overestimate(n: &u32) -> u32 {
+        match n {
+            1 => 5,
+            _ => 3 - n,
+        }
+    }
+
+    #[test]
+    fn heuristic_check_records() {
+        let check = HeuristicCheck::recording(overestimate);
+        let (_, cost) = astar(
+            &0,
+            check.successors(chain),
+            check.heuristic(),
+            check.success(|&n| n == 3),
+        )
+        .unwrap();
+        assert_eq!(cost, 3);
+        assert_eq!(
+            check.violations(),
+            vec![HeuristicViolation::Inconsistent {
+                from: 1,
+                to: 2,
+                move_cost: 1,
+                h_from: 5,
+                h_to: 1
+            }]
+        );
+
+        let check = HeuristicCheck::recording(|&n: &u32| 4 - n);
+        astar(
+            &0,
+            check.successors(chain),
+            check.heuristic(),
+            check.success(|&n| n == 3),
+        )
+        .unwrap();
+        assert_eq!(
+            check.violations(),
+            vec![HeuristicViolation::NonZeroAtGoal { goal: 3, h: 1 }]
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Inconsistent { from: 1, to: 2")]
+    fn heuristic_check_panics() {
+        let check = HeuristicCheck::new(overestimate);
+        astar(
+            &0,
+            check.successors(chain),
+            check.heuristic(),
+            check.success(|&n| n == 3),
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! A* over nodes that are the integers below a known bound, with vectors instead of a hash
+//! map for the bookkeeping.
+
+use std::collections::BinaryHeap;
+
+use ebi_arithmetic::ebi_number::Zero;
+
+use crate::{
+    astar::{Cost, NodeStorage, OpenList, SearchCore, SmallestCostHolder},
+    astar_options::SearchOptions,
+};
+
+/// Compute a shortest path like [`astar`](crate::astar::astar), for nodes that are the
+/// integers below `n_states`, such as the states of an automaton. The parent and the cost of
+/// the nodes are kept in vectors of `n_states` entries, so the nodes are never hashed.
+///
+/// If the costs are integers (see [`Cost::bucket`]), the open list is a bucket queue: a vector
+/// of the entries per estimated cost, which is faster than a binary heap for small costs.
+/// Among the entries of the same estimated cost, the last one added comes first.
+///
+/// # Panics
+///
+/// If `start` or a successor is not less than `n_states`.
+pub fn astar_indexed<C, FN, IN, FH, FS>(
+    n_states: usize,
+    start: usize,
+    mut successors: FN,
+    mut heuristic: FH,
+    mut success: FS,
+) -> Option<(Vec<usize>, C)>
+where
+    C: Cost,
+    FN: FnMut(&usize) -> IN,
+    IN: IntoIterator<Item = (usize, C)>,
+    FH: FnMut(&usize) -> C,
+    FS: FnMut(&usize) -> bool,
+{
+    let storage = IndexedStorage {
+        costs: vec![None; n_states],
+        parents: vec![usize::MAX; n_states],
+    };
+    if C::zero().bucket().is_some() {
+        let mut search =
+            SearchCore::with_options(storage, BucketQueue::default(), &SearchOptions::new());
+        search.reset(&start);
+        (search.run(&mut successors, &mut heuristic, &mut success, None)).found()
+    } else {
+        let mut search =
+            SearchCore::with_options(storage, BinaryHeap::new(), &SearchOptions::new());
+        search.reset(&start);
+        (search.run(&mut successors, &mut heuristic, &mut success, None)).found()
+    }
+}
+
+/// The costs of the shortest paths from `start` to every node below `n_states`, if any:
+/// Dijkstra's algorithm, as a search without heuristic and without goal.
+pub(crate) fn distances_from<C, FN, IN>(
+    n_states: usize,
+    start: usize,
+    mut successors: FN,
+) -> Vec<Option<C>>
+where
+    C: Cost,
+    FN: FnMut(&usize) -> IN,
+    IN: IntoIterator<Item = (usize, C)>,
+{
+    let storage = IndexedStorage {
+        costs: vec![None; n_states],
+        parents: vec![usize::MAX; n_states],
+    };
+    let mut heuristic = |_: &usize| C::zero();
+    let mut success = |_: &usize| false;
+    if C::zero().bucket().is_some() {
+        let mut search =
+            SearchCore::with_options(storage, BucketQueue::default(), &SearchOptions::new());
+        search.reset(&start);
+        search.run(&mut successors, &mut heuristic, &mut success, None);
+        search.parents.costs
+    } else {
+        let mut search =
+            SearchCore::with_options(storage, BinaryHeap::new(), &SearchOptions::new());
+        search.reset(&start);
+        search.run(&mut successors, &mut heuristic, &mut success, None);
+        search.parents.costs
+    }
+}
+
+/// The bookkeeping of [`astar_indexed`]: the index of a node is the node.
+pub(crate) struct IndexedStorage<C> {
+    costs: Vec<Option<C>>,
+    parents: Vec<usize>,
+}
+
+impl<C: Cost> NodeStorage<C> for IndexedStorage<C> {
+    type Node = usize;
+
+    fn clear(&mut self) {
+        self.costs.fill(None);
+    }
+
+    fn reset(&mut self, start: &usize) -> usize {
+        self.costs.fill(None);
+        self.costs[*start] = Some(Zero::zero());
+        self.parents[*start] = usize::MAX;
+        *start
+    }
+
+    fn index_of(&self, node: &usize) -> Option<usize> {
+        self.costs[*node].is_some().then_some(*node)
+    }
+
+    fn node<'a>(&'a self, index: &'a usize) -> &'a usize {
+        index
+    }
+
+    fn parent_and_cost(&self, index: usize) -> (usize, &C) {
+        (self.parents[index], self.costs[index].as_ref().unwrap()) // Cannot fail
+    }
+
+    fn relax(&mut self, node: usize, parent: usize, cost: C) -> Result<usize, usize> {
+        if self.costs[node]
+            .as_ref()
+            .is_some_and(|known| known <= &cost)
+        {
+            return Err(node);
+        }
+        self.costs[node] = Some(cost);
+        self.parents[node] = parent;
+        Ok(node)
+    }
+}
+
+/// An open list of a vector of entries per estimated cost, for integer costs. The entries of
+/// estimated costs of [`BucketQueue::MAX_BUCKETS`] or more are kept in a binary heap.
+pub(crate) struct BucketQueue<C> {
+    buckets: Vec<Vec<SmallestCostHolder<C>>>,
+    /// No bucket before this one has entries.
+    first: usize,
+    overflow: BinaryHeap<SmallestCostHolder<C>>,
+    len: usize,
+}
+
+impl<C> BucketQueue<C> {
+    const MAX_BUCKETS: usize = 1 << 20;
+}
+
+impl<C> Default for BucketQueue<C> {
+    fn default() -> Self {
+        BucketQueue {
+            buckets: vec![],
+            first: 0,
+            overflow: BinaryHeap::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<C: Cost> OpenList<C> for BucketQueue<C> {
+    fn push(&mut self, holder: SmallestCostHolder<C>) {
+        self.len += 1;
+        match holder.estimated_cost.bucket() {
+            Some(bucket) if bucket < Self::MAX_BUCKETS => {
+                if bucket >= self.buckets.len() {
+                    self.buckets.resize_with(bucket + 1, Vec::new);
+                }
+                self.buckets[bucket].push(holder);
+                self.first = self.first.min(bucket);
+            }
+            _ => self.overflow.push(holder),
+        }
+    }
+
+    fn pop(&mut self) -> Option<SmallestCostHolder<C>> {
+        while self.first < self.buckets.len() {
+            if let Some(holder) = self.buckets[self.first].pop() {
+                self.len -= 1;
+                return Some(holder);
+            }
+            self.first += 1;
+        }
+        let holder = self.overflow.pop()?;
+        self.len -= 1;
+        Some(holder)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            bucket.clear();
+        }
+        self.first = self.buckets.len();
+        self.overflow.clear();
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::{astar, tests::random_graph};
+    use ebi_arithmetic::Fraction;
+
+    #[test]
+    fn indexed_random_graphs() {
+        for seed in 0..20 {
+            let graph = random_graph(seed);
+            let (n, goal) = (graph.edges.len(), graph.goal);
+            let successors = |v: &usize| graph.successors(v);
+
+            let hashed = astar(&0, successors, |_| 0, |&v| v == goal);
+            let indexed = astar_indexed(n, 0, successors, |_| 0, |&v| v == goal);
+            assert_eq!(
+                hashed.as_ref().map(|(_, cost)| cost),
+                indexed.as_ref().map(|(_, cost)| cost)
+            );
+            if let Some((path, cost)) = indexed {
+                assert_eq!(path.first(), Some(&0));
+                assert_eq!(path.last(), Some(&goal));
+                assert_eq!(graph.path_cost(&path), cost);
+            }
+
+            // Costs without buckets.
+            let fraction_edges = |v: &usize| {
+                (graph.edges[*v].iter())
+                    .map(|&(next, cost)| (next, Fraction::from((cost as usize, 2))))
+                    .collect::<Vec<_>>()
+            };
+            let indexed = astar_indexed(n, 0, fraction_edges, |_| Fraction::zero(), |&v| v == goal);
+            assert_eq!(
+                hashed.map(|(_, cost)| Fraction::from((cost as usize, 2))),
+                indexed.map(|(_, cost)| cost)
+            );
+        }
+    }
+
+    #[test]
+    fn indexed_large_grid() {
+        // A 1000 × 1000 grid, by the index of the cells.
+        let side = 1000;
+        let successors = move |&v: &usize| {
+            let (x, y) = (v % side, v / side);
+            [
+                (x > 0).then(|| (v - 1, 1u32)),
+                (x + 1 < side).then(|| (v + 1, 1)),
+                (y > 0).then(|| (v - side, 1)),
+                (y + 1 < side).then(|| (v + side, 1)),
+            ]
+            .into_iter()
+            .flatten()
+        };
+        let goal = side * side - 1;
+        let heuristic = |&v: &usize| ((side - 1 - v % side) + (side - 1 - v / side)) as u32 / 2;
+
+        let (_, hashed) = astar(&0, successors, heuristic, |&v| v == goal).unwrap();
+        let (_, indexed) =
+            astar_indexed(side * side, 0, successors, heuristic, |&v| v == goal).unwrap();
+
+        assert_eq!(hashed, 2 * (side as u32 - 1));
+        assert_eq!(indexed, hashed);
+    }
+}
@@ -0,0 +1,222 @@
+//! Iterative-deepening A* (IDA*): A* in memory proportional to the length of the path.
+
+use std::{hash::Hash, time::Instant};
+
+use ebi_arithmetic::ebi_number::Zero;
+use log::debug;
+
+use crate::{astar::Cost, astar_stats::SearchStats};
+
+/// Compute a shortest path like [`astar`](crate::astar::astar), with depth-first searches
+/// that only hold the path to the current node. Each search skips the nodes whose cost plus
+/// heuristic exceeds a bound, which starts at the heuristic of `start` and is raised to the
+/// smallest such estimate that exceeded it in the last search, until a search reaches a node
+/// for which `success` holds.
+///
+/// The memory does not grow with the number of nodes reached, but the nodes are expanded
+/// again in every search, and also once for each path to them, as only the nodes on the
+/// current path are known: a path never visits a node twice. This pays off on trees and on
+/// graphs with few cycles when the open list of [`astar`](crate::astar::astar) does not fit in
+/// memory. The heuristic must be admissible for the path to be a shortest one.
+///
+/// Returns the path, comprising both the start and end node, and its cost, or `None` if there
+/// is no path; and the counters of the searches, with the number of searches in
+/// [`SearchStats::iterations`].
+pub fn idastar<N, C, FN, IN, FH, FS>(
+    start: &N,
+    mut successors: FN,
+    mut heuristic: FH,
+    mut success: FS,
+) -> (Option<(Vec<N>, C)>, SearchStats)
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    let start_time = Instant::now();
+    let mut stats = SearchStats {
+        max_open_len: 1,
+        ..SearchStats::default()
+    };
+    let mut path = vec![start.clone()];
+    let mut bound = heuristic(start);
+    let res = loop {
+        stats.iterations += 1;
+        debug!("IDA*: search {} with bound {:?}", stats.iterations, bound);
+        match probe(
+            &mut path,
+            &bound,
+            &mut successors,
+            &mut heuristic,
+            &mut success,
+            &mut stats,
+        ) {
+            Ok(cost) => break Some((path, cost)),
+            Err(Some(next_bound)) => bound = next_bound,
+            Err(None) => break None,
+        }
+    };
+    stats.time = start_time.elapsed();
+    debug!("IDA*: done, {}", stats);
+    (res, stats)
+}
+
+/// A depth-first search from the single node of `path` that skips the nodes whose estimated
+/// cost exceeds `bound`. Returns the cost of the path to a node for which `success` holds,
+/// which is then at the end of `path`, or else the smallest estimated cost that exceeded
+/// `bound`, if any.
+fn probe<N, C, FN, IN, FH, FS>(
+    path: &mut Vec<N>,
+    bound: &C,
+    successors: &mut FN,
+    heuristic: &mut FH,
+    success: &mut FS,
+    stats: &mut SearchStats,
+) -> Result<C, Option<C>>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    if success(&path[0]) {
+        return Ok(Zero::zero());
+    }
+    // The costs of the paths to the nodes of `path`, and the successors left to visit of each.
+    let mut costs: Vec<C> = vec![Zero::zero()];
+    let mut to_visit = vec![successors(&path[0]).into_iter()];
+    stats.expanded += 1;
+    let mut next_bound: Option<C> = None;
+    while let Some(node_successors) = to_visit.last_mut() {
+        let Some((successor, mut cost)) = node_successors.next() else {
+            to_visit.pop();
+            if !to_visit.is_empty() {
+                path.pop();
+                costs.pop();
+            }
+            continue;
+        };
+        if cost.is_forbidden() {
+            continue;
+        }
+        stats.generated += 1;
+        if path.contains(&successor) {
+            stats.duplicates_skipped += 1;
+            continue;
+        }
+        cost += costs.last().unwrap().clone(); // Cannot fail
+        let mut estimated_cost = cost.clone();
+        estimated_cost += heuristic(&successor);
+        if &estimated_cost > bound {
+            if next_bound
+                .as_ref()
+                .is_none_or(|next| &estimated_cost < next)
+            {
+                next_bound = Some(estimated_cost);
+            }
+            continue;
+        }
+        if success(&successor) {
+            path.push(successor);
+            return Ok(cost);
+        }
+
+        stats.expanded += 1;
+        to_visit.push(successors(&successor).into_iter());
+        path.push(successor);
+        costs.push(cost);
+        stats.max_open_len = stats.max_open_len.max(path.len());
+        stats.max_depth = stats.max_depth.max(path.len() - 1);
+    }
+    Err(next_bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar::astar_with_options, astar_options::SearchOptions};
+
+    const SOLVED: [u8; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 0];
+
+    /// The states after sliding a tile of the 3 × 3 puzzle into the gap, 0.
+    fn slides(state: &[u8; 9]) -> Vec<([u8; 9], u32)> {
+        let gap = state.iter().position(|&tile| tile == 0).unwrap();
+        let (x, y) = (gap % 3, gap / 3);
+        [
+            (x > 0).then(|| gap - 1),
+            (x < 2).then(|| gap + 1),
+            (y > 0).then(|| gap - 3),
+            (y < 2).then(|| gap + 3),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|tile| {
+            let mut next = *state;
+            next.swap(gap, tile);
+            (next, 1)
+        })
+        .collect()
+    }
+
+    /// The sum of the Manhattan distances of the tiles to their places.
+    fn manhattan(state: &[u8; 9]) -> u32 {
+        (state.iter().enumerate())
+            .filter(|&(_, &tile)| tile != 0)
+            .map(|(i, &tile)| {
+                let place = tile as usize - 1;
+                ((i % 3).abs_diff(place % 3) + (i / 3).abs_diff(place / 3)) as u32
+            })
+            .sum()
+    }
+
+    #[test]
+    fn idastar_puzzle() {
+        let start = [0, 2, 3, 1, 8, 7, 6, 5, 4];
+        let (res, _) = astar_with_options(
+            &start,
+            slides,
+            manhattan,
+            |state| *state == SOLVED,
+            &SearchOptions::new(),
+        );
+        let (_, astar_cost) = res.found().unwrap();
+        let (res, stats) = idastar(&start, slides, manhattan, |state| *state == SOLVED);
+        let (path, cost) = res.unwrap();
+
+        assert_eq!(cost, 16);
+        assert_eq!(cost, astar_cost);
+        assert_eq!(path.len(), 17);
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&SOLVED));
+        for step in path.windows(2) {
+            assert!(slides(&step[0]).iter().any(|(next, _)| *next == step[1]));
+        }
+        assert!(stats.iterations > 1);
+        // Only the path is held.
+        assert!(stats.max_open_len <= path.len());
+    }
+
+    #[test]
+    fn idastar_no_path() {
+        // A line of 6 nodes, both ways, without the goal.
+        let (res, stats) = idastar(
+            &0u32,
+            |&n| {
+                [n.checked_sub(1), (n < 5).then_some(n + 1)]
+                    .map(|next| next.map(|next| (next, 1u32)))
+                    .into_iter()
+                    .flatten()
+            },
+            |_| 0,
+            |&n| n == 10,
+        );
+        assert!(res.is_none());
+        assert_eq!(stats.iterations, 6);
+        assert_eq!(stats.max_depth, 5);
+    }
+}
@@ -0,0 +1,214 @@
+//! The k shortest paths to a goal, found by an A* search that expands every node up to k
+//! times.
+
+use std::{collections::BinaryHeap, hash::Hash};
+
+use ebi_arithmetic::ebi_number::Zero;
+
+use crate::{
+    astar::{Cost, FxIndexMap, SmallestCostHolder},
+    astar_options::TieBreak,
+};
+
+/// Search the `k` shortest paths from `start` to nodes for which `success` holds, with the
+/// arguments of [`astar`](crate::astar::astar). The paths are returned one at a time, with
+/// their costs, in nondecreasing order of cost; the search goes on only as far as needed for
+/// the next path, so the paths that are not consumed cost nothing.
+///
+/// Rather than the best path to every node, the search keeps every path it takes from the
+/// open list, and takes up to `k` paths to each node: the `i`th path taken to a node is its
+/// `i`th shortest one if the heuristic is consistent. Paths end at their first node for which
+/// `success` holds, but may visit other nodes several times. No path is returned twice: if
+/// `successors` returns a node several times, only its cheapest move is taken.
+pub fn astar_k<N, C, FN, IN, FH, FS>(
+    start: &N,
+    successors: FN,
+    heuristic: FH,
+    success: FS,
+    k: usize,
+) -> KShortestPaths<N, C, FN, FH, FS>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    let mut nodes = FxIndexMap::default();
+    nodes.insert(start.clone(), 0);
+    KShortestPaths {
+        successors,
+        heuristic,
+        success,
+        k,
+        found: 0,
+        to_see: BinaryHeap::from([SmallestCostHolder {
+            estimated_cost: Zero::zero(),
+            cost: Zero::zero(),
+            index: 0,
+            depth: 0,
+            seq: 0,
+            tie_break: TieBreak::default(),
+        }]),
+        nodes,
+        paths: vec![(0, usize::MAX)],
+    }
+}
+
+/// The iterator over the shortest paths returned by [`astar_k`].
+pub struct KShortestPaths<N, C, FN, FH, FS> {
+    successors: FN,
+    heuristic: FH,
+    success: FS,
+    k: usize,
+    /// The number of paths returned so far.
+    found: usize,
+    /// The open list, of indices in `paths`.
+    to_see: BinaryHeap<SmallestCostHolder<C>>,
+    /// Per node seen, the number of paths to it taken from the open list.
+    nodes: FxIndexMap<N, usize>,
+    /// The tree of the paths in the open list or taken from it: per path, the index of its
+    /// last node in `nodes` and the index of the path without that node.
+    paths: Vec<(usize, usize)>,
+}
+
+impl<N, C, FN, IN, FH, FS> Iterator for KShortestPaths<N, C, FN, FH, FS>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    type Item = (Vec<N>, C);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.found >= self.k {
+            return None;
+        }
+        while let Some(SmallestCostHolder {
+            cost, index, depth, ..
+        }) = self.to_see.pop()
+        {
+            let node_index = self.paths[index].0;
+            let (node, taken) = self.nodes.get_index_mut(node_index).unwrap(); // Cannot fail
+            if *taken >= self.k {
+                continue;
+            }
+            *taken += 1;
+            if (self.success)(node) {
+                self.found += 1;
+                return Some((self.path(index), cost));
+            }
+
+            // Keep the cheapest move to each successor.
+            let mut moves: Vec<(N, C)> = vec![];
+            for (successor, move_cost) in (self.successors)(node) {
+                if move_cost.is_forbidden() {
+                    continue;
+                }
+                match moves.iter_mut().find(|(other, _)| *other == successor) {
+                    Some((_, other_cost)) => {
+                        if move_cost < *other_cost {
+                            *other_cost = move_cost;
+                        }
+                    }
+                    None => moves.push((successor, move_cost)),
+                }
+            }
+            for (successor, mut new_cost) in moves {
+                new_cost += cost.clone();
+                let mut estimated_cost = new_cost.clone();
+                estimated_cost += (self.heuristic)(&successor);
+                let entry = self.nodes.entry(successor);
+                let successor_index = entry.index();
+                entry.or_insert(0);
+                self.paths.push((successor_index, index));
+                self.to_see.push(SmallestCostHolder {
+                    estimated_cost,
+                    cost: new_cost,
+                    index: self.paths.len() - 1,
+                    depth: depth + 1,
+                    seq: self.paths.len() - 1,
+                    tie_break: TieBreak::default(),
+                });
+            }
+        }
+        None
+    }
+}
+
+impl<N, C, FN, FH, FS> KShortestPaths<N, C, FN, FH, FS>
+where
+    N: Clone,
+{
+    /// The nodes of the path with index `end` in `paths`.
+    fn path(&self, end: usize) -> Vec<N> {
+        let mut path = vec![];
+        let mut i = end;
+        while i != usize::MAX {
+            let (node_index, parent) = self.paths[i];
+            path.push(self.nodes.get_index(node_index).unwrap().0.clone()); // Cannot fail
+            i = parent;
+        }
+        path.reverse();
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edges(n: &u32) -> Vec<(u32, u32)> {
+        match n {
+            0 => vec![(1, 1), (2, 2), (3, 4)],
+            1 => vec![(3, 2), (2, 3)],
+            2 => vec![(3, 1)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn k_shortest() {
+        let paths = astar_k(&0, edges, |_| 0, |&n| n == 3, 3).collect::<Vec<_>>();
+        assert_eq!(paths.len(), 3);
+        // The tied paths come both, in either order.
+        assert_eq!(paths[0].1, 3);
+        assert_eq!(paths[1].1, 3);
+        assert_ne!(paths[0].0, paths[1].0);
+        for (path, _) in &paths[..2] {
+            assert!(*path == [0, 1, 3] || *path == [0, 2, 3]);
+        }
+        assert_eq!(paths[2], (vec![0, 3], 4));
+
+        let paths = astar_k(&0, edges, |_| 0, |&n| n == 3, 10).collect::<Vec<_>>();
+        assert_eq!(paths.len(), 4);
+        assert_eq!(paths[3], (vec![0, 1, 2, 3], 5));
+
+        let mut paths = astar_k(&0, edges, |_| 0, |&n| n == 3, 10);
+        assert_eq!(paths.next().map(|(_, cost)| cost), Some(3));
+    }
+
+    #[test]
+    fn k_shortest_cycle() {
+        // 0 ⇄ 1 → 2, and a duplicate move from 0 to 1.
+        let successors = |&n: &u32| match n {
+            0 => vec![(1, 1u32), (1, 5)],
+            1 => vec![(0, 1), (2, 1)],
+            _ => vec![],
+        };
+        let heuristic = |&n: &u32| 2u32.saturating_sub(n);
+        let paths = astar_k(&0, successors, heuristic, |&n| n == 2, 3).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                (vec![0, 1, 2], 2),
+                (vec![0, 1, 0, 1, 2], 4),
+                (vec![0, 1, 0, 1, 0, 1, 2], 6)
+            ]
+        );
+    }
+}
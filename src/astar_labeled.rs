@@ -0,0 +1,151 @@
+//! A* searches whose moves carry a label, such as the transition of a Petri net that fires,
+//! returning the labels of the moves along the path.
+
+use std::hash::Hash;
+
+use crate::astar::{Cost, astar};
+
+/// A path of [`astar_labeled`]: its first node, then per move its label and the node it leads
+/// to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LabeledPath<N, L> {
+    pub start: N,
+    pub steps: Vec<(L, N)>,
+}
+
+impl<N, L> LabeledPath<N, L> {
+    /// The nodes of the path, comprising both the start and end node.
+    pub fn nodes(&self) -> impl Iterator<Item = &N> {
+        std::iter::once(&self.start).chain(self.steps.iter().map(|(_, node)| node))
+    }
+
+    /// The labels of the moves of the path.
+    pub fn labels(&self) -> impl Iterator<Item = &L> {
+        self.steps.iter().map(|(label, _)| label)
+    }
+
+    /// The nodes of the path, as returned by [`astar`].
+    pub fn into_nodes(self) -> Vec<N> {
+        let mut nodes = Vec::with_capacity(self.steps.len() + 1);
+        nodes.push(self.start);
+        nodes.extend(self.steps.into_iter().map(|(_, node)| node));
+        nodes
+    }
+}
+
+/// Like [`astar`], with `successors` that return triples of a successor, the cost of the move
+/// and its label. The path comes with the label of each move; when several moves join the
+/// same nodes, that of the cheapest, or of the first of the cheapest, which is the move the
+/// search took.
+///
+/// The labels are only looked up once a path is found, by calling `successors` again on the
+/// nodes of the path, which must then return the same moves.
+///
+/// ```
+/// # use ebi_optimisation::astar_labeled::astar_labeled;
+/// // Two moves from 0 to 1: the path takes the cheaper one.
+/// let successors = |&n: &u32| match n {
+///     0 => vec![(1, 5u32, "slow"), (1, 2, "fast")],
+///     _ => vec![],
+/// };
+/// let (path, cost) = astar_labeled(&0, successors, |_| 0, |&n| n == 1).unwrap();
+/// assert_eq!(path.steps, vec![("fast", 1)]);
+/// assert_eq!(cost, 2);
+/// ```
+pub fn astar_labeled<N, C, L, FN, IN, FH, FS>(
+    start: &N,
+    mut successors: FN,
+    heuristic: FH,
+    success: FS,
+) -> Option<(LabeledPath<N, L>, C)>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C, L)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    let (path, cost) = astar(
+        start,
+        |node: &N| (successors(node).into_iter()).map(|(successor, cost, _)| (successor, cost)),
+        heuristic,
+        success,
+    )?;
+    let labels = (path.windows(2))
+        .map(|step| cheapest_label(&mut successors, &step[0], &step[1]))
+        .collect::<Vec<_>>();
+    let mut nodes = path.into_iter();
+    let start = nodes.next().unwrap(); // Cannot fail
+    let steps = labels.into_iter().zip(nodes).collect();
+    Some((LabeledPath { start, steps }, cost))
+}
+
+/// The label of the first of the cheapest moves from `from` to `to`.
+fn cheapest_label<N, C, L, FN, IN>(successors: &mut FN, from: &N, to: &N) -> L
+where
+    N: Eq,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C, L)>,
+{
+    let mut cheapest: Option<(C, L)> = None;
+    for (successor, cost, label) in successors(from) {
+        if &successor == to
+            && !cost.is_forbidden()
+            && cheapest
+                .as_ref()
+                .is_none_or(|(cheapest, _)| &cost < cheapest)
+        {
+            cheapest = Some((cost, label));
+        }
+    }
+    cheapest
+        .expect("the successors of a node on the path must not change during a search")
+        .1
+}
+
+/// The successors of [`astar`] as those of [`astar_labeled`], with the unit label.
+pub fn unit_labels<N, C, FN, IN>(mut successors: FN) -> impl FnMut(&N) -> Vec<(N, C, ())>
+where
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+{
+    move |node| {
+        (successors(node).into_iter())
+            .map(|(successor, cost)| (successor, cost, ()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labeled_multigraph() {
+        // Two moves from 0 to 1 and two of the same cost from 1 to 2, and a dearer move from
+        // 0 to 2.
+        let successors = |&n: &u32| match n {
+            0 => vec![(1, 5u32, 'a'), (1, 2, 'b'), (2, 4, 'e')],
+            1 => vec![(2, 1, 'c'), (2, 1, 'd')],
+            _ => vec![],
+        };
+        let (path, cost) = astar_labeled(&0, successors, |_| 0, |&n| n == 2).unwrap();
+        assert_eq!(cost, 3);
+        assert_eq!(path.steps, vec![('b', 1), ('c', 2)]);
+        assert_eq!(path.labels().collect::<String>(), "bc");
+        assert_eq!(path.nodes().copied().collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(path.into_nodes(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn labeled_unit() {
+        let successors = |&n: &u32| vec![(n + 1, 1u32), (n + 2, 3)];
+        let (path, cost) = astar_labeled(&0, unit_labels(successors), |_| 0, |&n| n == 4).unwrap();
+        assert_eq!(
+            Some((path.into_nodes(), cost)),
+            astar(&0, successors, |_| 0, |&n| n == 4)
+        );
+    }
+}
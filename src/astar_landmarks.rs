@@ -0,0 +1,274 @@
+//! Landmark (ALT) heuristics: lower bounds on distances from the exact distances to and from
+//! a few landmark nodes, by the triangle inequality.
+
+use std::ops::Sub;
+
+use crate::{
+    astar::Cost,
+    astar_indexed::distances_from,
+    rand::{SeedableRng, rngs::StdRng, seq::index::sample},
+};
+
+/// A graph whose nodes are the integers below [`LandmarkGraph::node_count`], for
+/// [`Landmarks`].
+pub trait LandmarkGraph<C> {
+    fn node_count(&self) -> usize;
+
+    /// The moves from `node`, with their costs.
+    fn successors(&self, node: usize) -> Vec<(usize, C)>;
+}
+
+/// A graph as the list of the moves from each node.
+impl<C: Clone> LandmarkGraph<C> for Vec<Vec<(usize, C)>> {
+    fn node_count(&self) -> usize {
+        self.len()
+    }
+
+    fn successors(&self, node: usize) -> Vec<(usize, C)> {
+        self[node].clone()
+    }
+}
+
+/// How [`Landmarks::build`] chooses the landmarks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LandmarkSelection {
+    /// Each landmark is the node farthest from the landmarks chosen before, starting with the
+    /// node farthest from node 0. Landmarks at the edge of the graph give the best bounds.
+    Farthest,
+    /// Distinct nodes drawn at random with this seed.
+    Random(u64),
+}
+
+/// The exact distances from and to a few landmark nodes of a graph, which bound the distance
+/// between any two nodes from below: for a landmark `l`, the distance from `u` to `v` is at
+/// least `d(l, v) - d(l, u)` and at least `d(u, l) - d(v, l)`. The largest of those bounds is
+/// an admissible and consistent heuristic for searches to any goal, for instance with
+/// [`astar_indexed`](crate::astar_indexed::astar_indexed):
+///
+/// ```
+/// # use ebi_optimisation::{astar_indexed::astar_indexed, astar_landmarks::*};
+/// // A cycle of 100 nodes.
+/// let graph = (0..100).map(|u| vec![((u + 1) % 100, 1u32)]).collect::<Vec<_>>();
+/// let landmarks = Landmarks::build(&graph, 2, LandmarkSelection::Farthest);
+/// let goal = 60;
+/// let (_, cost) = astar_indexed(
+///     100,
+///     10,
+///     |&u| graph[u].clone(),
+///     |&u| landmarks.heuristic(u, goal),
+///     |&u| u == goal,
+/// )
+/// .unwrap();
+/// assert_eq!(cost, 50);
+/// ```
+pub struct Landmarks<C> {
+    landmarks: Vec<usize>,
+    /// Per landmark, the distances from it to the nodes.
+    from: Vec<Vec<Option<C>>>,
+    /// Per landmark, the distances from the nodes to it.
+    to: Vec<Vec<Option<C>>>,
+}
+
+impl<C> Landmarks<C>
+where
+    C: Cost + Sub<Output = C>,
+{
+    /// Choose `k` landmarks of `graph`, or all nodes if there are fewer, and compute their
+    /// distances to and from all nodes, with two searches per landmark over the whole graph.
+    pub fn build<G: LandmarkGraph<C>>(graph: &G, k: usize, selection: LandmarkSelection) -> Self {
+        let n = graph.node_count();
+        let k = k.min(n);
+        let mut predecessors = vec![vec![]; n];
+        for u in 0..n {
+            for (v, cost) in graph.successors(u) {
+                predecessors[v].push((u, cost));
+            }
+        }
+        let distances = |landmark| {
+            (
+                distances_from(n, landmark, |&u| graph.successors(u)),
+                distances_from(n, landmark, |&u| predecessors[u].clone()),
+            )
+        };
+
+        let mut result = Landmarks {
+            landmarks: vec![],
+            from: vec![],
+            to: vec![],
+        };
+        let add = |result: &mut Self, landmark| {
+            let (from, to) = distances(landmark);
+            result.landmarks.push(landmark);
+            result.from.push(from);
+            result.to.push(to);
+        };
+        match selection {
+            LandmarkSelection::Farthest => {
+                if k > 0 {
+                    let origin = distances_from(n, 0, |&u| graph.successors(u));
+                    add(&mut result, farthest(n, &[origin]).unwrap_or(0));
+                }
+                while result.landmarks.len() < k {
+                    let landmark = farthest(n, &result.from).unwrap_or_else(|| {
+                        (0..n).find(|u| !result.landmarks.contains(u)).unwrap() // Cannot fail
+                    });
+                    add(&mut result, landmark);
+                }
+            }
+            LandmarkSelection::Random(seed) => {
+                let mut rng = StdRng::seed_from_u64(seed);
+                for landmark in sample(&mut rng, n, k) {
+                    add(&mut result, landmark);
+                }
+            }
+        }
+        result
+    }
+
+    /// The landmarks.
+    pub fn landmarks(&self) -> &[usize] {
+        &self.landmarks
+    }
+
+    /// A lower bound on the distance from `node` to `goal`: the largest bound of the
+    /// landmarks, or zero.
+    pub fn heuristic(&self, node: usize, goal: usize) -> C {
+        let mut best = C::zero();
+        for (from, to) in self.from.iter().zip(&self.to) {
+            if let (Some(to_goal), Some(to_node)) = (&from[goal], &from[node]) {
+                best = larger_difference(best, to_goal, to_node);
+            }
+            if let (Some(from_node), Some(from_goal)) = (&to[node], &to[goal]) {
+                best = larger_difference(best, from_node, from_goal);
+            }
+        }
+        best
+    }
+}
+
+/// The largest of `best` and `a - b`.
+fn larger_difference<C>(best: C, a: &C, b: &C) -> C
+where
+    C: Cost + Sub<Output = C>,
+{
+    if a > b {
+        let difference = a.clone() - b.clone();
+        if difference > best {
+            return difference;
+        }
+    }
+    best
+}
+
+/// The node whose smallest distance from the sources is the largest, among the nodes that
+/// all sources reach and that are not sources themselves.
+fn farthest<C: Cost>(n: usize, distances: &[Vec<Option<C>>]) -> Option<usize> {
+    let mut best: Option<(usize, &C)> = None;
+    'nodes: for u in 0..n {
+        let mut distance: Option<&C> = None;
+        for distances in distances {
+            match &distances[u] {
+                Some(d) => {
+                    if distance.is_none_or(|distance| d < distance) {
+                        distance = Some(d);
+                    }
+                }
+                None => continue 'nodes,
+            }
+        }
+        let Some(distance) = distance else {
+            continue;
+        };
+        if *distance > C::zero() && best.is_none_or(|(_, best)| distance > best) {
+            best = Some((u, distance));
+        }
+    }
+    best.map(|(u, _)| u)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        astar::astar_with_options,
+        astar_options::SearchOptions,
+        rand::{Rng, SeedableRng, rngs::StdRng},
+    };
+
+    #[test]
+    fn landmarks_admissible() {
+        let mut rng = StdRng::seed_from_u64(697);
+        let n = 30;
+        let graph = (0..n)
+            .map(|_| {
+                (0..rng.random_range(1..4))
+                    .map(|_| (rng.random_range(0..n), rng.random_range(1..10u32)))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for selection in [LandmarkSelection::Farthest, LandmarkSelection::Random(1)] {
+            let landmarks = Landmarks::build(&graph, 3, selection);
+            assert_eq!(landmarks.landmarks().len(), 3);
+            for u in 0..n {
+                let distances = distances_from(n, u, |&v| graph[v].clone());
+                for (v, distance) in distances.into_iter().enumerate() {
+                    if let Some(distance) = distance {
+                        assert!(
+                            landmarks.heuristic(u, v) <= distance,
+                            "{:?}: from {} to {}",
+                            selection,
+                            u,
+                            v
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn landmarks_expand_less() {
+        // A 20 × 20 grid.
+        let side = 20;
+        let graph = (0..side * side)
+            .map(|u| {
+                let (x, y) = (u % side, u / side);
+                let mut moves = vec![];
+                if x > 0 {
+                    moves.push((u - 1, 1u32));
+                }
+                if x + 1 < side {
+                    moves.push((u + 1, 1));
+                }
+                if y > 0 {
+                    moves.push((u - side, 1));
+                }
+                if y + 1 < side {
+                    moves.push((u + side, 1));
+                }
+                moves
+            })
+            .collect::<Vec<_>>();
+        let landmarks = Landmarks::build(&graph, 4, LandmarkSelection::Farthest);
+        let (start, goal) = (side * side / 2, side - 1);
+        let search = |heuristic: &dyn Fn(usize) -> u32| {
+            let (outcome, stats) = astar_with_options(
+                &start,
+                |&u| graph[u].clone(),
+                |&u| heuristic(u),
+                |&u| u == goal,
+                &SearchOptions::new(),
+            );
+            (outcome.found().unwrap().1, stats.expanded)
+        };
+        let (cost, expanded) = search(&|u| landmarks.heuristic(u, goal));
+        let (zero_cost, zero_expanded) = search(&|_| 0);
+        assert_eq!(cost, zero_cost);
+        assert!(
+            expanded < zero_expanded,
+            "ALT: {}, zero: {}",
+            expanded,
+            zero_expanded
+        );
+    }
+}
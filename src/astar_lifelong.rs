@@ -0,0 +1,396 @@
+//! Lifelong Planning A* (LPA*): repeated searches between the same two nodes on a graph whose
+//! move costs change, each of which repairs the result of the previous one.
+
+use std::{collections::BinaryHeap, hash::Hash, mem, time::Instant};
+
+use ebi_arithmetic::ebi_number::Zero;
+use indexmap::map::Entry::{Occupied, Vacant};
+use log::debug;
+
+use crate::{
+    astar::{Cost, FxIndexMap, SmallestCostHolder},
+    astar_options::TieBreak,
+    astar_stats::SearchStats,
+};
+
+/// The costs of a node in [`LifelongSearcher`], `None` if infinite.
+struct LifelongState<C> {
+    /// The cost of the best path to the node found so far.
+    g: Option<C>,
+    /// The cost of the best path to the node through the `g` of its predecessors. The node is
+    /// consistent if it equals `g`.
+    rhs: Option<C>,
+    h: C,
+}
+
+impl<C: Cost> LifelongState<C> {
+    /// The smallest of `g` and `rhs`, which is finite for an inconsistent node.
+    fn min_cost(&self) -> Option<&C> {
+        match (&self.g, &self.rhs) {
+            (Some(g), Some(rhs)) => Some(g.min(rhs)),
+            (Some(cost), None) | (None, Some(cost)) => Some(cost),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Whether `a` < `b`, for costs that are infinite if `None`.
+fn less<C: Ord>(a: &Option<C>, b: &Option<C>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a < b,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+/// Shortest paths from `start` to `goal` while the costs of moves change, with Lifelong
+/// Planning A*. The first [`replan`](Self::replan) searches like [`astar`](crate::astar::astar);
+/// after the cost of a few moves changed, announced with
+/// [`update_edge`](Self::update_edge), the next one only revisits the nodes whose cost
+/// changed, which is usually far fewer than a new search.
+///
+/// `successors` and `predecessors` return the moves from and to a node with their costs;
+/// [`update_edge`](Self::update_edge) overrides the costs of those moves. The costs must be
+/// positive, and `heuristic` must be consistent.
+///
+/// ```
+/// # use ebi_optimisation::astar_lifelong::LifelongSearcher;
+/// // A line of 10 nodes, whose moves between neighbours cost 1 both ways.
+/// let neighbours = |&n: &u32| {
+///     [(n.wrapping_sub(1), 1u32), (n + 1, 1)]
+///         .into_iter()
+///         .filter(|&(m, _)| m < 10)
+/// };
+/// let mut searcher = LifelongSearcher::new(0, 9, neighbours, neighbours, |&n| 9 - n);
+/// assert_eq!(searcher.replan().unwrap().1, 9);
+/// searcher.update_edge(&4, &5, 3);
+/// assert_eq!(searcher.replan().unwrap().1, 11);
+/// ```
+pub struct LifelongSearcher<N, C, FN, FP, FH> {
+    successors: FN,
+    predecessors: FP,
+    heuristic: FH,
+    start: usize,
+    goal: usize,
+    states: FxIndexMap<N, LifelongState<C>>,
+    /// The costs set by [`Self::update_edge`], by the indices of the nodes of the move.
+    costs: FxIndexMap<(usize, usize), C>,
+    /// The inconsistent nodes, by their smallest cost plus heuristic, then smallest cost.
+    /// Entries whose node has become consistent or changed cost since are skipped.
+    to_see: BinaryHeap<SmallestCostHolder<C>>,
+    seq: usize,
+    /// The counters since the last replan.
+    current_stats: SearchStats,
+    /// The counters of the last replan.
+    last_stats: SearchStats,
+}
+
+impl<N, C, FN, IN, FP, IP, FH> LifelongSearcher<N, C, FN, FP, FH>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FP: FnMut(&N) -> IP,
+    IP: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+{
+    /// A searcher for paths from `start` to `goal`, which searches nothing until
+    /// [`replan`](Self::replan).
+    pub fn new(start: N, goal: N, successors: FN, predecessors: FP, heuristic: FH) -> Self {
+        let mut searcher = LifelongSearcher {
+            successors,
+            predecessors,
+            heuristic,
+            start: 0,
+            goal: 0,
+            states: FxIndexMap::default(),
+            costs: FxIndexMap::default(),
+            to_see: BinaryHeap::new(),
+            seq: 0,
+            current_stats: SearchStats::default(),
+            last_stats: SearchStats::default(),
+        };
+        searcher.start = searcher.index(start);
+        searcher.goal = searcher.index(goal);
+        searcher.states[searcher.start].rhs = Some(Zero::zero());
+        searcher.push(searcher.start);
+        searcher
+    }
+
+    /// Set the cost of the move from `from` to `to`, which `successors` and `predecessors`
+    /// return, to `cost`. The next [`replan`](Self::replan) takes it into account.
+    pub fn update_edge(&mut self, from: &N, to: &N, cost: C) {
+        let from = self.index(from.clone());
+        let to = self.index(to.clone());
+        self.costs.insert((from, to), cost);
+        self.update(to);
+    }
+
+    /// A shortest path from the start to the goal with the current costs, comprising both,
+    /// and its cost; `None` if there is none.
+    pub fn replan(&mut self) -> Option<(Vec<N>, C)> {
+        let start_time = Instant::now();
+        self.compute_shortest_path();
+        let res = self.path();
+        self.current_stats.time = start_time.elapsed();
+        debug!("LPA*: done, {}", self.current_stats);
+        self.last_stats = mem::take(&mut self.current_stats);
+        res
+    }
+
+    /// The counters of the last [`replan`](Self::replan), including the work of the
+    /// [`update_edge`](Self::update_edge) calls before it.
+    pub fn stats(&self) -> &SearchStats {
+        &self.last_stats
+    }
+
+    /// The index of `node`, which is added with infinite costs if it is new.
+    fn index(&mut self, node: N) -> usize {
+        match self.states.entry(node) {
+            Occupied(e) => e.index(),
+            Vacant(e) => {
+                let h = (self.heuristic)(e.key());
+                let index = e.index();
+                e.insert(LifelongState {
+                    g: None,
+                    rhs: None,
+                    h,
+                });
+                index
+            }
+        }
+    }
+
+    /// The cost of the move from the node with index `from` to the one with index `to`,
+    /// given as `cost` by the closures.
+    fn cost(&self, from: usize, to: usize, cost: C) -> C {
+        self.costs.get(&(from, to)).cloned().unwrap_or(cost)
+    }
+
+    /// The priority of the node with index `index` if it is inconsistent.
+    fn key(&self, index: usize) -> Option<(C, C)> {
+        let state = &self.states[index];
+        if state.g == state.rhs {
+            return None;
+        }
+        let cost = state.min_cost()?.clone();
+        let mut estimated_cost = cost.clone();
+        estimated_cost += state.h.clone();
+        Some((estimated_cost, cost))
+    }
+
+    /// Add the node with index `index` to the open list if it is inconsistent.
+    fn push(&mut self, index: usize) {
+        if let Some((estimated_cost, cost)) = self.key(index) {
+            self.seq += 1;
+            self.to_see.push(SmallestCostHolder {
+                estimated_cost,
+                cost,
+                index,
+                depth: 0,
+                seq: self.seq,
+                tie_break: TieBreak::PreferLowG,
+            });
+            self.current_stats.max_open_len =
+                self.current_stats.max_open_len.max(self.to_see.len());
+        }
+    }
+
+    /// Recompute the `rhs` of the node with index `index` from its predecessors.
+    fn update(&mut self, index: usize) {
+        if index != self.start {
+            self.current_stats.state_updates += 1;
+            let node = self.states.get_index(index).unwrap().0.clone(); // Cannot fail
+            let mut rhs: Option<C> = None;
+            for (predecessor, cost) in (self.predecessors)(&node) {
+                let predecessor = self.index(predecessor);
+                let cost = self.cost(predecessor, index, cost);
+                if cost.is_forbidden() {
+                    continue;
+                }
+                if let Some(g) = &self.states[predecessor].g {
+                    let mut new_cost = g.clone();
+                    new_cost += cost;
+                    if rhs.as_ref().is_none_or(|rhs| &new_cost < rhs) {
+                        rhs = Some(new_cost);
+                    }
+                }
+            }
+            self.states[index].rhs = rhs;
+        }
+        self.push(index);
+    }
+
+    fn compute_shortest_path(&mut self) {
+        while let Some(holder) = self.to_see.peek() {
+            let key = (holder.estimated_cost.clone(), holder.cost.clone());
+            let index = holder.index;
+            if self.key(index).as_ref() != Some(&key) {
+                self.to_see.pop();
+                self.current_stats.duplicates_skipped += 1;
+                continue;
+            }
+            // Done once the goal is consistent and comes before the open list.
+            let goal = &self.states[self.goal];
+            if let Some(cost) = goal.g.as_ref().filter(|_| goal.g == goal.rhs) {
+                let mut estimated_cost = cost.clone();
+                estimated_cost += goal.h.clone();
+                if key >= (estimated_cost, cost.clone()) {
+                    break;
+                }
+            }
+            self.to_see.pop();
+            self.current_stats.expanded += 1;
+
+            let node = self.states.get_index(index).unwrap().0.clone(); // Cannot fail
+            let state = &mut self.states[index];
+            if less(&state.rhs, &state.g) {
+                state.g = state.rhs.clone();
+            } else {
+                state.g = None;
+                self.update(index);
+            }
+            for (successor, _) in (self.successors)(&node) {
+                self.current_stats.generated += 1;
+                let successor = self.index(successor);
+                self.update(successor);
+            }
+        }
+    }
+
+    /// The path to the goal, back from it through the predecessors of the smallest `g` plus
+    /// move cost, if the goal is reachable.
+    fn path(&mut self) -> Option<(Vec<N>, C)> {
+        let cost = self.states[self.goal].g.clone()?;
+        let mut path = vec![self.goal];
+        let mut index = self.goal;
+        while index != self.start {
+            let node = self.states.get_index(index).unwrap().0.clone(); // Cannot fail
+            let mut parent: Option<(usize, C)> = None;
+            for (predecessor, move_cost) in (self.predecessors)(&node) {
+                let predecessor = self.index(predecessor);
+                let move_cost = self.cost(predecessor, index, move_cost);
+                if move_cost.is_forbidden() {
+                    continue;
+                }
+                if let Some(mut new_cost) = self.states[predecessor].g.clone() {
+                    new_cost += move_cost;
+                    if parent.as_ref().is_none_or(|(_, best)| &new_cost < best) {
+                        parent = Some((predecessor, new_cost));
+                    }
+                }
+            }
+            // The nodes on a shortest path to the consistent goal are consistent, so they
+            // have a predecessor with a finite cost.
+            index = parent.unwrap().0;
+            path.push(index);
+        }
+        let path = (path.into_iter().rev())
+            .map(|index| self.states.get_index(index).unwrap().0.clone())
+            .collect();
+        Some((path, cost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::astar;
+
+    #[test]
+    fn lifelong_replan() {
+        // A 20 × 20 grid with moves of cost 1 between neighbouring cells, and a wall on
+        // x = 10 with a gap at y = 5 and at y = 8.
+        let side = 20u32;
+        let neighbours = move |&(x, y): &(u32, u32)| {
+            [
+                (x > 0).then(|| (x - 1, y)),
+                (x + 1 < side).then(|| (x + 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (y + 1 < side).then(|| (x, y + 1)),
+            ]
+            .into_iter()
+            .flatten()
+            .filter(|&(x, y)| x != 10 || y == 5 || y == 8)
+            .map(|cell| (cell, 1u32))
+        };
+        let (start, goal): ((u32, u32), (u32, u32)) = ((0, 6), (19, 6));
+        let heuristic = move |&(x, y): &(u32, u32)| goal.0.abs_diff(x) + goal.1.abs_diff(y);
+        let gap = ((9, 5), (10, 5));
+        // The costs of the moves raised so far.
+        let raised = std::cell::RefCell::new(vec![]);
+        let successors = |&cell: &(u32, u32)| {
+            let raised = raised.borrow().clone();
+            neighbours(&cell).map(move |(next, cost)| {
+                match raised.iter().find(|(edge, _)| *edge == (cell, next)) {
+                    Some(&(_, raised_cost)) => (next, raised_cost),
+                    None => (next, cost),
+                }
+            })
+        };
+        // The cost and the number of state updates of a search from scratch.
+        let full_search = || {
+            let mut searcher =
+                LifelongSearcher::new(start, goal, neighbours, neighbours, heuristic);
+            for &((from, to), cost) in raised.borrow().iter() {
+                searcher.update_edge(&from, &to, cost);
+            }
+            let cost = searcher.replan().unwrap().1;
+            (cost, searcher.stats().state_updates)
+        };
+        let raise = |searcher: &mut LifelongSearcher<_, _, _, _, _>,
+                     edge: ((u32, u32), (u32, u32)),
+                     cost: u32| {
+            searcher.update_edge(&edge.0, &edge.1, cost);
+            raised.borrow_mut().retain(|(raised, _)| *raised != edge);
+            raised.borrow_mut().push((edge, cost));
+        };
+
+        let mut searcher = LifelongSearcher::new(start, goal, neighbours, neighbours, heuristic);
+        let (path, cost) = searcher.replan().unwrap();
+        assert_eq!(cost, 21);
+        assert!(path.contains(&gap.1));
+
+        // Make a move of the path beyond the wall expensive: a path of the same cost avoids
+        // it, which the search finds without going over the rest of the grid again.
+        let i = path.iter().position(|&(x, _)| x == 15).unwrap();
+        let edge = (path[i - 1], path[i]);
+        raise(&mut searcher, edge, 50);
+        let (path, cost) = searcher.replan().unwrap();
+        let expected = astar(&start, successors, heuristic, |&cell| cell == goal).unwrap();
+        assert_eq!(cost, expected.1);
+        assert!(!path.windows(2).any(|step| (step[0], step[1]) == edge));
+        let (full_cost, full_updates) = full_search();
+        assert_eq!(full_cost, cost);
+        let updates = searcher.stats().state_updates;
+        assert!(
+            updates * 4 < full_updates,
+            "replan: {}, full search: {}",
+            updates,
+            full_updates
+        );
+
+        // Make the gap at y = 5 expensive: the path goes through the other one. The cost of
+        // the whole grid beyond the wall changes, so the search goes over most of it again.
+        raise(&mut searcher, gap, 50);
+        let (path, cost) = searcher.replan().unwrap();
+        let expected = astar(&start, successors, heuristic, |&cell| cell == goal).unwrap();
+        assert_eq!(cost, expected.1);
+        assert_eq!(cost, 23);
+        assert!(path.contains(&(10, 8)));
+        assert_eq!(full_search().0, cost);
+
+        // Restoring the cost gives the first cost back.
+        raise(&mut searcher, gap, 1);
+        assert_eq!(searcher.replan().unwrap().1, 21);
+        let updates = searcher.stats().state_updates;
+        let (_, full_updates) = full_search();
+        assert!(
+            updates * 2 < full_updates,
+            "replan: {}, full search: {}",
+            updates,
+            full_updates
+        );
+    }
+}
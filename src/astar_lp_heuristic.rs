@@ -0,0 +1,222 @@
+//! An A* heuristic from the optimal value of a linear program, such as the marking equation of
+//! a Petri net in alignments.
+
+use std::hash::Hash;
+
+use ebi_arithmetic::Zero;
+
+use crate::{
+    abnormal_fraction::AbnormalFraction,
+    astar::FxIndexMap,
+    linear_programming::{Basis, Error, Problem, SolveOptions},
+};
+
+/// Counters of an [`LpHeuristic`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LpHeuristicStats {
+    /// Number of programs solved from scratch: the first, unless it failed.
+    pub cold_solves: usize,
+    /// Number of programs solved from the basis of the first.
+    pub warm_solves: usize,
+    /// Number of estimates taken from the cache.
+    pub cache_hits: usize,
+    /// Number of solves that failed other than by infeasibility, for which the estimate is
+    /// zero.
+    pub fallbacks: usize,
+}
+
+/// A heuristic whose estimate of a state is the optimal value of a minimisation problem, a
+/// template whose right-hand sides are set per state by `rhs`. If the problem relaxes the
+/// remaining search, as the marking equation does for alignments, the estimates are
+/// admissible.
+///
+/// The first estimate solves the problem from scratch; the later ones start from its basis,
+/// which remains dual feasible as only the right-hand sides change. To solve at the root of
+/// the search first, estimate the start state before the search. The estimates are cached per
+/// state. The estimate of a state for which the problem is infeasible is infinite, as no goal
+/// can be reached from it. When solving fails otherwise, for instance when it is interrupted
+/// through the [options](SolveOptions::cancel_token), the estimate is zero.
+///
+/// ```
+/// # use ebi_arithmetic::{One, Zero};
+/// # use ebi_optimisation::{*, abnormal_fraction::AbnormalFraction, astar::astar};
+/// # use ebi_optimisation::{astar_lp_heuristic::LpHeuristic, linear_programming::*};
+/// // Take down a pile of n stones, one or two at a time, for a cost of 1 per move.
+/// let mut problem = Problem::new(OptimisationDirection::Minimise);
+/// let one = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+/// let two = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+/// problem
+///     .add_constraint(&[(one, f1_ab!()), (two, f_ab!(2))], ComparisonOp::Eq, f0_ab!())
+///     .unwrap();
+/// let mut heuristic = LpHeuristic::new(problem, |&n: &usize| [(0, f_ab!(n))]);
+///
+/// let successors = |&n: &usize| [(n - 1, f1_ab!()), (n.saturating_sub(2), f1_ab!())];
+/// let (_, cost) = astar(&9, successors, |n| heuristic.estimate(n), |&n| n == 0).unwrap();
+/// assert_eq!(cost, f_ab!(5));
+/// ```
+pub struct LpHeuristic<N, F> {
+    problem: Problem,
+    rhs: F,
+    options: SolveOptions,
+    root_basis: Option<Basis>,
+    cache: FxIndexMap<N, AbnormalFraction>,
+    stats: LpHeuristicStats,
+}
+
+impl<N, F, I> LpHeuristic<N, F>
+where
+    N: Eq + Hash + Clone,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = (usize, AbnormalFraction)>,
+{
+    /// The heuristic of `problem`, with the right-hand sides of a state given by `rhs` as pairs
+    /// of a constraint and its right-hand side; see [`Problem::set_rhs`].
+    pub fn new(problem: Problem, rhs: F) -> Self {
+        Self::with_options(problem, rhs, SolveOptions::default())
+    }
+
+    /// Like [`Self::new`], solving with `options`.
+    pub fn with_options(problem: Problem, rhs: F, options: SolveOptions) -> Self {
+        LpHeuristic {
+            problem,
+            rhs,
+            options,
+            root_basis: None,
+            cache: FxIndexMap::default(),
+            stats: LpHeuristicStats::default(),
+        }
+    }
+
+    /// The estimate of the cost from `state` to a goal: the optimal value of the problem for
+    /// `state`, or zero if that is negative.
+    pub fn estimate(&mut self, state: &N) -> AbnormalFraction {
+        if let Some(estimate) = self.cache.get(state) {
+            self.stats.cache_hits += 1;
+            return estimate.clone();
+        }
+        let estimate = match self.solve(state) {
+            Ok(value) => value.max(AbnormalFraction::zero()),
+            Err(Error::Infeasible(_)) => AbnormalFraction::infinity(),
+            Err(_) => {
+                self.stats.fallbacks += 1;
+                AbnormalFraction::zero()
+            }
+        };
+        self.cache.insert(state.clone(), estimate.clone());
+        estimate
+    }
+
+    fn solve(&mut self, state: &N) -> Result<AbnormalFraction, Error> {
+        self.problem.set_rhs_many((self.rhs)(state))?;
+        let solution = match &self.root_basis {
+            Some(basis) => {
+                self.stats.warm_solves += 1;
+                (self.problem).solve_from_basis_with_options(basis, &self.options)?
+            }
+            None => {
+                self.stats.cold_solves += 1;
+                let solution = self.problem.solve_with_options(&self.options)?;
+                self.root_basis = Some(solution.extract_basis());
+                solution
+            }
+        };
+        Ok(solution.objective())
+    }
+
+    /// The counters of the heuristic so far.
+    pub fn stats(&self) -> &LpHeuristicStats {
+        &self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::ControlFlow;
+
+    use ebi_arithmetic::One;
+
+    use super::*;
+    use crate::{
+        astar::astar_with_options,
+        astar_options::SearchOptions,
+        f_ab, f0_ab, f1_ab,
+        linear_programming::{ComparisonOp, OptimisationDirection},
+    };
+
+    /// The marking equation of a net with places a and b, and transitions of cost 1 that take
+    /// a token from a, from b, or from both: per place, the transitions that take its tokens
+    /// fire as many times as it has tokens.
+    fn marking_equation() -> Problem {
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let unbounded = || (f0_ab!(), AbnormalFraction::infinity());
+        let take_a = problem.add_var(f1_ab!(), unbounded());
+        let take_b = problem.add_var(f1_ab!(), unbounded());
+        let take_both = problem.add_var(f1_ab!(), unbounded());
+        for take in [take_a, take_b] {
+            problem
+                .add_constraint(
+                    &[(take, f1_ab!()), (take_both, f1_ab!())],
+                    ComparisonOp::Eq,
+                    f0_ab!(),
+                )
+                .unwrap();
+        }
+        problem
+    }
+
+    fn marking_rhs(&(a, b): &(usize, usize)) -> [(usize, AbnormalFraction); 2] {
+        [(0, f_ab!(a)), (1, f_ab!(b))]
+    }
+
+    #[test]
+    fn lp_heuristic_expands_less() {
+        let successors = |&(a, b): &(usize, usize)| {
+            let mut succs = vec![];
+            if a > 0 {
+                succs.push(((a - 1, b), f1_ab!()));
+            }
+            if b > 0 {
+                succs.push(((a, b - 1), f1_ab!()));
+            }
+            if a > 0 && b > 0 {
+                succs.push(((a - 1, b - 1), f1_ab!()));
+            }
+            succs
+        };
+        let success = |&marking: &(usize, usize)| marking == (0, 0);
+        let options = SearchOptions::new();
+
+        let (zero, zero_stats) =
+            astar_with_options(&(10, 7), successors, |_| f0_ab!(), success, &options);
+        let mut heuristic = LpHeuristic::new(marking_equation(), marking_rhs);
+        let (lp, lp_stats) = astar_with_options(
+            &(10, 7),
+            successors,
+            |marking| heuristic.estimate(marking),
+            success,
+            &options,
+        );
+        assert_eq!(lp.found().unwrap().1, f_ab!(10));
+        assert_eq!(zero.found().unwrap().1, f_ab!(10));
+        assert!(
+            lp_stats.expanded < zero_stats.expanded,
+            "LP: {}, zero: {}",
+            lp_stats.expanded,
+            zero_stats.expanded
+        );
+        let stats = heuristic.stats();
+        assert_eq!(stats.cold_solves, 1);
+        assert!(stats.warm_solves > 0);
+        assert_eq!(stats.fallbacks, 0);
+    }
+
+    #[test]
+    fn lp_heuristic_interrupted() {
+        let options = SolveOptions::new().on_iteration(|_| ControlFlow::Break(()));
+        let mut heuristic = LpHeuristic::with_options(marking_equation(), marking_rhs, options);
+        assert_eq!(heuristic.estimate(&(3, 2)), f0_ab!());
+        assert_eq!(heuristic.estimate(&(3, 2)), f0_ab!());
+        assert_eq!(heuristic.stats().fallbacks, 1);
+        assert_eq!(heuristic.stats().cache_hits, 1);
+    }
+}
@@ -0,0 +1,281 @@
+//! Memoization of heuristics that are expensive to compute, on a projection of the nodes.
+
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+};
+
+use rustc_hash::FxHashMap;
+
+/// The table of the values of a [`MemoizedHeuristic`], by key of the projection.
+pub trait MemoTable<K, C> {
+    fn get(&self, key: &K) -> Option<&C>;
+
+    fn insert(&mut self, key: K, value: C);
+}
+
+impl<K, C, S> MemoTable<K, C> for HashMap<K, C, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    fn get(&self, key: &K) -> Option<&C> {
+        HashMap::get(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: C) {
+        HashMap::insert(self, key, value);
+    }
+}
+
+/// A table for keys that are small integers, which grows to the largest key inserted.
+impl<C> MemoTable<usize, C> for Vec<Option<C>> {
+    fn get(&self, key: &usize) -> Option<&C> {
+        self.as_slice().get(*key)?.as_ref()
+    }
+
+    fn insert(&mut self, key: usize, value: C) {
+        if key >= self.len() {
+            self.resize_with(key + 1, || None);
+        }
+        self[key] = Some(value);
+    }
+}
+
+/// A heuristic that is computed once per key of a projection of the nodes, for heuristics
+/// such as the exact distances in a projected graph, which are expensive and equal for all
+/// nodes with the same projection. The table outlives the searches, and with the `serde`
+/// feature it can be saved and loaded to be reused across runs; the counters of hits and
+/// misses are not saved.
+///
+/// ```
+/// # use ebi_optimisation::{astar::astar, astar_memoized::MemoizedHeuristic};
+/// // Nodes are a position and a parity; the heuristic only depends on the position.
+/// let successors = |&(x, parity): &(u32, bool)| vec![((x + 1, !parity), 1), ((x + 2, parity), 3)];
+/// let mut memo = MemoizedHeuristic::new();
+/// let (_, cost) = astar(
+///     &(0, false),
+///     successors,
+///     memo.heuristic(|&(x, _): &(u32, bool)| x, |&x| 10u32.saturating_sub(x)),
+///     |&(x, _)| x == 10,
+/// )
+/// .unwrap();
+/// assert_eq!(cost, 10);
+/// assert_eq!(memo.misses(), 11);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de>"
+    ))
+)]
+pub struct MemoizedHeuristic<K, C, T = FxHashMap<K, C>> {
+    table: T,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    hits: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    misses: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    marker: PhantomData<fn(K) -> C>,
+}
+
+impl<K, C> MemoizedHeuristic<K, C>
+where
+    K: Eq + Hash,
+{
+    /// An empty table, in a hash map.
+    pub fn new() -> Self {
+        Self::with_table(FxHashMap::default())
+    }
+}
+
+impl<K, C> Default for MemoizedHeuristic<K, C>
+where
+    K: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> MemoizedHeuristic<usize, C, Vec<Option<C>>> {
+    /// An empty table, in a vector for the keys below `len` and beyond.
+    pub fn indexed(len: usize) -> Self {
+        Self::with_table(Vec::from_iter(std::iter::repeat_with(|| None).take(len)))
+    }
+}
+
+impl<K, C, T> MemoizedHeuristic<K, C, T> {
+    pub fn with_table(table: T) -> Self {
+        Self {
+            table,
+            hits: 0,
+            misses: 0,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn table(&self) -> &T {
+        &self.table
+    }
+
+    /// Number of values taken from the table.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Number of values computed by the inner heuristic.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+}
+
+impl<K, C, T> MemoizedHeuristic<K, C, T>
+where
+    C: Clone,
+    T: MemoTable<K, C>,
+{
+    /// The value of `key`, from the table, or from `inner`, which is then stored.
+    pub fn get(&mut self, key: K, inner: impl FnOnce(&K) -> C) -> C {
+        if let Some(value) = self.table.get(&key) {
+            self.hits += 1;
+            return value.clone();
+        }
+        self.misses += 1;
+        let value = inner(&key);
+        self.table.insert(key, value.clone());
+        value
+    }
+
+    /// A heuristic for [`astar`](crate::astar::astar) and its variants, which computes the
+    /// value of a node with `inner` on its `projection` only if the table has no value for it.
+    pub fn heuristic<'a, N>(
+        &'a mut self,
+        mut projection: impl FnMut(&N) -> K + 'a,
+        mut inner: impl FnMut(&K) -> C + 'a,
+    ) -> impl FnMut(&N) -> C + 'a {
+        move |node| self.get(projection(node), &mut inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{
+        astar::{SearchOutcome, astar_with_options},
+        astar_options::SearchOptions,
+    };
+
+    /// A cell and whether the key was picked up.
+    type State = (u32, u32, bool);
+
+    /// The moves of a `side` × `side` grid with a key for the doors at (0, 0) and
+    /// (side - 1, 0), which the goal needs: cost 2 per move without the key and 1 with it.
+    fn successors(side: u32) -> impl FnMut(&State) -> Vec<(State, u32)> {
+        move |&(x, y, key)| {
+            let key = key || (y == 0 && (x == 0 || x == side - 1));
+            let cost = if key { 1 } else { 2 };
+            [
+                (x > 0).then(|| (x - 1, y)),
+                (x + 1 < side).then(|| (x + 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (y + 1 < side).then(|| (x, y + 1)),
+            ]
+            .into_iter()
+            .flatten()
+            .map(|(x, y)| ((x, y, key), cost))
+            .collect()
+        }
+    }
+
+    #[test]
+    fn memoized_once_per_projection() {
+        let side = 12;
+        let goal = |&(x, y, key): &State| key && (x, y) == (side / 2, side - 1);
+        let calls = Cell::new(0);
+        let inner = |&(x, y): &(u32, u32)| {
+            calls.set(calls.get() + 1);
+            x.abs_diff(side / 2) + y.abs_diff(side - 1)
+        };
+        let options = SearchOptions::new();
+        let plain = astar_with_options(
+            &(3, 5, false),
+            successors(side),
+            |&(x, y, _)| inner(&(x, y)),
+            goal,
+            &options,
+        );
+        let plain_calls = calls.replace(0);
+
+        let mut memo = MemoizedHeuristic::new();
+        let memoized = astar_with_options(
+            &(3, 5, false),
+            successors(side),
+            memo.heuristic(|&(x, y, _)| (x, y), inner),
+            goal,
+            &options,
+        );
+        assert_eq!(memoized.0, plain.0);
+        assert_eq!(memoized.1.expanded, plain.1.expanded);
+        assert!(matches!(memoized.0, SearchOutcome::Found { cost: 33, .. }));
+        // Nodes with and without the key share their projection.
+        assert_eq!(calls.get(), memo.table().len());
+        assert_eq!(calls.get(), memo.misses());
+        assert_eq!(memo.hits() + memo.misses(), plain_calls);
+        assert!(memo.hits() > 0);
+
+        // The same table as a vector, by index of the cell.
+        let mut memo = MemoizedHeuristic::indexed(0);
+        calls.set(0);
+        let indexed = astar_with_options(
+            &(3, 5, false),
+            successors(side),
+            memo.heuristic(
+                |&(x, y, _)| (y * side + x) as usize,
+                |&i| inner(&(i as u32 % side, i as u32 / side)),
+            ),
+            goal,
+            &options,
+        );
+        assert_eq!(indexed.0, plain.0);
+        assert_eq!(calls.get(), memo.table().iter().flatten().count());
+        assert_eq!(calls.get(), memo.misses());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn memoized_json() {
+        let side = 12;
+        let goal = |&(x, y, key): &State| key && (x, y) == (side - 1, side - 1);
+        let inner = |&(x, y): &(u32, u32)| x.abs_diff(side - 1) + y.abs_diff(side - 1);
+        let options = SearchOptions::new();
+        let mut memo = MemoizedHeuristic::new();
+        let first = astar_with_options(
+            &(2, 2, false),
+            successors(side),
+            memo.heuristic(|&(x, y, _)| y * side + x, |&i| inner(&(i % side, i / side))),
+            goal,
+            &options,
+        );
+        let json = serde_json::to_string(&memo).unwrap();
+
+        let mut memo: MemoizedHeuristic<u32, u32> = serde_json::from_str(&json).unwrap();
+        let second = astar_with_options(
+            &(2, 2, false),
+            successors(side),
+            memo.heuristic(|&(x, y, _)| y * side + x, |_| unreachable!()),
+            goal,
+            &options,
+        );
+        assert_eq!(second.0, first.0);
+        assert_eq!(second.1.expanded, first.1.expanded);
+        assert_eq!(second.1.generated, first.1.generated);
+        assert_eq!(memo.misses(), 0);
+    }
+}
@@ -0,0 +1,216 @@
+use std::{ops::ControlFlow, time::Duration};
+
+use crate::{
+    astar::DefaultSearchHasher,
+    astar_progress::{ProgressCallback, ProgressInfo},
+};
+
+/// Options that influence an A* search, see
+/// [`astar_with_options`](crate::astar::astar_with_options).
+///
+/// The options are set using builder-style methods:
+/// ```
+/// # use ebi_optimisation::astar_options::SearchOptions;
+/// let options = SearchOptions::<u64>::new()
+///     .log_every(Some(100_000))
+///     .upper_bound(Some(1000));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SearchOptions<C, S = DefaultSearchHasher> {
+    pub(crate) log_every: Option<u64>,
+    pub(crate) upper_bound: Option<C>,
+    pub(crate) tie_break: TieBreak,
+    pub(crate) hasher: S,
+    pub(crate) expected_states: Option<usize>,
+    pub(crate) max_expansions: Option<u64>,
+    pub(crate) time_limit: Option<Duration>,
+    pub(crate) record_search_graph: bool,
+    pub(crate) max_path_len: Option<usize>,
+    pub(crate) reopening: Reopening,
+    pub(crate) on_progress: Option<ProgressCallback<C>>,
+}
+
+impl<C> Default for SearchOptions<C> {
+    fn default() -> Self {
+        SearchOptions {
+            log_every: None,
+            upper_bound: None,
+            tie_break: TieBreak::default(),
+            hasher: DefaultSearchHasher::default(),
+            expected_states: None,
+            max_expansions: None,
+            time_limit: None,
+            record_search_graph: false,
+            max_path_len: None,
+            reopening: Reopening::default(),
+            on_progress: None,
+        }
+    }
+}
+
+/// Which node an A* search expands first among those of the same estimated cost. For a given
+/// policy, the order in which the nodes are expanded only depends on the successors and the
+/// heuristic: the remaining ties are broken by the order in which the nodes entered the open
+/// list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum TieBreak {
+    /// The node with the highest cost so far, first in first out among those. As its
+    /// heuristic is the lowest, it is likely closer to the goal.
+    #[default]
+    PreferHighG,
+    /// The node with the lowest cost so far, first in first out among those.
+    PreferLowG,
+    /// The node that entered the open list first.
+    Fifo,
+    /// The node that entered the open list last.
+    Lifo,
+}
+
+/// Whether an A* search expands a node again when it finds a cheaper path to it after
+/// expanding it, which only happens if the heuristic is not consistent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Reopening {
+    /// Expand each node at most once, ignoring the cheaper paths found later to the expanded
+    /// nodes. The path found is the shortest one if the heuristic is consistent; with a
+    /// heuristic that is only admissible, it may not be.
+    Forbid,
+    /// Expand a node again each time a cheaper path to it is found. The path found is the
+    /// shortest one if the heuristic is admissible, but a node may be expanded many times:
+    /// exponentially many in the number of nodes in the worst case.
+    #[default]
+    Allow,
+    /// Expand a node again when a cheaper path to it is found, at most the given number of
+    /// times. Each node is expanded at most that number plus one times. The path found is the
+    /// shortest one if the heuristic is consistent, or if it is admissible and no node needs
+    /// more re-expansions; otherwise, it may not be.
+    BoundedBy(usize),
+}
+
+impl Reopening {
+    /// The number of times a node may be expanded again, `None` if unbounded.
+    pub(crate) fn max_reexpansions(self) -> Option<usize> {
+        match self {
+            Reopening::Forbid => Some(0),
+            Reopening::Allow => None,
+            Reopening::BoundedBy(max) => Some(max),
+        }
+    }
+}
+
+impl<C> SearchOptions<C> {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<C, S> SearchOptions<C, S> {
+    /// Log a debug record every `expansions` expanded nodes, with the counters so far and the
+    /// priority of the last expanded node. Disabled by default.
+    pub fn log_every(mut self, expansions: Option<u64>) -> Self {
+        self.log_every = expansions.filter(|&expansions| expansions > 0);
+        self
+    }
+
+    /// Only search for paths of a cost of at most `bound`, typically the cost of a known
+    /// path: successors whose cost plus heuristic exceeds it are left out of the open list.
+    /// If no path is found then, the search returns
+    /// [`SearchOutcome::NoPathWithinBound`](crate::astar::SearchOutcome::NoPathWithinBound).
+    /// With an admissible heuristic, a path of a cost of at most `bound` is still found if
+    /// there is one. Unbounded by default.
+    pub fn upper_bound(mut self, bound: Option<C>) -> Self {
+        self.upper_bound = bound;
+        self
+    }
+
+    /// How to choose among the nodes of the same estimated cost. [`TieBreak::PreferHighG`] by
+    /// default.
+    pub fn tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    /// Hash the nodes with `hasher` rather than with [`DefaultSearchHasher`], an FxHash, for
+    /// instance one better suited to large nodes. The hasher does not influence the order in
+    /// which the nodes are expanded.
+    pub fn hasher<S2>(self, hasher: S2) -> SearchOptions<C, S2> {
+        SearchOptions {
+            log_every: self.log_every,
+            upper_bound: self.upper_bound,
+            tie_break: self.tie_break,
+            hasher,
+            expected_states: self.expected_states,
+            max_expansions: self.max_expansions,
+            time_limit: self.time_limit,
+            record_search_graph: self.record_search_graph,
+            max_path_len: self.max_path_len,
+            reopening: self.reopening,
+            on_progress: self.on_progress,
+        }
+    }
+
+    /// Reserve room for `states` nodes up front, if the size of the search is known
+    /// approximately. Unset by default.
+    pub fn expected_states(mut self, states: Option<usize>) -> Self {
+        self.expected_states = states;
+        self
+    }
+
+    /// Give up after `expansions` expanded nodes: the search then returns
+    /// [`SearchOutcome::Aborted`](crate::astar::SearchOutcome::Aborted). Unlimited by default.
+    pub fn max_expansions(mut self, expansions: Option<u64>) -> Self {
+        self.max_expansions = expansions;
+        self
+    }
+
+    /// Give up once the search has run for `limit`: the search then returns
+    /// [`SearchOutcome::Aborted`](crate::astar::SearchOutcome::Aborted). The time is checked
+    /// before each expansion, so a slow `successors` may overrun it by one call. Unlimited by
+    /// default.
+    pub fn time_limit(mut self, limit: Option<Duration>) -> Self {
+        self.time_limit = limit;
+        self
+    }
+
+    /// Record the nodes expanded and the moves generated by the searches of a
+    /// [`Searcher`](crate::astar::Searcher), for
+    /// [`Searcher::search_graph`](crate::astar::Searcher::search_graph). This costs memory
+    /// and time, for debugging on small searches. Disabled by default.
+    pub fn record_search_graph(mut self, record: bool) -> Self {
+        self.record_search_graph = record;
+        self
+    }
+
+    /// Only search for paths of at most `moves` moves, for
+    /// [`astar_with_options`](crate::astar::astar_with_options): the shortest path among those
+    /// is returned. If there is none, the search returns
+    /// [`SearchOutcome::NoPathWithinBound`](crate::astar::SearchOutcome::NoPathWithinBound).
+    /// The search tells apart the paths to a node by their number of moves, so it may hold up
+    /// to `moves` + 1 copies of each node. Unbounded by default.
+    pub fn max_path_len(mut self, moves: Option<usize>) -> Self {
+        self.max_path_len = moves;
+        self
+    }
+
+    /// Whether to expand a node again when a cheaper path to it is found, see [`Reopening`],
+    /// for heuristics that are admissible but not consistent. The re-expansions are counted
+    /// in [`SearchStats::reexpanded`](crate::astar_stats::SearchStats::reexpanded).
+    /// [`Reopening::Allow`] by default.
+    pub fn reopening(mut self, reopening: Reopening) -> Self {
+        self.reopening = reopening;
+        self
+    }
+
+    /// Call `callback` every `expansions` expanded nodes, before the next expansion, for
+    /// instance to show the progress of a long search. If the callback returns
+    /// [`ControlFlow::Break`], the search stops and returns
+    /// [`SearchOutcome::Aborted`](crate::astar::SearchOutcome::Aborted). Unset by default.
+    pub fn on_progress(
+        mut self,
+        expansions: u64,
+        callback: impl FnMut(&ProgressInfo<C>) -> ControlFlow<()> + Send + 'static,
+    ) -> Self {
+        self.on_progress = Some(ProgressCallback::new(expansions, Box::new(callback)));
+        self
+    }
+}
@@ -0,0 +1,371 @@
+//! Hash-distributed A* (HDA*): an A* search on several threads, each of which owns the nodes
+//! of a share of the hashes, for large graphs of cheap nodes.
+
+use std::{
+    collections::BinaryHeap,
+    hash::{BuildHasher, Hash},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+    time::Duration,
+};
+
+use ebi_arithmetic::ebi_number::Zero;
+use indexmap::map::Entry::{Occupied, Vacant};
+use log::debug;
+use parking_lot::Mutex;
+
+use crate::{
+    astar::{Cost, DefaultSearchHasher, FxIndexMap, SmallestCostHolder},
+    astar_options::TieBreak,
+};
+
+/// How long an idle thread waits for nodes before it checks whether the search is over.
+const IDLE_WAIT: Duration = Duration::from_micros(100);
+
+/// Search a shortest path like [`astar`](crate::astar::astar) on `threads` threads.
+///
+/// Each node belongs to the thread given by its hash, which keeps the cost of the best path to
+/// it and its open list. A thread expands the nodes of its open list and sends each successor
+/// to the thread it belongs to. A goal taken from an open list becomes the incumbent if it is
+/// cheaper than the one so far; the nodes whose cost plus heuristic is not below the incumbent
+/// are left out. The search ends when no thread has any other node to expand and no node is on
+/// its way between threads.
+///
+/// As a node may be expanded before the cheapest path to it is known, it is expanded again
+/// when a cheaper one is found, so the path returned is a shortest one with an admissible
+/// heuristic; with a consistent one, this seldom happens. Which of several shortest paths is
+/// returned depends on the scheduling of the threads.
+///
+/// ```
+/// # use ebi_optimisation::astar_parallel::astar_parallel;
+/// let successors = |&n: &u32| [(n + 1, 1u32), (n * 2, 1)];
+/// let (_, cost) = astar_parallel(&1, successors, |_| 0, |&n| n == 100, 4).unwrap();
+/// assert_eq!(cost, 8);
+/// ```
+///
+/// # Panics
+///
+/// If `threads` is zero, or if `successors`, `heuristic` or `success` panic.
+pub fn astar_parallel<N, C, FN, IN, FH, FS>(
+    start: &N,
+    successors: FN,
+    heuristic: FH,
+    success: FS,
+    threads: usize,
+) -> Option<(Vec<N>, C)>
+where
+    N: Eq + Hash + Clone + Send,
+    C: Cost + Send,
+    FN: Fn(&N) -> IN + Sync,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: Fn(&N) -> C + Sync,
+    FS: Fn(&N) -> bool + Sync,
+{
+    assert!(threads > 0, "a parallel search needs at least one thread");
+    let (senders, receivers): (Vec<_>, Vec<_>) = (0..threads).map(|_| mpsc::channel()).unzip();
+    let shared = Shared {
+        senders,
+        outstanding: AtomicUsize::new(1),
+        panicked: AtomicBool::new(false),
+        incumbent: Mutex::new(None),
+    };
+    shared.senders[owner(start, threads)]
+        .send(Message {
+            node: start.clone(),
+            parent: None,
+            cost: Zero::zero(),
+            depth: 0,
+        })
+        .unwrap(); // Cannot fail
+    let storages = thread::scope(|scope| {
+        let handles = (receivers.into_iter())
+            .map(|receiver| {
+                let (shared, successors, heuristic, success) =
+                    (&shared, &successors, &heuristic, &success);
+                scope.spawn(move || {
+                    let _guard = PanicGuard(&shared.panicked);
+                    Worker::new(shared, receiver).run(successors, heuristic, success)
+                })
+            })
+            .collect::<Vec<_>>();
+        (handles.into_iter())
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    debug!(
+        "parallel A*: done, nodes per thread: {:?}",
+        storages
+            .iter()
+            .map(|storage| storage.len())
+            .collect::<Vec<_>>()
+    );
+
+    let (goal, cost) = shared.incumbent.into_inner()?;
+    let mut path = vec![];
+    let mut node = Some(goal);
+    while let Some(n) = node {
+        node = storages[owner(&n, threads)][&n].0.clone();
+        path.push(n);
+    }
+    path.reverse();
+    Some((path, cost))
+}
+
+/// The thread that `node` belongs to.
+fn owner<N: Hash>(node: &N, threads: usize) -> usize {
+    let hash = DefaultSearchHasher::default().hash_one(node);
+    ((hash as u128 * threads as u128) >> 64) as usize
+}
+
+/// A node sent to the thread it belongs to, with its parent and the cost of the path to it.
+struct Message<N, C> {
+    node: N,
+    parent: Option<N>,
+    cost: C,
+    depth: usize,
+}
+
+/// The state of a search shared by its threads.
+struct Shared<N, C> {
+    senders: Vec<Sender<Message<N, C>>>,
+    /// The number of messages sent and not handled yet, plus the number of threads with nodes
+    /// to expand. Once it is zero, it remains so, and the search is over.
+    outstanding: AtomicUsize,
+    /// Whether a thread panicked, which ends the others.
+    panicked: AtomicBool,
+    /// The cheapest goal found so far, and its cost.
+    incumbent: Mutex<Option<(N, C)>>,
+}
+
+/// Sets its flag if the thread panics while it is alive.
+struct PanicGuard<'a>(&'a AtomicBool);
+
+impl Drop for PanicGuard<'_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.store(true, AtomicOrdering::SeqCst);
+        }
+    }
+}
+
+/// A thread of a search, with the nodes that belong to it.
+struct Worker<'a, N, C> {
+    shared: &'a Shared<N, C>,
+    receiver: Receiver<Message<N, C>>,
+    /// Per node, its parent and the cost of the best path to it found so far.
+    parents: FxIndexMap<N, (Option<N>, C)>,
+    to_see: BinaryHeap<SmallestCostHolder<C>>,
+    seq: usize,
+    /// Whether the thread counts as having nodes to expand in `outstanding`.
+    busy: bool,
+}
+
+impl<'a, N, C> Worker<'a, N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Cost,
+{
+    fn new(shared: &'a Shared<N, C>, receiver: Receiver<Message<N, C>>) -> Self {
+        Worker {
+            shared,
+            receiver,
+            parents: FxIndexMap::default(),
+            to_see: BinaryHeap::new(),
+            seq: 0,
+            busy: false,
+        }
+    }
+
+    /// Expand nodes until the search is over, and return the nodes of the thread.
+    fn run<FN, IN, FH, FS>(
+        mut self,
+        successors: &FN,
+        heuristic: &FH,
+        success: &FS,
+    ) -> FxIndexMap<N, (Option<N>, C)>
+    where
+        FN: Fn(&N) -> IN,
+        IN: IntoIterator<Item = (N, C)>,
+        FH: Fn(&N) -> C,
+        FS: Fn(&N) -> bool,
+    {
+        let shared = self.shared;
+        let threads = shared.senders.len();
+        while !shared.panicked.load(AtomicOrdering::SeqCst) {
+            while let Ok(message) = self.receiver.try_recv() {
+                self.receive(message, heuristic);
+            }
+
+            // Entries not below the incumbent are left out, and will remain so as it only
+            // decreases.
+            let bound = (shared.incumbent.lock().as_ref()).map(|(_, cost)| cost.clone());
+            let holder = (self.to_see.pop()).filter(|holder| {
+                (bound.as_ref()).is_none_or(|bound| &holder.estimated_cost < bound)
+            });
+            let Some(SmallestCostHolder {
+                cost, index, depth, ..
+            }) = holder
+            else {
+                self.to_see.clear();
+                if self.busy {
+                    self.busy = false;
+                    shared.outstanding.fetch_sub(1, AtomicOrdering::SeqCst);
+                }
+                if shared.outstanding.load(AtomicOrdering::SeqCst) == 0 {
+                    break;
+                }
+                if let Ok(message) = self.receiver.recv_timeout(IDLE_WAIT) {
+                    self.receive(message, heuristic);
+                }
+                continue;
+            };
+
+            let (node, (_, c)) = self.parents.get_index(index).unwrap(); // Cannot fail
+            if &cost > c {
+                continue;
+            }
+            if success(node) {
+                let mut incumbent = shared.incumbent.lock();
+                if incumbent.as_ref().is_none_or(|(_, best)| &cost < best) {
+                    *incumbent = Some((node.clone(), cost));
+                }
+                continue;
+            }
+            for (successor, move_cost) in successors(node) {
+                if move_cost.is_forbidden() {
+                    continue;
+                }
+                let mut new_cost = cost.clone();
+                new_cost += move_cost;
+                shared.outstanding.fetch_add(1, AtomicOrdering::SeqCst);
+                // The receiver lives until `outstanding` is zero.
+                shared.senders[owner(&successor, threads)]
+                    .send(Message {
+                        node: successor,
+                        parent: Some(node.clone()),
+                        cost: new_cost,
+                        depth: depth + 1,
+                    })
+                    .unwrap(); // Cannot fail
+            }
+        }
+        self.parents
+    }
+
+    /// Record the path to a node of the thread if it is cheaper than the best one known, and
+    /// put the node on the open list if so.
+    fn receive<FH>(&mut self, message: Message<N, C>, heuristic: &FH)
+    where
+        FH: Fn(&N) -> C,
+    {
+        let Message {
+            node,
+            parent,
+            cost,
+            depth,
+        } = message;
+        let index = match self.parents.entry(node) {
+            Occupied(e) if e.get().1 <= cost => None,
+            Occupied(mut e) => {
+                e.insert((parent, cost.clone()));
+                Some(e.index())
+            }
+            Vacant(e) => {
+                let index = e.index();
+                e.insert((parent, cost.clone()));
+                Some(index)
+            }
+        };
+        if let Some(index) = index {
+            let (node, _) = self.parents.get_index(index).unwrap(); // Cannot fail
+            let mut estimated_cost = cost.clone();
+            estimated_cost += heuristic(node);
+            self.seq += 1;
+            self.to_see.push(SmallestCostHolder {
+                estimated_cost,
+                cost,
+                index,
+                depth,
+                seq: self.seq,
+                tie_break: TieBreak::default(),
+            });
+            if !self.busy {
+                self.busy = true;
+                self.shared.outstanding.fetch_add(1, AtomicOrdering::SeqCst);
+            }
+        }
+        // The message is handled, after the thread counts as busy if it has to be.
+        self.shared.outstanding.fetch_sub(1, AtomicOrdering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        astar::{astar, tests::random_graph},
+        rand::{Rng, SeedableRng, rngs::StdRng},
+    };
+
+    #[test]
+    fn parallel_random_graphs() {
+        for seed in 0..20 {
+            let graph = random_graph(seed);
+            let successors = |n: &usize| graph.successors(n);
+            let success = |&n: &usize| n == graph.goal;
+            let sequential = astar(&0, successors, |_| 0, success).map(|(_, cost)| cost);
+            for threads in [1, 2, 4] {
+                let parallel = astar_parallel(&0, successors, |_| 0, success, threads);
+                if let Some((path, cost)) = &parallel {
+                    assert_eq!(path.first(), Some(&0));
+                    assert_eq!(path.last(), Some(&graph.goal));
+                    assert_eq!(&graph.path_cost(path), cost);
+                }
+                assert_eq!(parallel.map(|(_, cost)| cost), sequential, "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_stress() {
+        // A 100 × 100 grid whose cells cost from 1 to 9 to enter.
+        let side = 100;
+        let mut rng = StdRng::seed_from_u64(7060);
+        let costs = (0..side * side)
+            .map(|_| rng.random_range(1..10u32))
+            .collect::<Vec<_>>();
+        let successors = |&(x, y): &(usize, usize)| {
+            let mut succs = vec![];
+            if x > 0 {
+                succs.push((x - 1, y));
+            }
+            if x + 1 < side {
+                succs.push((x + 1, y));
+            }
+            if y > 0 {
+                succs.push((x, y - 1));
+            }
+            if y + 1 < side {
+                succs.push((x, y + 1));
+            }
+            succs
+                .into_iter()
+                .map(|(x, y)| ((x, y), costs[y * side + x]))
+                .collect::<Vec<_>>()
+        };
+        let goal = (side - 1, side - 1);
+        let heuristic = |&(x, y): &(usize, usize)| (goal.0 - x + goal.1 - y) as u32;
+        let success = |&cell: &(usize, usize)| cell == goal;
+        let optimum = astar(&(0, 0), successors, heuristic, success).unwrap().1;
+        for _ in 0..20 {
+            let (_, cost) = astar_parallel(&(0, 0), successors, heuristic, success, 4).unwrap();
+            assert_eq!(cost, optimum);
+        }
+        // Without a path, the search ends once every node is expanded.
+        let outcome = astar_parallel(&(0, 0), successors, heuristic, |_| false, 4);
+        assert_eq!(outcome, None);
+    }
+}
@@ -0,0 +1,65 @@
+//! Reporting the progress of an A* search to the caller.
+
+use std::{fmt::Debug, ops::ControlFlow, sync::Arc, time::Duration};
+
+use parking_lot::Mutex;
+
+/// The state of an A* search, before it expands its next node.
+#[derive(Debug)]
+pub struct ProgressInfo<C> {
+    /// Number of nodes expanded so far.
+    pub expanded: usize,
+    /// Number of entries in the open list, including the node about to be expanded.
+    pub open_len: usize,
+    /// The smallest cost plus heuristic of the open list and of the successors left out: a
+    /// lower bound on the cost of a shortest path if the heuristic is admissible.
+    pub min_f: C,
+    /// The upper bound of the search, typically the cost of a known path, see
+    /// [`SearchOptions::upper_bound`](crate::astar_options::SearchOptions::upper_bound).
+    pub incumbent: Option<C>,
+    /// Time since the search started.
+    pub elapsed: Duration,
+}
+
+type Callback<C> = Box<dyn FnMut(&ProgressInfo<C>) -> ControlFlow<()> + Send>;
+
+/// A shared handle to the callback set with
+/// [`SearchOptions::on_progress`](crate::astar_options::SearchOptions::on_progress), with
+/// the number of expansions between its calls.
+pub(crate) struct ProgressCallback<C> {
+    pub(crate) every: u64,
+    callback: Arc<Mutex<Callback<C>>>,
+}
+
+impl<C> ProgressCallback<C> {
+    pub(crate) fn new(every: u64, callback: Callback<C>) -> Self {
+        Self {
+            every: every.max(1),
+            callback: Arc::new(Mutex::new(callback)),
+        }
+    }
+
+    /// Whether the callback is due after `expanded` expansions.
+    pub(crate) fn due(&self, expanded: usize) -> bool {
+        expanded > 0 && (expanded as u64).is_multiple_of(self.every)
+    }
+
+    pub(crate) fn call(&self, info: &ProgressInfo<C>) -> ControlFlow<()> {
+        (self.callback.lock())(info)
+    }
+}
+
+impl<C> Clone for ProgressCallback<C> {
+    fn clone(&self) -> Self {
+        Self {
+            every: self.every,
+            callback: Arc::clone(&self.callback),
+        }
+    }
+}
+
+impl<C> Debug for ProgressCallback<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ProgressCallback(every {})", self.every)
+    }
+}
@@ -0,0 +1,344 @@
+//! The nodes and moves of an A* search, recorded with
+//! [`SearchOptions::record_search_graph`](crate::astar_options::SearchOptions::record_search_graph),
+//! and their export to DOT and GraphML for visualisation.
+
+use std::io::{self, Write};
+
+use crate::astar::{FxIndexMap, NodeStorage};
+
+/// A node of a [`SearchGraph`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchGraphNode<N> {
+    pub node: N,
+    /// The number of expansions before the first one of this node, if it was expanded.
+    pub expansion: Option<usize>,
+    /// Whether the node is on the path found.
+    pub on_path: bool,
+}
+
+/// A move generated in a search, between the nodes of a [`SearchGraph`] with indices `from`
+/// and `to`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchGraphEdge<C> {
+    pub from: usize,
+    pub to: usize,
+    /// The cost of the move.
+    pub cost: C,
+    /// The cost of the path to `to` through this move.
+    pub g: C,
+    /// The heuristic of `to`, or `None` if a path to it of at most `g` was known, so that the
+    /// move was skipped.
+    pub h: Option<C>,
+    /// Whether the move is on the path found.
+    pub on_path: bool,
+}
+
+/// The nodes reached and the moves generated by a search, see
+/// [`Searcher::search_graph`](crate::astar::Searcher::search_graph).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchGraph<N, C> {
+    /// The nodes, in the order in which they were reached, starting with the start node.
+    pub nodes: Vec<SearchGraphNode<N>>,
+    /// The moves, in the order in which they were generated.
+    pub edges: Vec<SearchGraphEdge<C>>,
+}
+
+impl<N, C> SearchGraph<N, C>
+where
+    C: std::fmt::Debug,
+{
+    /// Write the graph in the DOT language of Graphviz, with the nodes labelled by `label`.
+    /// The expanded nodes are filled in blue, lighter for the early expansions and darker
+    /// for the late ones, and the path found is drawn in red.
+    ///
+    /// # Errors
+    ///
+    /// The errors of writing to `w`.
+    pub fn write_dot<W: Write>(
+        &self,
+        mut w: W,
+        mut label: impl FnMut(&N) -> String,
+    ) -> io::Result<()> {
+        let last_expansion = self.last_expansion();
+        writeln!(w, "digraph search {{")?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            let fill = match node.expansion {
+                Some(expansion) => {
+                    let saturation = 0.1 + 0.9 * expansion as f64 / last_expansion.max(1) as f64;
+                    format!("0.600 {:.3} 1.000", saturation)
+                }
+                None => "white".to_string(),
+            };
+            write!(
+                w,
+                "    n{} [label=\"{}\", style=filled, fillcolor=\"{}\"",
+                i,
+                escape_dot(&label(&node.node)),
+                fill
+            )?;
+            if node.on_path {
+                write!(w, ", color=red, penwidth=2")?;
+            }
+            writeln!(w, "];")?;
+        }
+        for edge in &self.edges {
+            write!(
+                w,
+                "    n{} -> n{} [label=\"{}\"",
+                edge.from,
+                edge.to,
+                escape_dot(&edge_label(edge))
+            )?;
+            if edge.on_path {
+                write!(w, ", color=red, penwidth=2")?;
+            }
+            writeln!(w, "];")?;
+        }
+        writeln!(w, "}}")
+    }
+
+    /// Write the graph in GraphML, with the nodes labelled by `label`, and with the
+    /// expansion order, the costs and whether they are on the path found as data.
+    ///
+    /// # Errors
+    ///
+    /// The errors of writing to `w`.
+    pub fn write_graphml<W: Write>(
+        &self,
+        mut w: W,
+        mut label: impl FnMut(&N) -> String,
+    ) -> io::Result<()> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        for (id, domain, name, kind) in [
+            ("label", "node", "label", "string"),
+            ("expansion", "node", "expansion", "int"),
+            ("node_on_path", "node", "on_path", "boolean"),
+            ("cost", "edge", "cost", "string"),
+            ("g", "edge", "g", "string"),
+            ("h", "edge", "h", "string"),
+            ("edge_on_path", "edge", "on_path", "boolean"),
+        ] {
+            writeln!(
+                w,
+                r#"  <key id="{}" for="{}" attr.name="{}" attr.type="{}"/>"#,
+                id, domain, name, kind
+            )?;
+        }
+        writeln!(w, r#"  <graph id="search" edgedefault="directed">"#)?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            write!(
+                w,
+                r#"    <node id="n{}"><data key="label">{}</data>"#,
+                i,
+                escape_xml(&label(&node.node))
+            )?;
+            if let Some(expansion) = node.expansion {
+                write!(w, r#"<data key="expansion">{}</data>"#, expansion)?;
+            }
+            writeln!(
+                w,
+                r#"<data key="node_on_path">{}</data></node>"#,
+                node.on_path
+            )?;
+        }
+        for edge in &self.edges {
+            let h = match &edge.h {
+                Some(h) => format!("{:?}", h),
+                None => "-".to_string(),
+            };
+            writeln!(
+                w,
+                r#"    <edge source="n{}" target="n{}"><data key="cost">{}</data><data key="g">{}</data><data key="h">{}</data><data key="edge_on_path">{}</data></edge>"#,
+                edge.from,
+                edge.to,
+                escape_xml(&format!("{:?}", edge.cost)),
+                escape_xml(&format!("{:?}", edge.g)),
+                escape_xml(&h),
+                edge.on_path
+            )?;
+        }
+        writeln!(w, "  </graph>")?;
+        writeln!(w, "</graphml>")
+    }
+
+    fn last_expansion(&self) -> usize {
+        (self.nodes.iter())
+            .filter_map(|node| node.expansion)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn edge_label<C: std::fmt::Debug>(edge: &SearchGraphEdge<C>) -> String {
+    match &edge.h {
+        Some(h) => format!("{:?} (g {:?}, h {:?})", edge.cost, edge.g, h),
+        None => format!("{:?} (g {:?}, skipped)", edge.cost, edge.g),
+    }
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The record of a search, by the indices of the nodes in its storage.
+pub(crate) struct GraphRecorder<C> {
+    start: usize,
+    /// The expanded nodes, in the order of expansion.
+    expanded: Vec<usize>,
+    edges: Vec<SearchGraphEdge<C>>,
+    /// The end of the path found, if any.
+    path_end: Option<usize>,
+}
+
+impl<C> Default for GraphRecorder<C> {
+    fn default() -> Self {
+        GraphRecorder {
+            start: 0,
+            expanded: vec![],
+            edges: vec![],
+            path_end: None,
+        }
+    }
+}
+
+impl<C: Clone + PartialEq> GraphRecorder<C> {
+    pub(crate) fn reset(&mut self, start: usize) {
+        self.clear();
+        self.start = start;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.expanded.clear();
+        self.edges.clear();
+        self.path_end = None;
+    }
+
+    pub(crate) fn expanded(&mut self, index: usize) {
+        self.expanded.push(index);
+    }
+
+    pub(crate) fn edge(&mut self, from: usize, to: usize, cost: C, g: C, h: Option<C>) {
+        self.edges.push(SearchGraphEdge {
+            from,
+            to,
+            cost,
+            g,
+            h,
+            on_path: false,
+        });
+    }
+
+    pub(crate) fn found(&mut self, end: usize) {
+        self.path_end = Some(end);
+    }
+
+    /// The graph of the search, whose nodes are in `storage`.
+    pub(crate) fn build<M>(&self, storage: &M) -> SearchGraph<M::Node, C>
+    where
+        M: NodeStorage<C>,
+        M::Node: Clone,
+    {
+        // Per node reached, by its index in the storage, its first expansion.
+        let mut nodes: FxIndexMap<usize, Option<usize>> = FxIndexMap::default();
+        nodes.insert(self.start, None);
+        for edge in &self.edges {
+            nodes.entry(edge.to).or_insert(None);
+        }
+        for (expansion, index) in self.expanded.iter().enumerate() {
+            let first = nodes.entry(*index).or_insert(None);
+            if first.is_none() {
+                *first = Some(expansion);
+            }
+        }
+        let mut path: FxIndexMap<usize, ()> = FxIndexMap::default();
+        let mut i = self.path_end.unwrap_or(usize::MAX);
+        while i != usize::MAX {
+            path.insert(i, ());
+            i = storage.parent_and_cost(i).0;
+        }
+
+        let edges = (self.edges.iter())
+            .map(|edge| {
+                let on_path = path.contains_key(&edge.to) && {
+                    let (parent, cost) = storage.parent_and_cost(edge.to);
+                    parent == edge.from && cost == &edge.g
+                };
+                SearchGraphEdge {
+                    from: nodes.get_index_of(&edge.from).unwrap(), // Cannot fail
+                    to: nodes.get_index_of(&edge.to).unwrap(),     // Cannot fail
+                    on_path,
+                    ..edge.clone()
+                }
+            })
+            .collect();
+        let nodes = (nodes.iter())
+            .map(|(index, expansion)| SearchGraphNode {
+                node: storage.node(index).clone(),
+                expansion: *expansion,
+                on_path: path.contains_key(index),
+            })
+            .collect();
+        SearchGraph { nodes, edges }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{astar::Searcher, astar_options::SearchOptions};
+
+    #[test]
+    fn search_graph_dot() {
+        // The shortest path 0 -> 1 -> 2 -> 3 costs 3.
+        let successors = |&n: &u32| match n {
+            0 => vec![(1, 1u32), (2, 4)],
+            1 => vec![(2, 1), (3, 5)],
+            2 => vec![(3, 1)],
+            _ => vec![],
+        };
+        let mut searcher = Searcher::with_options(&SearchOptions::new().record_search_graph(true));
+        let (path, _) = searcher.search(&0, successors, |_| 0, |&n| n == 3).unwrap();
+        let graph = searcher.search_graph().unwrap();
+        assert_eq!(graph.nodes.len(), 4);
+        // 0 -> 1, 0 -> 2, 1 -> 2, 1 -> 3 and 2 -> 3.
+        assert_eq!(graph.edges.len(), 5);
+        let on_path = (graph.nodes.iter())
+            .filter(|node| node.on_path)
+            .map(|node| node.node)
+            .collect::<Vec<_>>();
+        assert_eq!(on_path, path);
+
+        let mut dot = vec![];
+        graph
+            .write_dot(&mut dot, |n| format!("state {}", n))
+            .unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        let edges = dot.lines().filter(|line| line.contains("->"));
+        assert_eq!(edges.clone().count(), 5);
+        let nodes = dot.lines().filter(|line| line.contains("[label=\"state"));
+        assert_eq!(nodes.count(), 4);
+        let path_edges = edges
+            .filter(|line| line.contains("color=red"))
+            .map(|line| line.trim().split(" [").next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(path_edges, vec!["n0 -> n1", "n1 -> n2", "n2 -> n3"]);
+
+        let mut graphml = vec![];
+        graph
+            .write_graphml(&mut graphml, |n| n.to_string())
+            .unwrap();
+        let graphml = String::from_utf8(graphml).unwrap();
+        assert_eq!(graphml.matches("<node ").count(), 4);
+        assert_eq!(graphml.matches("<edge ").count(), 5);
+    }
+}
@@ -0,0 +1,344 @@
+//! A* searches from the same start that reuse the part of the graph explored by the earlier
+//! ones, such as alignments of traces with a common prefix against the same model.
+
+use std::{collections::BinaryHeap, hash::Hash, time::Instant};
+
+use ebi_arithmetic::ebi_number::Zero;
+use indexmap::map::Entry::Vacant;
+use log::debug;
+
+use crate::{
+    astar::{Cost, FxIndexMap, NodeStorage, SmallestCostHolder},
+    astar_options::TieBreak,
+    astar_stats::SearchStats,
+};
+
+/// The nodes of a shared component of the graphs of several searches from the same start,
+/// settled by the earlier searches, with their distance from the start.
+///
+/// The shared component is given per search by a projection of the nodes to keys, `None` for
+/// the nodes outside the component; for alignments of traces with a common prefix, the
+/// states whose trace position is within the prefix. The moves from the nodes of the
+/// component and their costs must be the same in all searches, which the caller asserts on
+/// each search: otherwise, the cache is cleared first. The heuristics must be consistent, so
+/// that the nodes expanded are settled.
+///
+/// A search starts from the settled nodes that may lead elsewhere than to other settled
+/// nodes, with their distance from the start, rather than from the start, so it does not
+/// expand the nodes within the settled part again.
+pub struct SharedExploration<K, N, C> {
+    start: Option<N>,
+    /// The settled nodes by their key, each after its parent.
+    settled: FxIndexMap<K, SettledNode<N, C>>,
+}
+
+struct SettledNode<N, C> {
+    node: N,
+    /// The index of the parent in the settled nodes, `usize::MAX` for the start.
+    parent: usize,
+    /// The distance from the start.
+    cost: C,
+    depth: usize,
+    /// Whether all the successors are settled as well.
+    interior: bool,
+}
+
+impl<K, N, C> Default for SharedExploration<K, N, C> {
+    fn default() -> Self {
+        SharedExploration {
+            start: None,
+            settled: FxIndexMap::default(),
+        }
+    }
+}
+
+impl<K, N, C> SharedExploration<K, N, C>
+where
+    K: Eq + Hash,
+    N: Eq + Hash + Clone,
+    C: Cost,
+{
+    /// An empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of settled nodes.
+    pub fn len(&self) -> usize {
+        self.settled.len()
+    }
+
+    /// Whether no node is settled.
+    pub fn is_empty(&self) -> bool {
+        self.settled.is_empty()
+    }
+
+    /// Forget the settled nodes.
+    pub fn clear(&mut self) {
+        self.start = None;
+        self.settled.clear();
+    }
+
+    /// Search a shortest path like [`astar`](crate::astar::astar), from the settled nodes of
+    /// the earlier searches, and settle the nodes of the shared component given by `key` that
+    /// the search expands. Unless `costs_unchanged` holds and the start is that of the earlier
+    /// searches, the cache is cleared first.
+    ///
+    /// Also returns the counters of the search, which do not count the nodes of the cache.
+    pub fn search<FN, IN, FH, FS, FK>(
+        &mut self,
+        start: &N,
+        mut successors: FN,
+        mut heuristic: FH,
+        mut success: FS,
+        mut key: FK,
+        costs_unchanged: bool,
+    ) -> (Option<(Vec<N>, C)>, SearchStats)
+    where
+        FN: FnMut(&N) -> IN,
+        IN: IntoIterator<Item = (N, C)>,
+        FH: FnMut(&N) -> C,
+        FS: FnMut(&N) -> bool,
+        FK: FnMut(&N) -> Option<K>,
+    {
+        let start_time = Instant::now();
+        if !costs_unchanged || self.start.as_ref() != Some(start) {
+            self.clear();
+            self.start = Some(start.clone());
+        }
+        let mut stats = SearchStats::default();
+
+        // The settled nodes come first, at the same indices.
+        let mut parents: FxIndexMap<N, (usize, C)> = FxIndexMap::default();
+        for settled in self.settled.values() {
+            parents.insert(settled.node.clone(), (settled.parent, settled.cost.clone()));
+        }
+        let num_settled = parents.len();
+        if parents.is_empty() {
+            parents.insert(start.clone(), (usize::MAX, Zero::zero()));
+        }
+        let mut to_see = BinaryHeap::new();
+        for (index, (node, (_, cost))) in parents.iter().enumerate() {
+            let settled = self.settled.get_index(index).map(|(_, settled)| settled);
+            if settled.is_none_or(|settled| !settled.interior) || success(node) {
+                let mut estimated_cost = cost.clone();
+                estimated_cost += heuristic(node);
+                to_see.push(SmallestCostHolder {
+                    estimated_cost,
+                    cost: cost.clone(),
+                    index,
+                    depth: settled.map_or(0, |settled| settled.depth),
+                    seq: to_see.len(),
+                    tie_break: TieBreak::default(),
+                });
+            }
+        }
+        stats.max_open_len = to_see.len();
+        let mut seq = to_see.len();
+
+        // The expanded nodes of the shared component, with their key and the indices of
+        // their successors.
+        let mut expanded = vec![];
+        let mut found = None;
+        while let Some(SmallestCostHolder {
+            cost, index, depth, ..
+        }) = to_see.pop()
+        {
+            let (mut record, successors) = {
+                let (node, (_, c)) = parents.get_index(index).unwrap(); // Cannot fail
+                if &cost > c {
+                    stats.duplicates_skipped += 1;
+                    continue;
+                }
+                if success(node) {
+                    found = Some(index);
+                    break;
+                }
+                stats.expanded += 1;
+                stats.max_depth = stats.max_depth.max(depth);
+                (key(node).map(|key| (key, vec![])), successors(node))
+            };
+            for (successor, move_cost) in successors {
+                if move_cost.is_forbidden() {
+                    continue;
+                }
+                stats.generated += 1;
+                let mut new_cost = cost.clone();
+                new_cost += move_cost;
+                let relaxed = parents.relax(successor, index, new_cost.clone());
+                if let Some((_, successor_indices)) = &mut record {
+                    successor_indices.push(match relaxed {
+                        Ok(n) | Err(n) => n,
+                    });
+                }
+                let Ok(n) = relaxed else {
+                    stats.duplicates_skipped += 1;
+                    continue;
+                };
+                let mut estimated_cost = new_cost.clone();
+                estimated_cost += heuristic(parents.node(&n));
+                seq += 1;
+                to_see.push(SmallestCostHolder {
+                    estimated_cost,
+                    cost: new_cost,
+                    index: n,
+                    depth: depth + 1,
+                    seq,
+                    tie_break: TieBreak::default(),
+                });
+            }
+            if let Some((key, successor_indices)) = record {
+                expanded.push((index, key, successor_indices));
+            }
+            stats.max_open_len = stats.max_open_len.max(to_see.len());
+        }
+
+        let num_before = self.settled.len();
+        self.settle(&parents, num_settled, expanded);
+        stats.time = start_time.elapsed();
+        debug!(
+            "shared A*: done, {}, settled: {} (+{})",
+            stats,
+            self.settled.len(),
+            self.settled.len() - num_before
+        );
+        let path = found.map(|end| {
+            let mut path = vec![];
+            let mut i = end;
+            while i != usize::MAX {
+                let (node, (parent, _)) = parents.get_index(i).unwrap(); // Cannot fail
+                path.push(node.clone());
+                i = *parent;
+            }
+            path.reverse();
+            (path, parents[end].1.clone())
+        });
+        (path, stats)
+    }
+
+    /// Settle the `expanded` nodes of the shared component whose parent is settled, and mark
+    /// the nodes whose successors are all settled as interior. The first `num_settled` nodes
+    /// of `parents` are the settled ones.
+    fn settle(
+        &mut self,
+        parents: &FxIndexMap<N, (usize, C)>,
+        num_settled: usize,
+        expanded: Vec<(usize, K, Vec<usize>)>,
+    ) {
+        // Per node of `parents`, its index in the settled nodes.
+        let mut settled_index = (0..parents.len())
+            .map(|index| (index < num_settled).then_some(index))
+            .collect::<Vec<_>>();
+        // Expanded before their successors, parents come before their children.
+        let mut settled_expanded = vec![];
+        for (index, key, successor_indices) in expanded {
+            if settled_index[index].is_none() {
+                let (node, (parent, cost)) = parents.get_index(index).unwrap(); // Cannot fail
+                let (parent, depth) = match *parent {
+                    usize::MAX => (usize::MAX, 0),
+                    parent => match settled_index[parent] {
+                        Some(parent) => (parent, self.settled[parent].depth + 1),
+                        None => continue,
+                    },
+                };
+                let Vacant(e) = self.settled.entry(key) else {
+                    continue;
+                };
+                settled_index[index] = Some(e.index());
+                e.insert(SettledNode {
+                    node: node.clone(),
+                    parent,
+                    cost: cost.clone(),
+                    depth,
+                    interior: false,
+                });
+            }
+            settled_expanded.push((index, successor_indices));
+        }
+        for (index, successor_indices) in settled_expanded {
+            let interior = settled_index[index]
+                .filter(|_| (successor_indices.iter()).all(|&n| settled_index[n].is_some()));
+            if let Some(settled) = interior {
+                self.settled[settled].interior = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astar::product_successors;
+
+    /// The labels of a model with a move from each state to the next, and a move labelled x
+    /// from each state to the one after the next.
+    const MODEL: &[u8] = b"abcdefghijkl";
+
+    /// A state of an alignment: the positions in the model and in the trace.
+    type State = (usize, usize);
+
+    /// The successors of the alignment of `trace` against the model, with a cost of 1 per
+    /// move on the model or the trace only.
+    fn alignment(trace: &[u8]) -> impl FnMut(&State) -> Vec<(State, u32)> {
+        let model = |&m: &usize| {
+            let mut moves = vec![];
+            if m < MODEL.len() {
+                moves.push((MODEL[m], m + 1));
+            }
+            if m + 2 <= MODEL.len() {
+                moves.push((b'x', m + 2));
+            }
+            moves
+        };
+        let trace = |&t: &usize| trace.get(t).map(|&label| (label, t + 1));
+        let cost = |m: Option<&u8>, t: Option<&u8>| match (m, t) {
+            (Some(m), Some(t)) => (m == t).then_some(0u32),
+            _ => Some(1),
+        };
+        product_successors(model, trace, cost)
+    }
+
+    #[test]
+    fn shared_prefix() {
+        let (trace_1, trace_2) = (b"abcdefghijzzzz", b"abcdefghijlk");
+        // The states within the common prefix of the traces.
+        let shared = |&(m, t): &(usize, usize)| (t <= 10).then_some((m, t));
+        let goal_1 = |&state: &(usize, usize)| state == (MODEL.len(), trace_1.len());
+        let goal_2 = |&state: &(usize, usize)| state == (MODEL.len(), trace_2.len());
+
+        let mut exploration = SharedExploration::new();
+        let (first, _) =
+            exploration.search(&(0, 0), alignment(trace_1), |_| 0, goal_1, shared, true);
+        assert_eq!(first.unwrap().1, 5);
+        assert!(!exploration.is_empty());
+
+        let (fresh, fresh_stats) = SharedExploration::new().search(
+            &(0, 0),
+            alignment(trace_2),
+            |_| 0,
+            goal_2,
+            shared,
+            true,
+        );
+        let (reused, reused_stats) =
+            exploration.search(&(0, 0), alignment(trace_2), |_| 0, goal_2, shared, true);
+        let (fresh_path, fresh_cost) = fresh.unwrap();
+        let (reused_path, reused_cost) = reused.unwrap();
+        assert_eq!(fresh_cost, 2);
+        assert_eq!(reused_cost, fresh_cost);
+        assert_eq!(reused_path.first(), fresh_path.first());
+        assert_eq!(reused_path.last(), fresh_path.last());
+        assert!(
+            reused_stats.expanded < fresh_stats.expanded,
+            "reused: {}, fresh: {}",
+            reused_stats.expanded,
+            fresh_stats.expanded
+        );
+
+        // Without the assertion that the costs are unchanged, the search starts afresh.
+        let (cleared, cleared_stats) =
+            exploration.search(&(0, 0), alignment(trace_2), |_| 0, goal_2, shared, false);
+        assert_eq!(cleared.unwrap().1, fresh_cost);
+        assert_eq!(cleared_stats.expanded, fresh_stats.expanded);
+    }
+}
@@ -0,0 +1,474 @@
+//! Simplified memory-bounded A* (SMA*): A* within a fixed number of nodes, forgetting the
+//! least promising ones when memory is full.
+
+use std::{cmp::Reverse, collections::BTreeSet, time::Instant};
+
+use ebi_arithmetic::ebi_number::Zero;
+use log::debug;
+
+use crate::{astar::Cost, astar_stats::SearchStats};
+
+/// The result of [`smastar`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SmaOutcome<N, C> {
+    /// The path found to a node for which `success` holds, comprising both the start and end
+    /// node, and its cost.
+    pub best: Option<(Vec<N>, C)>,
+    /// Whether `best` is a shortest path, or if there is none, whether there is no path at
+    /// all. Paths of more nodes than fit in memory are cut off; if a cut off path may have led
+    /// to a cheaper goal, `best` is only the cheapest path that fits.
+    pub optimal: bool,
+    /// The largest number of nodes held at once, at most the budget of the search.
+    pub max_nodes_held: usize,
+    /// The counters of the search. A node is counted as expanded each time one of its
+    /// successors is generated.
+    pub stats: SearchStats,
+}
+
+/// Search a shortest path from `start` to a node for which `success` holds like
+/// [`astar`](crate::astar::astar), holding at most `max_nodes` nodes at once: between
+/// [`astar`](crate::astar::astar), which holds every node it reaches, and
+/// [`idastar`](crate::astar_iterative_deepening::idastar), which only holds the current path.
+///
+/// The nodes form a tree of the paths from `start`, which generates one successor at a time
+/// from the node of the smallest cost plus heuristic, the deepest first. When memory is full,
+/// the leaf of the largest cost plus heuristic, the shallowest first, is forgotten, and its
+/// estimate is backed up into its parent, which generates it again if the paths through it
+/// become the most promising ones. As a tree search, a node is held once per path to it, but
+/// never twice on the same path.
+///
+/// The path is a shortest one if the heuristic is admissible and a shortest path has fewer
+/// than `max_nodes` nodes. Otherwise, the paths of `max_nodes` nodes that do not reach a
+/// goal are cut off, and the search returns the cheapest path that fits, which
+/// [`SmaOutcome::optimal`] tells. The successors of a node must be returned in the same order
+/// each time, as they are generated again by their position.
+///
+/// # Panics
+///
+/// If `max_nodes` is zero.
+pub fn smastar<N, C, FN, IN, FH, FS>(
+    start: &N,
+    mut successors: FN,
+    mut heuristic: FH,
+    mut success: FS,
+    max_nodes: usize,
+) -> SmaOutcome<N, C>
+where
+    N: Eq + Clone,
+    C: Cost,
+    FN: FnMut(&N) -> IN,
+    IN: IntoIterator<Item = (N, C)>,
+    FH: FnMut(&N) -> C,
+    FS: FnMut(&N) -> bool,
+{
+    assert!(max_nodes > 0, "the memory of SMA* must hold a node");
+    let start_time = Instant::now();
+    let mut stats = SearchStats {
+        max_open_len: 1,
+        ..SearchStats::default()
+    };
+    let mut memory = Memory::default();
+    // The smallest cost plus heuristic of the nodes cut off at the maximum depth.
+    let mut lowest_cut_off: Option<C> = None;
+    let max_depth = max_nodes - 1;
+    let mut cut_off = |node: &N, depth: usize, f: C, success: &mut FS| {
+        if depth == max_depth && !success(node) {
+            if lowest_cut_off.as_ref().is_none_or(|lowest| &f < lowest) {
+                lowest_cut_off = Some(f);
+            }
+            Bound::Infinite
+        } else {
+            Bound::Finite(f)
+        }
+    };
+
+    let f = cut_off(start, 0, heuristic(start), &mut success);
+    memory.insert(SmaNode {
+        node: start.clone(),
+        parent: NONE,
+        position: 0,
+        cost: Zero::zero(),
+        f,
+        depth: 0,
+        children: None,
+        in_open: false,
+        in_leaves: false,
+    });
+    let found = loop {
+        let Some(&(Bound::Finite(_), _, best)) = memory.open.first() else {
+            break None;
+        };
+        if success(&memory.slots[best].as_ref().unwrap().node) {
+            break Some(best);
+        }
+
+        // Generate the first successor not generated yet, or else the most promising one of
+        // those forgotten.
+        let node = memory.slots[best].as_ref().unwrap(); // Cannot fail
+        if node.children.is_none() {
+            let children = (successors(&node.node).into_iter())
+                .map(|(successor, move_cost)| {
+                    if move_cost.is_forbidden() || memory.on_path(best, &successor) {
+                        Child::Pruned
+                    } else {
+                        Child::Ungenerated
+                    }
+                })
+                .collect::<Vec<_>>();
+            // Without successors, the node leads nowhere.
+            let dead_end = children.iter().all(|child| matches!(child, Child::Pruned));
+            memory.slot(best).children = Some(children);
+            if dead_end {
+                memory.update(best, Some(Bound::Infinite));
+                memory.back_up(memory.slots[best].as_ref().unwrap().parent);
+                continue;
+            }
+        }
+        let node = memory.slots[best].as_ref().unwrap(); // Cannot fail
+        let children = node.children.as_ref().unwrap(); // Cannot fail
+        let (position, forgotten) = match children
+            .iter()
+            .position(|child| matches!(child, Child::Ungenerated))
+        {
+            Some(position) => (position, None),
+            None => (children.iter().enumerate())
+                .filter_map(|(position, child)| match child {
+                    Child::Forgotten(f) => Some((position, f.clone())),
+                    _ => None,
+                })
+                .min_by(|(_, f_a), (_, f_b)| f_a.cmp(f_b))
+                .map(|(position, f)| (position, Some(f)))
+                .unwrap(), // Cannot fail: the node is on the open list
+        };
+        let (successor, move_cost) = (successors(&node.node).into_iter())
+            .nth(position)
+            .expect("the successors of a node must not change during a search");
+        stats.expanded += 1;
+        stats.generated += 1;
+        let mut cost = node.cost.clone();
+        cost += move_cost;
+        let depth = node.depth + 1;
+        stats.max_depth = stats.max_depth.max(depth);
+        let mut estimated_cost = cost.clone();
+        estimated_cost += heuristic(&successor);
+        // The estimate of a node is at least that of its parent, and that of its forgotten
+        // self.
+        let f = (cut_off(&successor, depth, estimated_cost, &mut success))
+            .max(node.f.clone())
+            .max(forgotten.unwrap_or(Bound::Finite(Zero::zero())));
+
+        if memory.len == max_nodes {
+            memory.forget_worst_leaf(best);
+        }
+        let child = memory.insert(SmaNode {
+            node: successor,
+            parent: best,
+            position,
+            cost,
+            f,
+            depth,
+            children: None,
+            in_open: false,
+            in_leaves: false,
+        });
+        let children = memory.slot(best).children.as_mut().unwrap(); // Cannot fail
+        children[position] = Child::InMemory(child);
+        memory.update(best, None);
+        memory.back_up(best);
+        stats.max_open_len = stats.max_open_len.max(memory.open.len());
+    };
+
+    let best = found.map(|end| {
+        let mut path = vec![];
+        let mut i = end;
+        while i != NONE {
+            let node = memory.slots[i].as_ref().unwrap(); // Cannot fail
+            path.push(node.node.clone());
+            i = node.parent;
+        }
+        path.reverse();
+        (path, memory.slots[end].as_ref().unwrap().cost.clone()) // Cannot fail
+    });
+    let optimal = match (&best, &lowest_cut_off) {
+        (_, None) => true,
+        (Some((_, cost)), Some(lowest)) => cost <= lowest,
+        (None, Some(_)) => false,
+    };
+    stats.time = start_time.elapsed();
+    debug!(
+        "SMA*: done, optimal: {}, max. nodes held: {}, {}",
+        optimal, memory.max_len, stats
+    );
+    SmaOutcome {
+        best,
+        optimal,
+        max_nodes_held: memory.max_len,
+        stats,
+    }
+}
+
+const NONE: usize = usize::MAX;
+
+/// An estimate of the cost of a path through a node: infinite for the nodes that lead to no
+/// goal within memory.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Bound<C> {
+    Finite(C),
+    Infinite,
+}
+
+/// A successor of a node, by its position among the successors.
+enum Child<C> {
+    Ungenerated,
+    InMemory(usize),
+    /// Forgotten, with its estimate then.
+    Forgotten(Bound<C>),
+    /// Forbidden, or on the path to the node.
+    Pruned,
+}
+
+struct SmaNode<N, C> {
+    node: N,
+    /// The slot of the parent, [`NONE`] for the start.
+    parent: usize,
+    /// The position of the node among the successors of its parent.
+    position: usize,
+    cost: C,
+    /// The estimate of the cost of a path through the node: its cost plus heuristic, or once
+    /// all its successors were generated, the smallest estimate of those.
+    f: Bound<C>,
+    depth: usize,
+    /// The successors, once the node was expanded.
+    children: Option<Vec<Child<C>>>,
+    /// Whether the node has successors to generate, so is on the open list.
+    in_open: bool,
+    /// Whether the node is not the start and has no successors in memory, so may be
+    /// forgotten.
+    in_leaves: bool,
+}
+
+/// The nodes in memory, in slots, with their order by estimate: the open list first by the
+/// smallest estimate, then the deepest node; and the leaves last by the largest estimate,
+/// then the shallowest node.
+struct Memory<N, C> {
+    slots: Vec<Option<SmaNode<N, C>>>,
+    free: Vec<usize>,
+    len: usize,
+    max_len: usize,
+    open: BTreeSet<(Bound<C>, Reverse<usize>, usize)>,
+    leaves: BTreeSet<(Bound<C>, Reverse<usize>, usize)>,
+}
+
+impl<N, C> Default for Memory<N, C> {
+    fn default() -> Self {
+        Memory {
+            slots: vec![],
+            free: vec![],
+            len: 0,
+            max_len: 0,
+            open: BTreeSet::new(),
+            leaves: BTreeSet::new(),
+        }
+    }
+}
+
+impl<N: Eq, C: Cost> Memory<N, C> {
+    fn slot(&mut self, slot: usize) -> &mut SmaNode<N, C> {
+        self.slots[slot].as_mut().unwrap() // Cannot fail
+    }
+
+    fn insert(&mut self, node: SmaNode<N, C>) -> usize {
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                self.slots[slot] = Some(node);
+                slot
+            }
+            None => {
+                self.slots.push(Some(node));
+                self.slots.len() - 1
+            }
+        };
+        self.len += 1;
+        self.max_len = self.max_len.max(self.len);
+        self.update(slot, None);
+        slot
+    }
+
+    /// Whether `node` is the node in `slot` or one on the path to it.
+    fn on_path(&self, mut slot: usize, node: &N) -> bool {
+        while slot != NONE {
+            let on_path = self.slots[slot].as_ref().unwrap(); // Cannot fail
+            if &on_path.node == node {
+                return true;
+            }
+            slot = on_path.parent;
+        }
+        false
+    }
+
+    /// Set the estimate of the node in `slot` if given, and put it on the open list and among
+    /// the leaves according to its successors.
+    fn update(&mut self, slot: usize, f: Option<Bound<C>>) {
+        let node = self.slots[slot].as_mut().unwrap(); // Cannot fail
+        let key = (node.f.clone(), Reverse(node.depth), slot);
+        if node.in_open {
+            self.open.remove(&key);
+        }
+        if node.in_leaves {
+            self.leaves.remove(&key);
+        }
+        if let Some(f) = f {
+            node.f = f;
+        }
+        node.in_open = node.children.as_ref().is_none_or(|children| {
+            (children.iter()).any(|child| matches!(child, Child::Ungenerated | Child::Forgotten(_)))
+        });
+        node.in_leaves = node.parent != NONE
+            && node.children.as_ref().is_none_or(|children| {
+                !(children.iter()).any(|child| matches!(child, Child::InMemory(_)))
+            });
+        let key = (node.f.clone(), Reverse(node.depth), slot);
+        if node.in_open {
+            self.open.insert(key.clone());
+        }
+        if node.in_leaves {
+            self.leaves.insert(key);
+        }
+    }
+
+    /// Once all the successors of the node in `slot` were generated, set its estimate to the
+    /// smallest one of those, and so on up the path.
+    fn back_up(&mut self, mut slot: usize) {
+        while slot != NONE {
+            let node = self.slots[slot].as_ref().unwrap(); // Cannot fail
+            let Some(children) = &node.children else {
+                return;
+            };
+            let mut f = Bound::Infinite;
+            for child in children {
+                let child_f = match child {
+                    Child::Ungenerated => return,
+                    Child::InMemory(child) => &self.slots[*child].as_ref().unwrap().f,
+                    Child::Forgotten(child_f) => child_f,
+                    Child::Pruned => continue,
+                };
+                if child_f < &f {
+                    f = child_f.clone();
+                }
+            }
+            if f == node.f {
+                return;
+            }
+            let parent = node.parent;
+            self.update(slot, Some(f));
+            slot = parent;
+        }
+    }
+
+    /// Forget the leaf of the largest estimate, the shallowest first, other than `keep`, and
+    /// record its estimate in its parent.
+    fn forget_worst_leaf(&mut self, keep: usize) {
+        let &(_, _, worst) = (self.leaves.iter().rev())
+            .find(|(_, _, slot)| *slot != keep)
+            .expect("the memory must hold a leaf off the path to the best node");
+        let node = self.slots[worst].take().unwrap(); // Cannot fail
+        let key = (node.f.clone(), Reverse(node.depth), worst);
+        if node.in_open {
+            self.open.remove(&key);
+        }
+        self.leaves.remove(&key);
+        self.free.push(worst);
+        self.len -= 1;
+        let parent = self.slot(node.parent);
+        parent.children.as_mut().unwrap()[node.position] = Child::Forgotten(node.f); // Cannot fail
+        self.update(node.parent, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        astar::astar,
+        rand::{Rng, SeedableRng, rngs::StdRng},
+    };
+
+    const SIDE: u8 = 7;
+
+    type Cell = (u8, u8);
+
+    /// The successors in a grid whose cells cost from 1 to 9 to enter.
+    fn grid(seed: u64) -> impl Fn(&Cell) -> Vec<(Cell, u32)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let costs = (0..SIDE as usize * SIDE as usize)
+            .map(|_| rng.random_range(1..10u32))
+            .collect::<Vec<_>>();
+        move |&(x, y): &Cell| {
+            let mut succs = vec![];
+            if x > 0 {
+                succs.push((x - 1, y));
+            }
+            if x + 1 < SIDE {
+                succs.push((x + 1, y));
+            }
+            if y > 0 {
+                succs.push((x, y - 1));
+            }
+            if y + 1 < SIDE {
+                succs.push((x, y + 1));
+            }
+            (succs.into_iter())
+                .map(|(x, y)| ((x, y), costs[y as usize * SIDE as usize + x as usize]))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn smastar_budget() {
+        // The Manhattan distance to the far corner.
+        let heuristic = |&(x, y): &Cell| (2 * (SIDE - 1) - x - y) as u32;
+        let success = |&node: &Cell| node == (SIDE - 1, SIDE - 1);
+        for seed in 0..5 {
+            let successors = grid(712 + seed);
+            let (_, optimum) = astar(&(0, 0), &successors, heuristic, success).unwrap();
+
+            // Ample memory for a shortest path, but not for the nodes A* reaches.
+            let max_nodes = 40;
+            let outcome = smastar(&(0, 0), &successors, heuristic, success, max_nodes);
+            let (path, cost) = outcome.best.unwrap();
+            assert_eq!(cost, optimum);
+            assert!(outcome.optimal);
+            assert_eq!(path.first(), Some(&(0, 0)));
+            assert_eq!(path.last(), Some(&(SIDE - 1, SIDE - 1)));
+            assert_eq!(outcome.max_nodes_held, max_nodes);
+        }
+    }
+
+    #[test]
+    fn smastar_cut_off() {
+        // A chain 0 -> 1 -> ... -> 5 of cost 1 per move, and a move 0 -> 5 of cost 10.
+        let successors = |&n: &u32| {
+            let mut succs = vec![];
+            if n < 5 {
+                succs.push((n + 1, 1u32));
+            }
+            if n == 0 {
+                succs.push((5, 10));
+            }
+            succs
+        };
+        let success = |&n: &u32| n == 5;
+        let roomy = smastar(&0, successors, |_| 0, success, 6);
+        assert_eq!(roomy.best, Some((vec![0, 1, 2, 3, 4, 5], 5)));
+        assert!(roomy.optimal);
+        // The chain does not fit in 4 nodes, the dearer path does.
+        let cramped = smastar(&0, successors, |_| 0, success, 4);
+        assert_eq!(cramped.best, Some((vec![0, 5], 10)));
+        assert!(!cramped.optimal);
+        assert!(cramped.max_nodes_held <= 4);
+        // Nor in 1 node, which only holds the start.
+        let tiny = smastar(&0, successors, |_| 0, success, 1);
+        assert_eq!(tiny.best, None);
+        assert!(!tiny.optimal);
+    }
+}
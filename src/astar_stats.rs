@@ -0,0 +1,58 @@
+use std::{fmt::Display, time::Duration};
+
+/// Counters collected during an A* search, see
+/// [`astar_with_options`](crate::astar::astar_with_options).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Number of nodes whose successors were generated, counting a node again each time it
+    /// is expanded again.
+    pub expanded: usize,
+    /// Number of successors generated, without the forbidden moves.
+    pub generated: usize,
+    /// Number of successors that were not added to the open list because a path to them of at
+    /// most the same cost was known, and of entries of the open list that were superseded by
+    /// a cheaper path to their node when they were taken from it, or that lead to a node that
+    /// may not be expanded again, see [`Reopening`](crate::astar_options::Reopening).
+    pub duplicates_skipped: usize,
+    /// The largest number of entries in the open list; for
+    /// [`idastar`](crate::astar_iterative_deepening::idastar), the largest number of nodes on
+    /// the path it holds.
+    pub max_open_len: usize,
+    /// The largest number of moves on the path to an expanded node.
+    pub max_depth: usize,
+    /// Number of depth-first searches with a raised bound on the estimated cost, for
+    /// [`idastar`](crate::astar_iterative_deepening::idastar).
+    pub iterations: usize,
+    /// Number of times the cost of the best path to a node through its predecessors was
+    /// recomputed, for [`LifelongSearcher`](crate::astar_lifelong::LifelongSearcher).
+    pub state_updates: usize,
+    /// Number of entries added to or taken from the open list, for the searches of
+    /// [`astar`](crate::astar::astar) and its variants on the same loop, such as
+    /// [`dijkstra_buckets`](crate::astar_dijkstra::dijkstra_buckets). Zero for
+    /// [`fringe_search`](crate::astar_fringe::fringe_search), whose lists are not ordered.
+    pub queue_operations: usize,
+    /// Number of expansions of nodes that were expanded before, see
+    /// [`Reopening`](crate::astar_options::Reopening).
+    pub reexpanded: usize,
+    /// The duration of the search.
+    pub time: Duration,
+}
+
+impl Display for SearchStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "expanded: {}, generated: {}, duplicates skipped: {}, max. open list: {}, max. depth: {}, iterations: {}, state updates: {}, queue operations: {}, re-expanded: {}, time: {:?}",
+            self.expanded,
+            self.generated,
+            self.duplicates_skipped,
+            self.max_open_len,
+            self.max_depth,
+            self.iterations,
+            self.state_updates,
+            self.queue_operations,
+            self.reexpanded,
+            self.time,
+        )
+    }
+}
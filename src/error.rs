@@ -0,0 +1,210 @@
+//! The errors of the crate: each module has its own error type, and [`Error`] wraps them all,
+//! so that callers that use several modules can propagate their errors with `?`. All of them
+//! implement [`std::error::Error`], so that they also convert into `anyhow` errors.
+//!
+//! The network simplex and the transportation problems return [`Error`]. The linear
+//! programming modules keep returning their own `SolveError` and `SparseError`: most of their
+//! errors are outcomes that callers match on, such as the certificate of an infeasible problem,
+//! the ray of an unbounded one or the partial solution of an interrupted solve, and the methods
+//! that build a problem return them on every call. Both convert into [`Error`] with `?`.
+
+use std::fmt::Display;
+
+use crate::network_simplex::NetworkSimplexError;
+#[cfg(feature = "linear_programming")]
+use crate::{
+    linear_programming::SolveError, linear_programming_sparse::SparseError,
+    transportation::TransportError,
+};
+
+/// An error of the crate, wrapping the error of the module it comes from, which is its
+/// [`source`](std::error::Error::source).
+#[derive(Debug)]
+pub enum Error {
+    /// Building or solving a linear programming problem failed.
+    #[cfg(feature = "linear_programming")]
+    Lp(SolveError),
+    /// Setting up the network simplex failed.
+    NetworkSimplex(NetworkSimplexError),
+    /// A sparse factorisation failed.
+    #[cfg(feature = "linear_programming")]
+    Sparse(SparseError),
+    /// Solving a transportation problem failed.
+    #[cfg(feature = "linear_programming")]
+    Transport(TransportError),
+    /// A value cannot be converted to the required kind of number.
+    Arithmetic(ArithmeticError),
+    /// Reading or writing failed, e.g. when writing a search graph.
+    Io(std::io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "linear_programming")]
+            Error::Lp(error) => write!(f, "linear programming: {}", error),
+            Error::NetworkSimplex(error) => write!(f, "network simplex: {}", error),
+            #[cfg(feature = "linear_programming")]
+            Error::Sparse(error) => write!(f, "sparse factorisation: {}", error),
+            #[cfg(feature = "linear_programming")]
+            Error::Transport(error) => write!(f, "transportation: {}", error),
+            Error::Arithmetic(error) => write!(f, "arithmetic: {}", error),
+            Error::Io(error) => write!(f, "i/o: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "linear_programming")]
+            Error::Lp(error) => Some(error),
+            Error::NetworkSimplex(error) => Some(error),
+            #[cfg(feature = "linear_programming")]
+            Error::Sparse(error) => Some(error),
+            #[cfg(feature = "linear_programming")]
+            Error::Transport(error) => Some(error),
+            Error::Arithmetic(error) => Some(error),
+            Error::Io(error) => Some(error),
+        }
+    }
+}
+
+#[cfg(feature = "linear_programming")]
+impl From<SolveError> for Error {
+    fn from(error: SolveError) -> Self {
+        Error::Lp(error)
+    }
+}
+
+impl From<NetworkSimplexError> for Error {
+    fn from(error: NetworkSimplexError) -> Self {
+        Error::NetworkSimplex(error)
+    }
+}
+
+#[cfg(feature = "linear_programming")]
+impl From<SparseError> for Error {
+    fn from(error: SparseError) -> Self {
+        Error::Sparse(error)
+    }
+}
+
+#[cfg(feature = "linear_programming")]
+impl From<TransportError> for Error {
+    fn from(error: TransportError) -> Self {
+        Error::Transport(error)
+    }
+}
+
+impl From<ArithmeticError> for Error {
+    fn from(error: ArithmeticError) -> Self {
+        Error::Arithmetic(error)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+/// A value that cannot be converted to the required kind of number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArithmeticError {
+    /// The value is infinite or NaN.
+    NotFinite,
+    /// The value is approximate where an exact one is required.
+    NotExact,
+    /// The value is not an integer, or its type has no integers.
+    NotAnInteger,
+}
+
+impl Display for ArithmeticError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArithmeticError::NotFinite => "value is not finite".fmt(f),
+            ArithmeticError::NotExact => "value is not exact".fmt(f),
+            ArithmeticError::NotAnInteger => "value is not an integer".fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ArithmeticError {}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+
+    /// Check that `source` converts into an [`Error`] that displays it and has it as its
+    /// source, also through a boxed and an `anyhow` error.
+    fn check<E>(source: E)
+    where
+        E: std::error::Error + PartialEq + Clone + Send + Sync + 'static,
+        Error: From<E>,
+    {
+        let error = Error::from(source.clone());
+        assert!(error.to_string().ends_with(&source.to_string()));
+        assert_eq!(error.source().unwrap().downcast_ref::<E>(), Some(&source));
+
+        let boxed: Box<dyn std::error::Error + Send + Sync> = error.into();
+        let error = boxed.downcast_ref::<Error>().unwrap();
+        assert_eq!(error.source().unwrap().downcast_ref::<E>(), Some(&source));
+
+        let anyhow = crate::anyhow::Error::from(Error::from(source.clone()));
+        let error = anyhow.downcast_ref::<Error>().unwrap();
+        assert_eq!(error.source().unwrap().downcast_ref::<E>(), Some(&source));
+    }
+
+    #[test]
+    fn error_conversions() {
+        check(NetworkSimplexError::SelfLoop(3));
+        check(ArithmeticError::NotAnInteger);
+
+        let io = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "end of graph");
+        let error = Error::from(io);
+        assert!(matches!(error, Error::Io(_)));
+        let source = error.source().unwrap().downcast_ref::<std::io::Error>();
+        assert_eq!(source.unwrap().kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[cfg(feature = "linear_programming")]
+    #[test]
+    fn error_conversions_linear_programming() {
+        use crate::linear_programming::{
+            InfeasibilityCertificate, ModelError, PartialSolution, UnboundedRay,
+        };
+
+        check(SolveError::Infeasible(InfeasibilityCertificate::default()));
+        check(SolveError::InvalidModel(ModelError::InvalidRange));
+        check(SparseError::SymbolicMismatch { step: 4 });
+        check(TransportError::Lp(SolveError::Unbounded(
+            UnboundedRay::default(),
+        )));
+
+        // The crate error is returned by `?` from the errors of the modules.
+        let solved: Result<(), SolveError> =
+            Err(SolveError::Interrupted(PartialSolution::default()));
+        let solve = || -> Result<(), Error> { Ok(solved.clone()?) };
+        assert!(matches!(
+            solve(),
+            Err(Error::Lp(SolveError::Interrupted(_)))
+        ));
+    }
+
+    #[cfg(feature = "linear_programming")]
+    #[test]
+    fn error_exact_value() {
+        use crate::{abnormal_fraction::AbnormalFraction, f_ab};
+
+        assert!(f_ab!(1, 3).exact_value().is_ok());
+        let infinity = AbnormalFraction::infinity();
+        let error = infinity.exact_value().map_err(Error::from);
+        assert!(matches!(
+            error,
+            Err(Error::Arithmetic(ArithmeticError::NotFinite))
+        ));
+    }
+}
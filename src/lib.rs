@@ -1,15 +1,82 @@
+#[cfg(feature = "linear_programming")]
+pub mod abnormal_fraction;
 pub mod astar;
-// pub mod linear_programming;
-// pub mod linear_programming_helpers;
-// pub mod linear_programming_lu;
-// pub mod linear_programming_ordering;
-// pub mod linear_programming_solver;
-// pub mod linear_programming_sparse;
-// pub mod abnormal_fraction;
+pub mod astar_all_optimal;
+pub mod astar_anytime;
+pub mod astar_beam;
+pub mod astar_bidirectional;
+pub mod astar_dijkstra;
+pub mod astar_focal;
+pub mod astar_fringe;
+pub mod astar_goals;
+pub mod astar_heuristic_check;
+pub mod astar_indexed;
+pub mod astar_iterative_deepening;
+pub mod astar_k_shortest;
+pub mod astar_labeled;
+pub mod astar_landmarks;
+pub mod astar_lifelong;
+#[cfg(feature = "linear_programming")]
+pub mod astar_lp_heuristic;
+pub mod astar_memoized;
+pub mod astar_options;
+#[cfg(feature = "parallel")]
+pub mod astar_parallel;
+pub mod astar_progress;
+pub mod astar_search_graph;
+pub mod astar_shared;
+pub mod astar_smastar;
+pub mod astar_stats;
+#[cfg(test)]
+mod counting_allocator;
+pub mod error;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_basis;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_certificate;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_helpers;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_hybrid;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_iis;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_lp_file;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_lu;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_names;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_options;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_ordering;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_presolve;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_progress;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_scaling;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_size;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_solver;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_sparse;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_standard_form;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_stats;
+#[cfg(feature = "linear_programming")]
+pub mod linear_programming_verify;
 pub mod network_simplex;
 pub mod network_simplex_value_type;
+#[cfg(feature = "linear_programming")]
+pub mod transportation;
 
 pub use ebi_arithmetic;
-pub use ebi_arithmetic::rand;
 pub use ebi_arithmetic::anyhow;
-pub use ebi_arithmetic::malachite;
\ No newline at end of file
+pub use ebi_arithmetic::malachite;
+pub use ebi_arithmetic::rand;
+pub use error::Error;
@@ -23,23 +23,23 @@ to the problem.
 # Example
 
 ```
-# use ebi_optimisation::{*, linear_programming::*};
-# use ebi_arithmetic::{*,ebi_number::*,fraction::*};
+# use ebi_optimisation::{*, abnormal_fraction::AbnormalFraction, linear_programming::*};
+# use ebi_arithmetic::{One, Zero};
 
 // Maximise an objective function x + 2 * y of two variables x >= 0 and 0 <= y <= 3
 let mut problem = Problem::new(OptimisationDirection::Maximise);
-let x = problem.add_var(f1!(), (f0!(), Fraction::infinity()));
-let y = problem.add_var(f!(2), (f0!(), f!(3)));
+let x = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+let y = problem.add_var(f_ab!(2), (f0_ab!(), f_ab!(3)));
 
 // subject to constraints: x + y <= 4 and 2 * x + y >= 2.
-problem.add_constraint(&[(x, f1!()), (y, f1!())], ComparisonOp::Le, f!(4));
-problem.add_constraint(&[(x, f!(2)), (y, f1!())], ComparisonOp::Ge, f!(2));
+problem.add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Le, f_ab!(4)).unwrap();
+problem.add_constraint(&[(x, f_ab!(2)), (y, f1_ab!())], ComparisonOp::Ge, f_ab!(2)).unwrap();
 
 // Optimal value is 7, achieved at x = 1 and y = 3.
 let solution = problem.solve().unwrap();
-assert_eq!(solution.objective(), f!(7));
-assert_eq!(solution[x], f1!());
-assert_eq!(solution[y], f!(3));
+assert_eq!(solution.objective(), f_ab!(7));
+assert_eq!(solution[x], f1_ab!());
+assert_eq!(solution[y], f_ab!(3));
 ```
 */
 
@@ -68,12 +68,14 @@ impl Variable {
     }
 }
 
-/// A sum of variables multiplied by constant coefficients used as a left-hand side
-/// when defining constraints.
+/// A sum of variables multiplied by constant coefficients, plus a constant, used as a
+/// left-hand side when defining constraints or as an objective. In a constraint, the constant
+/// is moved to the right-hand side.
 #[derive(Clone, Debug)]
 pub struct LinearExpr {
     vars: Vec<usize>,
     coeffs: Vec<AbnormalFraction>,
+    constant: AbnormalFraction,
 }
 
 impl LinearExpr {
@@ -82,19 +84,61 @@ impl LinearExpr {
         Self {
             vars: vec![],
             coeffs: vec![],
+            constant: f0_ab!(),
         }
     }
 
     /// Add a single term to the linear expression.
     ///
-    /// Variables can be added to an expression in any order, but adding the same variable
-    /// several times is forbidden (the [`Problem::add_constraint`] method will panic).
-    ///
-    /// [`Problem::add_constraint`]: struct.Problem.html#method.add_constraint
+    /// Variables can be added to an expression in any order. If the same variable is added
+    /// several times, its coefficients are summed.
     pub fn add(&mut self, var: Variable, coeff: AbnormalFraction) {
         self.vars.push(var.0);
         self.coeffs.push(coeff);
     }
+
+    /// Add a constant to the linear expression. Constants added several times are summed.
+    pub fn add_constant(&mut self, constant: AbnormalFraction) {
+        self.constant += constant;
+    }
+
+    /// The constant of the linear expression.
+    pub fn constant(&self) -> &AbnormalFraction {
+        &self.constant
+    }
+
+    /// The coefficients as a sparse vector of length `num_vars` (see
+    /// [`LinearExpr::into_csvec`]), and the constant.
+    fn into_parts(self, num_vars: usize) -> Result<(CsVec, AbnormalFraction), Error> {
+        check_finite(&self.constant, "constant", None)?;
+        let constant = self.constant.clone();
+        Ok((self.into_csvec(num_vars)?, constant))
+    }
+
+    /// The coefficients as a sparse vector of length `num_vars`, with the coefficients of
+    /// each variable summed and zero coefficients left out.
+    fn into_csvec(self, num_vars: usize) -> Result<CsVec, Error> {
+        let mut terms = self.vars.into_iter().zip(self.coeffs).collect::<Vec<_>>();
+        for (var, coeff) in &terms {
+            check_finite(coeff, "coefficient", Some(Variable(*var)))?;
+        }
+        terms.sort_by_key(|(var, _)| *var);
+
+        let mut indices: Vec<usize> = Vec::with_capacity(terms.len());
+        let mut data: Vec<AbnormalFraction> = Vec::with_capacity(terms.len());
+        for (var, coeff) in terms {
+            if indices.last() == Some(&var) {
+                *data.last_mut().unwrap() += coeff;
+            } else {
+                indices.push(var);
+                data.push(coeff);
+            }
+        }
+        let (indices, data) = (indices.into_iter().zip(data))
+            .filter(|(_, coeff)| !coeff.is_zero())
+            .unzip();
+        Ok(CsVec::new(num_vars, indices, data))
+    }
 }
 
 /// A single `variable * constant` term in a linear expression.
@@ -145,7 +189,7 @@ impl std::iter::Extend<(Variable, AbnormalFraction)> for LinearExpr {
 }
 
 /// An operator specifying the relation between left-hand and right-hand sides of the constraint.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ComparisonOp {
     /// The == operator (equal to)
     Eq,
@@ -155,26 +199,194 @@ pub enum ComparisonOp {
     Ge,
 }
 
-/// An error encountered while solving a problem.
+/// An error encountered while building or solving a problem. It is returned as is rather than
+/// as the crate's [`Error`](crate::Error), so that callers match on the certificates and
+/// partial solutions directly; it converts into the latter with `?`.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Error {
-    /// Constrains can't simultaneously be satisfied.
-    Infeasible,
-    /// The objective function is unbounded.
-    Unbounded,
+    /// Constrains can't simultaneously be satisfied, as the certificate proves.
+    Infeasible(InfeasibilityCertificate),
+    /// The objective function is unbounded along the ray.
+    Unbounded(UnboundedRay),
+    /// The solver was stopped by the callback of [`SolveOptions::on_iteration`] or by the token
+    /// of [`SolveOptions::cancel_token`], at the point it holds.
+    Interrupted(PartialSolution),
+    /// The LU factorisation found the basis matrix to be singular, which can happen when
+    /// approximate arithmetic loses too much precision.
+    SingularBasis {
+        /// Where the singularity was found.
+        detail: String,
+        /// The position in the basis of the column for which no acceptable pivot was left, if
+        /// known.
+        basis_col: Option<usize>,
+        /// The constraint without an acceptable pivot, if known.
+        row: Option<usize>,
+    },
+    /// The problem, or an input to it, is invalid.
+    InvalidModel(ModelError),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let msg = match self {
-            Error::Infeasible => "problem is infeasible",
-            Error::Unbounded => "problem is unbounded",
-        };
-        msg.fmt(f)
+        match self {
+            Error::Infeasible(_) => "problem is infeasible".fmt(f),
+            Error::Unbounded(_) => "problem is unbounded".fmt(f),
+            Error::Interrupted(_) => "solver was interrupted".fmt(f),
+            Error::SingularBasis { detail, .. } => {
+                write!(f, "basis matrix is singular: {}", detail)
+            }
+            Error::InvalidModel(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InvalidModel(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ModelError> for Error {
+    fn from(err: ModelError) -> Self {
+        Error::InvalidModel(err)
+    }
+}
+
+/// An invalid problem or input to a problem, which is rejected before solving.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModelError {
+    /// The name is already used by another variable or constraint.
+    DuplicateName(String),
+    /// The basis does not match the variables and constraints of the problem.
+    BasisMismatch,
+    /// The lower bound of the variable would be greater than its upper bound.
+    InvalidBounds(Variable),
+    /// The lower bound of a range constraint would be greater than its upper bound.
+    InvalidRange,
+    /// No Gomory cut can be derived for the variable: the problem was scaled or presolve
+    /// removed the variable.
+    NoGomoryCut(Variable),
+    /// The constraint at this position has no single right-hand side to change, because it is
+    /// a range constraint or has no finite bound.
+    NoSingleRhs(usize),
+    /// A coefficient, right-hand side or bound is infinite or NaN. Bounds may only be
+    /// infinite on the side where they are absent.
+    NonFinite {
+        /// The kind of value.
+        part: &'static str,
+        /// The variable the value belongs to, if any.
+        var: Option<Variable>,
+    },
+    /// A part of a problem given to [`Problem::from_parts`], or the permutation of a column
+    /// ordering (see [`Ordering::Explicit`]), has the wrong length.
+    DimensionMismatch {
+        /// The part with the wrong length.
+        part: &'static str,
+        /// The length implied by the other parts.
+        expected: usize,
+        /// The length of the part.
+        found: usize,
+    },
+}
+
+impl std::fmt::Display for ModelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ModelError::DuplicateName(name) => {
+                write!(f, "name `{}` is used more than once", name)
+            }
+            ModelError::BasisMismatch => "basis does not match the problem".fmt(f),
+            ModelError::InvalidBounds(var) => write!(
+                f,
+                "lower bound of variable {} is greater than its upper bound",
+                var.0
+            ),
+            ModelError::InvalidRange => {
+                "lower bound of a range constraint is greater than its upper bound".fmt(f)
+            }
+            ModelError::NoGomoryCut(var) => {
+                write!(f, "no Gomory cut can be derived for variable {}", var.0)
+            }
+            ModelError::NoSingleRhs(constraint) => {
+                write!(f, "constraint {} has no single right-hand side", constraint)
+            }
+            ModelError::NonFinite {
+                part,
+                var: Some(var),
+            } => write!(f, "{} of variable {} is not finite", part, var.0),
+            ModelError::NonFinite { part, var: None } => write!(f, "{} is not finite", part),
+            ModelError::DimensionMismatch {
+                part,
+                expected,
+                found,
+            } => write!(f, "{} has length {}, expected {}", part, found, expected),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+/// The error of solving a problem; the same type as [`Error`].
+pub type SolveError = Error;
+
+fn check_finite(
+    val: &AbnormalFraction,
+    part: &'static str,
+    var: Option<Variable>,
+) -> Result<(), Error> {
+    if val.is_finite() {
+        Ok(())
+    } else {
+        Err(ModelError::NonFinite { part, var }.into())
+    }
+}
+
+/// Check that `min` and `max` are not NaN, and only infinite if they are absent.
+fn check_bounds(
+    min: &AbnormalFraction,
+    max: &AbnormalFraction,
+    var: Option<Variable>,
+) -> Result<(), Error> {
+    if matches!(min, AbnormalFraction::Infinite | AbnormalFraction::NaN)
+        || matches!(max, AbnormalFraction::NegInfinite | AbnormalFraction::NaN)
+    {
+        Err(ModelError::NonFinite { part: "bound", var }.into())
+    } else {
+        Ok(())
     }
 }
 
-impl std::error::Error for Error {}
+/// One of the objectives of [`Problem::solve_lexicographic`].
+#[derive(Clone, Debug)]
+pub struct ObjectiveSpec {
+    direction: OptimisationDirection,
+    expr: LinearExpr,
+}
+
+impl ObjectiveSpec {
+    /// An objective that minimises or maximises `expr`.
+    pub fn new(direction: OptimisationDirection, expr: impl Into<LinearExpr>) -> Self {
+        Self {
+            direction,
+            expr: expr.into(),
+        }
+    }
+
+    /// Dense coefficients of the objective in the form that is minimised.
+    fn obj_coeffs(&self, num_vars: usize) -> Vec<AbnormalFraction> {
+        let mut obj_coeffs = vec![f0_ab!(); num_vars];
+        for (var, coeff) in self.expr.vars.iter().zip(&self.expr.coeffs) {
+            obj_coeffs[*var] += match self.direction {
+                OptimisationDirection::Minimise => coeff.clone(),
+                OptimisationDirection::Maximise => -coeff.clone(),
+            };
+        }
+        obj_coeffs
+    }
+}
 
 /// A specification of a linear programming problem.
 #[derive(Clone)]
@@ -183,7 +395,13 @@ pub struct Problem {
     obj_coeffs: Vec<AbnormalFraction>,
     var_mins: Vec<AbnormalFraction>,
     var_maxs: Vec<AbnormalFraction>,
-    constraints: Vec<(CsVec, ComparisonOp, AbnormalFraction)>,
+    constraints: Vec<Constraint>,
+    /// The constant term of the objective, not negated when maximising.
+    obj_constant: AbnormalFraction,
+    var_names: Names,
+    constraint_names: Names,
+    /// The first invalid input of [`Problem::add_var`], which is reported when solving.
+    invalid_var: Option<Error>,
 }
 
 impl std::fmt::Debug for Problem {
@@ -199,6 +417,60 @@ impl std::fmt::Debug for Problem {
 
 pub(crate) type CsVec = sprs::CsVecI<AbnormalFraction, usize>;
 
+/// A constraint `min <= coeffs * x <= max`. At most one of the bounds is finite, unless the
+/// constraint is an equality or a range.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Constraint {
+    pub(crate) coeffs: CsVec,
+    pub(crate) min: AbnormalFraction,
+    pub(crate) max: AbnormalFraction,
+}
+
+impl Constraint {
+    pub(crate) fn new(coeffs: CsVec, cmp_op: ComparisonOp, rhs: AbnormalFraction) -> Self {
+        let (min, max) = match cmp_op {
+            ComparisonOp::Eq => (rhs.clone(), rhs),
+            ComparisonOp::Le => (AbnormalFraction::neg_infinity(), rhs),
+            ComparisonOp::Ge => (rhs, AbnormalFraction::infinity()),
+        };
+        Self { coeffs, min, max }
+    }
+
+    /// Whether the constraint is an equality `coeffs * x == rhs`.
+    pub(crate) fn is_equality(&self) -> bool {
+        self.min == self.max
+    }
+
+    /// Whether the constraint holds if `coeffs * x` evaluates to `val`.
+    pub(crate) fn is_satisfied_by(&self, val: &AbnormalFraction) -> bool {
+        &self.min <= val && val <= &self.max
+    }
+
+    /// The right-hand side and the bounds of the slack var `s` for which the constraint reads
+    /// `coeffs * x + s == rhs`. A range constraint gets a slack var with two finite bounds.
+    ///
+    /// The slack var of an equality is fixed at zero. The solver only creates it if no var of
+    /// the row can be basic in it from the start, and then it serves as an artificial var.
+    pub(crate) fn slack(&self) -> (AbnormalFraction, AbnormalFraction, AbnormalFraction) {
+        if self.max.is_finite() {
+            let slack_max = if self.min.is_finite() {
+                &self.max - &self.min
+            } else {
+                AbnormalFraction::infinity()
+            };
+            (self.max.clone(), f0_ab!(), slack_max)
+        } else if self.min.is_finite() {
+            (self.min.clone(), AbnormalFraction::neg_infinity(), f0_ab!())
+        } else {
+            (
+                f0_ab!(),
+                AbnormalFraction::neg_infinity(),
+                AbnormalFraction::infinity(),
+            )
+        }
+    }
+}
+
 impl Problem {
     /// Create a new problem instance.
     pub fn new(direction: OptimisationDirection) -> Self {
@@ -208,7 +480,113 @@ impl Problem {
             var_mins: vec![],
             var_maxs: vec![],
             constraints: vec![],
+            obj_constant: f0_ab!(),
+            var_names: Names::default(),
+            constraint_names: Names::default(),
+            invalid_var: None,
+        }
+    }
+
+    /// Create a problem from its objective coefficients, constraint matrix, constraint senses,
+    /// right-hand sides and variable bounds, where an absent bound means unbounded. Variable
+    /// `j` is column `j` of `a` and can be obtained with [`Problem::var`]; constraint `i` is row
+    /// `i` of `a`.
+    ///
+    /// The rows of a matrix in CSR storage are moved into the problem rather than copied; a
+    /// matrix in CSC storage is converted first.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`ModelError::DimensionMismatch`] if the lengths of the parts do not match the
+    /// shape of `a`, and [`ModelError::NonFinite`] if a coefficient, right-hand side or given bound
+    /// is not finite.
+    pub fn from_parts(
+        obj: Vec<AbnormalFraction>,
+        a: sprs::CsMatI<AbnormalFraction, usize>,
+        senses: Vec<ComparisonOp>,
+        rhs: Vec<AbnormalFraction>,
+        var_bounds: Vec<(Option<AbnormalFraction>, Option<AbnormalFraction>)>,
+        direction: OptimisationDirection,
+    ) -> Result<Self, Error> {
+        let check = |part, expected, found| {
+            if expected == found {
+                Ok(())
+            } else {
+                Err(Error::InvalidModel(ModelError::DimensionMismatch {
+                    part,
+                    expected,
+                    found,
+                }))
+            }
+        };
+        check("objective", a.cols(), obj.len())?;
+        check("variable bounds", a.cols(), var_bounds.len())?;
+        check("senses", a.rows(), senses.len())?;
+        check("right-hand sides", a.rows(), rhs.len())?;
+
+        for (var, (obj_coeff, (min, max))) in obj.iter().zip(&var_bounds).enumerate() {
+            let var = Some(Variable(var));
+            check_finite(obj_coeff, "objective coefficient", var)?;
+            for bound in min.iter().chain(max) {
+                check_finite(bound, "bound", var)?;
+            }
+        }
+        for rhs in &rhs {
+            check_finite(rhs, "right-hand side", None)?;
+        }
+        for (coeff, (_, var)) in a.iter() {
+            check_finite(coeff, "coefficient", Some(Variable(var)))?;
+        }
+
+        let mut problem = Problem::new(direction);
+        problem.obj_coeffs.reserve(obj.len());
+        for (obj_coeff, (min, max)) in obj.into_iter().zip(var_bounds) {
+            problem.add_var(
+                obj_coeff,
+                (
+                    min.unwrap_or_else(AbnormalFraction::neg_infinity),
+                    max.unwrap_or_else(AbnormalFraction::infinity),
+                ),
+            );
+        }
+
+        let a = if a.is_csr() { a } else { a.to_csr() };
+        let num_vars = a.cols();
+        let (indptr, mut indices, mut data) = a.into_raw_storage();
+        // Split the rows off from the back, which moves the coefficients.
+        let mut rows = Vec::with_capacity(senses.len());
+        for row in (0..senses.len()).rev() {
+            let start = indptr[row] - indptr[0];
+            rows.push(CsVec::new(
+                num_vars,
+                indices.split_off(start),
+                data.split_off(start),
+            ));
         }
+        problem.constraints = (rows.into_iter().rev().zip(senses).zip(rhs))
+            .map(|((coeffs, cmp_op), rhs)| Constraint::new(coeffs, cmp_op, rhs))
+            .collect();
+        Ok(problem)
+    }
+
+    /// Number of variables of the problem.
+    pub fn num_vars(&self) -> usize {
+        self.obj_coeffs.len()
+    }
+
+    /// Number of constraints of the problem.
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// The variable that was added as the `idx`-th, counting from 0.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the problem has no such variable.
+    pub fn var(&self, idx: usize) -> Variable {
+        assert!(idx < self.num_vars(), "variable {} does not exist", idx);
+        Variable(idx)
     }
 
     /// Add a new variable to the problem.
@@ -217,12 +595,20 @@ impl Problem {
     /// variable, `min` and `max` are the minimum and maximum (inclusive) bounds of this
     /// variable. If one of the bounds is absent, use `Fraction::neg_infinity()` for minimum and
     /// `Fraction::infinity()` for maximum.
+    ///
+    /// A variable with an objective coefficient that is not finite, or a bound that is NaN or
+    /// infinite on the wrong side, makes solving the problem fail with [`ModelError::NonFinite`].
     pub fn add_var(
         &mut self,
         obj_coeff: AbnormalFraction,
         (min, max): (AbnormalFraction, AbnormalFraction),
     ) -> Variable {
         let var = Variable(self.obj_coeffs.len());
+        if self.invalid_var.is_none() {
+            self.invalid_var = (check_finite(&obj_coeff, "objective coefficient", Some(var)))
+                .and_then(|()| check_bounds(&min, &max, Some(var)))
+                .err();
+        }
         let obj_coeff = match self.direction {
             OptimisationDirection::Minimise => obj_coeff,
             OptimisationDirection::Maximise => -obj_coeff,
@@ -233,500 +619,4775 @@ impl Problem {
         var
     }
 
-    /// Add a linear constraint to the problem.
+    /// Add a new named variable to the problem. See [`Problem::add_var`].
+    ///
+    /// # Errors
+    ///
+    /// Will return [`ModelError::DuplicateName`] if another variable has the same name; the problem
+    /// is not changed in that case.
+    pub fn add_var_named(
+        &mut self,
+        name: &str,
+        obj_coeff: AbnormalFraction,
+        (min, max): (AbnormalFraction, AbnormalFraction),
+    ) -> Result<Variable, Error> {
+        if self.var_names.contains(name) {
+            return Err(ModelError::DuplicateName(name.to_string()).into());
+        }
+        let var = self.add_var(obj_coeff, (min, max));
+        self.var_names.insert(var.0, name);
+        Ok(var)
+    }
+
+    /// Change the objective coefficient of a variable. As the constraints and bounds stay the
+    /// same, the basis of a previous solution remains primal feasible: solving with
+    /// [`Problem::solve_from_basis`] and that basis only needs the primal simplex.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`ModelError::NonFinite`] if the coefficient is not finite; the problem is not
+    /// changed in that case.
+    pub fn set_objective_coeff(
+        &mut self,
+        var: Variable,
+        obj_coeff: AbnormalFraction,
+    ) -> Result<(), Error> {
+        check_finite(&obj_coeff, "objective coefficient", Some(var))?;
+        self.obj_coeffs[var.0] = match self.direction {
+            OptimisationDirection::Minimise => obj_coeff,
+            OptimisationDirection::Maximise => -obj_coeff,
+        };
+        Ok(())
+    }
+
+    /// Set the constant term of the objective function, such as a fixed cost. It does not
+    /// influence the optimal solution, but is included in [`Solution::objective`].
+    ///
+    /// # Errors
+    ///
+    /// Will return [`ModelError::NonFinite`] if the constant is not finite; the problem is not
+    /// changed in that case.
+    pub fn set_objective_constant(&mut self, constant: AbnormalFraction) -> Result<(), Error> {
+        check_finite(&constant, "objective constant", None)?;
+        self.obj_constant = constant;
+        Ok(())
+    }
+
+    /// The constant term of the objective function.
+    pub fn objective_constant(&self) -> &AbnormalFraction {
+        &self.obj_constant
+    }
+
+    /// The constant term of the objective in the form that is minimised.
+    fn minimised_obj_constant(&self) -> AbnormalFraction {
+        match self.direction {
+            OptimisationDirection::Minimise => self.obj_constant.clone(),
+            OptimisationDirection::Maximise => -self.obj_constant.clone(),
+        }
+    }
+
+    /// Replace the objective function; variables that do not occur in `expr` get coefficient
+    /// zero, and the coefficients of a variable that occurs more than once are summed. The
+    /// constant of `expr` becomes the constant term of the objective. See
+    /// [`Problem::set_objective_coeff`].
+    ///
+    /// # Errors
+    ///
+    /// Will return [`ModelError::NonFinite`] if a coefficient or the constant is not finite; the
+    /// problem is not changed in that case.
+    pub fn set_objective(&mut self, expr: impl Into<LinearExpr>) -> Result<(), Error> {
+        let expr = expr.into();
+        for (&var, coeff) in expr.vars.iter().zip(&expr.coeffs) {
+            check_finite(coeff, "objective coefficient", Some(Variable(var)))?;
+        }
+        let (coeffs, constant) = expr.into_parts(self.obj_coeffs.len())?;
+        self.set_objective_constant(constant)?;
+        self.obj_coeffs = vec![f0_ab!(); self.obj_coeffs.len()];
+        for (var, coeff) in coeffs.iter() {
+            self.set_objective_coeff(Variable(var), coeff.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Change the lower bound of a variable; `None` removes the bound. The constraints are
+    /// kept, so the problem can be solved again right away, for instance with
+    /// [`Problem::solve_from_basis`] and the basis of the previous solution. A non-basic
+    /// variable of that basis starts at its new bound; a basic variable that violates its new
+    /// bound is repaired by the dual simplex.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`ModelError::InvalidBounds`] if the bound is greater than the upper bound of
+    /// the variable, and [`ModelError::NonFinite`] if it is not finite; the problem is not changed
+    /// in that case.
+    pub fn set_var_lower(
+        &mut self,
+        var: Variable,
+        min: Option<AbnormalFraction>,
+    ) -> Result<(), Error> {
+        if let Some(min) = &min {
+            check_finite(min, "bound", Some(var))?;
+        }
+        let min = min.unwrap_or_else(AbnormalFraction::neg_infinity);
+        if min > self.var_maxs[var.0] {
+            return Err(ModelError::InvalidBounds(var).into());
+        }
+        self.var_mins[var.0] = min;
+        Ok(())
+    }
+
+    /// Change the upper bound of a variable; `None` removes the bound. See
+    /// [`Problem::set_var_lower`].
+    ///
+    /// # Errors
+    ///
+    /// Will return [`ModelError::InvalidBounds`] if the bound is less than the lower bound of the
+    /// variable, and [`ModelError::NonFinite`] if it is not finite; the problem is not changed in
+    /// that case.
+    pub fn set_var_upper(
+        &mut self,
+        var: Variable,
+        max: Option<AbnormalFraction>,
+    ) -> Result<(), Error> {
+        if let Some(max) = &max {
+            check_finite(max, "bound", Some(var))?;
+        }
+        let max = max.unwrap_or_else(AbnormalFraction::infinity);
+        if max < self.var_mins[var.0] {
+            return Err(ModelError::InvalidBounds(var).into());
+        }
+        self.var_maxs[var.0] = max;
+        Ok(())
+    }
+
+    /// The variable with the given name, if any.
+    pub fn var_by_name(&self, name: &str) -> Option<Variable> {
+        self.var_names.index(name).map(Variable)
+    }
+
+    /// The name of the variable, if it was added with [`Problem::add_var_named`].
+    pub fn var_name(&self, var: Variable) -> Option<&str> {
+        self.var_names.get(var.0)
+    }
+
+    /// Remove a variable and its terms in the objective and the constraints. The variables
+    /// that were added after it move down by one, so their [`Variable`]s refer to the next
+    /// variable afterwards; their names move along, so [`Problem::var_by_name`] finds them.
     ///
     /// # Panics
     ///
-    /// Will panic if a variable was added more than once to the left-hand side expression.
+    /// Will panic if the problem has no such variable.
+    pub fn remove_var(&mut self, var: Variable) {
+        assert!(var.0 < self.num_vars(), "variable {} does not exist", var.0);
+        self.obj_coeffs.remove(var.0);
+        self.var_mins.remove(var.0);
+        self.var_maxs.remove(var.0);
+        for constraint in &mut self.constraints {
+            if var.0 >= constraint.coeffs.dim() {
+                continue;
+            }
+            let (indices, data) = (constraint.coeffs.iter())
+                .filter(|&(idx, _)| idx != var.0)
+                .map(|(idx, coeff)| (if idx > var.0 { idx - 1 } else { idx }, coeff.clone()))
+                .unzip();
+            constraint.coeffs = CsVec::new(constraint.coeffs.dim() - 1, indices, data);
+        }
+        self.var_names.remove(var.0);
+
+        if self.invalid_var.is_some() {
+            // The invalid variable may be gone, and the later ones have moved.
+            let vars = (self.obj_coeffs.iter())
+                .zip(&self.var_mins)
+                .zip(&self.var_maxs);
+            self.invalid_var = (vars.enumerate()).find_map(|(idx, ((obj_coeff, min), max))| {
+                let var = Some(Variable(idx));
+                (check_finite(obj_coeff, "objective coefficient", var))
+                    .and_then(|()| check_bounds(min, max, var))
+                    .err()
+            });
+        }
+    }
+
+    /// Add a linear constraint to the problem. The coefficients of a variable that occurs more
+    /// than once in `expr` are summed, and terms with coefficient zero are left out.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`ModelError::NonFinite`] if a coefficient or the right-hand side is not finite;
+    /// the problem is not changed in that case.
     ///
     /// # Examples
     ///
     /// Left-hand side of the constraint can be specified in several ways:
     /// ```
-    /// # use ebi_optimisation::{*, linear_programming::*};
-    /// # use ebi_arithmetic::{*,ebi_number::*,fraction::*};
+    /// # use ebi_optimisation::{*, abnormal_fraction::AbnormalFraction, linear_programming::*};
+    /// # use ebi_arithmetic::{One, Zero};
     /// let mut problem = Problem::new(OptimisationDirection::Minimise);
-    /// let x = problem.add_var(f1!(), (f0!(), AbnormalFraction::infinity()));
-    /// let y = problem.add_var(f1!(), (f0!(), AbnormalFraction::infinity()));
+    /// let x = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+    /// let y = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
     ///
     /// // Add an x + y >= 2 constraint, specifying the left-hand side expression:
     ///
     /// // * by passing a slice of pairs (useful when explicitly enumerating variables)
-    /// problem.add_constraint(&[(x, f1!()), (y, f1!())], ComparisonOp::Ge, f!(2));
+    /// problem.add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Ge, f_ab!(2)).unwrap();
     ///
     /// // * by passing an iterator of variable-coefficient pairs.
     /// let vars = [x, y];
-    /// problem.add_constraint(vars.iter().map(|&v| (v, f1!())), ComparisonOp::Ge, f!(2));
+    /// let lhs = vars.iter().map(|&v| (v, f1_ab!()));
+    /// problem.add_constraint(lhs, ComparisonOp::Ge, f_ab!(2)).unwrap();
     ///
     /// // * by manually constructing a LinearExpr.
     /// let mut lhs = LinearExpr::empty();
     /// for &v in &vars {
-    ///     lhs.add(v, f1!());
+    ///     lhs.add(v, f1_ab!());
     /// }
-    /// problem.add_constraint(lhs, ComparisonOp::Ge, f!(2));
+    /// problem.add_constraint(lhs, ComparisonOp::Ge, f_ab!(2)).unwrap();
     /// ```
     pub fn add_constraint(
         &mut self,
         expr: impl Into<LinearExpr>,
         cmp_op: ComparisonOp,
         rhs: AbnormalFraction,
-    ) {
-        let expr = expr.into();
-        self.constraints.push((
-            CsVec::new(self.obj_coeffs.len(), expr.vars, expr.coeffs),
+    ) -> Result<(), Error> {
+        check_finite(&rhs, "right-hand side", None)?;
+        let (coeffs, constant) = expr.into().into_parts(self.obj_coeffs.len())?;
+        self.constraints
+            .push(Constraint::new(coeffs, cmp_op, &rhs - &constant));
+        Ok(())
+    }
+
+    /// Add a linear constraint whose left-hand side is given as a sparse vector indexed by
+    /// variable. Entries that are explicitly zero are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Problem::add_constraint`].
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the vector has an entry for a variable that does not exist.
+    pub fn add_constraint_sparse(
+        &mut self,
+        row: &ScatteredVec,
+        cmp_op: ComparisonOp,
+        rhs: AbnormalFraction,
+    ) -> Result<(), Error> {
+        check_finite(&rhs, "right-hand side", None)?;
+        for (var, coeff) in row.iter() {
+            check_finite(coeff, "coefficient", Some(Variable(var)))?;
+        }
+        let coeffs = row.to_csvec();
+        assert!(
+            coeffs
+                .indices()
+                .last()
+                .is_none_or(|&var| var < self.obj_coeffs.len()),
+            "constraint refers to a variable that does not exist"
+        );
+        let (indices, data) = coeffs.into_raw_storage();
+        self.constraints.push(Constraint::new(
+            CsVec::new(self.obj_coeffs.len(), indices, data),
             cmp_op,
             rhs,
         ));
+        Ok(())
     }
 
-    /// Solve the problem, finding the optimal objective function value and variable values.
+    /// Add an equality constraint `row · x == rhs` whose left-hand side is given as a sparse
+    /// vector indexed by variable. Like any equality constraint, it gets no slack variable in
+    /// the solver unless none of its variables can take the place of one in the initial basis.
     ///
     /// # Errors
     ///
-    /// Will return an error, if the problem is infeasible (constraints can't be satisfied)
-    /// or if the objective value is unbounded.
-    pub fn solve(&self) -> Result<Solution, Error> {
-        let mut solver = Solver::try_new(
-            &self.obj_coeffs,
-            &self.var_mins,
-            &self.var_maxs,
-            &self.constraints,
-        )?;
-        solver.initial_solve()?;
-        Ok(Solution {
-            num_vars: self.obj_coeffs.len(),
-            direction: self.direction,
-            solver,
-        })
+    /// Same as [`Problem::add_constraint`].
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the vector has an entry for a variable that does not exist.
+    pub fn add_constraint_eq(
+        &mut self,
+        row: &ScatteredVec,
+        rhs: AbnormalFraction,
+    ) -> Result<(), Error> {
+        self.add_constraint_sparse(row, ComparisonOp::Eq, rhs)
     }
-}
-
-/// A solution of a problem: optimal objective function value and variable values.
-///
-/// Note that a `Solution` instance contains the whole solver machinery which can require
-/// a lot of memory for larger problems. Thus saving the `Solution` instance (as opposed
-/// to getting the values of interest and discarding the solution) is mainly useful if you
-/// want to add more constraints to it later.
-#[derive(Clone)]
-pub struct Solution {
-    direction: OptimisationDirection,
-    num_vars: usize,
-    solver: Solver,
-}
 
-impl std::fmt::Debug for Solution {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // Only printing lengths here because actual data is probably huge.
-        f.debug_struct("Solution")
-            .field("direction", &self.direction)
-            .field("num_vars", &self.num_vars)
-            .field("num_constraints", &self.solver.num_constraints())
-            .field("objective", &self.objective())
-            .finish()
+    /// Add a linear constraint whose left-hand side is given as a sparse vector indexed by
+    /// variable, with sorted indices.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Problem::add_constraint`].
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the vector has an entry for a variable that does not exist. In debug
+    /// builds, will panic if an index occurs more than once.
+    pub fn add_constraint_csvec(
+        &mut self,
+        row: sprs::CsVecViewI<'_, AbnormalFraction, usize>,
+        cmp_op: ComparisonOp,
+        rhs: AbnormalFraction,
+    ) -> Result<(), Error> {
+        check_finite(&rhs, "right-hand side", None)?;
+        for (var, coeff) in row.iter() {
+            check_finite(coeff, "coefficient", Some(Variable(var)))?;
+        }
+        debug_assert!(
+            row.indices().windows(2).all(|pair| pair[0] < pair[1]),
+            "duplicate variable in constraint"
+        );
+        assert!(
+            row.indices()
+                .last()
+                .is_none_or(|&var| var < self.obj_coeffs.len()),
+            "constraint refers to a variable that does not exist"
+        );
+        self.constraints.push(Constraint::new(
+            CsVec::new(
+                self.obj_coeffs.len(),
+                row.indices().to_vec(),
+                row.data().to_vec(),
+            ),
+            cmp_op,
+            rhs,
+        ));
+        Ok(())
     }
-}
 
-impl Solution {
-    /// Optimal value of the objective function.
-    pub fn objective(&self) -> AbnormalFraction {
-        match self.direction {
-            OptimisationDirection::Minimise => self.solver.cur_obj_val.clone(),
-            OptimisationDirection::Maximise => -self.solver.cur_obj_val.clone(),
+    /// Add several linear constraints at once. See [`Problem::add_constraint`].
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Problem::add_constraint`]; the constraints before the invalid one are added.
+    pub fn add_constraints<E: Into<LinearExpr>>(
+        &mut self,
+        constraints: impl IntoIterator<Item = (E, ComparisonOp, AbnormalFraction)>,
+    ) -> Result<(), Error> {
+        let constraints = constraints.into_iter();
+        self.constraints.reserve(constraints.size_hint().0);
+        for (expr, cmp_op, rhs) in constraints {
+            self.add_constraint(expr, cmp_op, rhs)?;
         }
+        Ok(())
     }
 
-    /// Value of the variable at optimum.
+    /// Add a named linear constraint to the problem. See [`Problem::add_constraint`].
     ///
-    /// Note that you can use indexing operations to get variable values.
-    pub fn var_value(&self, var: Variable) -> &AbnormalFraction {
-        assert!(var.0 < self.num_vars);
-        self.solver.get_value(var.0)
+    /// # Errors
+    ///
+    /// Will return [`ModelError::DuplicateName`] if another constraint has the same name, and the
+    /// errors of [`Problem::add_constraint`]; the problem is not changed in that case.
+    pub fn add_constraint_named(
+        &mut self,
+        name: &str,
+        expr: impl Into<LinearExpr>,
+        cmp_op: ComparisonOp,
+        rhs: AbnormalFraction,
+    ) -> Result<(), Error> {
+        if self.constraint_names.contains(name) {
+            return Err(ModelError::DuplicateName(name.to_string()).into());
+        }
+        self.add_constraint(expr, cmp_op, rhs)?;
+        self.constraint_names
+            .insert(self.constraints.len() - 1, name);
+        Ok(())
     }
 
-    /// Iterate over the variable-value pairs of the solution.
-    pub fn iter(&self) -> SolutionIter {
-        SolutionIter {
-            solution: self,
-            var_idx: 0,
-        }
+    /// The position of the constraint with the given name in the order in which constraints
+    /// were added, if any.
+    pub fn constraint_by_name(&self, name: &str) -> Option<usize> {
+        self.constraint_names.index(name)
     }
 
-    /// Add another constraint and return the solution to the updated problem.
+    /// Add a range constraint `min <= expr <= max` to the problem.
     ///
-    /// This method will consume the solution and not return it in case of error. See also
-    /// examples of specifying the left-hand side in the docs for the [`Problem::add_constraint`]
-    /// method.
+    /// The constraint is stored as a single row, which is cheaper than adding the two
+    /// inequalities separately. Its [dual](Solution::dual) tells which side binds: when
+    /// minimising, it is positive if `min` binds and negative if `max` binds, and the other way
+    /// round when maximising.
     ///
-    /// [`Problem::add_constraint`]: struct.Problem.html#method.add_constraint
+    /// # Errors
+    ///
+    /// Will return [`ModelError::InvalidRange`] if `min > max`, and [`ModelError::NonFinite`] if a
+    /// coefficient is not finite or a bound is NaN or infinite on the wrong side; the problem
+    /// is not changed in that case.
+    pub fn add_range_constraint(
+        &mut self,
+        expr: impl Into<LinearExpr>,
+        min: AbnormalFraction,
+        max: AbnormalFraction,
+    ) -> Result<(), Error> {
+        check_bounds(&min, &max, None)?;
+        if min > max {
+            return Err(ModelError::InvalidRange.into());
+        }
+        let (coeffs, constant) = expr.into().into_parts(self.obj_coeffs.len())?;
+        self.constraints.push(Constraint {
+            coeffs,
+            min: &min - &constant,
+            max: &max - &constant,
+        });
+        Ok(())
+    }
+
+    /// Add a variable `t` that models `|expr + constant|`: `t` gets the coefficient `weight`
+    /// in the objective, and the constraints `t >= expr + constant` and
+    /// `t >= -(expr + constant)` are added. At the optimum, `t` equals the absolute value as
+    /// long as the objective pushes `t` down, that is, `weight` is positive when minimising
+    /// or negative when maximising.
+    ///
+    /// Returns `t`, whose value can be read from the solution.
     ///
     /// # Errors
     ///
-    /// Will return an error if the problem becomes infeasible with the additional constraint.
-    pub fn add_constraint(
-        mut self,
+    /// Will return [`ModelError::NonFinite`] if `constant`, `weight` or a coefficient of `expr` is
+    /// not finite; the problem is not changed in that case.
+    pub fn add_abs_term(
+        &mut self,
         expr: impl Into<LinearExpr>,
-        cmp_op: ComparisonOp,
-        rhs: AbnormalFraction,
-    ) -> Result<Self, Error> {
+        constant: AbnormalFraction,
+        weight: AbnormalFraction,
+    ) -> Result<Variable, Error> {
         let expr = expr.into();
-        self.solver.add_constraint(
-            CsVec::new(self.num_vars, expr.vars, expr.coeffs),
-            cmp_op,
-            rhs,
-        )?;
-        Ok(self)
+        check_finite(&constant, "right-hand side", None)?;
+        check_finite(&weight, "objective coefficient", None)?;
+        expr.clone().into_parts(self.obj_coeffs.len())?;
+        let t = self.add_var(weight, (f0_ab!(), AbnormalFraction::infinity()));
+
+        // t - expr >= constant; t is the last var, so its term comes last.
+        let mut upper = LinearExpr::empty();
+        for (&var, coeff) in expr.vars.iter().zip(&expr.coeffs) {
+            upper.add(Variable(var), -coeff.clone());
+        }
+        upper.add(t, f1_ab!());
+        upper.add_constant(-expr.constant.clone());
+        self.add_constraint(upper, ComparisonOp::Ge, constant.clone())?;
+
+        // t + expr >= -constant
+        let mut lower = expr;
+        lower.add(t, f1_ab!());
+        self.add_constraint(lower, ComparisonOp::Ge, -constant)?;
+        Ok(t)
     }
 
-    /// Fix the variable to the specified value and return the solution to the updated problem.
+    /// Add a variable `t` that models the maximum of several terms `expr + constant`: `t`
+    /// gets the coefficient `weight` in the objective, and a constraint `t >= expr + constant`
+    /// is added per term. As with [`Problem::add_abs_term`], the objective must push `t` down.
     ///
-    /// This method will consume the solution and not return it in case of error.
+    /// Returns `t`, whose value can be read from the solution.
     ///
     /// # Errors
     ///
-    /// Will return an error if the problem becomes infeasible with the additional constraint.
-    pub fn fix_var(mut self, var: Variable, val: AbnormalFraction) -> Result<Self, Error> {
-        assert!(var.0 < self.num_vars);
-        self.solver.fix_var(var.0, val)?;
-        Ok(self)
+    /// Will return [`ModelError::NonFinite`] if `weight`, a constant or a coefficient of one of the
+    /// expressions is not finite; the problem is not changed in that case.
+    pub fn add_max_term<E: Into<LinearExpr>>(
+        &mut self,
+        terms: impl IntoIterator<Item = (E, AbnormalFraction)>,
+        weight: AbnormalFraction,
+    ) -> Result<Variable, Error> {
+        check_finite(&weight, "objective coefficient", None)?;
+        let terms = (terms.into_iter())
+            .map(|(expr, constant)| (expr.into(), constant))
+            .collect::<Vec<(LinearExpr, _)>>();
+        for (expr, constant) in &terms {
+            check_finite(constant, "right-hand side", None)?;
+            expr.clone().into_parts(self.obj_coeffs.len())?;
+        }
+        let t = self.add_var(
+            weight,
+            (
+                AbnormalFraction::neg_infinity(),
+                AbnormalFraction::infinity(),
+            ),
+        );
+        for (expr, constant) in terms {
+            // t - expr >= constant
+            let mut constraint = LinearExpr::empty();
+            for (var, coeff) in expr.vars.into_iter().zip(expr.coeffs) {
+                constraint.add(Variable(var), -coeff);
+            }
+            constraint.add(t, f1_ab!());
+            constraint.add_constant(-expr.constant);
+            self.add_constraint(constraint, ComparisonOp::Ge, constant)?;
+        }
+        Ok(t)
     }
 
-    /// If the variable was fixed with [`fix_var`](#method.fix_var) before, remove that constraint
-    /// and return the solution to the updated problem and a boolean indicating if the variable was
-    /// really fixed before.
-    pub fn unfix_var(mut self, var: Variable) -> (Self, bool) {
-        assert!(var.0 < self.num_vars);
-        let res = self.solver.unfix_var(var.0);
-        (self, res)
+    /// The bounds `(min, max)` of the left-hand side of a constraint; an inequality has one
+    /// infinite bound.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there is no constraint at this position.
+    pub fn constraint_bounds(&self, constraint: usize) -> (&AbnormalFraction, &AbnormalFraction) {
+        let constraint = &self.constraints[constraint];
+        (&constraint.min, &constraint.max)
     }
 
-    // TODO: remove_constraint
+    /// Change the right-hand side of an equality or inequality constraint. The basis of a
+    /// previous solution remains dual feasible, so solving with [`Problem::solve_from_basis`]
+    /// and that basis only needs the dual simplex to repair the violated rows.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`ModelError::NonFinite`] if `rhs` is not finite, and
+    /// [`ModelError::NoSingleRhs`] if the constraint is a range constraint or has no finite
+    /// bound; use [`Problem::set_constraint_bounds`] for those. The problem is not changed in
+    /// that case.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there is no constraint at this position.
+    pub fn set_rhs(&mut self, constraint: usize, rhs: AbnormalFraction) -> Result<(), Error> {
+        check_finite(&rhs, "right-hand side", None)?;
+        self.check_single_rhs(constraint)?;
+        let constraint = &mut self.constraints[constraint];
+        if constraint.is_equality() {
+            constraint.min = rhs.clone();
+            constraint.max = rhs;
+        } else if constraint.max.is_finite() {
+            constraint.max = rhs;
+        } else {
+            constraint.min = rhs;
+        }
+        Ok(())
+    }
 
-    /// Add a [Gomory cut] constraint to the problem and return the solution.
+    /// Change the right-hand sides of several constraints. See [`Problem::set_rhs`].
     ///
-    /// [Gomory cut]: https://en.wikipedia.org/wiki/Cutting-plane_method#Gomory's_cut
+    /// # Errors
+    ///
+    /// Same as [`Problem::set_rhs`]; no right-hand side is changed in that case.
+    ///
+    /// # Panics
+    ///
+    /// Same as [`Problem::set_rhs`].
+    pub fn set_rhs_many(
+        &mut self,
+        rhs: impl IntoIterator<Item = (usize, AbnormalFraction)>,
+    ) -> Result<(), Error> {
+        let rhs = rhs.into_iter().collect::<Vec<_>>();
+        for (constraint, rhs) in &rhs {
+            check_finite(rhs, "right-hand side", None)?;
+            self.check_single_rhs(*constraint)?;
+        }
+        for (constraint, rhs) in rhs {
+            self.set_rhs(constraint, rhs)?;
+        }
+        Ok(())
+    }
+
+    /// Check that the constraint is an equality or has exactly one finite bound.
+    fn check_single_rhs(&self, constraint: usize) -> Result<(), Error> {
+        let Constraint { min, max, .. } = &self.constraints[constraint];
+        if min == max || min.is_finite() != max.is_finite() {
+            Ok(())
+        } else {
+            Err(ModelError::NoSingleRhs(constraint).into())
+        }
+    }
+
+    /// Replace the bounds of the left-hand side of a constraint by `min <= expr <= max`; use
+    /// infinite bounds for an inequality. See [`Problem::set_rhs`].
     ///
     /// # Errors
     ///
-    /// Will return an error if the problem becomes infeasible with the additional constraint.
+    /// Will return [`ModelError::InvalidRange`] if `min > max`, and [`ModelError::NonFinite`] if a bound
+    /// is NaN or infinite on the wrong side; the problem is not changed in that case.
     ///
     /// # Panics
     ///
-    /// Will panic if the variable is not basic (variable is basic if it has value other than
-    /// its bounds).
-    pub fn add_gomory_cut(mut self, var: Variable) -> Result<Self, Error> {
-        assert!(var.0 < self.num_vars);
-        self.solver.add_gomory_cut(var.0)?;
-        Ok(self)
+    /// Will panic if there is no constraint at this position.
+    pub fn set_constraint_bounds(
+        &mut self,
+        constraint: usize,
+        min: AbnormalFraction,
+        max: AbnormalFraction,
+    ) -> Result<(), Error> {
+        check_bounds(&min, &max, None)?;
+        if min > max {
+            return Err(ModelError::InvalidRange.into());
+        }
+        let constraint = &mut self.constraints[constraint];
+        constraint.min = min;
+        constraint.max = max;
+        Ok(())
     }
-}
 
-impl std::ops::Index<Variable> for Solution {
-    type Output = AbnormalFraction;
+    /// The size of the problem as the solver will see it, with estimates of the memory it
+    /// needs, and structural properties such as dense columns and empty or free rows.
+    pub fn size_report(&self) -> SizeReport {
+        size_report(self.obj_coeffs.len(), &self.constraints)
+    }
 
-    fn index(&self, var: Variable) -> &Self::Output {
-        self.var_value(var)
+    /// Write the problem in the CPLEX LP file format, for use by other solvers or to inspect
+    /// it. Variables and constraints appear under their names; unnamed variables are written
+    /// as `#` followed by their index, and unnamed constraints without a label. Numbers are
+    /// written as the nearest float.
+    ///
+    /// # Errors
+    ///
+    /// Will return the errors of writing to `out`.
+    pub fn write_lp(&self, out: impl std::io::Write) -> std::io::Result<()> {
+        LpFile {
+            direction: self.direction,
+            obj_coeffs: &self.obj_coeffs,
+            obj_constant: &self.obj_constant,
+            var_mins: &self.var_mins,
+            var_maxs: &self.var_maxs,
+            constraints: &self.constraints,
+            var_names: &self.var_names,
+            constraint_names: &self.constraint_names,
+        }
+        .write(out)
     }
-}
 
-/// An iterator over the variable-value pairs of a [`Solution`].
-#[derive(Debug, Clone)]
-pub struct SolutionIter<'a> {
-    solution: &'a Solution,
-    var_idx: usize,
-}
+    /// The problem in the standard form min cᵀx subject to Ax = b, x ≥ 0, for use by other
+    /// solvers. [`StandardForm::recover_solution`] maps a solution of the standard form back to
+    /// the variables of this problem.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`ModelError::NonFinite`] or [`ModelError::InvalidBounds`] if a variable was added
+    /// with an invalid objective coefficient or bounds.
+    pub fn to_standard_form(&self) -> Result<StandardForm, Error> {
+        if let Some(err) = &self.invalid_var {
+            return Err(err.clone());
+        }
+        let mut form = standard_form(
+            &self.obj_coeffs,
+            &self.var_mins,
+            &self.var_maxs,
+            &self.constraints,
+        );
+        form.obj_offset += self.minimised_obj_constant();
+        Ok(form)
+    }
 
-impl<'a> Iterator for SolutionIter<'a> {
-    type Item = (Variable, &'a AbnormalFraction);
+    /// Solve the problem, finding the optimal objective function value and variable values.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error, if the problem is infeasible (constraints can't be satisfied)
+    /// or if the objective value is unbounded.
+    pub fn solve(&self) -> Result<Solution, Error> {
+        self.solve_with_options(&SolveOptions::default())
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.var_idx < self.solution.num_vars {
-            let var_idx = self.var_idx;
-            self.var_idx += 1;
-            Some((Variable(var_idx), self.solution.solver.get_value(var_idx)))
+    /// Solve the problem using the given options.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Problem::solve`].
+    pub fn solve_with_options(&self, options: &SolveOptions) -> Result<Solution, Error> {
+        self.solve_impl(options, None)
+    }
+
+    /// The optimal objective value, without keeping the [`Solution`]. If the objective is
+    /// unbounded, this is infinity when maximising and minus infinity when minimising, so
+    /// callers that only need the value can treat both cases alike.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Problem::solve`], except that an unbounded objective is not an error.
+    pub fn optimal_value(&self, options: &SolveOptions) -> Result<AbnormalFraction, Error> {
+        match self.solve_with_options(options) {
+            Ok(solution) => Ok(solution.objective()),
+            Err(Error::Unbounded(_)) => Ok(match self.direction {
+                OptimisationDirection::Minimise => AbnormalFraction::neg_infinity(),
+                OptimisationDirection::Maximise => AbnormalFraction::infinity(),
+            }),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Enumerate up to `limit` distinct optimal vertices, starting with the solution of
+    /// [`Problem::solve`]. From each optimal basis, every non-basic variable with a zero reduced
+    /// cost is pivoted into the basis, which leads to an adjacent basis with the same
+    /// objective; bases are explored breadth first. Vertices are compared by the exact values
+    /// of the variables, so bases of a degenerate vertex count once. Moves along which the
+    /// optimum extends without limit are skipped, as they reach no other vertex.
+    ///
+    /// The number of bases explored can grow quickly with the size of the problem, so this is
+    /// meant for small problems.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Problem::solve`].
+    pub fn alternative_optima(&self, limit: usize) -> Result<Vec<Solution>, Error> {
+        if limit == 0 {
+            return Ok(vec![]);
+        }
+        let first = self.solve()?;
+        let mut bases = vec![first.solver.basis_statuses()];
+        let mut queue = VecDeque::from([first.clone()]);
+        let mut vertices = vec![first];
+
+        while let Some(solution) = queue.pop_front() {
+            for (col, increase) in solution.solver.zero_cost_moves() {
+                if vertices.len() >= limit {
+                    return Ok(vertices);
+                }
+                let mut next = solution.clone();
+                let moved = next.solver.move_nonbasic(col, increase);
+                if !moved.map_err(|err| next.problem_error(err))? {
+                    continue;
+                }
+                let basis = next.solver.basis_statuses();
+                if bases.contains(&basis) {
+                    continue;
+                }
+                bases.push(basis);
+                next.unscale();
+
+                let is_new = vertices
+                    .iter()
+                    .all(|vertex| (vertex.iter().zip(next.iter())).any(|((_, a), (_, b))| a != b));
+                if is_new {
+                    vertices.push(next.clone());
+                }
+                queue.push_back(next);
+            }
+        }
+        Ok(vertices)
+    }
+
+    /// Optimise several objectives in order of priority: each objective is optimised among the
+    /// optimal solutions of the objectives before it. The objective of the problem itself is
+    /// ignored.
+    ///
+    /// After an objective is optimised, a constraint keeps its value at the optimum, and the
+    /// next objective is optimised starting from the current basis. Returns the solution for
+    /// the last objective, and the value of every objective in that solution.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if no objectives are given.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Problem::solve`]; an objective can only be unbounded if all objectives before
+    /// it are optimal.
+    pub fn solve_lexicographic(
+        &self,
+        objectives: &[ObjectiveSpec],
+    ) -> Result<(Solution, Vec<AbnormalFraction>), Error> {
+        let (first, rest) = objectives
+            .split_first()
+            .expect("at least one objective is needed");
+        let num_vars = self.obj_coeffs.len();
+
+        let mut problem = self.clone();
+        problem.direction = first.direction;
+        problem.obj_coeffs = first.obj_coeffs(num_vars);
+        problem.obj_constant = first.expr.constant.clone();
+        let mut solution = problem.solve()?;
+        let mut values = vec![solution.objective()];
+
+        for (prev, objective) in objectives.iter().zip(rest) {
+            // An inequality suffices, as the value cannot be improved further.
+            let cmp_op = match prev.direction {
+                OptimisationDirection::Minimise => ComparisonOp::Le,
+                OptimisationDirection::Maximise => ComparisonOp::Ge,
+            };
+            let prev_value = values.last().unwrap().clone();
+            solution = solution.add_constraint(prev.expr.clone(), cmp_op, prev_value)?;
+
+            solution.direction = objective.direction;
+            solution.obj_constant = objective.expr.constant.clone();
+            solution
+                .solver
+                .change_objective(&objective.obj_coeffs(num_vars))?;
+            values.push(solution.objective());
+        }
+
+        // The added constraints keep the earlier objectives at their values.
+        Ok((solution, values))
+    }
+
+    /// Solve the problem, starting the simplex from a basis extracted from the solution of an
+    /// identical problem with [`Solution::extract_basis`]. If the basis is still optimal, no
+    /// simplex iterations are needed.
+    ///
+    /// A basis that is not valid for the problem, for instance because it has too many or too
+    /// few basic variables, is repaired rather than rejected.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`ModelError::BasisMismatch`] if the basis does not have a status for every
+    /// variable and constraint of the problem. Otherwise, same as [`Problem::solve`].
+    pub fn solve_from_basis(&self, basis: &Basis) -> Result<Solution, Error> {
+        self.solve_from_basis_with_options(basis, &SolveOptions::default())
+    }
+
+    /// Solve the problem from a basis using the given options. See
+    /// [`Problem::solve_from_basis`].
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Problem::solve_from_basis`].
+    pub fn solve_from_basis_with_options(
+        &self,
+        basis: &Basis,
+        options: &SolveOptions,
+    ) -> Result<Solution, Error> {
+        if basis.var_statuses().len() != self.obj_coeffs.len()
+            || basis.row_statuses().len() != self.constraints.len()
+        {
+            return Err(ModelError::BasisMismatch.into());
+        }
+        self.solve_impl(options, Some(basis))
+    }
+
+    /// Find an irreducible infeasible subsystem (IIS) of an infeasible problem: a set of
+    /// constraints and variable bounds that cannot be satisfied simultaneously, but can be if
+    /// any one of them is removed. Returns `None` if the problem is feasible.
+    ///
+    /// Uses the deletion filter, which solves a feasibility problem for every constraint and
+    /// finite variable bound; [`IisOptions::max_solves`] limits the number of solves.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::Interrupted`] if a solve was interrupted through the options of
+    /// [`IisOptions::solve_options`].
+    pub fn find_iis(&self, options: &IisOptions) -> Result<Option<Iis>, Error> {
+        if let Some(err) = &self.invalid_var {
+            return Err(err.clone());
+        }
+        let num_vars = self.obj_coeffs.len();
+        deletion_filter(
+            &self.var_mins,
+            &self.var_maxs,
+            &self.constraints,
+            options.max_solves,
+            |subsystem| {
+                let problem = Problem {
+                    obj_coeffs: vec![f0_ab!(); num_vars],
+                    var_mins: (self.var_mins.iter().zip(&subsystem.mins))
+                        .map(|(min, &active)| {
+                            if active {
+                                min.clone()
+                            } else {
+                                AbnormalFraction::neg_infinity()
+                            }
+                        })
+                        .collect(),
+                    var_maxs: (self.var_maxs.iter().zip(&subsystem.maxs))
+                        .map(|(max, &active)| {
+                            if active {
+                                max.clone()
+                            } else {
+                                AbnormalFraction::infinity()
+                            }
+                        })
+                        .collect(),
+                    constraints: (self.constraints.iter().zip(&subsystem.constraints))
+                        .filter(|(_, active)| **active)
+                        .map(|(constraint, _)| constraint.clone())
+                        .collect(),
+                    ..Problem::new(OptimisationDirection::Minimise)
+                };
+                match problem.solve_with_options(&options.solve_options) {
+                    Ok(_) => Ok(true),
+                    Err(Error::Infeasible(_)) => Ok(false),
+                    Err(err) => Err(err),
+                }
+            },
+        )
+    }
+
+    /// Check a solution of this problem independently of the solver: recompute the left-hand
+    /// side of every constraint and the objective from the values of the variables, and
+    /// compare them with the bounds and with [`Solution::objective`]. All computations are
+    /// exact.
+    ///
+    /// # Errors
+    ///
+    /// Will return every violated bound, and a mismatch of the objective, if any.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the solution has a different number of variables than the problem.
+    pub fn verify(&self, solution: &Solution) -> Result<(), Vec<SolutionViolation>> {
+        self.verify_with_tolerance(solution, &f0_ab!())
+    }
+
+    /// Same as [`Problem::verify`], but ignores violations of at most `tolerance`. Useful with
+    /// approximate arithmetic.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Problem::verify`].
+    ///
+    /// # Panics
+    ///
+    /// Same as [`Problem::verify`].
+    pub fn verify_with_tolerance(
+        &self,
+        solution: &Solution,
+        tolerance: &AbnormalFraction,
+    ) -> Result<(), Vec<SolutionViolation>> {
+        assert_eq!(solution.num_vars, self.obj_coeffs.len());
+        let values = (0..solution.num_vars)
+            .map(|var| solution[Variable(var)].clone())
+            .collect::<Vec<_>>();
+        let mut violations = match self.verify_values(&values, tolerance) {
+            Ok(()) => vec![],
+            Err(violations) => violations,
+        };
+
+        let mut computed = f0_ab!();
+        for (obj_coeff, val) in self.obj_coeffs.iter().zip(&values) {
+            if !val.is_zero() {
+                computed += obj_coeff * val;
+            }
+        }
+        if matches!(self.direction, OptimisationDirection::Maximise) {
+            computed = -computed;
+        }
+        computed += self.obj_constant.clone();
+        let reported = solution.objective();
+        if differ(&reported, &computed, tolerance) {
+            violations.push(SolutionViolation::ObjectiveMismatch { reported, computed });
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Check values of the variables, for instance from another solver, against the bounds
+    /// and the constraints of this problem. See [`Problem::verify_with_tolerance`].
+    ///
+    /// # Errors
+    ///
+    /// Will return every bound that is violated by more than `tolerance`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there is not exactly one value per variable.
+    pub fn verify_values(
+        &self,
+        values: &[AbnormalFraction],
+        tolerance: &AbnormalFraction,
+    ) -> Result<(), Vec<SolutionViolation>> {
+        assert_eq!(values.len(), self.obj_coeffs.len());
+        let violations = check_values(
+            values,
+            &self.var_mins,
+            &self.var_maxs,
+            &self.constraints,
+            tolerance,
+        );
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    fn solve_impl(&self, options: &SolveOptions, basis: Option<&Basis>) -> Result<Solution, Error> {
+        if let Some(err) = &self.invalid_var {
+            return Err(err.clone());
+        }
+        self.check_empty_constraints()?;
+        let presolved = if options.presolve {
+            Some(presolve(
+                &self.obj_coeffs,
+                &self.var_mins,
+                &self.var_maxs,
+                &self.constraints,
+                options.remove_dependent_rows,
+            )?)
+        } else {
+            None
+        };
+        let (obj_coeffs, var_mins, var_maxs, constraints) = match &presolved {
+            Some(presolved) => (
+                &presolved.obj_coeffs,
+                &presolved.var_mins,
+                &presolved.var_maxs,
+                &presolved.constraints,
+            ),
+            None => (
+                &self.obj_coeffs,
+                &self.var_mins,
+                &self.var_maxs,
+                &self.constraints,
+            ),
+        };
+
+        let scaled = if options.scaling.is_enabled() {
+            Some(scale(
+                obj_coeffs,
+                var_mins,
+                var_maxs,
+                constraints,
+                options.deterministic,
+            ))
         } else {
             None
+        };
+        let (obj_coeffs, var_mins, var_maxs, constraints) = match &scaled {
+            Some(scaled) => (
+                &scaled.obj_coeffs,
+                &scaled.var_mins,
+                &scaled.var_maxs,
+                &scaled.constraints,
+            ),
+            None => (obj_coeffs, var_mins, var_maxs, constraints),
+        };
+
+        let mut solver = Solver::try_new(obj_coeffs, var_mins, var_maxs, constraints, options)?;
+        if let Some(reporter) = &mut solver.reporter {
+            let constant = self.minimised_obj_constant();
+            reporter.set_objective(
+                matches!(self.direction, OptimisationDirection::Maximise),
+                Some(match &presolved {
+                    Some(presolved) => &presolved.postsolve.obj_offset + &constant,
+                    None => constant,
+                }),
+            );
+        }
+        let postsolve = presolved.as_ref().map(|presolved| &presolved.postsolve);
+        let rows = solver_rows(self.constraints.len(), postsolve, constraints);
+        let mut statuses = None;
+        if let Some(basis) = basis {
+            let num_solver_vars = obj_coeffs.len();
+            let mut solver_statuses =
+                vec![BasisStatus::NonbasicLower; num_solver_vars + solver.num_constraints()];
+            for (var, status) in basis.var_statuses().iter().enumerate() {
+                let solver_var = match postsolve {
+                    Some(postsolve) => postsolve.reduced_var(var),
+                    None => Some(var),
+                };
+                if let Some(solver_var) = solver_var {
+                    solver_statuses[solver_var] = *status;
+                }
+            }
+            for (row, status) in basis.row_statuses().iter().enumerate() {
+                if let Some(solver_row) = rows[row] {
+                    solver_statuses[num_solver_vars + solver_row] = slack_status(*status);
+                }
+            }
+            statuses = Some(solver_statuses);
+        } else if options.method == Method::HybridExact && f1_ab!().is_exact() {
+            let approximate =
+                approximate_basis(obj_coeffs, var_mins, var_maxs, constraints, options);
+            if let Some((approximate_statuses, iterations)) = approximate {
+                statuses = Some(approximate_statuses);
+                solver.stats.approximate_iterations = iterations;
+            }
+        }
+        if let Some(presolved) = &presolved {
+            solver.stats.presolve_removed_rows = presolved.postsolve.num_removed_rows();
+            solver.stats.presolve_removed_vars = presolved.postsolve.num_removed_vars();
+        }
+        let solved = initial_solve(
+            &mut solver,
+            statuses.as_deref(),
+            var_mins,
+            var_maxs,
+            constraints,
+        );
+
+        let mut solution = Solution {
+            num_vars: self.obj_coeffs.len(),
+            var_names: self.var_names.clone(),
+            direction: self.direction,
+            obj_constant: self.obj_constant.clone(),
+            solver,
+            rows,
+            added_rows: vec![],
+            postsolve: presolved.map(|presolved| presolved.postsolve),
+            scale_factors: scaled.map(|scaled| scaled.scale_factors),
+        };
+        solved.map_err(|err| solution.problem_error(err))?;
+        solution.unscale();
+        Ok(solution)
+    }
+
+    /// Check that no constraint without variables excludes zero; the solver and presolve
+    /// leave such constraints out.
+    fn check_empty_constraints(&self) -> Result<(), Error> {
+        for (row, constraint) in self.constraints.iter().enumerate() {
+            if constraint.coeffs.indices().is_empty() && !constraint.is_satisfied_by(&f0_ab!()) {
+                let mut multipliers = vec![f0_ab!(); self.constraints.len()];
+                multipliers[row] = if constraint.min.is_positive() {
+                    f1_ab!()
+                } else {
+                    -f1_ab!()
+                };
+                return Err(Error::Infeasible(InfeasibilityCertificate::new(
+                    multipliers,
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A solution of a problem: optimal objective function value and variable values.
+///
+/// Note that a `Solution` instance contains the whole solver machinery which can require
+/// a lot of memory for larger problems. Thus saving the `Solution` instance (as opposed
+/// to getting the values of interest and discarding the solution) is mainly useful if you
+/// want to add more constraints to it later.
+#[derive(Clone)]
+pub struct Solution {
+    direction: OptimisationDirection,
+    /// The constant term of the objective, not negated when maximising.
+    obj_constant: AbnormalFraction,
+    num_vars: usize,
+    var_names: Names,
+    solver: Solver,
+    /// For each constraint of the problem: its row in the solver, unless presolve removed it or
+    /// it had no coefficients.
+    rows: Vec<Option<usize>>,
+    /// For each constraint and cut added to the solution: its row in the solver, unless it had
+    /// no coefficients there, and its coefficients on the variables of the problem. Only those
+    /// on the variables that presolve removed are needed, so a cut, which never has them,
+    /// keeps none.
+    added_rows: Vec<(Option<usize>, CsVec)>,
+    postsolve: Option<Postsolve>,
+    scale_factors: Option<ScaleFactors>,
+}
+
+impl std::fmt::Debug for Solution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Only printing lengths here because actual data is probably huge.
+        f.debug_struct("Solution")
+            .field("direction", &self.direction)
+            .field("num_vars", &self.num_vars)
+            .field("num_constraints", &self.solver.num_constraints())
+            .field("objective", &self.objective())
+            .finish()
+    }
+}
+
+impl Solution {
+    /// Optimal value of the objective function. A solution always has a finite objective; see
+    /// [`Problem::optimal_value`] for a value that is infinite if the objective is unbounded.
+    pub fn objective(&self) -> AbnormalFraction {
+        let obj_val = match &self.postsolve {
+            Some(postsolve) => &self.solver.cur_obj_val + &postsolve.obj_offset,
+            None => self.solver.cur_obj_val.clone(),
+        };
+        let obj_val = match self.direction {
+            OptimisationDirection::Minimise => obj_val,
+            OptimisationDirection::Maximise => -obj_val,
+        };
+        &obj_val + &self.obj_constant
+    }
+
+    /// Counters collected by the solver, accumulated over the initial solve and all
+    /// subsequent modifications of this solution.
+    pub fn stats(&self) -> &SolveStats {
+        &self.solver.stats
+    }
+
+    /// Whether no non-basic variable, including the slack of a constraint, has a zero reduced
+    /// cost, which means that the optimum is unique. If this is false, the optimum may still be
+    /// unique if the optimal vertex is degenerate; [`Problem::alternative_optima`] tells for
+    /// sure on small problems.
+    pub fn is_unique(&self) -> bool {
+        self.solver.zero_cost_moves().is_empty()
+    }
+
+    /// Value of the variable at optimum.
+    ///
+    /// Note that you can use indexing operations to get variable values.
+    pub fn var_value(&self, var: Variable) -> &AbnormalFraction {
+        assert!(var.0 < self.num_vars);
+        match (self.solver_var(var), &self.scale_factors) {
+            (Some(solver_var), Some(scale_factors)) => scale_factors.value(solver_var),
+            (Some(solver_var), None) => self.solver.get_value(solver_var),
+            (None, _) => self.postsolve.as_ref().unwrap().removed_value(var.0),
+        }
+    }
+
+    /// The name of the variable, if it was added with [`Problem::add_var_named`].
+    pub fn var_name(&self, var: Variable) -> Option<&str> {
+        self.var_names.get(var.0)
+    }
+
+    /// Dual value (shadow price) of a constraint of the problem: the change of the optimal
+    /// objective per unit increase of the bound of the constraint that binds, as long as the
+    /// optimal basis stays the same. A constraint that does not bind has a zero dual.
+    ///
+    /// When minimising, the dual is non-negative if the lower bound (`>=`) of the constraint
+    /// binds and non-positive if the upper bound (`<=`) binds; when maximising, it is the
+    /// other way round. Constraints added to the solution are not numbered here.
+    ///
+    /// This solves with the basis matrix; use [`Solution::duals`] for many constraints.
+    pub fn dual(&self, constraint: usize) -> AbnormalFraction {
+        assert!(constraint < self.rows.len());
+        self.dual_values().0.swap_remove(constraint)
+    }
+
+    /// [Dual values](Solution::dual) of all constraints of the problem.
+    pub fn duals(&self) -> Vec<AbnormalFraction> {
+        self.dual_values().0
+    }
+
+    /// Reduced cost of a variable: the change of the optimal objective per unit increase of
+    /// the bound at which the variable is held, as long as the optimal basis stays the same.
+    /// A variable strictly between its bounds has a zero reduced cost. The sign follows the
+    /// same convention as that of [`Solution::dual`].
+    ///
+    /// This solves with the basis matrix; use [`Solution::reduced_costs`] for many variables.
+    pub fn reduced_cost(&self, var: Variable) -> AbnormalFraction {
+        assert!(var.0 < self.num_vars);
+        self.dual_values().1.swap_remove(var.0)
+    }
+
+    /// [Reduced costs](Solution::reduced_cost) of all variables.
+    pub fn reduced_costs(&self) -> Vec<AbnormalFraction> {
+        self.dual_values().1
+    }
+
+    /// The duals of the constraints and the reduced costs of the variables of the problem.
+    fn dual_values(&self) -> (Vec<AbnormalFraction>, Vec<AbnormalFraction>) {
+        let (mut duals, mut reduced_costs) = self.problem_multipliers(&self.solver.duals(), false);
+        duals.truncate(self.rows.len());
+        if let OptimisationDirection::Maximise = self.direction {
+            for val in duals.iter_mut().chain(&mut reduced_costs) {
+                *val = -val.clone();
+            }
+        }
+        (duals, reduced_costs)
+    }
+
+    /// Translate multipliers of the rows of the solver, its duals or those of an
+    /// infeasibility certificate (`is_ray`), to the constraints of the problem followed by the
+    /// added ones, along with the reduced costs of the variables; see
+    /// [`Postsolve::complete_duals`].
+    fn problem_multipliers(
+        &self,
+        solver_multipliers: &[AbnormalFraction],
+        is_ray: bool,
+    ) -> (Vec<AbnormalFraction>, Vec<AbnormalFraction>) {
+        let mut solver_multipliers = solver_multipliers.to_vec();
+        solver_multipliers.resize(self.solver.num_constraints(), f0_ab!());
+        let mut multipliers = (self.rows.iter().enumerate())
+            .map(
+                |(row, solver_row)| match (solver_row, &self.scale_factors) {
+                    (Some(solver_row), Some(scale_factors)) => {
+                        let scaled_row = match &self.postsolve {
+                            Some(postsolve) => postsolve.reduced_row(row).unwrap(),
+                            None => row,
+                        };
+                        scale_factors.unscale_dual(scaled_row, &solver_multipliers[*solver_row])
+                    }
+                    (Some(solver_row), None) => solver_multipliers[*solver_row].clone(),
+                    (None, _) => f0_ab!(),
+                },
+            )
+            .collect::<Vec<_>>();
+        // The added rows are not scaled.
+        multipliers.extend(
+            (self.added_rows.iter()).map(|(solver_row, _)| match solver_row {
+                Some(solver_row) => solver_multipliers[*solver_row].clone(),
+                None => f0_ab!(),
+            }),
+        );
+        let mut reduced_costs = (0..self.num_vars)
+            .map(|var| match self.solver_var(Variable(var)) {
+                Some(solver_var) => {
+                    let reduced_cost =
+                        (self.solver).reduced_cost(solver_var, &solver_multipliers, is_ray);
+                    match &self.scale_factors {
+                        Some(scale_factors) => {
+                            scale_factors.unscale_reduced_cost(solver_var, &reduced_cost)
+                        }
+                        None => reduced_cost,
+                    }
+                }
+                None => f0_ab!(),
+            })
+            .collect::<Vec<_>>();
+        self.complete_multipliers(&mut multipliers, &mut reduced_costs, is_ray);
+        (multipliers, reduced_costs)
+    }
+
+    /// Complete the multipliers and the reduced costs for the variables and constraints that
+    /// presolve removed: the added rows still contain the removed variables.
+    fn complete_multipliers(
+        &self,
+        multipliers: &mut [AbnormalFraction],
+        reduced_costs: &mut [AbnormalFraction],
+        is_ray: bool,
+    ) {
+        let Some(postsolve) = &self.postsolve else {
+            return;
+        };
+        let added_multipliers = &multipliers[self.rows.len()..];
+        for ((_, coeffs), multiplier) in self.added_rows.iter().zip(added_multipliers) {
+            for (var, coeff) in coeffs.iter() {
+                if postsolve.reduced_var(var).is_none() {
+                    reduced_costs[var] -= coeff * multiplier;
+                }
+            }
+        }
+        let num_rows = self.rows.len();
+        postsolve.complete_duals(&mut multipliers[..num_rows], reduced_costs, is_ray);
+    }
+
+    /// Translate an error of the solver to the problem: the certificate and the ray to the
+    /// constraints and variables of the problem, and an interruption to the point it reached.
+    fn problem_error(&mut self, err: Error) -> Error {
+        match err {
+            Error::Infeasible(certificate) => {
+                let (multipliers, _) = self.problem_multipliers(certificate.multipliers(), true);
+                Error::Infeasible(InfeasibilityCertificate::new(multipliers))
+            }
+            Error::Unbounded(ray) => {
+                let direction = (0..self.num_vars)
+                    .map(|var| match self.solver_var(Variable(var)) {
+                        Some(solver_var) => match &self.scale_factors {
+                            Some(scale_factors) => scale_factors
+                                .unscale_value(solver_var, &ray.direction()[solver_var]),
+                            None => ray.direction()[solver_var].clone(),
+                        },
+                        // The variables that presolve removed keep their values.
+                        None => f0_ab!(),
+                    })
+                    .collect();
+                Error::Unbounded(UnboundedRay::new(direction))
+            }
+            Error::Interrupted(_) => {
+                self.unscale();
+                let values = (0..self.num_vars)
+                    .map(|var| self.var_value(Variable(var)).clone())
+                    .collect();
+                Error::Interrupted(PartialSolution::new(
+                    values,
+                    self.extract_basis(),
+                    self.solver.is_primal_feasible,
+                ))
+            }
+            err => err,
+        }
+    }
+
+    /// Iterate over the variables with a non-zero value, labelled by their name if they have
+    /// one.
+    pub fn iter_nonzero_labelled(&self) -> impl Iterator<Item = (VarLabel<'_>, &AbnormalFraction)> {
+        self.iter()
+            .filter(|(_, val)| !val.is_zero())
+            .map(|(var, val)| {
+                let label = match self.var_name(var) {
+                    Some(name) => VarLabel::Name(name),
+                    None => VarLabel::Index(var.0),
+                };
+                (label, val)
+            })
+    }
+
+    /// Iterate over the variable-value pairs of the solution.
+    pub fn iter(&self) -> SolutionIter<'_> {
+        SolutionIter {
+            solution: self,
+            var_idx: 0,
+        }
+    }
+
+    /// Add another constraint and return the solution to the updated problem.
+    ///
+    /// This method will consume the solution and not return it in case of error. See also
+    /// examples of specifying the left-hand side in the docs for the [`Problem::add_constraint`]
+    /// method.
+    ///
+    /// [`Problem::add_constraint`]: struct.Problem.html#method.add_constraint
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the problem becomes infeasible with the additional constraint,
+    /// or for the invalid constraints rejected by [`Problem::add_constraint`].
+    pub fn add_constraint(
+        mut self,
+        expr: impl Into<LinearExpr>,
+        cmp_op: ComparisonOp,
+        rhs: AbnormalFraction,
+    ) -> Result<Self, Error> {
+        check_finite(&rhs, "right-hand side", None)?;
+        let (coeffs, constant) = expr.into().into_parts(self.num_vars)?;
+        self.add_solver_constraint(Constraint::new(coeffs, cmp_op, &rhs - &constant))?;
+        Ok(self)
+    }
+
+    /// Add a range constraint `min <= expr <= max` and return the solution to the updated
+    /// problem.
+    ///
+    /// This method will consume the solution and not return it in case of error.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if `min > max` or if the problem becomes infeasible with the
+    /// additional constraint, or for the invalid constraints rejected by
+    /// [`Problem::add_range_constraint`].
+    pub fn add_range_constraint(
+        mut self,
+        expr: impl Into<LinearExpr>,
+        min: AbnormalFraction,
+        max: AbnormalFraction,
+    ) -> Result<Self, Error> {
+        check_bounds(&min, &max, None)?;
+        if min > max {
+            return Err(ModelError::InvalidRange.into());
+        }
+        let (coeffs, constant) = expr.into().into_parts(self.num_vars)?;
+        self.add_solver_constraint(Constraint {
+            coeffs,
+            min: &min - &constant,
+            max: &max - &constant,
+        })?;
+        Ok(self)
+    }
+
+    /// Translate a constraint over the original variables to the solver and add it there.
+    fn add_solver_constraint(&mut self, mut constraint: Constraint) -> Result<(), Error> {
+        let coeffs = constraint.coeffs.clone();
+        if let Some(postsolve) = &self.postsolve {
+            constraint = postsolve.reduce_constraint(constraint);
+        }
+        if let Some(scale_factors) = &self.scale_factors {
+            constraint.coeffs = scale_factors.scale_row(&constraint.coeffs);
+        }
+        if constraint.coeffs.indices().is_empty() {
+            self.added_rows.push((None, coeffs));
+            if constraint.is_satisfied_by(&f0_ab!()) {
+                return Ok(());
+            }
+            // As with the constraints of the problem: the constraint alone excludes the values
+            // of the variables that presolve removed.
+            let mut multipliers = vec![f0_ab!(); self.rows.len() + self.added_rows.len()];
+            *multipliers.last_mut().unwrap() = if constraint.min.is_positive() {
+                f1_ab!()
+            } else {
+                -f1_ab!()
+            };
+            let mut reduced_costs = vec![f0_ab!(); self.num_vars];
+            self.complete_multipliers(&mut multipliers, &mut reduced_costs, true);
+            return Err(Error::Infeasible(InfeasibilityCertificate::new(
+                multipliers,
+            )));
+        }
+        self.added_rows
+            .push((Some(self.solver.num_constraints()), coeffs));
+        (self.solver.add_constraint(constraint)).map_err(|err| self.problem_error(err))?;
+        self.unscale();
+        Ok(())
+    }
+
+    /// Fix the variable to the specified value and return the solution to the updated problem.
+    ///
+    /// This method will consume the solution and not return it in case of error.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the problem becomes infeasible with the additional constraint.
+    pub fn fix_var(mut self, var: Variable, val: AbnormalFraction) -> Result<Self, Error> {
+        assert!(var.0 < self.num_vars);
+        match self.solver_var(var) {
+            Some(solver_var) => {
+                let val = match &self.scale_factors {
+                    Some(scale_factors) => scale_factors.scale_value(solver_var, &val),
+                    None => val,
+                };
+                (self.solver.fix_var(solver_var, val)).map_err(|err| self.problem_error(err))?;
+                self.unscale();
+            }
+            None if &val == self.var_value(var) => {}
+            None => return Err(Error::Infeasible(InfeasibilityCertificate::default())),
+        }
+        Ok(self)
+    }
+
+    /// If the variable was fixed with [`fix_var`](#method.fix_var) before, remove that constraint
+    /// and return the solution to the updated problem and a boolean indicating if the variable was
+    /// really fixed before.
+    pub fn unfix_var(mut self, var: Variable) -> (Self, bool) {
+        assert!(var.0 < self.num_vars);
+        let res = match self.solver_var(var) {
+            Some(solver_var) => self.solver.unfix_var(solver_var),
+            None => false,
+        };
+        self.unscale();
+        (self, res)
+    }
+
+    // TODO: remove_constraint
+
+    /// Add a [Gomory cut] constraint to the problem and return the solution.
+    ///
+    /// [Gomory cut]: https://en.wikipedia.org/wiki/Cutting-plane_method#Gomory's_cut
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the problem becomes infeasible with the additional constraint,
+    /// and [`ModelError::NoGomoryCut`] if the problem was scaled, as the cut relies on the
+    /// integrality of the unscaled variables, or if presolve removed the variable.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the variable is not basic (variable is basic if it has value other than
+    /// its bounds).
+    pub fn add_gomory_cut(mut self, var: Variable) -> Result<Self, Error> {
+        assert!(var.0 < self.num_vars);
+        if self.scale_factors.is_some() {
+            return Err(ModelError::NoGomoryCut(var).into());
+        }
+        let Some(solver_var) = self.solver_var(var) else {
+            return Err(ModelError::NoGomoryCut(var).into());
+        };
+        self.added_rows.push((
+            Some(self.solver.num_constraints()),
+            CsVec::empty(self.num_vars),
+        ));
+        (self.solver.add_gomory_cut(solver_var)).map_err(|err| self.problem_error(err))?;
+        Ok(self)
+    }
+
+    /// Recompute the unscaled variable values after the solver has changed its solution.
+    fn unscale(&mut self) {
+        if let Some(scale_factors) = &mut self.scale_factors {
+            scale_factors.unscale(&self.solver);
+        }
+    }
+
+    /// The statuses of the variables and constraints of the problem in the current basis, to
+    /// be stored and passed to [`Problem::solve_from_basis`] later. Constraints added to the
+    /// solution are not included.
+    pub fn extract_basis(&self) -> Basis {
+        let statuses = self.solver.basis_statuses();
+        let num_solver_vars = self.solver.num_vars;
+        let vars = (0..self.num_vars)
+            .map(|var| match self.solver_var(Variable(var)) {
+                Some(solver_var) => statuses[solver_var],
+                // Presolve removes the var again when the basis is used.
+                None => BasisStatus::NonbasicLower,
+            })
+            .collect();
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| match row {
+                Some(solver_row) => slack_status(statuses[num_solver_vars + solver_row]),
+                // The row did not restrict the solver, as if its slack var were basic.
+                None => BasisStatus::Basic,
+            })
+            .collect();
+        Basis::new(vars, rows)
+    }
+
+    /// Index of the variable in the solver, or `None` if it was removed by presolve.
+    fn solver_var(&self, var: Variable) -> Option<usize> {
+        match &self.postsolve {
+            Some(postsolve) => postsolve.reduced_var(var.0),
+            None => Some(var.0),
+        }
+    }
+}
+
+/// Solve from the given basis statuses, if any, and solve again without dropping entries from
+/// the factors if that cost too much accuracy.
+fn initial_solve(
+    solver: &mut Solver,
+    statuses: Option<&[BasisStatus]>,
+    var_mins: &[AbnormalFraction],
+    var_maxs: &[AbnormalFraction],
+    constraints: &[Constraint],
+) -> Result<(), Error> {
+    if let Some(statuses) = statuses {
+        solver.warm_start(statuses)?;
+    }
+    solver.initial_solve()?;
+    if solver.drops_entries() {
+        // The entries that the factors left out may have cost more accuracy than noise.
+        let values = (0..var_mins.len())
+            .map(|var| solver.get_value(var).clone())
+            .collect::<Vec<_>>();
+        let tolerance = f_ab!(1, 1_000_000);
+        if !check_values(&values, var_mins, var_maxs, constraints, &tolerance).is_empty() {
+            solver.solve_without_dropping()?;
+        }
+    }
+    Ok(())
+}
+
+/// For each constraint of the problem: its row in the solver, which skips the constraints that
+/// presolve removed and those without coefficients.
+fn solver_rows(
+    num_rows: usize,
+    postsolve: Option<&Postsolve>,
+    constraints: &[Constraint],
+) -> Vec<Option<usize>> {
+    let mut num_solver_rows = 0;
+    let constraint_rows = constraints
+        .iter()
+        .map(|constraint| {
+            (!constraint.coeffs.indices().is_empty()).then(|| {
+                num_solver_rows += 1;
+                num_solver_rows - 1
+            })
+        })
+        .collect::<Vec<_>>();
+    (0..num_rows)
+        .map(|row| {
+            let row = match postsolve {
+                Some(postsolve) => postsolve.reduced_row(row),
+                None => Some(row),
+            };
+            row.and_then(|row| constraint_rows[row])
+        })
+        .collect()
+}
+
+/// Translate between the status of a row and that of its slack var: as the slack var is the
+/// right-hand side minus the left-hand side, its bounds are swapped.
+fn slack_status(status: BasisStatus) -> BasisStatus {
+    match status {
+        BasisStatus::Basic => BasisStatus::Basic,
+        BasisStatus::NonbasicLower => BasisStatus::NonbasicUpper,
+        BasisStatus::NonbasicUpper => BasisStatus::NonbasicLower,
+    }
+}
+
+impl std::ops::Index<Variable> for Solution {
+    type Output = AbnormalFraction;
+
+    fn index(&self, var: Variable) -> &Self::Output {
+        self.var_value(var)
+    }
+}
+
+/// A variable identified by its name, or by its index if it has no name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VarLabel<'a> {
+    /// The name given in [`Problem::add_var_named`].
+    Name(&'a str),
+    /// The sequence number of an unnamed variable.
+    Index(usize),
+}
+
+impl std::fmt::Display for VarLabel<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VarLabel::Name(name) => write!(f, "{}", name),
+            VarLabel::Index(idx) => write!(f, "#{}", idx),
+        }
+    }
+}
+
+/// An iterator over the variable-value pairs of a [`Solution`].
+#[derive(Debug, Clone)]
+pub struct SolutionIter<'a> {
+    solution: &'a Solution,
+    var_idx: usize,
+}
+
+impl<'a> Iterator for SolutionIter<'a> {
+    type Item = (Variable, &'a AbnormalFraction);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.var_idx < self.solution.num_vars {
+            let var_idx = self.var_idx;
+            self.var_idx += 1;
+            let var = Variable(var_idx);
+            Some((var, self.solution.var_value(var)))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Solution {
+    type Item = (Variable, &'a AbnormalFraction);
+    type IntoIter = SolutionIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+use std::collections::VecDeque;
+
+use ebi_arithmetic::{MaybeExact, One, Signed, Zero};
+
+use crate::{
+    abnormal_fraction::AbnormalFraction,
+    f_ab, f0_ab, f1_ab,
+    linear_programming_hybrid::approximate_basis,
+    linear_programming_iis::deletion_filter,
+    linear_programming_lp_file::LpFile,
+    linear_programming_names::Names,
+    linear_programming_presolve::{Postsolve, presolve},
+    linear_programming_scaling::{ScaleFactors, scale},
+    linear_programming_size::size_report,
+    linear_programming_solver::Solver,
+    linear_programming_sparse::ScatteredVec,
+    linear_programming_standard_form::standard_form,
+    linear_programming_verify::{check_values, differ},
+};
+
+pub use crate::linear_programming_basis::{Basis, BasisStatus};
+pub use crate::linear_programming_certificate::{
+    InfeasibilityCertificate, PartialSolution, UnboundedRay,
+};
+pub use crate::linear_programming_iis::{Iis, IisMember, IisOptions};
+pub use crate::linear_programming_options::{
+    BasisUpdate, DropTolerance, LuPivoting, Method, Ordering, OrderingCachePolicy, PartialPricing,
+    Pricing, RefactorPolicy, RowHint, Scaling, SolveOptions,
+};
+pub use crate::linear_programming_ordering::OrderingStrategy;
+pub use crate::linear_programming_progress::{IterationInfo, Phase};
+pub use crate::linear_programming_size::SizeReport;
+pub use crate::linear_programming_standard_form::{StandardForm, StandardVar};
+pub use crate::linear_programming_stats::{FactorStats, SolveStats, SolveTimings};
+pub use crate::linear_programming_verify::SolutionViolation;
+
+#[cfg(test)]
+mod tests {
+
+    use std::{
+        cell::RefCell,
+        ops::ControlFlow,
+        sync::{
+            Arc, Once,
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+        },
+    };
+
+    use ebi_arithmetic::{One, Signed, Zero};
+
+    use crate::{abnormal_fraction::AbnormalFraction, f_ab, f0_ab, f1_ab};
+
+    use super::*;
+
+    #[test]
+    fn optimise() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let v1 = problem.add_var(f_ab!(3), (f_ab!(12), AbnormalFraction::infinity()));
+        let v2 = problem.add_var(f_ab!(4), (f_ab!(5), AbnormalFraction::infinity()));
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(20),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, -f_ab!(4))],
+                ComparisonOp::Ge,
+                -f_ab!(20),
+            )
+            .unwrap();
+
+        let sol = problem.solve().unwrap();
+        assert_eq!(sol[v1], f_ab!(12));
+        assert_eq!(sol[v2], f_ab!(8));
+        assert_eq!(sol.objective(), f_ab!(68));
+    }
+
+    #[test]
+    fn empty_expr_constraints() {
+        let trivial = [
+            (LinearExpr::empty(), ComparisonOp::Eq, f0_ab!()),
+            (LinearExpr::empty(), ComparisonOp::Ge, -f1_ab!()),
+            (LinearExpr::empty(), ComparisonOp::Le, f1_ab!()),
+        ];
+
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let _ = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        for (expr, op, b) in trivial.iter().cloned() {
+            problem.add_constraint(expr, op, b).unwrap();
+        }
+        assert_eq!(problem.solve().map(|s| s.objective()), Ok(f0_ab!()));
+
+        {
+            let mut sol = problem.solve().unwrap();
+            for (expr, op, b) in trivial.iter().cloned() {
+                sol = sol.add_constraint(expr, op, b).unwrap();
+            }
+            assert_eq!(sol.objective(), f0_ab!());
+        }
+
+        let infeasible = [
+            (LinearExpr::empty(), ComparisonOp::Eq, f_ab!(12)),
+            (LinearExpr::empty(), ComparisonOp::Ge, f_ab!(34)),
+            (LinearExpr::empty(), ComparisonOp::Le, -f_ab!(56)),
+        ];
+
+        for (expr, op, b) in infeasible.iter().cloned() {
+            let mut cloned = problem.clone();
+            cloned.add_constraint(expr, op, b).unwrap();
+            check_certificate(&cloned, &[], cloned.solve());
+        }
+
+        for (expr, op, b) in infeasible.iter().cloned() {
+            let added = Constraint::new(CsVec::empty(1), op, b.clone());
+            let sol = problem.solve().unwrap().add_constraint(expr, op, b);
+            check_certificate(&problem, &[added], sol);
+        }
+
+        let _ = problem.add_var(-f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        assert!(matches!(
+            problem.solve().map(|_| "solved"),
+            Err(Error::Unbounded(_))
+        ));
+    }
+
+    #[test]
+    fn free_variables() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let v1 = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        let v2 = problem.add_var(
+            f_ab!(2),
+            (
+                AbnormalFraction::neg_infinity(),
+                AbnormalFraction::infinity(),
+            ),
+        );
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(4),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, f1_ab!())],
+                ComparisonOp::Ge,
+                f_ab!(2),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, -f1_ab!())],
+                ComparisonOp::Ge,
+                f0_ab!(),
+            )
+            .unwrap();
+
+        let sol = problem.solve().unwrap();
+        assert_eq!(sol[v1], f_ab!(2));
+        assert_eq!(sol[v2], f_ab!(2));
+        assert_eq!(sol.objective(), f_ab!(6));
+    }
+
+    #[test]
+    fn fix_unfix_var() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let v1 = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(3)));
+        let v2 = problem.add_var(f_ab!(2), (f0_ab!(), f_ab!(3)));
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(4),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, f1_ab!())],
+                ComparisonOp::Ge,
+                f1_ab!(),
+            )
+            .unwrap();
+
+        let orig_sol = problem.solve().unwrap();
+
+        {
+            let mut sol = orig_sol.clone().fix_var(v1, f_ab!(1, 2)).unwrap();
+            assert_eq!(sol[v1], f_ab!(1, 2));
+            assert_eq!(sol[v2], f_ab!(3));
+            assert_eq!(sol.objective(), f_ab!(65, 10));
+
+            sol = sol.unfix_var(v1).0;
+            assert_eq!(sol[v1], f1_ab!());
+            assert_eq!(sol[v2], f_ab!(3));
+            assert_eq!(sol.objective(), f_ab!(7));
+        }
+
+        {
+            let mut sol = orig_sol.clone().fix_var(v2, f_ab!(25, 10)).unwrap();
+            assert_eq!(sol[v1], f_ab!(15, 10));
+            assert_eq!(sol[v2], f_ab!(25, 10));
+            assert_eq!(sol.objective(), f_ab!(65, 10));
+
+            sol = sol.unfix_var(v2).0;
+            assert_eq!(sol[v1], f1_ab!());
+            assert_eq!(sol[v2], f_ab!(3));
+            assert_eq!(sol.objective(), f_ab!(7));
+        }
+    }
+
+    #[test]
+    fn add_constraint() {
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let v1 = problem.add_var(f_ab!(2), (f0_ab!(), AbnormalFraction::infinity()));
+        let v2 = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(4),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, f1_ab!())],
+                ComparisonOp::Ge,
+                f_ab!(2),
+            )
+            .unwrap();
+
+        let orig_sol = problem.solve().unwrap();
+
+        {
+            let sol = orig_sol
+                .clone()
+                .add_constraint(
+                    &[(v1, -f1_ab!()), (v2, f1_ab!())],
+                    ComparisonOp::Le,
+                    f0_ab!(),
+                )
+                .unwrap();
+
+            assert_eq!(sol[v1], f1_ab!());
+            assert_eq!(sol[v2], f1_ab!());
+            assert_eq!(sol.objective(), f_ab!(3));
+        }
+
+        {
+            let sol = orig_sol
+                .clone()
+                .fix_var(v2, f_ab!(15, 10))
+                .unwrap()
+                .add_constraint(
+                    &[(v1, -f1_ab!()), (v2, f1_ab!())],
+                    ComparisonOp::Le,
+                    f0_ab!(),
+                )
+                .unwrap();
+            assert_eq!(sol[v1], f_ab!(15, 10));
+            assert_eq!(sol[v2], f_ab!(15, 10));
+            assert_eq!(sol.objective(), f_ab!(45, 10));
+        }
+
+        {
+            let sol = orig_sol
+                .clone()
+                .add_constraint(
+                    &[(v1, -f1_ab!()), (v2, f1_ab!())],
+                    ComparisonOp::Ge,
+                    f_ab!(3),
+                )
+                .unwrap();
+
+            assert_eq!(sol[v1], f0_ab!());
+            assert_eq!(sol[v2], f_ab!(3));
+            assert_eq!(sol.objective(), f_ab!(3));
+        }
+    }
+
+    #[test]
+    fn range_constraint() {
+        let range_problem = |direction| {
+            let mut problem = Problem::new(direction);
+            let x = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(4)));
+            let y = problem.add_var(f_ab!(2), (f0_ab!(), f_ab!(4)));
+            problem
+                .add_range_constraint(&[(x, f1_ab!()), (y, f1_ab!())], f_ab!(2), f_ab!(5))
+                .unwrap();
+            (problem, x, y)
+        };
+
+        // Active at its lower bound.
+        let (problem, x, y) = range_problem(OptimisationDirection::Minimise);
+        let sol = problem.solve().unwrap();
+        assert_eq!(sol[x], f_ab!(2));
+        assert_eq!(sol[y], f0_ab!());
+        assert_eq!(sol.objective(), f_ab!(2));
+        assert_eq!(sol.solver.num_constraints(), 1);
+
+        // Active at its upper bound.
+        let (problem, x, y) = range_problem(OptimisationDirection::Maximise);
+        let sol = problem.solve().unwrap();
+        assert_eq!(sol[x], f1_ab!());
+        assert_eq!(sol[y], f_ab!(4));
+        assert_eq!(sol.objective(), f_ab!(9));
+        assert_eq!(sol.solver.num_constraints(), 1);
+
+        let sol = sol
+            .add_range_constraint(&[(x, f1_ab!()), (y, -f1_ab!())], f_ab!(1), f_ab!(3))
+            .unwrap();
+        assert_eq!(sol[x], f_ab!(3));
+        assert_eq!(sol[y], f_ab!(2));
+        assert_eq!(sol.objective(), f_ab!(7));
+
+        let (mut problem, x, _) = range_problem(OptimisationDirection::Minimise);
+        assert_eq!(
+            problem.add_range_constraint(&[(x, f1_ab!())], f_ab!(3), f_ab!(2)),
+            Err(ModelError::InvalidRange.into())
+        );
+        assert_eq!(problem.constraints.len(), 1);
+    }
+
+    #[test]
+    fn range_constraint_duals() {
+        // x + y in [2, 5] with x, y in [0, 4]; y has the larger coefficient, so the side that
+        // binds is the one that y is pushed to.
+        let solve = |direction, sign: AbnormalFraction| {
+            let mut problem = Problem::new(direction);
+            let x = problem.add_var(sign.clone(), (f0_ab!(), f_ab!(4)));
+            let y = problem.add_var(&sign * &f_ab!(2), (f0_ab!(), f_ab!(4)));
+            problem
+                .add_range_constraint(&[(x, f1_ab!()), (y, f1_ab!())], f_ab!(2), f_ab!(5))
+                .unwrap();
+            let solution = problem.solve().unwrap();
+            check_duals(&problem, &solution);
+            (solution, x, y)
+        };
+        let lower_binds = |solution: &Solution, x, y| {
+            assert_eq!((&solution[x], &solution[y]), (&f_ab!(2), &f0_ab!()));
+            let status = solution.extract_basis().row_statuses()[0];
+            assert_eq!(status, BasisStatus::NonbasicLower);
+        };
+        let upper_binds = |solution: &Solution, x, y| {
+            assert_eq!((&solution[x], &solution[y]), (&f1_ab!(), &f_ab!(4)));
+            let status = solution.extract_basis().row_statuses()[0];
+            assert_eq!(status, BasisStatus::NonbasicUpper);
+        };
+
+        // Raising the bound that binds moves x along with it.
+        let (solution, x, y) = solve(OptimisationDirection::Minimise, f1_ab!());
+        lower_binds(&solution, x, y);
+        assert_eq!(solution.dual(0), f1_ab!());
+
+        let (solution, x, y) = solve(OptimisationDirection::Minimise, -f1_ab!());
+        upper_binds(&solution, x, y);
+        assert_eq!(solution.dual(0), -f1_ab!());
+
+        let (solution, x, y) = solve(OptimisationDirection::Maximise, f1_ab!());
+        upper_binds(&solution, x, y);
+        assert_eq!(solution.dual(0), f1_ab!());
+
+        let (solution, x, y) = solve(OptimisationDirection::Maximise, -f1_ab!());
+        lower_binds(&solution, x, y);
+        assert_eq!(solution.dual(0), -f1_ab!());
+    }
+
+    #[test]
+    fn equality_rows() {
+        // x_i + x_{i+1} == 2 for 100 rows: as equalities, as sparse equalities and as pairs of
+        // inequalities.
+        #[derive(PartialEq)]
+        enum Rows {
+            Eq,
+            SparseEq,
+            Ineq,
+        }
+        let build = |rows: Rows| {
+            let mut problem = Problem::new(OptimisationDirection::Minimise);
+            let vars = (0..101)
+                .map(|i| problem.add_var(f_ab!(i % 3 + 1), (f0_ab!(), f_ab!(10))))
+                .collect::<Vec<_>>();
+            let mut row = ScatteredVec::empty(vars.len());
+            for pair in vars.windows(2) {
+                let expr = [(pair[0], f1_ab!()), (pair[1], f1_ab!())];
+                if rows == Rows::Eq {
+                    problem
+                        .add_constraint(&expr, ComparisonOp::Eq, f_ab!(2))
+                        .unwrap();
+                } else if rows == Rows::SparseEq {
+                    row.clear();
+                    *row.get_mut(pair[0].0) = f1_ab!();
+                    *row.get_mut(pair[1].0) = f1_ab!();
+                    problem.add_constraint_eq(&row, f_ab!(2)).unwrap();
+                } else {
+                    problem
+                        .add_constraint(&expr, ComparisonOp::Le, f_ab!(2))
+                        .unwrap();
+                    problem
+                        .add_constraint(&expr, ComparisonOp::Ge, f_ab!(2))
+                        .unwrap();
+                }
+            }
+            problem
+        };
+
+        let eq = build(Rows::Eq).solve().unwrap();
+        let sparse_eq = build(Rows::SparseEq).solve().unwrap();
+        let ineq = build(Rows::Ineq).solve().unwrap();
+        assert_eq!(eq.objective(), ineq.objective());
+        assert_eq!(sparse_eq.objective(), ineq.objective());
+
+        // No slack column for the equality rows, and one for each inequality.
+        assert_eq!(eq.solver.num_total_vars(), 101);
+        assert_eq!(sparse_eq.solver.num_total_vars(), 101);
+        assert_eq!(ineq.solver.num_total_vars(), 101 + 200);
+        // The crash basis of the equality rows is already all but optimal, while the
+        // inequalities start from the slack basis.
+        assert_eq!(sparse_eq.stats().iterations(), eq.stats().iterations());
+        assert!(eq.stats().iterations() <= 2, "eq {:?}", eq.stats());
+        assert!(
+            10 * eq.stats().iterations() < ineq.stats().iterations(),
+            "eq {:?} ineq {:?}",
+            eq.stats(),
+            ineq.stats()
+        );
+
+        // The vars of a repeated row are all in its first copy, so the copy keeps a slack var.
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        let y = problem.add_var(f_ab!(2), (f0_ab!(), f_ab!(10)));
+        for _ in 0..2 {
+            problem
+                .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Eq, f_ab!(2))
+                .unwrap();
+        }
+        let sol = problem.solve().unwrap();
+        assert_eq!(sol.solver.num_total_vars(), 2 + 1);
+        assert_eq!(sol[x], f_ab!(2));
+        assert_eq!(sol.objective(), f_ab!(2));
+    }
+
+    #[test]
+    fn names() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let x = problem
+            .add_var_named("x_t3", f1_ab!(), (f0_ab!(), f_ab!(3)))
+            .unwrap();
+        let y = problem.add_var(f_ab!(2), (f0_ab!(), f_ab!(3)));
+        let z = problem
+            .add_var_named("z", f0_ab!(), (f0_ab!(), f_ab!(3)))
+            .unwrap();
+        assert_eq!(
+            problem.add_var_named("x_t3", f1_ab!(), (f0_ab!(), f1_ab!())),
+            Err(ModelError::DuplicateName("x_t3".to_string()).into())
+        );
+        assert_eq!(problem.obj_coeffs.len(), 3);
+
+        problem
+            .add_constraint_named(
+                "cap",
+                &[(x, f1_ab!()), (y, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(4),
+            )
+            .unwrap();
+        assert_eq!(
+            problem.add_constraint_named("cap", &[(z, f1_ab!())], ComparisonOp::Le, f1_ab!()),
+            Err(ModelError::DuplicateName("cap".to_string()).into())
+        );
+        assert_eq!(problem.constraints.len(), 1);
+        assert_eq!(problem.constraint_by_name("cap"), Some(0));
+
+        assert_eq!(problem.var_by_name("x_t3"), Some(x));
+        assert_eq!(problem.var_by_name("z"), Some(z));
+        assert_eq!(problem.var_by_name("y"), None);
+        assert_eq!(problem.var_name(y), None);
+
+        let sol = problem.solve().unwrap();
+        assert_eq!(sol.var_name(x), Some("x_t3"));
+        let labelled = sol
+            .iter_nonzero_labelled()
+            .map(|(label, val)| (label.to_string(), val.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labelled,
+            vec![("x_t3".to_string(), f1_ab!()), ("#1".to_string(), f_ab!(3))]
+        );
+
+        // The name of a variable that presolve removed is still known.
+        let fixed = (problem.add_var_named("fixed", f1_ab!(), (f_ab!(2), f_ab!(2)))).unwrap();
+        let sol = (problem.solve_with_options(&SolveOptions::new().presolve(true))).unwrap();
+        assert_eq!(sol.solver_var(fixed), None);
+        assert_eq!(problem.var_by_name("fixed"), Some(fixed));
+        assert_eq!(sol.var_name(fixed), Some("fixed"));
+        assert!(
+            sol.iter_nonzero_labelled()
+                .any(|(label, val)| label == VarLabel::Name("fixed") && *val == f_ab!(2))
+        );
+
+        // Removing a variable moves the later ones down, and their names with them.
+        problem.remove_var(y);
+        assert_eq!(problem.var_by_name("x_t3"), Some(x));
+        assert_eq!(problem.var_by_name("z"), Some(Variable(1)));
+        assert_eq!(problem.var_by_name("fixed"), Some(Variable(2)));
+        problem.remove_var(x);
+        assert_eq!(problem.var_by_name("x_t3"), None);
+        assert_eq!(problem.var_by_name("z"), Some(Variable(0)));
+        assert_eq!(problem.var_name(Variable(1)), Some("fixed"));
+        let x = (problem.add_var_named("x_t3", f1_ab!(), (f0_ab!(), f1_ab!()))).unwrap();
+        assert_eq!(x, Variable(2));
+        assert_eq!(problem.var_by_name("x_t3"), Some(x));
+        assert_eq!(problem.solve().unwrap().objective(), f_ab!(3));
+    }
+
+    #[test]
+    fn remove_var() {
+        // max x + 2y + 3z s.t. x + y + z <= 4 and y + z <= 3, with each var in [0, 2].
+        let build = |with_y: bool| {
+            let mut problem = Problem::new(OptimisationDirection::Maximise);
+            let x = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(2)));
+            let y = with_y.then(|| problem.add_var(f_ab!(2), (f0_ab!(), f_ab!(2))));
+            let z = problem.add_var(f_ab!(3), (f0_ab!(), f_ab!(2)));
+            let mut rows = [vec![(x, f1_ab!()), (z, f1_ab!())], vec![(z, f1_ab!())]];
+            if let Some(y) = y {
+                rows[0].push((y, f1_ab!()));
+                rows[1].push((y, f1_ab!()));
+            }
+            for (row, rhs) in rows.iter().zip([f_ab!(4), f_ab!(3)]) {
+                problem.add_constraint(row, ComparisonOp::Le, rhs).unwrap();
+            }
+            (problem, y)
+        };
+
+        let (mut problem, y) = build(true);
+        problem.remove_var(y.unwrap());
+        let (expected, _) = build(false);
+        assert_eq!(problem.obj_coeffs, expected.obj_coeffs);
+        assert_eq!(problem.constraints, expected.constraints);
+        assert_eq!(problem.solve().unwrap().objective(), f_ab!(8));
+
+        // An invalid variable is only reported while it is part of the problem.
+        let (mut problem, _) = build(true);
+        let invalid = problem.add_var(AbnormalFraction::infinity(), (f0_ab!(), f1_ab!()));
+        let last = problem.add_var(f1_ab!(), (f1_ab!(), AbnormalFraction::neg_infinity()));
+        problem.remove_var(invalid);
+        assert_eq!(
+            problem.solve().unwrap_err(),
+            ModelError::NonFinite {
+                part: "bound",
+                var: Some(Variable(last.0 - 1))
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn on_iteration() {
+        // Every var enters the basis in a separate iteration, limited by its own row.
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        for _ in 0..20 {
+            let var = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+            problem
+                .add_constraint(&[(var, f1_ab!())], ComparisonOp::Le, f1_ab!())
+                .unwrap();
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let options = SolveOptions::new().on_iteration({
+            let calls = calls.clone();
+            move |info| {
+                let calls = calls.fetch_add(1, Ordering::Relaxed) + 1;
+                assert_eq!(info.iteration, calls);
+                assert_eq!(info.phase, Phase::Optimality);
+                assert_eq!(info.objective, f_ab!(calls));
+                if calls == 5 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            }
+        });
+        assert!(matches!(
+            problem.solve_with_options(&options).err(),
+            Some(Error::Interrupted(_))
+        ));
+        assert_eq!(calls.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn cancel_token() {
+        // Every var enters the basis in a separate iteration, limited by its own row.
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        for _ in 0..20 {
+            let var = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+            problem
+                .add_constraint(&[(var, f1_ab!())], ComparisonOp::Le, f1_ab!())
+                .unwrap();
+        }
+
+        // A token that is already set stops the solver before the first iteration.
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cancel = Arc::new(AtomicBool::new(true));
+        let options = SolveOptions::new()
+            .cancel_token(cancel.clone())
+            .on_iteration({
+                let calls = calls.clone();
+                move |_| {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    ControlFlow::Continue(())
+                }
+            });
+        assert!(matches!(
+            problem.solve_with_options(&options).err(),
+            Some(Error::Interrupted(_))
+        ));
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+
+        // A token set during the fifth iteration stops the solver before the sixth.
+        cancel.store(false, Ordering::Relaxed);
+        let options = options.on_iteration({
+            let calls = calls.clone();
+            let cancel = cancel.clone();
+            move |info| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                if info.iteration == 5 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+                ControlFlow::Continue(())
+            }
+        });
+        assert!(matches!(
+            problem.solve_with_options(&options).err(),
+            Some(Error::Interrupted(_))
+        ));
+        assert_eq!(calls.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn warm_start_from_basis() {
+        let solution = random_problem(3, 30, 20).solve().unwrap();
+        assert!(solution.stats().iterations() > 0);
+        let basis = solution.extract_basis();
+        assert_eq!(basis.num_basic(), 20);
+
+        let problem = random_problem(3, 30, 20);
+        let warm = problem.solve_from_basis(&basis).unwrap();
+        assert_eq!(warm.objective(), solution.objective());
+        assert_eq!(warm.stats().phase1_iterations, 0);
+        assert_eq!(warm.stats().phase2_iterations, 0);
+
+        // Too many basic statuses are repaired.
+        let all_basic = Basis::new(vec![BasisStatus::Basic; 30], vec![BasisStatus::Basic; 20]);
+        let repaired = problem.solve_from_basis(&all_basic).unwrap();
+        assert_eq!(repaired.objective(), solution.objective());
+
+        let too_short = Basis::new(vec![BasisStatus::Basic; 29], vec![BasisStatus::Basic; 20]);
+        assert_eq!(
+            problem.solve_from_basis(&too_short).err(),
+            Some(ModelError::BasisMismatch.into())
+        );
+
+        // A basis that is not optimal for the problem.
+        let at_upper = Basis::new(
+            vec![BasisStatus::NonbasicUpper; 30],
+            vec![BasisStatus::Basic; 20],
+        );
+        let warm = problem.solve_from_basis(&at_upper).unwrap();
+        assert_eq!(warm.objective(), solution.objective());
+
+        // Without rows, the vars move to the bounds that the objective prefers.
+        let mut no_rows = Problem::new(OptimisationDirection::Maximise);
+        let x = no_rows.add_var(f_ab!(3), (-f_ab!(2), f1_ab!()));
+        let y = no_rows.add_var(-f1_ab!(), (f_ab!(3), f_ab!(8)));
+        let wrong_bounds = Basis::new(
+            vec![BasisStatus::NonbasicLower, BasisStatus::NonbasicUpper],
+            vec![],
+        );
+        let warm = no_rows.solve_from_basis(&wrong_bounds).unwrap();
+        assert_eq!((&warm[x], &warm[y]), (&f1_ab!(), &f_ab!(3)));
+        assert_eq!(warm.objective(), no_rows.solve().unwrap().objective());
+
+        // Unless that bound is infinite.
+        let z = no_rows.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        let at_lower = Basis::new(vec![BasisStatus::NonbasicLower; 3], vec![]);
+        match no_rows.solve_from_basis(&at_lower) {
+            Err(Error::Unbounded(ray)) => assert_eq!(ray.direction()[z.0], f1_ab!()),
+            res => panic!("expected an unbounded ray, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn change_bounds() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        let y = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        problem
+            .add_constraint(&[(x, f1_ab!()), (y, f_ab!(2))], ComparisonOp::Le, f_ab!(14))
+            .unwrap();
+        let solution = problem.solve().unwrap();
+        assert_eq!((&solution[x], &solution[y]), (&f_ab!(10), &f_ab!(2)));
+
+        // Cut off the previous optimum.
+        problem.set_var_lower(y, Some(f_ab!(3))).unwrap();
+        let warm = problem.solve_from_basis(&solution.extract_basis()).unwrap();
+        assert_eq!((&warm[x], &warm[y]), (&f_ab!(8), &f_ab!(3)));
+        assert_eq!(warm.objective(), f_ab!(11));
+        assert!(warm.stats().iterations() <= 1);
+        assert_eq!(problem.solve().unwrap().objective(), f_ab!(11));
+
+        assert_eq!(
+            problem.set_var_upper(y, Some(f_ab!(2))),
+            Err(ModelError::InvalidBounds(y).into())
+        );
+        problem.set_var_upper(x, Some(f_ab!(5))).unwrap();
+        problem.set_var_lower(y, None).unwrap();
+        let solution = problem.solve().unwrap();
+        assert_eq!((&solution[x], &solution[y]), (&f_ab!(5), &f_ab!(9, 2)));
+    }
+
+    #[test]
+    fn change_objective() {
+        let mut problem = random_problem(5, 30, 20);
+        let solution = problem.solve().unwrap();
+        let basis = solution.extract_basis();
+        let var = (0..30)
+            .map(Variable)
+            .max_by(|a, b| solution[*a].partial_cmp(&solution[*b]).unwrap())
+            .unwrap();
+        assert!(solution[var].is_positive());
+
+        problem.set_objective_coeff(var, -f_ab!(5)).unwrap();
+        let cold = problem.solve().unwrap();
+        let warm = problem.solve_from_basis(&basis).unwrap();
+        assert_eq!(warm.objective(), cold.objective());
+        assert!(warm.objective() < solution.objective());
+        // The objective does not change feasibility, so the old basis needs no phase 1.
+        assert_eq!(warm.stats().phase1_iterations, 0);
+        // From the optimal basis of the new objective, there is nothing left to pivot.
+        let again = problem.solve_from_basis(&cold.extract_basis()).unwrap();
+        assert_eq!(again.objective(), cold.objective());
+        assert_eq!(again.stats().iterations(), 0);
+
+        // Replacing the whole objective.
+        problem.set_objective(&[(var, f1_ab!())]).unwrap();
+        let solution = problem.solve_from_basis(&basis).unwrap();
+        assert_eq!(solution.objective(), solution[var]);
+        assert_eq!(solution.objective(), problem.solve().unwrap().objective());
+    }
+
+    #[test]
+    fn change_objective_warm_start() {
+        // Without rows: the old basis holds x1 at the bound that the new objective does not
+        // prefer.
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let x0 = problem.add_var(f_ab!(3), (-f_ab!(2), f1_ab!()));
+        let x1 = problem.add_var(-f1_ab!(), (f_ab!(3), f_ab!(8)));
+        let basis = problem.solve().unwrap().extract_basis();
+        problem.set_objective_coeff(x1, f_ab!(5)).unwrap();
+        let warm = problem.solve_from_basis(&basis).unwrap();
+        assert_eq!((&warm[x0], &warm[x1]), (&f1_ab!(), &f_ab!(8)));
+        assert_eq!(warm.objective(), f_ab!(43));
+        assert_eq!(warm.objective(), problem.solve().unwrap().objective());
+
+        // New coefficients of both signs, with and without rows.
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+        let mut rng = StdRng::seed_from_u64(7);
+        for seed in 0..10 {
+            for num_constraints in [0, 8] {
+                let mut problem = random_problem(seed, 12, num_constraints);
+                let basis = problem.solve().unwrap().extract_basis();
+                for var in (0..12).map(Variable) {
+                    if rng.random_bool(0.5) {
+                        let coeff = f_ab!(rng.random_range(0..10usize));
+                        let coeff = if rng.random_bool(0.5) { -coeff } else { coeff };
+                        problem.set_objective_coeff(var, coeff).unwrap();
+                    }
+                }
+                let cold = problem.solve().unwrap();
+                let warm = problem.solve_from_basis(&basis).unwrap();
+                let case = format!("seed {}, {} rows", seed, num_constraints);
+                assert_eq!(warm.objective(), cold.objective(), "{}", case);
+                assert_eq!(warm.stats().phase1_iterations, 0, "{}", case);
+            }
+        }
+    }
+
+    #[test]
+    fn change_rhs() {
+        let supplies = [25, 30];
+        let demands = [10, 25, 15];
+        let costs = [[8, 6, 10], [9, 12, 13]];
+
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let vars = costs.map(|row| {
+            row.map(|cost| problem.add_var(f_ab!(cost), (f0_ab!(), AbnormalFraction::infinity())))
+        });
+        for (i, &supply) in supplies.iter().enumerate() {
+            let expr = vars[i]
+                .iter()
+                .map(|&var| (var, f1_ab!()))
+                .collect::<LinearExpr>();
+            problem
+                .add_constraint(expr, ComparisonOp::Le, f_ab!(supply))
+                .unwrap();
+        }
+        for (j, &demand) in demands.iter().enumerate() {
+            let expr = vars
+                .iter()
+                .map(|row| (row[j], f1_ab!()))
+                .collect::<LinearExpr>();
+            problem
+                .add_constraint(expr, ComparisonOp::Ge, f_ab!(demand))
+                .unwrap();
+        }
+        let solution = problem.solve().unwrap();
+
+        // Increase the second demand.
+        problem.set_rhs(3, f_ab!(28)).unwrap();
+        assert_eq!(
+            problem.constraint_bounds(3),
+            (&f_ab!(28), &AbnormalFraction::infinity())
+        );
+        let cold = problem.solve().unwrap();
+        let warm = problem.solve_from_basis(&solution.extract_basis()).unwrap();
+        assert_eq!(warm.objective(), cold.objective());
+        assert!(warm.objective() > solution.objective());
+        // The basis stays dual feasible, so only the dual simplex (phase 1) runs.
+        assert_eq!(warm.stats().phase2_iterations, 0);
+        assert!(warm.stats().iterations() <= cold.stats().iterations());
+
+        problem
+            .set_rhs_many([(0, f_ab!(20)), (1, f_ab!(40))])
+            .unwrap();
+        assert_eq!(problem.constraint_bounds(0).1, &f_ab!(20));
+        assert_eq!(
+            problem.set_constraint_bounds(2, f_ab!(5), f1_ab!()),
+            Err(ModelError::InvalidRange.into())
+        );
+        problem
+            .set_constraint_bounds(2, f_ab!(5), f_ab!(10))
+            .unwrap();
+        // A range constraint has no single right-hand side.
+        assert_eq!(
+            problem.set_rhs(2, f_ab!(7)),
+            Err(ModelError::NoSingleRhs(2).into())
+        );
+        assert_eq!(
+            problem.set_rhs_many([(0, f_ab!(25)), (2, f_ab!(7))]),
+            Err(ModelError::NoSingleRhs(2).into())
+        );
+        assert_eq!(problem.constraint_bounds(0).1, &f_ab!(20));
+        assert_eq!(problem.constraint_bounds(2), (&f_ab!(5), &f_ab!(10)));
+        let solution = problem.solve().unwrap();
+        assert_eq!(
+            solution.objective(),
+            problem
+                .solve_from_basis(&cold.extract_basis())
+                .unwrap()
+                .objective()
+        );
+    }
+
+    /// Check the duals and the reduced costs of an optimal solution: the reduced costs are
+    /// `c - Aᵀy`, and every non-zero dual or reduced cost names a bound that binds.
+    fn check_duals(problem: &Problem, solution: &Solution) {
+        let sign = match problem.direction {
+            OptimisationDirection::Minimise => f1_ab!(),
+            OptimisationDirection::Maximise => -f1_ab!(),
+        };
+        let duals = solution.duals();
+        let reduced_costs = solution.reduced_costs();
+        let mut expected = (problem.obj_coeffs.iter())
+            .map(|obj_coeff| &sign * obj_coeff)
+            .collect::<Vec<_>>();
+        for (constraint, dual) in problem.constraints.iter().zip(&duals) {
+            let mut lhs = f0_ab!();
+            for (var, coeff) in constraint.coeffs.iter() {
+                expected[var] -= coeff * dual;
+                lhs += coeff * &solution[Variable(var)];
+            }
+            let dual = &sign * dual;
+            if dual.is_positive() {
+                assert_eq!(lhs, constraint.min);
+            } else if dual.is_negative() {
+                assert_eq!(lhs, constraint.max);
+            }
+        }
+        assert_eq!(reduced_costs, expected);
+        for (var, reduced_cost) in reduced_costs.iter().enumerate() {
+            let reduced_cost = &sign * reduced_cost;
+            if reduced_cost.is_positive() {
+                assert_eq!(solution[Variable(var)], problem.var_mins[var]);
+            } else if reduced_cost.is_negative() {
+                assert_eq!(solution[Variable(var)], problem.var_maxs[var]);
+            }
+        }
+    }
+
+    #[test]
+    fn duals() {
+        // min 2x + 3y + 5z s.t. x + y + z >= 4, 2x <= 4, z = 1: x = 2, y = 1 and objective 12.
+        // The second row is a singleton and z is fixed, which presolve removes.
+        for direction in [
+            OptimisationDirection::Minimise,
+            OptimisationDirection::Maximise,
+        ] {
+            let sign = match direction {
+                OptimisationDirection::Minimise => f1_ab!(),
+                OptimisationDirection::Maximise => -f1_ab!(),
+            };
+            let mut problem = Problem::new(direction);
+            let x = problem.add_var(&sign * &f_ab!(2), (f0_ab!(), AbnormalFraction::infinity()));
+            let y = problem.add_var(&sign * &f_ab!(3), (f0_ab!(), AbnormalFraction::infinity()));
+            let z = problem.add_var(&sign * &f_ab!(5), (f1_ab!(), f1_ab!()));
+            problem
+                .add_constraint(
+                    &[(x, f1_ab!()), (y, f1_ab!()), (z, f1_ab!())],
+                    ComparisonOp::Ge,
+                    f_ab!(4),
+                )
+                .unwrap();
+            problem
+                .add_constraint(&[(x, f_ab!(2))], ComparisonOp::Le, f_ab!(4))
+                .unwrap();
+
+            for presolve in [false, true] {
+                let solution = problem
+                    .solve_with_options(&SolveOptions::new().presolve(presolve))
+                    .unwrap();
+                assert_eq!(solution.objective(), &sign * &f_ab!(12));
+                // Raising the first bound makes y larger; raising the second makes x larger
+                // and y smaller by half as much.
+                assert_eq!(solution.dual(0), &sign * &f_ab!(3));
+                assert_eq!(solution.dual(1), &sign * &-f_ab!(1, 2));
+                assert_eq!(solution.reduced_cost(x), f0_ab!());
+                assert_eq!(solution.reduced_cost(y), f0_ab!());
+                // Raising z by one lowers y by one.
+                assert_eq!(solution.reduced_cost(z), &sign * &f_ab!(2));
+                check_duals(&problem, &solution);
+            }
+        }
+
+        // max 3x + 2y s.t. x + y <= 4, x + 3y <= 6: x = 4, y = 0 at the lower bound.
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let x = problem.add_var(f_ab!(3), (f0_ab!(), AbnormalFraction::infinity()));
+        let y = problem.add_var(f_ab!(2), (f0_ab!(), AbnormalFraction::infinity()));
+        problem
+            .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Le, f_ab!(4))
+            .unwrap();
+        problem
+            .add_constraint(&[(x, f1_ab!()), (y, f_ab!(3))], ComparisonOp::Le, f_ab!(6))
+            .unwrap();
+        let solution = problem.solve().unwrap();
+        assert_eq!(solution.objective(), f_ab!(12));
+        assert_eq!(solution.duals(), vec![f_ab!(3), f0_ab!()]);
+        assert_eq!(solution.reduced_costs(), vec![f0_ab!(), -f1_ab!()]);
+        check_duals(&problem, &solution);
+    }
+
+    #[test]
+    fn verify() {
+        let problem = random_problem(9, 30, 20);
+        let solution = problem.solve().unwrap();
+        assert_eq!(problem.verify(&solution), Ok(()));
+
+        let mut values = solution
+            .iter()
+            .map(|(_, val)| val.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(problem.verify_values(&values, &f0_ab!()), Ok(()));
+
+        // Push one var past its upper bound; also every constraint it occurs in gets violated
+        // if it was tight.
+        values[4] = f_ab!(21, 2);
+        let violations = problem.verify_values(&values, &f0_ab!()).unwrap_err();
+        assert_eq!(
+            violations[0],
+            SolutionViolation::VarAboveMax {
+                var: Variable(4),
+                amount: f_ab!(1, 2)
+            }
+        );
+        assert!(
+            violations[1..]
+                .iter()
+                .all(|violation| matches!(violation, SolutionViolation::ConstraintAboveMax { .. }))
+        );
+        assert_eq!(problem.verify_values(&values, &f_ab!(100)), Ok(()));
+
+        // The objective must match the values.
+        let mut other = problem.clone();
+        other.set_objective(LinearExpr::empty()).unwrap();
+        let violations = other.verify(&solution).unwrap_err();
+        assert!(matches!(
+            violations[..],
+            [SolutionViolation::ObjectiveMismatch { .. }]
+        ));
+    }
+
+    #[test]
+    fn abs_terms() {
+        // Fit y = a * x + b to the points with the least absolute deviations.
+        let points = [(0, 0), (1, 1), (2, 2), (3, 10)];
+        let free = (
+            AbnormalFraction::neg_infinity(),
+            AbnormalFraction::infinity(),
+        );
+
+        let mut with_helper = Problem::new(OptimisationDirection::Minimise);
+        let a = with_helper.add_var(f0_ab!(), free.clone());
+        let b = with_helper.add_var(f0_ab!(), free.clone());
+        let residuals = points
+            .iter()
+            .map(|&(x, y)| {
+                with_helper
+                    .add_abs_term(&[(a, f_ab!(x)), (b, f1_ab!())], -f_ab!(y), f1_ab!())
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let mut by_hand = Problem::new(OptimisationDirection::Minimise);
+        by_hand.add_var(f0_ab!(), free.clone());
+        by_hand.add_var(f0_ab!(), free);
+        for &(x, y) in &points {
+            let t = by_hand.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+            by_hand
+                .add_constraint(
+                    &[(a, -f_ab!(x)), (b, -f1_ab!()), (t, f1_ab!())],
+                    ComparisonOp::Ge,
+                    -f_ab!(y),
+                )
+                .unwrap();
+            by_hand
+                .add_constraint(
+                    &[(a, f_ab!(x)), (b, f1_ab!()), (t, f1_ab!())],
+                    ComparisonOp::Ge,
+                    f_ab!(y),
+                )
+                .unwrap();
+        }
+
+        let helper_solution = with_helper.solve().unwrap();
+        let hand_solution = by_hand.solve().unwrap();
+        assert_eq!(helper_solution.objective(), f_ab!(7));
+        assert_eq!(helper_solution.objective(), hand_solution.objective());
+        for (helper_val, hand_val) in helper_solution.iter().zip(hand_solution.iter()) {
+            assert_eq!(helper_val, hand_val);
+        }
+        let total = residuals
+            .iter()
+            .fold(f0_ab!(), |sum, &t| sum + helper_solution[t].clone());
+        assert_eq!(total, f_ab!(7));
+
+        // max(x - y) over the points for the line y = x.
+        let mut max_problem = Problem::new(OptimisationDirection::Minimise);
+        let x = max_problem.add_var(f0_ab!(), (f1_ab!(), f1_ab!()));
+        let terms = points.map(|(px, py)| ([(x, f_ab!(px))], -f_ab!(py)));
+        let t = max_problem.add_max_term(terms, f1_ab!()).unwrap();
+        let solution = max_problem.solve().unwrap();
+        assert_eq!(solution[t], f0_ab!());
+    }
+
+    /// The same model as [`random_problem`], with the constraints added through each of the
+    /// ways to add constraints.
+    fn random_problems_sparse(seed: u64, num_vars: usize, num_constraints: usize) -> [Problem; 4] {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut problems = [0, 1, 2, 3].map(|_| Problem::new(OptimisationDirection::Maximise));
+        for _ in 0..num_vars {
+            let obj_coeff = f_ab!(rng.random_range(1..10usize));
+            for problem in &mut problems {
+                problem.add_var(obj_coeff.clone(), (f0_ab!(), f_ab!(10)));
+            }
+        }
+
+        let mut rows = vec![];
+        for _ in 0..num_constraints {
+            let mut row = ScatteredVec::empty(num_vars);
+            for var in 0..num_vars {
+                if rng.random_bool(0.5) {
+                    *row.get_mut(var) = f_ab!(rng.random_range(1..5usize));
+                }
+            }
+            rows.push((row, f_ab!(rng.random_range(10..50usize))));
+        }
+
+        let [by_expr, by_bulk, by_scattered, by_csvec] = &mut problems;
+        for (row, rhs) in &rows {
+            let expr = row
+                .iter()
+                .map(|(var, coeff)| (Variable(var), coeff.clone()))
+                .collect::<LinearExpr>();
+            by_expr
+                .add_constraint(expr, ComparisonOp::Le, rhs.clone())
+                .unwrap();
+            by_scattered
+                .add_constraint_sparse(row, ComparisonOp::Le, rhs.clone())
+                .unwrap();
+            by_csvec
+                .add_constraint_csvec(row.to_csvec().view(), ComparisonOp::Le, rhs.clone())
+                .unwrap();
+        }
+        by_bulk
+            .add_constraints(rows.iter().map(|(row, rhs)| {
+                let expr = row
+                    .iter()
+                    .map(|(var, coeff)| (Variable(var), coeff.clone()))
+                    .collect::<LinearExpr>();
+                (expr, ComparisonOp::Le, rhs.clone())
+            }))
+            .unwrap();
+        problems
+    }
+
+    #[test]
+    fn sparse_constraints() {
+        let problems = random_problems_sparse(13, 40, 25);
+        let nnz = |problem: &Problem| {
+            (problem.constraints.iter())
+                .map(|constraint| constraint.coeffs.nnz())
+                .sum::<usize>()
+        };
+        let objective = problems[0].solve().unwrap().objective();
+        for problem in &problems[1..] {
+            assert_eq!(problem.constraints, problems[0].constraints);
+            assert_eq!(nnz(problem), nnz(&problems[0]));
+            assert_eq!(problem.solve().unwrap().objective(), objective);
+        }
+    }
+
+    #[test]
+    fn sparse_constraints_large() {
+        let [by_expr, _, by_scattered, _] = random_problems_sparse(17, 2000, 1000);
+        let rows = (by_scattered.constraints.iter())
+            .map(|constraint| {
+                let mut row = ScatteredVec::empty(2000);
+                row.set(constraint.coeffs.iter());
+                (row, constraint.max.clone())
+            })
+            .collect::<Vec<_>>();
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        for _ in 0..2000 {
+            problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        }
+
+        let mut expr_problem = problem.clone();
+        for (row, rhs) in &rows {
+            let expr = row
+                .iter()
+                .map(|(var, coeff)| (Variable(var), coeff.clone()))
+                .collect::<LinearExpr>();
+            expr_problem
+                .add_constraint(expr, ComparisonOp::Le, rhs.clone())
+                .unwrap();
+        }
+
+        for (row, rhs) in &rows {
+            problem
+                .add_constraint_sparse(row, ComparisonOp::Le, rhs.clone())
+                .unwrap();
+        }
+
+        assert_eq!(expr_problem.constraints, by_expr.constraints);
+        assert_eq!(problem.constraints, by_expr.constraints);
+    }
+
+    #[test]
+    fn size_report() {
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        let y = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        let unused = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        problem
+            .add_constraint(&[(x, f1_ab!()), (y, f_ab!(2))], ComparisonOp::Le, f_ab!(14))
+            .unwrap();
+        problem
+            .add_constraint(&[(y, f1_ab!())], ComparisonOp::Ge, f1_ab!())
+            .unwrap();
+        problem
+            .add_constraint(LinearExpr::empty(), ComparisonOp::Le, f1_ab!())
+            .unwrap();
+        problem
+            .add_range_constraint(
+                &[(x, f1_ab!())],
+                AbnormalFraction::neg_infinity(),
+                AbnormalFraction::infinity(),
+            )
+            .unwrap();
+
+        let report = problem.size_report();
+        assert_eq!(report.rows, 4);
+        assert_eq!(report.structural_columns, 3);
+        assert_eq!(report.columns(), 7);
+        assert_eq!(report.nnz, 4);
+        assert_eq!(report.nnz_with_slacks, 8);
+        assert!(report.matrix_bytes > 0);
+        assert!(report.factorisation_bytes(2.0) > report.factorisation_bytes(1.0));
+        assert_eq!(report.dense_columns, vec![]);
+        assert_eq!(report.empty_rows, vec![2]);
+        assert_eq!(report.empty_columns, vec![unused]);
+        assert_eq!(report.free_rows, vec![3]);
+        assert!(report.to_string().contains("empty rows: 1"));
+
+        // A var in every row of a larger problem makes a dense column.
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let vars = (0..40)
+            .map(|_| problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10))))
+            .collect::<Vec<_>>();
+        for i in 0..39 {
+            problem
+                .add_constraint(
+                    &[(vars[i], f1_ab!()), (vars[39], f1_ab!())],
+                    ComparisonOp::Le,
+                    f_ab!(10),
+                )
+                .unwrap();
+        }
+        assert_eq!(problem.size_report().dense_columns, vec![vars[39]]);
+    }
+
+    #[test]
+    fn refactor_policy() {
+        let problem = random_problem(3, 30, 20);
+        let default = problem.solve().unwrap();
+        let every_pivot = problem
+            .solve_with_options(&SolveOptions::new().refactor_policy(RefactorPolicy {
+                max_updates: 1,
+                ..Default::default()
+            }))
+            .unwrap();
+        assert_eq!(every_pivot.objective(), default.objective());
+        for ((_, default_val), (_, val)) in default.iter().zip(every_pivot.iter()) {
+            assert_eq!(val, default_val);
+        }
+        assert_eq!(
+            every_pivot.stats().refactors_max_updates,
+            every_pivot.stats().iterations() - every_pivot.stats().bound_flips
+        );
+        assert!(every_pivot.stats().lu_factorisations > default.stats().lu_factorisations);
+
+        if !f1_ab!().is_exact() {
+            return;
+        }
+        // The coefficients of the updates are fractions with more than a few bits.
+        let small_coeffs = problem
+            .solve_with_options(&SolveOptions::new().refactor_policy(RefactorPolicy {
+                max_coeff_bits: Some(4),
+                ..Default::default()
+            }))
+            .unwrap();
+        assert_eq!(small_coeffs.objective(), default.objective());
+        assert!(small_coeffs.stats().refactors_max_coeff_bits > 0);
+        assert_eq!(default.stats().refactors_max_coeff_bits, 0);
+    }
+
+    #[test]
+    fn eta_updates() {
+        let option_sets = [
+            SolveOptions::new(),
+            SolveOptions::new().method(Method::DualSimplex),
+            SolveOptions::new().dense_column_fraction(Some(0.3)),
+        ];
+        for seed in 0..20 {
+            let problem = random_problem(seed, 15 + seed as usize, 10);
+            for options in &option_sets {
+                let default = problem.solve_with_options(options).unwrap();
+                let eta = problem
+                    .solve_with_options(&options.clone().basis_update(BasisUpdate::Eta))
+                    .unwrap();
+                if !f1_ab!().is_exact() {
+                    let diff = (eta.objective() - default.objective()).abs();
+                    assert!(diff <= f_ab!(1, 1_000_000), "seed {}", seed);
+                    continue;
+                }
+                assert_eq!(eta.objective(), default.objective(), "seed {}", seed);
+                for ((_, default_val), (_, val)) in default.iter().zip(eta.iter()) {
+                    assert_eq!(val, default_val, "seed {}", seed);
+                }
+                assert_eq!(eta.stats().iterations(), default.stats().iterations());
+                assert_eq!(eta.stats().pivot_hash, default.stats().pivot_hash);
+            }
+        }
+    }
+
+    #[test]
+    fn reuse_symbolic() {
+        let options = SolveOptions::new().refactor_policy(RefactorPolicy {
+            max_updates: 3,
+            ..Default::default()
+        });
+        for seed in 0..10 {
+            let problem = random_problem(seed, 20, 12);
+            let default = problem.solve_with_options(&options).unwrap();
+            let reused = problem
+                .solve_with_options(&options.clone().reuse_symbolic(true))
+                .unwrap();
+            if f1_ab!().is_exact() {
+                assert_eq!(reused.objective(), default.objective(), "seed {}", seed);
+                assert_eq!(reused.stats().pivot_hash, default.stats().pivot_hash);
+            } else {
+                let diff = (reused.objective() - default.objective()).abs();
+                assert!(diff <= f_ab!(1, 1_000_000), "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn factor_stats() {
+        let problem = random_problem(3, 30, 20);
+        // Refactorise after every second pivot.
+        let options = SolveOptions::new().refactor_policy(RefactorPolicy {
+            max_updates: 2,
+            ..Default::default()
+        });
+        let default = problem.solve_with_options(&options).unwrap();
+        let stats = default.stats();
+        assert_eq!(stats.factorisations.len(), stats.lu_factorisations);
+        let updates = (stats.factorisations.iter())
+            .map(|factor| factor.updates)
+            .collect::<Vec<_>>();
+        assert_eq!(updates[0], 0);
+        assert!(updates.iter().all(|&count| count <= 1) && updates.contains(&1));
+        for factor in &stats.factorisations {
+            assert!(factor.fill_ratio() >= 1.0);
+            assert!(factor.max_coeff_bits.is_none() && factor.total_coeff_bits.is_none());
+        }
+        assert_eq!(
+            (stats.factorisations.iter())
+                .map(FactorStats::lu_nnz)
+                .sum::<usize>(),
+            stats.lu_nnz
+        );
+
+        let with_bits = problem
+            .solve_with_options(&options.coeff_bit_stats(true))
+            .unwrap();
+        for factor in &with_bits.stats().factorisations {
+            assert_eq!(factor.max_coeff_bits.is_some(), f1_ab!().is_exact());
+            if let (Some(max), Some(total)) = (factor.max_coeff_bits, factor.total_coeff_bits) {
+                assert!(max > 0 && total >= max);
+            }
+        }
+    }
+
+    #[test]
+    fn drop_tolerance() {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+
+        // Every coefficient that random_problem leaves out is noise instead.
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let vars = (0..30)
+            .map(|_| problem.add_var(f_ab!(rng.random_range(1..10usize)), (f0_ab!(), f_ab!(10))))
+            .collect::<Vec<_>>();
+        for _ in 0..20 {
+            let expr = (vars.iter())
+                .map(|&var| {
+                    if rng.random_bool(0.5) {
+                        (var, f_ab!(rng.random_range(1..5usize)))
+                    } else {
+                        (var, f_ab!(1, 1_000_000_000_000_000))
+                    }
+                })
+                .collect::<LinearExpr>();
+            problem
+                .add_constraint(expr, ComparisonOp::Le, f_ab!(rng.random_range(10..50usize)))
+                .unwrap();
+        }
+
+        let options = SolveOptions::new().refactor_policy(RefactorPolicy {
+            max_updates: 1,
+            ..Default::default()
+        });
+        let kept = problem.solve_with_options(&options).unwrap();
+        let drop_tolerance = DropTolerance::Relative(f_ab!(1, 1_000_000_000_000));
+        let dropped = problem
+            .solve_with_options(&options.drop_tolerance(Some(drop_tolerance)))
+            .unwrap();
+        let tolerance = f_ab!(1, 1_000_000);
+        assert_eq!(problem.verify_with_tolerance(&dropped, &tolerance), Ok(()));
+        assert!((dropped.objective() - kept.objective()).abs() <= tolerance);
+
+        let num_dropped = (dropped.stats().factorisations.iter())
+            .map(|factor| factor.dropped_entries + factor.update_dropped_entries)
+            .sum::<usize>();
+        assert_eq!(num_dropped > 0, !f1_ab!().is_exact());
+    }
+
+    #[test]
+    fn error_variants() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        let y = problem.add_var(f0_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        problem
+            .add_constraint(&[(x, f1_ab!()), (y, -f1_ab!())], ComparisonOp::Le, f1_ab!())
+            .unwrap();
+        assert!(matches!(problem.solve(), Err(SolveError::Unbounded(_))));
+        problem
+            .add_constraint(&[(x, f1_ab!())], ComparisonOp::Le, -f1_ab!())
+            .unwrap();
+        assert!(matches!(problem.solve(), Err(SolveError::Infeasible(_))));
+
+        // Errors convert into anyhow errors with `?`.
+        let solve = || -> ebi_arithmetic::anyhow::Result<Solution> { Ok(problem.solve()?) };
+        let err = solve().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::Infeasible(_))
+        ));
+        assert_eq!(err.to_string(), "problem is infeasible");
+    }
+
+    /// Check that an infeasibility certificate proves the constraints of the problem, followed
+    /// by the `added` ones, infeasible.
+    fn check_certificate(problem: &Problem, added: &[Constraint], error: Result<Solution, Error>) {
+        let Err(Error::Infeasible(certificate)) = error else {
+            panic!("expected an infeasible problem, got {:?}", error);
+        };
+        let multipliers = certificate.multipliers();
+        let constraints = problem.constraints.iter().chain(added);
+        assert_eq!(multipliers.len(), problem.constraints.len() + added.len());
+        let mut combination = vec![f0_ab!(); problem.obj_coeffs.len()];
+        let mut smallest = f0_ab!();
+        for (constraint, multiplier) in constraints.zip(multipliers) {
+            for (var, coeff) in constraint.coeffs.iter() {
+                combination[var] += coeff * multiplier;
+            }
+            if multiplier.is_positive() {
+                smallest += multiplier * &constraint.min;
+            } else if multiplier.is_negative() {
+                smallest += multiplier * &constraint.max;
+            }
+        }
+        let mut largest = f0_ab!();
+        for (var, coeff) in combination.iter().enumerate() {
+            if coeff.is_positive() {
+                largest += coeff * &problem.var_maxs[var];
+            } else if coeff.is_negative() {
+                largest += coeff * &problem.var_mins[var];
+            }
+        }
+        assert!(smallest.is_finite());
+        assert!(smallest > largest, "{} <= {}", smallest, largest);
+    }
+
+    /// Check that the problem is unbounded along the ray: moving along it improves the
+    /// objective and keeps all constraints and bounds.
+    fn check_ray(problem: &Problem, error: Result<Solution, Error>) {
+        let Err(Error::Unbounded(ray)) = error else {
+            panic!("expected an unbounded problem, got {:?}", error);
+        };
+        let direction = ray.direction();
+        assert_eq!(direction.len(), problem.obj_coeffs.len());
+        let change: AbnormalFraction = (problem.obj_coeffs.iter().zip(direction))
+            .map(|(obj_coeff, dir)| obj_coeff * dir)
+            .sum();
+        // The objective is stored in the form that is minimised.
+        assert!(change.is_negative());
+        for constraint in &problem.constraints {
+            let change: AbnormalFraction = (constraint.coeffs.iter())
+                .map(|(var, coeff)| coeff * &direction[var])
+                .sum();
+            assert!(!change.is_negative() || constraint.min.is_infinite());
+            assert!(!change.is_positive() || constraint.max.is_infinite());
+        }
+        for (var, dir) in direction.iter().enumerate() {
+            assert!(!dir.is_negative() || problem.var_mins[var].is_infinite());
+            assert!(!dir.is_positive() || problem.var_maxs[var].is_infinite());
+        }
+    }
+
+    #[test]
+    fn infeasibility_certificate() {
+        let all_options = [false, true].into_iter().flat_map(|presolve| {
+            [Scaling::Off, Scaling::Always]
+                .map(|scaling| SolveOptions::new().presolve(presolve).scaling(scaling))
+        });
+        for options in all_options {
+            // x + 2y >= 10 and 3x + y <= 6 with y <= 2 and a fixed z: the dual simplex finds
+            // no entering column.
+            let mut problem = Problem::new(OptimisationDirection::Minimise);
+            let x = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+            let y = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(2)));
+            let z = problem.add_var(f1_ab!(), (f1_ab!(), f1_ab!()));
+            problem
+                .add_constraint(
+                    &[(x, f1_ab!()), (y, f_ab!(2)), (z, f1_ab!())],
+                    ComparisonOp::Ge,
+                    f_ab!(11),
+                )
+                .unwrap();
+            problem
+                .add_constraint(&[(x, f_ab!(3)), (y, f1_ab!())], ComparisonOp::Le, f_ab!(6))
+                .unwrap();
+            check_certificate(&problem, &[], problem.solve_with_options(&options));
+
+            // Two singleton rows contradict each other, which presolve finds.
+            let mut problem = Problem::new(OptimisationDirection::Maximise);
+            let x = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+            let y = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+            problem
+                .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Le, f_ab!(15))
+                .unwrap();
+            problem
+                .add_constraint(&[(x, f_ab!(2))], ComparisonOp::Ge, f_ab!(8))
+                .unwrap();
+            problem
+                .add_constraint(&[(x, -f1_ab!())], ComparisonOp::Ge, -f_ab!(3))
+                .unwrap();
+            check_certificate(&problem, &[], problem.solve_with_options(&options));
+
+            // A fixed var leaves a row without vars that excludes its value.
+            let mut problem = Problem::new(OptimisationDirection::Minimise);
+            let x = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+            let y = problem.add_var(f1_ab!(), (f_ab!(2), f_ab!(2)));
+            problem
+                .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Le, f_ab!(15))
+                .unwrap();
+            problem
+                .add_constraint(&[(y, f_ab!(3))], ComparisonOp::Ge, f_ab!(7))
+                .unwrap();
+            check_certificate(&problem, &[], problem.solve_with_options(&options));
+
+            // A constraint added to the solution, also on vars that presolve removed.
+            let mut problem = Problem::new(OptimisationDirection::Maximise);
+            let x = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+            let y = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+            let z = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+            problem
+                .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Le, f_ab!(4))
+                .unwrap();
+            problem
+                .add_constraint(&[(z, f_ab!(2))], ComparisonOp::Le, f_ab!(6))
+                .unwrap();
+            problem
+                .add_constraint(&[(z, f1_ab!())], ComparisonOp::Ge, f_ab!(3))
+                .unwrap();
+            let added = [
+                Constraint::new(
+                    CsVec::new(3, vec![0, 2], vec![f1_ab!(), f1_ab!()]),
+                    ComparisonOp::Ge,
+                    f_ab!(8),
+                ),
+                Constraint::new(
+                    CsVec::new(3, vec![2], vec![f1_ab!()]),
+                    ComparisonOp::Le,
+                    f1_ab!(),
+                ),
+            ];
+            let solution = problem.solve_with_options(&options).unwrap();
+            check_certificate(
+                &problem,
+                &added[..1],
+                solution.clone().add_constraint(
+                    &[(x, f1_ab!()), (z, f1_ab!())],
+                    ComparisonOp::Ge,
+                    f_ab!(8),
+                ),
+            );
+            check_certificate(
+                &problem,
+                &added[1..],
+                solution.add_constraint(&[(z, f1_ab!())], ComparisonOp::Le, f1_ab!()),
+            );
+        }
+    }
+
+    #[test]
+    fn unbounded_ray() {
+        let all_options = [false, true].into_iter().flat_map(|presolve| {
+            [Scaling::Off, Scaling::Always]
+                .map(|scaling| SolveOptions::new().presolve(presolve).scaling(scaling))
+        });
+        for options in all_options {
+            // max x + y s.t. x - 2y <= 1 and -3x + y <= 2: both can grow along (2, 1).
+            let mut problem = Problem::new(OptimisationDirection::Maximise);
+            let x = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+            let y = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+            let z = problem.add_var(f1_ab!(), (f1_ab!(), f1_ab!()));
+            problem
+                .add_constraint(
+                    &[(x, f1_ab!()), (y, -f_ab!(2)), (z, f1_ab!())],
+                    ComparisonOp::Le,
+                    f_ab!(2),
+                )
+                .unwrap();
+            problem
+                .add_constraint(&[(x, -f_ab!(3)), (y, f1_ab!())], ComparisonOp::Le, f_ab!(2))
+                .unwrap();
+            check_ray(&problem, problem.solve_with_options(&options));
+
+            // Without constraints, a free var with a cost.
+            let mut problem = Problem::new(OptimisationDirection::Minimise);
+            problem.add_var(f1_ab!(), (f0_ab!(), f1_ab!()));
+            problem.add_var(
+                f1_ab!(),
+                (
+                    AbnormalFraction::neg_infinity(),
+                    AbnormalFraction::infinity(),
+                ),
+            );
+            check_ray(&problem, problem.solve_with_options(&options));
+        }
+    }
+
+    #[test]
+    fn interrupted_partial_solution() {
+        // Every var enters the basis in a separate iteration, limited by its own row.
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        for _ in 0..20 {
+            let var = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+            problem
+                .add_constraint(&[(var, f1_ab!())], ComparisonOp::Le, f1_ab!())
+                .unwrap();
+        }
+        let options = SolveOptions::new().on_iteration(|info| {
+            if info.iteration == 5 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        let Err(Error::Interrupted(partial)) = problem.solve_with_options(&options) else {
+            panic!("expected an interrupted solve");
+        };
+        assert!(partial.is_feasible());
+        assert_eq!(partial.values().len(), 20);
+        assert_eq!(
+            partial.values().iter().cloned().sum::<AbnormalFraction>(),
+            f_ab!(5)
+        );
+        assert_eq!(partial.basis().var_statuses().len(), 20);
+
+        // The basis continues the solve from there.
+        let solution = problem.solve_from_basis(partial.basis()).unwrap();
+        assert_eq!(solution.objective(), f_ab!(20));
+        assert_eq!(solution.stats().iterations(), 15);
+    }
+
+    #[test]
+    fn duplicate_terms() {
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        let y = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        problem
+            .add_constraint(
+                &[(y, f1_ab!()), (x, f_ab!(2)), (y, -f1_ab!()), (x, f_ab!(3))],
+                ComparisonOp::Ge,
+                f_ab!(10),
+            )
+            .unwrap();
+        let coeffs = &problem.constraints[0].coeffs;
+        assert_eq!(coeffs.indices(), &[x.0]);
+        assert_eq!(coeffs.data(), &[f_ab!(5)]);
+
+        problem
+            .set_objective(&[(x, f1_ab!()), (y, f_ab!(2)), (x, f1_ab!())])
+            .unwrap();
+        assert_eq!(problem.obj_coeffs, vec![f_ab!(2), f_ab!(2)]);
+
+        let solution = problem.solve().unwrap();
+        assert_eq!(solution[x], f_ab!(2));
+        assert_eq!(solution.objective(), f_ab!(4));
+    }
+
+    #[test]
+    fn non_finite_inputs() {
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        let non_finite = |part, var| Err(ModelError::NonFinite { part, var }.into());
+
+        assert_eq!(
+            problem.add_constraint(
+                &[(x, AbnormalFraction::infinity())],
+                ComparisonOp::Le,
+                f1_ab!()
+            ),
+            non_finite("coefficient", Some(x))
+        );
+        assert_eq!(
+            problem.add_constraint(&[(x, f1_ab!())], ComparisonOp::Le, AbnormalFraction::NaN),
+            non_finite("right-hand side", None)
+        );
+        assert_eq!(
+            problem.add_range_constraint(&[(x, f1_ab!())], AbnormalFraction::infinity(), f1_ab!()),
+            non_finite("bound", None)
+        );
+        assert_eq!(problem.num_constraints(), 0);
+
+        problem
+            .add_constraint(&[(x, f1_ab!())], ComparisonOp::Le, f1_ab!())
+            .unwrap();
+        assert_eq!(
+            problem.set_rhs(0, AbnormalFraction::neg_infinity()),
+            non_finite("right-hand side", None)
+        );
+        assert_eq!(
+            problem.set_objective_coeff(x, AbnormalFraction::NaN),
+            non_finite("objective coefficient", Some(x))
+        );
+        assert_eq!(
+            problem.set_var_upper(x, Some(AbnormalFraction::infinity())),
+            non_finite("bound", Some(x))
+        );
+        assert_eq!(problem.solve().unwrap().objective(), f0_ab!());
+
+        // An invalid variable is reported when solving.
+        let y = problem.add_var(f1_ab!(), (AbnormalFraction::NaN, f1_ab!()));
+        assert_eq!(problem.solve().err(), non_finite("bound", Some(y)).err());
+
+        let err = Problem::from_parts(
+            vec![f1_ab!()],
+            sprs::CsMat::new(
+                (1, 1),
+                vec![0, 1],
+                vec![0],
+                vec![AbnormalFraction::infinity()],
+            ),
+            vec![ComparisonOp::Le],
+            vec![f1_ab!()],
+            vec![(None, None)],
+            OptimisationDirection::Minimise,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ModelError::NonFinite {
+                part: "coefficient",
+                var: Some(Variable(0))
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn from_parts() {
+        let mut built = Problem::new(OptimisationDirection::Maximise);
+        let x = built.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        let y = built.add_var(f_ab!(2), (f0_ab!(), f_ab!(3)));
+        let z = built.add_var(f0_ab!(), (AbnormalFraction::neg_infinity(), f1_ab!()));
+        built
+            .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Le, f_ab!(4))
+            .unwrap();
+        built
+            .add_constraint(&[(x, f_ab!(2)), (y, f1_ab!())], ComparisonOp::Ge, f_ab!(2))
+            .unwrap();
+        built
+            .add_constraint(&[(y, f1_ab!()), (z, f1_ab!())], ComparisonOp::Eq, f_ab!(3))
+            .unwrap();
+
+        let mut a = sprs::TriMat::new((3, 3));
+        for (r, c, val) in [
+            (0, 0, 1usize),
+            (0, 1, 1),
+            (1, 0, 2),
+            (1, 1, 1),
+            (2, 1, 1),
+            (2, 2, 1),
+        ] {
+            a.add_triplet(r, c, f_ab!(val));
+        }
+        let parts = |a: sprs::CsMatI<AbnormalFraction, usize>| {
+            Problem::from_parts(
+                vec![f1_ab!(), f_ab!(2), f0_ab!()],
+                a,
+                vec![ComparisonOp::Le, ComparisonOp::Ge, ComparisonOp::Eq],
+                vec![f_ab!(4), f_ab!(2), f_ab!(3)],
+                vec![
+                    (Some(f0_ab!()), None),
+                    (Some(f0_ab!()), Some(f_ab!(3))),
+                    (None, Some(f1_ab!())),
+                ],
+                OptimisationDirection::Maximise,
+            )
+            .unwrap()
+        };
+
+        for from_parts in [parts(a.to_csr()), parts(a.to_csc())] {
+            assert_eq!(from_parts.constraints, built.constraints);
+            assert_eq!(from_parts.size_report(), built.size_report());
+            assert_eq!(from_parts.var(1), y);
+            let solution = from_parts.solve().unwrap();
+            let expected = built.solve().unwrap();
+            assert_eq!(solution.objective(), expected.objective());
+            assert_eq!(solution.objective(), f_ab!(7));
+            for ((_, val), (_, expected_val)) in solution.iter().zip(expected.iter()) {
+                assert_eq!(val, expected_val);
+            }
+        }
+
+        let err = Problem::from_parts(
+            vec![f1_ab!(); 2],
+            a.to_csr(),
+            vec![ComparisonOp::Le; 3],
+            vec![f0_ab!(); 3],
+            vec![(None, None); 3],
+            OptimisationDirection::Minimise,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ModelError::DimensionMismatch {
+                part: "objective",
+                expected: 3,
+                found: 2
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn dense_columns() {
+        // z occurs in every constraint and ends up in the basis.
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let z = problem.add_var(f_ab!(10), (f0_ab!(), AbnormalFraction::infinity()));
+        for i in 0..40 {
+            let x = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+            problem
+                .add_constraint(
+                    &[(z, f1_ab!()), (x, f1_ab!())],
+                    ComparisonOp::Ge,
+                    f_ab!(1 + i % 3),
+                )
+                .unwrap();
+        }
+        let refactor_policy = RefactorPolicy {
+            max_updates: 1,
+            ..Default::default()
+        };
+        let default = problem
+            .solve_with_options(&SolveOptions::new().refactor_policy(refactor_policy.clone()))
+            .unwrap();
+        let dense = problem
+            .solve_with_options(
+                &SolveOptions::new()
+                    .refactor_policy(refactor_policy)
+                    .dense_column_fraction(Some(0.5)),
+            )
+            .unwrap();
+        assert_eq!(default.objective(), f_ab!(30));
+        assert_eq!(dense.objective(), default.objective());
+        assert_eq!(dense[z], f_ab!(3));
+        for ((_, default_val), (_, val)) in default.iter().zip(dense.iter()) {
+            assert_eq!(val, default_val);
+        }
+        assert_eq!(default.stats().dense_columns, 0);
+        assert!(dense.stats().dense_columns > 0);
+        assert!(dense.stats().lu_nnz < default.stats().lu_nnz);
+    }
+
+    #[test]
+    fn lexicographic_two_objectives() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let x = problem.add_var(f0_ab!(), (f0_ab!(), f_ab!(10)));
+        let y = problem.add_var(f0_ab!(), (f0_ab!(), f_ab!(10)));
+        problem
+            .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Le, f_ab!(10))
+            .unwrap();
+
+        let objectives = [
+            ObjectiveSpec::new(
+                OptimisationDirection::Maximise,
+                &[(x, f1_ab!()), (y, f1_ab!())],
+            ),
+            ObjectiveSpec::new(OptimisationDirection::Minimise, &[(x, f1_ab!())]),
+        ];
+        let (solution, values) = problem.solve_lexicographic(&objectives).unwrap();
+        assert_eq!(values, vec![f_ab!(10), f0_ab!()]);
+        assert_eq!(solution[x], f0_ab!());
+        assert_eq!(solution[y], f_ab!(10));
+        assert_eq!(solution.objective(), f0_ab!());
+    }
+
+    #[test]
+    fn lexicographic_three_objectives() {
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let x = problem.add_var(f0_ab!(), (f0_ab!(), f_ab!(10)));
+        let y = problem.add_var(f0_ab!(), (f0_ab!(), f_ab!(10)));
+        let z = problem.add_var(f0_ab!(), (f0_ab!(), f_ab!(10)));
+        problem
+            .add_constraint(
+                &[(x, f1_ab!()), (y, f1_ab!()), (z, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(10),
+            )
+            .unwrap();
+        problem
+            .add_constraint(&[(x, f1_ab!()), (z, f1_ab!())], ComparisonOp::Le, f_ab!(6))
+            .unwrap();
+
+        // On its own, the last objective would take x = 6.
+        let objectives = [
+            ObjectiveSpec::new(
+                OptimisationDirection::Maximise,
+                &[(x, f1_ab!()), (y, f1_ab!()), (z, f1_ab!())],
+            ),
+            ObjectiveSpec::new(OptimisationDirection::Maximise, &[(z, f_ab!(2))]),
+            ObjectiveSpec::new(OptimisationDirection::Maximise, &[(x, f1_ab!())]),
+        ];
+        let (solution, values) = problem.solve_lexicographic(&objectives).unwrap();
+        assert_eq!(values, vec![f_ab!(10), f_ab!(12), f0_ab!()]);
+        assert_eq!(solution[x], f0_ab!());
+        assert_eq!(solution[y], f_ab!(4));
+        assert_eq!(solution[z], f_ab!(6));
+    }
+
+    #[test]
+    fn perturbation() {
+        // Supplies and demands are equal, so the optimum is highly degenerate.
+        let n = 8;
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let vars = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| {
+                        let profit = f_ab!((i * 7 + j * 3) % 11 + 1);
+                        problem.add_var(profit, (f0_ab!(), AbnormalFraction::infinity()))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for row in &vars {
+            let supply = (row.iter()).map(|&var| (var, f1_ab!())).collect::<Vec<_>>();
+            problem
+                .add_constraint(&supply, ComparisonOp::Le, f_ab!(10))
+                .unwrap();
+        }
+        for j in 0..n {
+            let demand = (vars.iter())
+                .map(|row| (row[j], f1_ab!()))
+                .collect::<Vec<_>>();
+            problem
+                .add_constraint(&demand, ComparisonOp::Le, f_ab!(10))
+                .unwrap();
+        }
+
+        let plain = problem.solve().unwrap();
+        let perturbed = problem
+            .solve_with_options(&SolveOptions::new().perturbation(true))
+            .unwrap();
+        assert_eq!(perturbed.objective(), plain.objective());
+
+        // The solution is exactly feasible for the unperturbed problem.
+        for i in 0..n {
+            let mut supply = f0_ab!();
+            let mut demand = f0_ab!();
+            for j in 0..n {
+                assert!(perturbed[vars[i][j]].is_not_negative());
+                supply += perturbed[vars[i][j]].clone();
+                demand += perturbed[vars[j][i]].clone();
+            }
+            assert!(supply <= f_ab!(10));
+            assert!(demand <= f_ab!(10));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn basis_json() {
+        let basis = random_problem(5, 20, 10).solve().unwrap().extract_basis();
+        let json = serde_json::to_string(&basis).unwrap();
+        let basis: Basis = serde_json::from_str(&json).unwrap();
+
+        let warm = random_problem(5, 20, 10).solve_from_basis(&basis).unwrap();
+        assert_eq!(warm.stats().phase1_iterations, 0);
+    }
+
+    #[test]
+    fn gomory_cut() {
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let v1 = problem.add_var(f0_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        let v2 = problem.add_var(-f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+
+        problem
+            .add_constraint(
+                &[(v1, f_ab!(3)), (v2, f_ab!(2))],
+                ComparisonOp::Le,
+                f_ab!(6),
+            )
+            .unwrap();
+
+        problem
+            .add_constraint(
+                &[(v1, -f_ab!(3)), (v2, f_ab!(2))],
+                ComparisonOp::Le,
+                f0_ab!(),
+            )
+            .unwrap();
+
+        let mut sol = problem.solve().unwrap();
+        assert_eq!(sol[v1], f1_ab!());
+        assert_eq!(sol[v2], f_ab!(15, 10));
+        assert_eq!(sol.objective(), -f_ab!(15, 10));
+
+        sol = sol.add_gomory_cut(v2).unwrap();
+        assert!(AbnormalFraction::abs(&sol[v1] - &f_ab!(2, 3)) < f_ab!(1, 1000000000));
+        assert_eq!(sol[v2], f1_ab!());
+        assert_eq!(sol.objective(), -f1_ab!());
+
+        sol = sol.add_gomory_cut(v1).unwrap();
+        assert!(AbnormalFraction::abs(&sol[v1] - &f1_ab!()) < f_ab!(1, 1000000000));
+        assert_eq!(sol[v2], f1_ab!());
+        assert_eq!(sol.objective(), -f1_ab!());
+
+        // Presolve removes a fixed var, so it is not basic.
+        let v3 = problem.add_var(f0_ab!(), (f_ab!(2), f_ab!(2)));
+        let sol = (problem.solve_with_options(&SolveOptions::new().presolve(true))).unwrap();
+        let err = sol.add_gomory_cut(v3).unwrap_err();
+        assert_eq!(err, ModelError::NoGomoryCut(v3).into());
+    }
+
+    const ALL_PRICINGS: [Pricing; 3] = [Pricing::Dantzig, Pricing::Devex, Pricing::SteepestEdge];
+
+    fn random_problem(seed: u64, num_vars: usize, num_constraints: usize) -> Problem {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let vars = (0..num_vars)
+            .map(|_| problem.add_var(f_ab!(rng.random_range(1..10usize)), (f0_ab!(), f_ab!(10))))
+            .collect::<Vec<_>>();
+        for _ in 0..num_constraints {
+            let expr = (vars.iter())
+                .filter_map(|&var| {
+                    let coeff = f_ab!(rng.random_range(1..5usize));
+                    rng.random_bool(0.5).then_some((var, coeff))
+                })
+                .collect::<LinearExpr>();
+            problem
+                .add_constraint(expr, ComparisonOp::Le, f_ab!(rng.random_range(10..50usize)))
+                .unwrap();
+        }
+        problem
+    }
+
+    #[test]
+    fn pricing_rules_agree() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let v1 = problem.add_var(f_ab!(3), (f_ab!(12), AbnormalFraction::infinity()));
+        let v2 = problem.add_var(f_ab!(4), (f_ab!(5), AbnormalFraction::infinity()));
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(20),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, -f_ab!(4))],
+                ComparisonOp::Ge,
+                -f_ab!(20),
+            )
+            .unwrap();
+
+        for pricing in ALL_PRICINGS {
+            let sol = problem
+                .solve_with_options(&SolveOptions::new().pricing(pricing))
+                .unwrap();
+            assert_eq!(sol[v1], f_ab!(12), "{:?}", pricing);
+            assert_eq!(sol[v2], f_ab!(8), "{:?}", pricing);
+            assert_eq!(sol.objective(), f_ab!(68), "{:?}", pricing);
+        }
+    }
+
+    #[test]
+    fn pricing_rules_random() {
+        let mut dantzig_iterations = 0;
+        let mut devex_iterations = 0;
+        for seed in 0..20 {
+            let problem = random_problem(seed, 15, 10);
+
+            let objectives = ALL_PRICINGS.map(|pricing| {
+                let sol = problem
+                    .solve_with_options(&SolveOptions::new().pricing(pricing))
+                    .unwrap();
+                match pricing {
+                    Pricing::Dantzig => dantzig_iterations += sol.stats().iterations(),
+                    Pricing::Devex => devex_iterations += sol.stats().iterations(),
+                    Pricing::SteepestEdge => {}
+                }
+                sol.objective()
+            });
+            assert_eq!(objectives[0], objectives[1], "seed {}", seed);
+            assert_eq!(objectives[0], objectives[2], "seed {}", seed);
+        }
+
+        // Devex may lose on individual problems, but should not be much worse overall.
+        assert!(
+            devex_iterations <= 2 * dantzig_iterations + 10,
+            "devex {} dantzig {}",
+            devex_iterations,
+            dantzig_iterations
+        );
+    }
+
+    #[test]
+    fn dual_simplex() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let v1 = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        let v2 = problem.add_var(
+            f_ab!(2),
+            (
+                AbnormalFraction::neg_infinity(),
+                AbnormalFraction::infinity(),
+            ),
+        );
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(4),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, f1_ab!())],
+                ComparisonOp::Ge,
+                f_ab!(2),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, -f1_ab!())],
+                ComparisonOp::Ge,
+                f0_ab!(),
+            )
+            .unwrap();
+
+        let options = SolveOptions::new().method(Method::DualSimplex);
+        let sol = problem.solve_with_options(&options).unwrap();
+        assert_eq!(sol[v1], f_ab!(2));
+        assert_eq!(sol[v2], f_ab!(2));
+        assert_eq!(sol.objective(), f_ab!(6));
+
+        // Box-constrained with costs of both signs: the starting basis is dual feasible, so
+        // there is no phase 2.
+        for seed in 0..10 {
+            let mut problem = random_problem(seed, 15, 10);
+            let v = problem.add_var(-f_ab!(3), (f0_ab!(), f_ab!(5)));
+            problem
+                .add_constraint(&[(v, f1_ab!())], ComparisonOp::Ge, f1_ab!())
+                .unwrap();
+            let auto = problem.solve().unwrap();
+            let dual = problem.solve_with_options(&options).unwrap();
+            assert_eq!(auto.objective(), dual.objective(), "seed {}", seed);
+            assert_eq!(dual.stats().phase2_iterations, 0, "seed {}", seed);
+        }
+
+        let mut infeasible = Problem::new(OptimisationDirection::Minimise);
+        let v = infeasible.add_var(f1_ab!(), (f0_ab!(), f_ab!(3)));
+        infeasible
+            .add_constraint(&[(v, f1_ab!())], ComparisonOp::Ge, f_ab!(4))
+            .unwrap();
+        assert!(matches!(
+            infeasible.solve_with_options(&options).err(),
+            Some(Error::Infeasible(_))
+        ));
+    }
+
+    #[test]
+    fn dual_bound_flipping() {
+        // Every pivot refactorises, so fewer pivots means fewer factorisations.
+        let options = SolveOptions::new()
+            .method(Method::DualSimplex)
+            .refactor_policy(RefactorPolicy {
+                max_updates: 1,
+                ..Default::default()
+            });
+        let without_flips = options.clone().bound_flipping(false);
+
+        let (mut flips, mut factorisations, mut factorisations_without) = (0, 0, 0);
+        for seed in 0..10 {
+            // All variables start at their upper bound and most constraints are violated.
+            let problem = random_problem(seed, 30, 10);
+            let sol = problem.solve_with_options(&options).unwrap();
+            let sol_without = problem.solve_with_options(&without_flips).unwrap();
+            assert_eq!(sol.objective(), sol_without.objective(), "seed {}", seed);
+            assert_eq!(problem.verify(&sol), Ok(()), "seed {}", seed);
+            assert_eq!(sol_without.stats().dual_bound_flips, 0);
+
+            flips += sol.stats().dual_bound_flips;
+            factorisations += sol.stats().lu_factorisations;
+            factorisations_without += sol_without.stats().lu_factorisations;
         }
+        assert!(flips > 0);
+        assert!(factorisations < factorisations_without);
     }
-}
 
-impl<'a> IntoIterator for &'a Solution {
-    type Item = (Variable, &'a AbnormalFraction);
-    type IntoIter = SolutionIter<'a>;
+    #[test]
+    fn harris_ratio_test() {
+        // Constraints with the rows of a Hilbert matrix, which is badly conditioned.
+        let n = 8;
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let vars = (0..n)
+            .map(|i| problem.add_var(f_ab!(i + 1), (f0_ab!(), f_ab!(1000))))
+            .collect::<Vec<_>>();
+        for i in 0..n {
+            let expr = (vars.iter().enumerate())
+                .map(|(j, &var)| (var, f_ab!(1, i + j + 1)))
+                .collect::<LinearExpr>();
+            problem
+                .add_constraint(expr, ComparisonOp::Le, f1_ab!())
+                .unwrap();
+        }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
+        let sol = problem.solve().unwrap();
+        let loose = problem
+            .solve_with_options(&SolveOptions::new().harris_tolerance(f_ab!(1, 1000)))
+            .unwrap();
+        if f1_ab!().is_exact() {
+            // The tolerance is not used.
+            assert_eq!(problem.verify(&sol), Ok(()));
+            assert_eq!(sol.objective(), loose.objective());
+            assert_eq!(sol.stats().pivot_hash, loose.stats().pivot_hash);
+        } else {
+            let tolerance = f_ab!(1, 1_000_000);
+            assert_eq!(problem.verify_with_tolerance(&sol, &tolerance), Ok(()));
+        }
     }
-}
 
-use crate::{abnormal_fraction::AbnormalFraction, linear_programming_solver::Solver};
+    // Needs both exact and approximate fractions.
+    #[cfg(not(any(feature = "exactarithmetic", feature = "approximatearithmetic")))]
+    #[test]
+    fn hybrid_exact() {
+        assert!(f1_ab!().is_exact() && f1_ab!().to_approx().is_some());
+        let options = SolveOptions::new().method(Method::HybridExact);
 
-#[cfg(test)]
-mod tests {
+        // The approximate basis is exactly optimal.
+        let problem = random_problem(11, 30, 20);
+        let exact = problem.solve().unwrap();
+        let hybrid = problem.solve_with_options(&options).unwrap();
+        assert_eq!(hybrid.objective(), exact.objective());
+        assert!(hybrid.stats().approximate_iterations > 0);
+        assert_eq!(hybrid.stats().iterations(), 0);
 
-    use ebi_arithmetic::{One, Signed, Zero};
+        // The objective coefficients are equal in f64, so the approximate solve stops at y,
+        // while x is better by 2^-60.
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let tiny = f_ab!(1usize, 1usize << 60);
+        let x = problem.add_var(f1_ab!() + tiny.clone(), (f0_ab!(), f1_ab!()));
+        let y = problem.add_var(f1_ab!(), (f0_ab!(), f1_ab!()));
+        problem
+            .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Le, f1_ab!())
+            .unwrap();
+        let hybrid = problem.solve_with_options(&options).unwrap();
+        assert_eq!(hybrid[x], f1_ab!());
+        assert_eq!(hybrid[y], f0_ab!());
+        assert_eq!(hybrid.objective(), f1_ab!() + tiny);
+        assert!(hybrid.stats().iterations() >= 1);
+    }
 
-    use crate::{abnormal_fraction::AbnormalFraction, f_ab, f0_ab, f1_ab};
+    #[test]
+    fn iis_constraints() {
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        let y = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        let vars = (0..8)
+            .map(|_| problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10))))
+            .collect::<Vec<_>>();
+        for i in 0..50 {
+            match i {
+                // x + y >= 15, x <= 4 and y <= 5 conflict.
+                7 => problem
+                    .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Ge, f_ab!(15))
+                    .unwrap(),
+                23 => problem
+                    .add_constraint(&[(x, f1_ab!())], ComparisonOp::Le, f_ab!(4))
+                    .unwrap(),
+                41 => problem
+                    .add_constraint(&[(y, f1_ab!())], ComparisonOp::Le, f_ab!(5))
+                    .unwrap(),
+                // The other constraints hold for all values of the other vars within their
+                // bounds.
+                _ => {
+                    let (a, b) = (vars[i % 8], vars[(i * 3 + 1) % 8]);
+                    if i % 2 == 0 {
+                        problem
+                            .add_constraint(
+                                &[(a, f1_ab!()), (b, f1_ab!())],
+                                ComparisonOp::Le,
+                                f_ab!(20 + i),
+                            )
+                            .unwrap();
+                    } else {
+                        problem
+                            .add_constraint(
+                                &[(a, f1_ab!()), (b, -f1_ab!())],
+                                ComparisonOp::Ge,
+                                -f_ab!(10 + i),
+                            )
+                            .unwrap();
+                    }
+                }
+            }
+        }
+        assert!(matches!(problem.solve().err(), Some(Error::Infeasible(_))));
 
-    use super::*;
+        let iis = problem.find_iis(&IisOptions::new()).unwrap().unwrap();
+        assert!(iis.is_minimal());
+        assert_eq!(
+            iis.members(),
+            &[
+                IisMember::Constraint(7),
+                IisMember::Constraint(23),
+                IisMember::Constraint(41)
+            ]
+        );
+        assert_eq!(iis.constraints().collect::<Vec<_>>(), vec![7, 23, 41]);
+
+        problem.constraints.remove(23);
+        assert_eq!(problem.find_iis(&IisOptions::new()).unwrap(), None);
+    }
 
     #[test]
-    fn optimise() {
+    fn iis_bounds() {
         let mut problem = Problem::new(OptimisationDirection::Maximise);
-        let v1 = problem.add_var(f_ab!(3), (f_ab!(12), AbnormalFraction::infinity()));
-        let v2 = problem.add_var(f_ab!(4), (f_ab!(5), AbnormalFraction::infinity()));
-        problem.add_constraint(
-            &[(v1, f1_ab!()), (v2, f1_ab!())],
-            ComparisonOp::Le,
-            f_ab!(20),
-        );
-        problem.add_constraint(
-            &[(v1, f1_ab!()), (v2, -f_ab!(4))],
-            ComparisonOp::Ge,
-            -f_ab!(20),
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(4)));
+        let y = problem.add_var(f1_ab!(), (f_ab!(3), AbnormalFraction::infinity()));
+        let z = problem.add_var(f1_ab!(), (f0_ab!(), f1_ab!()));
+        problem
+            .add_constraint(&[(x, f1_ab!()), (y, -f1_ab!())], ComparisonOp::Ge, f_ab!(2))
+            .unwrap();
+        problem
+            .add_constraint(&[(z, f1_ab!())], ComparisonOp::Le, f_ab!(5))
+            .unwrap();
+
+        let iis = problem.find_iis(&IisOptions::new()).unwrap().unwrap();
+        assert!(iis.is_minimal());
+        assert_eq!(
+            iis.members(),
+            &[
+                IisMember::Constraint(0),
+                IisMember::UpperBound(x),
+                IisMember::LowerBound(y)
+            ]
         );
 
-        let sol = problem.solve().unwrap();
-        assert_eq!(sol[v1], f_ab!(12));
-        assert_eq!(sol[v2], f_ab!(8));
-        assert_eq!(sol.objective(), f_ab!(68));
+        // With too few solves, not every member is tested.
+        let partial = problem
+            .find_iis(&IisOptions::new().max_solves(2))
+            .unwrap()
+            .unwrap();
+        assert!(!partial.is_minimal());
+        assert_eq!(partial.num_solves(), 2);
+        assert!(partial.members().len() > iis.members().len());
     }
 
     #[test]
-    fn empty_expr_constraints() {
-        let trivial = [
-            (LinearExpr::empty(), ComparisonOp::Eq, f0_ab!()),
-            (LinearExpr::empty(), ComparisonOp::Ge, -f1_ab!()),
-            (LinearExpr::empty(), ComparisonOp::Le, f1_ab!()),
-        ];
+    fn partial_pricing_wide() {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
 
-        let mut problem = Problem::new(OptimisationDirection::Minimise);
-        let _ = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
-        for (expr, op, b) in trivial.iter().cloned() {
-            problem.add_constraint(expr, op, b);
+        // Without upper bounds, the vars start at 0 and the primal simplex prices the columns.
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let vars = (0..500)
+            .map(|_| {
+                let obj_coeff = f_ab!(rng.random_range(1..10usize));
+                problem.add_var(obj_coeff, (f0_ab!(), AbnormalFraction::infinity()))
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..5 {
+            let expr = (vars.iter())
+                .map(|&var| (var, f_ab!(rng.random_range(1..5usize))))
+                .collect::<LinearExpr>();
+            problem
+                .add_constraint(expr, ComparisonOp::Le, f_ab!(rng.random_range(10..50usize)))
+                .unwrap();
         }
-        assert_eq!(problem.solve().map(|s| s.objective()), Ok(f0_ab!()));
 
-        {
-            let mut sol = problem.solve().unwrap();
-            for (expr, op, b) in trivial.iter().cloned() {
-                sol = sol.add_constraint(expr, op, b).unwrap();
+        let full = problem.solve().unwrap();
+        let partial = problem
+            .solve_with_options(&SolveOptions::new().partial_pricing(PartialPricing::Window(20)))
+            .unwrap();
+        assert_eq!(full.objective(), partial.objective());
+
+        // Full pricing scans every column in every iteration.
+        assert_eq!(full.stats().full_scans, full.stats().phase2_iterations + 1);
+        assert!(partial.stats().full_scans >= 1);
+        assert!(
+            2 * partial.stats().reduced_cost_evaluations < full.stats().reduced_cost_evaluations,
+            "partial {:?} full {:?}",
+            partial.stats(),
+            full.stats()
+        );
+    }
+
+    #[test]
+    fn stats_consistent() {
+        let problem = random_problem(3, 60, 40);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let options = SolveOptions::new().timings(true).on_iteration({
+            let calls = calls.clone();
+            move |_| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                ControlFlow::Continue(())
             }
-            assert_eq!(sol.objective(), f0_ab!());
-        }
+        });
+        let solution = problem.solve_with_options(&options).unwrap();
+        let stats = solution.stats();
 
-        let infeasible = [
-            (LinearExpr::empty(), ComparisonOp::Eq, f_ab!(12)),
-            (LinearExpr::empty(), ComparisonOp::Ge, f_ab!(34)),
-            (LinearExpr::empty(), ComparisonOp::Le, -f_ab!(56)),
-        ];
+        assert!(stats.iterations() > 0);
+        assert_eq!(
+            stats.iterations(),
+            stats.phase1_iterations + stats.phase2_iterations
+        );
+        assert_eq!(calls.load(Ordering::Relaxed), stats.iterations());
+        assert!(stats.lu_factorisations >= 1);
+        assert!(stats.average_fill_in() > 0.0);
+        assert!(stats.peak_fill_in + 1e-9 >= stats.average_fill_in());
+        assert!(stats.degenerate_pivots + stats.bound_flips <= stats.iterations());
+        assert!(stats.timings.is_some());
+        assert!(stats.to_string().starts_with(&format!(
+            "iterations: {} (phase 1: {}, phase 2: {})",
+            stats.iterations(),
+            stats.phase1_iterations,
+            stats.phase2_iterations
+        )));
 
-        for (expr, op, b) in infeasible.iter().cloned() {
-            let mut cloned = problem.clone();
-            cloned.add_constraint(expr, op, b);
-            assert_eq!(cloned.solve().map(|_| "solved"), Err(Error::Infeasible));
+        let untimed = problem.solve().unwrap();
+        assert_eq!(untimed.stats().timings, None);
+        assert_eq!(untimed.stats().iterations(), stats.iterations());
+    }
+
+    thread_local! {
+        static LOG_RECORDS: RefCell<Option<Vec<(log::Level, String)>>> = const { RefCell::new(None) };
+    }
+
+    /// Records the log messages of threads that are inside [`capture_log`].
+    struct CapturingLogger;
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            LOG_RECORDS.with_borrow(|records| records.is_some())
         }
 
-        for (expr, op, b) in infeasible.iter().cloned() {
-            let sol = problem.solve().unwrap().add_constraint(expr, op, b);
-            assert_eq!(sol.map(|_| "solved"), Err(Error::Infeasible));
+        fn log(&self, record: &log::Record) {
+            LOG_RECORDS.with_borrow_mut(|records| {
+                if let Some(records) = records {
+                    records.push((record.level(), record.args().to_string()));
+                }
+            });
         }
 
-        let _ = problem.add_var(-f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
-        assert_eq!(problem.solve().map(|_| "solved"), Err(Error::Unbounded));
+        fn flush(&self) {}
+    }
+
+    fn capture_log<T>(f: impl FnOnce() -> T) -> (T, Vec<(log::Level, String)>) {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&CapturingLogger).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+        LOG_RECORDS.with_borrow_mut(|records| *records = Some(Vec::new()));
+        let res = f();
+        let records = LOG_RECORDS.with_borrow_mut(|records| records.take().unwrap());
+        (res, records)
     }
 
     #[test]
-    fn free_variables() {
+    fn log_records() {
         let mut problem = Problem::new(OptimisationDirection::Maximise);
-        let v1 = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
-        let v2 = problem.add_var(
-            f_ab!(2),
-            (
-                AbnormalFraction::neg_infinity(),
-                AbnormalFraction::infinity(),
-            ),
-        );
-        problem.add_constraint(
-            &[(v1, f1_ab!()), (v2, f1_ab!())],
-            ComparisonOp::Le,
-            f_ab!(4),
-        );
-        problem.add_constraint(
-            &[(v1, f1_ab!()), (v2, f1_ab!())],
-            ComparisonOp::Ge,
-            f_ab!(2),
-        );
-        problem.add_constraint(
-            &[(v1, f1_ab!()), (v2, -f1_ab!())],
-            ComparisonOp::Ge,
-            f0_ab!(),
-        );
+        let v1 = problem.add_var(f_ab!(3), (f_ab!(12), AbnormalFraction::infinity()));
+        let v2 = problem.add_var(f_ab!(4), (f_ab!(5), AbnormalFraction::infinity()));
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(20),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(v1, f1_ab!()), (v2, -f_ab!(4))],
+                ComparisonOp::Ge,
+                -f_ab!(20),
+            )
+            .unwrap();
 
-        let sol = problem.solve().unwrap();
-        assert_eq!(sol[v1], f_ab!(2));
-        assert_eq!(sol[v2], f_ab!(2));
-        assert_eq!(sol.objective(), f_ab!(6));
+        let (sol, records) = capture_log(|| problem.solve().unwrap());
+        assert_eq!(sol.objective(), f_ab!(68));
+
+        let iteration_records = records
+            .iter()
+            .filter(|(level, msg)| *level == log::Level::Trace && msg.starts_with("iteration "))
+            .count();
+        assert!(sol.stats().iterations() > 0);
+        assert_eq!(iteration_records, sol.stats().iterations());
+        let summaries = records
+            .iter()
+            .filter(|(level, msg)| *level == log::Level::Debug && msg.starts_with("solved"))
+            .count();
+        assert_eq!(summaries, 1);
     }
 
     #[test]
-    fn fix_unfix_var() {
+    fn parallel_pricing() {
+        // With the `parallel` feature, a threshold of 0 scores every full scan in parallel.
+        let problem = random_problem(11, 300, 40);
+        let pivots = |threshold: usize| {
+            let pivots = Arc::new(parking_lot::Mutex::new(Vec::new()));
+            let options = SolveOptions::new()
+                .parallel_pricing_threshold(threshold)
+                .on_iteration({
+                    let pivots = pivots.clone();
+                    move |info| {
+                        pivots.lock().push((info.entering_var, info.leaving_var));
+                        ControlFlow::Continue(())
+                    }
+                });
+            let solution = problem.solve_with_options(&options).unwrap();
+            let pivots = pivots.lock().clone();
+            (solution.objective(), pivots)
+        };
+
+        let (sequential_obj, sequential) = pivots(usize::MAX);
+        let (parallel_obj, parallel) = pivots(0);
+        assert!(!sequential.is_empty());
+        assert_eq!(sequential, parallel);
+        assert_eq!(sequential_obj, parallel_obj);
+    }
+
+    #[test]
+    fn alternative_optima() {
+        // Every point between (3, 1) and (1, 3) is optimal.
         let mut problem = Problem::new(OptimisationDirection::Maximise);
-        let v1 = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(3)));
-        let v2 = problem.add_var(f_ab!(2), (f0_ab!(), f_ab!(3)));
-        problem.add_constraint(
-            &[(v1, f1_ab!()), (v2, f1_ab!())],
-            ComparisonOp::Le,
-            f_ab!(4),
-        );
-        problem.add_constraint(
-            &[(v1, f1_ab!()), (v2, f1_ab!())],
-            ComparisonOp::Ge,
-            f1_ab!(),
-        );
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(3)));
+        let y = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(3)));
+        problem
+            .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Le, f_ab!(4))
+            .unwrap();
+        assert!(!problem.solve().unwrap().is_unique());
 
-        let orig_sol = problem.solve().unwrap();
+        let optima = problem.alternative_optima(5).unwrap();
+        let mut vertices = optima
+            .iter()
+            .map(|sol| {
+                assert_eq!(sol.objective(), f_ab!(4));
+                (sol[x].clone(), sol[y].clone())
+            })
+            .collect::<Vec<_>>();
+        vertices.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(vertices, vec![(f1_ab!(), f_ab!(3)), (f_ab!(3), f1_ab!())]);
+        assert_eq!(problem.alternative_optima(1).unwrap().len(), 1);
+        assert!(problem.alternative_optima(0).unwrap().is_empty());
 
-        {
-            let mut sol = orig_sol.clone().fix_var(v1, f_ab!(1, 2)).unwrap();
-            assert_eq!(sol[v1], f_ab!(1, 2));
-            assert_eq!(sol[v2], f_ab!(3));
-            assert_eq!(sol.objective(), f_ab!(65, 10));
+        // With another objective, only (1, 3) is optimal.
+        problem.set_objective_coeff(y, f_ab!(2)).unwrap();
+        let sol = problem.solve().unwrap();
+        assert!(sol.is_unique());
+        let optima = problem.alternative_optima(5).unwrap();
+        assert_eq!(optima.len(), 1);
+        assert_eq!((&optima[0][x], &optima[0][y]), (&f1_ab!(), &f_ab!(3)));
+    }
 
-            sol = sol.unfix_var(v1).0;
-            assert_eq!(sol[v1], f1_ab!());
-            assert_eq!(sol[v2], f_ab!(3));
-            assert_eq!(sol.objective(), f_ab!(7));
-        }
+    #[test]
+    fn objective_constant() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(3)));
+        let y = problem.add_var(f_ab!(2), (f0_ab!(), f_ab!(3)));
+        problem.set_objective_constant(-f_ab!(10)).unwrap();
+        problem
+            .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Le, f_ab!(4))
+            .unwrap();
 
-        {
-            let mut sol = orig_sol.clone().fix_var(v2, f_ab!(25, 10)).unwrap();
-            assert_eq!(sol[v1], f_ab!(15, 10));
-            assert_eq!(sol[v2], f_ab!(25, 10));
-            assert_eq!(sol.objective(), f_ab!(65, 10));
+        let sol = problem.solve().unwrap();
+        assert_eq!(sol.objective(), -f_ab!(3));
+        assert_eq!(problem.verify(&sol), Ok(()));
+        assert_eq!(
+            problem.optimal_value(&SolveOptions::new().presolve(true)),
+            Ok(-f_ab!(3))
+        );
 
-            sol = sol.unfix_var(v2).0;
-            assert_eq!(sol[v1], f1_ab!());
-            assert_eq!(sol[v2], f_ab!(3));
-            assert_eq!(sol.objective(), f_ab!(7));
-        }
+        // The constant of an objective expression replaces the constant term.
+        let mut expr = LinearExpr::from(&[(x, f1_ab!())]);
+        expr.add_constant(f_ab!(5));
+        problem.set_objective(expr).unwrap();
+        assert_eq!(problem.objective_constant(), &f_ab!(5));
+        assert_eq!(problem.solve().unwrap().objective(), f_ab!(8));
+
+        assert!(matches!(
+            problem.set_objective_constant(AbnormalFraction::infinity()),
+            Err(Error::InvalidModel(ModelError::NonFinite { .. }))
+        ));
     }
 
     #[test]
-    fn add_constraint() {
-        let mut problem = Problem::new(OptimisationDirection::Minimise);
-        let v1 = problem.add_var(f_ab!(2), (f0_ab!(), AbnormalFraction::infinity()));
-        let v2 = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
-        problem.add_constraint(
-            &[(v1, f1_ab!()), (v2, f1_ab!())],
-            ComparisonOp::Le,
-            f_ab!(4),
-        );
-        problem.add_constraint(
-            &[(v1, f1_ab!()), (v2, f1_ab!())],
-            ComparisonOp::Ge,
-            f_ab!(2),
-        );
+    fn affine_constraints() {
+        // x + y + 3 <= 7 and 2 <= x - y + 1 <= 4 written as expressions with constants, and
+        // with the constants moved to the right-hand sides by hand.
+        let build = |with_constants: bool| {
+            let mut problem = Problem::new(OptimisationDirection::Maximise);
+            let x = problem.add_var(f_ab!(2), (f0_ab!(), AbnormalFraction::infinity()));
+            let y = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+            let mut sum = LinearExpr::from(&[(x, f1_ab!()), (y, f1_ab!())]);
+            let mut diff = LinearExpr::from(&[(x, f1_ab!()), (y, -f1_ab!())]);
+            if with_constants {
+                sum.add_constant(f_ab!(3));
+                diff.add_constant(f1_ab!());
+                problem
+                    .add_constraint(sum, ComparisonOp::Le, f_ab!(7))
+                    .unwrap();
+                problem
+                    .add_range_constraint(diff, f_ab!(2), f_ab!(4))
+                    .unwrap();
+            } else {
+                problem
+                    .add_constraint(sum, ComparisonOp::Le, f_ab!(4))
+                    .unwrap();
+                problem
+                    .add_range_constraint(diff, f_ab!(1), f_ab!(3))
+                    .unwrap();
+            }
+            (problem, x, y)
+        };
 
-        let orig_sol = problem.solve().unwrap();
+        let (affine, x, y) = build(true);
+        let (normalised, _, _) = build(false);
+        assert_eq!(affine.constraints, normalised.constraints);
+        let sol = affine.solve().unwrap();
+        assert_eq!(sol.objective(), f_ab!(15, 2));
+        assert_eq!(sol[x], f_ab!(7, 2));
+        assert_eq!(sol[y], f_ab!(1, 2));
 
-        {
-            let sol = orig_sol
-                .clone()
+        // The same holds for constraints added to a solution.
+        let mut expr = LinearExpr::from(&[(x, f1_ab!())]);
+        expr.add_constant(f1_ab!());
+        let sol = sol
+            .add_constraint(expr, ComparisonOp::Le, f_ab!(3))
+            .unwrap();
+        assert_eq!(sol[x], f_ab!(2));
+        assert_eq!(sol[y], f1_ab!());
+        assert_eq!(sol.objective(), f_ab!(5));
+    }
+
+    #[test]
+    fn standard_form_round_trip() {
+        for direction in [
+            OptimisationDirection::Minimise,
+            OptimisationDirection::Maximise,
+        ] {
+            let mut problem = Problem::new(direction);
+            let shifted = problem.add_var(f1_ab!(), (f_ab!(1), f_ab!(6)));
+            let negated = problem.add_var(-f_ab!(2), (AbnormalFraction::neg_infinity(), f_ab!(4)));
+            let free = problem.add_var(
+                f_ab!(3),
+                (
+                    AbnormalFraction::neg_infinity(),
+                    AbnormalFraction::infinity(),
+                ),
+            );
+            let fixed = problem.add_var(f1_ab!(), (f_ab!(2), f_ab!(2)));
+            problem
                 .add_constraint(
-                    &[(v1, -f1_ab!()), (v2, f1_ab!())],
+                    &[(shifted, f1_ab!()), (negated, f1_ab!()), (free, f1_ab!())],
                     ComparisonOp::Le,
-                    f0_ab!(),
+                    f_ab!(8),
                 )
                 .unwrap();
-
-            assert_eq!(sol[v1], f1_ab!());
-            assert_eq!(sol[v2], f1_ab!());
-            assert_eq!(sol.objective(), f_ab!(3));
-        }
-
-        {
-            let sol = orig_sol
-                .clone()
-                .fix_var(v2, f_ab!(15, 10))
-                .unwrap()
-                .add_constraint(
-                    &[(v1, -f1_ab!()), (v2, f1_ab!())],
-                    ComparisonOp::Le,
-                    f0_ab!(),
+            problem
+                .add_range_constraint(
+                    &[(negated, f1_ab!()), (free, -f1_ab!())],
+                    -f_ab!(5),
+                    f_ab!(5),
                 )
                 .unwrap();
-            assert_eq!(sol[v1], f_ab!(15, 10));
-            assert_eq!(sol[v2], f_ab!(15, 10));
-            assert_eq!(sol.objective(), f_ab!(45, 10));
-        }
-
-        {
-            let sol = orig_sol
-                .clone()
+            problem
                 .add_constraint(
-                    &[(v1, -f1_ab!()), (v2, f1_ab!())],
+                    &[(shifted, f_ab!(2)), (free, f1_ab!()), (fixed, f1_ab!())],
                     ComparisonOp::Ge,
+                    -f_ab!(3),
+                )
+                .unwrap();
+            problem
+                .add_constraint(
+                    &[(negated, f1_ab!()), (fixed, f1_ab!())],
+                    ComparisonOp::Eq,
                     f_ab!(3),
                 )
                 .unwrap();
+            let direct = problem.solve().unwrap();
 
-            assert_eq!(sol[v1], f0_ab!());
-            assert_eq!(sol[v2], f_ab!(3));
-            assert_eq!(sol.objective(), f_ab!(3));
+            let form = problem.to_standard_form().unwrap();
+            // One row per constraint, a second one for the range and one for the upper bound of
+            // each of the two shifted vars with a finite upper bound.
+            assert_eq!(form.num_rows(), 4 + 1 + 2);
+            // Four vars, the negative part of the free var and one slack per inequality row.
+            assert_eq!(form.num_cols(), 4 + 1 + 6);
+            assert_eq!(form.vars[free.idx()], StandardVar::Split { pos: 2, neg: 4 });
+
+            let std_problem = Problem::from_parts(
+                form.c.clone(),
+                form.a.clone(),
+                vec![ComparisonOp::Eq; form.num_rows()],
+                form.b.clone(),
+                vec![(Some(f0_ab!()), None); form.num_cols()],
+                OptimisationDirection::Minimise,
+            )
+            .unwrap();
+            let std_solution = std_problem.solve().unwrap();
+            let x_std = (0..form.num_cols())
+                .map(|col| std_solution[std_problem.var(col)].clone())
+                .collect::<Vec<_>>();
+            let values = form.recover_solution(&x_std);
+
+            let std_objective = &std_solution.objective() + &form.obj_offset;
+            let expected = match direction {
+                OptimisationDirection::Minimise => direct.objective(),
+                OptimisationDirection::Maximise => -direct.objective(),
+            };
+            assert_eq!(std_objective, expected);
+            assert_eq!(problem.verify_values(&values, &f0_ab!()), Ok(()));
+            let objective = (problem.obj_coeffs.iter().zip(&values))
+                .fold(f0_ab!(), |sum, (coeff, val)| sum + coeff * val);
+            assert_eq!(objective, std_objective);
         }
     }
 
     #[test]
-    fn gomory_cut() {
+    fn empty_problems() {
+        // No vars and no constraints.
+        let problem = Problem::new(OptimisationDirection::Maximise);
+        let sol = problem.solve().unwrap();
+        assert_eq!(sol.objective(), f0_ab!());
+        assert_eq!(sol.iter().count(), 0);
+        assert_eq!(sol.stats().iterations(), 0);
+
+        // Vars without constraints end up at their best bounds.
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let x = problem.add_var(f_ab!(2), (f_ab!(1), f_ab!(5)));
+        let y = problem.add_var(-f1_ab!(), (f_ab!(1), f_ab!(5)));
+        let z = problem.add_var(f0_ab!(), (-f_ab!(3), AbnormalFraction::infinity()));
+        let sol = problem.solve().unwrap();
+        assert_eq!(sol[x], f_ab!(1));
+        assert_eq!(sol[y], f_ab!(5));
+        assert_eq!(sol[z], -f_ab!(3));
+        assert_eq!(sol.objective(), -f_ab!(3));
+        assert_eq!(sol.stats().iterations(), 0);
+        assert_eq!(
+            problem.optimal_value(&SolveOptions::new()).unwrap(),
+            -f_ab!(3)
+        );
+
+        // A var whose best bound is infinite.
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        assert!(matches!(problem.solve().unwrap_err(), Error::Unbounded(_)));
+        assert_eq!(
+            problem.optimal_value(&SolveOptions::new()).unwrap(),
+            AbnormalFraction::infinity()
+        );
         let mut problem = Problem::new(OptimisationDirection::Minimise);
+        problem.add_var(f1_ab!(), (AbnormalFraction::neg_infinity(), f0_ab!()));
+        assert_eq!(
+            problem.optimal_value(&SolveOptions::new()).unwrap(),
+            AbnormalFraction::neg_infinity()
+        );
 
-        println!("{:?}", problem);
+        // Constraints without vars are satisfied or not by zero.
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        problem
+            .add_constraint(LinearExpr::empty(), ComparisonOp::Le, f1_ab!())
+            .unwrap();
+        let sol = problem.solve().unwrap();
+        assert_eq!(sol.objective(), f0_ab!());
+        assert_eq!(sol.iter().count(), 0);
+        problem
+            .add_constraint(LinearExpr::empty(), ComparisonOp::Ge, f1_ab!())
+            .unwrap();
+        assert!(matches!(problem.solve().unwrap_err(), Error::Infeasible(_)));
+        assert!(matches!(
+            problem.optimal_value(&SolveOptions::new()).unwrap_err(),
+            Error::Infeasible(_)
+        ));
+    }
 
-        let v1 = problem.add_var(f0_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
-        let v2 = problem.add_var(-f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+    #[test]
+    fn deterministic_pivots() {
+        let problem = random_problem(13, 60, 30);
+        let options = SolveOptions::new()
+            .presolve(true)
+            .scaling(Scaling::Always)
+            .deterministic(true);
+        let first = problem.solve_with_options(&options).unwrap();
+        assert!(first.stats().iterations() > 0);
+        assert_ne!(first.stats().pivot_hash, 0);
 
-        println!("{:?}", problem);
-        println!("{:?}", v1);
+        for _ in 0..50 {
+            let solution = problem.solve_with_options(&options).unwrap();
+            assert_eq!(solution.stats().pivot_hash, first.stats().pivot_hash);
+            assert_eq!(
+                solution.stats().phase1_iterations,
+                first.stats().phase1_iterations
+            );
+            assert_eq!(
+                solution.stats().phase2_iterations,
+                first.stats().phase2_iterations
+            );
+            assert_eq!(solution.objective(), first.objective());
+            for ((_, val), (_, first_val)) in solution.iter().zip(first.iter()) {
+                assert_eq!(val, first_val);
+            }
+        }
 
-        problem.add_constraint(
-            &[(v1, f_ab!(3)), (v2, f_ab!(2))],
-            ComparisonOp::Le,
-            f_ab!(6),
-        );
+        // Scoring in parallel (with the `parallel` feature) makes the same pivots.
+        let parallel = problem
+            .solve_with_options(&options.clone().parallel_pricing_threshold(0))
+            .unwrap();
+        assert_eq!(parallel.stats().pivot_hash, first.stats().pivot_hash);
+    }
+
+    #[test]
+    fn presolve() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let inf = AbnormalFraction::infinity;
+        let x0 = problem.add_var(f1_ab!(), (f_ab!(2), f_ab!(2)));
+        let x1 = problem.add_var(f1_ab!(), (f_ab!(3), f_ab!(3)));
+        let x2 = problem.add_var(f_ab!(2), (f0_ab!(), f_ab!(5)));
+        let x3 = problem.add_var(f_ab!(3), (f0_ab!(), inf()));
+        let x4 = problem.add_var(-f1_ab!(), (f0_ab!(), inf()));
+        let x5 = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        let x6 = problem.add_var(f_ab!(2), (f0_ab!(), inf()));
+        let x7 = problem.add_var(f1_ab!(), (f0_ab!(), inf()));
+        let x8 = problem.add_var(f0_ab!(), (AbnormalFraction::neg_infinity(), inf()));
+        let vars = [x0, x1, x2, x3, x4, x5, x6, x7, x8];
+
+        // empty after substituting fixed vars
+        problem
+            .add_constraint(
+                &[(x0, f1_ab!()), (x1, f1_ab!())],
+                ComparisonOp::Ge,
+                f_ab!(5),
+            )
+            .unwrap();
+        // singletons
+        problem
+            .add_constraint(&[(x3, f_ab!(2))], ComparisonOp::Le, f_ab!(8))
+            .unwrap();
+        problem
+            .add_constraint(&[(x4, -f1_ab!())], ComparisonOp::Le, -f1_ab!())
+            .unwrap();
+        problem
+            .add_constraint(&[(x5, f1_ab!())], ComparisonOp::Eq, f_ab!(4))
+            .unwrap();
+        // empty
+        problem
+            .add_constraint(LinearExpr::empty(), ComparisonOp::Le, f_ab!(5))
+            .unwrap();
+        // duplicates
+        problem
+            .add_constraint(
+                &[(x6, f1_ab!()), (x7, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(10),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(x6, f1_ab!()), (x7, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(10),
+            )
+            .unwrap();
+        // remaining
+        problem
+            .add_constraint(
+                &[(x0, f1_ab!()), (x3, f1_ab!()), (x6, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(12),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(x4, f1_ab!()), (x5, f1_ab!()), (x7, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(9),
+            )
+            .unwrap();
+
+        let direct = problem.solve().unwrap();
+        let presolved = problem
+            .solve_with_options(&SolveOptions::new().presolve(true))
+            .unwrap();
+
+        assert_eq!(presolved.stats().presolve_removed_rows, 6);
+        assert_eq!(presolved.stats().presolve_removed_vars, 5);
+        assert_eq!(direct.objective(), f_ab!(46));
+        assert_eq!(presolved.objective(), direct.objective());
+        for var in vars {
+            assert_eq!(presolved[var], direct[var], "{:?}", var);
+        }
+        check_duals(&problem, &direct);
+        check_duals(&problem, &presolved);
+
+        // Removed vars act as constants in added constraints.
+        let direct = direct
+            .add_constraint(
+                &[(x0, f1_ab!()), (x6, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(7),
+            )
+            .unwrap();
+        let presolved = presolved
+            .add_constraint(
+                &[(x0, f1_ab!()), (x6, f1_ab!())],
+                ComparisonOp::Le,
+                f_ab!(7),
+            )
+            .unwrap();
+        assert_eq!(presolved.objective(), direct.objective());
+        for var in vars {
+            assert_eq!(presolved[var], direct[var], "{:?}", var);
+        }
+    }
+
+    #[test]
+    fn presolve_infeasible() {
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        let y = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        problem
+            .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Ge, f1_ab!())
+            .unwrap();
+        problem
+            .add_constraint(&[(x, f1_ab!())], ComparisonOp::Le, f1_ab!())
+            .unwrap();
+        problem
+            .add_constraint(&[(x, f_ab!(2))], ComparisonOp::Ge, f_ab!(4))
+            .unwrap();
+
+        let options = SolveOptions::new().presolve(true);
+        assert!(matches!(
+            problem.solve_with_options(&options).map(|_| "solved"),
+            Err(Error::Infeasible(_))
+        ));
+        assert!(matches!(
+            problem.solve().map(|_| "solved"),
+            Err(Error::Infeasible(_))
+        ));
+    }
 
-        println!("{:?}", problem);
+    #[test]
+    fn presolve_dependent_rows() {
+        // The third equality is the sum of the first two.
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        let y = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        let z = problem.add_var(f1_ab!(), (f0_ab!(), f_ab!(10)));
+        problem
+            .add_constraint(&[(x, f1_ab!()), (y, f1_ab!())], ComparisonOp::Eq, f_ab!(2))
+            .unwrap();
+        problem
+            .add_constraint(&[(y, f1_ab!()), (z, f1_ab!())], ComparisonOp::Eq, f_ab!(3))
+            .unwrap();
+        let mut inconsistent = problem.clone();
+        problem
+            .add_constraint(
+                &[(x, f1_ab!()), (y, f_ab!(2)), (z, f1_ab!())],
+                ComparisonOp::Eq,
+                f_ab!(5),
+            )
+            .unwrap();
+        inconsistent
+            .add_constraint(
+                &[(x, f1_ab!()), (y, f_ab!(2)), (z, f1_ab!())],
+                ComparisonOp::Eq,
+                f_ab!(6),
+            )
+            .unwrap();
 
-        problem.add_constraint(
-            &[(v1, -f_ab!(3)), (v2, f_ab!(2))],
-            ComparisonOp::Le,
-            f0_ab!(),
+        let options = SolveOptions::new()
+            .presolve(true)
+            .remove_dependent_rows(true);
+        let direct = problem.solve().unwrap();
+        let presolved = problem.solve_with_options(&options).unwrap();
+        assert_eq!(presolved.stats().presolve_removed_rows, 1);
+        assert_eq!(presolved.objective(), direct.objective());
+        for var in [x, y, z] {
+            assert_eq!(presolved[var], direct[var], "{:?}", var);
+        }
+        assert_eq!(
+            (problem.solve_with_options(&SolveOptions::new().presolve(true)))
+                .unwrap()
+                .stats()
+                .presolve_removed_rows,
+            0
         );
 
-        let mut sol = problem.solve().unwrap();
-        assert_eq!(sol[v1], f1_ab!());
-        assert_eq!(sol[v2], f_ab!(15, 10));
-        assert_eq!(sol.objective(), -f_ab!(15, 10));
+        assert!(matches!(
+            inconsistent.solve_with_options(&options).map(|_| "solved"),
+            Err(Error::Infeasible(_))
+        ));
+    }
 
-        sol = sol.add_gomory_cut(v2).unwrap();
-        assert!(AbnormalFraction::abs(&sol[v1] - &f_ab!(2, 3)) < f_ab!(1, 1000000000));
-        assert_eq!(sol[v2], f1_ab!());
-        assert_eq!(sol.objective(), -f1_ab!());
+    #[test]
+    fn scaling() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let x = problem.add_var(f1_ab!(), (f0_ab!(), AbnormalFraction::infinity()));
+        let y = problem.add_var(f_ab!(1, 1000), (f0_ab!(), AbnormalFraction::infinity()));
+        let z = problem.add_var(f_ab!(1000000), (f0_ab!(), f_ab!(3)));
+        problem
+            .add_constraint(
+                &[(x, f_ab!(1000000)), (y, f_ab!(1, 1000))],
+                ComparisonOp::Le,
+                f_ab!(3000000),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(x, f_ab!(1, 1000000)), (z, f_ab!(1, 1000000))],
+                ComparisonOp::Le,
+                f_ab!(4, 1000000),
+            )
+            .unwrap();
+        problem
+            .add_constraint(
+                &[(y, f_ab!(1000)), (z, f_ab!(1000))],
+                ComparisonOp::Le,
+                f_ab!(5000),
+            )
+            .unwrap();
 
-        sol = sol.add_gomory_cut(v1).unwrap();
-        assert!(AbnormalFraction::abs(&sol[v1] - &f1_ab!()) < f_ab!(1, 1000000000));
-        assert_eq!(sol[v2], f1_ab!());
-        assert_eq!(sol.objective(), -f1_ab!());
+        let unscaled = problem.solve().unwrap();
+        let scaled = problem
+            .solve_with_options(&SolveOptions::new().scaling(Scaling::Always))
+            .unwrap();
+
+        let tolerance = f_ab!(1, 1000000);
+        let close = |a: &AbnormalFraction, b: &AbnormalFraction| {
+            (a - b).abs() <= &tolerance * &(f1_ab!() + b.clone().abs())
+        };
+        assert!(close(&scaled.objective(), &unscaled.objective()));
+        for var in [x, y, z] {
+            assert!(close(&scaled[var], &unscaled[var]), "{:?}", var);
+        }
+        // The duals and reduced costs are unscaled too.
+        check_duals(&problem, &unscaled);
+        for (dual, unscaled_dual) in scaled.duals().iter().zip(&unscaled.duals()) {
+            assert!(close(dual, unscaled_dual), "{} != {}", dual, unscaled_dual);
+        }
+        let reduced_costs = scaled.reduced_costs();
+        for (reduced_cost, unscaled_reduced_cost) in
+            reduced_costs.iter().zip(&unscaled.reduced_costs())
+        {
+            assert!(close(reduced_cost, unscaled_reduced_cost));
+        }
+        let err = scaled.clone().add_gomory_cut(x).unwrap_err();
+        assert_eq!(err, ModelError::NoGomoryCut(x).into());
+
+        // Added constraints are scaled as well.
+        let unscaled = unscaled
+            .add_constraint(&[(z, f_ab!(1, 1000))], ComparisonOp::Le, f_ab!(2, 1000))
+            .unwrap();
+        let scaled = scaled
+            .add_constraint(&[(z, f_ab!(1, 1000))], ComparisonOp::Le, f_ab!(2, 1000))
+            .unwrap();
+        assert!(close(&scaled.objective(), &unscaled.objective()));
+        for var in [x, y, z] {
+            assert!(close(&scaled[var], &unscaled[var]), "{:?}", var);
+        }
     }
 
-     #[test]
+    #[test]
     fn gomory_cut_original() {
         let mut problem = minilp::Problem::new(minilp::OptimizationDirection::Minimize);
         let v1 = problem.add_var(0.0, (0.0, f64::INFINITY));
         let v2 = problem.add_var(-1.0, (0.0, f64::INFINITY));
-        problem.add_constraint(&[(v1, 3.0), (v2, 2.0)], minilp::ComparisonOp::Le, 6.0);
-        problem.add_constraint(&[(v1, -3.0), (v2, 2.0)], minilp::ComparisonOp::Le, 0.0);
-
-        println!("bla");
+        problem.add_constraint([(v1, 3.0), (v2, 2.0)], minilp::ComparisonOp::Le, 6.0);
+        problem.add_constraint([(v1, -3.0), (v2, 2.0)], minilp::ComparisonOp::Le, 0.0);
 
         let mut sol = problem.solve().unwrap();
         assert_eq!(sol[v1], 1.0);
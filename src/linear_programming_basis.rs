@@ -0,0 +1,59 @@
+//! A basis that can be stored independently of the problem it was taken from.
+
+/// The status of a variable or of a constraint row in a [`Basis`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BasisStatus {
+    /// Basic; its value follows from the values of the non-basic variables.
+    Basic,
+    /// Non-basic at the lower bound. For a row, the bound applies to the value of its left-hand
+    /// side.
+    NonbasicLower,
+    /// Non-basic at the upper bound. For a row, the bound applies to the value of its left-hand
+    /// side.
+    NonbasicUpper,
+}
+
+/// The statuses of the variables and constraint rows of a problem, in the order in which they
+/// were added to it.
+///
+/// A basis taken from a [`Solution`] with [`Solution::extract_basis`] can be stored (with the
+/// `serde` feature) and passed to [`Problem::solve_from_basis`] of an identical problem, which
+/// then starts the simplex from it instead of from scratch.
+///
+/// [`Solution`]: crate::linear_programming::Solution
+/// [`Solution::extract_basis`]: crate::linear_programming::Solution::extract_basis
+/// [`Problem::solve_from_basis`]: crate::linear_programming::Problem::solve_from_basis
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Basis {
+    vars: Vec<BasisStatus>,
+    rows: Vec<BasisStatus>,
+}
+
+impl Basis {
+    /// Create a basis from the statuses of the variables and of the constraint rows.
+    pub fn new(vars: Vec<BasisStatus>, rows: Vec<BasisStatus>) -> Self {
+        Self { vars, rows }
+    }
+
+    /// The status of each variable.
+    pub fn var_statuses(&self) -> &[BasisStatus] {
+        &self.vars
+    }
+
+    /// The status of each constraint row.
+    pub fn row_statuses(&self) -> &[BasisStatus] {
+        &self.rows
+    }
+
+    /// Number of variables and rows that are basic. A valid basis has one basic variable or row
+    /// per row.
+    pub fn num_basic(&self) -> usize {
+        self.vars
+            .iter()
+            .chain(&self.rows)
+            .filter(|status| **status == BasisStatus::Basic)
+            .count()
+    }
+}
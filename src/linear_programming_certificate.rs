@@ -0,0 +1,91 @@
+//! What a failed solve tells about the problem: a proof of infeasibility, a direction of
+//! unboundedness, or the point that an interrupted solve had reached.
+
+use crate::{abnormal_fraction::AbnormalFraction, linear_programming_basis::Basis};
+
+/// Proof that no point satisfies the constraints and the bounds of the variables of a problem
+/// (a Farkas ray): multipliers `y` of the constraints such that their combination
+/// `Σ yᵢ·(aᵢ·x)` cannot take any value that the bounds of the constraints allow. The
+/// smallest allowed value, the sum of `yᵢ·minᵢ` over the positive `yᵢ` and of `yᵢ·maxᵢ` over
+/// the negative ones, exceeds the largest value of the combination within the bounds of the
+/// variables.
+///
+/// The multipliers are numbered like the constraints of the problem, followed by the
+/// constraints and cuts that were added to the [`Solution`] in the order in which they were
+/// added. They are all zero if no constraint is
+/// involved, as when [`Solution::fix_var`] fixes a variable outside of its bounds; the bounds
+/// include the values that variables were fixed to.
+///
+/// [`Solution`]: crate::linear_programming::Solution
+/// [`Solution::fix_var`]: crate::linear_programming::Solution::fix_var
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InfeasibilityCertificate {
+    multipliers: Vec<AbnormalFraction>,
+}
+
+impl InfeasibilityCertificate {
+    pub(crate) fn new(multipliers: Vec<AbnormalFraction>) -> Self {
+        Self { multipliers }
+    }
+
+    /// The multiplier of each constraint.
+    pub fn multipliers(&self) -> &[AbnormalFraction] {
+        &self.multipliers
+    }
+}
+
+/// A direction in which the objective improves without limit: moving the variables of a
+/// feasible point any distance along it keeps all constraints and bounds satisfied.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnboundedRay {
+    direction: Vec<AbnormalFraction>,
+}
+
+impl UnboundedRay {
+    pub(crate) fn new(direction: Vec<AbnormalFraction>) -> Self {
+        Self { direction }
+    }
+
+    /// The change of each variable per unit of the step along the ray.
+    pub fn direction(&self) -> &[AbnormalFraction] {
+        &self.direction
+    }
+}
+
+/// The point that an interrupted solve had reached: the values of the variables and the basis
+/// of the last iteration. Passing the basis to [`Problem::solve_from_basis`] continues the
+/// solve from there.
+///
+/// [`Problem::solve_from_basis`]: crate::linear_programming::Problem::solve_from_basis
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PartialSolution {
+    values: Vec<AbnormalFraction>,
+    basis: Basis,
+    is_feasible: bool,
+}
+
+impl PartialSolution {
+    pub(crate) fn new(values: Vec<AbnormalFraction>, basis: Basis, is_feasible: bool) -> Self {
+        Self {
+            values,
+            basis,
+            is_feasible,
+        }
+    }
+
+    /// The value of each variable.
+    pub fn values(&self) -> &[AbnormalFraction] {
+        &self.values
+    }
+
+    /// The basis of the last iteration.
+    pub fn basis(&self) -> &Basis {
+        &self.basis
+    }
+
+    /// Whether the values satisfy all constraints, which they do once the solver is past
+    /// restoring feasibility; they need not be optimal.
+    pub fn is_feasible(&self) -> bool {
+        self.is_feasible
+    }
+}
@@ -5,7 +5,7 @@ use std::ops::Deref;
 pub(crate) fn resized_view<IStorage, DStorage>(
     vec: &CsVecBase<IStorage, DStorage, AbnormalFraction>,
     len: usize,
-) -> CsVecView<AbnormalFraction>
+) -> CsVecView<'_, AbnormalFraction>
 where
     IStorage: Deref<Target = [usize]>,
     DStorage: Deref<Target = [AbnormalFraction]>,
@@ -0,0 +1,58 @@
+//! Finding a starting basis for the exact simplex with approximate arithmetic.
+//!
+//! The exact simplex spends most of its time on arithmetic with large rationals, while the
+//! optimal basis found with floating-point numbers is usually exactly optimal as well. The
+//! problem is therefore solved approximately first; the exact simplex starts from the resulting
+//! basis and only pivots where the basis turns out not to be exactly feasible or optimal.
+
+use crate::{
+    abnormal_fraction::AbnormalFraction,
+    linear_programming::{Constraint, CsVec},
+    linear_programming_basis::BasisStatus,
+    linear_programming_options::{Method, SolveOptions},
+    linear_programming_solver::Solver,
+};
+
+/// Solve the problem approximately and return the statuses of all vars, including the slack
+/// vars, in the final basis, together with the number of approximate iterations. Returns `None`
+/// if approximate fractions are not available or if the approximate solve failed.
+pub(crate) fn approximate_basis(
+    obj_coeffs: &[AbnormalFraction],
+    var_mins: &[AbnormalFraction],
+    var_maxs: &[AbnormalFraction],
+    constraints: &[Constraint],
+    options: &SolveOptions,
+) -> Option<(Vec<BasisStatus>, usize)> {
+    let obj_coeffs = to_approx(obj_coeffs)?;
+    let var_mins = to_approx(var_mins)?;
+    let var_maxs = to_approx(var_maxs)?;
+    let constraints = constraints
+        .iter()
+        .map(|constraint| {
+            Some(Constraint {
+                coeffs: CsVec::new(
+                    constraint.coeffs.dim(),
+                    constraint.coeffs.indices().to_vec(),
+                    to_approx(constraint.coeffs.data())?,
+                ),
+                min: constraint.min.to_approx()?,
+                max: constraint.max.to_approx()?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    // Iterations of the approximate solve are not reported.
+    let options = SolveOptions {
+        method: Method::Auto,
+        on_iteration: None,
+        ..options.clone()
+    };
+    let mut solver =
+        Solver::try_new(&obj_coeffs, &var_mins, &var_maxs, &constraints, &options).ok()?;
+    solver.initial_solve().ok()?;
+    Some((solver.basis_statuses(), solver.stats.iterations()))
+}
+
+fn to_approx(values: &[AbnormalFraction]) -> Option<Vec<AbnormalFraction>> {
+    values.iter().map(AbnormalFraction::to_approx).collect()
+}
@@ -0,0 +1,199 @@
+//! Finding an irreducible infeasible subsystem (IIS) of an infeasible problem.
+
+use crate::{
+    abnormal_fraction::AbnormalFraction,
+    linear_programming::{Constraint, Error, Variable},
+    linear_programming_options::SolveOptions,
+};
+
+/// A constraint or a variable bound that is part of an [`Iis`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IisMember {
+    /// The constraint at this position in the order in which constraints were added.
+    Constraint(usize),
+    /// The lower bound of the variable.
+    LowerBound(Variable),
+    /// The upper bound of the variable.
+    UpperBound(Variable),
+}
+
+/// An infeasible subsystem of a problem, found by
+/// [`Problem::find_iis`](crate::linear_programming::Problem::find_iis): the constraints and
+/// variable bounds in it cannot be satisfied simultaneously.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Iis {
+    members: Vec<IisMember>,
+    is_minimal: bool,
+    num_solves: usize,
+}
+
+impl Iis {
+    /// The constraints of the subsystem in the order in which they were added, followed by the
+    /// variable bounds.
+    pub fn members(&self) -> &[IisMember] {
+        &self.members
+    }
+
+    /// The positions of the constraints of the subsystem.
+    pub fn constraints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.members.iter().filter_map(|member| match member {
+            IisMember::Constraint(constraint) => Some(*constraint),
+            _ => None,
+        })
+    }
+
+    /// Whether removing any member makes the subsystem feasible. This is only false if the
+    /// budget of [`IisOptions::max_solves`] ran out before every member was tested.
+    pub fn is_minimal(&self) -> bool {
+        self.is_minimal
+    }
+
+    /// Number of feasibility problems that were solved to find the subsystem.
+    pub fn num_solves(&self) -> usize {
+        self.num_solves
+    }
+}
+
+/// Options for [`Problem::find_iis`](crate::linear_programming::Problem::find_iis).
+#[derive(Clone, Debug)]
+pub struct IisOptions {
+    pub(crate) solve_options: SolveOptions,
+    pub(crate) max_solves: usize,
+}
+
+impl Default for IisOptions {
+    fn default() -> Self {
+        Self {
+            solve_options: SolveOptions::default(),
+            max_solves: usize::MAX,
+        }
+    }
+}
+
+impl IisOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the options of the feasibility problems that are solved.
+    pub fn solve_options(mut self, options: SolveOptions) -> Self {
+        self.solve_options = options;
+        self
+    }
+
+    /// Set the maximum number of feasibility problems to solve. Members that were not tested
+    /// when the budget runs out stay in the subsystem, which is then not minimal. Unlimited by
+    /// default.
+    pub fn max_solves(mut self, max_solves: usize) -> Self {
+        self.max_solves = max_solves;
+        self
+    }
+}
+
+/// The constraints and variable bounds that take part in a feasibility problem.
+pub(crate) struct Subsystem {
+    pub(crate) constraints: Vec<bool>,
+    pub(crate) mins: Vec<bool>,
+    pub(crate) maxs: Vec<bool>,
+}
+
+/// The deletion filter: remove the members of the system one by one and put a member back if
+/// the system becomes feasible without it. What remains is infeasible, and removing any
+/// member makes it feasible.
+///
+/// Returns `None` if the whole system is feasible.
+pub(crate) fn deletion_filter(
+    var_mins: &[AbnormalFraction],
+    var_maxs: &[AbnormalFraction],
+    constraints: &[Constraint],
+    max_solves: usize,
+    mut is_feasible: impl FnMut(&Subsystem) -> Result<bool, Error>,
+) -> Result<Option<Iis>, Error> {
+    let mut subsystem = Subsystem {
+        constraints: vec![true; constraints.len()],
+        mins: var_mins.iter().map(|min| min.is_finite()).collect(),
+        maxs: var_maxs.iter().map(|max| max.is_finite()).collect(),
+    };
+    let mut num_solves = 1;
+    if is_feasible(&subsystem)? {
+        return Ok(None);
+    }
+    let mut is_minimal = true;
+
+    for constraint in 0..constraints.len() {
+        if num_solves >= max_solves {
+            is_minimal = false;
+            break;
+        }
+        subsystem.constraints[constraint] = false;
+        num_solves += 1;
+        if is_feasible(&subsystem)? {
+            subsystem.constraints[constraint] = true;
+        }
+    }
+
+    // The bounds of a var that occurs in no remaining constraint can only conflict with each
+    // other, so they need no solve.
+    let mut in_constraints = vec![false; var_mins.len()];
+    for (constraint, &active) in constraints.iter().zip(&subsystem.constraints) {
+        if active {
+            for &var in constraint.coeffs.indices() {
+                in_constraints[var] = true;
+            }
+        }
+    }
+    for var in 0..var_mins.len() {
+        if !in_constraints[var] && var_mins[var] <= var_maxs[var] {
+            subsystem.mins[var] = false;
+            subsystem.maxs[var] = false;
+        }
+    }
+
+    for var in 0..var_mins.len() {
+        for is_max in [false, true] {
+            let bounds = if is_max {
+                &subsystem.maxs
+            } else {
+                &subsystem.mins
+            };
+            if !bounds[var] {
+                continue;
+            }
+            if num_solves >= max_solves {
+                is_minimal = false;
+                continue;
+            }
+
+            set_bound(&mut subsystem, var, is_max, false);
+            num_solves += 1;
+            if is_feasible(&subsystem)? {
+                set_bound(&mut subsystem, var, is_max, true);
+            }
+        }
+    }
+
+    let constraint_members = (subsystem.constraints.iter().enumerate())
+        .filter(|(_, active)| **active)
+        .map(|(constraint, _)| IisMember::Constraint(constraint));
+    let bound_members = (0..var_mins.len()).flat_map(|var| {
+        let min = subsystem.mins[var].then_some(IisMember::LowerBound(Variable(var)));
+        let max = subsystem.maxs[var].then_some(IisMember::UpperBound(Variable(var)));
+        min.into_iter().chain(max)
+    });
+    let members = constraint_members.chain(bound_members).collect();
+
+    Ok(Some(Iis {
+        members,
+        is_minimal,
+        num_solves,
+    }))
+}
+
+fn set_bound(subsystem: &mut Subsystem, var: usize, is_max: bool, active: bool) {
+    if is_max {
+        subsystem.maxs[var] = active;
+    } else {
+        subsystem.mins[var] = active;
+    }
+}
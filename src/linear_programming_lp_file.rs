@@ -0,0 +1,223 @@
+//! Writing a problem in the CPLEX LP file format.
+
+use std::io::{self, Write};
+
+use ebi_arithmetic::{
+    MaybeExact, Signed, Zero,
+    malachite::base::{num::conversion::traits::RoundingFrom, rounding_modes::RoundingMode},
+};
+
+use crate::{
+    abnormal_fraction::AbnormalFraction,
+    linear_programming::{Constraint, OptimisationDirection, VarLabel},
+    linear_programming_names::Names,
+};
+
+/// The parts of a problem that end up in an LP file.
+pub(crate) struct LpFile<'a> {
+    pub(crate) direction: OptimisationDirection,
+    /// In the minimised form, as stored by the problem.
+    pub(crate) obj_coeffs: &'a [AbnormalFraction],
+    pub(crate) obj_constant: &'a AbnormalFraction,
+    pub(crate) var_mins: &'a [AbnormalFraction],
+    pub(crate) var_maxs: &'a [AbnormalFraction],
+    pub(crate) constraints: &'a [Constraint],
+    pub(crate) var_names: &'a Names,
+    pub(crate) constraint_names: &'a Names,
+}
+
+impl LpFile<'_> {
+    pub(crate) fn write(&self, mut out: impl Write) -> io::Result<()> {
+        let objective = self.obj_coeffs.iter().map(|coeff| match self.direction {
+            OptimisationDirection::Minimise => coeff.clone(),
+            OptimisationDirection::Maximise => -coeff.clone(),
+        });
+        match self.direction {
+            OptimisationDirection::Minimise => writeln!(out, "Minimize")?,
+            OptimisationDirection::Maximise => writeln!(out, "Maximize")?,
+        }
+        write!(out, " obj: ")?;
+        self.write_expr(&mut out, objective.enumerate())?;
+        if !self.obj_constant.is_zero() {
+            write_term(&mut out, self.obj_constant, None)?;
+        }
+        writeln!(out)?;
+
+        writeln!(out, "Subject To")?;
+        for (idx, constraint) in self.constraints.iter().enumerate() {
+            write!(out, " ")?;
+            if let Some(name) = self.constraint_names.get(idx) {
+                write!(out, "{}: ", name)?;
+            }
+            let expr = constraint
+                .coeffs
+                .iter()
+                .map(|(var, coeff)| (var, coeff.clone()));
+            if constraint.is_equality() {
+                self.write_expr(&mut out, expr)?;
+                writeln!(out, " = {}", Number(&constraint.max))?;
+            } else if constraint.min.is_neg_infinite() && !constraint.max.is_infinite() {
+                self.write_expr(&mut out, expr)?;
+                writeln!(out, " <= {}", Number(&constraint.max))?;
+            } else if constraint.max.is_infinite() && !constraint.min.is_neg_infinite() {
+                self.write_expr(&mut out, expr)?;
+                writeln!(out, " >= {}", Number(&constraint.min))?;
+            } else {
+                write!(out, "{} <= ", Number(&constraint.min))?;
+                self.write_expr(&mut out, expr)?;
+                writeln!(out, " <= {}", Number(&constraint.max))?;
+            }
+        }
+
+        writeln!(out, "Bounds")?;
+        for (var, (min, max)) in self.var_mins.iter().zip(self.var_maxs).enumerate() {
+            let label = self.var_label(var);
+            if min.is_zero() && max.is_infinite() {
+                // The default bounds.
+            } else if min.is_neg_infinite() && max.is_infinite() {
+                writeln!(out, " {} free", label)?;
+            } else if min == max {
+                writeln!(out, " {} = {}", label, Number(min))?;
+            } else if max.is_infinite() {
+                writeln!(out, " {} >= {}", label, Number(min))?;
+            } else {
+                writeln!(out, " {} <= {} <= {}", Number(min), label, Number(max))?;
+            }
+        }
+        writeln!(out, "End")
+    }
+
+    fn var_label(&self, var: usize) -> VarLabel<'_> {
+        match self.var_names.get(var) {
+            Some(name) => VarLabel::Name(name),
+            None => VarLabel::Index(var),
+        }
+    }
+
+    /// Write the non-zero terms of a linear expression, or `0` if it has none.
+    fn write_expr(
+        &self,
+        out: &mut impl Write,
+        terms: impl Iterator<Item = (usize, AbnormalFraction)>,
+    ) -> io::Result<()> {
+        let mut is_first = true;
+        for (var, coeff) in terms.filter(|(_, coeff)| !coeff.is_zero()) {
+            if is_first {
+                write!(out, "{} {}", Number(&coeff), self.var_label(var))?;
+                is_first = false;
+            } else {
+                write_term(out, &coeff, Some(self.var_label(var)))?;
+            }
+        }
+        if is_first {
+            write!(out, "0")?;
+        }
+        Ok(())
+    }
+}
+
+/// Write ` + coeff label` or ` - |coeff| label`, following an earlier term.
+fn write_term(
+    out: &mut impl Write,
+    coeff: &AbnormalFraction,
+    label: Option<VarLabel>,
+) -> io::Result<()> {
+    if coeff.is_negative() {
+        write!(out, " - {}", Number(&-coeff.clone()))?;
+    } else {
+        write!(out, " + {}", Number(coeff))?;
+    }
+    match label {
+        Some(label) => write!(out, " {}", label),
+        None => Ok(()),
+    }
+}
+
+/// A number as the LP format writes it: the nearest float, or `inf` and `-inf`.
+struct Number<'a>(&'a AbnormalFraction);
+
+impl std::fmt::Display for Number<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_infinite() {
+            "inf".fmt(f)
+        } else if self.0.is_neg_infinite() {
+            "-inf".fmt(f)
+        } else if let Ok(approx) = self.0.approx_ref() {
+            approx.fmt(f)
+        } else if let Ok(exact) = self.0.exact_ref() {
+            f64::rounding_from(exact, RoundingMode::Nearest).0.fmt(f)
+        } else {
+            "nan".fmt(f)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ebi_arithmetic::{One, Zero};
+
+    use crate::{
+        abnormal_fraction::AbnormalFraction,
+        f_ab, f0_ab, f1_ab,
+        linear_programming::{ComparisonOp, OptimisationDirection, Problem},
+    };
+
+    #[test]
+    fn write_lp() {
+        let mut problem = Problem::new(OptimisationDirection::Maximise);
+        let x = problem
+            .add_var_named("x_t3", f_ab!(3), (f0_ab!(), AbnormalFraction::infinity()))
+            .unwrap();
+        let y = problem.add_var(-f_ab!(1, 2), (-f1_ab!(), f_ab!(4)));
+        let z = problem.add_var(f0_ab!(), (f_ab!(2), f_ab!(2)));
+        let w = problem.add_var(
+            f1_ab!(),
+            (
+                AbnormalFraction::neg_infinity(),
+                AbnormalFraction::infinity(),
+            ),
+        );
+        problem
+            .add_constraint_named(
+                "cap",
+                &[(x, f1_ab!()), (y, -f_ab!(2))],
+                ComparisonOp::Le,
+                f_ab!(4),
+            )
+            .unwrap();
+        problem
+            .add_constraint(&[(y, f1_ab!()), (z, f1_ab!())], ComparisonOp::Ge, -f1_ab!())
+            .unwrap();
+        problem
+            .add_range_constraint(&[(w, f1_ab!())], -f1_ab!(), f_ab!(5, 2))
+            .unwrap();
+        problem
+            .add_constraint_named(
+                "fix",
+                &[(x, f1_ab!()), (w, f1_ab!())],
+                ComparisonOp::Eq,
+                f_ab!(3),
+            )
+            .unwrap();
+        problem.set_objective_constant(f_ab!(5)).unwrap();
+
+        let mut out = Vec::new();
+        problem.write_lp(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Maximize
+ obj: 3 x_t3 - 0.5 #1 + 1 #3 + 5
+Subject To
+ cap: 1 x_t3 - 2 #1 <= 4
+ 1 #1 + 1 #2 >= -1
+ -1 <= 1 #3 <= 2.5
+ fix: 1 x_t3 + 1 #3 = 3
+Bounds
+ -1 <= #1 <= 4
+ #2 = 2
+ #3 free
+End
+"
+        );
+    }
+}
@@ -1,10 +1,19 @@
-use ebi_arithmetic::{Signed, Zero};
+#![deny(clippy::print_stdout, clippy::print_stderr)]
+
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use ebi_arithmetic::{MaybeExact, One, Signed, Zero};
 use log::trace;
 
 use crate::{
     abnormal_fraction::AbnormalFraction,
-    f0_ab,
-    linear_programming_ordering::order_simple,
+    f_ab, f0_ab, f1_ab,
+    linear_programming_options::{self, BasisUpdate, DropTolerance, OrderingCachePolicy, RowHint},
+    linear_programming_ordering::{ColsQueue, OrderingCache},
     linear_programming_sparse::{Error, Perm, ScatteredVec, SparseMat, TriangleMat},
 };
 
@@ -14,6 +23,8 @@ pub struct LUFactors {
     upper: TriangleMat,
     row_perm: Option<Perm>,
     col_perm: Option<Perm>,
+    /// Dense columns of the matrix that were replaced by unit columns, see [`lu_factorise`].
+    dense_cols: Vec<usize>,
 }
 
 #[derive(Clone, Debug)]
@@ -21,6 +32,11 @@ pub struct ScratchSpace {
     rhs: ScatteredVec,
     dense_rhs: Vec<AbnormalFraction>,
     mark_nonzero: MarkNonzero,
+    /// The columns of L that [`LUFactors::solve_many`] visits, in topological order.
+    reach: Vec<usize>,
+    /// Number of lines of the triangular factors, and of entries in them, that sparse solves
+    /// processed; only ever incremented, for tests of the sparsity of the solves.
+    visited_entries: usize,
 }
 
 impl ScratchSpace {
@@ -29,6 +45,8 @@ impl ScratchSpace {
             rhs: ScatteredVec::empty(n),
             dense_rhs: vec![f0_ab!(); n],
             mark_nonzero: MarkNonzero::with_capacity(n),
+            reach: Vec::with_capacity(n),
+            visited_entries: 0,
         }
     }
 
@@ -38,6 +56,166 @@ impl ScratchSpace {
     }
 }
 
+/// Storage that [`lu_factorise_with_scratch`] and [`lu_factorise_markowitz_with_scratch`] keep
+/// between factorisations: the scratch space of the solves (the DFS stack and the visited
+/// markers of the symbolic phase, the dense accumulator column and its nonzero pattern), the
+/// work arrays of the factorisation, and the storage of the factors that were handed back
+/// with [`LuScratch::recycle`]. All of it grows when needed, so once it has grown to a matrix,
+/// refactorising a matrix of the same size and pattern allocates nothing. It also keeps the
+/// drop tolerance of the factorisations, see [`LuScratch::set_drop_tolerance`], their column
+/// ordering, see [`LuScratch::set_ordering`], and their row hints, see
+/// [`LuScratch::set_row_hints`].
+#[derive(Clone, Debug)]
+pub struct LuScratch {
+    space: ScratchSpace,
+    cols_queue: ColsQueue,
+    is_dense: Vec<bool>,
+    row_counts: Vec<usize>,
+    spare: Option<LUFactors>,
+    rejected_pivots: usize,
+    unit_pivots: usize,
+    drop_tolerance: Option<DropTolerance>,
+    dropped_entries: usize,
+    reused_cols: usize,
+    ordering: linear_programming_options::Ordering,
+    ordering_cache: OrderingCache,
+    row_hints: Vec<RowHint>,
+}
+
+impl LuScratch {
+    pub fn with_capacity(n: usize) -> LuScratch {
+        LuScratch {
+            space: ScratchSpace::with_capacity(n),
+            cols_queue: ColsQueue::new(n),
+            is_dense: Vec::with_capacity(n),
+            row_counts: Vec::with_capacity(n),
+            spare: None,
+            rejected_pivots: 0,
+            unit_pivots: 0,
+            drop_tolerance: None,
+            dropped_entries: 0,
+            reused_cols: 0,
+            ordering: linear_programming_options::Ordering::default(),
+            ordering_cache: OrderingCache::default(),
+            row_hints: vec![],
+        }
+    }
+
+    /// Number of candidate pivots that the threshold test rejected in the last factorisation.
+    pub fn rejected_pivots(&self) -> usize {
+        self.rejected_pivots
+    }
+
+    /// Number of pivots equal to 1 or -1 in the last factorisation.
+    pub fn unit_pivots(&self) -> usize {
+        self.unit_pivots
+    }
+
+    /// Leave entries below `drop_tolerance` out of the factors, except the pivots and the
+    /// largest entry of each column. Ignored with exact arithmetic, where every entry counts.
+    pub fn set_drop_tolerance(&mut self, drop_tolerance: Option<DropTolerance>) {
+        self.drop_tolerance = drop_tolerance.filter(|_| !f1_ab!().is_exact());
+    }
+
+    /// The drop tolerance of the factorisations, see [`LuScratch::set_drop_tolerance`].
+    pub fn drop_tolerance(&self) -> Option<&DropTolerance> {
+        self.drop_tolerance.as_ref()
+    }
+
+    /// Number of entries that the drop tolerance left out of the factors in the last
+    /// factorisation.
+    pub fn dropped_entries(&self) -> usize {
+        self.dropped_entries
+    }
+
+    /// Eliminate the columns of the matrix in the order `ordering` in the factorisations of
+    /// [`lu_factorise_with_scratch`] and [`lu_analyse_with_scratch`]. Refactorisations keep the
+    /// order of the factors they start from, and Markowitz pivoting chooses its own.
+    pub fn set_ordering(&mut self, ordering: linear_programming_options::Ordering) {
+        self.ordering = ordering;
+        self.ordering_cache.clear();
+    }
+
+    /// The column ordering of the factorisations, see [`LuScratch::set_ordering`].
+    pub fn ordering(&self) -> &linear_programming_options::Ordering {
+        &self.ordering
+    }
+
+    /// Reuse the column ordering of an earlier factorisation while `policy` allows it, instead
+    /// of computing it for every factorisation.
+    pub fn set_ordering_cache(&mut self, policy: Option<OrderingCachePolicy>) {
+        self.ordering_cache.set_policy(policy);
+    }
+
+    /// Whether the last factorisation reused the ordering of an earlier one; `None` if it did
+    /// not look for one (see [`LuScratch::set_ordering_cache`]).
+    pub fn ordering_cache_hit(&self) -> Option<bool> {
+        self.ordering_cache.last_hit()
+    }
+
+    /// Prefer to pivot on row `r` of the matrix as `hints[r]` says in the factorisations of
+    /// [`lu_factorise_with_scratch`] and [`lu_analyse_with_scratch`], among the rows that pass
+    /// the threshold test; rows without a hint are [`RowHint::Free`]. Markowitz pivoting
+    /// ignores the hints.
+    pub fn set_row_hints(&mut self, hints: Option<Vec<RowHint>>) {
+        self.row_hints = hints.unwrap_or_default();
+    }
+
+    /// The row hints of the factorisations, see [`LuScratch::set_row_hints`].
+    pub fn row_hints(&self) -> &[RowHint] {
+        &self.row_hints
+    }
+
+    /// Number of columns of the last factorisation that were taken over from earlier factors
+    /// (see [`lu_refactorise_with_scratch`]).
+    pub fn reused_cols(&self) -> usize {
+        self.reused_cols
+    }
+
+    /// The scratch space for solves with the factors.
+    pub fn space(&mut self) -> &mut ScratchSpace {
+        &mut self.space
+    }
+
+    /// Hand back factors that are no longer needed, so that the next factorisation reuses
+    /// their storage.
+    pub fn recycle(&mut self, factors: LUFactors) {
+        self.spare = Some(factors);
+    }
+
+    /// Take the storage for the next factors, with the optional parts present.
+    fn take_spare(&mut self) -> LUFactors {
+        let empty_perm = || Perm {
+            orig2new: vec![],
+            new2orig: vec![],
+        };
+        match self.spare.take() {
+            Some(factors) => LUFactors {
+                upper: TriangleMat {
+                    nondiag: factors.upper.nondiag,
+                    diag: Some(factors.upper.diag.unwrap_or_default()),
+                },
+                row_perm: Some(factors.row_perm.unwrap_or_else(empty_perm)),
+                col_perm: Some(factors.col_perm.unwrap_or_else(empty_perm)),
+                ..factors
+            },
+            None => LUFactors {
+                lower: TriangleMat {
+                    nondiag: SparseMat::new(0),
+                    diag: None,
+                },
+                upper: TriangleMat {
+                    nondiag: SparseMat::new(0),
+                    diag: Some(vec![]),
+                },
+                row_perm: Some(empty_perm()),
+                col_perm: Some(empty_perm()),
+                dense_cols: vec![],
+            },
+        }
+    }
+}
+
 impl std::fmt::Debug for LUFactors {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "L:\n{:?}", self.lower)?;
@@ -61,12 +239,212 @@ impl LUFactors {
         self.lower.nondiag.nnz() + self.upper.nondiag.nnz() + self.lower.cols()
     }
 
+    pub fn size(&self) -> usize {
+        self.lower.cols()
+    }
+
+    /// Number of nonzeros in L, without its diagonal of ones.
+    pub fn lower_nnz(&self) -> usize {
+        self.lower.nondiag.nnz()
+    }
+
+    /// Number of nonzeros in U, including its diagonal.
+    pub fn upper_nnz(&self) -> usize {
+        self.upper.nondiag.nnz() + self.upper.cols()
+    }
+
+    /// The largest and the total size in bits of the coefficients of the factors (see
+    /// [`AbnormalFraction::complexity`]), which are 0 in approximate arithmetic.
+    pub fn coeff_bits(&self) -> (u64, u64) {
+        let lower_vals = (0..self.lower.cols()).flat_map(|c| self.lower.nondiag.col_data(c));
+        let upper_vals = (0..self.upper.cols()).flat_map(|c| self.upper.nondiag.col_data(c));
+        let diag_vals = self.upper.diag.iter().flatten();
+        lower_vals
+            .chain(upper_vals)
+            .chain(diag_vals)
+            .map(|val| val.complexity())
+            .fold((0, 0), |(max, total), bits| (max.max(bits), total + bits))
+    }
+
+    /// The columns that were left out of the factorisation because they are dense.
+    pub fn dense_cols(&self) -> &[usize] {
+        &self.dense_cols
+    }
+
+    /// The L factor, lower triangular with rows and columns in the new order.
+    pub fn lower(&self) -> &TriangleMat {
+        &self.lower
+    }
+
+    /// The U factor, upper triangular with rows and columns in the new order.
+    pub fn upper(&self) -> &TriangleMat {
+        &self.upper
+    }
+
+    /// The order of the rows of the factorised matrix in the factors, or `None` if they are in
+    /// their original order.
+    pub fn row_perm(&self) -> Option<&Perm> {
+        self.row_perm.as_ref()
+    }
+
+    /// The order of the columns of the factorised matrix in the factors, or `None` if they are
+    /// in their original order.
+    pub fn col_perm(&self) -> Option<&Perm> {
+        self.col_perm.as_ref()
+    }
+
+    /// The product of L and U. Its entry (r, c) is the entry of the factorised matrix in row
+    /// `row_perm.new2orig()[r]` and column `col_perm.new2orig()[c]`, where dense columns
+    /// are unit columns (see [`lu_factorise`]). Meant for checking the factors, not for solves.
+    pub fn reconstruct(&self) -> SparseMat {
+        let size = self.size();
+        let diag = |tri_mat: &TriangleMat, i: usize| match &tri_mat.diag {
+            Some(diag) => diag[i].clone(),
+            None => f1_ab!(),
+        };
+        let mut product = SparseMat::new(size);
+        let mut col = ScatteredVec::empty(size);
+        for c in 0..size {
+            col.clear();
+            let upper_col = (self.upper.nondiag.col_iter(c))
+                .map(|(k, val)| (k, val.clone()))
+                .chain([(c, diag(&self.upper, c))]);
+            for (k, upper_val) in upper_col {
+                *col.get_mut(k) += &diag(&self.lower, k) * &upper_val;
+                for (r, lower_val) in self.lower.nondiag.col_iter(k) {
+                    *col.get_mut(r) += lower_val * &upper_val;
+                }
+            }
+            let mut rows = col.indices().to_vec();
+            rows.sort_unstable();
+            product.append_col(
+                (rows.into_iter())
+                    .map(|r| (r, col.get(r).clone()))
+                    .filter(|(_, val)| !val.is_zero()),
+            );
+        }
+        product
+    }
+
+    /// An estimate of the condition number ‖B‖₁‖B⁻¹‖₁ of the factorised matrix B, in which
+    /// the dense columns are unit columns (see [`lu_factorise`]).
+    ///
+    /// ‖B‖₁ is computed from the factors. ‖B⁻¹‖₁ is estimated from a few solves with vectors
+    /// of ones and minus ones, with Hager's method as refined in:
+    ///
+    /// Higham, N. J. (1988). FORTRAN codes for estimating the one-norm of a real or complex
+    /// matrix, with applications to condition estimation. ACM Transactions on Mathematical
+    /// Software, 14(4), 381-396.
+    ///
+    /// The estimate never exceeds the condition number and is rarely far below it. A large
+    /// condition number means that solves with the factors lose precision, which only matters
+    /// in approximate arithmetic; in exact arithmetic the estimate is computed exactly and
+    /// rounded at the end.
+    pub fn condest(&self) -> f64 {
+        let size = self.size();
+        if size == 0 {
+            return 1.0;
+        }
+        let mut scratch = ScratchSpace::with_capacity(size);
+        let transp = self.transpose();
+        let vec_norm = |vals: &[AbnormalFraction]| {
+            (vals.iter())
+                .map(|val| val.clone().abs())
+                .fold(f0_ab!(), |sum, abs| sum + abs)
+        };
+
+        // Hager: maximise ‖B⁻¹x‖₁ over the x with ‖x‖₁ = 1, which is attained at a unit vector,
+        // by a gradient ascent from the vector with equal entries.
+        let mut x = vec![f_ab!(1, size); size];
+        let mut inv_norm = f0_ab!();
+        let mut last_unit = None;
+        for _ in 0..5 {
+            let mut y = x.clone();
+            self.solve_dense(&mut y, &mut scratch);
+            let norm = vec_norm(&y);
+            if last_unit.is_some() && norm <= inv_norm {
+                break;
+            }
+            inv_norm = norm;
+
+            let mut z = (y.iter())
+                .map(|val| {
+                    if val.is_negative() {
+                        -f1_ab!()
+                    } else {
+                        f1_ab!()
+                    }
+                })
+                .collect::<Vec<_>>();
+            transp.solve_dense(&mut z, &mut scratch);
+            let mut unit = 0;
+            for i in 1..size {
+                if z[i].clone().abs() > z[unit].clone().abs() {
+                    unit = i;
+                }
+            }
+            let z_dot_x = (z.iter().zip(&x))
+                .map(|(z, x)| z * x)
+                .fold(f0_ab!(), |sum, term| sum + term);
+            if z[unit].clone().abs() <= z_dot_x || last_unit == Some(unit) {
+                break;
+            }
+            x = vec![f0_ab!(); size];
+            x[unit] = f1_ab!();
+            last_unit = Some(unit);
+        }
+
+        // Higham: a vector with alternating signs catches matrices that fool the ascent.
+        let mut alternating = (0..size)
+            .map(|i| {
+                let val = f1_ab!() + f_ab!(i, (size - 1).max(1));
+                if i % 2 == 0 { val } else { -val }
+            })
+            .collect::<Vec<_>>();
+        self.solve_dense(&mut alternating, &mut scratch);
+        let alternating_norm = &(&f_ab!(2) * &vec_norm(&alternating)) / &f_ab!(3 * size);
+        if alternating_norm > inv_norm {
+            inv_norm = alternating_norm;
+        }
+
+        let estimate = &self.one_norm() * &inv_norm;
+        (estimate.to_approx())
+            .and_then(|estimate| estimate.approx_ref().ok().copied())
+            .unwrap_or(f64::INFINITY)
+    }
+
+    /// The 1-norm of the factorised matrix: the largest sum of the absolute values in a
+    /// column. Row permutations do not change the sums, so the columns of L * U suffice.
+    fn one_norm(&self) -> AbnormalFraction {
+        let size = self.size();
+        let upper_diag = self.upper.diag.as_ref().unwrap();
+        let mut col = ScatteredVec::empty(size);
+        let mut max_sum = f0_ab!();
+        for (c, diag) in upper_diag.iter().enumerate() {
+            col.clear();
+            let upper_col = (self.upper.nondiag.col_iter(c)).chain([(c, diag)]);
+            for (k, upper_val) in upper_col {
+                *col.get_mut(k) += upper_val.clone();
+                for (r, lower_val) in self.lower.nondiag.col_iter(k) {
+                    *col.get_mut(r) += upper_val * lower_val;
+                }
+            }
+            let sum = (col.iter())
+                .map(|(_, val)| val.clone().abs())
+                .fold(f0_ab!(), |sum, abs| sum + abs);
+            if sum > max_sum {
+                max_sum = sum;
+            }
+        }
+        max_sum
+    }
+
     pub fn solve_dense(&self, rhs: &mut [AbnormalFraction], scratch: &mut ScratchSpace) {
         scratch.dense_rhs.resize(rhs.len(), f0_ab!());
 
         if let Some(row_perm) = &self.row_perm {
-            for i in 0..rhs.len() {
-                scratch.dense_rhs[row_perm.orig2new[i]] = rhs[i].clone();
+            for (i, val) in rhs.iter().enumerate() {
+                scratch.dense_rhs[row_perm.orig2new[i]] = val.clone();
             }
         } else {
             scratch.dense_rhs.clone_from_slice(rhs);
@@ -80,7 +458,7 @@ impl LUFactors {
                 rhs[col_perm.new2orig[i]] = scratch.dense_rhs[i].clone();
             }
         } else {
-            rhs.clone_from_slice(&mut scratch.dense_rhs);
+            rhs.clone_from_slice(&scratch.dense_rhs);
         }
     }
 
@@ -97,8 +475,8 @@ impl LUFactors {
             std::mem::swap(&mut scratch.rhs, rhs);
         }
 
-        tri_solve_sparse(&self.lower, scratch);
-        tri_solve_sparse(&self.upper, scratch);
+        tri_solve_sparse(&self.lower, Triangle::Lower, scratch);
+        tri_solve_sparse(&self.upper, Triangle::Upper, scratch);
 
         if let Some(col_perm) = &self.col_perm {
             rhs.clear();
@@ -113,526 +491,4036 @@ impl LUFactors {
         }
     }
 
-    pub fn transpose(&self) -> LUFactors {
-        LUFactors {
-            lower: self.upper.transpose(),
-            upper: self.lower.transpose(),
-            row_perm: self.col_perm.clone(),
-            col_perm: self.row_perm.clone(),
-        }
-    }
-}
-
-pub fn lu_factorise<'a>(
-    size: usize,
-    get_col: impl Fn(usize) -> (&'a [usize], &'a [AbnormalFraction]),
-    stability_coeff: AbnormalFraction,
-    scratch: &mut ScratchSpace,
-) -> Result<LUFactors, Error> {
-    // Implementation of the Gilbert-Peierls algorithm:
-    //
-    // Gilbert, John R., and Tim Peierls. "Sparse partial pivoting in time
-    // proportional to arithmetic operations." SIAM Journal on Scientific and
-    // Statistical Computing 9.5 (1988): 862-874.
-    //
-    // https://ecommons.cornell.edu/bitstream/handle/1813/6623/86-783.pdf
+    /// Solve Bᵀy = c for the factorised matrix B, with c given in `rhs` and y returned in it.
+    ///
+    /// The factors are those of B with its rows and columns permuted. On input, c is indexed
+    /// by the columns of B and is put in the order of the factors with the column permutation;
+    /// on output, y is put back from the order of the factors with the row permutation, so it
+    /// is indexed by the rows of B. Callers thus see neither permutation. If columns were left
+    /// out of the factors because they are dense (see [`lu_factorise`]), B has unit columns in
+    /// their place.
+    ///
+    /// The columns of L and U are the rows of their transposes, so the factors need not be
+    /// transposed; see [`LUFactors::solve_transp`] for sparse vectors.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ebi_optimisation::{abnormal_fraction::AbnormalFraction, f_ab, linear_programming_lu::*};
+    /// // B = [[2, 3], [1, 4]], by columns.
+    /// let rows = [0, 1];
+    /// let cols = [[f_ab!(2), f_ab!(1)], [f_ab!(3), f_ab!(4)]];
+    /// let mut scratch = ScratchSpace::with_capacity(2);
+    /// let get_col = |c: usize| (&rows[..], &cols[c][..]);
+    /// let lu = lu_factorise(2, get_col, f_ab!(1, 10), None, &mut scratch, None).unwrap();
+    ///
+    /// // y = B⁻ᵀc for c = (7, 18).
+    /// let c = [f_ab!(7), f_ab!(18)];
+    /// let mut y = c.to_vec();
+    /// lu.solve_transp_dense(&mut y, &mut scratch);
+    /// assert_eq!(y, [f_ab!(2), f_ab!(3)]);
+    ///
+    /// // Bᵀy = c: entry j of Bᵀy is column j of B times y.
+    /// for j in 0..2 {
+    ///     let dot = &(&cols[j][0] * &y[0]) + &(&cols[j][1] * &y[1]);
+    ///     assert_eq!(dot, c[j]);
+    /// }
+    /// ```
+    pub fn solve_transp_dense(&self, rhs: &mut [AbnormalFraction], scratch: &mut ScratchSpace) {
+        scratch.dense_rhs.resize(rhs.len(), f0_ab!());
 
-    let mat_nnz = (0..size).map(|c| get_col(c).0.len()).sum::<usize>();
-    trace!(
-        "lu_factorise: starting, matrix size: {}, nnz: {} (excess: {})",
-        size,
-        mat_nnz,
-        mat_nnz - size,
-    );
+        if let Some(col_perm) = &self.col_perm {
+            for (i, val) in rhs.iter().enumerate() {
+                scratch.dense_rhs[col_perm.orig2new[i]] = val.clone();
+            }
+        } else {
+            scratch.dense_rhs.clone_from_slice(rhs);
+        }
 
-    let col_perm = order_simple(size, |c| get_col(c).0);
+        tri_solve_transp_dense(&self.upper, Triangle::Upper, &mut scratch.dense_rhs);
+        tri_solve_transp_dense(&self.lower, Triangle::Lower, &mut scratch.dense_rhs);
 
-    let mut orig_row2elt_count = vec![0; size];
-    for col_rows in (0..size).map(|c| get_col(c).0) {
-        for &orig_r in col_rows {
-            orig_row2elt_count[orig_r] += 1;
+        if let Some(row_perm) = &self.row_perm {
+            for (i, val) in scratch.dense_rhs.iter().enumerate() {
+                rhs[row_perm.new2orig[i]] = val.clone();
+            }
+        } else {
+            rhs.clone_from_slice(&scratch.dense_rhs);
         }
     }
 
-    scratch.clear_sparse(size);
-
-    let mut lower = SparseMat::new(size);
-    let mut upper = SparseMat::new(size);
-    let mut upper_diag = Vec::with_capacity(size);
+    /// Solve Bᵀy = c like [`LUFactors::solve_transp_dense`], with c and y as sparse vectors.
+    /// The solve itself is dense: it takes time linear in the size and the nonzeros of the
+    /// factors, however few nonzeros c has. Entries of y that are zero are left out of its
+    /// pattern.
+    pub fn solve_transp(&self, rhs: &mut ScatteredVec, scratch: &mut ScratchSpace) {
+        scratch.dense_rhs.clear();
+        scratch.dense_rhs.resize(rhs.len(), f0_ab!());
+        for &i in &rhs.nonzero {
+            let new_i = self.col_perm.as_ref().map_or(i, |perm| perm.orig2new[i]);
+            scratch.dense_rhs[new_i] = rhs.values[i].clone();
+        }
 
-    let mut new2orig_row = (0..size).collect::<Vec<_>>();
-    let mut orig2new_row = new2orig_row.clone();
+        tri_solve_transp_dense(&self.upper, Triangle::Upper, &mut scratch.dense_rhs);
+        tri_solve_transp_dense(&self.lower, Triangle::Lower, &mut scratch.dense_rhs);
 
-    for i_col in 0..size {
-        let mat_col = get_col(col_perm.new2orig[i_col]);
+        rhs.clear();
+        for (i, val) in scratch.dense_rhs.iter_mut().enumerate() {
+            if !val.is_zero() {
+                let orig_i = self.row_perm.as_ref().map_or(i, |perm| perm.new2orig[i]);
+                rhs.is_nonzero[orig_i] = true;
+                rhs.nonzero.push(orig_i);
+                rhs.values[orig_i] = std::mem::replace(val, f0_ab!());
+            }
+        }
+    }
 
-        // Solve the equation L'_j * x = a_j (x will be in scratch.rhs).
-        // L'_j is a sq. matrix with the first j columns of L
-        // and columns of identity matrix after j.
-        // Part of x above the diagonal (in the new row indices) is the column of U
-        // and part of x below the diagonal divided by the pivot value is the column of L
+    /// Solve with each of the right-hand sides in `rhs`, like [`LUFactors::solve`]. The
+    /// nonzeros of the solutions are computed once for all of them, from the union of their
+    /// nonzero patterns, so right-hand sides with overlapping patterns share the symbolic work;
+    /// each is then solved numerically over the union, skipping the zeros. Entries that are
+    /// zero are left out of the patterns of the solutions.
+    pub fn solve_many(&self, rhs: &mut [ScatteredVec], scratch: &mut ScratchSpace) {
+        let orig2new_row = |i: usize| self.row_perm.as_ref().map_or(i, |perm| perm.orig2new[i]);
+        let new2orig_col = |i: usize| self.col_perm.as_ref().map_or(i, |perm| perm.new2orig[i]);
 
-        scratch.rhs.set(mat_col.0.iter().copied().zip(mat_col.1));
+        scratch.rhs.clear();
+        for vec in rhs.iter() {
+            for &i in &vec.nonzero {
+                let new_i = orig2new_row(i);
+                if !scratch.rhs.is_nonzero[new_i] {
+                    scratch.rhs.is_nonzero[new_i] = true;
+                    scratch.rhs.nonzero.push(new_i);
+                }
+            }
+        }
 
+        // The reach in L, then the reach in U of that; the latter ends up in mark_nonzero.
+        // rev() because DFS returns vertices in reverse topological order.
         scratch.mark_nonzero.run(
             &mut scratch.rhs,
-            |new_i| &lower.col_rows(new_i),
-            |new_i| new_i < i_col,
-            |orig_r| orig2new_row[orig_r],
+            |col| self.lower.nondiag.col_rows(col),
+            |_| true,
+            |orig_i| orig_i,
+        );
+        scratch.reach.clear();
+        scratch
+            .reach
+            .extend(scratch.mark_nonzero.visited.iter().rev());
+        scratch.mark_nonzero.run(
+            &mut scratch.rhs,
+            |col| self.upper.nondiag.col_rows(col),
+            |_| true,
+            |orig_i| orig_i,
         );
 
-        // At this point all future nonzero positions of scratch.rhs are marked
-        // and the order in which variables depend on each other is determined.
-        // rev() because DFS returns vertices in reverse topological order.
-        for &orig_i in scratch.mark_nonzero.visited.iter().rev() {
-            // values[orig_i] is already fully calculated, diag coeff = f1!().
-            let new_i = orig2new_row[orig_i];
-            if new_i < i_col {
-                let x_val = scratch.rhs.values[orig_i].clone();
-                for (orig_r, coeff) in lower.col_iter(new_i) {
-                    scratch.rhs.values[orig_r] -= &x_val * coeff;
-                }
+        let values = &mut scratch.rhs.values;
+        for vec in rhs.iter_mut() {
+            for &i in &vec.nonzero {
+                values[orig2new_row(i)] = vec.values[i].clone();
             }
-        }
-
-        // Next we choose a pivot among values of x below the diagonal.
-        // Pivoting by choosing the max element is good for stability,
-        // but bad for sparseness, so we do threshold pivoting instead.
-
-        let pivot_orig_r = {
-            let mut max_abs = f0_ab!();
-            for &orig_r in &scratch.rhs.nonzero {
-                if orig2new_row[orig_r] < i_col {
-                    continue;
-                }
-
-                let abs = AbnormalFraction::abs(scratch.rhs.values[orig_r].clone());
-                if abs > max_abs {
-                    max_abs = abs;
+            for &col in &scratch.reach {
+                if !values[col].is_zero() {
+                    tri_solve_process_col(&self.lower, col, values);
                 }
             }
-
-            if max_abs.is_zero() {
-                return Err(Error::SingularMatrix);
-            }
-
-            // Choose among eligible pivot rows one with the least elements.
-            // Gilbert-Peierls suggest to choose row with least elements *to the right*,
-            // but it yielded poor results. Our heuristic is not a huge improvement either,
-            // but at least we are less dependent on initial row ordering.
-            let mut best_orig_r = None;
-            let mut best_elt_count = None;
-            for &orig_r in &scratch.rhs.nonzero {
-                if orig2new_row[orig_r] < i_col {
-                    continue;
+            for &col in scratch.mark_nonzero.visited.iter().rev() {
+                if !values[col].is_zero() {
+                    tri_solve_process_col(&self.upper, col, values);
                 }
+            }
 
-                if AbnormalFraction::abs(scratch.rhs.values[orig_r].clone())
-                    >= &stability_coeff * &max_abs
-                {
-                    let elt_count = orig_row2elt_count[orig_r];
-                    if best_elt_count.is_none() || best_elt_count.unwrap() > elt_count {
-                        best_orig_r = Some(orig_r);
-                        best_elt_count = Some(elt_count);
-                    }
+            vec.clear();
+            for &i in &scratch.rhs.nonzero {
+                let val = std::mem::replace(&mut values[i], f0_ab!());
+                if !val.is_zero() {
+                    let orig_i = new2orig_col(i);
+                    vec.is_nonzero[orig_i] = true;
+                    vec.nonzero.push(orig_i);
+                    vec.values[orig_i] = val;
                 }
             }
-            best_orig_r.unwrap()
-        };
-
-        let pivot_val = &scratch.rhs.values[pivot_orig_r];
-
-        {
-            // Keep track of row permutations.
-            let row = i_col;
-            let orig_row = new2orig_row[row];
-            let pivot_row = orig2new_row[pivot_orig_r];
-            new2orig_row.swap(row, pivot_row);
-            orig2new_row.swap(orig_row, pivot_orig_r);
         }
+        scratch.rhs.clear();
+    }
 
-        // Gather the values of x into lower and upper matrices.
-
-        for &orig_r in &scratch.rhs.nonzero {
-            let val = &scratch.rhs.values[orig_r];
+    /// Solve with the transpose of the factorised matrix for each of the right-hand sides in
+    /// `rhs`, like [`LUFactors::solve_many`]. The factors are transposed once for the batch.
+    pub fn solve_transp_many(&self, rhs: &mut [ScatteredVec], scratch: &mut ScratchSpace) {
+        self.transpose().solve_many(rhs, scratch);
+    }
 
-            if val.is_zero() {
-                continue;
+    /// Solve with each of the dense right-hand sides in `rhs`, like [`LUFactors::solve_dense`].
+    /// The factors are traversed once, each column being applied to all right-hand sides.
+    pub fn solve_dense_block(&self, rhs: &mut [Vec<AbnormalFraction>], scratch: &mut ScratchSpace) {
+        for vec in rhs.iter_mut() {
+            if let Some(row_perm) = &self.row_perm {
+                permute_dense(vec, &row_perm.orig2new, &mut scratch.dense_rhs);
             }
-
-            let new_r = orig2new_row[orig_r];
-            if new_r < i_col {
-                upper.push(new_r, val.clone());
-            } else if new_r == i_col {
-                upper_diag.push(pivot_val.clone());
-            } else {
-                lower.push(orig_r, val / pivot_val);
+        }
+        for col in 0..self.size() {
+            for vec in rhs.iter_mut() {
+                tri_solve_process_col(&self.lower, col, vec);
+            }
+        }
+        for col in (0..self.size()).rev() {
+            for vec in rhs.iter_mut() {
+                tri_solve_process_col(&self.upper, col, vec);
+            }
+        }
+        for vec in rhs.iter_mut() {
+            if let Some(col_perm) = &self.col_perm {
+                permute_dense(vec, &col_perm.new2orig, &mut scratch.dense_rhs);
             }
         }
+    }
 
-        upper.seal_column();
-        lower.seal_column();
+    /// Solve with the transpose of the factorised matrix for each of the dense right-hand sides
+    /// in `rhs`, like [`LUFactors::solve_dense_block`]. The columns of the factors are the rows
+    /// of their transposes, so no transposes are computed.
+    pub fn solve_transp_dense_block(
+        &self,
+        rhs: &mut [Vec<AbnormalFraction>],
+        scratch: &mut ScratchSpace,
+    ) {
+        for vec in rhs.iter_mut() {
+            if let Some(col_perm) = &self.col_perm {
+                permute_dense(vec, &col_perm.orig2new, &mut scratch.dense_rhs);
+            }
+        }
+        for col in 0..self.size() {
+            for vec in rhs.iter_mut() {
+                tri_solve_transp_process_col(&self.upper, col, vec);
+            }
+        }
+        for col in (0..self.size()).rev() {
+            for vec in rhs.iter_mut() {
+                tri_solve_transp_process_col(&self.lower, col, vec);
+            }
+        }
+        for vec in rhs.iter_mut() {
+            if let Some(row_perm) = &self.row_perm {
+                permute_dense(vec, &row_perm.new2orig, &mut scratch.dense_rhs);
+            }
+        }
     }
 
-    // permute rows of lower to "new" indices.
-    for i_col in 0..lower.cols() {
-        for r in lower.col_rows_mut(i_col) {
-            *r = orig2new_row[*r];
+    pub fn transpose(&self) -> LUFactors {
+        LUFactors {
+            lower: self.upper.transpose(),
+            upper: self.lower.transpose(),
+            row_perm: self.col_perm.clone(),
+            col_perm: self.row_perm.clone(),
+            dense_cols: self.dense_cols.clone(),
         }
     }
+}
 
-    let lower_nnz = lower.nnz();
-    let upper_nnz = upper.nnz();
-    trace!(
-        "lu_factorise: done, lower nnz: {} (excess: {}), upper nnz: {} (excess: {}), additional fill-in: {}",
-        lower_nnz + size,
-        lower_nnz,
-        upper_nnz + size,
-        upper_nnz,
-        lower_nnz + upper_nnz + size - mat_nnz,
+/// Factorise the matrix with columns `get_col`.
+///
+/// If `dense_col_threshold` is set, columns with more nonzeros than that are not factorised:
+/// each is replaced by a unit column whose one is in a row that none of the other columns
+/// pivots on, and the factors are those of the resulting matrix. The caller gets the replaced
+/// columns from [`LUFactors::dense_cols`] and has to restore them, e.g. with eta matrices.
+/// Factorised, a dense column would become a dense column of U.
+pub fn lu_factorise<'a>(
+    size: usize,
+    get_col: impl Fn(usize) -> (&'a [usize], &'a [AbnormalFraction]),
+    stability_coeff: AbnormalFraction,
+    dense_col_threshold: Option<usize>,
+    scratch: &mut ScratchSpace,
+    cancel: Option<&AtomicBool>,
+) -> Result<LUFactors, Error> {
+    let mut lu_scratch = LuScratch::with_capacity(0);
+    std::mem::swap(&mut lu_scratch.space, scratch);
+    let res = lu_factorise_with_scratch(
+        size,
+        get_col,
+        stability_coeff,
+        dense_col_threshold,
+        &mut lu_scratch,
+        cancel,
     );
+    std::mem::swap(&mut lu_scratch.space, scratch);
+    res
+}
 
-    let res = LUFactors {
-        lower: TriangleMat {
-            nondiag: lower,
-            diag: None,
-        },
-        upper: TriangleMat {
-            nondiag: upper,
-            diag: Some(upper_diag),
-        },
-        row_perm: Some(Perm {
-            orig2new: orig2new_row,
-            new2orig: new2orig_row,
-        }),
-        col_perm: Some(col_perm),
-    };
-
-    Ok(res)
+/// Like [`lu_factorise`], but with the work arrays in `scratch` and the factors in the storage
+/// of the factors last handed to [`LuScratch::recycle`].
+pub fn lu_factorise_with_scratch<'a>(
+    size: usize,
+    get_col: impl Fn(usize) -> (&'a [usize], &'a [AbnormalFraction]),
+    stability_coeff: AbnormalFraction,
+    dense_col_threshold: Option<usize>,
+    scratch: &mut LuScratch,
+    cancel: Option<&AtomicBool>,
+) -> Result<LUFactors, Error> {
+    factorise_gilbert_peierls(
+        size,
+        get_col,
+        stability_coeff,
+        dense_col_threshold,
+        None,
+        None,
+        scratch,
+        cancel,
+    )
 }
 
-#[derive(Clone, Debug)]
-struct MarkNonzero {
-    dfs_stack: Vec<DfsStep>,
-    is_visited: Vec<bool>,
-    visited: Vec<usize>, // in reverse topological order
+/// Like [`lu_factorise_with_scratch`], but start from `prev`, the factors of a matrix that
+/// differs from this one only in the columns `changed_cols`.
+///
+/// The columns keep their order in `prev`, except that the changed columns move to the end.
+/// The factors of the columns before the first changed one only depend on those columns, so
+/// they are taken over from `prev`, and the elimination starts at the first changed column;
+/// [`LuScratch::reused_cols`] tells how many columns were taken over. This needs factors of
+/// [`lu_factorise`] or [`lu_factorise_markowitz`] of a matrix of the same size, and no dense
+/// columns in either matrix; otherwise the matrix is factorised from scratch.
+#[allow(clippy::too_many_arguments)]
+pub fn lu_refactorise_with_scratch<'a>(
+    size: usize,
+    get_col: impl Fn(usize) -> (&'a [usize], &'a [AbnormalFraction]),
+    stability_coeff: AbnormalFraction,
+    dense_col_threshold: Option<usize>,
+    prev: &LUFactors,
+    changed_cols: &[usize],
+    scratch: &mut LuScratch,
+    cancel: Option<&AtomicBool>,
+) -> Result<LUFactors, Error> {
+    let can_reuse = prev.size() == size
+        && prev.dense_cols.is_empty()
+        && prev.lower.diag.is_none()
+        && prev.row_perm.is_some()
+        && prev.col_perm.is_some()
+        && (0..size)
+            .all(|c| dense_col_threshold.is_none_or(|threshold| get_col(c).0.len() <= threshold));
+    factorise_gilbert_peierls(
+        size,
+        get_col,
+        stability_coeff,
+        dense_col_threshold,
+        can_reuse.then_some((prev, changed_cols)),
+        None,
+        scratch,
+        cancel,
+    )
 }
 
+/// The symbolic part of a factorisation by [`lu_analyse_with_scratch`]: the pattern of the
+/// matrix, the pivots, and for each elimination step the columns of L that it applies and the
+/// pattern of its solve. [`lu_factorise_symbolic`] factorises matrices with the same pattern
+/// with the same steps, without searching the patterns and choosing pivots again.
 #[derive(Clone, Debug)]
-struct DfsStep {
-    orig_i: usize,
-    cur_child: usize,
+pub struct SymbolicLU {
+    /// The rows of each column of the matrix.
+    mat_pattern: Vec<Vec<usize>>,
+    row_perm: Perm,
+    col_perm: Perm,
+    /// For each step, the steps whose columns of L it applies, in order.
+    reach: Vec<Vec<usize>>,
+    /// For each step, the rows of the solve, in the order of the factorisation.
+    nonzero: Vec<Vec<usize>>,
+    /// For each step, the rows of the solve that the factors left out because they were zero
+    /// or dropped. The steps after it do not account for them, so they must stay out.
+    skipped: Vec<Vec<usize>>,
 }
 
-impl MarkNonzero {
-    fn with_capacity(n: usize) -> MarkNonzero {
-        MarkNonzero {
-            dfs_stack: Vec::with_capacity(n),
-            is_visited: vec![false; n],
-            visited: vec![],
+impl SymbolicLU {
+    fn new() -> SymbolicLU {
+        SymbolicLU {
+            mat_pattern: vec![],
+            row_perm: Perm {
+                orig2new: vec![],
+                new2orig: vec![],
+            },
+            col_perm: Perm {
+                orig2new: vec![],
+                new2orig: vec![],
+            },
+            reach: vec![],
+            nonzero: vec![],
+            skipped: vec![],
         }
     }
 
-    fn clear(&mut self) {
-        assert!(self.dfs_stack.is_empty());
-        for &i in &self.visited {
-            self.is_visited[i] = false;
-        }
-        self.visited.clear();
+    /// Size of the matrix.
+    pub fn size(&self) -> usize {
+        self.mat_pattern.len()
     }
 
-    fn clear_and_resize(&mut self, n: usize) {
-        self.clear();
-        self.dfs_stack.reserve(n);
-        self.is_visited.resize(n, false);
+    /// Whether the matrix with the columns `get_col` has the pattern of the analysed matrix;
+    /// otherwise, the step of the first column that differs.
+    fn check_pattern<'a>(
+        &self,
+        size: usize,
+        get_col: impl Fn(usize) -> (&'a [usize], &'a [AbnormalFraction]),
+    ) -> Result<(), Error> {
+        if size != self.size() {
+            return Err(Error::SymbolicMismatch { step: 0 });
+        }
+        for (step, &c) in self.col_perm.new2orig.iter().enumerate() {
+            if get_col(c).0 != self.mat_pattern[c] {
+                return Err(Error::SymbolicMismatch { step });
+            }
+        }
+        Ok(())
     }
+}
 
-    // compute the non-zero elements of the result by dfs traversal
-    fn run<'a>(
-        &mut self,
-        rhs: &mut ScatteredVec,
-        get_children: impl Fn(usize) -> &'a [usize] + 'a,
-        filter: impl Fn(usize) -> bool,
-        orig2new_row: impl Fn(usize) -> usize,
-    ) {
-        self.clear();
+/// Like [`lu_factorise_with_scratch`] without dense columns, and also return the symbolic part
+/// of the factorisation, for [`lu_factorise_symbolic`].
+pub fn lu_analyse_with_scratch<'a>(
+    size: usize,
+    get_col: impl Fn(usize) -> (&'a [usize], &'a [AbnormalFraction]),
+    stability_coeff: AbnormalFraction,
+    scratch: &mut LuScratch,
+    cancel: Option<&AtomicBool>,
+) -> Result<(LUFactors, SymbolicLU), Error> {
+    let mut symbolic = SymbolicLU::new();
+    let lu = factorise_gilbert_peierls(
+        size,
+        get_col,
+        stability_coeff,
+        None,
+        None,
+        Some(&mut symbolic),
+        scratch,
+        cancel,
+    )?;
+    symbolic.row_perm = lu.row_perm.clone().unwrap();
+    symbolic.col_perm = lu.col_perm.clone().unwrap();
+    Ok((lu, symbolic))
+}
 
-        for &orig_r in &rhs.nonzero {
-            let new_r = orig2new_row(orig_r);
-            if !filter(new_r) {
-                continue;
-            }
-            if self.is_visited[orig_r] {
-                continue;
-            }
+/// Factorise a matrix with the pattern of the one that `symbolic` was analysed from, with its
+/// pivots, and in the same order of operations: with the same values, the factors equal those
+/// of [`lu_analyse_with_scratch`].
+///
+/// Returns [`Error::SymbolicMismatch`] if the pattern differs, if a pivot fails the threshold
+/// test with `stability_coeff` for the new values, or if the factors get an entry that was
+/// zero or dropped in the analysis; the matrix then needs a full factorisation.
+pub fn lu_factorise_symbolic<'a>(
+    symbolic: &SymbolicLU,
+    get_col: impl Fn(usize) -> (&'a [usize], &'a [AbnormalFraction]),
+    stability_coeff: AbnormalFraction,
+    scratch: &mut LuScratch,
+    cancel: Option<&AtomicBool>,
+) -> Result<LUFactors, Error> {
+    let size = symbolic.size();
+    symbolic.check_pattern(size, &get_col)?;
 
-            self.dfs_stack.push(DfsStep {
-                orig_i: orig_r,
-                cur_child: 0,
-            });
-            while !self.dfs_stack.is_empty() {
-                let cur_step = self.dfs_stack.last_mut().unwrap();
-                let new_i = orig2new_row(cur_step.orig_i);
-                let children = if filter(new_i) {
-                    get_children(new_i)
-                } else {
-                    &[]
-                };
-                if !self.is_visited[cur_step.orig_i] {
-                    self.is_visited[cur_step.orig_i] = true;
-                } else {
-                    cur_step.cur_child += 1;
-                }
+    let LUFactors {
+        lower: TriangleMat {
+            nondiag: mut lower, ..
+        },
+        upper: TriangleMat {
+            nondiag: mut upper,
+            diag: upper_diag,
+        },
+        mut dense_cols,
+        ..
+    } = scratch.take_spare();
+    let mut upper_diag = upper_diag.unwrap();
+    scratch.rejected_pivots = 0;
+    scratch.unit_pivots = 0;
+    scratch.dropped_entries = 0;
+    scratch.reused_cols = 0;
+    scratch.space.clear_sparse(size);
+    lower.clear_and_resize(size);
+    upper.clear_and_resize(size);
+    upper_diag.clear();
+    dense_cols.clear();
 
-                while cur_step.cur_child < children.len() {
-                    let child_orig_r = children[cur_step.cur_child];
-                    if !self.is_visited[child_orig_r] {
-                        break;
-                    }
-                    cur_step.cur_child += 1;
-                }
+    let SymbolicLU {
+        row_perm, col_perm, ..
+    } = symbolic;
+    let rhs = &mut scratch.space.rhs;
+    let mut is_skipped = vec![false; size];
+    for i_col in 0..size {
+        if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            return Err(Error::Interrupted);
+        }
 
-                if cur_step.cur_child < children.len() {
-                    let i_child = cur_step.cur_child;
-                    self.dfs_stack.push(DfsStep {
-                        orig_i: children[i_child],
-                        cur_child: 0,
-                    });
-                } else {
-                    self.visited.push(cur_step.orig_i);
-                    self.dfs_stack.pop();
-                }
+        // The solve of the factorisation, with its pattern and order from the analysis.
+        let mat_col = get_col(col_perm.new2orig[i_col]);
+        rhs.set(mat_col.0.iter().copied().zip(mat_col.1));
+        for &orig_r in &symbolic.nonzero[i_col] {
+            if !rhs.is_nonzero[orig_r] {
+                rhs.is_nonzero[orig_r] = true;
+                rhs.nonzero.push(orig_r);
             }
         }
-
-        for &i in &self.visited {
-            if !rhs.is_nonzero[i] {
-                rhs.is_nonzero[i] = true;
-                rhs.nonzero.push(i)
+        for &new_i in &symbolic.reach[i_col] {
+            let x_val = rhs.values[row_perm.new2orig[new_i]].clone();
+            for (new_r, coeff) in lower.col_iter(new_i) {
+                rhs.values[row_perm.new2orig[new_r]] -= &x_val * coeff;
             }
         }
-    }
-}
 
-enum Triangle {
-    Lower,
-    Upper,
-}
+        let pivot_val = rhs.values[row_perm.new2orig[i_col]].clone();
+        let col_max_abs = max_abs(
+            (rhs.nonzero.iter())
+                .filter(|&&orig_r| row_perm.orig2new[orig_r] >= i_col)
+                .map(|&orig_r| &rhs.values[orig_r]),
+        );
+        if pivot_val.is_zero()
+            || AbnormalFraction::abs(pivot_val.clone()) < &stability_coeff * &col_max_abs
+        {
+            return Err(Error::SymbolicMismatch { step: i_col });
+        }
+        if is_unit(&pivot_val) {
+            scratch.unit_pivots += 1;
+        }
 
-fn tri_solve_dense(tri_mat: &TriangleMat, triangle: Triangle, rhs: &mut [AbnormalFraction]) {
-    assert_eq!(tri_mat.rows(), rhs.len());
-    match triangle {
-        Triangle::Lower => {
-            for col in 0..tri_mat.cols() {
-                tri_solve_process_col(tri_mat, col, rhs);
-            }
+        let x_max_abs = match &scratch.drop_tolerance {
+            Some(_) => max_abs(rhs.nonzero.iter().map(|&r| &rhs.values[r])),
+            None => f0_ab!(),
+        };
+        for &orig_r in &symbolic.skipped[i_col] {
+            is_skipped[orig_r] = true;
         }
+        for &orig_r in &rhs.nonzero {
+            let val = &rhs.values[orig_r];
+            if val.is_zero() {
+                continue;
+            }
 
-        Triangle::Upper => {
-            for col in (0..tri_mat.cols()).rev() {
-                tri_solve_process_col(tri_mat, col, rhs);
+            let new_r = row_perm.orig2new[orig_r];
+            if new_r != i_col && is_dropped(scratch.drop_tolerance.as_ref(), val, &x_max_abs) {
+                scratch.dropped_entries += 1;
+            } else if is_skipped[orig_r] {
+                // Fill that the analysis did not see.
+                return Err(Error::SymbolicMismatch { step: i_col });
+            } else if new_r < i_col {
+                upper.push(new_r, val.clone());
+            } else if new_r == i_col {
+                upper_diag.push(pivot_val.clone());
+            } else {
+                lower.push(new_r, val / &pivot_val);
             }
         }
-    };
+        for &orig_r in &symbolic.skipped[i_col] {
+            is_skipped[orig_r] = false;
+        }
+        upper.seal_column();
+        lower.seal_column();
+    }
+
+    Ok(LUFactors {
+        lower: TriangleMat {
+            nondiag: lower,
+            diag: None,
+        },
+        upper: TriangleMat {
+            nondiag: upper,
+            diag: Some(upper_diag),
+        },
+        row_perm: Some(row_perm.clone()),
+        col_perm: Some(col_perm.clone()),
+        dense_cols,
+    })
 }
 
-/// rhs is passed via scratch.visited, scratch.values.
-fn tri_solve_sparse(tri_mat: &TriangleMat, scratch: &mut ScratchSpace) {
-    assert_eq!(tri_mat.rows(), scratch.rhs.len());
+/// The factorisation of [`lu_factorise_with_scratch`], starting with the factors of the
+/// unchanged leading columns of `reuse` if set (see [`lu_refactorise_with_scratch`]), and
+/// recording its symbolic part in `symbolic` if set.
+#[allow(clippy::too_many_arguments)]
+fn factorise_gilbert_peierls<'a>(
+    size: usize,
+    get_col: impl Fn(usize) -> (&'a [usize], &'a [AbnormalFraction]),
+    stability_coeff: AbnormalFraction,
+    dense_col_threshold: Option<usize>,
+    reuse: Option<(&LUFactors, &[usize])>,
+    mut symbolic: Option<&mut SymbolicLU>,
+    scratch: &mut LuScratch,
+    cancel: Option<&AtomicBool>,
+) -> Result<LUFactors, Error> {
+    // Implementation of the Gilbert-Peierls algorithm:
+    //
+    // Gilbert, John R., and Tim Peierls. "Sparse partial pivoting in time
+    // proportional to arithmetic operations." SIAM Journal on Scientific and
+    // Statistical Computing 9.5 (1988): 862-874.
+    //
+    // https://ecommons.cornell.edu/bitstream/handle/1813/6623/86-783.pdf
 
-    // compute the non-zero elements of the result by dfs traversal
-    scratch.mark_nonzero.run(
-        &mut scratch.rhs,
-        |col| tri_mat.nondiag.col_rows(col),
-        |_| true,
-        |orig_i| orig_i,
+    let mat_nnz = (0..size).map(|c| get_col(c).0.len()).sum::<usize>();
+    trace!(
+        "lu_factorise: starting, matrix size: {}, nnz: {} (excess: {})",
+        size,
+        mat_nnz,
+        mat_nnz - size,
     );
 
-    // solve for the non-zero values into dense workspace.
-    // rev() because DFS returns vertices in reverse topological order.
-    for &col in scratch.mark_nonzero.visited.iter().rev() {
-        tri_solve_process_col(tri_mat, col, &mut scratch.rhs.values);
-    }
-}
+    let LUFactors {
+        lower: TriangleMat {
+            nondiag: mut lower, ..
+        },
+        upper: TriangleMat {
+            nondiag: mut upper,
+            diag: upper_diag,
+        },
+        row_perm,
+        col_perm,
+        mut dense_cols,
+    } = scratch.take_spare();
+    let (mut upper_diag, row_perm, mut col_perm) =
+        (upper_diag.unwrap(), row_perm.unwrap(), col_perm.unwrap());
+    let LuScratch {
+        space: scratch,
+        cols_queue,
+        is_dense,
+        row_counts: orig_row2elt_count,
+        rejected_pivots,
+        unit_pivots,
+        drop_tolerance,
+        dropped_entries,
+        reused_cols,
+        ordering,
+        ordering_cache,
+        row_hints,
+        ..
+    } = scratch;
+    *rejected_pivots = 0;
+    *unit_pivots = 0;
+    *dropped_entries = 0;
 
-fn tri_solve_process_col(tri_mat: &TriangleMat, col: usize, rhs: &mut [AbnormalFraction]) {
-    // all other variables in this row (multiplied by their coeffs)
-    // are already subtracted from rhs[col].
-    let x_val = if let Some(diag) = tri_mat.diag.as_ref() {
-        &rhs[col] / &diag[col]
-    } else {
-        rhs[col].clone()
+    *reused_cols = match reuse {
+        Some((prev, changed_cols)) => {
+            // The order of prev with the changed columns at the end.
+            let prev_order = &prev.col_perm.as_ref().unwrap().new2orig;
+            let mut is_changed = vec![false; size];
+            for &c in changed_cols {
+                is_changed[c] = true;
+            }
+            col_perm.new2orig.clear();
+            (col_perm.new2orig).extend(prev_order.iter().filter(|&&c| !is_changed[c]));
+            (col_perm.new2orig).extend(prev_order.iter().filter(|&&c| is_changed[c]));
+            col_perm.orig2new.clear();
+            col_perm.orig2new.resize(size, 0);
+            ordering_cache.skip();
+            prev_order.iter().take_while(|&&c| !is_changed[c]).count()
+        }
+        None => {
+            ordering_cache.order(ordering, size, &get_col, cols_queue, &mut col_perm)?;
+            0
+        }
     };
 
-    rhs[col] = x_val.clone();
-    for (r, coeff) in tri_mat.nondiag.col_iter(col) {
-        rhs[r] -= &x_val * coeff;
+    // Move the dense columns to the end, where they are replaced by unit columns.
+    is_dense.clear();
+    is_dense.extend(
+        (0..size)
+            .map(|c| dense_col_threshold.is_some_and(|threshold| get_col(c).0.len() > threshold)),
+    );
+    dense_cols.clear();
+    let mut num_sparse_cols = 0;
+    for new_c in 0..size {
+        let orig_c = col_perm.new2orig[new_c];
+        if is_dense[orig_c] {
+            dense_cols.push(orig_c);
+        } else {
+            col_perm.new2orig[num_sparse_cols] = orig_c;
+            num_sparse_cols += 1;
+        }
+    }
+    col_perm.new2orig[num_sparse_cols..].copy_from_slice(&dense_cols);
+    for (new_c, &orig_c) in col_perm.new2orig.iter().enumerate() {
+        col_perm.orig2new[orig_c] = new_c;
+    }
+    let factorised_nnz = mat_nnz + dense_cols.len()
+        - (dense_cols.iter())
+            .map(|&c| get_col(c).0.len())
+            .sum::<usize>();
+    if !dense_cols.is_empty() {
+        trace!(
+            "lu_factorise: {} dense columns replaced by unit columns",
+            dense_cols.len()
+        );
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::{
-        f_ab, f1_ab,
-        linear_programming_helpers::{assert_matrix_eq, to_dense, to_sparse},
-    };
-
-    use super::*;
-    use ebi_arithmetic::One;
-    use sprs::{CsMat, TriMat};
 
-    fn mat_from_triplets(
-        rows: usize,
-        cols: usize,
-        triplets: &[(usize, usize, AbnormalFraction)],
-    ) -> CsMat<AbnormalFraction> {
-        let mut mat = TriMat::with_capacity((rows, cols), triplets.len());
-        for (r, c, val) in triplets {
-            mat.add_triplet(*r, *c, val.clone());
+    orig_row2elt_count.clear();
+    orig_row2elt_count.resize(size, 0);
+    for col_rows in (0..size).filter(|&c| !is_dense[c]).map(|c| get_col(c).0) {
+        for &orig_r in col_rows {
+            orig_row2elt_count[orig_r] += 1;
         }
-        mat.to_csc()
     }
 
-    #[test]
-    fn lu_simple() {
-        let mat = mat_from_triplets(
-            3,
-            4,
+    scratch.clear_sparse(size);
+
+    lower.clear_and_resize(size);
+    upper.clear_and_resize(size);
+    upper_diag.clear();
+
+    let Perm {
+        orig2new: mut orig2new_row,
+        new2orig: mut new2orig_row,
+    } = row_perm;
+    new2orig_row.clear();
+    new2orig_row.extend(0..size);
+    orig2new_row.clear();
+    orig2new_row.extend(0..size);
+
+    if let Some((prev, _)) = reuse {
+        // Take over the first steps of prev: its pivot rows, and the columns of its factors.
+        // Until the end, the rows of lower are original rows.
+        let prev_row_perm = prev.row_perm.as_ref().unwrap();
+        new2orig_row.copy_from_slice(&prev_row_perm.new2orig);
+        orig2new_row.copy_from_slice(&prev_row_perm.orig2new);
+        let prev_upper_diag = prev.upper.diag.as_ref().unwrap();
+        for (c, diag) in prev_upper_diag.iter().enumerate().take(*reused_cols) {
+            for (new_r, val) in prev.lower.nondiag.col_iter(c) {
+                lower.push(new2orig_row[new_r], val.clone());
+            }
+            lower.seal_column();
+            for (new_r, val) in prev.upper.nondiag.col_iter(c) {
+                upper.push(new_r, val.clone());
+            }
+            upper.seal_column();
+            upper_diag.push(diag.clone());
+        }
+        trace!("lu_factorise: reusing {} of {} columns", reused_cols, size);
+    }
+
+    if let Some(symbolic) = &mut symbolic {
+        symbolic.mat_pattern.clear();
+        (symbolic.mat_pattern).extend((0..size).map(|c| get_col(c).0.to_vec()));
+        symbolic.reach.clear();
+        symbolic.nonzero.clear();
+        symbolic.skipped.clear();
+    }
+
+    for i_col in *reused_cols..size {
+        if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            return Err(Error::Interrupted);
+        }
+
+        // Solve the equation L'_j * x = a_j (x will be in scratch.rhs).
+        // L'_j is a sq. matrix with the first j columns of L
+        // and columns of identity matrix after j.
+        // Part of x above the diagonal (in the new row indices) is the column of U
+        // and part of x below the diagonal divided by the pivot value is the column of L
+
+        if i_col < num_sparse_cols {
+            let mat_col = get_col(col_perm.new2orig[i_col]);
+            scratch.rhs.set(mat_col.0.iter().copied().zip(mat_col.1));
+        } else {
+            // A unit column in a row that no column pivoted on yet, which becomes the pivot.
+            scratch.rhs.set([(new2orig_row[i_col], &f1_ab!())]);
+        }
+
+        scratch.mark_nonzero.run(
+            &mut scratch.rhs,
+            |new_i| lower.col_rows(new_i),
+            |new_i| new_i < i_col,
+            |orig_r| orig2new_row[orig_r],
+        );
+
+        // At this point all future nonzero positions of scratch.rhs are marked
+        // and the order in which variables depend on each other is determined.
+        // rev() because DFS returns vertices in reverse topological order.
+        for &orig_i in scratch.mark_nonzero.visited.iter().rev() {
+            // values[orig_i] is already fully calculated, diag coeff = f1!().
+            let new_i = orig2new_row[orig_i];
+            if new_i < i_col {
+                let x_val = scratch.rhs.values[orig_i].clone();
+                for (orig_r, coeff) in lower.col_iter(new_i) {
+                    scratch.rhs.values[orig_r] -= &x_val * coeff;
+                }
+            }
+        }
+        if let Some(symbolic) = &mut symbolic {
+            symbolic.reach.push(
+                (scratch.mark_nonzero.visited.iter().rev())
+                    .map(|&orig_i| orig2new_row[orig_i])
+                    .filter(|&new_i| new_i < i_col)
+                    .collect(),
+            );
+            symbolic.nonzero.push(scratch.rhs.nonzero.clone());
+        }
+
+        // Next we choose a pivot among values of x below the diagonal.
+        // Pivoting by choosing the max element is good for stability,
+        // but bad for sparseness, so we do threshold pivoting instead.
+
+        let pivot_orig_r = {
+            let mut max_abs = f0_ab!();
+            for &orig_r in &scratch.rhs.nonzero {
+                if orig2new_row[orig_r] < i_col {
+                    continue;
+                }
+
+                let abs = AbnormalFraction::abs(scratch.rhs.values[orig_r].clone());
+                if abs > max_abs {
+                    max_abs = abs;
+                }
+            }
+
+            if max_abs.is_zero() {
+                return Err(Error::SingularMatrix {
+                    step: i_col,
+                    row: Some(new2orig_row[i_col]),
+                    col: Some(col_perm.new2orig[i_col]),
+                });
+            }
+
+            // Choose among eligible pivot rows one with the earliest hint, and among those
+            // one with the least elements.
+            // Gilbert-Peierls suggest to choose row with least elements *to the right*,
+            // but it yielded poor results. Our heuristic is not a huge improvement either,
+            // but at least we are less dependent on initial row ordering.
+            let mut best_orig_r = None;
+            let mut best_key = None;
+            for &orig_r in &scratch.rhs.nonzero {
+                if orig2new_row[orig_r] < i_col {
+                    continue;
+                }
+
+                // An entry that cancelled out is no pivot, even with a stability coefficient of 0.
+                let abs = AbnormalFraction::abs(scratch.rhs.values[orig_r].clone());
+                if abs.is_zero() {
+                    continue;
+                }
+                if abs >= &stability_coeff * &max_abs {
+                    let hint = row_hints.get(orig_r).copied().unwrap_or_default();
+                    let key = (hint, orig_row2elt_count[orig_r]);
+                    if best_key.is_none_or(|best_key| best_key > key) {
+                        best_orig_r = Some(orig_r);
+                        best_key = Some(key);
+                    }
+                } else {
+                    *rejected_pivots += 1;
+                }
+            }
+            best_orig_r.unwrap()
+        };
+
+        let pivot_val = &scratch.rhs.values[pivot_orig_r];
+        if is_unit(pivot_val) {
+            *unit_pivots += 1;
+        }
+
+        {
+            // Keep track of row permutations.
+            let row = i_col;
+            let orig_row = new2orig_row[row];
+            let pivot_row = orig2new_row[pivot_orig_r];
+            new2orig_row.swap(row, pivot_row);
+            orig2new_row.swap(orig_row, pivot_orig_r);
+        }
+
+        // Gather the values of x into lower and upper matrices.
+
+        let x_max_abs = match drop_tolerance {
+            Some(_) => max_abs((scratch.rhs.nonzero.iter()).map(|&r| &scratch.rhs.values[r])),
+            None => f0_ab!(),
+        };
+        for &orig_r in &scratch.rhs.nonzero {
+            let val = &scratch.rhs.values[orig_r];
+
+            if val.is_zero() {
+                continue;
+            }
+
+            let new_r = orig2new_row[orig_r];
+            if new_r != i_col && is_dropped(drop_tolerance.as_ref(), val, &x_max_abs) {
+                *dropped_entries += 1;
+            } else if new_r < i_col {
+                upper.push(new_r, val.clone());
+            } else if new_r == i_col {
+                upper_diag.push(pivot_val.clone());
+            } else {
+                lower.push(orig_r, val / pivot_val);
+            }
+        }
+        if let Some(symbolic) = &mut symbolic {
+            symbolic.skipped.push(
+                (scratch.rhs.nonzero.iter().copied())
+                    .filter(|&orig_r| {
+                        let val = &scratch.rhs.values[orig_r];
+                        orig2new_row[orig_r] != i_col
+                            && (val.is_zero()
+                                || is_dropped(drop_tolerance.as_ref(), val, &x_max_abs))
+                    })
+                    .collect(),
+            );
+        }
+
+        upper.seal_column();
+        lower.seal_column();
+    }
+
+    // permute rows of lower to "new" indices.
+    for i_col in 0..lower.cols() {
+        for r in lower.col_rows_mut(i_col) {
+            *r = orig2new_row[*r];
+        }
+    }
+
+    let lower_nnz = lower.nnz();
+    let upper_nnz = upper.nnz();
+    trace!(
+        "lu_factorise: done, lower nnz: {} (excess: {}), upper nnz: {} (excess: {}), additional fill-in: {}",
+        lower_nnz + size,
+        lower_nnz,
+        upper_nnz + size,
+        upper_nnz,
+        (lower_nnz + upper_nnz + size).saturating_sub(factorised_nnz),
+    );
+
+    let res = LUFactors {
+        lower: TriangleMat {
+            nondiag: lower,
+            diag: None,
+        },
+        upper: TriangleMat {
+            nondiag: upper,
+            diag: Some(upper_diag),
+        },
+        row_perm: Some(Perm {
+            orig2new: orig2new_row,
+            new2orig: new2orig_row,
+        }),
+        col_perm: Some(col_perm),
+        dense_cols,
+    };
+
+    Ok(res)
+}
+
+/// The numbers of nonzeros of the LU factors of a matrix, computed by [`estimate_fill`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FillEstimate {
+    /// Number of nonzeros in L, without its diagonal of ones.
+    pub lower_nnz: usize,
+    /// Number of nonzeros in U, including its diagonal.
+    pub upper_nnz: usize,
+}
+
+impl FillEstimate {
+    /// Number of nonzeros in the L and U factors, like [`LUFactors::nnz`].
+    pub fn nnz(&self) -> usize {
+        self.lower_nnz + self.upper_nnz
+    }
+}
+
+/// The numbers of nonzeros of the LU factors that [`lu_factorise`] computes for the square
+/// matrix `pattern` if it eliminates the columns in the order `perm` with a stability
+/// coefficient of 0, from a symbolic elimination that only looks at the pattern: it finds the
+/// pattern of each column of the factors like the factorisation, and chooses the pivot rows
+/// like it, by their numbers of nonzeros. The estimate is exact unless entries of the factors
+/// cancel out to zero, which the factorisation leaves out, and costs about as much as a
+/// factorisation without any arithmetic. A column without a pivot left, which makes the
+/// factorisation fail, counts as a unit column in a row that no column pivoted on yet.
+pub fn estimate_fill(pattern: &SparseMat, perm: &Perm) -> FillEstimate {
+    estimate_fill_cols(pattern.cols(), |c| pattern.col_rows(c), &perm.new2orig)
+}
+
+/// [`estimate_fill`] for the square matrix whose columns `get_col` returns, eliminated in the
+/// order `new2orig`.
+pub(crate) fn estimate_fill_cols<'a>(
+    size: usize,
+    get_col: impl Fn(usize) -> &'a [usize],
+    new2orig: &[usize],
+) -> FillEstimate {
+    let mut row_counts = vec![0; size];
+    for c in 0..size {
+        for &r in get_col(c) {
+            row_counts[r] += 1;
+        }
+    }
+
+    // The same steps as factorise_gilbert_peierls, without the values. Until the end, the
+    // rows of lower are original rows.
+    let one = f1_ab!();
+    let mut rhs = ScatteredVec::empty(size);
+    let mut mark_nonzero = MarkNonzero::with_capacity(size);
+    let mut lower: Vec<Vec<usize>> = Vec::with_capacity(size);
+    let mut new2orig_row = (0..size).collect::<Vec<_>>();
+    let mut orig2new_row = (0..size).collect::<Vec<_>>();
+    let mut upper_nnz = 0;
+    for (i_col, &c) in new2orig.iter().enumerate() {
+        rhs.set(get_col(c).iter().map(|&r| (r, &one)));
+        mark_nonzero.run(
+            &mut rhs,
+            |new_i| lower[new_i].as_slice(),
+            |new_i| new_i < i_col,
+            |orig_r| orig2new_row[orig_r],
+        );
+
+        let pivot_orig_r = (rhs.nonzero.iter().copied())
+            .filter(|&orig_r| orig2new_row[orig_r] >= i_col)
+            .min_by_key(|&orig_r| row_counts[orig_r])
+            .unwrap_or(new2orig_row[i_col]);
+        let orig_row = new2orig_row[i_col];
+        let pivot_row = orig2new_row[pivot_orig_r];
+        new2orig_row.swap(i_col, pivot_row);
+        orig2new_row.swap(orig_row, pivot_orig_r);
+
+        let mut lower_col = vec![];
+        for &orig_r in &rhs.nonzero {
+            if orig2new_row[orig_r] > i_col {
+                lower_col.push(orig_r);
+            } else {
+                upper_nnz += 1;
+            }
+        }
+        if orig2new_row[pivot_orig_r] == i_col && !rhs.is_nonzero[pivot_orig_r] {
+            // The unit column of a column without a pivot.
+            upper_nnz += 1;
+        }
+        lower.push(lower_col);
+    }
+
+    FillEstimate {
+        lower_nnz: lower.iter().map(|col| col.len()).sum(),
+        upper_nnz,
+    }
+}
+
+/// Number of columns with the fewest nonzeros that [`lu_factorise_markowitz`] examines for the
+/// pivot with the lowest Markowitz cost.
+const MARKOWITZ_SEARCH_COLS: usize = 4;
+
+/// Factorise the matrix with columns `get_col` like [`lu_factorise`], but choose each pivot in
+/// the remaining submatrix with the Markowitz criterion:
+///
+/// Markowitz, H. M. (1957). The elimination form of the inverse and its application to linear
+/// programming. Management Science, 3(3), 255-269.
+///
+/// Among the entries that pass the threshold test, the pivot minimises (r - 1)(c - 1), where r
+/// and c are the numbers of nonzeros of its row and column in the remaining submatrix; this
+/// bounds the fill-in of the elimination step. The counts are kept up to date after every
+/// step, and only the [`MARKOWITZ_SEARCH_COLS`] columns with the fewest nonzeros are searched.
+///
+/// With approximate arithmetic, an entry passes the threshold test if its absolute value is at
+/// least `stability_coeff` times the largest absolute value in its column. With exact
+/// arithmetic, rounding is no concern but large numerators and denominators are: an entry
+/// passes if `stability_coeff` times its size in bits (see [`AbnormalFraction::complexity`]) is
+/// at most the smallest size in its column. Dense columns are handled as in [`lu_factorise`].
+///
+/// If `prefer_small_pivots` is set and the arithmetic is exact, ties between pivots of the
+/// lowest cost go to 1 and -1, and then to the pivot with the fewest bits. Every division by a
+/// pivot puts its numerator into the denominators of the factors, so on a totally unimodular
+/// matrix this keeps all entries of the factors integer.
+pub fn lu_factorise_markowitz<'a>(
+    size: usize,
+    get_col: impl Fn(usize) -> (&'a [usize], &'a [AbnormalFraction]),
+    stability_coeff: AbnormalFraction,
+    dense_col_threshold: Option<usize>,
+    prefer_small_pivots: bool,
+    cancel: Option<&AtomicBool>,
+) -> Result<LUFactors, Error> {
+    lu_factorise_markowitz_with_scratch(
+        size,
+        get_col,
+        stability_coeff,
+        dense_col_threshold,
+        prefer_small_pivots,
+        &mut LuScratch::with_capacity(0),
+        cancel,
+    )
+}
+
+/// Like [`lu_factorise_markowitz`], but with the factors in the storage of the factors last
+/// handed to [`LuScratch::recycle`]. The remaining submatrix is still allocated anew.
+pub fn lu_factorise_markowitz_with_scratch<'a>(
+    size: usize,
+    get_col: impl Fn(usize) -> (&'a [usize], &'a [AbnormalFraction]),
+    stability_coeff: AbnormalFraction,
+    dense_col_threshold: Option<usize>,
+    prefer_small_pivots: bool,
+    scratch: &mut LuScratch,
+    cancel: Option<&AtomicBool>,
+) -> Result<LUFactors, Error> {
+    let is_exact = f1_ab!().is_exact();
+    let prefer_small_pivots = prefer_small_pivots && is_exact;
+    let LUFactors {
+        lower: TriangleMat {
+            nondiag: mut lower, ..
+        },
+        upper: TriangleMat {
+            nondiag: mut upper,
+            diag: upper_diag,
+        },
+        row_perm,
+        col_perm,
+        mut dense_cols,
+    } = scratch.take_spare();
+    let (mut upper_diag, row_perm, col_perm) =
+        (upper_diag.unwrap(), row_perm.unwrap(), col_perm.unwrap());
+    let Perm {
+        orig2new: mut orig2new_row,
+        new2orig: mut new2orig_row,
+    } = row_perm;
+    let Perm {
+        orig2new: mut orig2new_col,
+        new2orig: mut new2orig_col,
+    } = col_perm;
+    dense_cols.clear();
+    upper_diag.clear();
+    new2orig_row.clear();
+    new2orig_col.clear();
+
+    // The remaining submatrix, by columns with values and by rows with the pattern only.
+    let mut cols = vec![vec![]; size];
+    let mut rows = vec![vec![]; size];
+    let queue = &mut scratch.cols_queue;
+    queue.clear_and_resize(size);
+    scratch.rejected_pivots = 0;
+    scratch.unit_pivots = 0;
+    scratch.dropped_entries = 0;
+    scratch.reused_cols = 0;
+    for (c, col) in cols.iter_mut().enumerate() {
+        let (col_rows, col_data) = get_col(c);
+        if dense_col_threshold.is_some_and(|threshold| col_rows.len() > threshold) {
+            dense_cols.push(c);
+            continue;
+        }
+        for (&r, val) in col_rows.iter().zip(col_data) {
+            if !val.is_zero() {
+                col.push((r, val.clone()));
+                rows[r].push(c);
+            }
+        }
+        if col.is_empty() {
+            return Err(Error::SingularMatrix {
+                step: 0,
+                row: None,
+                col: Some(c),
+            });
+        }
+        queue.add(c, col.len() - 1);
+    }
+    if !dense_cols.is_empty() {
+        trace!(
+            "lu_factorise_markowitz: {} dense columns replaced by unit columns",
+            dense_cols.len()
+        );
+    }
+
+    let mut lower_cols = Vec::with_capacity(size);
+    let mut upper_rows = Vec::with_capacity(size);
+    let mut is_pivot_row = vec![false; size];
+    let mut row_pos = vec![None; size];
+
+    for _ in 0..size - dense_cols.len() {
+        if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            return Err(Error::Interrupted);
+        }
+
+        // Search the columns with the fewest nonzeros for the pivot with the lowest cost, and
+        // among those, the smallest pivot if preferred.
+        let mut best: Option<((usize, u64), usize, usize)> = None;
+        let mut examined = Vec::with_capacity(MARKOWITZ_SEARCH_COLS);
+        while examined.len() < MARKOWITZ_SEARCH_COLS {
+            let Some(c) = queue.pop_min() else {
+                break;
+            };
+            examined.push(c);
+            let col_cost = cols[c].len() - 1;
+            let is_eligible = pivot_threshold_test(&cols[c], &stability_coeff, is_exact);
+            scratch.rejected_pivots += is_eligible.iter().filter(|&&eligible| !eligible).count();
+            for (idx, (r, val)) in cols[c].iter().enumerate() {
+                let cost = (rows[*r].len() - 1) * col_cost;
+                let bits = prefer_small_pivots.then(|| pivot_size(val));
+                let key = (cost, bits.unwrap_or(0));
+                if is_eligible[idx] && best.is_none_or(|(best_key, _, _)| key < best_key) {
+                    best = Some((key, c, idx));
+                }
+            }
+            if best.is_some_and(|(key, _, _)| key == (0, 0)) {
+                break;
+            }
+        }
+        let (_, pivot_c, pivot_idx) = best.unwrap();
+        for &c in examined.iter().filter(|&&c| c != pivot_c) {
+            queue.add(c, cols[c].len() - 1);
+        }
+
+        // Take the pivot column and row out of the submatrix.
+        let mut col = std::mem::take(&mut cols[pivot_c]);
+        let (pivot_r, pivot_val) = col.swap_remove(pivot_idx);
+        if is_unit(&pivot_val) {
+            scratch.unit_pivots += 1;
+        }
+        for (r, _) in &col {
+            let pos = rows[*r].iter().position(|&c| c == pivot_c).unwrap();
+            rows[*r].swap_remove(pos);
+        }
+        let pos = rows[pivot_r].iter().position(|&c| c == pivot_c).unwrap();
+        rows[pivot_r].swap_remove(pos);
+        let lower_col = (col.into_iter())
+            .map(|(r, val)| (r, &val / &pivot_val))
+            .collect::<Vec<_>>();
+        let mut upper_row = Vec::with_capacity(rows[pivot_r].len());
+        for c in std::mem::take(&mut rows[pivot_r]) {
+            let pos = cols[c].iter().position(|(r, _)| *r == pivot_r).unwrap();
+            let (_, val) = cols[c].swap_remove(pos);
+            upper_row.push((c, val));
+        }
+
+        // Update the submatrix: subtract the outer product of the L column and the U row.
+        for (c, upper_val) in &upper_row {
+            queue.remove(*c, cols[*c].len());
+            for (idx, (r, _)) in cols[*c].iter().enumerate() {
+                row_pos[*r] = Some(idx);
+            }
+            for (r, lower_val) in &lower_col {
+                let diff = lower_val * upper_val;
+                if let Some(idx) = row_pos[*r] {
+                    cols[*c][idx].1 -= diff;
+                } else {
+                    cols[*c].push((*r, -diff));
+                    rows[*r].push(*c);
+                }
+            }
+            for (r, _) in &cols[*c] {
+                row_pos[*r] = None;
+            }
+
+            // Drop entries that cancelled out, and those below the drop tolerance.
+            let col_max_abs = match &scratch.drop_tolerance {
+                Some(_) => max_abs(cols[*c].iter().map(|(_, val)| val)),
+                None => f0_ab!(),
+            };
+            let mut idx = 0;
+            while idx < cols[*c].len() {
+                let val = &cols[*c][idx].1;
+                let is_small = is_dropped(scratch.drop_tolerance.as_ref(), val, &col_max_abs);
+                if is_small && !val.is_zero() {
+                    scratch.dropped_entries += 1;
+                }
+                if is_small || val.is_zero() {
+                    let (r, _) = cols[*c].swap_remove(idx);
+                    let pos = rows[r].iter().position(|&col| col == *c).unwrap();
+                    rows[r].swap_remove(pos);
+                } else {
+                    idx += 1;
+                }
+            }
+            if cols[*c].is_empty() {
+                return Err(Error::SingularMatrix {
+                    step: new2orig_col.len(),
+                    row: (0..size).find(|&r| !is_pivot_row[r] && r != pivot_r),
+                    col: Some(*c),
+                });
+            }
+            queue.add(*c, cols[*c].len() - 1);
+        }
+
+        is_pivot_row[pivot_r] = true;
+        new2orig_row.push(pivot_r);
+        new2orig_col.push(pivot_c);
+        upper_diag.push(pivot_val);
+        lower_cols.push(lower_col);
+        upper_rows.push(upper_row);
+    }
+
+    // A unit column for each dense column, in a row that no column pivoted on.
+    let free_rows = (0..size).filter(|&r| !is_pivot_row[r]).collect::<Vec<_>>();
+    for (&c, r) in dense_cols.iter().zip(free_rows) {
+        new2orig_row.push(r);
+        new2orig_col.push(c);
+        upper_diag.push(f1_ab!());
+        lower_cols.push(vec![]);
+        upper_rows.push(vec![]);
+    }
+
+    orig2new_row.clear();
+    orig2new_row.resize(size, 0);
+    for (new_r, &orig_r) in new2orig_row.iter().enumerate() {
+        orig2new_row[orig_r] = new_r;
+    }
+    orig2new_col.clear();
+    orig2new_col.resize(size, 0);
+    for (new_c, &orig_c) in new2orig_col.iter().enumerate() {
+        orig2new_col[orig_c] = new_c;
+    }
+
+    lower.clear_and_resize(size);
+    for lower_col in lower_cols {
+        for (r, val) in lower_col {
+            lower.push(orig2new_row[r], val);
+        }
+        lower.seal_column();
+    }
+    let mut upper_cols = vec![vec![]; size];
+    for (new_r, upper_row) in upper_rows.into_iter().enumerate() {
+        for (c, val) in upper_row {
+            upper_cols[orig2new_col[c]].push((new_r, val));
+        }
+    }
+    upper.clear_and_resize(size);
+    for upper_col in upper_cols {
+        upper.append_col(upper_col);
+    }
+
+    trace!(
+        "lu_factorise_markowitz: done, lower nnz: {}, upper nnz: {}",
+        lower.nnz() + size,
+        upper.nnz() + size,
+    );
+
+    Ok(LUFactors {
+        lower: TriangleMat {
+            nondiag: lower,
+            diag: None,
+        },
+        upper: TriangleMat {
+            nondiag: upper,
+            diag: Some(upper_diag),
+        },
+        row_perm: Some(Perm {
+            orig2new: orig2new_row,
+            new2orig: new2orig_row,
+        }),
+        col_perm: Some(Perm {
+            orig2new: orig2new_col,
+            new2orig: new2orig_col,
+        }),
+        dense_cols,
+    })
+}
+
+/// The largest absolute value of `vals`, or 0 if there are none.
+fn max_abs<'a>(vals: impl Iterator<Item = &'a AbnormalFraction>) -> AbnormalFraction {
+    vals.map(|val| val.clone().abs())
+        .fold(f0_ab!(), |max, abs| if abs > max { abs } else { max })
+}
+
+/// Whether `drop_tolerance` drops `val` from a column whose largest absolute value is
+/// `max_abs`. The largest entry of a column is never dropped, so no column becomes empty.
+fn is_dropped(
+    drop_tolerance: Option<&DropTolerance>,
+    val: &AbnormalFraction,
+    max_abs: &AbnormalFraction,
+) -> bool {
+    drop_tolerance.is_some_and(|drop_tolerance| {
+        let abs = val.clone().abs();
+        &abs < max_abs && abs <= drop_tolerance.threshold(max_abs)
+    })
+}
+
+fn is_unit(val: &AbnormalFraction) -> bool {
+    val.clone().abs() == f1_ab!()
+}
+
+/// How much a pivot is avoided by [`lu_factorise_markowitz`] when it prefers small pivots: 0
+/// for 1 and -1, otherwise the size in bits.
+fn pivot_size(val: &AbnormalFraction) -> u64 {
+    if is_unit(val) { 0 } else { val.complexity() }
+}
+
+/// Which entries of a column of the remaining submatrix may be pivots, see
+/// [`lu_factorise_markowitz`].
+fn pivot_threshold_test(
+    col: &[(usize, AbnormalFraction)],
+    stability_coeff: &AbnormalFraction,
+    is_exact: bool,
+) -> Vec<bool> {
+    if is_exact {
+        let min_bits = col
+            .iter()
+            .map(|(_, val)| val.complexity())
+            .min()
+            .unwrap_or(0);
+        col.iter()
+            .map(|(_, val)| {
+                stability_coeff * &f_ab!(val.complexity() as usize) <= f_ab!(min_bits as usize)
+            })
+            .collect()
+    } else {
+        let max_abs = (col.iter())
+            .map(|(_, val)| val.clone().abs())
+            .fold(f0_ab!(), |max, abs| if abs > max { abs } else { max });
+        col.iter()
+            .map(|(_, val)| val.clone().abs() >= stability_coeff * &max_abs)
+            .collect()
+    }
+}
+
+/// The rank of a matrix and its rows that depend linearly on the rows before them, see
+/// [`analyze_rank`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RankInfo {
+    pub rank: usize,
+    /// The rows that are linear combinations of the rows before them (zero rows included), in
+    /// increasing order. Without them, the rows are linearly independent.
+    pub dependent_rows: Vec<usize>,
+}
+
+/// Determine the rank of `mat` and its dependent rows.
+///
+/// Unlike [`lu_factorise`], which stops with [`Error::SingularMatrix`] at the first column it
+/// finds no pivot for, the elimination continues past the rows that reduce to zero and
+/// records them. The earliest rows are kept: of two equal rows, the second is dependent.
+#[cfg_attr(not(test), allow(dead_code))] // Presolve eliminates rows itself.
+pub(crate) fn analyze_rank(mat: &SparseMat) -> RankInfo {
+    let rows = mat.transpose();
+    let mut eliminator = RowEliminator::new(mat.cols(), mat.cols());
+    let dependent_rows = (0..rows.cols())
+        .filter(|&r| !eliminator.add_row(rows.col_iter(r)))
+        .collect();
+    RankInfo {
+        rank: eliminator.rank(),
+        dependent_rows,
+    }
+}
+
+/// Gaussian elimination of rows that are added one at a time, to find the rows that depend on
+/// the rows before them.
+///
+/// Columns from `num_pivot_cols` on take part in the elimination but are never pivots, so a
+/// row that depends on the rows before it may have a residual there. With an appended
+/// right-hand side, a nonzero residual means that the equations are inconsistent.
+pub(crate) struct RowEliminator {
+    num_pivot_cols: usize,
+    /// The independent rows, each reduced by the ones before it and scaled to a one in its
+    /// pivot column. A row has no entries in the pivot columns of the rows before it.
+    pivot_rows: Vec<Vec<(usize, AbnormalFraction)>>,
+    pivot_cols: Vec<usize>,
+    col2pivot: Vec<Option<usize>>,
+    /// The last added row, after its reduction.
+    row: ScatteredVec,
+    /// Entries of a reduced row with an absolute value up to this are zero. Only used in
+    /// approximate arithmetic, relative to the largest entry of the row before its reduction.
+    zero_tolerance: Option<AbnormalFraction>,
+    row_tolerance: AbnormalFraction,
+}
+
+impl RowEliminator {
+    pub(crate) fn new(num_cols: usize, num_pivot_cols: usize) -> RowEliminator {
+        RowEliminator {
+            num_pivot_cols,
+            pivot_rows: vec![],
+            pivot_cols: vec![],
+            col2pivot: vec![None; num_cols],
+            row: ScatteredVec::empty(num_cols),
+            zero_tolerance: (!f1_ab!().is_exact()).then(|| f_ab!(1, 1_000_000_000)),
+            row_tolerance: f0_ab!(),
+        }
+    }
+
+    /// The number of independent rows that were added.
+    pub(crate) fn rank(&self) -> usize {
+        self.pivot_rows.len()
+    }
+
+    /// Reduce `row` by the independent rows added before it. Returns whether it is
+    /// independent of them, in which case it becomes one of them.
+    pub(crate) fn add_row<'a>(
+        &mut self,
+        row: impl IntoIterator<Item = (usize, &'a AbnormalFraction)>,
+    ) -> bool {
+        self.row.set(row);
+        if let Some(zero_tolerance) = &self.zero_tolerance {
+            let max_abs = (self.row.iter())
+                .map(|(_, val)| val.clone().abs())
+                .fold(f0_ab!(), |max, abs| if abs > max { abs } else { max });
+            self.row_tolerance = zero_tolerance * &max_abs;
+        }
+
+        // Eliminate the pivot columns in the order of the pivot rows: subtracting a pivot row
+        // only creates entries in the pivot columns of later pivot rows.
+        let mut queue = BinaryHeap::new();
+        for &c in self.row.indices() {
+            if let Some(k) = self.col2pivot[c] {
+                queue.push(Reverse(k));
+            }
+        }
+        let mut last = None;
+        while let Some(Reverse(k)) = queue.pop() {
+            if last == Some(k) {
+                continue;
+            }
+            last = Some(k);
+            let factor = self.row.get(self.pivot_cols[k]).clone();
+            if factor.is_zero() {
+                continue;
+            }
+            for (c, val) in &self.pivot_rows[k] {
+                let entry = self.row.get_mut(*c);
+                let was_zero = entry.is_zero();
+                *entry -= &factor * val;
+                if let Some(j) = self.col2pivot[*c].filter(|_| was_zero) {
+                    queue.push(Reverse(j));
+                }
+            }
+            *self.row.get_mut(self.pivot_cols[k]) = f0_ab!();
+        }
+
+        // Choose the pivot: the largest entry in approximate arithmetic, the one with the
+        // fewest bits in exact arithmetic.
+        let mut pivot: Option<(usize, &AbnormalFraction)> = None;
+        for (c, val) in self.row.iter() {
+            if c >= self.num_pivot_cols || self.is_zero(val) {
+                continue;
+            }
+            let is_better = match pivot {
+                None => true,
+                Some((best_c, best_val)) => {
+                    if self.zero_tolerance.is_some() {
+                        let (abs, best_abs) = (val.clone().abs(), best_val.clone().abs());
+                        abs > best_abs || (abs == best_abs && c < best_c)
+                    } else {
+                        (val.complexity(), c) < (best_val.complexity(), best_c)
+                    }
+                }
+            };
+            if is_better {
+                pivot = Some((c, val));
+            }
+        }
+        let Some((pivot_c, pivot_val)) = pivot else {
+            return false;
+        };
+
+        let pivot_val = pivot_val.clone();
+        let pivot_row = (self.row.iter())
+            .filter(|(c, val)| !val.is_zero() && self.col2pivot[*c].is_none())
+            .map(|(c, val)| (c, val / &pivot_val))
+            .collect();
+        self.col2pivot[pivot_c] = Some(self.pivot_rows.len());
+        self.pivot_cols.push(pivot_c);
+        self.pivot_rows.push(pivot_row);
+        true
+    }
+
+    /// Whether the last added row, if it was dependent, has no residual outside the pivot
+    /// columns.
+    pub(crate) fn residual_is_zero(&self) -> bool {
+        (self.row.iter()).all(|(c, val)| c < self.num_pivot_cols || self.is_zero(val))
+    }
+
+    fn is_zero(&self, val: &AbnormalFraction) -> bool {
+        if self.zero_tolerance.is_some() {
+            val.clone().abs() <= self.row_tolerance
+        } else {
+            val.is_zero()
+        }
+    }
+}
+
+/// LU factors of a matrix whose columns are replaced one at a time, for the basis of the
+/// simplex method. A replaced column is a Forrest–Tomlin update:
+///
+/// Forrest, J. J. H., & Tomlin, J. A. (1972).
+/// Updated triangular factors of the basis to maintain sparsity in the product form simplex
+/// method. Mathematical Programming, 2(1), 263-278.
+///
+/// L and the permutations stay as factorised. The new column, solved with L, replaces a
+/// column of U; its row is then moved to the end of the triangular order and its entries
+/// right of the diagonal are eliminated with the rows below it. The multipliers of the
+/// elimination form a row eta matrix, which FTRAN applies between L and U. Unlike the product
+/// form, an update only grows U by the entries of the new column, and keeps the triangular
+/// solves sparse.
+#[derive(Clone, Debug)]
+pub struct UpdatableLU {
+    lower: TriangleMat,
+    lower_transp: TriangleMat,
+    row_perm: Perm,
+    col_perm: Perm,
+    upper: UpdatableUpper,
+    /// Multipliers of the eliminations of the updates, one column per update.
+    row_etas: SparseMat,
+    /// The row that each update eliminated.
+    eta_rows: Vec<usize>,
+    dense_cols: Vec<usize>,
+    /// Workspace for the row that an update eliminates.
+    elim_row: ScatteredVec,
+    drop_tolerance: Option<DropTolerance>,
+    dropped_entries: usize,
+    update: BasisUpdate,
+    /// With [`BasisUpdate::Eta`]: the solves of the entering columns without their pivots,
+    /// one column per update.
+    col_etas: SparseMat,
+    /// The column that each eta column replaced.
+    eta_cols: Vec<usize>,
+    /// The pivot of each eta column.
+    eta_pivots: Vec<AbnormalFraction>,
+}
+
+/// The U factor of an [`UpdatableLU`], stored both by columns and by rows. It is upper
+/// triangular in the order of `order` rather than in index order; diagonal elements are stored
+/// separately.
+#[derive(Clone, Debug)]
+struct UpdatableUpper {
+    col_rows: Vec<Vec<usize>>,
+    col_vals: Vec<Vec<AbnormalFraction>>,
+    row_cols: Vec<Vec<usize>>,
+    row_vals: Vec<Vec<AbnormalFraction>>,
+    diag: Vec<AbnormalFraction>,
+    /// The indices in triangular order.
+    order: Vec<usize>,
+    /// The position of each index in `order`.
+    position: Vec<usize>,
+    nnz: usize,
+}
+
+impl UpdatableUpper {
+    fn new(upper: &TriangleMat) -> UpdatableUpper {
+        let size = upper.cols();
+        let mut res = UpdatableUpper {
+            col_rows: vec![vec![]; size],
+            col_vals: vec![vec![]; size],
+            row_cols: vec![vec![]; size],
+            row_vals: vec![vec![]; size],
+            diag: upper.diag.clone().unwrap_or_else(|| vec![f1_ab!(); size]),
+            order: (0..size).collect(),
+            position: (0..size).collect(),
+            nnz: 0,
+        };
+        for c in 0..size {
+            for (r, val) in upper.nondiag.col_iter(c) {
+                res.insert(r, c, val.clone());
+            }
+        }
+        res
+    }
+
+    fn insert(&mut self, row: usize, col: usize, val: AbnormalFraction) {
+        self.col_rows[col].push(row);
+        self.col_vals[col].push(val.clone());
+        self.row_cols[row].push(col);
+        self.row_vals[row].push(val);
+        self.nnz += 1;
+    }
+
+    /// Remove the off-diagonal entries of column `col`.
+    fn clear_col(&mut self, col: usize) {
+        for &row in &self.col_rows[col] {
+            let idx = self.row_cols[row].iter().position(|&c| c == col).unwrap();
+            self.row_cols[row].swap_remove(idx);
+            self.row_vals[row].swap_remove(idx);
+        }
+        self.nnz -= self.col_rows[col].len();
+        self.col_rows[col].clear();
+        self.col_vals[col].clear();
+    }
+
+    /// Remove the off-diagonal entries of row `row`.
+    fn clear_row(&mut self, row: usize) {
+        for &col in &self.row_cols[row] {
+            let idx = self.col_rows[col].iter().position(|&r| r == row).unwrap();
+            self.col_rows[col].swap_remove(idx);
+            self.col_vals[col].swap_remove(idx);
+        }
+        self.nnz -= self.row_cols[row].len();
+        self.row_cols[row].clear();
+        self.row_vals[row].clear();
+    }
+
+    /// Move `idx` to the end of the triangular order.
+    fn move_to_end(&mut self, idx: usize) {
+        let start = self.position[idx];
+        self.order.remove(start);
+        self.order.push(idx);
+        for (pos, &i) in self.order.iter().enumerate().skip(start) {
+            self.position[i] = pos;
+        }
+    }
+
+    fn solve_sparse(&self, scratch: &mut ScratchSpace) {
+        let ScratchSpace {
+            rhs,
+            mark_nonzero,
+            visited_entries,
+            ..
+        } = scratch;
+        let is_hyper_sparse = rhs.nonzero.len() * HYPER_SPARSE_RATIO < rhs.len();
+        if is_hyper_sparse {
+            mark_nonzero.run(rhs, |col| &self.col_rows[col], |_| true, |i| i);
+        }
+        let mut process_col = |col: usize| {
+            if rhs.values[col].is_zero() {
+                return;
+            }
+            *visited_entries += 1 + self.col_rows[col].len();
+            process_upper_line(
+                &self.col_rows[col],
+                &self.col_vals[col],
+                &self.diag[col],
+                col,
+                &mut rhs.values,
+            );
+        };
+        if is_hyper_sparse {
+            for &col in mark_nonzero.visited.iter().rev() {
+                process_col(col);
+            }
+        } else {
+            self.order.iter().rev().for_each(|&col| process_col(col));
+            mark_nonzero_values(rhs);
+        }
+    }
+
+    fn solve_transp_sparse(&self, scratch: &mut ScratchSpace) {
+        let ScratchSpace {
+            rhs,
+            mark_nonzero,
+            visited_entries,
+            ..
+        } = scratch;
+        let is_hyper_sparse = rhs.nonzero.len() * HYPER_SPARSE_RATIO < rhs.len();
+        if is_hyper_sparse {
+            mark_nonzero.run(rhs, |row| &self.row_cols[row], |_| true, |i| i);
+        }
+        let mut process_row = |row: usize| {
+            if rhs.values[row].is_zero() {
+                return;
+            }
+            *visited_entries += 1 + self.row_cols[row].len();
+            process_upper_line(
+                &self.row_cols[row],
+                &self.row_vals[row],
+                &self.diag[row],
+                row,
+                &mut rhs.values,
+            );
+        };
+        if is_hyper_sparse {
+            for &row in mark_nonzero.visited.iter().rev() {
+                process_row(row);
+            }
+        } else {
+            self.order.iter().for_each(|&row| process_row(row));
+            mark_nonzero_values(rhs);
+        }
+    }
+
+    fn solve_dense(&self, rhs: &mut [AbnormalFraction]) {
+        for &col in self.order.iter().rev() {
+            process_upper_line(
+                &self.col_rows[col],
+                &self.col_vals[col],
+                &self.diag[col],
+                col,
+                rhs,
+            );
+        }
+    }
+
+    fn solve_transp_dense(&self, rhs: &mut [AbnormalFraction]) {
+        for &row in &self.order {
+            process_upper_line(
+                &self.row_cols[row],
+                &self.row_vals[row],
+                &self.diag[row],
+                row,
+                rhs,
+            );
+        }
+    }
+}
+
+/// Divide `rhs[idx]` by the diagonal element and subtract its multiples from the entries at
+/// `indices`, which depend on it.
+fn process_upper_line(
+    indices: &[usize],
+    vals: &[AbnormalFraction],
+    diag: &AbnormalFraction,
+    idx: usize,
+    rhs: &mut [AbnormalFraction],
+) {
+    let x_val = &rhs[idx] / diag;
+    for (&i, val) in indices.iter().zip(vals) {
+        rhs[i] -= &x_val * val;
+    }
+    rhs[idx] = x_val;
+}
+
+impl UpdatableLU {
+    pub fn new(factors: LUFactors) -> UpdatableLU {
+        let size = factors.lower.cols();
+        let identity = || Perm {
+            orig2new: (0..size).collect(),
+            new2orig: (0..size).collect(),
+        };
+        UpdatableLU {
+            lower_transp: factors.lower.transpose(),
+            upper: UpdatableUpper::new(&factors.upper),
+            lower: factors.lower,
+            row_perm: factors.row_perm.unwrap_or_else(identity),
+            col_perm: factors.col_perm.unwrap_or_else(identity),
+            row_etas: SparseMat::new(size),
+            eta_rows: vec![],
+            dense_cols: factors.dense_cols,
+            elim_row: ScatteredVec::empty(size),
+            drop_tolerance: None,
+            dropped_entries: 0,
+            update: BasisUpdate::default(),
+            col_etas: SparseMat::new(size),
+            eta_cols: vec![],
+            eta_pivots: vec![],
+        }
+    }
+
+    /// Set how [`UpdatableLU::replace_col`] updates the factors; only before the first
+    /// update.
+    pub fn set_update(&mut self, update: BasisUpdate) {
+        assert_eq!(self.num_updates(), 0);
+        self.update = update;
+    }
+
+    /// Leave entries of the updated columns of U below `drop_tolerance` out, like
+    /// [`LuScratch::set_drop_tolerance`] does for factorisations.
+    pub fn set_drop_tolerance(&mut self, drop_tolerance: Option<DropTolerance>) {
+        self.drop_tolerance = drop_tolerance.filter(|_| !f1_ab!().is_exact());
+    }
+
+    /// Number of entries that the drop tolerance left out of the updates.
+    pub fn dropped_entries(&self) -> usize {
+        self.dropped_entries
+    }
+
+    /// Nonzeros of L and U, including the diagonal of U, and of the row and column eta
+    /// matrices.
+    pub fn nnz(&self) -> usize {
+        self.lower.nondiag.nnz()
+            + self.upper.nnz
+            + self.lower.cols()
+            + self.row_etas.nnz()
+            + self.col_etas.nnz()
+            + self.eta_pivots.len()
+    }
+
+    /// The columns that were left out of the factorisation because they are dense, see
+    /// [`lu_factorise`].
+    pub fn dense_cols(&self) -> &[usize] {
+        &self.dense_cols
+    }
+
+    /// Number of columns replaced since the factorisation.
+    pub fn num_updates(&self) -> usize {
+        self.eta_rows.len() + self.eta_cols.len()
+    }
+
+    /// The coefficients that the last update added to U and to the row eta matrices, or to the
+    /// column eta matrices.
+    pub fn last_update_coeffs(&self) -> impl Iterator<Item = &AbnormalFraction> {
+        let forrest_tomlin =
+            (self.eta_rows.len().checked_sub(1))
+                .into_iter()
+                .flat_map(move |last| {
+                    let idx = self.eta_rows[last];
+                    (self.upper.col_vals[idx].iter())
+                        .chain([&self.upper.diag[idx]])
+                        .chain(self.row_etas.col_data(last))
+                });
+        let eta = (self.eta_cols.len().checked_sub(1))
+            .into_iter()
+            .flat_map(move |last| {
+                (self.col_etas.col_data(last).iter()).chain([&self.eta_pivots[last]])
+            });
+        forrest_tomlin.chain(eta)
+    }
+
+    /// Double the pivot of the last update, to test that stale factors are detected.
+    #[cfg(test)]
+    pub(crate) fn corrupt_last_update(&mut self) {
+        if let Some(pivot) = self.eta_pivots.last_mut() {
+            *pivot = &*pivot * &f_ab!(2);
+            return;
+        }
+        let idx = *self.eta_rows.last().unwrap();
+        self.upper.diag[idx] = &self.upper.diag[idx] * &f_ab!(2);
+    }
+
+    /// Apply the inverses of the column eta matrices to the solution of the factors.
+    fn apply_col_etas(&self, rhs: &mut ScatteredVec) {
+        for (idx, &col) in self.eta_cols.iter().enumerate() {
+            if rhs.get(col).is_zero() {
+                continue;
+            }
+            let x = rhs.get(col) / &self.eta_pivots[idx];
+            for (i, val) in self.col_etas.col_iter(idx) {
+                *rhs.get_mut(i) -= val * &x;
+            }
+            *rhs.get_mut(col) = x;
+        }
+    }
+
+    fn apply_col_etas_transp(&self, rhs: &mut ScatteredVec) {
+        for (idx, &col) in self.eta_cols.iter().enumerate().rev() {
+            let mut coeff = rhs.get(col).clone();
+            for (i, val) in self.col_etas.col_iter(idx) {
+                coeff -= val * rhs.get(i);
+            }
+            if !coeff.is_zero() || rhs.is_nonzero[col] {
+                *rhs.get_mut(col) = &coeff / &self.eta_pivots[idx];
+            }
+        }
+    }
+
+    /// Apply the row eta matrices to the solution of L.
+    fn apply_row_etas(&self, rhs: &mut ScatteredVec) {
+        for (idx, &row) in self.eta_rows.iter().enumerate() {
+            let mut coeff = f0_ab!();
+            for (i, val) in self.row_etas.col_iter(idx) {
+                coeff += val * rhs.get(i);
+            }
+            if !coeff.is_zero() {
+                *rhs.get_mut(row) -= coeff;
+            }
+        }
+    }
+
+    fn apply_row_etas_transp(&self, rhs: &mut ScatteredVec) {
+        for (idx, &row) in self.eta_rows.iter().enumerate().rev() {
+            let coeff = rhs.get(row).clone();
+            if coeff.is_zero() {
+                continue;
+            }
+            for (i, val) in self.row_etas.col_iter(idx) {
+                *rhs.get_mut(i) -= &coeff * val;
+            }
+        }
+    }
+
+    /// Permute `rhs` to the new row indices in `scratch.rhs` and solve with L.
+    fn solve_lower(&self, rhs: &ScatteredVec, scratch: &mut ScratchSpace) {
+        scratch.rhs.clear();
+        for &i in &rhs.nonzero {
+            let new_i = self.row_perm.orig2new[i];
+            scratch.rhs.nonzero.push(new_i);
+            scratch.rhs.is_nonzero[new_i] = true;
+            scratch.rhs.values[new_i] = rhs.values[i].clone();
+        }
+        tri_solve_sparse(&self.lower, Triangle::Lower, scratch);
+    }
+
+    pub fn solve(&self, rhs: &mut ScatteredVec, scratch: &mut ScratchSpace) {
+        self.solve_lower(rhs, scratch);
+        self.apply_row_etas(&mut scratch.rhs);
+        self.upper.solve_sparse(scratch);
+
+        rhs.clear();
+        for &i in &scratch.rhs.nonzero {
+            let new_i = self.col_perm.new2orig[i];
+            rhs.nonzero.push(new_i);
+            rhs.is_nonzero[new_i] = true;
+            rhs.values[new_i] = scratch.rhs.values[i].clone();
+        }
+        self.apply_col_etas(rhs);
+    }
+
+    pub fn solve_transp(&self, rhs: &mut ScatteredVec, scratch: &mut ScratchSpace) {
+        self.apply_col_etas_transp(rhs);
+        scratch.rhs.clear();
+        for &i in &rhs.nonzero {
+            let new_i = self.col_perm.orig2new[i];
+            scratch.rhs.nonzero.push(new_i);
+            scratch.rhs.is_nonzero[new_i] = true;
+            scratch.rhs.values[new_i] = rhs.values[i].clone();
+        }
+        self.upper.solve_transp_sparse(scratch);
+        self.apply_row_etas_transp(&mut scratch.rhs);
+        tri_solve_sparse(&self.lower_transp, Triangle::Upper, scratch);
+
+        rhs.clear();
+        for &i in &scratch.rhs.nonzero {
+            let new_i = self.row_perm.new2orig[i];
+            rhs.nonzero.push(new_i);
+            rhs.is_nonzero[new_i] = true;
+            rhs.values[new_i] = scratch.rhs.values[i].clone();
+        }
+    }
+
+    pub fn solve_dense(&self, rhs: &mut [AbnormalFraction], scratch: &mut ScratchSpace) {
+        let dense_rhs = &mut scratch.dense_rhs;
+        dense_rhs.resize(rhs.len(), f0_ab!());
+        for i in 0..rhs.len() {
+            dense_rhs[self.row_perm.orig2new[i]] = rhs[i].clone();
+        }
+        tri_solve_dense(&self.lower, Triangle::Lower, dense_rhs);
+        for (idx, &row) in self.eta_rows.iter().enumerate() {
+            for (i, val) in self.row_etas.col_iter(idx) {
+                let diff = val * &dense_rhs[i];
+                dense_rhs[row] -= diff;
+            }
+        }
+        self.upper.solve_dense(dense_rhs);
+        for i in 0..rhs.len() {
+            rhs[self.col_perm.new2orig[i]] = dense_rhs[i].clone();
+        }
+        for (idx, &col) in self.eta_cols.iter().enumerate() {
+            let x = &rhs[col] / &self.eta_pivots[idx];
+            for (i, val) in self.col_etas.col_iter(idx) {
+                rhs[i] -= val * &x;
+            }
+            rhs[col] = x;
+        }
+    }
+
+    pub fn solve_transp_dense(&self, rhs: &mut [AbnormalFraction], scratch: &mut ScratchSpace) {
+        for (idx, &col) in self.eta_cols.iter().enumerate().rev() {
+            let mut coeff = rhs[col].clone();
+            for (i, val) in self.col_etas.col_iter(idx) {
+                coeff -= val * &rhs[i];
+            }
+            rhs[col] = &coeff / &self.eta_pivots[idx];
+        }
+        let dense_rhs = &mut scratch.dense_rhs;
+        dense_rhs.resize(rhs.len(), f0_ab!());
+        for i in 0..rhs.len() {
+            dense_rhs[self.col_perm.orig2new[i]] = rhs[i].clone();
+        }
+        self.upper.solve_transp_dense(dense_rhs);
+        for (idx, &row) in self.eta_rows.iter().enumerate().rev() {
+            let coeff = dense_rhs[row].clone();
+            for (i, val) in self.row_etas.col_iter(idx) {
+                dense_rhs[i] -= &coeff * val;
+            }
+        }
+        tri_solve_dense(&self.lower_transp, Triangle::Upper, dense_rhs);
+        for i in 0..rhs.len() {
+            rhs[self.row_perm.new2orig[i]] = dense_rhs[i].clone();
+        }
+    }
+
+    /// Replace column `col` of the factorised matrix by `new_col`.
+    ///
+    /// Returns [`Error::SingularMatrix`] if the resulting matrix is singular; the factors are
+    /// then invalid and the matrix has to be factorised anew.
+    pub fn replace_col(
+        &mut self,
+        col: usize,
+        new_col: &ScatteredVec,
+        scratch: &mut ScratchSpace,
+    ) -> Result<(), Error> {
+        if self.update == BasisUpdate::Eta {
+            return self.replace_col_eta(col, new_col, scratch);
+        }
+
+        // The spike: the new column with L and the previous updates applied.
+        self.solve_lower(new_col, scratch);
+        self.apply_row_etas(&mut scratch.rhs);
+
+        let idx = self.col_perm.orig2new[col];
+        let upper = &mut self.upper;
+        upper.clear_col(idx);
+        let spike_max_abs = match &self.drop_tolerance {
+            Some(_) => max_abs((scratch.rhs.nonzero.iter()).map(|&r| &scratch.rhs.values[r])),
+            None => f0_ab!(),
+        };
+        for &r in &scratch.rhs.nonzero {
+            let val = &scratch.rhs.values[r];
+            if r == idx || val.is_zero() {
+                continue;
+            }
+            if is_dropped(self.drop_tolerance.as_ref(), val, &spike_max_abs) {
+                self.dropped_entries += 1;
+            } else {
+                upper.insert(r, idx, val.clone());
+            }
+        }
+        let mut diag = scratch.rhs.values[idx].clone();
+
+        // Move the row and column of the spike to the end of the order. The entries of the
+        // row now lie left of the diagonal; eliminate them with the rows that precede it.
+        let start = upper.position[idx];
+        self.elim_row.clear();
+        for (&c, val) in upper.row_cols[idx].iter().zip(&upper.row_vals[idx]) {
+            *self.elim_row.get_mut(c) = val.clone();
+        }
+        upper.clear_row(idx);
+        upper.move_to_end(idx);
+
+        let mut multipliers = vec![];
+        for pos in start..upper.order.len() - 1 {
+            let i = upper.order[pos];
+            if self.elim_row.get(i).is_zero() {
+                continue;
+            }
+            let multiplier = self.elim_row.get(i) / &upper.diag[i];
+            for (&c, val) in upper.row_cols[i].iter().zip(&upper.row_vals[i]) {
+                if c == idx {
+                    diag -= &multiplier * val;
+                } else {
+                    *self.elim_row.get_mut(c) -= &multiplier * val;
+                }
+            }
+            multipliers.push((i, multiplier));
+        }
+        self.elim_row.clear();
+
+        if diag.is_zero() {
+            return Err(Error::SingularMatrix {
+                step: upper.order.len() - 1,
+                row: Some(self.row_perm.new2orig[idx]),
+                col: Some(col),
+            });
+        }
+        upper.diag[idx] = diag;
+        self.row_etas.append_col(multipliers);
+        self.eta_rows.push(idx);
+        Ok(())
+    }
+
+    /// [`UpdatableLU::replace_col`] with [`BasisUpdate::Eta`]: if B is the current matrix and d
+    /// the solution of B·d = `new_col`, the new matrix is B times the identity with column
+    /// `col` replaced by d. Store d as the eta column of this update.
+    fn replace_col_eta(
+        &mut self,
+        col: usize,
+        new_col: &ScatteredVec,
+        scratch: &mut ScratchSpace,
+    ) -> Result<(), Error> {
+        let mut spike = std::mem::replace(&mut self.elim_row, ScatteredVec::empty(0));
+        spike.set(new_col.iter());
+        self.solve(&mut spike, scratch);
+
+        let pivot = spike.get(col).clone();
+        if pivot.is_zero() {
+            spike.clear();
+            self.elim_row = spike;
+            return Err(Error::SingularMatrix {
+                step: self.lower.cols() - 1,
+                row: None,
+                col: Some(col),
+            });
+        }
+        let spike_max_abs = match &self.drop_tolerance {
+            Some(_) => max_abs(spike.iter().map(|(_, val)| val)),
+            None => f0_ab!(),
+        };
+        let mut eta = vec![];
+        for (i, val) in spike.iter() {
+            if i == col || val.is_zero() {
+                continue;
+            }
+            if is_dropped(self.drop_tolerance.as_ref(), val, &spike_max_abs) {
+                self.dropped_entries += 1;
+            } else {
+                eta.push((i, val.clone()));
+            }
+        }
+        spike.clear();
+        self.elim_row = spike;
+
+        self.col_etas.append_col(eta);
+        self.eta_cols.push(col);
+        self.eta_pivots.push(pivot);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct MarkNonzero {
+    dfs_stack: Vec<DfsStep>,
+    is_visited: Vec<bool>,
+    visited: Vec<usize>, // in reverse topological order
+}
+
+#[derive(Clone, Debug)]
+struct DfsStep {
+    orig_i: usize,
+    cur_child: usize,
+}
+
+impl MarkNonzero {
+    fn with_capacity(n: usize) -> MarkNonzero {
+        MarkNonzero {
+            dfs_stack: Vec::with_capacity(n),
+            is_visited: vec![false; n],
+            visited: vec![],
+        }
+    }
+
+    fn clear(&mut self) {
+        assert!(self.dfs_stack.is_empty());
+        for &i in &self.visited {
+            self.is_visited[i] = false;
+        }
+        self.visited.clear();
+    }
+
+    fn clear_and_resize(&mut self, n: usize) {
+        self.clear();
+        self.dfs_stack.reserve(n);
+        self.is_visited.resize(n, false);
+    }
+
+    // compute the non-zero elements of the result by dfs traversal
+    fn run<'a>(
+        &mut self,
+        rhs: &mut ScatteredVec,
+        get_children: impl Fn(usize) -> &'a [usize] + 'a,
+        filter: impl Fn(usize) -> bool,
+        orig2new_row: impl Fn(usize) -> usize,
+    ) {
+        self.clear();
+
+        for &orig_r in &rhs.nonzero {
+            let new_r = orig2new_row(orig_r);
+            if !filter(new_r) {
+                continue;
+            }
+            if self.is_visited[orig_r] {
+                continue;
+            }
+
+            self.dfs_stack.push(DfsStep {
+                orig_i: orig_r,
+                cur_child: 0,
+            });
+            while !self.dfs_stack.is_empty() {
+                let cur_step = self.dfs_stack.last_mut().unwrap();
+                let new_i = orig2new_row(cur_step.orig_i);
+                let children = if filter(new_i) {
+                    get_children(new_i)
+                } else {
+                    &[]
+                };
+                if !self.is_visited[cur_step.orig_i] {
+                    self.is_visited[cur_step.orig_i] = true;
+                } else {
+                    cur_step.cur_child += 1;
+                }
+
+                while cur_step.cur_child < children.len() {
+                    let child_orig_r = children[cur_step.cur_child];
+                    if !self.is_visited[child_orig_r] {
+                        break;
+                    }
+                    cur_step.cur_child += 1;
+                }
+
+                if cur_step.cur_child < children.len() {
+                    let i_child = cur_step.cur_child;
+                    self.dfs_stack.push(DfsStep {
+                        orig_i: children[i_child],
+                        cur_child: 0,
+                    });
+                } else {
+                    self.visited.push(cur_step.orig_i);
+                    self.dfs_stack.pop();
+                }
+            }
+        }
+
+        for &i in &self.visited {
+            if !rhs.is_nonzero[i] {
+                rhs.is_nonzero[i] = true;
+                rhs.nonzero.push(i)
+            }
+        }
+    }
+}
+
+enum Triangle {
+    Lower,
+    Upper,
+}
+
+fn tri_solve_dense(tri_mat: &TriangleMat, triangle: Triangle, rhs: &mut [AbnormalFraction]) {
+    assert_eq!(tri_mat.rows(), rhs.len());
+    match triangle {
+        Triangle::Lower => {
+            for col in 0..tri_mat.cols() {
+                tri_solve_process_col(tri_mat, col, rhs);
+            }
+        }
+
+        Triangle::Upper => {
+            for col in (0..tri_mat.cols()).rev() {
+                tri_solve_process_col(tri_mat, col, rhs);
+            }
+        }
+    };
+}
+
+/// Sparse triangular solves search for the nonzeros of the solution only if the right-hand
+/// side has fewer than one nonzero in this many entries. Otherwise the search costs more than
+/// it saves, and the solves process all lines of the factor in order, skipping the zeros.
+const HYPER_SPARSE_RATIO: usize = 10;
+
+/// rhs is passed via scratch.visited, scratch.values.
+fn tri_solve_sparse(tri_mat: &TriangleMat, triangle: Triangle, scratch: &mut ScratchSpace) {
+    assert_eq!(tri_mat.rows(), scratch.rhs.len());
+    let ScratchSpace {
+        rhs,
+        mark_nonzero,
+        visited_entries,
+        ..
+    } = scratch;
+    let is_hyper_sparse = rhs.nonzero.len() * HYPER_SPARSE_RATIO < rhs.len();
+
+    if is_hyper_sparse {
+        // compute the non-zero elements of the result by dfs traversal
+        mark_nonzero.run(
+            rhs,
+            |col| tri_mat.nondiag.col_rows(col),
+            |_| true,
+            |orig_i| orig_i,
+        );
+    }
+
+    let mut process_col = |col: usize| {
+        if rhs.values[col].is_zero() {
+            return;
+        }
+        *visited_entries += 1 + tri_mat.nondiag.col_rows(col).len();
+        tri_solve_process_col(tri_mat, col, &mut rhs.values);
+    };
+    if is_hyper_sparse {
+        // solve for the non-zero values into dense workspace.
+        // rev() because DFS returns vertices in reverse topological order.
+        for &col in mark_nonzero.visited.iter().rev() {
+            process_col(col);
+        }
+    } else {
+        match triangle {
+            Triangle::Lower => (0..tri_mat.cols()).for_each(process_col),
+            Triangle::Upper => (0..tri_mat.cols()).rev().for_each(process_col),
+        }
+        mark_nonzero_values(rhs);
+    }
+}
+
+/// Add the entries of `rhs` that became nonzero in a solve without a search for them to its
+/// nonzero pattern.
+fn mark_nonzero_values(rhs: &mut ScatteredVec) {
+    for (i, val) in rhs.values.iter().enumerate() {
+        if !rhs.is_nonzero[i] && !val.is_zero() {
+            rhs.is_nonzero[i] = true;
+            rhs.nonzero.push(i);
+        }
+    }
+}
+
+/// Move each entry `i` of `vals` to position `new_pos[i]`, with `buf` as the workspace.
+fn permute_dense(
+    vals: &mut [AbnormalFraction],
+    new_pos: &[usize],
+    buf: &mut Vec<AbnormalFraction>,
+) {
+    buf.resize(vals.len(), f0_ab!());
+    for (i, val) in vals.iter_mut().enumerate() {
+        buf[new_pos[i]] = std::mem::replace(val, f0_ab!());
+    }
+    vals.swap_with_slice(&mut buf[..vals.len()]);
+}
+
+/// Solve with the transpose of `tri_mat`, which is upper triangular if `tri_mat` is lower
+/// triangular and vice versa.
+fn tri_solve_transp_dense(tri_mat: &TriangleMat, triangle: Triangle, rhs: &mut [AbnormalFraction]) {
+    assert_eq!(tri_mat.rows(), rhs.len());
+    match triangle {
+        Triangle::Lower => {
+            for col in (0..tri_mat.cols()).rev() {
+                tri_solve_transp_process_col(tri_mat, col, rhs);
+            }
+        }
+
+        Triangle::Upper => {
+            for col in 0..tri_mat.cols() {
+                tri_solve_transp_process_col(tri_mat, col, rhs);
+            }
+        }
+    };
+}
+
+/// Solve for `rhs[col]` with the transpose of `tri_mat`: column `col` of `tri_mat` is row `col`
+/// of the transpose, whose other variables must already be solved for.
+fn tri_solve_transp_process_col(tri_mat: &TriangleMat, col: usize, rhs: &mut [AbnormalFraction]) {
+    let mut val = rhs[col].clone();
+    for (r, coeff) in tri_mat.nondiag.col_iter(col) {
+        val -= coeff * &rhs[r];
+    }
+    rhs[col] = match tri_mat.diag.as_ref() {
+        Some(diag) => &val / &diag[col],
+        None => val,
+    };
+}
+
+fn tri_solve_process_col(tri_mat: &TriangleMat, col: usize, rhs: &mut [AbnormalFraction]) {
+    // all other variables in this row (multiplied by their coeffs)
+    // are already subtracted from rhs[col].
+    let x_val = if let Some(diag) = tri_mat.diag.as_ref() {
+        &rhs[col] / &diag[col]
+    } else {
+        rhs[col].clone()
+    };
+
+    rhs[col] = x_val.clone();
+    for (r, coeff) in tri_mat.nondiag.col_iter(col) {
+        rhs[r] -= &x_val * coeff;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        counting_allocator::count_allocations,
+        f_ab, f1_ab,
+        linear_programming_helpers::{assert_matrix_eq, to_dense, to_sparse},
+    };
+
+    use super::*;
+    use ebi_arithmetic::One;
+    use sprs::{CsMat, CsVec, TriMat};
+
+    fn mat_from_triplets(
+        rows: usize,
+        cols: usize,
+        triplets: &[(usize, usize, AbnormalFraction)],
+    ) -> CsMat<AbnormalFraction> {
+        let mut mat = TriMat::with_capacity((rows, cols), triplets.len());
+        for (r, c, val) in triplets {
+            mat.add_triplet(*r, *c, val.clone());
+        }
+        mat.to_csc()
+    }
+
+    #[test]
+    fn lu_simple() {
+        let mat = mat_from_triplets(
+            3,
+            4,
+            &[
+                (0, 1, f_ab!(2)),
+                (0, 0, f_ab!(2)),
+                (0, 2, f_ab!(123)),
+                (1, 2, f_ab!(456)),
+                (1, 3, f1_ab!()),
+                (2, 1, f_ab!(4)),
+                (2, 0, f_ab!(3)),
+                (2, 2, f_ab!(789)),
+                (2, 3, f1_ab!()),
+            ],
+        );
+
+        let mut scratch = ScratchSpace::with_capacity(mat.rows());
+        let lu = lu_factorise(
+            mat.rows(),
+            |c| mat.outer_view([1, 0, 3][c]).unwrap().into_raw_storage(),
+            f_ab!(9, 10),
+            None,
+            &mut scratch,
+            None,
+        )
+        .unwrap();
+        let lu_transp = lu.transpose();
+
+        let l_nondiag_ref = [
+            vec![f0_ab!(), f0_ab!(), f0_ab!()],
+            vec![f_ab!(5, 10), f0_ab!(), f0_ab!()],
+            vec![f0_ab!(), f0_ab!(), f0_ab!()],
+        ];
+        assert_matrix_eq(&lu.lower.nondiag.to_csmat(), &l_nondiag_ref);
+        assert_eq!(lu.lower.diag, None);
+
+        let u_nondiag_ref = [
+            vec![f0_ab!(), f_ab!(3), f1_ab!()],
+            vec![f0_ab!(), f0_ab!(), -f_ab!(5, 10)],
+            vec![f0_ab!(), f0_ab!(), f0_ab!()],
+        ];
+        let u_diag_ref = [f_ab!(4), f_ab!(5, 10), f1_ab!()];
+        assert_matrix_eq(&lu.upper.nondiag.to_csmat(), &u_nondiag_ref);
+        assert_eq!(lu.upper.diag.as_ref().unwrap(), &u_diag_ref);
+
+        assert_eq!(lu.row_perm.as_ref().unwrap().new2orig, &[2, 0, 1]);
+        assert_eq!(lu.col_perm.as_ref().unwrap().new2orig, &[0, 1, 2]);
+
+        {
+            let mut rhs_dense = [f_ab!(6), f_ab!(3), f_ab!(13)];
+            lu.solve_dense(&mut rhs_dense, &mut scratch);
+            assert_eq!(&rhs_dense, &[f1_ab!(), f_ab!(2), f_ab!(3)]);
+        }
+
+        {
+            let mut rhs_dense_t = [f_ab!(14), f_ab!(11), f_ab!(5)];
+            lu_transp.solve_dense(&mut rhs_dense_t, &mut scratch);
+            assert_eq!(&rhs_dense_t, &[f1_ab!(), f_ab!(2), f_ab!(3)]);
+        }
+
+        {
+            let mut rhs = ScatteredVec::empty(3);
+            rhs.set(to_sparse(&[f0_ab!(), -f1_ab!(), f0_ab!()]).iter());
+            lu.solve(&mut rhs, &mut scratch);
+            assert_eq!(
+                to_dense(&rhs.to_csvec()),
+                vec![f1_ab!(), -f1_ab!(), -f1_ab!()]
+            );
+        }
+
+        {
+            let mut rhs = ScatteredVec::empty(3);
+            rhs.set(to_sparse(&[f0_ab!(), -f1_ab!(), f1_ab!()]).iter());
+            lu_transp.solve(&mut rhs, &mut scratch);
+            assert_eq!(
+                to_dense(&rhs.to_csvec()),
+                vec![-f_ab!(2), f0_ab!(), f1_ab!()]
+            );
+        }
+    }
+
+    #[test]
+    fn solve_many_rhs() {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+
+        // The matrix of lu_markowitz, whose factors in column order have fill-in.
+        let size = 10;
+        let mut triplets = vec![];
+        for r in 0..size {
+            triplets.push((r, r, f_ab!(20)));
+            if r > 0 {
+                triplets.push((r, r - 1, f1_ab!()));
+            }
+            if r + 1 < size {
+                triplets.push((r, r + 1, f1_ab!()));
+            }
+        }
+        for c in 2..size {
+            triplets.push((0, c, f1_ab!()));
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let mut scratch = ScratchSpace::with_capacity(size);
+        let lu = lu_factorise(
+            size,
+            |c| mat.outer_view(c).unwrap().into_raw_storage(),
+            f_ab!(1, 10),
+            None,
+            &mut scratch,
+            None,
+        )
+        .unwrap();
+        let lu_transp = lu.transpose();
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let rhs = (0..10)
+            .map(|_| {
+                let mut vals = vec![f0_ab!(); size];
+                for _ in 0..2 {
+                    vals[rng.random_range(0..size)] = f_ab!(rng.random_range(1..10usize));
+                }
+                vals
+            })
+            .collect::<Vec<_>>();
+        let assert_solutions_eq = |expected: &[AbnormalFraction], actual: &[AbnormalFraction]| {
+            if f1_ab!().is_exact() {
+                assert_eq!(expected, actual);
+            } else {
+                for (expected, actual) in expected.iter().zip(actual) {
+                    assert!((expected - actual).abs() <= f_ab!(1, 1_000_000_000));
+                }
+            }
+        };
+
+        for transp in [false, true] {
+            let mut expected = rhs.clone();
+            for vec in &mut expected {
+                if transp {
+                    lu_transp.solve_dense(vec, &mut scratch);
+                } else {
+                    lu.solve_dense(vec, &mut scratch);
+                }
+            }
+
+            let mut sparse = (rhs.iter())
+                .map(|vals| {
+                    let mut vec = ScatteredVec::empty(size);
+                    vec.set(to_sparse(vals).iter());
+                    vec
+                })
+                .collect::<Vec<_>>();
+            let mut dense = rhs.clone();
+            if transp {
+                lu.solve_transp_many(&mut sparse, &mut scratch);
+                lu.solve_transp_dense_block(&mut dense, &mut scratch);
+            } else {
+                lu.solve_many(&mut sparse, &mut scratch);
+                lu.solve_dense_block(&mut dense, &mut scratch);
+            }
+
+            for ((expected, sparse), dense) in expected.iter().zip(&sparse).zip(&dense) {
+                assert_solutions_eq(expected, &to_dense(&sparse.to_csvec()));
+                assert_solutions_eq(expected, dense);
+            }
+        }
+    }
+
+    #[test]
+    fn solve_transposed() {
+        // Not symmetric, so the solutions with the matrix and with its transpose differ.
+        let triplets = [
+            (0, 0, f_ab!(2)),
+            (0, 2, f1_ab!()),
+            (1, 0, f1_ab!()),
+            (1, 1, f_ab!(3)),
+            (2, 1, f1_ab!()),
+            (2, 2, f_ab!(4)),
+        ];
+        let mat = mat_from_triplets(3, 3, &triplets);
+        let mut scratch = ScratchSpace::with_capacity(3);
+        let lu = lu_factorise(
+            3,
+            |c| mat.outer_view(c).unwrap().into_raw_storage(),
+            f_ab!(9, 10),
+            None,
+            &mut scratch,
+            None,
+        )
+        .unwrap();
+        let assert_solutions_eq = |expected: &[AbnormalFraction], actual: &[AbnormalFraction]| {
+            if f1_ab!().is_exact() {
+                assert_eq!(expected, actual);
+            } else {
+                for (expected, actual) in expected.iter().zip(actual) {
+                    assert!((expected - actual).abs() <= f_ab!(1, 1_000_000_000));
+                }
+            }
+        };
+
+        let rhs = [f1_ab!(), f_ab!(2), f_ab!(3)];
+        let mut x = rhs.to_vec();
+        lu.solve_dense(&mut x, &mut scratch);
+        let mut y = rhs.to_vec();
+        lu.solve_transp_dense(&mut y, &mut scratch);
+        assert_ne!(x, y);
+
+        let mut mat_x = vec![f0_ab!(); 3];
+        let mut mat_transp_y = vec![f0_ab!(); 3];
+        for (r, c, val) in &triplets {
+            mat_x[*r] += val * &x[*c];
+            mat_transp_y[*c] += val * &y[*r];
+        }
+        assert_solutions_eq(&rhs, &mat_x);
+        assert_solutions_eq(&rhs, &mat_transp_y);
+
+        // The sparse solve computes the same, and agrees with the transposed factors.
+        let mut sparse = ScatteredVec::empty(3);
+        sparse.set(to_sparse(&rhs).iter());
+        lu.solve_transp(&mut sparse, &mut scratch);
+        assert_eq!(to_dense(&sparse.to_csvec()), y);
+        let mut y_transp = rhs.to_vec();
+        lu.transpose().solve_dense(&mut y_transp, &mut scratch);
+        assert_solutions_eq(&y, &y_transp);
+    }
+
+    #[test]
+    fn hyper_sparse_solve() {
+        // Tridiagonal blocks of 4, so the solution of a right-hand side with few nonzeros has
+        // few nonzeros too.
+        let size = 5000;
+        let mut triplets = vec![];
+        for r in 0..size {
+            triplets.push((r, r, f_ab!(4)));
+            if r % 4 != 3 {
+                triplets.push((r, r + 1, f1_ab!()));
+                triplets.push((r + 1, r, f1_ab!()));
+            }
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let mut scratch = ScratchSpace::with_capacity(size);
+        let lu = lu_factorise(
+            size,
+            |c| mat.outer_view(c).unwrap().into_raw_storage(),
+            f_ab!(9, 10),
+            None,
+            &mut scratch,
+            None,
+        )
+        .unwrap();
+        let assert_solutions_eq = |expected: &[AbnormalFraction], actual: &[AbnormalFraction]| {
+            if f1_ab!().is_exact() {
+                assert_eq!(expected, actual);
+            } else {
+                for (expected, actual) in expected.iter().zip(actual) {
+                    assert!((expected - actual).abs() <= f_ab!(1, 1_000_000_000));
+                }
+            }
+        };
+
+        let mut rhs = vec![f0_ab!(); size];
+        rhs[10] = f_ab!(3);
+        rhs[2500] = f1_ab!();
+        rhs[4997] = f_ab!(7);
+        let mut expected = rhs.clone();
+        lu.solve_dense(&mut expected, &mut scratch);
+
+        let mut sparse = ScatteredVec::empty(size);
+        sparse.set(to_sparse(&rhs).iter());
+        scratch.visited_entries = 0;
+        lu.solve(&mut sparse, &mut scratch);
+        assert!(scratch.visited_entries < size / 100);
+        assert_solutions_eq(&expected, &to_dense(&sparse.to_csvec()));
+
+        // Too many nonzeros for the search: all columns are processed in order.
+        let rhs = (0..size).map(|i| f_ab!(i % 5)).collect::<Vec<_>>();
+        let mut expected = rhs.clone();
+        lu.solve_dense(&mut expected, &mut scratch);
+        let mut sparse = ScatteredVec::empty(size);
+        sparse.set(to_sparse(&rhs).iter());
+        lu.solve(&mut sparse, &mut scratch);
+        assert_solutions_eq(&expected, &to_dense(&sparse.to_csvec()));
+    }
+
+    #[test]
+    fn reconstruct_factors() {
+        let triplets = [
+            (0, 0, f_ab!(2)),
+            (0, 2, f1_ab!()),
+            (1, 0, f_ab!(4)),
+            (1, 1, f_ab!(3)),
+            (2, 1, f1_ab!()),
+            (2, 2, f_ab!(5)),
+            (2, 3, f_ab!(2)),
+            (3, 0, f1_ab!()),
+            (3, 3, f_ab!(7)),
+        ];
+        let size = 4;
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+        let mut scratch = ScratchSpace::with_capacity(size);
+        let lu = lu_factorise(size, get_col, f_ab!(1, 10), None, &mut scratch, None).unwrap();
+        let lu_markowitz =
+            lu_factorise_markowitz(size, get_col, f_ab!(1, 10), None, true, None).unwrap();
+
+        for (lu, transp) in [
+            (&lu, false),
+            (&lu_markowitz, false),
+            (&lu.transpose(), true),
+        ] {
+            let row_perm = lu.row_perm().unwrap().new2orig();
+            let col_perm = lu.col_perm().unwrap().new2orig();
+            let product = lu.reconstruct();
+            let mut dense = vec![vec![f0_ab!(); size]; size];
+            for (c, col) in dense.iter_mut().enumerate() {
+                for (r, val) in product.col_iter(c) {
+                    col[r] = val.clone();
+                }
+            }
+            for r in 0..size {
+                for c in 0..size {
+                    let (orig_r, orig_c) = (row_perm[r], col_perm[c]);
+                    let expected = if transp {
+                        mat.get(orig_c, orig_r)
+                    } else {
+                        mat.get(orig_r, orig_c)
+                    };
+                    let expected = expected.map_or(f0_ab!(), |val| val.clone());
+                    if f1_ab!().is_exact() {
+                        assert_eq!(dense[c][r], expected);
+                    } else {
+                        assert!((&dense[c][r] - &expected).abs() <= f_ab!(1, 1_000_000_000));
+                    }
+                }
+            }
+        }
+        assert_eq!(lu.lower().cols(), size);
+        assert!(lu.upper().diag().is_some() && lu.lower().diag().is_none());
+    }
+
+    #[test]
+    fn lu_singular() {
+        let size = 3;
+
+        {
+            let symbolically_singular = mat_from_triplets(
+                size,
+                size,
+                &[
+                    (0, 0, f1_ab!()),
+                    (1, 0, f1_ab!()),
+                    (1, 1, f_ab!(2)),
+                    (1, 2, f_ab!(3)),
+                ],
+            );
+
+            let mut scratch = ScratchSpace::with_capacity(size);
+            let err = lu_factorise(
+                size,
+                |c| {
+                    symbolically_singular
+                        .outer_view(c)
+                        .unwrap()
+                        .into_raw_storage()
+                },
+                f_ab!(9, 10),
+                None,
+                &mut scratch,
+                None,
+            );
+            // Column 2 is the second in the order and has no entry outside of the pivot row of
+            // column 1, which was the first.
+            assert_eq!(
+                err.unwrap_err(),
+                Error::SingularMatrix {
+                    step: 1,
+                    row: Some(0),
+                    col: Some(2),
+                }
+            );
+        }
+
+        {
+            let numerically_singular = mat_from_triplets(
+                size,
+                size,
+                &[
+                    (0, 0, f1_ab!()),
+                    (1, 0, f1_ab!()),
+                    (1, 1, f_ab!(2)),
+                    (1, 2, f_ab!(3)),
+                    (2, 0, f_ab!(2)),
+                    (2, 1, f_ab!(2)),
+                    (2, 2, f_ab!(3)),
+                ],
+            );
+
+            let mut scratch = ScratchSpace::with_capacity(size);
+            let err = lu_factorise(
+                size,
+                |c| {
+                    numerically_singular
+                        .outer_view(c)
+                        .unwrap()
+                        .into_raw_storage()
+                },
+                f_ab!(9, 10),
+                None,
+                &mut scratch,
+                None,
+            );
+            assert!(matches!(err, Err(Error::SingularMatrix { .. })));
+        }
+    }
+
+    #[test]
+    fn lu_dense_col() {
+        // Lower bidiagonal with a last column of ones, which only the pivots on the diagonal
+        // satisfy the stability threshold for.
+        let size = 501;
+        let mut triplets = vec![];
+        for c in 0..size - 1 {
+            triplets.push((c, c, f_ab!(4)));
+            triplets.push((c + 1, c, f1_ab!()));
+        }
+        for r in 0..size {
+            triplets.push((r, size - 1, f1_ab!()));
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+
+        let mut scratch = ScratchSpace::with_capacity(size);
+        let lu = lu_factorise(size, get_col, f_ab!(9, 10), None, &mut scratch, None).unwrap();
+        let lu_dense =
+            lu_factorise(size, get_col, f_ab!(9, 10), Some(10), &mut scratch, None).unwrap();
+        assert!(lu.dense_cols().is_empty());
+        assert_eq!(lu_dense.dense_cols(), &[size - 1]);
+        assert!(lu.upper.nondiag.nnz() >= size - 1);
+        assert_eq!(lu_dense.upper.nondiag.nnz(), 0);
+        assert!(lu_dense.nnz() + size - 1 <= lu.nnz());
+
+        // Restore the dense column with an eta matrix and compare the solutions.
+        let dense_col = size - 1;
+        let mut eta_col = ScatteredVec::empty(size);
+        eta_col.set(mat.outer_view(dense_col).unwrap().iter());
+        lu_dense.solve(&mut eta_col, &mut scratch);
+        let pivot = eta_col.get(dense_col).clone();
+
+        let rhs = (0..size).map(|r| f_ab!(r % 7)).collect::<Vec<_>>();
+        let mut expected = rhs.clone();
+        lu.solve_dense(&mut expected, &mut scratch);
+        let mut actual = rhs.clone();
+        lu_dense.solve_dense(&mut actual, &mut scratch);
+        let coeff = &actual[dense_col] / &pivot;
+        for (r, value) in actual.iter_mut().enumerate() {
+            *value = if r == dense_col {
+                coeff.clone()
+            } else {
+                &*value - &(&coeff * eta_col.get(r))
+            };
+        }
+
+        let tolerance = f_ab!(1, 1_000_000);
+        for (expected, actual) in expected.iter().zip(&actual) {
+            assert!((expected - actual).abs() <= tolerance);
+        }
+    }
+
+    #[test]
+    fn amd_ordering() {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+
+        // The 5-point Laplacian of a grid, numbered row by row. The natural order fills the
+        // band of width `side` between the neighbours above and below.
+        let side = 16;
+        let size = side * side;
+        let mut triplets = vec![];
+        for r in 0..size {
+            triplets.push((r, r, f_ab!(4)));
+            if r % side > 0 {
+                triplets.push((r, r - 1, -f1_ab!()));
+                triplets.push((r - 1, r, -f1_ab!()));
+            }
+            if r >= side {
+                triplets.push((r, r - side, -f1_ab!()));
+                triplets.push((r - side, r, -f1_ab!()));
+            }
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+
+        // Only the largest entry is an acceptable pivot, which keeps the pivots on the
+        // diagonal.
+        let factorise = |ordering| {
+            let mut scratch = LuScratch::with_capacity(size);
+            scratch.set_ordering(ordering);
+            lu_factorise_with_scratch(size, get_col, f1_ab!(), None, &mut scratch, None).unwrap()
+        };
+        let natural = factorise(linear_programming_options::Ordering::Natural);
+        let amd = factorise(linear_programming_options::Ordering::Amd);
+        assert!(natural.nnz() > mat.nnz());
+        assert!(
+            4 * amd.nnz() <= 3 * natural.nnz(),
+            "AMD: {}, natural: {}",
+            amd.nnz(),
+            natural.nnz()
+        );
+        // The pattern is symmetric, so Auto prefers AMD; it only keeps COLAMD if that has a
+        // smaller fill estimate.
+        let auto = factorise(linear_programming_options::Ordering::Auto);
+        let colamd = factorise(linear_programming_options::Ordering::Colamd);
+        assert!([amd.nnz(), colamd.nnz()].contains(&auto.nnz()));
+
+        let mut rng = StdRng::seed_from_u64(672);
+        let mut scratch = ScratchSpace::with_capacity(size);
+        for _ in 0..3 {
+            let rhs = (0..size)
+                .map(|_| f_ab!(rng.random_range(0..10usize)))
+                .collect::<Vec<_>>();
+            let mut natural_sol = rhs.clone();
+            natural.solve_dense(&mut natural_sol, &mut scratch);
+            let mut amd_sol = rhs;
+            amd.solve_dense(&mut amd_sol, &mut scratch);
+            for (natural_val, amd_val) in natural_sol.iter().zip(&amd_sol) {
+                if f1_ab!().is_exact() {
+                    assert_eq!(natural_val, amd_val);
+                } else {
+                    assert!((natural_val - amd_val).abs() <= f_ab!(1, 1_000_000_000));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn row_hints() {
+        // A dense row 0 above a shifted diagonal. Pivoting on row 0 early makes the solve of
+        // each later column subtract it from the next row, which fills in both factors.
+        let size = 6;
+        let mut triplets = vec![];
+        for c in 0..size {
+            triplets.push((0, c, f1_ab!()));
+            if c + 1 < size {
+                triplets.push((c + 1, c, f_ab!(4)));
+            }
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+
+        let factorise = |hint, stability_coeff| {
+            let mut hints = vec![RowHint::Free; size];
+            hints[0] = hint;
+            let mut scratch = LuScratch::with_capacity(size);
+            scratch.set_ordering(linear_programming_options::Ordering::Natural);
+            scratch.set_row_hints(Some(hints));
+            lu_factorise_with_scratch(size, get_col, stability_coeff, None, &mut scratch, None)
+                .unwrap()
+        };
+        let early = factorise(RowHint::EliminateEarly, f_ab!(1, 10));
+        let late = factorise(RowHint::EliminateLate, f_ab!(1, 10));
+
+        assert_eq!(early.row_perm().unwrap().orig2new()[0], 0);
+        assert_eq!(late.row_perm().unwrap().orig2new()[0], size - 1);
+        assert!(late.nnz() < early.nnz());
+        // Eliminated last, the dense row only occupies the last row of L.
+        assert_eq!(late.upper().nondiag().nnz(), 0);
+        for c in 0..size {
+            for &r in late.lower().nondiag().col_rows(c) {
+                assert_eq!(r, size - 1);
+            }
+        }
+
+        let mut scratch = ScratchSpace::with_capacity(size);
+        let rhs = (0..size).map(|i| f_ab!(i + 1)).collect::<Vec<_>>();
+        let mut early_sol = rhs.clone();
+        early.solve_dense(&mut early_sol, &mut scratch);
+        let mut late_sol = rhs;
+        late.solve_dense(&mut late_sol, &mut scratch);
+        for (early_val, late_val) in early_sol.iter().zip(&late_sol) {
+            if f1_ab!().is_exact() {
+                assert_eq!(early_val, late_val);
+            } else {
+                assert!((early_val - late_val).abs() <= f_ab!(1, 1_000_000_000));
+            }
+        }
+
+        // A hint does not override the threshold test: only the entries of 4 pass it.
+        let strict = factorise(RowHint::EliminateEarly, f1_ab!());
+        assert_eq!(strict.row_perm().unwrap().orig2new()[0], size - 1);
+    }
+
+    #[test]
+    fn nested_dissection_ordering() {
+        use crate::linear_programming_ordering::OrderingStrategy;
+        use linear_programming_options::Ordering;
+
+        // The 5-point Laplacian of a `side` by `side` grid.
+        let laplacian = |side: usize| {
+            let size = side * side;
+            let mut triplets = vec![];
+            for r in 0..size {
+                triplets.push((r, r, f_ab!(4)));
+                if r % side > 0 {
+                    triplets.push((r, r - 1, -f1_ab!()));
+                    triplets.push((r - 1, r, -f1_ab!()));
+                }
+                if r >= side {
+                    triplets.push((r, r - side, -f1_ab!()));
+                    triplets.push((r - side, r, -f1_ab!()));
+                }
+            }
+            mat_from_triplets(size, size, &triplets)
+        };
+
+        // On a grid large enough for its separators to pay off, compare the fill of the orders
+        // symbolically, as the factorisations themselves are slow in exact arithmetic.
+        let mat = laplacian(40);
+        let mut pattern = SparseMat::new(mat.rows());
+        for c in 0..mat.cols() {
+            let col = mat.outer_view(c).unwrap();
+            pattern.append_col(col.iter().map(|(r, val)| (r, val.clone())));
+        }
+        let fill = |ordering: Ordering| estimate_fill(&pattern, &ordering.order(&pattern)).nnz();
+        let (amd, nested_dissection) = (
+            fill(Ordering::Amd),
+            fill(Ordering::NestedDissection { cutoff: 16 }),
+        );
+        assert!(
+            nested_dissection < amd,
+            "nested dissection: {}, AMD: {}",
+            nested_dissection,
+            amd
+        );
+
+        // On a small grid, the factors in both orders solve the same.
+        let mat = laplacian(12);
+        let size = mat.rows();
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+        // Only the largest entry is an acceptable pivot, which keeps the pivots on the
+        // diagonal.
+        let factorise = |ordering| {
+            let mut scratch = LuScratch::with_capacity(size);
+            scratch.set_ordering(ordering);
+            lu_factorise_with_scratch(size, get_col, f1_ab!(), None, &mut scratch, None).unwrap()
+        };
+        let amd = factorise(Ordering::Amd);
+        let nested_dissection = factorise(Ordering::NestedDissection { cutoff: 4 });
+
+        let mut scratch = ScratchSpace::with_capacity(size);
+        let rhs = (0..size).map(|i| f_ab!(i % 7)).collect::<Vec<_>>();
+        let mut amd_sol = rhs.clone();
+        amd.solve_dense(&mut amd_sol, &mut scratch);
+        let mut nested_dissection_sol = rhs;
+        nested_dissection.solve_dense(&mut nested_dissection_sol, &mut scratch);
+        for (amd_val, nested_dissection_val) in amd_sol.iter().zip(&nested_dissection_sol) {
+            if f1_ab!().is_exact() {
+                assert_eq!(amd_val, nested_dissection_val);
+            } else {
+                assert!((amd_val - nested_dissection_val).abs() <= f_ab!(1, 1_000_000_000));
+            }
+        }
+    }
+
+    #[test]
+    fn colamd_ordering() {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+        // Two diagonals above the diagonal and one three below, none of them mirrored, plus a
+        // few random entries far from the band, with the columns shuffled so that the natural
+        // order is a poor one.
+        let size = 80;
+        let mut rng = StdRng::seed_from_u64(673);
+        let mut shuffled = (0..size).collect::<Vec<_>>();
+        shuffled.shuffle(&mut rng);
+        let mut triplets = vec![];
+        for r in 0..size {
+            triplets.push((r, shuffled[r], f_ab!(20)));
+            for c in [r + 1, r + 2] {
+                if c < size {
+                    triplets.push((r, shuffled[c], f1_ab!()));
+                }
+            }
+            if r >= 3 {
+                triplets.push((r, shuffled[r - 3], -f1_ab!()));
+            }
+        }
+        for _ in 0..size / 4 {
+            let r: usize = rng.random_range(0..size);
+            let c = rng.random_range(0..size);
+            if r.abs_diff(c) > 3 {
+                triplets.push((r, shuffled[c], f1_ab!()));
+            }
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+
+        let factorise = |ordering| {
+            let mut scratch = LuScratch::with_capacity(size);
+            scratch.set_ordering(ordering);
+            (lu_factorise_with_scratch(size, get_col, f_ab!(1, 10), None, &mut scratch, None))
+                .unwrap()
+        };
+        let natural = factorise(linear_programming_options::Ordering::Natural);
+        let amd = factorise(linear_programming_options::Ordering::Amd);
+        let colamd = factorise(linear_programming_options::Ordering::Colamd);
+        assert!(
+            colamd.nnz() <= natural.nnz(),
+            "COLAMD: {}, AMD: {}, natural: {}",
+            colamd.nnz(),
+            amd.nnz(),
+            natural.nnz()
+        );
+        // The pattern is far from symmetric, so Auto prefers COLAMD; it only keeps AMD if that
+        // has a smaller fill estimate.
+        let mut sparse_mat = SparseMat::new(size);
+        for c in 0..size {
+            let col = mat.outer_view(c).unwrap();
+            sparse_mat.append_col(col.iter().map(|(r, val)| (r, val.clone())));
+        }
+        assert!(sparse_mat.symmetry_ratio() < 0.5);
+        let auto = factorise(linear_programming_options::Ordering::Auto);
+        assert!([colamd.nnz(), amd.nnz()].contains(&auto.nnz()));
+
+        // All orders factorise the same matrix.
+        let mut scratch = ScratchSpace::with_capacity(size);
+        let rhs = (0..size).map(|i| f_ab!(i % 7)).collect::<Vec<_>>();
+        let mut colamd_sol = rhs.clone();
+        colamd.solve_dense(&mut colamd_sol, &mut scratch);
+        let mut natural_sol = rhs;
+        natural.solve_dense(&mut natural_sol, &mut scratch);
+        for (natural_val, colamd_val) in natural_sol.iter().zip(&colamd_sol) {
+            if f1_ab!().is_exact() {
+                assert_eq!(natural_val, colamd_val);
+            } else {
+                assert!((natural_val - colamd_val).abs() <= f_ab!(1, 1_000_000_000));
+            }
+        }
+    }
+
+    #[test]
+    fn rcm_ordering() {
+        use crate::rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+        // A tridiagonal Laplacian with its rows and columns numbered at random.
+        let size = 40;
+        let mut labels = (0..size).collect::<Vec<_>>();
+        labels.shuffle(&mut StdRng::seed_from_u64(674));
+        let mut triplets = vec![];
+        for i in 0..size {
+            triplets.push((labels[i], labels[i], f_ab!(4)));
+            if i + 1 < size {
+                triplets.push((labels[i], labels[i + 1], -f1_ab!()));
+                triplets.push((labels[i + 1], labels[i], -f1_ab!()));
+            }
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+        let mut sparse_mat = SparseMat::new(size);
+        for c in 0..size {
+            let col = mat.outer_view(c).unwrap();
+            sparse_mat.append_col(col.iter().map(|(r, val)| (r, val.clone())));
+        }
+        assert!(sparse_mat.bandwidth(None) > 2);
+
+        // Only the largest entry is an acceptable pivot, which keeps the pivots on the
+        // diagonal, so the rows are permuted like the columns.
+        let mut scratch = LuScratch::with_capacity(size);
+        scratch.set_ordering(linear_programming_options::Ordering::Rcm);
+        let lu =
+            lu_factorise_with_scratch(size, get_col, f1_ab!(), None, &mut scratch, None).unwrap();
+        let col_perm = lu.col_perm().unwrap();
+        assert!(sparse_mat.bandwidth(Some(col_perm)) <= 2);
+        assert_eq!(lu.row_perm().unwrap().new2orig(), col_perm.new2orig());
+
+        // No fill-in beyond the band.
+        assert_eq!(lu.nnz(), mat.nnz());
+    }
+
+    #[test]
+    fn ordering_cache() {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+
+        // The 5-point pattern of a grid, strictly diagonally dominant by columns, in which one
+        // column after the other is replaced by a column with two random entries besides the
+        // diagonal, like a basis that changes by one column per pivot.
+        let side = 10;
+        let size = side * side;
+        let mut cols = (0..size)
+            .map(|c| {
+                let mut col = vec![(c, f_ab!(5))];
+                if c % side > 0 {
+                    col.push((c - 1, -f1_ab!()));
+                }
+                if c % side + 1 < side {
+                    col.push((c + 1, -f1_ab!()));
+                }
+                if c >= side {
+                    col.push((c - side, -f1_ab!()));
+                }
+                if c + side < size {
+                    col.push((c + side, -f1_ab!()));
+                }
+                col
+            })
+            .collect::<Vec<_>>();
+
+        let mut rng = StdRng::seed_from_u64(676);
+        let mut cached_scratch = LuScratch::with_capacity(size);
+        cached_scratch.set_ordering(linear_programming_options::Ordering::Amd);
+        cached_scratch.set_ordering_cache(Some(OrderingCachePolicy {
+            max_changed_cols: 5,
+            max_reuses: 100,
+        }));
+        let mut num_hits = 0;
+        let num_steps = 21;
+        for step in 0..num_steps {
+            if step > 0 {
+                let c = rng.random_range(0..size);
+                let mut col = vec![(c, f_ab!(10))];
+                while col.len() < 3 {
+                    let r = rng.random_range(0..size);
+                    if col.iter().all(|&(other_r, _)| other_r != r) {
+                        col.push((r, f1_ab!()));
+                    }
+                }
+                cols[c] = col;
+            }
+            let triplets = (cols.iter().enumerate())
+                .flat_map(|(c, col)| col.iter().map(move |(r, val)| (*r, c, val.clone())))
+                .collect::<Vec<_>>();
+            let mat = mat_from_triplets(size, size, &triplets);
+            let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+
+            let cached = lu_factorise_with_scratch(
+                size,
+                get_col,
+                f_ab!(1, 10),
+                None,
+                &mut cached_scratch,
+                None,
+            )
+            .unwrap();
+            if cached_scratch.ordering_cache_hit() == Some(true) {
+                num_hits += 1;
+            }
+
+            // The baseline computes the ordering for every factorisation.
+            let mut scratch = LuScratch::with_capacity(size);
+            scratch.set_ordering(linear_programming_options::Ordering::Amd);
+            let reordered =
+                lu_factorise_with_scratch(size, get_col, f_ab!(1, 10), None, &mut scratch, None)
+                    .unwrap();
+            assert_eq!(scratch.ordering_cache_hit(), None);
+            assert!(
+                cached.nnz() <= 2 * reordered.nnz(),
+                "step {}: cached {}, reordered {}",
+                step,
+                cached.nnz(),
+                reordered.nnz()
+            );
+        }
+        // Every fifth change makes the factorisation compute the ordering anew.
+        assert!(num_hits >= 15, "{} hits in {} steps", num_hits, num_steps);
+    }
+
+    #[test]
+    fn explicit_and_custom_orderings() {
+        use crate::linear_programming_ordering::OrderingStrategy;
+        use linear_programming_options::Ordering;
+        use std::sync::Arc;
+
+        // Diagonally dominant with columns of different counts, so that only the diagonal
+        // entries are acceptable pivots with a threshold of 1.
+        let size = 6;
+        let mut triplets = vec![];
+        for c in 0..size {
+            triplets.push((c, c, f_ab!(10)));
+            for r in 0..c {
+                if (r + c) % 2 == 1 {
+                    triplets.push((r, c, f1_ab!()));
+                }
+            }
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+        let factorise = |ordering| {
+            let mut scratch = LuScratch::with_capacity(size);
+            scratch.set_ordering(ordering);
+            lu_factorise_with_scratch(size, get_col, f1_ab!(), None, &mut scratch, None)
+        };
+
+        // The reverse order, with the pivots on the diagonal.
+        let reverse = (0..size).rev().collect::<Vec<_>>();
+        let perm = Perm::from_new2orig(reverse.clone()).unwrap();
+        let lu = factorise(Ordering::Explicit(perm)).unwrap();
+        assert_eq!(lu.col_perm().unwrap().new2orig(), &reverse);
+        assert_eq!(lu.row_perm().unwrap().new2orig(), &reverse);
+
+        let short = Perm::from_new2orig(vec![1, 0]).unwrap();
+        assert_eq!(
+            factorise(Ordering::Explicit(short)).unwrap_err(),
+            Error::InvalidOrdering { size, len: 2 }
+        );
+        assert!(Perm::from_new2orig(vec![0, 0]).is_none());
+        assert!(Perm::from_new2orig(vec![0, 2]).is_none());
+
+        // Sorting the columns by their counts, stably, is the count order.
+        #[derive(Debug)]
+        struct ByCount;
+
+        impl OrderingStrategy for ByCount {
+            fn order(&self, pattern: &SparseMat) -> Perm {
+                let mut new2orig = (0..pattern.cols()).collect::<Vec<_>>();
+                new2orig.sort_by_key(|&c| pattern.col_rows(c).len());
+                Perm::from_new2orig(new2orig).unwrap()
+            }
+        }
+
+        let custom = factorise(Ordering::Custom(Arc::new(ByCount))).unwrap();
+        let current = factorise(Ordering::Current).unwrap();
+        assert_eq!(
+            custom.col_perm().unwrap().new2orig(),
+            current.col_perm().unwrap().new2orig()
+        );
+        assert_eq!(
+            Ordering::Current
+                .order(&custom.reconstruct())
+                .new2orig()
+                .len(),
+            size
+        );
+    }
+
+    #[test]
+    fn fill_estimate() {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+        use linear_programming_options::Ordering;
+
+        // Random values keep the entries of the factors from cancelling out.
+        let mut rng = StdRng::seed_from_u64(677);
+        let mut random_val = move || f_ab!(rng.random_range(1..1000usize), 7);
+        let side = 8;
+        let arrowhead = (0..20)
+            .flat_map(|i| [(i, i), (i, 0), (0, i)])
+            .collect::<Vec<_>>();
+        let grid = (0..side * side)
+            .flat_map(|r| {
+                let mut entries = vec![(r, r)];
+                if r % side > 0 {
+                    entries.extend([(r, r - 1), (r - 1, r)]);
+                }
+                if r >= side {
+                    entries.extend([(r, r - side), (r - side, r)]);
+                }
+                entries
+            })
+            .collect::<Vec<_>>();
+        let mut pattern_rng = StdRng::seed_from_u64(6770);
+        let random = (0..50)
+            .map(|i| (i, i))
+            .chain((0..150).map(|_| {
+                (
+                    pattern_rng.random_range(0..50),
+                    pattern_rng.random_range(0..50),
+                )
+            }))
+            .collect::<Vec<_>>();
+
+        for (entries, is_exact) in [(arrowhead, true), (grid, false), (random, false)] {
+            let size = entries.iter().map(|&(r, _)| r + 1).max().unwrap();
+            let triplets = (entries.into_iter())
+                .map(|(r, c)| (r, c, random_val()))
+                .collect::<Vec<_>>();
+            let mat = mat_from_triplets(size, size, &triplets);
+            let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+            let mut sparse_mat = SparseMat::new(size);
+            for c in 0..size {
+                let col = mat.outer_view(c).unwrap();
+                sparse_mat.append_col(col.iter().map(|(r, val)| (r, val.clone())));
+            }
+
+            let mut estimates = vec![];
+            for ordering in [
+                Ordering::Natural,
+                Ordering::Amd,
+                Ordering::Colamd,
+                Ordering::Auto,
+            ] {
+                let mut scratch = LuScratch::with_capacity(size);
+                scratch.set_ordering(ordering.clone());
+                let lu =
+                    (lu_factorise_with_scratch(size, get_col, f0_ab!(), None, &mut scratch, None))
+                        .unwrap();
+                let estimate = estimate_fill(&sparse_mat, lu.col_perm().unwrap());
+                let actual = FillEstimate {
+                    lower_nnz: lu.lower_nnz(),
+                    upper_nnz: lu.upper_nnz(),
+                };
+                // The eliminations of the grid and of the random pattern cancel out entries
+                // whatever the values, after which the estimate follows another pattern.
+                if is_exact {
+                    assert_eq!(estimate, actual, "{:?}", ordering);
+                }
+                estimates.push(estimate.nnz());
+            }
+            // Auto keeps the better of AMD and COLAMD.
+            assert_eq!(estimates[3], estimates[1].min(estimates[2]));
+        }
+    }
+
+    #[test]
+    fn lu_markowitz() {
+        // Tridiagonal with a full first row. Eliminating the columns in order of their counts
+        // spreads the first row over the factors; the Markowitz pivots cause no fill-in at all.
+        let size = 10;
+        let mut triplets = vec![];
+        for r in 0..size {
+            triplets.push((r, r, f_ab!(20)));
+            if r > 0 {
+                triplets.push((r, r - 1, f1_ab!()));
+            }
+            if r + 1 < size {
+                triplets.push((r, r + 1, f1_ab!()));
+            }
+        }
+        for c in 2..size {
+            triplets.push((0, c, f1_ab!()));
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+
+        let mut scratch = ScratchSpace::with_capacity(size);
+        let lu = lu_factorise(size, get_col, f_ab!(1, 10), None, &mut scratch, None).unwrap();
+        let lu_markowitz =
+            lu_factorise_markowitz(size, get_col, f_ab!(1, 10), None, false, None).unwrap();
+        assert!(lu.nnz() > mat.nnz());
+        assert_eq!(lu_markowitz.nnz(), mat.nnz());
+
+        // L * U must be the matrix with its rows and columns permuted.
+        let row_perm = lu_markowitz.row_perm.as_ref().unwrap();
+        let col_perm = lu_markowitz.col_perm.as_ref().unwrap();
+        let mut lower = vec![vec![f0_ab!(); size]; size];
+        let mut upper = vec![vec![f0_ab!(); size]; size];
+        for c in 0..size {
+            lower[c][c] = f1_ab!();
+            for (r, val) in lu_markowitz.lower.nondiag.col_iter(c) {
+                lower[r][c] = val.clone();
+            }
+            upper[c][c] = lu_markowitz.upper.diag.as_ref().unwrap()[c].clone();
+            for (r, val) in lu_markowitz.upper.nondiag.col_iter(c) {
+                upper[r][c] = val.clone();
+            }
+        }
+        for (r, lower_row) in lower.iter().enumerate() {
+            for c in 0..size {
+                let product = (lower_row.iter().zip(&upper))
+                    .map(|(lower_val, upper_row)| lower_val * &upper_row[c])
+                    .fold(f0_ab!(), |sum, term| sum + term);
+                let expected = (mat.get(row_perm.new2orig[r], col_perm.new2orig[c]))
+                    .map_or(f0_ab!(), |val| val.clone());
+                if f1_ab!().is_exact() {
+                    assert_eq!(product, expected);
+                } else {
+                    assert!((product - expected).abs() <= f_ab!(1, 1_000_000));
+                }
+            }
+        }
+
+        // A singular matrix has a column that runs out of entries.
+        let singular = mat_from_triplets(
+            3,
+            3,
             &[
+                (0, 0, f1_ab!()),
+                (1, 0, f1_ab!()),
                 (0, 1, f_ab!(2)),
-                (0, 0, f_ab!(2)),
-                (0, 2, f_ab!(123)),
-                (1, 2, f_ab!(456)),
-                (1, 3, f1_ab!()),
-                (2, 1, f_ab!(4)),
-                (2, 0, f_ab!(3)),
-                (2, 2, f_ab!(789)),
-                (2, 3, f1_ab!()),
+                (1, 1, f_ab!(2)),
+                (2, 2, f1_ab!()),
             ],
         );
+        let err = lu_factorise_markowitz(
+            3,
+            |c| singular.outer_view(c).unwrap().into_raw_storage(),
+            f_ab!(1, 10),
+            None,
+            false,
+            None,
+        );
+        assert!(matches!(err, Err(Error::SingularMatrix { .. })));
+    }
 
-        let mut scratch = ScratchSpace::with_capacity(mat.rows());
-        let lu = lu_factorise(
-            mat.rows(),
-            |c| mat.outer_view([1, 0, 3][c]).unwrap().into_raw_storage(),
-            f_ab!(9, 10),
+    #[test]
+    fn markowitz_small_pivots() {
+        use ebi_arithmetic::malachite::base::num::logic::traits::SignificantBits;
+
+        let factor_vals = |lu: &LUFactors| {
+            let size = lu.size();
+            let mut vals = (0..size)
+                .flat_map(|c| lu.lower.nondiag.col_data(c).iter().cloned())
+                .collect::<Vec<_>>();
+            vals.extend((0..size).flat_map(|c| lu.upper.nondiag.col_data(c).iter().cloned()));
+            vals.extend(lu.upper.diag.iter().flatten().cloned());
+            vals
+        };
+        let is_integer = |val: &AbnormalFraction| {
+            val.exact_ref()
+                .is_ok_and(|exact| exact.denominator_ref().significant_bits() == 1)
+        };
+
+        // The incidence matrix of a tree with arcs 0→1, 1→2, 1→3, 3→4, 2→5 and 4→6, without
+        // the row of node 0, is totally unimodular.
+        let arcs = [(0, 1), (1, 2), (1, 3), (3, 4), (2, 5), (4, 6)];
+        let mut triplets = vec![];
+        for (c, &(tail, head)) in arcs.iter().enumerate() {
+            if tail > 0 {
+                triplets.push((tail - 1, c, f1_ab!()));
+            }
+            triplets.push((head - 1, c, -f1_ab!()));
+        }
+        let mat = mat_from_triplets(arcs.len(), arcs.len(), &triplets);
+        let mut scratch = LuScratch::with_capacity(0);
+        let lu = lu_factorise_markowitz_with_scratch(
+            arcs.len(),
+            |c| mat.outer_view(c).unwrap().into_raw_storage(),
+            f_ab!(1, 10),
+            None,
+            true,
             &mut scratch,
+            None,
         )
         .unwrap();
-        let lu_transp = lu.transpose();
+        assert_eq!(scratch.unit_pivots(), arcs.len());
+        if f1_ab!().is_exact() {
+            assert!(factor_vals(&lu).iter().all(is_integer));
+        }
 
-        let l_nondiag_ref = [
-            vec![f0_ab!(), f0_ab!(), f0_ab!()],
-            vec![f_ab!(5, 10), f0_ab!(), f0_ab!()],
-            vec![f0_ab!(), f0_ab!(), f0_ab!()],
-        ];
-        assert_matrix_eq(&lu.lower.nondiag.to_csmat(), &l_nondiag_ref);
-        assert_eq!(lu.lower.diag, None);
+        // All pivots have the same cost, and the first one found in either column is 2, which
+        // puts a fraction in L. Pivoting on the 1 first keeps the factors integer.
+        let mat = mat_from_triplets(
+            2,
+            2,
+            &[
+                (0, 0, f_ab!(2)),
+                (1, 0, f1_ab!()),
+                (0, 1, f_ab!(2)),
+                (1, 1, f_ab!(3)),
+            ],
+        );
+        for prefer_small_pivots in [false, true] {
+            let lu = lu_factorise_markowitz_with_scratch(
+                2,
+                |c| mat.outer_view(c).unwrap().into_raw_storage(),
+                f_ab!(1, 10),
+                None,
+                prefer_small_pivots,
+                &mut scratch,
+                None,
+            )
+            .unwrap();
+            if f1_ab!().is_exact() {
+                let all_integer = factor_vals(&lu).iter().all(is_integer);
+                assert_eq!(all_integer, prefer_small_pivots);
+                assert_eq!(scratch.unit_pivots(), prefer_small_pivots as usize);
+            }
+        }
+    }
 
-        let u_nondiag_ref = [
-            vec![f0_ab!(), f_ab!(3), f1_ab!()],
-            vec![f0_ab!(), f0_ab!(), -f_ab!(5, 10)],
-            vec![f0_ab!(), f0_ab!(), f0_ab!()],
-        ];
-        let u_diag_ref = [f_ab!(4), f_ab!(5, 10), f1_ab!()];
-        assert_matrix_eq(&lu.upper.nondiag.to_csmat(), &u_nondiag_ref);
-        assert_eq!(lu.upper.diag.as_ref().unwrap(), &u_diag_ref);
+    #[test]
+    fn drop_tolerance() {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
 
-        assert_eq!(lu.row_perm.as_ref().unwrap().new2orig, &[2, 0, 1]);
-        assert_eq!(lu.col_perm.as_ref().unwrap().new2orig, &[0, 1, 2]);
+        // A diagonally dominant matrix with a few real entries and many entries of the size of
+        // rounding errors.
+        let size = 30;
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut triplets = vec![];
+        for r in 0..size {
+            for c in 0..size {
+                if r == c {
+                    triplets.push((r, c, f_ab!(20)));
+                } else if rng.random_bool(0.1) {
+                    triplets.push((r, c, f_ab!(rng.random_range(1..5usize))));
+                } else if rng.random_bool(0.3) {
+                    triplets.push((r, c, f_ab!(1, 1_000_000_000_000_000)));
+                }
+            }
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
 
-        {
-            let mut rhs_dense = [f_ab!(6), f_ab!(3), f_ab!(13)];
-            lu.solve_dense(&mut rhs_dense, &mut scratch);
-            assert_eq!(&rhs_dense, &[f1_ab!(), f_ab!(2), f_ab!(3)]);
+        let mut scratch = LuScratch::with_capacity(size);
+        let lu = lu_factorise_with_scratch(size, get_col, f_ab!(1, 10), None, &mut scratch, None)
+            .unwrap();
+        assert_eq!(scratch.dropped_entries(), 0);
+        scratch.set_drop_tolerance(Some(DropTolerance::Relative(f_ab!(1, 1_000_000_000_000))));
+        let lu_dropped =
+            lu_factorise_with_scratch(size, get_col, f_ab!(1, 10), None, &mut scratch, None)
+                .unwrap();
+        if f1_ab!().is_exact() {
+            assert!(scratch.drop_tolerance().is_none());
+            assert_eq!(scratch.dropped_entries(), 0);
+            assert_eq!(lu_dropped.nnz(), lu.nnz());
+            return;
         }
+        assert!(scratch.dropped_entries() > 0);
+        assert!(lu_dropped.nnz() < lu.nnz());
 
-        {
-            let mut rhs_dense_t = [f_ab!(14), f_ab!(11), f_ab!(5)];
-            lu_transp.solve_dense(&mut rhs_dense_t, &mut scratch);
-            assert_eq!(&rhs_dense_t, &[f1_ab!(), f_ab!(2), f_ab!(3)]);
+        // The solution with the factors without the noise still solves the equations.
+        let rhs = (0..size).map(|i| f_ab!(i % 7)).collect::<Vec<_>>();
+        let mut x = rhs.clone();
+        lu_dropped.solve_dense(&mut x, &mut scratch.space);
+        let mut mat_x = vec![f0_ab!(); size];
+        for (r, c, val) in &triplets {
+            mat_x[*r] += val * &x[*c];
         }
+        for (expected, actual) in rhs.iter().zip(&mat_x) {
+            assert!((expected - actual).abs() <= f_ab!(1, 1_000_000_000));
+        }
+    }
 
-        {
-            let mut rhs = ScatteredVec::empty(3);
-            rhs.set(to_sparse(&[f0_ab!(), -f1_ab!(), f0_ab!()]).iter());
-            lu.solve(&mut rhs, &mut scratch);
-            assert_eq!(
-                to_dense(&rhs.to_csvec()),
-                vec![f1_ab!(), -f1_ab!(), -f1_ab!()]
-            );
+    #[test]
+    fn partial_refactorise() {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+
+        // The basis starts with the unit columns of the pool and changes one column at a time.
+        let size = 20;
+        let num_cols = 3 * size;
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut triplets = (0..size).map(|i| (i, i, f1_ab!())).collect::<Vec<_>>();
+        for c in size..num_cols {
+            for r in 0..size {
+                if rng.random_bool(0.2) {
+                    triplets.push((
+                        r,
+                        c,
+                        f_ab!(rng.random_range(1..10usize), rng.random_range(1..4usize)),
+                    ));
+                }
+            }
         }
+        let pool = mat_from_triplets(size, num_cols, &triplets);
 
-        {
-            let mut rhs = ScatteredVec::empty(3);
-            rhs.set(to_sparse(&[f0_ab!(), -f1_ab!(), f1_ab!()]).iter());
-            lu_transp.solve(&mut rhs, &mut scratch);
-            assert_eq!(
-                to_dense(&rhs.to_csvec()),
-                vec![-f_ab!(2), f0_ab!(), f1_ab!()]
+        let mut basis = (0..size).collect::<Vec<_>>();
+        let mut scratch = LuScratch::with_capacity(size);
+        let mut prev = lu_factorise_with_scratch(
+            size,
+            |c| pool.outer_view(basis[c]).unwrap().into_raw_storage(),
+            f_ab!(1, 10),
+            None,
+            &mut scratch,
+            None,
+        )
+        .unwrap();
+        let mut full_scratch = LuScratch::with_capacity(size);
+        let mut reused_cols = 0;
+        for _ in 0..50 {
+            let pos = rng.random_range(0..size);
+            let col = rng.random_range(0..num_cols);
+            if basis.contains(&col) {
+                continue;
+            }
+            let mut new_basis = basis.clone();
+            new_basis[pos] = col;
+            let get_col = |c: usize| pool.outer_view(new_basis[c]).unwrap().into_raw_storage();
+
+            let full = lu_factorise_with_scratch(
+                size,
+                get_col,
+                f_ab!(1, 10),
+                None,
+                &mut full_scratch,
+                None,
             );
+            let partial = lu_refactorise_with_scratch(
+                size,
+                get_col,
+                f_ab!(1, 10),
+                None,
+                &prev,
+                &[pos],
+                &mut scratch,
+                None,
+            );
+            let (full, partial) = match (full, partial) {
+                (Ok(full), Ok(partial)) => (full, partial),
+                (Err(Error::SingularMatrix { .. }), Err(Error::SingularMatrix { .. })) => continue,
+                _ => panic!("only one of the factorisations is singular"),
+            };
+            assert_eq!(full_scratch.reused_cols(), 0);
+            reused_cols += scratch.reused_cols();
+
+            let rhs = (0..size)
+                .map(|_| f_ab!(rng.random_range(0..5usize)))
+                .collect::<Vec<_>>();
+            let mut x_full = rhs.clone();
+            full.solve_dense(&mut x_full, &mut full_scratch.space);
+            let mut x_partial = rhs;
+            partial.solve_dense(&mut x_partial, &mut scratch.space);
+            if f1_ab!().is_exact() {
+                assert_eq!(x_partial, x_full);
+            } else {
+                for (expected, actual) in x_full.iter().zip(&x_partial) {
+                    assert!((expected - actual).abs() <= f_ab!(1, 1_000_000_000));
+                }
+            }
+
+            basis = new_basis;
+            prev = partial;
         }
+        assert!(reused_cols > 0);
     }
 
     #[test]
-    fn lu_singular() {
-        let size = 3;
+    fn symbolic_factorisation() {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
 
-        {
-            let symbolically_singular = mat_from_triplets(
-                size,
-                size,
-                &[
-                    (0, 0, f1_ab!()),
-                    (1, 0, f1_ab!()),
-                    (1, 1, f_ab!(2)),
-                    (1, 2, f_ab!(3)),
-                ],
+        let size = 25;
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut pattern = vec![];
+        for r in 0..size {
+            for c in 0..size {
+                if r == c || rng.random_bool(0.15) {
+                    pattern.push((r, c));
+                }
+            }
+        }
+        let random_values = |rng: &mut StdRng| {
+            (pattern.iter())
+                .map(|&(r, c)| {
+                    (
+                        r,
+                        c,
+                        f_ab!(rng.random_range(1..20usize), rng.random_range(1..4usize)),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let same_factors = |expected: &LUFactors, actual: &LUFactors| {
+            assert_eq!(
+                actual.lower().nondiag().to_csmat(),
+                expected.lower().nondiag().to_csmat()
+            );
+            assert_eq!(
+                actual.upper().nondiag().to_csmat(),
+                expected.upper().nondiag().to_csmat()
+            );
+            assert_eq!(actual.upper().diag(), expected.upper().diag());
+            assert_eq!(
+                actual.row_perm().unwrap().new2orig(),
+                expected.row_perm().unwrap().new2orig()
+            );
+            assert_eq!(
+                actual.col_perm().unwrap().new2orig(),
+                expected.col_perm().unwrap().new2orig()
             );
+        };
 
-            let mut scratch = ScratchSpace::with_capacity(size);
-            let err = lu_factorise(
-                size,
-                |c| {
-                    symbolically_singular
-                        .outer_view(c)
-                        .unwrap()
-                        .into_raw_storage()
-                },
-                f_ab!(9, 10),
+        let triplets = random_values(&mut rng);
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+        let mut scratch = LuScratch::with_capacity(size);
+        let (lu, symbolic) =
+            lu_analyse_with_scratch(size, get_col, f_ab!(1, 10), &mut scratch, None).unwrap();
+        let full = lu_factorise_with_scratch(size, get_col, f_ab!(1, 10), None, &mut scratch, None)
+            .unwrap();
+        same_factors(&full, &lu);
+
+        // With the same values, the numeric phase computes the same factors.
+        let numeric =
+            lu_factorise_symbolic(&symbolic, get_col, f_ab!(1, 10), &mut scratch, None).unwrap();
+        same_factors(&lu, &numeric);
+
+        // With values close to the analysed ones, it factorises the new matrix.
+        let mut num_numeric = 0;
+        for _ in 0..10 {
+            let new_triplets = (triplets.iter())
+                .map(|(r, c, val)| (*r, *c, val * &f_ab!(rng.random_range(19..22usize), 20)))
+                .collect::<Vec<_>>();
+            let new_mat = mat_from_triplets(size, size, &new_triplets);
+            let get_col = |c| new_mat.outer_view(c).unwrap().into_raw_storage();
+            let numeric =
+                match lu_factorise_symbolic(&symbolic, get_col, f_ab!(1, 10), &mut scratch, None) {
+                    Ok(numeric) => numeric,
+                    Err(Error::SymbolicMismatch { .. }) => continue,
+                    Err(err) => panic!("{:?}", err),
+                };
+            num_numeric += 1;
+
+            let rhs = (0..size).map(|i| f_ab!(i % 5)).collect::<Vec<_>>();
+            let mut x = rhs.clone();
+            numeric.solve_dense(&mut x, &mut scratch.space);
+            let mut mat_x = vec![f0_ab!(); size];
+            for (r, c, val) in &new_triplets {
+                mat_x[*r] += val * &x[*c];
+            }
+            for (expected, actual) in rhs.iter().zip(&mat_x) {
+                if f1_ab!().is_exact() {
+                    assert_eq!(actual, expected);
+                } else {
+                    assert!((expected - actual).abs() <= f_ab!(1, 1_000_000_000));
+                }
+            }
+        }
+        assert!(num_numeric > 0);
+
+        // A matrix with another pattern is detected.
+        let mut other_triplets = triplets.clone();
+        let missing = (0..size)
+            .flat_map(|r| (0..size).map(move |c| (r, c)))
+            .find(|entry| !pattern.contains(entry))
+            .unwrap();
+        other_triplets.push((missing.0, missing.1, f1_ab!()));
+        let other = mat_from_triplets(size, size, &other_triplets);
+        assert!(matches!(
+            lu_factorise_symbolic(
+                &symbolic,
+                |c| other.outer_view(c).unwrap().into_raw_storage(),
+                f_ab!(1, 10),
                 &mut scratch,
+                None,
+            ),
+            Err(Error::SymbolicMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rank_of_dependent_rows() {
+        // Rows 2 and 4 duplicate rows 0 and 1, and row 3 is zero.
+        let rows = [
+            [f1_ab!(), f_ab!(2), f0_ab!(), f0_ab!()],
+            [f0_ab!(), f1_ab!(), f_ab!(3), f0_ab!()],
+            [f1_ab!(), f_ab!(2), f0_ab!(), f0_ab!()],
+            [f0_ab!(), f0_ab!(), f0_ab!(), f0_ab!()],
+            [f0_ab!(), f1_ab!(), f_ab!(3), f0_ab!()],
+            [f_ab!(4), f0_ab!(), f1_ab!(), f1_ab!()],
+        ];
+        let mut mat = SparseMat::new(rows.len());
+        for c in 0..4 {
+            mat.append_col(
+                (rows.iter().enumerate())
+                    .filter(|(_, row)| !row[c].is_zero())
+                    .map(|(r, row)| (r, row[c].clone())),
             );
-            assert_eq!(err.unwrap_err(), Error::SingularMatrix);
         }
 
-        {
-            let numerically_singular = mat_from_triplets(
-                size,
-                size,
-                &[
-                    (0, 0, f1_ab!()),
-                    (1, 0, f1_ab!()),
-                    (1, 1, f_ab!(2)),
-                    (1, 2, f_ab!(3)),
-                    (2, 0, f_ab!(2)),
-                    (2, 1, f_ab!(2)),
-                    (2, 2, f_ab!(3)),
-                ],
-            );
+        let info = analyze_rank(&mat);
+        assert_eq!(info.rank, 3);
+        assert_eq!(info.dependent_rows, vec![2, 3, 4]);
 
-            let mut scratch = ScratchSpace::with_capacity(size);
-            let err = lu_factorise(
+        // A combination of the other rows is found as well, also past the zero row.
+        let mut eliminator = RowEliminator::new(4, 4);
+        for row in [&rows[0], &rows[3], &rows[1]] {
+            eliminator.add_row(row.iter().enumerate());
+        }
+        let combination = [f1_ab!(), f_ab!(3), f_ab!(3), f0_ab!()];
+        assert!(!eliminator.add_row(combination.iter().enumerate()));
+        assert!(eliminator.add_row(rows[5].iter().enumerate()));
+        assert_eq!(eliminator.rank(), 3);
+    }
+
+    #[test]
+    fn condition_estimate() {
+        let condest = |triplets: &[(usize, usize, AbnormalFraction)]| {
+            let mat = mat_from_triplets(3, 3, triplets);
+            let mut scratch = ScratchSpace::with_capacity(3);
+            let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+            (lu_factorise(3, get_col, f_ab!(1, 10), None, &mut scratch, None).unwrap()).condest()
+        };
+
+        // ‖B‖₁ = 6 and ‖B⁻¹‖₁ = 3/7.
+        let well_conditioned = condest(&[
+            (0, 0, f_ab!(4)),
+            (0, 1, f1_ab!()),
+            (1, 0, f1_ab!()),
+            (1, 1, f_ab!(4)),
+            (1, 2, f1_ab!()),
+            (2, 1, f1_ab!()),
+            (2, 2, f_ab!(4)),
+        ]);
+        assert!((1.0..=18.0 / 7.0 + 1e-9).contains(&well_conditioned));
+
+        // The first two rows almost coincide: the condition number is about 4 * 10^8.
+        let nearly_singular = condest(&[
+            (0, 0, f1_ab!()),
+            (0, 1, f1_ab!()),
+            (1, 0, f1_ab!()),
+            (1, 1, f_ab!(100_000_001, 100_000_000)),
+            (2, 2, f1_ab!()),
+        ]);
+        assert!(nearly_singular > 1e8 && nearly_singular <= 4.0000001e8);
+    }
+
+    #[test]
+    fn refactorise_without_allocations() {
+        // Lower bidiagonal with a full first row and a dense last column of ones.
+        let size = 30;
+        let mut triplets = vec![];
+        for c in 0..size - 1 {
+            triplets.push((c, c, f1_ab!()));
+            triplets.push((c + 1, c, -f1_ab!()));
+            if c > 0 {
+                triplets.push((0, c, f1_ab!()));
+            }
+        }
+        for r in 0..size {
+            triplets.push((r, size - 1, f1_ab!()));
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage();
+        let stability_coeff = f_ab!(1, 10);
+        let factorise = |scratch: &mut LuScratch| {
+            let lu = lu_factorise_with_scratch(
                 size,
-                |c| {
-                    numerically_singular
-                        .outer_view(c)
-                        .unwrap()
-                        .into_raw_storage()
-                },
-                f_ab!(9, 10),
-                &mut scratch,
+                get_col,
+                stability_coeff.clone(),
+                Some(size / 2),
+                scratch,
+                None,
+            )
+            .unwrap();
+            assert_eq!(lu.dense_cols(), &[size - 1]);
+            scratch.recycle(lu);
+        };
+
+        let mut scratch = LuScratch::with_capacity(size);
+        factorise(&mut scratch);
+        let allocations = count_allocations(|| {
+            for _ in 0..1000 {
+                factorise(&mut scratch);
+            }
+        });
+        assert_eq!(allocations, 0);
+    }
+
+    #[test]
+    fn forrest_tomlin_updates() {
+        check_updates(BasisUpdate::ForrestTomlin);
+    }
+
+    #[test]
+    fn eta_updates() {
+        check_updates(BasisUpdate::Eta);
+    }
+
+    /// Compare the solves after random column replacements with `update` with the solves with
+    /// a fresh factorisation.
+    fn check_updates(update: BasisUpdate) {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+
+        let size = 20;
+        let mut rng = StdRng::seed_from_u64(3);
+        let random_sparse = |rng: &mut StdRng, nnz: usize| {
+            let mut vals = vec![f0_ab!(); size];
+            for _ in 0..nnz {
+                let val = f_ab!(rng.random_range(1..10usize));
+                vals[rng.random_range(0..size)] = if rng.random_bool(0.5) { -val } else { val };
+            }
+            to_sparse(&vals)
+        };
+        let assert_solutions_eq = |expected: &ScatteredVec, actual: &ScatteredVec| {
+            let (expected, actual) = (to_dense(&expected.to_csvec()), to_dense(&actual.to_csvec()));
+            if f1_ab!().is_exact() {
+                assert_eq!(expected, actual);
+            } else {
+                let tolerance = f_ab!(1, 1_000_000);
+                for (expected, actual) in expected.iter().zip(&actual) {
+                    assert!(
+                        (expected - actual).abs()
+                            <= &tolerance * &(f1_ab!() + expected.clone().abs())
+                    );
+                }
+            }
+        };
+
+        // Start from the identity matrix and replace random columns by random columns.
+        let mut cols = (0..size)
+            .map(|c| {
+                let mut vals = vec![f0_ab!(); size];
+                vals[c] = f1_ab!();
+                to_sparse(&vals)
+            })
+            .collect::<Vec<_>>();
+        let mut scratch = ScratchSpace::with_capacity(size);
+        let factorise = |cols: &[CsVec<AbnormalFraction>], scratch: &mut ScratchSpace| {
+            let get_col = |c: usize| (cols[c].indices(), cols[c].data());
+            lu_factorise(size, get_col, f_ab!(9, 10), None, scratch, None)
+        };
+        let mut lu = UpdatableLU::new(factorise(&cols, &mut scratch).unwrap());
+        lu.set_update(update);
+
+        // Like the solver, refactorise before an update would reach the
+        // `RefactorPolicy::max_updates` threshold, so that the updates also start from the
+        // factors of a matrix other than the identity.
+        let max_updates = 16;
+        let mut num_refactors = 0;
+        let mut num_updates = 0;
+        while num_updates < 40 {
+            if lu.num_updates() + 1 >= max_updates {
+                lu = UpdatableLU::new(factorise(&cols, &mut scratch).unwrap());
+                lu.set_update(update);
+                num_refactors += 1;
+            }
+            let c = rng.random_range(0..size);
+            let mut new_cols = cols.clone();
+            new_cols[c] = random_sparse(&mut rng, 3);
+            let Ok(fresh) = factorise(&new_cols, &mut scratch) else {
+                // The new column would make the matrix singular.
+                continue;
+            };
+            let mut new_col = ScatteredVec::empty(size);
+            new_col.set(new_cols[c].iter());
+            lu.replace_col(c, &new_col, &mut scratch).unwrap();
+            cols = new_cols;
+            num_updates += 1;
+            assert_eq!(
+                lu.num_updates(),
+                num_updates - num_refactors * (max_updates - 1)
             );
-            assert_eq!(err.unwrap_err(), Error::SingularMatrix);
+
+            let rhs = random_sparse(&mut rng, 4);
+            let fresh_transp = fresh.transpose();
+            for transp in [false, true] {
+                let mut expected = ScatteredVec::empty(size);
+                expected.set(rhs.iter());
+                let mut actual = expected.clone();
+                if transp {
+                    fresh_transp.solve(&mut expected, &mut scratch);
+                    lu.solve_transp(&mut actual, &mut scratch);
+                } else {
+                    fresh.solve(&mut expected, &mut scratch);
+                    lu.solve(&mut actual, &mut scratch);
+                }
+                assert_solutions_eq(&expected, &actual);
+
+                let mut expected_dense = to_dense(&rhs);
+                let mut actual_dense = expected_dense.clone();
+                if transp {
+                    fresh_transp.solve_dense(&mut expected_dense, &mut scratch);
+                    lu.solve_transp_dense(&mut actual_dense, &mut scratch);
+                } else {
+                    fresh.solve_dense(&mut expected_dense, &mut scratch);
+                    lu.solve_dense(&mut actual_dense, &mut scratch);
+                }
+                expected.set(to_sparse(&expected_dense).iter());
+                actual.set(to_sparse(&actual_dense).iter());
+                assert_solutions_eq(&expected, &actual);
+            }
         }
+        assert_eq!(num_refactors, (num_updates - 1) / (max_updates - 1));
     }
 }
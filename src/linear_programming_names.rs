@@ -0,0 +1,95 @@
+//! Optional names of variables and constraints.
+
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+
+/// Names of a sequence of items, of which only some may be named. A sequence without any names
+/// allocates nothing.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Names {
+    /// Only grown up to the last named item.
+    names: Vec<Option<Arc<str>>>,
+    indices: FxHashMap<Arc<str>, usize>,
+}
+
+impl Names {
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.indices.contains_key(name)
+    }
+
+    /// Name the item at `idx`. Returns false, and changes nothing, if the name is already in use.
+    pub(crate) fn insert(&mut self, idx: usize, name: &str) -> bool {
+        if self.contains(name) {
+            return false;
+        }
+        let name: Arc<str> = Arc::from(name);
+        if self.names.len() <= idx {
+            self.names.resize(idx + 1, None);
+        }
+        if let Some(old_name) = self.names[idx].replace(name.clone()) {
+            self.indices.remove(&old_name);
+        }
+        self.indices.insert(name, idx);
+        true
+    }
+
+    /// Remove the item at `idx` and its name, if any; the later items move down by one.
+    pub(crate) fn remove(&mut self, idx: usize) {
+        if idx >= self.names.len() {
+            return;
+        }
+        if let Some(name) = self.names.remove(idx) {
+            self.indices.remove(&name);
+        }
+        for (idx, name) in self.names.iter().enumerate().skip(idx) {
+            if let Some(name) = name {
+                self.indices.insert(name.clone(), idx);
+            }
+        }
+        while self.names.last() == Some(&None) {
+            self.names.pop();
+        }
+    }
+
+    pub(crate) fn get(&self, idx: usize) -> Option<&str> {
+        self.names.get(idx)?.as_deref()
+    }
+
+    pub(crate) fn index(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        let mut names = Names::default();
+        assert_eq!(names.get(3), None);
+
+        assert!(names.insert(3, "x"));
+        assert!(!names.insert(1, "x"));
+        assert_eq!(names.get(3), Some("x"));
+        assert_eq!(names.get(1), None);
+        assert_eq!(names.index("x"), Some(3));
+        assert_eq!(names.index("y"), None);
+
+        // renaming releases the old name
+        assert!(names.insert(3, "y"));
+        assert_eq!(names.index("x"), None);
+        assert_eq!(names.index("y"), Some(3));
+
+        // removing moves the later names down
+        assert!(names.insert(5, "z"));
+        names.remove(4);
+        assert_eq!(names.index("z"), Some(4));
+        names.remove(3);
+        assert_eq!(names.index("y"), None);
+        assert_eq!(names.get(3), Some("z"));
+        names.remove(3);
+        assert!(names.names.is_empty());
+    }
+}
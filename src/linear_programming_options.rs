@@ -0,0 +1,686 @@
+use std::{
+    ops::ControlFlow,
+    sync::{Arc, atomic::AtomicBool},
+};
+
+use ebi_arithmetic::{MaybeExact, One};
+
+use crate::{
+    abnormal_fraction::AbnormalFraction,
+    f_ab, f1_ab,
+    linear_programming_ordering::OrderingStrategy,
+    linear_programming_progress::{IterationCallback, IterationInfo},
+    linear_programming_sparse::Perm,
+};
+
+/// The simplex method used for the initial solve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Method {
+    /// If the starting basis is primal infeasible, restore feasibility with the dual simplex,
+    /// using an artificial objective if the basis is not dual feasible either; then optimise
+    /// with the primal simplex.
+    #[default]
+    Auto,
+    /// Solve with the dual simplex, which suits problems whose starting basis is dual feasible,
+    /// such as box-constrained problems with costs of either sign. Non-basic variables are first
+    /// moved to the bound that makes their reduced cost dual feasible; variables without such a
+    /// bound temporarily get an artificial one, and the primal simplex finishes if the optimum
+    /// lies beyond it.
+    DualSimplex,
+    /// Solve a copy of the problem with approximate arithmetic first, and start the exact
+    /// simplex from the basis it finds. The exact simplex only pivots if that basis is not
+    /// exactly feasible or optimal, so the solution is still exact. Behaves like
+    /// [`Method::Auto`] if the problem is already approximate or if approximate fractions are
+    /// not available.
+    HybridExact,
+}
+
+/// When the solver computes a fresh LU factorisation of the basis matrix instead of updating
+/// the current one for the pivot (a Forrest–Tomlin update). A factorisation is computed as soon as
+/// any of the limits is reached.
+///
+/// The defaults keep the earlier behaviour, which refactorises once the updates have as many
+/// nonzeros as the factors, and add the common cap of 100 updates; they were not tuned on
+/// measurements.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RefactorPolicy {
+    /// Maximum number of pivots between two factorisations; 1 refactorises after every
+    /// pivot.
+    pub max_updates: usize,
+    /// Maximum ratio of the nonzeros of the updates to the nonzeros of the LU factors.
+    pub max_fill_ratio: AbnormalFraction,
+    /// Maximum size in bits of a coefficient of an update, counting numerator and denominator
+    /// (see [`AbnormalFraction::complexity`]). Only applies with exact arithmetic, where
+    /// coefficients can grow even if there are few updates. No limit by default.
+    pub max_coeff_bits: Option<usize>,
+    /// If set, a factorisation takes over the factors of the basis columns before the first
+    /// column that changed since the previous factorisation, as long as at most this fraction
+    /// of the basis columns changed; otherwise the basis is factorised from scratch. Only
+    /// applies with [`LuPivoting::ColumnOrder`]. Off by default.
+    pub partial_refactor: Option<f64>,
+}
+
+impl Default for RefactorPolicy {
+    fn default() -> Self {
+        Self {
+            max_updates: 100,
+            max_fill_ratio: f1_ab!(),
+            max_coeff_bits: None,
+            partial_refactor: None,
+        }
+    }
+}
+
+/// Which entries the LU factors of the basis matrix leave out as noise, see
+/// [`SolveOptions::drop_tolerance`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum DropTolerance {
+    /// Drop entries with at most this absolute value.
+    Absolute(AbnormalFraction),
+    /// Drop entries with at most this times the largest absolute value in their column.
+    Relative(AbnormalFraction),
+}
+
+impl DropTolerance {
+    /// The largest absolute value that is dropped from a column whose largest absolute value is
+    /// `max_abs`.
+    pub(crate) fn threshold(&self, max_abs: &AbnormalFraction) -> AbnormalFraction {
+        match self {
+            DropTolerance::Absolute(tolerance) => tolerance.clone(),
+            DropTolerance::Relative(tolerance) => tolerance * max_abs,
+        }
+    }
+}
+
+/// The rule used by the primal simplex to choose the entering variable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Pricing {
+    /// Choose the variable with the largest absolute reduced cost. Cheapest per iteration,
+    /// but typically needs the most iterations.
+    Dantzig,
+    /// Approximate steepest edge with reference weights (Devex). The weights are updated using
+    /// only the pivot row and column, which are computed anyway, and are reset to one whenever
+    /// they drift too far from their true values.
+    Devex,
+    /// Exact steepest edge. Needs the fewest iterations, but the weight updates multiply large
+    /// rationals in exact arithmetic.
+    #[default]
+    SteepestEdge,
+}
+
+/// Which columns the primal simplex examines to choose the entering variable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PartialPricing {
+    /// Examine all columns in every iteration.
+    #[default]
+    Off,
+    /// Examine a window of columns, with a size derived from the number of columns.
+    Auto,
+    /// Examine a window of the given number of columns.
+    ///
+    /// Each iteration continues where the previous window ended. Only if the window contains no
+    /// column that improves the objective, all columns are examined.
+    Window(usize),
+}
+
+impl PartialPricing {
+    pub(crate) fn window_size(&self, num_cols: usize) -> usize {
+        match self {
+            PartialPricing::Off => num_cols,
+            PartialPricing::Auto => (num_cols / 10).max(100).min(num_cols),
+            PartialPricing::Window(size) => (*size).clamp(1, num_cols.max(1)),
+        }
+    }
+}
+
+/// Whether the constraint matrix is scaled before solving.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Scaling {
+    /// Solve the problem as given.
+    #[default]
+    Off,
+    /// Scale the problem if the arithmetic is approximate. In exact arithmetic, badly scaled
+    /// coefficients cause no numerical trouble, so scaling would only cost time.
+    Auto,
+    /// Scale the problem regardless of the arithmetic.
+    Always,
+}
+
+impl Scaling {
+    pub(crate) fn is_enabled(&self) -> bool {
+        match self {
+            Scaling::Off => false,
+            Scaling::Auto => !f1_ab!().is_exact(),
+            Scaling::Always => true,
+        }
+    }
+}
+
+/// How the LU factorisation of the basis matrix chooses its pivots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LuPivoting {
+    /// Eliminate the columns in an order computed from the nonzero pattern beforehand, and
+    /// choose the pivot row of each column when it is eliminated (Gilbert-Peierls).
+    #[default]
+    ColumnOrder,
+    /// Choose each pivot by the Markowitz criterion, using the nonzero counts of the rows and
+    /// columns that are left. Usually less fill-in, at the cost of maintaining those counts.
+    Markowitz,
+}
+
+/// The order in which [`LuPivoting::ColumnOrder`] eliminates the columns of the basis matrix.
+/// Only the nonzero pattern of the basis matrix determines the built-in orders.
+#[derive(Clone, Debug, Default)]
+pub enum Ordering {
+    /// Eliminate the columns in the order of the basis.
+    Natural,
+    /// Eliminate the columns in the order of their numbers of nonzeros.
+    #[default]
+    Current,
+    /// Eliminate the columns in an approximate minimum degree order of the pattern of Bᵀ + B,
+    /// where B is the basis matrix. Causes much less fill-in than the other orders on bases
+    /// with a symmetric pattern whose pivots stay on the diagonal, such as discretised grids,
+    /// but the order itself costs more to compute.
+    Amd,
+    /// Eliminate the columns in reverse Cuthill–McKee order of the pattern of Bᵀ + B, which
+    /// narrows the band around the diagonal that holds the nonzeros. Suits bases with a nearly
+    /// banded pattern, such as those of sequential process models, whose fill-in then stays
+    /// within the band (see
+    /// [`SparseMat::bandwidth`](crate::linear_programming_sparse::SparseMat::bandwidth)).
+    Rcm,
+    /// Eliminate the columns in a nested dissection order of the pattern of Bᵀ + B (see
+    /// [`order_nested_dissection`]), which orders parts of at most `cutoff` columns like
+    /// [`Ordering::Amd`]. Beats [`Ordering::Amd`] on large bases with a mesh-like pattern, such
+    /// as those of the product of a model automaton and a long trace, whose graphs split into
+    /// halves along small separators.
+    ///
+    /// [`order_nested_dissection`]: crate::linear_programming_ordering::order_nested_dissection
+    NestedDissection { cutoff: usize },
+    /// Eliminate the columns in a column approximate minimum degree order (COLAMD), which
+    /// bounds the fill-in from the pattern of BᵀB and therefore suits bases with an
+    /// unsymmetric pattern, whatever rows partial pivoting chooses. Columns of one nonzero
+    /// come first.
+    Colamd,
+    /// Choose [`Ordering::Amd`] if the pattern of the basis matrix is mostly symmetric (see
+    /// [`SparseMat::symmetry_ratio`]) and its diagonal mostly nonzero, and [`Ordering::Colamd`]
+    /// otherwise; decided anew for each factorisation. For basis matrices of up to 1000
+    /// columns, both orderings are computed and the one with the smaller
+    /// [`estimate_fill`] is kept, preferring the former choice on a tie. With the `parallel`
+    /// feature, both are computed concurrently, and the choice is the same.
+    ///
+    /// [`SparseMat::symmetry_ratio`]: crate::linear_programming_sparse::SparseMat::symmetry_ratio
+    /// [`estimate_fill`]: crate::linear_programming_lu::estimate_fill
+    Auto,
+    /// Eliminate the columns in the given order, which must have an entry for every column of
+    /// the basis matrix; otherwise the solver returns
+    /// [`ModelError::DimensionMismatch`](crate::linear_programming::ModelError::DimensionMismatch).
+    Explicit(Perm),
+    /// Eliminate the columns in the order of a strategy of the caller, for instance one that
+    /// knows the structure of the problem. Its permutations are checked like those of
+    /// [`Ordering::Explicit`]. Two custom orderings are equal if they share the strategy.
+    Custom(Arc<dyn OrderingStrategy>),
+}
+
+impl PartialEq for Ordering {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Ordering::NestedDissection { cutoff },
+                Ordering::NestedDissection {
+                    cutoff: other_cutoff,
+                },
+            ) => cutoff == other_cutoff,
+            (Ordering::Explicit(perm), Ordering::Explicit(other_perm)) => perm == other_perm,
+            (Ordering::Custom(strategy), Ordering::Custom(other_strategy)) => {
+                Arc::ptr_eq(strategy, other_strategy)
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for Ordering {}
+
+/// When a factorisation of the basis matrix reuses the column ordering of an earlier one
+/// instead of computing it anew, see [`SolveOptions::ordering_cache`]. The basis usually
+/// changes by one column per pivot, so an ordering stays good for a while, and computing
+/// [`Ordering::Amd`] or [`Ordering::Colamd`] for every factorisation is wasteful.
+///
+/// The defaults were not tuned on measurements.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderingCachePolicy {
+    /// Reuse the ordering if fewer than this many basis columns have another pattern than
+    /// when it was computed. The ordering of a basis matrix with the same pattern is always
+    /// reused.
+    pub max_changed_cols: usize,
+    /// Compute the ordering anew after it was reused this many times.
+    pub max_reuses: usize,
+}
+
+impl Default for OrderingCachePolicy {
+    fn default() -> Self {
+        Self {
+            max_changed_cols: 10,
+            max_reuses: 50,
+        }
+    }
+}
+
+/// When the LU factorisation of the basis matrix prefers to pivot on a row, see
+/// [`SolveOptions::row_hints`]. The variants are ordered from the earliest to the latest
+/// preference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum RowHint {
+    /// Pivot on the row as soon as it passes the threshold test, before the rows of the other
+    /// hints.
+    EliminateEarly,
+    /// No preference: among the rows that pass the threshold test, the one with the fewest
+    /// nonzeros is chosen.
+    #[default]
+    Free,
+    /// Pivot on the row only if no row of the other hints passes the threshold test. Suits a
+    /// dense row, such as one that sums frequencies, whose nonzeros would otherwise spread
+    /// through the factors of the columns after its pivot.
+    EliminateLate,
+}
+
+/// How the factors of the basis matrix are updated for a pivot between two factorisations
+/// (see [`RefactorPolicy`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BasisUpdate {
+    /// Replace the column in U and restore its triangular form with a row eta matrix
+    /// (Forrest–Tomlin).
+    #[default]
+    ForrestTomlin,
+    /// Keep the LU factors and store the solve of the entering column as an eta column, which
+    /// the solves apply after (resp. before) those with the factors: the product form of the
+    /// inverse. Simpler than Forrest–Tomlin, and with small bases often as fast.
+    Eta,
+}
+
+/// Options that influence how a [`Problem`](crate::linear_programming::Problem) is solved.
+///
+/// The options are set using builder-style methods:
+/// ```
+/// # use ebi_optimisation::linear_programming::*;
+/// let options = SolveOptions::new().pricing(Pricing::Devex);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SolveOptions {
+    pub(crate) method: Method,
+    pub(crate) pricing: Pricing,
+    pub(crate) partial_pricing: PartialPricing,
+    pub(crate) parallel_pricing_threshold: usize,
+    pub(crate) presolve: bool,
+    pub(crate) remove_dependent_rows: bool,
+    pub(crate) perturbation: bool,
+    pub(crate) bound_flipping: bool,
+    pub(crate) harris_tolerance: AbnormalFraction,
+    pub(crate) scaling: Scaling,
+    pub(crate) on_iteration: Option<IterationCallback>,
+    pub(crate) cancel: Option<Arc<AtomicBool>>,
+    pub(crate) timings: bool,
+    pub(crate) refactor_policy: RefactorPolicy,
+    pub(crate) dense_column_fraction: Option<f64>,
+    pub(crate) lu_pivoting: LuPivoting,
+    pub(crate) ordering: Ordering,
+    pub(crate) ordering_cache: Option<OrderingCachePolicy>,
+    pub(crate) row_hints: Option<Vec<RowHint>>,
+    pub(crate) basis_update: BasisUpdate,
+    pub(crate) reuse_symbolic: bool,
+    pub(crate) lu_pivot_threshold: AbnormalFraction,
+    pub(crate) small_pivots: bool,
+    pub(crate) drop_tolerance: Option<DropTolerance>,
+    pub(crate) condition_limit: Option<f64>,
+    pub(crate) coeff_bit_stats: bool,
+    pub(crate) check_residuals: bool,
+    pub(crate) deterministic: bool,
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        Self {
+            method: Method::default(),
+            pricing: Pricing::default(),
+            partial_pricing: PartialPricing::default(),
+            parallel_pricing_threshold: 5000,
+            presolve: false,
+            remove_dependent_rows: false,
+            perturbation: false,
+            bound_flipping: true,
+            harris_tolerance: f_ab!(1, 1_000_000_000),
+            scaling: Scaling::default(),
+            on_iteration: None,
+            cancel: None,
+            timings: false,
+            refactor_policy: RefactorPolicy::default(),
+            dense_column_fraction: None,
+            lu_pivoting: LuPivoting::default(),
+            ordering: Ordering::default(),
+            ordering_cache: None,
+            row_hints: None,
+            basis_update: BasisUpdate::default(),
+            reuse_symbolic: false,
+            lu_pivot_threshold: f_ab!(1, 10),
+            small_pivots: true,
+            drop_tolerance: None,
+            condition_limit: None,
+            coeff_bit_stats: false,
+            check_residuals: false,
+            deterministic: true,
+        }
+    }
+}
+
+impl SolveOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the simplex method.
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Set the pricing rule of the primal simplex.
+    pub fn pricing(mut self, pricing: Pricing) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Set which columns the primal simplex examines in each iteration. For problems with
+    /// many more variables than constraints, partial pricing avoids computing the score of
+    /// every column in every iteration.
+    pub fn partial_pricing(mut self, partial_pricing: PartialPricing) -> Self {
+        self.partial_pricing = partial_pricing;
+        self
+    }
+
+    /// Set the minimum number of columns for which the primal simplex scores all columns in
+    /// parallel, if the `parallel` feature is enabled. The choice of the entering variable is
+    /// the same as without parallelism. Defaults to 5000.
+    pub fn parallel_pricing_threshold(mut self, threshold: usize) -> Self {
+        self.parallel_pricing_threshold = threshold;
+        self
+    }
+
+    /// Enable or disable presolve, which removes fixed variables, variables that occur in no
+    /// constraint, and empty, singleton and duplicate constraints before the simplex starts.
+    /// Disabled by default.
+    ///
+    /// Variables removed by presolve keep their value when the [`Solution`] is modified
+    /// afterwards: they act as constants in added constraints and cannot be fixed to another
+    /// value.
+    ///
+    /// [`Solution`]: crate::linear_programming::Solution
+    pub fn presolve(mut self, presolve: bool) -> Self {
+        self.presolve = presolve;
+        self
+    }
+
+    /// Enable or disable the removal of equality constraints that are linear combinations of
+    /// other equality constraints during presolve, such as the dependent rows of the incidence
+    /// matrix of a Petri net. A removed constraint whose bound does not match the combination
+    /// makes the problem infeasible. Disabled by default, as it needs a Gaussian elimination
+    /// of the equality constraints; has no effect without [`presolve`](Self::presolve).
+    pub fn remove_dependent_rows(mut self, remove: bool) -> Self {
+        self.remove_dependent_rows = remove;
+        self
+    }
+
+    /// Enable or disable the perturbation of bounds during phase 2. Disabled by default.
+    ///
+    /// On problems where many basic variables sit at their bounds, the primal simplex may
+    /// perform long sequences of iterations that do not improve the objective. Perturbation
+    /// widens the bounds of the basic variables by distinct tiny amounts before phase 2 and
+    /// restores them afterwards, followed by a few iterations of the dual simplex. The reported
+    /// solution is optimal for the original, unperturbed problem.
+    pub fn perturbation(mut self, perturbation: bool) -> Self {
+        self.perturbation = perturbation;
+        self
+    }
+
+    /// Enable or disable bound flipping in the ratio test of the dual simplex. Enabled by
+    /// default.
+    ///
+    /// When the leaving variable is far from its bound, the dual step may pass the points at
+    /// which the reduced costs of several boxed variables change sign. Instead of stopping at
+    /// the first of them, these variables move to their other bound without a basis change,
+    /// and the step continues as long as the leaving variable stays infeasible. All flips of
+    /// an iteration are applied with one solve with the basis matrix, so problems with many
+    /// boxed variables need fewer pivots and LU updates. The primal simplex always flips a
+    /// boxed entering variable that reaches its other bound first.
+    pub fn bound_flipping(mut self, bound_flipping: bool) -> Self {
+        self.bound_flipping = bound_flipping;
+        self
+    }
+
+    /// Set the tolerance of the ratio tests with approximate arithmetic. Defaults to 10⁻⁹.
+    ///
+    /// Both the primal and the dual simplex choose the leaving (resp. entering) variable with
+    /// Harris' two-pass ratio test: the first pass finds the largest step for which no bound
+    /// (resp. reduced cost) is violated by more than the tolerance, and the second pass chooses
+    /// the candidate with the largest pivot coefficient among those that block within this
+    /// step. Avoiding tiny pivots keeps the LU factorisation accurate, at the price of
+    /// violations of at most the tolerance. With exact arithmetic the tolerance is not used,
+    /// and the ratio tests never violate a bound.
+    pub fn harris_tolerance(mut self, tolerance: AbnormalFraction) -> Self {
+        self.harris_tolerance = tolerance;
+        self
+    }
+
+    /// Set whether rows and columns of the constraint matrix are scaled by powers of two
+    /// before solving. The solution is reported in terms of the original variables.
+    pub fn scaling(mut self, scaling: Scaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    /// Call `callback` after every iteration of the simplex, including the iterations needed to
+    /// re-optimise after the [`Solution`] is modified. If the callback returns
+    /// [`ControlFlow::Break`], the solver stops and returns [`Error::Interrupted`].
+    ///
+    /// [`Solution`]: crate::linear_programming::Solution
+    /// [`Error::Interrupted`]: crate::linear_programming::Error::Interrupted
+    pub fn on_iteration(
+        mut self,
+        callback: impl FnMut(&IterationInfo) -> ControlFlow<()> + Send + 'static,
+    ) -> Self {
+        self.on_iteration = Some(IterationCallback::new(Box::new(callback)));
+        self
+    }
+
+    /// Stop the solver as soon as `cancel` is set, from any thread. The flag is checked before
+    /// every iteration of the simplex and for every column of an LU factorisation; the solver
+    /// then returns [`Error::Interrupted`]. Like [`SolveOptions::on_iteration`], this also
+    /// applies to modifications of the [`Solution`].
+    ///
+    /// [`Solution`]: crate::linear_programming::Solution
+    /// [`Error::Interrupted`]: crate::linear_programming::Error::Interrupted
+    pub fn cancel_token(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Enable or disable measuring the time spent in pricing, ratio tests, FTRAN/BTRAN and LU
+    /// factorisation, reported in [`SolveStats::timings`]. Disabled by default, as reading the
+    /// clock in every iteration is not free.
+    ///
+    /// [`SolveStats::timings`]: crate::linear_programming::SolveStats::timings
+    pub fn timings(mut self, timings: bool) -> Self {
+        self.timings = timings;
+        self
+    }
+
+    /// Set when the LU factorisation of the basis matrix is recomputed.
+    pub fn refactor_policy(mut self, policy: RefactorPolicy) -> Self {
+        self.refactor_policy = policy;
+        self
+    }
+
+    /// Leave basis columns with nonzeros in more than `fraction` of the rows out of the LU
+    /// factorisation. The factors then have a unit column in place of each dense column, and
+    /// the dense columns are put back with one update of the factors each. Factorised with the
+    /// others, a dense column causes fill-in in the factors of the other columns; put back
+    /// afterwards, it only adds its own nonzeros to U. Disabled by default.
+    pub fn dense_column_fraction(mut self, fraction: Option<f64>) -> Self {
+        self.dense_column_fraction = fraction;
+        self
+    }
+
+    /// Set how the LU factorisation of the basis matrix chooses its pivots.
+    pub fn lu_pivoting(mut self, pivoting: LuPivoting) -> Self {
+        self.lu_pivoting = pivoting;
+        self
+    }
+
+    /// Set the order in which the LU factorisation eliminates the columns of the basis matrix.
+    /// Only with [`LuPivoting::ColumnOrder`]; the fill-in of each factorisation is reported in
+    /// [`FactorStats::fill_in`].
+    ///
+    /// [`FactorStats::fill_in`]: crate::linear_programming::FactorStats::fill_in
+    pub fn ordering(mut self, ordering: Ordering) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    /// Reuse the column ordering of an earlier factorisation of the basis matrix as long as
+    /// `policy` allows it. The reuses are reported in [`SolveStats::ordering_cache_hits`].
+    /// Disabled by default. Has no effect with [`Ordering::Natural`] and
+    /// [`Ordering::Explicit`], which cost nothing to compute.
+    ///
+    /// [`SolveStats::ordering_cache_hits`]: crate::linear_programming::SolveStats::ordering_cache_hits
+    pub fn ordering_cache(mut self, policy: Option<OrderingCachePolicy>) -> Self {
+        self.ordering_cache = policy;
+        self
+    }
+
+    /// Bias the choice of the pivot rows of the LU factorisation of the basis matrix: hint `r`
+    /// applies to row `r` of the basis matrix, that is, to constraint `r` of the problem as
+    /// solved, and rows without a hint are [`RowHint::Free`]. A hint only chooses among the
+    /// pivots that pass the threshold test of [`lu_pivot_threshold`](Self::lu_pivot_threshold),
+    /// so it never costs stability. Only with [`LuPivoting::ColumnOrder`]; the resulting row
+    /// order is [`LUFactors::row_perm`]. No hints by default.
+    ///
+    /// [`LUFactors::row_perm`]: crate::linear_programming_lu::LUFactors::row_perm
+    pub fn row_hints(mut self, hints: Option<Vec<RowHint>>) -> Self {
+        self.row_hints = hints;
+        self
+    }
+
+    /// Set how the factors of the basis matrix are updated for a pivot. Both updates give the
+    /// same results, up to rounding with approximate arithmetic.
+    pub fn basis_update(mut self, update: BasisUpdate) -> Self {
+        self.basis_update = update;
+        self
+    }
+
+    /// Enable or disable reusing the symbolic part of the last LU factorisation (the pattern of
+    /// the factors and the pivots) when the basis matrix is factorised again with the same
+    /// pattern, for instance when the values are recomputed for the same basis after the
+    /// right-hand sides or the objective changed. A basis matrix with another pattern, or whose
+    /// values make a pivot unstable, is factorised from scratch. Only with
+    /// [`LuPivoting::ColumnOrder`] and without
+    /// [`dense_column_fraction`](Self::dense_column_fraction). Disabled by default.
+    pub fn reuse_symbolic(mut self, reuse: bool) -> Self {
+        self.reuse_symbolic = reuse;
+        self
+    }
+
+    /// Set the threshold of the LU factorisation for a pivot to be acceptable, 1/10 by default.
+    /// A pivot must have at least `threshold` times the absolute value of the largest entry in
+    /// its column. With exact arithmetic and [`LuPivoting::Markowitz`], magnitude does not
+    /// matter, and instead the size in bits of a pivot times `threshold` must be at most the
+    /// smallest size in its column, which keeps the numbers in the factors small. Closer to 1
+    /// is stricter; closer to 0 leaves more room to avoid fill-in.
+    pub fn lu_pivot_threshold(mut self, threshold: AbnormalFraction) -> Self {
+        self.lu_pivot_threshold = threshold;
+        self
+    }
+
+    /// Enable or disable the preference of [`LuPivoting::Markowitz`] for small pivots in exact
+    /// arithmetic. Among the pivots of the lowest Markowitz cost, it then chooses 1 or -1 if
+    /// possible, and otherwise the pivot with the fewest bits in its numerator and
+    /// denominator, as dividing by a pivot puts its numerator into the denominators of the
+    /// factors. On a totally unimodular basis, the factors then stay integer. Enabled by
+    /// default; has no effect with approximate arithmetic.
+    pub fn small_pivots(mut self, small_pivots: bool) -> Self {
+        self.small_pivots = small_pivots;
+        self
+    }
+
+    /// Leave entries of the LU factors of the basis matrix out if they are below `tolerance`,
+    /// both when factorising and when updating the factors. Only with approximate arithmetic,
+    /// whose factors accumulate entries of the size of rounding errors; they cost time in every
+    /// solve with the factors but barely change its result. The number of dropped entries is
+    /// reported in [`FactorStats`]. As a safeguard, the solution is checked against the
+    /// bounds and constraints, and if it violates them, solved again without dropping.
+    /// Disabled by default, and always with exact arithmetic.
+    ///
+    /// [`FactorStats`]: crate::linear_programming::FactorStats
+    pub fn drop_tolerance(mut self, tolerance: Option<DropTolerance>) -> Self {
+        self.drop_tolerance = tolerance;
+        self
+    }
+
+    /// Estimate the condition number of the basis matrix after each LU factorisation, and log
+    /// a warning if it exceeds `limit`. Only with approximate arithmetic, whose solves lose
+    /// about log10 of the condition number in decimal digits; the largest estimate is reported
+    /// in [`SolveStats::max_condition_estimate`]. A solve with an ill-conditioned basis is a
+    /// candidate to be repeated in exact arithmetic. Disabled by default, as each estimate
+    /// costs a few solves with the factors.
+    ///
+    /// [`SolveStats::max_condition_estimate`]: crate::linear_programming::SolveStats::max_condition_estimate
+    pub fn condition_limit(mut self, limit: Option<f64>) -> Self {
+        self.condition_limit = limit;
+        self
+    }
+
+    /// Enable or disable collecting the size in bits of the coefficients of each LU
+    /// factorisation, reported in [`FactorStats::max_coeff_bits`] and
+    /// [`FactorStats::total_coeff_bits`]. Only with exact arithmetic, where the growth of the
+    /// numerators and denominators in the factors often explains a slow solve. Disabled by
+    /// default, as it takes a pass over all coefficients of each factorisation.
+    ///
+    /// [`FactorStats::max_coeff_bits`]: crate::linear_programming::FactorStats::max_coeff_bits
+    /// [`FactorStats::total_coeff_bits`]: crate::linear_programming::FactorStats::total_coeff_bits
+    pub fn coeff_bit_stats(mut self, coeff_bit_stats: bool) -> Self {
+        self.coeff_bit_stats = coeff_bit_stats;
+        self
+    }
+
+    /// Enable or disable checking the residual ‖B·x − b‖∞ of the solves with the basis matrix
+    /// that compute a column or a row of the tableau. If the residual exceeds 10⁻⁹ times
+    /// 1 + ‖b‖∞, the factors are stale or degraded: the basis matrix is factorised anew and
+    /// the solve repeated, counted in [`SolveStats::residual_refactors`]. Only with
+    /// approximate arithmetic, whose solves are exact otherwise. Disabled by default, as each
+    /// check costs a product with the basis matrix.
+    ///
+    /// [`SolveStats::residual_refactors`]: crate::linear_programming::SolveStats::residual_refactors
+    pub fn check_residuals(mut self, check_residuals: bool) -> Self {
+        self.check_residuals = check_residuals;
+        self
+    }
+
+    /// Enable or disable deterministic solving. Enabled by default.
+    ///
+    /// The simplex makes its choices by iterating over variables and rows in index order and
+    /// breaks ties by the lowest index, also when pricing in parallel; no choice depends on the
+    /// iteration order of a hash map or on pointer values. A deterministic solve of the same
+    /// problem with the same options therefore makes the same pivots on every run, reported in
+    /// [`SolveStats::pivot_hash`]. The only floating-point function whose result may differ
+    /// between platforms is the logarithm used by [`Scaling`]; a deterministic solve computes
+    /// it from the bits of the coefficients instead. With approximate arithmetic, this
+    /// guarantee assumes that the platform implements IEEE 754 arithmetic.
+    ///
+    /// Disabling this only makes scaling use the logarithm of the platform, which is slightly
+    /// more precise.
+    ///
+    /// [`SolveStats::pivot_hash`]: crate::linear_programming::SolveStats::pivot_hash
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+}
@@ -1,31 +1,914 @@
+use std::{
+    fmt::{Debug, Display},
+    time::{Duration, Instant},
+};
+
 use log::trace;
 
-use crate::linear_programming_sparse::{Error, Perm};
+use crate::{
+    abnormal_fraction::AbnormalFraction,
+    linear_programming_lu::{FillEstimate, estimate_fill, estimate_fill_cols},
+    linear_programming_options::{Ordering, OrderingCachePolicy},
+    linear_programming_sparse::{Error, Perm, SparseMat, symmetry_ratio},
+};
+
+/// A strategy for the order in which the LU factorisation eliminates the columns of the basis
+/// matrix, set with [`Ordering::Custom`]. The built-in orderings implement it too, as
+/// variants of [`Ordering`].
+///
+/// ```
+/// # use ebi_optimisation::linear_programming::*;
+/// # use ebi_optimisation::linear_programming_sparse::{Perm, SparseMat};
+/// # use std::sync::Arc;
+/// /// Eliminate the columns with the fewest nonzeros first.
+/// #[derive(Debug)]
+/// struct ByCount;
+///
+/// impl OrderingStrategy for ByCount {
+///     fn order(&self, pattern: &SparseMat) -> Perm {
+///         let mut new2orig = (0..pattern.cols()).collect::<Vec<_>>();
+///         new2orig.sort_by_key(|&c| pattern.col_rows(c).len());
+///         Perm::from_new2orig(new2orig).unwrap()
+///     }
+/// }
+///
+/// let options = SolveOptions::new().ordering(Ordering::Custom(Arc::new(ByCount)));
+/// ```
+pub trait OrderingStrategy: Debug + Send + Sync {
+    /// The order of the columns of the square matrix `pattern`: step `i` of the factorisation
+    /// eliminates column `new2orig()[i]`. The order should depend only on the pattern of the
+    /// matrix, as the factorisation chooses the pivot rows by their values anyway.
+    fn order(&self, pattern: &SparseMat) -> Perm;
+}
+
+impl OrderingStrategy for Ordering {
+    /// # Panics
+    ///
+    /// If the permutation of [`Ordering::Explicit`] or [`Ordering::Custom`] does not have an
+    /// entry for every column of `pattern`.
+    fn order(&self, pattern: &SparseMat) -> Perm {
+        let size = pattern.cols();
+        let mut perm = Perm {
+            orig2new: vec![],
+            new2orig: vec![],
+        };
+        order_with_queue(
+            self,
+            size,
+            |c| (pattern.col_rows(c), pattern.col_data(c)),
+            &mut ColsQueue::new(size),
+            &mut perm,
+        )
+        .unwrap();
+        perm
+    }
+}
+
+/// How one ordering does on a matrix, see [`ordering_report`].
+#[derive(Clone, Debug)]
+pub struct OrderingReport {
+    pub ordering: Ordering,
+    /// The time it took to compute the order.
+    pub time: Duration,
+    /// The numbers of nonzeros of the LU factors in the order, see [`estimate_fill`].
+    pub fill: FillEstimate,
+    /// The bandwidth of the matrix with its rows and columns in the order, see
+    /// [`SparseMat::bandwidth`].
+    pub bandwidth: usize,
+    pub perm: Perm,
+}
+
+/// Order the columns of the square matrix `pattern` with each of `strategies`, to compare them
+/// before choosing one for a family of problems. Print the reports as a table with
+/// [`OrderingTable`].
+///
+/// # Panics
+///
+/// If the permutation of [`Ordering::Explicit`] or [`Ordering::Custom`] does not have an
+/// entry for every column of `pattern`.
+pub fn ordering_report(pattern: &SparseMat, strategies: &[Ordering]) -> Vec<OrderingReport> {
+    (strategies.iter())
+        .map(|ordering| {
+            let start = Instant::now();
+            let perm = ordering.order(pattern);
+            let time = start.elapsed();
+            OrderingReport {
+                ordering: ordering.clone(),
+                time,
+                fill: estimate_fill(pattern, &perm),
+                bandwidth: pattern.bandwidth(Some(&perm)),
+                perm,
+            }
+        })
+        .collect()
+}
+
+/// A table of the reports of [`ordering_report`], one line per ordering.
+pub struct OrderingTable<'a>(pub &'a [OrderingReport]);
+
+impl Display for OrderingTable<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>12} {:>10} {:>10} {:>10} {:>10}",
+            "ordering", "time", "L nnz", "U nnz", "LU nnz", "bandwidth"
+        )?;
+        for report in self.0 {
+            writeln!(
+                f,
+                "{:<24} {:>12.2?} {:>10} {:>10} {:>10} {:>10}",
+                ordering_name(&report.ordering),
+                report.time,
+                report.fill.lower_nnz,
+                report.fill.upper_nnz,
+                report.fill.nnz(),
+                report.bandwidth,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A short name of `ordering`, without the permutation of [`Ordering::Explicit`].
+fn ordering_name(ordering: &Ordering) -> String {
+    match ordering {
+        Ordering::NestedDissection { cutoff } => format!("NestedDissection({})", cutoff),
+        Ordering::Explicit(_) => "Explicit".to_string(),
+        Ordering::Custom(strategy) => format!("Custom({:?})", strategy),
+        ordering => format!("{:?}", ordering),
+    }
+}
+
+/// Order the columns of the square matrix whose columns `get_col` returns with `ordering`,
+/// reusing the storage of `cols_queue` and `perm`. Fails if the permutation of
+/// [`Ordering::Explicit`] or [`Ordering::Custom`] has another size than the matrix.
+pub(crate) fn order_with_queue<'a>(
+    ordering: &Ordering,
+    size: usize,
+    get_col: impl Fn(usize) -> (&'a [usize], &'a [AbnormalFraction]),
+    cols_queue: &mut ColsQueue,
+    perm: &mut Perm,
+) -> Result<(), Error> {
+    let get_rows = |c| get_col(c).0;
+    match ordering {
+        Ordering::Auto => {
+            let preferred = auto_ordering(size, get_rows);
+            if size <= AUTO_ESTIMATE_MAX_SIZE {
+                let other = match preferred {
+                    Ordering::Amd => Ordering::Colamd,
+                    _ => Ordering::Amd,
+                };
+                order_best_estimate(&[preferred, other], size, get_rows, cols_queue, perm);
+            } else {
+                order_builtin(&preferred, size, get_rows, cols_queue, perm);
+            }
+        }
+        Ordering::Explicit(explicit) => perm.clone_from(explicit),
+        Ordering::Custom(strategy) => {
+            let mut pattern = SparseMat::new(size);
+            for c in 0..size {
+                let (rows, vals) = get_col(c);
+                pattern.append_col(rows.iter().copied().zip(vals.iter().cloned()));
+            }
+            *perm = strategy.order(&pattern);
+        }
+        ordering => order_builtin(ordering, size, get_rows, cols_queue, perm),
+    }
+
+    if perm.new2orig.len() != size {
+        return Err(Error::InvalidOrdering {
+            size,
+            len: perm.new2orig.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Order the columns with one of the orderings that only need the pattern and have no choice
+/// to make.
+fn order_builtin<'a>(
+    ordering: &Ordering,
+    size: usize,
+    get_col: impl Fn(usize) -> &'a [usize] + Copy,
+    cols_queue: &mut ColsQueue,
+    perm: &mut Perm,
+) {
+    trace!("ordering {} cols with {:?}", size, ordering);
+    match ordering {
+        Ordering::Natural => {
+            perm.new2orig.clear();
+            perm.new2orig.extend(0..size);
+            perm.orig2new.clear();
+            perm.orig2new.extend(0..size);
+        }
+        Ordering::Current => order_simple_with_queue(size, get_col, cols_queue, perm),
+        Ordering::Amd => order_amd_with_queue(size, get_col, cols_queue, perm),
+        Ordering::Rcm => *perm = order_rcm(size, get_col),
+        Ordering::NestedDissection { cutoff } => {
+            *perm = order_nested_dissection(size, get_col, *cutoff)
+        }
+        Ordering::Colamd => {
+            if order_colamd_with_queue(size, get_col, cols_queue, perm).is_err() {
+                // The matrix is structurally singular; leave it to the factorisation to find
+                // the column without a pivot.
+                order_simple_with_queue(size, get_col, cols_queue, perm);
+            }
+        }
+        Ordering::Auto | Ordering::Explicit(_) | Ordering::Custom(_) => {
+            unreachable!("{:?} is not a built-in ordering", ordering)
+        }
+    }
+}
+
+/// Up to this size of the matrix, [`Ordering::Auto`] computes both candidate orderings and
+/// keeps the one with the smaller [`estimate_fill`](crate::linear_programming_lu::estimate_fill).
+const AUTO_ESTIMATE_MAX_SIZE: usize = 1000;
+
+/// Order the columns with each of the built-in `candidates` and keep the order whose
+/// factors have the fewest nonzeros by a symbolic elimination; ties go to the earlier
+/// candidate. With the `parallel` feature, the candidates are computed concurrently.
+fn order_best_estimate<'a>(
+    candidates: &[Ordering],
+    size: usize,
+    get_col: impl Fn(usize) -> &'a [usize] + Copy,
+    cols_queue: &mut ColsQueue,
+    perm: &mut Perm,
+) {
+    #[cfg(feature = "parallel")]
+    if candidates.len() > 1 {
+        *perm = order_best_estimate_parallel(candidates, size, get_col);
+        return;
+    }
+    order_best_estimate_serial(candidates, size, get_col, cols_queue, perm);
+}
+
+/// Same as [`order_best_estimate`], computing one candidate after the other.
+fn order_best_estimate_serial<'a>(
+    candidates: &[Ordering],
+    size: usize,
+    get_col: impl Fn(usize) -> &'a [usize] + Copy,
+    cols_queue: &mut ColsQueue,
+    perm: &mut Perm,
+) {
+    let mut best: Option<(usize, Perm)> = None;
+    for candidate in candidates {
+        order_builtin(candidate, size, get_col, cols_queue, perm);
+        let nnz = estimate_fill_cols(size, get_col, &perm.new2orig).nnz();
+        trace!("{:?}: estimated nnz of the factors: {}", candidate, nnz);
+        if best.as_ref().is_none_or(|(best_nnz, _)| nnz < *best_nnz) {
+            best = Some((nnz, perm.clone()));
+        }
+    }
+    if let Some((_, best_perm)) = best {
+        *perm = best_perm;
+    }
+}
+
+/// Same as [`order_best_estimate`], computing the candidates in parallel. The result is the
+/// same as that of [`order_best_estimate_serial`]: ties go to the earlier candidate, whichever
+/// finishes first.
+#[cfg(feature = "parallel")]
+fn order_best_estimate_parallel<'a>(
+    candidates: &[Ordering],
+    size: usize,
+    get_col: impl Fn(usize) -> &'a [usize],
+) -> Perm {
+    use rayon::prelude::*;
+
+    // get_col need not be Sync, so the threads share a copy of the pattern.
+    let pattern = (0..size).map(|c| get_col(c).to_vec()).collect::<Vec<_>>();
+    let get_col = |c: usize| pattern[c].as_slice();
+    let results = (candidates.par_iter())
+        .map(|candidate| {
+            let mut perm = Perm {
+                orig2new: vec![],
+                new2orig: vec![],
+            };
+            let mut cols_queue = ColsQueue::new(size);
+            order_builtin(candidate, size, get_col, &mut cols_queue, &mut perm);
+            let nnz = estimate_fill_cols(size, get_col, &perm.new2orig).nnz();
+            trace!("{:?}: estimated nnz of the factors: {}", candidate, nnz);
+            (nnz, perm)
+        })
+        .collect::<Vec<_>>();
+    let (_, best_perm) = (results.into_iter())
+        .reduce(|best, other| if other.0 < best.0 { other } else { best })
+        .unwrap();
+    best_perm
+}
+
+/// The column ordering of the last factorisation that computed one, kept for factorisations
+/// of matrices whose pattern changed little since (see [`OrderingCachePolicy`]).
+#[derive(Clone, Debug, Default)]
+pub(crate) struct OrderingCache {
+    policy: Option<OrderingCachePolicy>,
+    /// The ordering and a hash of the pattern of each column of the matrix it was computed
+    /// for; no ordering if empty.
+    perm: Option<Perm>,
+    col_hashes: Vec<u64>,
+    new_col_hashes: Vec<u64>,
+    num_reuses: usize,
+    last_hit: Option<bool>,
+}
+
+impl OrderingCache {
+    pub(crate) fn set_policy(&mut self, policy: Option<OrderingCachePolicy>) {
+        self.policy = policy;
+        self.clear();
+    }
+
+    /// Forget the cached ordering.
+    pub(crate) fn clear(&mut self) {
+        self.perm = None;
+        self.col_hashes.clear();
+        self.num_reuses = 0;
+    }
+
+    /// Record that the last factorisation did not compute an ordering.
+    pub(crate) fn skip(&mut self) {
+        self.last_hit = None;
+    }
+
+    /// Whether the last ordering was taken from the cache; `None` if the cache was not
+    /// consulted.
+    pub(crate) fn last_hit(&self) -> Option<bool> {
+        self.last_hit
+    }
+
+    /// Like [`order_with_queue`], but reusing the cached ordering if the policy allows it.
+    pub(crate) fn order<'a>(
+        &mut self,
+        ordering: &Ordering,
+        size: usize,
+        get_col: impl Fn(usize) -> (&'a [usize], &'a [AbnormalFraction]),
+        cols_queue: &mut ColsQueue,
+        perm: &mut Perm,
+    ) -> Result<(), Error> {
+        self.last_hit = None;
+        let Some(policy) = &self.policy else {
+            return order_with_queue(ordering, size, get_col, cols_queue, perm);
+        };
+        if matches!(ordering, Ordering::Natural | Ordering::Explicit(_)) {
+            // Nothing to save.
+            return order_with_queue(ordering, size, get_col, cols_queue, perm);
+        }
+
+        self.new_col_hashes.clear();
+        (self.new_col_hashes).extend((0..size).map(|c| col_hash(get_col(c).0)));
+        let num_changed = (self.col_hashes.iter())
+            .zip(&self.new_col_hashes)
+            .filter(|(hash, new_hash)| hash != new_hash)
+            .count();
+        let reusable = self.col_hashes.len() == size
+            && self.num_reuses < policy.max_reuses
+            && (num_changed == 0 || num_changed < policy.max_changed_cols);
+        if let Some(cached) = self.perm.as_ref().filter(|_| reusable) {
+            perm.clone_from(cached);
+            self.num_reuses += 1;
+            self.last_hit = Some(true);
+            return Ok(());
+        }
+
+        order_with_queue(ordering, size, get_col, cols_queue, perm)?;
+        match &mut self.perm {
+            Some(cached) => cached.clone_from(perm),
+            None => self.perm = Some(perm.clone()),
+        }
+        std::mem::swap(&mut self.col_hashes, &mut self.new_col_hashes);
+        self.num_reuses = 0;
+        self.last_hit = Some(false);
+        Ok(())
+    }
+}
+
+/// A hash of the rows of a column (FNV-1a), the same on every run.
+fn col_hash(rows: &[usize]) -> u64 {
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = 0xcbf2_9ce4_8422_2325;
+    for &r in rows {
+        hash = (hash ^ r as u64).wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// The ordering that [`Ordering::Auto`] chooses for the square matrix whose columns `get_col`
+/// returns: [`Ordering::Amd`] if at least half of the off-diagonal nonzeros have a mirror
+/// entry and at least 90% of the diagonal is nonzero, so that the pivots can stay on the
+/// diagonal; [`Ordering::Colamd`] otherwise.
+pub(crate) fn auto_ordering<'a>(size: usize, get_col: impl Fn(usize) -> &'a [usize]) -> Ordering {
+    let num_diag = (0..size).filter(|&c| get_col(c).contains(&c)).count();
+    if symmetry_ratio(size, size, &get_col) >= 0.5 && num_diag as f64 >= 0.9 * size as f64 {
+        Ordering::Amd
+    } else {
+        Ordering::Colamd
+    }
+}
 
 /// Simplest preordering: order columns based on their size
 pub fn order_simple<'a>(size: usize, get_col: impl Fn(usize) -> &'a [usize]) -> Perm {
-    let mut cols_queue = ColsQueue::new(size);
+    let mut perm = Perm {
+        orig2new: vec![],
+        new2orig: vec![],
+    };
+    order_simple_with_queue(size, get_col, &mut ColsQueue::new(size), &mut perm);
+    perm
+}
+
+/// Like [`order_simple`], but reusing the storage of `cols_queue` and `perm`.
+pub(crate) fn order_simple_with_queue<'a>(
+    size: usize,
+    get_col: impl Fn(usize) -> &'a [usize],
+    cols_queue: &mut ColsQueue,
+    perm: &mut Perm,
+) {
+    cols_queue.clear_and_resize(size);
     for c in 0..size {
         cols_queue.add(c, get_col(c).len() - 1);
     }
 
+    perm.new2orig.clear();
+    while perm.new2orig.len() < size {
+        perm.new2orig.push(cols_queue.pop_min().unwrap());
+    }
+
+    perm.orig2new.clear();
+    perm.orig2new.resize(size, 0);
+    for (new, &orig) in perm.new2orig.iter().enumerate() {
+        perm.orig2new[orig] = new;
+    }
+}
+
+/// Approximate minimum degree ordering of the pattern of Bᵀ + B, where B is the square matrix
+/// whose columns `get_col` returns. Suits matrices whose pattern is (nearly) symmetric and
+/// whose pivots can stay on the diagonal.
+pub fn order_amd<'a>(size: usize, get_col: impl Fn(usize) -> &'a [usize]) -> Perm {
+    let mut perm = Perm {
+        orig2new: vec![],
+        new2orig: vec![],
+    };
+    order_amd_with_queue(size, get_col, &mut ColsQueue::new(size), &mut perm);
+    perm
+}
+
+/// Like [`order_amd`], but reusing the storage of `cols_queue` and `perm`.
+pub(crate) fn order_amd_with_queue<'a>(
+    size: usize,
+    get_col: impl Fn(usize) -> &'a [usize],
+    cols_queue: &mut ColsQueue,
+    perm: &mut Perm,
+) {
+    // Implementation of (a part of) the AMD algorithm:
+    //
+    // Amestoy, Patrick R., Timothy A. Davis, and Iain S. Duff. "An approximate minimum
+    // degree ordering algorithm." SIAM Journal on Matrix Analysis and Applications 17.4
+    // (1996): 886-905.
+    //
+    // The elimination is simulated on the quotient graph: an eliminated variable becomes an
+    // element whose variables form a clique, so the graph never grows beyond the pattern of
+    // the matrix. The degree of a variable is bounded from above by its neighbours in the
+    // graph plus the sizes of its elements outside the pivot element.
+    //
+    // TODO:
+    // * supervariables and mass elimination
+    // * dense rows
+
+    let mut var_adj = symmetric_adjacency(size, get_col);
+
+    let mut elem_adj: Vec<Vec<usize>> = vec![vec![]; size];
+    let mut elem_vars: Vec<Vec<usize>> = vec![vec![]; size];
+    let mut is_elim = vec![false; size];
+    let mut is_absorbed = vec![false; size];
+    // For the elements adjacent to the pivot element, the number of their variables outside
+    // of it; valid if the stamp of the element is the current one.
+    let mut outside = vec![0; size];
+    let mut outside_stamp = vec![0; size];
+
+    cols_queue.clear_and_resize(size);
+    let mut degrees = Vec::with_capacity(size);
+    for (i, adj) in var_adj.iter().enumerate() {
+        degrees.push(adj.len());
+        cols_queue.add(i, adj.len());
+    }
+
+    let mut mark = vec![0; size];
+    let mut pivot_elem = vec![];
+    let mut num_absorbed = 0;
+
+    perm.new2orig.clear();
+    while let Some(pivot) = cols_queue.pop_min() {
+        let stamp = perm.new2orig.len() + 1;
+        perm.new2orig.push(pivot);
+        is_elim[pivot] = true;
+
+        // The variables of the new element: the neighbours of the pivot and the variables of
+        // its elements, which the new element absorbs.
+        pivot_elem.clear();
+        mark[pivot] = stamp;
+        for &j in &var_adj[pivot] {
+            if !is_elim[j] && std::mem::replace(&mut mark[j], stamp) != stamp {
+                pivot_elem.push(j);
+            }
+        }
+        for &e in &elem_adj[pivot] {
+            if !std::mem::replace(&mut is_absorbed[e], true) {
+                num_absorbed += 1;
+                for &j in &elem_vars[e] {
+                    if !is_elim[j] && std::mem::replace(&mut mark[j], stamp) != stamp {
+                        pivot_elem.push(j);
+                    }
+                }
+                elem_vars[e] = vec![];
+            }
+        }
+        var_adj[pivot] = vec![];
+        elem_adj[pivot] = vec![];
+
+        // Count the variables of the other elements outside of the new element.
+        for &i in &pivot_elem {
+            for &e in &elem_adj[i] {
+                if is_absorbed[e] {
+                    continue;
+                }
+                if outside_stamp[e] != stamp {
+                    outside_stamp[e] = stamp;
+                    elem_vars[e].retain(|&j| !is_elim[j]);
+                    outside[e] = elem_vars[e].len();
+                }
+                outside[e] -= 1;
+            }
+        }
+
+        // Update the approximate degrees.
+        let num_left = size - perm.new2orig.len();
+        for &i in &pivot_elem {
+            cols_queue.remove(i, degrees[i]);
+
+            let mut external = 0;
+            for &e in &elem_adj[i] {
+                if is_absorbed[e] {
+                    continue;
+                }
+                if outside[e] == 0 {
+                    // aggressive absorption: all variables of e are in the new element.
+                    is_absorbed[e] = true;
+                    num_absorbed += 1;
+                    elem_vars[e] = vec![];
+                } else {
+                    external += outside[e];
+                }
+            }
+            elem_adj[i].retain(|&e| !is_absorbed[e]);
+            elem_adj[i].push(pivot);
+
+            // Neighbours in the new element are reached through it.
+            var_adj[i].retain(|&j| !is_elim[j] && mark[j] != stamp);
+            external += var_adj[i].len();
+
+            let degree = (pivot_elem.len() - 1 + external)
+                .min(degrees[i] + pivot_elem.len() - 1)
+                .min(num_left - 1);
+            degrees[i] = degree;
+            cols_queue.add(i, degree);
+        }
+        elem_vars[pivot] = pivot_elem.clone();
+    }
+
+    perm.orig2new.clear();
+    perm.orig2new.resize(size, 0);
+    for (new, &orig) in perm.new2orig.iter().enumerate() {
+        perm.orig2new[orig] = new;
+    }
+
+    trace!(
+        "AMD: ordered {} cols, absorbed elements: {}",
+        size, num_absorbed
+    );
+}
+
+/// Reverse Cuthill–McKee ordering of the pattern of Bᵀ + B, where B is the square matrix whose
+/// columns `get_col` returns. Numbers the columns in breadth-first order from a
+/// pseudo-peripheral column, which keeps the nonzeros of a matrix with a (nearly) banded
+/// pattern in a narrow band and confines the fill-in to it. Each connected component is
+/// ordered separately.
+pub fn order_rcm<'a>(size: usize, get_col: impl Fn(usize) -> &'a [usize]) -> Perm {
+    // Cuthill, Elizabeth, and James McKee. "Reducing the bandwidth of sparse symmetric
+    // matrices." Proceedings of the 1969 24th national conference (1969): 157-172.
+    //
+    // The starting column of each component is found with the heuristic of:
+    //
+    // George, Alan, and Joseph W. H. Liu. "An implementation of a pseudoperipheral node
+    // finder." ACM Transactions on Mathematical Software 5.3 (1979): 284-295.
+
+    let adj = symmetric_adjacency(size, get_col);
+
     let mut new2orig = Vec::with_capacity(size);
-    while new2orig.len() < size {
-        new2orig.push(cols_queue.pop_min().unwrap());
+    let mut is_ordered = vec![false; size];
+    let mut levels = LevelStructure::new(size);
+    let mut num_components = 0;
+    let mut by_degree = (0..size).collect::<Vec<_>>();
+    by_degree.sort_by_key(|&c| adj[c].len());
+    for &start in &by_degree {
+        if is_ordered[start] {
+            continue;
+        }
+        num_components += 1;
+
+        let start = levels.build_pseudo_peripheral(start, &adj, |_| true);
+
+        // Breadth-first search, visiting the neighbours in the order of their degrees.
+        let component_begin = new2orig.len();
+        new2orig.push(start);
+        is_ordered[start] = true;
+        let mut next = component_begin;
+        while next < new2orig.len() {
+            let c = new2orig[next];
+            next += 1;
+            let neighbours_begin = new2orig.len();
+            for &other_c in &adj[c] {
+                if !std::mem::replace(&mut is_ordered[other_c], true) {
+                    new2orig.push(other_c);
+                }
+            }
+            new2orig[neighbours_begin..].sort_by_key(|&c| adj[c].len());
+        }
     }
+    new2orig.reverse();
 
     let mut orig2new = vec![0; size];
     for (new, &orig) in new2orig.iter().enumerate() {
         orig2new[orig] = new;
     }
 
+    trace!(
+        "RCM: ordered {} cols, components: {}",
+        size, num_components
+    );
+
     Perm { orig2new, new2orig }
 }
 
+/// Nested dissection ordering of the pattern of Bᵀ + B, where B is the square matrix whose
+/// columns `get_col` returns. Splits the graph of the pattern in two halves with a separator
+/// and orders the halves, recursively, before the separator; parts of at most `cutoff` columns
+/// are ordered with [`order_amd`]. The fill-in of eliminating a half stays within the half and
+/// the separator, so on large matrices whose pattern has small separators, such as grids, the
+/// order causes less fill-in than a minimum degree order. Each connected component is ordered
+/// separately.
+pub fn order_nested_dissection<'a>(
+    size: usize,
+    get_col: impl Fn(usize) -> &'a [usize],
+    cutoff: usize,
+) -> Perm {
+    // George, Alan. "Nested dissection of a regular finite element mesh." SIAM Journal on
+    // Numerical Analysis 10.2 (1973): 345-363.
+    //
+    // The separator is a level of the level structure of a pseudo-peripheral column near the
+    // middle, without the columns of that level that have no neighbour in the next one.
+
+    let adj = symmetric_adjacency(size, get_col);
+
+    let mut new2orig = vec![0; size];
+    // The parts left to order, with the position in new2orig of their first column. Each part
+    // has a number in part_of; the separators keep the number of the part they split.
+    let mut parts = vec![];
+    if size > 0 {
+        parts.push((0, (0..size).collect::<Vec<_>>()));
+    }
+    let mut part_of = vec![0; size];
+    let mut num_parts = 1;
+    let mut levels = LevelStructure::new(size);
+    let mut mark = vec![0; size];
+    let mut num_marks = 0;
+    let mut local = vec![0; size];
+    let mut num_separators = 0;
+    while let Some((begin, cols)) = parts.pop() {
+        let part = part_of[cols[0]];
+
+        if cols.len() > cutoff {
+            // Split a disconnected part into its components.
+            num_marks += 1;
+            let mut components = vec![];
+            for &c in &cols {
+                if mark[c] != num_marks {
+                    levels.build(c, &adj, |c| part_of[c] == part);
+                    for &other_c in &levels.cols {
+                        mark[other_c] = num_marks;
+                    }
+                    components.push(levels.cols.clone());
+                }
+            }
+            if components.len() > 1 {
+                let mut component_begin = begin;
+                for component in components {
+                    for &c in &component {
+                        part_of[c] = num_parts;
+                    }
+                    num_parts += 1;
+                    let len = component.len();
+                    parts.push((component_begin, component));
+                    component_begin += len;
+                }
+                continue;
+            }
+
+            levels.build_pseudo_peripheral(cols[0], &adj, |c| part_of[c] == part);
+            let num_levels = levels.num_levels();
+            if num_levels >= 3 {
+                // Neither the first nor the last level: the smallest level that leaves at most
+                // two thirds of the columns on either side, the closest one to the middle of
+                // those, or else the level after which more than half of the columns are
+                // visited.
+                let len = cols.len();
+                let middle = (1..num_levels - 1)
+                    .find(|&l| levels.level_begins[l + 1] > len / 2)
+                    .unwrap_or(num_levels - 2);
+                let mid = (1..num_levels - 1)
+                    .filter(|&l| {
+                        let before = levels.level_begins[l];
+                        let after = len - levels.level_begins[l + 1];
+                        3 * before.max(after) <= 2 * len
+                    })
+                    .min_by_key(|&l| (levels.level(l).len(), l.abs_diff(middle)))
+                    .unwrap_or(middle);
+                num_marks += 1;
+                for &c in levels.level(mid + 1) {
+                    mark[c] = num_marks;
+                }
+                let mut first = levels.cols[..levels.level_begins[mid]].to_vec();
+                let second = levels.cols[levels.level_begins[mid + 1]..].to_vec();
+                let mut separator = vec![];
+                for &c in levels.level(mid) {
+                    if adj[c].iter().any(|&other_c| mark[other_c] == num_marks) {
+                        separator.push(c);
+                    } else {
+                        first.push(c);
+                    }
+                }
+                num_separators += 1;
+
+                let separator_begin = begin + first.len() + second.len();
+                new2orig[separator_begin..begin + cols.len()].copy_from_slice(&separator);
+                let second_begin = begin + first.len();
+                for (half_begin, half) in [(begin, first), (second_begin, second)] {
+                    for &c in &half {
+                        part_of[c] = num_parts;
+                    }
+                    num_parts += 1;
+                    parts.push((half_begin, half));
+                }
+                continue;
+            }
+            // The part is too compact to have a small separator.
+        }
+
+        for (i, &c) in cols.iter().enumerate() {
+            local[c] = i;
+        }
+        let local_adj = (cols.iter())
+            .map(|&c| {
+                (adj[c].iter())
+                    .filter(|&&other_c| part_of[other_c] == part)
+                    .map(|&other_c| local[other_c])
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let amd = order_amd(cols.len(), |i| local_adj[i].as_slice());
+        for (new, &i) in amd.new2orig.iter().enumerate() {
+            new2orig[begin + new] = cols[i];
+        }
+    }
+
+    let mut orig2new = vec![0; size];
+    for (new, &orig) in new2orig.iter().enumerate() {
+        orig2new[orig] = new;
+    }
+
+    trace!(
+        "nested dissection: ordered {} cols, separators: {}",
+        size, num_separators
+    );
+
+    Perm { orig2new, new2orig }
+}
+
+/// The level structure of a breadth-first search: the columns by their distance from the root.
+struct LevelStructure {
+    cols: Vec<usize>,
+    /// The start of each level in `cols`, and the end of the last.
+    level_begins: Vec<usize>,
+    /// The number of the last search that visited each column.
+    visited_on: Vec<usize>,
+    num_searches: usize,
+}
+
+impl LevelStructure {
+    fn new(size: usize) -> LevelStructure {
+        LevelStructure {
+            cols: Vec::with_capacity(size),
+            level_begins: vec![],
+            visited_on: vec![0; size],
+            num_searches: 0,
+        }
+    }
+
+    /// Search the component of `root` in the subgraph of `adj` induced by the columns for which
+    /// `is_in` holds.
+    fn build(&mut self, root: usize, adj: &[Vec<usize>], is_in: impl Fn(usize) -> bool) {
+        self.cols.clear();
+        self.level_begins.clear();
+        self.num_searches += 1;
+        self.cols.push(root);
+        self.visited_on[root] = self.num_searches;
+        let mut level_begin = 0;
+        while level_begin < self.cols.len() {
+            self.level_begins.push(level_begin);
+            let level_end = self.cols.len();
+            for i in level_begin..level_end {
+                for &c in &adj[self.cols[i]] {
+                    if self.visited_on[c] != self.num_searches && is_in(c) {
+                        self.visited_on[c] = self.num_searches;
+                        self.cols.push(c);
+                    }
+                }
+            }
+            level_begin = level_end;
+        }
+        self.level_begins.push(self.cols.len());
+    }
+
+    /// Build the level structure of a pseudo-peripheral column in the component of `start`:
+    /// move the root to the last level of its level structure as long as that makes the
+    /// structure deeper. Returns the root.
+    fn build_pseudo_peripheral(
+        &mut self,
+        start: usize,
+        adj: &[Vec<usize>],
+        is_in: impl Fn(usize) -> bool + Copy,
+    ) -> usize {
+        let mut root = start;
+        self.build(root, adj, is_in);
+        loop {
+            let last_level = self.level(self.num_levels() - 1);
+            let candidate = *last_level.iter().min_by_key(|&&c| adj[c].len()).unwrap();
+            let depth = self.num_levels();
+            self.build(candidate, adj, is_in);
+            if self.num_levels() > depth {
+                root = candidate;
+            } else {
+                self.build(root, adj, is_in);
+                return root;
+            }
+        }
+    }
+
+    fn num_levels(&self) -> usize {
+        self.level_begins.len() - 1
+    }
+
+    fn level(&self, i: usize) -> &[usize] {
+        &self.cols[self.level_begins[i]..self.level_begins[i + 1]]
+    }
+}
+
+/// For each column of the square matrix whose columns `get_col` returns, its neighbours in the
+/// pattern of Bᵀ + B without the diagonal.
+fn symmetric_adjacency<'a>(size: usize, get_col: impl Fn(usize) -> &'a [usize]) -> Vec<Vec<usize>> {
+    let mut adj = vec![vec![]; size];
+    for c in 0..size {
+        for &r in get_col(c) {
+            if r != c {
+                adj[c].push(r);
+                adj[r].push(c);
+            }
+        }
+    }
+    let mut mark = vec![0; size];
+    for (i, neighbours) in adj.iter_mut().enumerate() {
+        neighbours.retain(|&j| std::mem::replace(&mut mark[j], i + 1) != i + 1);
+    }
+    adj
+}
+
 pub fn order_colamd<'a>(
     size: usize,
     get_col: impl Fn(usize) -> &'a [usize],
 ) -> Result<Perm, Error> {
+    let mut perm = Perm {
+        orig2new: vec![],
+        new2orig: vec![],
+    };
+    order_colamd_with_queue(size, get_col, &mut ColsQueue::new(size), &mut perm)?;
+    Ok(perm)
+}
+
+/// Like [`order_colamd`], but reusing the storage of `cols_queue` and `perm`, whose contents
+/// are unspecified on error.
+pub(crate) fn order_colamd_with_queue<'a>(
+    size: usize,
+    get_col: impl Fn(usize) -> &'a [usize],
+    cols_queue: &mut ColsQueue,
+    perm: &mut Perm,
+) -> Result<(), Error> {
     // Implementation of (a part of) the COLAMD algorithm:
     //
     // "An approximate minimum degree column ordering algorithm",
@@ -48,7 +931,9 @@ pub fn order_colamd<'a>(
     let mut cols = vec![Slice { begin: 0, end: 0 }; size];
     let mut row_storage = vec![];
 
-    let mut new2orig = vec![0; size];
+    let new2orig = &mut perm.new2orig;
+    new2orig.clear();
+    new2orig.resize(size, 0);
     let mut cur_ordered_col = 0;
     let mut is_ordered_col = vec![false; size];
 
@@ -69,7 +954,11 @@ pub fn order_colamd<'a>(
 
             let rows_end = row_storage.len();
             if rows_end - rows_begin == 0 {
-                return Err(Error::SingularMatrix);
+                return Err(Error::SingularMatrix {
+                    step: cur_ordered_col,
+                    row: None,
+                    col: Some(c),
+                });
             } else if rows_end - rows_begin > 1 {
                 cols[c].begin = rows_begin;
                 cols[c].end = rows_end;
@@ -90,7 +979,11 @@ pub fn order_colamd<'a>(
 
     for r in 0..size {
         if rows[r].end == 0 && !is_absorbed_row[r] {
-            return Err(Error::SingularMatrix);
+            return Err(Error::SingularMatrix {
+                step: cur_ordered_col,
+                row: Some(r),
+                col: None,
+            });
         }
     }
 
@@ -104,8 +997,8 @@ pub fn order_colamd<'a>(
             rows[i].end += prev_end;
         }
 
-        for c in 0..size {
-            for &r in cols[c].elems(&row_storage) {
+        for (c, col) in cols.iter().enumerate().take(size) {
+            for &r in col.elems(&row_storage) {
                 let row = &mut rows[r];
                 col_storage[row.begin] = c;
                 row.begin += 1;
@@ -133,8 +1026,7 @@ pub fn order_colamd<'a>(
             // all columns on the stack are of size 1
             stack.clear();
             stack.push(c);
-            while !stack.is_empty() {
-                let c = stack.pop().unwrap();
+            while let Some(c) = stack.pop() {
                 let r = *cols[c]
                     .elems(&row_storage)
                     .iter()
@@ -172,7 +1064,7 @@ pub fn order_colamd<'a>(
         }
     }
 
-    let mut cols_queue = ColsQueue::new(cols.len());
+    cols_queue.clear_and_resize(cols.len());
     let mut num_dense_cols = 0;
     let mut num_cols_only_dense_rows = 0;
 
@@ -367,16 +1259,17 @@ pub fn order_colamd<'a>(
         }
     }
 
-    let mut orig2new = vec![0; size];
-    for (new, &orig) in new2orig.iter().enumerate() {
-        orig2new[orig] = new;
+    perm.orig2new.clear();
+    perm.orig2new.resize(size, 0);
+    for (new, &orig) in perm.new2orig.iter().enumerate() {
+        perm.orig2new[orig] = new;
     }
 
     trace!(
         "COLAMD: ordered {} cols, singletons: {} (cheap: {}), dense_rows: {}, dense_cols: {}, cols_only_dense_rows: {}, mass_eliminated: {}",
         size, num_singletons, num_cheap_singletons, num_dense_rows, num_dense_cols, num_cols_only_dense_rows, num_mass_eliminated);
 
-    Ok(Perm { orig2new, new2orig })
+    Ok(())
 }
 
 #[derive(Clone, Debug)]
@@ -391,8 +1284,10 @@ impl Slice {
     }
 }
 
-#[derive(Debug)]
-struct ColsQueue {
+/// Columns by an integer score below the number of columns, for popping a column with the
+/// minimum score. Columns with equal scores come out in the order in which they were added.
+#[derive(Clone, Debug)]
+pub(crate) struct ColsQueue {
     score2head: Vec<Option<usize>>,
     prev: Vec<usize>,
     next: Vec<usize>,
@@ -401,7 +1296,7 @@ struct ColsQueue {
 }
 
 impl ColsQueue {
-    fn new(num_cols: usize) -> ColsQueue {
+    pub(crate) fn new(num_cols: usize) -> ColsQueue {
         ColsQueue {
             score2head: vec![None; num_cols],
             prev: vec![0; num_cols],
@@ -411,11 +1306,20 @@ impl ColsQueue {
         }
     }
 
+    pub(crate) fn clear_and_resize(&mut self, num_cols: usize) {
+        self.score2head.clear();
+        self.score2head.resize(num_cols, None);
+        self.prev.resize(num_cols, 0);
+        self.next.resize(num_cols, 0);
+        self.min_score = num_cols;
+        self.len = 0;
+    }
+
     fn len(&self) -> usize {
         self.len
     }
 
-    fn pop_min(&mut self) -> Option<usize> {
+    pub(crate) fn pop_min(&mut self) -> Option<usize> {
         let col = loop {
             if self.min_score >= self.score2head.len() {
                 return None;
@@ -430,7 +1334,7 @@ impl ColsQueue {
         Some(col)
     }
 
-    fn add(&mut self, col: usize, score: usize) {
+    pub(crate) fn add(&mut self, col: usize, score: usize) {
         self.min_score = std::cmp::min(self.min_score, score);
         self.len += 1;
 
@@ -446,7 +1350,7 @@ impl ColsQueue {
         }
     }
 
-    fn remove(&mut self, col: usize, score: usize) {
+    pub(crate) fn remove(&mut self, col: usize, score: usize) {
         self.len -= 1;
         if self.next[col] == col {
             self.score2head[score] = None;
@@ -553,6 +1457,8 @@ pub struct BlockDiagForm {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::f1_ab;
+    use ebi_arithmetic::One;
     use sprs::{CsMat, TriMat};
 
     fn mat_from_triplets(rows: usize, cols: usize, triplets: &[(usize, usize)]) -> CsMat<f64> {
@@ -563,6 +1469,108 @@ mod tests {
         mat.to_csc()
     }
 
+    #[test]
+    fn amd() {
+        // Arrowhead: eliminating the hub 0 first would fill the whole matrix, eliminating it
+        // last causes no fill-in.
+        let size = 5;
+        let mut triplets = vec![];
+        for i in 0..size {
+            triplets.push((i, i));
+            if i > 0 {
+                triplets.push((0, i));
+                triplets.push((i, 0));
+            }
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+
+        let perm = order_amd(size, |c| mat.outer_view(c).unwrap().into_raw_storage().0);
+        assert_eq!(&perm.new2orig, &[1, 2, 3, 4, 0]);
+        assert_eq!(&perm.orig2new, &[4, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn rcm() {
+        use crate::rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+        // Two paths of 10 and 5 columns, the pattern of a Laplacian, with the columns numbered
+        // at random.
+        let size = 15;
+        let mut labels = (0..size).collect::<Vec<_>>();
+        labels.shuffle(&mut StdRng::seed_from_u64(674));
+        let mut triplets = vec![];
+        for i in 0..size {
+            triplets.push((labels[i], labels[i]));
+            if i + 1 < size && i != 9 {
+                triplets.push((labels[i], labels[i + 1]));
+                triplets.push((labels[i + 1], labels[i]));
+            }
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage().0;
+
+        let perm = order_rcm(size, get_col);
+        let mut new2orig = perm.new2orig.clone();
+        new2orig.sort();
+        assert_eq!(new2orig, (0..size).collect::<Vec<_>>());
+
+        // Each path ends up in consecutive positions, numbered from one end.
+        let bandwidth = (0..size)
+            .flat_map(|c| get_col(c).iter().map(move |&r| (r, c)))
+            .map(|(r, c)| perm.orig2new[r].abs_diff(perm.orig2new[c]))
+            .max()
+            .unwrap();
+        assert!(bandwidth <= 2, "bandwidth {}", bandwidth);
+    }
+
+    #[test]
+    fn nested_dissection() {
+        // A path of 7 columns: the middle column separates the path, and the middles of the
+        // halves separate those.
+        let size = 7;
+        let mut triplets = vec![];
+        for i in 0..size {
+            triplets.push((i, i));
+            if i + 1 < size {
+                triplets.push((i, i + 1));
+                triplets.push((i + 1, i));
+            }
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage().0;
+        let perm = order_nested_dissection(size, get_col, 2);
+        assert_eq!(&perm.new2orig, &[0, 2, 1, 4, 6, 5, 3]);
+        assert_eq!(
+            order_nested_dissection(size, get_col, size).new2orig,
+            order_amd(size, get_col).new2orig
+        );
+
+        // Two paths of 8 and 5 columns and three columns without neighbours.
+        let size = 16;
+        let mut triplets = vec![];
+        for i in 0..size {
+            triplets.push((i, i));
+            if i + 1 < 13 && i != 7 {
+                triplets.push((i, i + 1));
+                triplets.push((i + 1, i));
+            }
+        }
+        let mat = mat_from_triplets(size, size, &triplets);
+        let perm = order_nested_dissection(
+            size,
+            |c| mat.outer_view(c).unwrap().into_raw_storage().0,
+            1,
+        );
+        let mut new2orig = perm.new2orig.clone();
+        new2orig.sort();
+        assert_eq!(new2orig, (0..size).collect::<Vec<_>>());
+        for (new, &orig) in perm.new2orig.iter().enumerate() {
+            assert_eq!(perm.orig2new[orig], new);
+        }
+
+        assert!(order_nested_dissection(0, |_| &[], 1).new2orig.is_empty());
+    }
+
     #[test]
     fn colamd() {
         let mat = mat_from_triplets(
@@ -600,7 +1608,14 @@ mod tests {
             let res = order_colamd(3, |c| {
                 empty_col_mat.outer_view(c).unwrap().into_raw_storage().0
             });
-            assert_eq!(res.unwrap_err(), Error::SingularMatrix);
+            assert_eq!(
+                res.unwrap_err(),
+                Error::SingularMatrix {
+                    step: 1,
+                    row: None,
+                    col: Some(2),
+                }
+            );
         }
 
         {
@@ -608,10 +1623,87 @@ mod tests {
             let res = order_colamd(3, |c| {
                 empty_row_mat.outer_view(c).unwrap().into_raw_storage().0
             });
-            assert_eq!(res.unwrap_err(), Error::SingularMatrix);
+            assert_eq!(
+                res.unwrap_err(),
+                Error::SingularMatrix {
+                    step: 1,
+                    row: Some(2),
+                    col: None,
+                }
+            );
         }
     }
 
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn best_estimate_parallel() {
+        use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+
+        let mut rng = StdRng::seed_from_u64(679);
+        for size in [1, 2, 10, 50, 200, 1000] {
+            for _ in 0..3 {
+                let mut triplets = (0..size).map(|i| (i, i)).collect::<Vec<_>>();
+                for _ in 0..2 * size {
+                    triplets.push((rng.random_range(0..size), rng.random_range(0..size)));
+                }
+                let mat = mat_from_triplets(size, size, &triplets);
+                let get_col = |c| mat.outer_view(c).unwrap().into_raw_storage().0;
+
+                let mut serial = Perm {
+                    orig2new: vec![],
+                    new2orig: vec![],
+                };
+                let mut cols_queue = ColsQueue::new(size);
+                let candidates = [Ordering::Amd, Ordering::Colamd, Ordering::Rcm];
+                order_best_estimate_serial(
+                    &candidates,
+                    size,
+                    get_col,
+                    &mut cols_queue,
+                    &mut serial,
+                );
+                let parallel = order_best_estimate_parallel(&candidates, size, get_col);
+                assert_eq!(parallel, serial, "size {}", size);
+            }
+        }
+    }
+
+    #[test]
+    fn report() {
+        // The arrowhead of the test of AMD: eliminating the hub last causes no fill-in.
+        let size = 5;
+        let mut pattern = SparseMat::new(size);
+        for c in 0..size {
+            if c == 0 {
+                pattern.append_col((0..size).map(|r| (r, f1_ab!())));
+            } else {
+                pattern.append_col([(0, f1_ab!()), (c, f1_ab!())]);
+            }
+        }
+
+        let reports = ordering_report(&pattern, &[Ordering::Natural, Ordering::Amd]);
+        assert_eq!(reports.len(), 2);
+        let natural_perm = Perm::from_new2orig((0..size).collect()).unwrap();
+        assert_eq!(reports[0].ordering, Ordering::Natural);
+        assert_eq!(reports[0].perm, natural_perm);
+        assert_eq!(reports[0].fill, estimate_fill(&pattern, &natural_perm));
+        assert_eq!(reports[0].bandwidth, 4);
+        assert_eq!(reports[1].ordering, Ordering::Amd);
+        assert_eq!(&reports[1].perm.new2orig, &[1, 2, 3, 4, 0]);
+        assert_eq!(
+            reports[1].fill,
+            FillEstimate {
+                lower_nnz: 4,
+                upper_nnz: 9,
+            }
+        );
+        assert_eq!(reports[1].bandwidth, 4);
+
+        let table = OrderingTable(&reports).to_string();
+        assert_eq!(table.lines().count(), 3);
+        assert!(table.lines().nth(2).unwrap().starts_with("Amd "));
+    }
+
     #[test]
     fn diag_matching() {
         let size = 3;
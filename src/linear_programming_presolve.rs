@@ -0,0 +1,623 @@
+//! Cheap reductions of a problem before it is handed to the simplex.
+//!
+//! Models that are generated mechanically contain a lot of structure that can be removed
+//! without any simplex iteration: variables fixed by their bounds, rows that only bound a
+//! single variable, empty rows and columns, duplicate rows and, optionally, equalities that
+//! follow from other equalities. The reductions are recorded such that the solution of the
+//! reduced problem can be expressed in terms of the original variables.
+
+use ebi_arithmetic::{One, Signed, Zero};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    abnormal_fraction::AbnormalFraction,
+    f0_ab, f1_ab,
+    linear_programming::{Constraint, CsVec, Error},
+    linear_programming_certificate::InfeasibilityCertificate,
+    linear_programming_lu::RowEliminator,
+};
+
+/// A single reduction, in the order in which they were applied.
+#[derive(Clone, Debug)]
+pub(crate) enum Reduction {
+    /// The var had equal bounds (possibly after tightening by singleton rows). `col` holds its
+    /// entries in the rows that remained at that point, for its reduced cost.
+    FixedVar {
+        var: usize,
+        val: AbnormalFraction,
+        obj_coeff: AbnormalFraction,
+        col: Vec<(usize, AbnormalFraction)>,
+    },
+    /// The var did not occur in any remaining row and was moved to its best bound.
+    EmptyCol {
+        var: usize,
+        val: AbnormalFraction,
+        obj_coeff: AbnormalFraction,
+    },
+    /// The row contained a single var and was turned into the bounds `lower` and `upper` on
+    /// that var, which may be infinite.
+    SingletonRow {
+        row: usize,
+        var: usize,
+        coeff: AbnormalFraction,
+        lower: AbnormalFraction,
+        upper: AbnormalFraction,
+    },
+    /// The row contained no vars (after substituting fixed vars) and was satisfied.
+    EmptyRow,
+    /// The row was identical to an earlier row.
+    DuplicateRow,
+    /// The row was an equality that followed from earlier equalities.
+    DependentRow,
+}
+
+#[derive(Clone, Debug)]
+enum VarMapping {
+    /// Index of the var in the reduced problem.
+    Kept(usize),
+    /// Index of the reduction that removed the var.
+    Removed(usize),
+}
+
+/// The information needed to translate between the original and the reduced problem.
+#[derive(Clone, Debug)]
+pub(crate) struct Postsolve {
+    reductions: Vec<Reduction>,
+    vars: Vec<VarMapping>,
+    /// For each row: its index in the reduced problem, if it was not removed.
+    rows: Vec<Option<usize>>,
+    num_reduced_vars: usize,
+    /// The bounds of the vars after tightening by the singleton rows.
+    var_mins: Vec<AbnormalFraction>,
+    var_maxs: Vec<AbnormalFraction>,
+    /// Contribution of the removed vars to the (minimised) objective.
+    pub(crate) obj_offset: AbnormalFraction,
+}
+
+pub(crate) struct Presolved {
+    pub(crate) obj_coeffs: Vec<AbnormalFraction>,
+    pub(crate) var_mins: Vec<AbnormalFraction>,
+    pub(crate) var_maxs: Vec<AbnormalFraction>,
+    pub(crate) constraints: Vec<Constraint>,
+    pub(crate) postsolve: Postsolve,
+}
+
+/// The non-zero entries of a row and its bounds.
+type Row = (
+    Vec<(usize, AbnormalFraction)>,
+    AbnormalFraction,
+    AbnormalFraction,
+);
+
+pub(crate) fn presolve(
+    obj_coeffs: &[AbnormalFraction],
+    var_mins: &[AbnormalFraction],
+    var_maxs: &[AbnormalFraction],
+    constraints: &[Constraint],
+    remove_dependent: bool,
+) -> Result<Presolved, Error> {
+    let num_vars = obj_coeffs.len();
+    let mut var_mins = var_mins.to_vec();
+    let mut var_maxs = var_maxs.to_vec();
+    let mut removed_by: Vec<Option<usize>> = vec![None; num_vars];
+    let mut row_is_removed = vec![false; constraints.len()];
+    let mut reductions = vec![];
+
+    let mut rows: Vec<Row> = constraints
+        .iter()
+        .map(|constraint| {
+            let entries = constraint
+                .coeffs
+                .iter()
+                .filter(|(_, coeff)| !coeff.is_zero())
+                .map(|(var, coeff)| (var, coeff.clone()))
+                .collect();
+            (entries, constraint.min.clone(), constraint.max.clone())
+        })
+        .collect();
+
+    // Fixing a var may create new singleton or empty rows, and a singleton row may fix a var,
+    // so repeat until nothing changes.
+    loop {
+        let mut changed = false;
+
+        for var in 0..num_vars {
+            if removed_by[var].is_none()
+                && var_mins[var].is_finite()
+                && var_mins[var] == var_maxs[var]
+            {
+                let val = var_mins[var].clone();
+                let col = substitute(&mut rows, &row_is_removed, var, &val);
+                removed_by[var] = Some(reductions.len());
+                reductions.push(Reduction::FixedVar {
+                    var,
+                    val,
+                    obj_coeff: obj_coeffs[var].clone(),
+                    col,
+                });
+                changed = true;
+            }
+        }
+
+        for row in 0..rows.len() {
+            if row_is_removed[row] {
+                continue;
+            }
+
+            let (entries, min, max) = &rows[row];
+            match entries.len() {
+                0 => {
+                    if min.is_positive() || max.is_negative() {
+                        let mut multipliers = vec![f0_ab!(); rows.len()];
+                        multipliers[row] = if min.is_positive() {
+                            f1_ab!()
+                        } else {
+                            -f1_ab!()
+                        };
+                        return Err(infeasible(&reductions, &var_mins, &var_maxs, multipliers));
+                    }
+                    reductions.push(Reduction::EmptyRow);
+                }
+                1 => {
+                    let (var, coeff) = &entries[0];
+                    let var = *var;
+                    // Dividing by a negative coefficient swaps the bounds.
+                    let (min, max) = if coeff.is_negative() {
+                        (max, min)
+                    } else {
+                        (min, max)
+                    };
+                    let lower = if min.is_finite() {
+                        min / coeff
+                    } else {
+                        AbnormalFraction::neg_infinity()
+                    };
+                    let upper = if max.is_finite() {
+                        max / coeff
+                    } else {
+                        AbnormalFraction::infinity()
+                    };
+                    if lower > var_mins[var] {
+                        var_mins[var] = lower.clone();
+                    }
+                    if upper < var_maxs[var] {
+                        var_maxs[var] = upper.clone();
+                    }
+                    reductions.push(Reduction::SingletonRow {
+                        row,
+                        var,
+                        coeff: coeff.clone(),
+                        lower,
+                        upper,
+                    });
+                    if var_mins[var] > var_maxs[var] {
+                        let multipliers =
+                            bound_conflict(&reductions, &var_mins, &var_maxs, var, rows.len());
+                        return Err(infeasible(&reductions, &var_mins, &var_maxs, multipliers));
+                    }
+                }
+                _ => continue,
+            }
+
+            row_is_removed[row] = true;
+            changed = true;
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    remove_duplicate_rows(&rows, &mut row_is_removed, &mut reductions);
+    if remove_dependent {
+        remove_dependent_rows(num_vars, &rows, &mut row_is_removed, &mut reductions);
+    }
+
+    // Vars that do not occur in any row can be moved to their best bound, unless that bound is
+    // infinite, in which case the simplex has to report unboundedness.
+    let mut occurs = vec![false; num_vars];
+    for (row, (entries, _, _)) in rows.iter().enumerate() {
+        if !row_is_removed[row] {
+            for (var, _) in entries {
+                occurs[*var] = true;
+            }
+        }
+    }
+    for var in 0..num_vars {
+        if occurs[var] || removed_by[var].is_some() {
+            continue;
+        }
+        let obj_coeff = &obj_coeffs[var];
+        let val = if obj_coeff.is_positive() || (obj_coeff.is_zero() && var_mins[var].is_finite()) {
+            var_mins[var].clone()
+        } else if obj_coeff.is_negative() || var_maxs[var].is_finite() {
+            var_maxs[var].clone()
+        } else {
+            // A free var that does not influence anything.
+            f0_ab!()
+        };
+        if val.is_finite() {
+            removed_by[var] = Some(reductions.len());
+            reductions.push(Reduction::EmptyCol {
+                var,
+                val,
+                obj_coeff: obj_coeff.clone(),
+            });
+        }
+    }
+
+    // Build the reduced problem.
+    let mut vars = Vec::with_capacity(num_vars);
+    let mut reduced_obj_coeffs = vec![];
+    let mut reduced_var_mins = vec![];
+    let mut reduced_var_maxs = vec![];
+    let mut obj_offset = f0_ab!();
+    for var in 0..num_vars {
+        if let Some(reduction) = removed_by[var] {
+            if !obj_coeffs[var].is_zero() {
+                obj_offset += &obj_coeffs[var] * removed_value(&reductions[reduction]);
+            }
+            vars.push(VarMapping::Removed(reduction));
+        } else {
+            vars.push(VarMapping::Kept(reduced_obj_coeffs.len()));
+            reduced_obj_coeffs.push(obj_coeffs[var].clone());
+            reduced_var_mins.push(var_mins[var].clone());
+            reduced_var_maxs.push(var_maxs[var].clone());
+        }
+    }
+    let num_reduced_vars = reduced_obj_coeffs.len();
+
+    let mut num_reduced_rows = 0;
+    let reduced_rows = row_is_removed
+        .iter()
+        .map(|is_removed| {
+            (!is_removed).then(|| {
+                num_reduced_rows += 1;
+                num_reduced_rows - 1
+            })
+        })
+        .collect();
+
+    let reduced_constraints = rows
+        .into_iter()
+        .zip(row_is_removed)
+        .filter(|(_, is_removed)| !is_removed)
+        .map(|((entries, min, max), _)| {
+            let (indices, coeffs) = entries
+                .into_iter()
+                .map(|(var, coeff)| match vars[var] {
+                    VarMapping::Kept(reduced_var) => (reduced_var, coeff),
+                    VarMapping::Removed(_) => unreachable!(),
+                })
+                .unzip();
+            Constraint {
+                coeffs: CsVec::new(num_reduced_vars, indices, coeffs),
+                min,
+                max,
+            }
+        })
+        .collect();
+
+    Ok(Presolved {
+        obj_coeffs: reduced_obj_coeffs,
+        var_mins: reduced_var_mins,
+        var_maxs: reduced_var_maxs,
+        constraints: reduced_constraints,
+        postsolve: Postsolve {
+            reductions,
+            vars,
+            rows: reduced_rows,
+            num_reduced_vars,
+            var_mins,
+            var_maxs,
+            obj_offset,
+        },
+    })
+}
+
+/// Move a fixed var to the bounds of the rows; returns its entries in those rows.
+fn substitute(
+    rows: &mut [Row],
+    row_is_removed: &[bool],
+    var: usize,
+    val: &AbnormalFraction,
+) -> Vec<(usize, AbnormalFraction)> {
+    let mut col = vec![];
+    for (row, (entries, min, max)) in rows.iter_mut().enumerate() {
+        if row_is_removed[row] {
+            continue;
+        }
+        if let Ok(pos) = entries.binary_search_by_key(&var, |(v, _)| *v) {
+            let (_, coeff) = entries.remove(pos);
+            let diff = &coeff * val;
+            if min.is_finite() {
+                *min -= diff.clone();
+            }
+            if max.is_finite() {
+                *max -= diff;
+            }
+            col.push((row, coeff));
+        }
+    }
+    col
+}
+
+fn remove_duplicate_rows(
+    rows: &[Row],
+    row_is_removed: &mut [bool],
+    reductions: &mut Vec<Reduction>,
+) {
+    let mut by_vars: FxHashMap<Vec<usize>, Vec<usize>> = FxHashMap::default();
+    for (row, (entries, _, _)) in rows.iter().enumerate() {
+        if row_is_removed[row] {
+            continue;
+        }
+        let key = entries.iter().map(|(var, _)| *var).collect::<Vec<_>>();
+        let candidates = by_vars.entry(key).or_default();
+        if candidates.iter().any(|&other| rows[row] == rows[other]) {
+            row_is_removed[row] = true;
+            reductions.push(Reduction::DuplicateRow);
+        } else {
+            candidates.push(row);
+        }
+    }
+}
+
+/// Remove the equality rows that are linear combinations of earlier equality rows. If the
+/// bound of such a row is not the same combination of their bounds, no point satisfies all of
+/// them; the row is kept so that the simplex proves that.
+fn remove_dependent_rows(
+    num_vars: usize,
+    rows: &[Row],
+    row_is_removed: &mut [bool],
+    reductions: &mut Vec<Reduction>,
+) {
+    // The bound is an extra column, which is eliminated along but never a pivot.
+    let mut eliminator = RowEliminator::new(num_vars + 1, num_vars);
+    for (row, (entries, min, max)) in rows.iter().enumerate() {
+        if row_is_removed[row] || min != max || !min.is_finite() {
+            continue;
+        }
+        let entries = (entries.iter())
+            .map(|(var, coeff)| (*var, coeff))
+            .chain([(num_vars, min)]);
+        if !eliminator.add_row(entries) && eliminator.residual_is_zero() {
+            row_is_removed[row] = true;
+            reductions.push(Reduction::DependentRow);
+        }
+    }
+}
+
+fn removed_value(reduction: &Reduction) -> &AbnormalFraction {
+    match reduction {
+        Reduction::FixedVar { val, .. } | Reduction::EmptyCol { val, .. } => val,
+        _ => unreachable!("reduction does not remove a var"),
+    }
+}
+
+/// The multipliers of the singleton rows that gave `var` the lower bound above its upper one:
+/// scaled such that they combine into `var` for the lower bound and minus `var` for the upper
+/// one. A bound of the var itself needs no row.
+fn bound_conflict(
+    reductions: &[Reduction],
+    var_mins: &[AbnormalFraction],
+    var_maxs: &[AbnormalFraction],
+    var: usize,
+    num_rows: usize,
+) -> Vec<AbnormalFraction> {
+    let mut multipliers = vec![f0_ab!(); num_rows];
+    let singleton_rows = reductions.iter().rev().filter_map(|reduction| match reduction {
+        Reduction::SingletonRow {
+            row,
+            var: row_var,
+            coeff,
+            lower,
+            upper,
+        } if *row_var == var => Some((*row, coeff, lower, upper)),
+        _ => None,
+    });
+    let mut lower_row = None;
+    let mut upper_row = None;
+    for (row, coeff, lower, upper) in singleton_rows {
+        if lower_row.is_none() && lower == &var_mins[var] {
+            lower_row = Some((row, coeff));
+        }
+        if upper_row.is_none() && upper == &var_maxs[var] {
+            upper_row = Some((row, coeff));
+        }
+    }
+    if let Some((row, coeff)) = lower_row {
+        multipliers[row] += &f1_ab!() / coeff;
+    }
+    if let Some((row, coeff)) = upper_row {
+        multipliers[row] -= &f1_ab!() / coeff;
+    }
+    multipliers
+}
+
+/// The infeasibility that presolve found, given the multipliers of the rows that show it over
+/// the vars that remained: the vars that were fixed on the way are moved back into the rows.
+fn infeasible(
+    reductions: &[Reduction],
+    var_mins: &[AbnormalFraction],
+    var_maxs: &[AbnormalFraction],
+    mut multipliers: Vec<AbnormalFraction>,
+) -> Error {
+    let mut reduced_costs = vec![f0_ab!(); var_mins.len()];
+    for reduction in reductions {
+        if let Reduction::SingletonRow {
+            row, var, coeff, ..
+        } = reduction
+        {
+            reduced_costs[*var] -= &multipliers[*row] * coeff;
+        }
+    }
+    complete_duals(
+        reductions,
+        var_mins,
+        var_maxs,
+        &mut multipliers,
+        &mut reduced_costs,
+        true,
+    );
+    Error::Infeasible(InfeasibilityCertificate::new(multipliers))
+}
+
+/// Complete the duals of the rows and the reduced costs of the vars, both of the minimised
+/// objective, which are given for the rows and vars that were kept and zero for the others
+/// (apart from the part of the reduced costs of removed vars that comes from rows added
+/// later). A removed row that duplicated or followed from other rows, or was empty, keeps a
+/// zero dual. The reductions are undone in reverse order: a removed var gets its objective
+/// coefficient minus the duals of the rows it was substituted into as its reduced cost, and a
+/// singleton row takes over the reduced cost of its var if the bound that the row implies is
+/// the one at which the var is held, so that the var keeps none.
+///
+/// With `is_ray`, the duals are the multipliers of an infeasibility certificate and the
+/// objective counts as zero; a var is then held at the bound where the combination of the
+/// rows is largest.
+fn complete_duals(
+    reductions: &[Reduction],
+    var_mins: &[AbnormalFraction],
+    var_maxs: &[AbnormalFraction],
+    duals: &mut [AbnormalFraction],
+    reduced_costs: &mut [AbnormalFraction],
+    is_ray: bool,
+) {
+    for reduction in reductions.iter().rev() {
+        match reduction {
+            Reduction::FixedVar {
+                var,
+                obj_coeff,
+                col,
+                ..
+            } => {
+                let dot_prod: AbnormalFraction =
+                    col.iter().map(|(row, coeff)| coeff * &duals[*row]).sum();
+                if !is_ray {
+                    reduced_costs[*var] += obj_coeff.clone();
+                }
+                reduced_costs[*var] -= dot_prod;
+            }
+            Reduction::EmptyCol { var, obj_coeff, .. } => {
+                if !is_ray {
+                    reduced_costs[*var] += obj_coeff.clone();
+                }
+            }
+            Reduction::SingletonRow {
+                row,
+                var,
+                coeff,
+                lower,
+                upper,
+            } => {
+                let reduced_cost = &reduced_costs[*var];
+                // A positive reduced cost pushes the var down, so it is held at a lower bound;
+                // a negative one at an upper bound.
+                let is_held = (reduced_cost.is_positive() && lower == &var_mins[*var])
+                    || (reduced_cost.is_negative() && upper == &var_maxs[*var]);
+                if is_held {
+                    duals[*row] += reduced_cost / coeff;
+                    reduced_costs[*var] = f0_ab!();
+                }
+            }
+            Reduction::EmptyRow | Reduction::DuplicateRow | Reduction::DependentRow => {}
+        }
+    }
+}
+
+impl Postsolve {
+    /// Index of the var in the reduced problem, if it was not removed.
+    pub(crate) fn reduced_var(&self, var: usize) -> Option<usize> {
+        match self.vars[var] {
+            VarMapping::Kept(reduced_var) => Some(reduced_var),
+            VarMapping::Removed(_) => None,
+        }
+    }
+
+    /// Index of the row in the reduced problem, if it was not removed.
+    pub(crate) fn reduced_row(&self, row: usize) -> Option<usize> {
+        self.rows[row]
+    }
+
+    /// Value of a var that was removed.
+    pub(crate) fn removed_value(&self, var: usize) -> &AbnormalFraction {
+        match self.vars[var] {
+            VarMapping::Kept(_) => panic!("var {} was not removed", var),
+            VarMapping::Removed(reduction) => removed_value(&self.reductions[reduction]),
+        }
+    }
+
+    /// Translate a constraint over the original vars into a constraint over the reduced vars.
+    /// Removed vars are moved to the bounds.
+    pub(crate) fn reduce_constraint(&self, constraint: Constraint) -> Constraint {
+        let Constraint {
+            coeffs,
+            mut min,
+            mut max,
+        } = constraint;
+        let mut reduced_vars = vec![];
+        let mut reduced_coeffs = vec![];
+        for (var, coeff) in coeffs.iter() {
+            match self.vars[var] {
+                VarMapping::Kept(reduced_var) => {
+                    reduced_vars.push(reduced_var);
+                    reduced_coeffs.push(coeff.clone());
+                }
+                VarMapping::Removed(reduction) => {
+                    let diff = coeff * removed_value(&self.reductions[reduction]);
+                    if min.is_finite() {
+                        min -= diff.clone();
+                    }
+                    if max.is_finite() {
+                        max -= diff;
+                    }
+                }
+            }
+        }
+        Constraint {
+            coeffs: CsVec::new(self.num_reduced_vars, reduced_vars, reduced_coeffs),
+            min,
+            max,
+        }
+    }
+
+    /// Complete the duals of the rows and the reduced costs of the vars, or with `is_ray` the
+    /// multipliers of an infeasibility certificate; see [`complete_duals`].
+    pub(crate) fn complete_duals(
+        &self,
+        duals: &mut [AbnormalFraction],
+        reduced_costs: &mut [AbnormalFraction],
+        is_ray: bool,
+    ) {
+        complete_duals(
+            &self.reductions,
+            &self.var_mins,
+            &self.var_maxs,
+            duals,
+            reduced_costs,
+            is_ray,
+        );
+    }
+
+    pub(crate) fn num_removed_rows(&self) -> usize {
+        self.reductions
+            .iter()
+            .filter(|reduction| {
+                matches!(
+                    reduction,
+                    Reduction::SingletonRow { .. }
+                        | Reduction::EmptyRow
+                        | Reduction::DuplicateRow
+                        | Reduction::DependentRow
+                )
+            })
+            .count()
+    }
+
+    pub(crate) fn num_removed_vars(&self) -> usize {
+        self.vars
+            .iter()
+            .filter(|mapping| matches!(mapping, VarMapping::Removed(_)))
+            .count()
+    }
+}
@@ -0,0 +1,131 @@
+//! Reporting the progress of the simplex to the caller.
+
+use std::{
+    fmt::Debug,
+    ops::ControlFlow,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use crate::{
+    abnormal_fraction::AbnormalFraction, linear_programming::Error,
+    linear_programming_certificate::PartialSolution,
+};
+
+/// The part of the simplex an iteration belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Phase 1: the dual simplex restores primal feasibility.
+    Feasibility,
+    /// Phase 2: the primal simplex optimises the objective.
+    Optimality,
+}
+
+/// The state of the simplex after an iteration.
+///
+/// Variable indices refer to the variables of the solver: if presolve is enabled, these are the
+/// variables that remain after presolve. Slack variables are numbered after the structural
+/// variables: one per inequality constraint, while an equality constraint only has one if none
+/// of its variables could take its place in the initial basis.
+#[derive(Debug)]
+pub struct IterationInfo {
+    /// Number of iterations so far, including this one.
+    pub iteration: usize,
+    /// The part of the simplex this iteration belongs to.
+    pub phase: Phase,
+    /// Current value of the objective function. During phase 1, this may be the value of an
+    /// artificial objective.
+    pub objective: AbnormalFraction,
+    /// Sum of the violations of the variable bounds; only computed during phase 1.
+    pub primal_infeasibility: Option<AbnormalFraction>,
+    /// The variable that entered the basis, or that moved from one bound to the other.
+    pub entering_var: usize,
+    /// The variable that left the basis; `None` if the entering variable only moved from one
+    /// bound to the other.
+    pub leaving_var: Option<usize>,
+    /// Time since the solver was created.
+    pub elapsed: Duration,
+}
+
+type Callback = Box<dyn FnMut(&IterationInfo) -> ControlFlow<()> + Send>;
+
+/// A shared handle to the callback set with
+/// [`SolveOptions::on_iteration`](crate::linear_programming::SolveOptions::on_iteration).
+#[derive(Clone)]
+pub(crate) struct IterationCallback(Arc<Mutex<Callback>>);
+
+impl IterationCallback {
+    pub(crate) fn new(callback: Callback) -> Self {
+        Self(Arc::new(Mutex::new(callback)))
+    }
+
+    pub(crate) fn call(&self, info: &IterationInfo) -> ControlFlow<()> {
+        (self.0.lock())(info)
+    }
+}
+
+impl Debug for IterationCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("IterationCallback")
+    }
+}
+
+/// Calls the callback after each iteration of a solver.
+#[derive(Clone, Debug)]
+pub(crate) struct Reporter {
+    callback: IterationCallback,
+    start: Instant,
+    negate_obj: bool,
+    obj_offset: Option<AbnormalFraction>,
+}
+
+impl Reporter {
+    pub(crate) fn new(callback: IterationCallback) -> Self {
+        Self {
+            callback,
+            start: Instant::now(),
+            negate_obj: false,
+            obj_offset: None,
+        }
+    }
+
+    /// Report the objective of the solver as `offset + obj`, negated if `negate` is set, such that
+    /// the callback sees the objective of the problem rather than the minimised one.
+    pub(crate) fn set_objective(&mut self, negate: bool, offset: Option<AbnormalFraction>) {
+        self.negate_obj = negate;
+        self.obj_offset = offset;
+    }
+
+    pub(crate) fn report(
+        &self,
+        iteration: usize,
+        phase: Phase,
+        obj_val: &AbnormalFraction,
+        primal_infeasibility: Option<AbnormalFraction>,
+        entering_var: usize,
+        leaving_var: Option<usize>,
+    ) -> Result<(), Error> {
+        let mut objective = match &self.obj_offset {
+            Some(offset) => obj_val + offset,
+            None => obj_val.clone(),
+        };
+        if self.negate_obj {
+            objective = -objective;
+        }
+        let info = IterationInfo {
+            iteration,
+            phase,
+            objective,
+            primal_infeasibility,
+            entering_var,
+            leaving_var,
+            elapsed: self.start.elapsed(),
+        };
+        match self.callback.call(&info) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(()) => Err(Error::Interrupted(PartialSolution::default())),
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! Geometric-mean scaling of the constraint matrix.
+//!
+//! Badly scaled models make LU pivoting and the ratio tests erratic in approximate arithmetic.
+//! Rows and columns are multiplied by powers of two such that, in each row and column, the
+//! geometric mean of the smallest and the largest absolute coefficient is close to one. As the
+//! factors are powers of two, scaling introduces no rounding errors of its own.
+
+use ebi_arithmetic::{MaybeExact, One, Zero, malachite::base::num::logic::traits::SignificantBits};
+
+use crate::{
+    abnormal_fraction::AbnormalFraction,
+    f_ab, f1_ab,
+    linear_programming::{Constraint, CsVec},
+    linear_programming_solver::Solver,
+};
+
+/// Maximum number of alternating row and column passes.
+const MAX_PASSES: usize = 20;
+
+/// Largest exponent of a scale factor.
+const MAX_EXPONENT: i32 = 60;
+
+/// The scale factors and the unscaled values of the current solution.
+#[derive(Clone, Debug)]
+pub(crate) struct ScaleFactors {
+    /// For each row: the scaled row is the original row multiplied by this factor.
+    row_scales: Vec<AbnormalFraction>,
+    /// For each var: the original value is the scaled value multiplied by this factor.
+    col_scales: Vec<AbnormalFraction>,
+    values: Vec<AbnormalFraction>,
+}
+
+pub(crate) struct Scaled {
+    pub(crate) obj_coeffs: Vec<AbnormalFraction>,
+    pub(crate) var_mins: Vec<AbnormalFraction>,
+    pub(crate) var_maxs: Vec<AbnormalFraction>,
+    pub(crate) constraints: Vec<Constraint>,
+    pub(crate) scale_factors: ScaleFactors,
+}
+
+pub(crate) fn scale(
+    obj_coeffs: &[AbnormalFraction],
+    var_mins: &[AbnormalFraction],
+    var_maxs: &[AbnormalFraction],
+    constraints: &[Constraint],
+    deterministic: bool,
+) -> Scaled {
+    let num_vars = obj_coeffs.len();
+
+    // Work with the base-2 logarithms of the absolute values of the coefficients.
+    let log_coeffs = constraints
+        .iter()
+        .map(|constraint| {
+            constraint
+                .coeffs
+                .iter()
+                .filter(|(_, coeff)| !coeff.is_zero())
+                .map(|(var, coeff)| (var, log2_abs(coeff, deterministic)))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut row_exps = vec![0; constraints.len()];
+    let mut col_exps = vec![0; num_vars];
+    for _ in 0..MAX_PASSES {
+        let mut changed = false;
+
+        for (row, entries) in log_coeffs.iter().enumerate() {
+            let range = log_range(
+                entries
+                    .iter()
+                    .map(|(var, log_coeff)| log_coeff + col_exps[*var] as f64),
+            );
+            if let Some((min, max)) = range {
+                let exp = to_exponent(-(min + max) / 2.0);
+                changed |= exp != row_exps[row];
+                row_exps[row] = exp;
+            }
+        }
+
+        let mut col_ranges: Vec<Option<(f64, f64)>> = vec![None; num_vars];
+        for (row, entries) in log_coeffs.iter().enumerate() {
+            for (var, log_coeff) in entries {
+                let val = log_coeff + row_exps[row] as f64;
+                col_ranges[*var] = Some(match col_ranges[*var] {
+                    Some((min, max)) => (min.min(val), max.max(val)),
+                    None => (val, val),
+                });
+            }
+        }
+        for (var, range) in col_ranges.into_iter().enumerate() {
+            if let Some((min, max)) = range {
+                let exp = to_exponent(-(min + max) / 2.0);
+                changed |= exp != col_exps[var];
+                col_exps[var] = exp;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    let row_scales = row_exps.into_iter().map(power_of_two).collect::<Vec<_>>();
+    let col_scales = col_exps.into_iter().map(power_of_two).collect::<Vec<_>>();
+
+    // With x = c * x', the scaled problem has coefficients r * a * c, objective coefficients
+    // c * o, row bounds r * b and var bounds l / c and u / c.
+    let scaled_constraints = constraints
+        .iter()
+        .zip(&row_scales)
+        .map(|(constraint, row_scale)| {
+            let coeffs = constraint.coeffs.map(|coeff| coeff * row_scale);
+            Constraint {
+                coeffs: scale_row(&col_scales, &coeffs),
+                min: scale_bound(&constraint.min, row_scale),
+                max: scale_bound(&constraint.max, row_scale),
+            }
+        })
+        .collect();
+    let scaled_obj_coeffs = obj_coeffs
+        .iter()
+        .zip(&col_scales)
+        .map(|(obj_coeff, col_scale)| obj_coeff * col_scale)
+        .collect();
+    let scaled_var_mins = var_mins
+        .iter()
+        .zip(&col_scales)
+        .map(|(min, col_scale)| scale_bound(min, &(f1_ab!() / col_scale.clone())))
+        .collect();
+    let scaled_var_maxs = var_maxs
+        .iter()
+        .zip(&col_scales)
+        .map(|(max, col_scale)| scale_bound(max, &(f1_ab!() / col_scale.clone())))
+        .collect();
+
+    Scaled {
+        obj_coeffs: scaled_obj_coeffs,
+        var_mins: scaled_var_mins,
+        var_maxs: scaled_var_maxs,
+        constraints: scaled_constraints,
+        scale_factors: ScaleFactors {
+            row_scales,
+            col_scales,
+            values: vec![],
+        },
+    }
+}
+
+impl ScaleFactors {
+    /// Scale a row over the vars of the problem; the row itself is not scaled.
+    pub(crate) fn scale_row(&self, coeffs: &CsVec) -> CsVec {
+        scale_row(&self.col_scales, coeffs)
+    }
+
+    /// Translate a value of a var into the scaled problem.
+    pub(crate) fn scale_value(&self, var: usize, val: &AbnormalFraction) -> AbnormalFraction {
+        val / &self.col_scales[var]
+    }
+
+    /// Translate a value of a var of the scaled problem back.
+    pub(crate) fn unscale_value(&self, var: usize, val: &AbnormalFraction) -> AbnormalFraction {
+        val * &self.col_scales[var]
+    }
+
+    /// Recompute the unscaled values after the solver has changed its solution.
+    pub(crate) fn unscale(&mut self, solver: &Solver) {
+        self.values = self
+            .col_scales
+            .iter()
+            .enumerate()
+            .map(|(var, col_scale)| solver.get_value(var) * col_scale)
+            .collect();
+    }
+
+    /// Unscaled value of a var.
+    pub(crate) fn value(&self, var: usize) -> &AbnormalFraction {
+        &self.values[var]
+    }
+
+    /// Translate the dual of a row of the scaled problem back: as the bounds of the row were
+    /// multiplied by its scale factor, so is the change of the objective per unit of them.
+    pub(crate) fn unscale_dual(&self, row: usize, dual: &AbnormalFraction) -> AbnormalFraction {
+        dual * &self.row_scales[row]
+    }
+
+    /// Translate the reduced cost of a var of the scaled problem back.
+    pub(crate) fn unscale_reduced_cost(
+        &self,
+        var: usize,
+        reduced_cost: &AbnormalFraction,
+    ) -> AbnormalFraction {
+        reduced_cost / &self.col_scales[var]
+    }
+}
+
+fn scale_row(col_scales: &[AbnormalFraction], coeffs: &CsVec) -> CsVec {
+    CsVec::new(
+        coeffs.dim(),
+        coeffs.indices().to_vec(),
+        coeffs
+            .iter()
+            .map(|(var, coeff)| coeff * &col_scales[var])
+            .collect(),
+    )
+}
+
+fn scale_bound(bound: &AbnormalFraction, factor: &AbnormalFraction) -> AbnormalFraction {
+    if bound.is_finite() {
+        bound * factor
+    } else {
+        bound.clone()
+    }
+}
+
+fn log_range(vals: impl Iterator<Item = f64>) -> Option<(f64, f64)> {
+    vals.fold(None, |range, val| match range {
+        Some((min, max)) => Some((val.min(min), val.max(max))),
+        None => Some((val, val)),
+    })
+}
+
+fn to_exponent(log: f64) -> i32 {
+    (log.round() as i32).clamp(-MAX_EXPONENT, MAX_EXPONENT)
+}
+
+fn power_of_two(exp: i32) -> AbnormalFraction {
+    if exp >= 0 {
+        f_ab!(1usize << exp)
+    } else {
+        f_ab!(1usize, 1usize << -exp)
+    }
+}
+
+/// Approximate base-2 logarithm of the absolute value of a finite, non-zero number. If
+/// `deterministic` is set, the result does not depend on the `log2` of the platform.
+fn log2_abs(value: &AbnormalFraction, deterministic: bool) -> f64 {
+    if let Ok(approx) = value.approx_ref() {
+        if deterministic {
+            log2_from_bits(approx.abs())
+        } else {
+            approx.abs().log2()
+        }
+    } else if let Ok(exact) = value.exact_ref() {
+        exact.numerator_ref().significant_bits() as f64
+            - exact.denominator_ref().significant_bits() as f64
+    } else {
+        0.0
+    }
+}
+
+/// Base-2 logarithm of a positive, finite float, approximated from its exponent and a linear
+/// interpolation of its significand. Uses only exact operations, so every platform gives the
+/// same result; the error is below 0.09, which does not matter for choosing scale factors.
+fn log2_from_bits(value: f64) -> f64 {
+    const SIGNIFICAND_MASK: u64 = (1 << 52) - 1;
+    const EXPONENT_OF_ONE: u64 = 1023 << 52;
+    if value < f64::MIN_POSITIVE {
+        // Subnormal; 2^64 * value is normal.
+        return log2_from_bits(value * 18446744073709551616.0) - 64.0;
+    }
+    let bits = value.to_bits();
+    let exponent = (bits >> 52) as i64 - 1023;
+    let significand = f64::from_bits((bits & SIGNIFICAND_MASK) | EXPONENT_OF_ONE);
+    exponent as f64 + (significand - 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_of_two_exponents() {
+        assert_eq!(power_of_two(0), f_ab!(1usize));
+        assert_eq!(power_of_two(3), f_ab!(8usize));
+        assert_eq!(power_of_two(-2), f_ab!(1usize, 4usize));
+        assert_eq!(to_exponent(100.0), MAX_EXPONENT);
+    }
+
+    #[test]
+    fn log2_without_platform_functions() {
+        assert_eq!(log2_from_bits(1.0), 0.0);
+        assert_eq!(log2_from_bits(8.0), 3.0);
+        assert_eq!(log2_from_bits(0.25), -2.0);
+        assert_eq!(log2_from_bits(f64::MIN_POSITIVE / 4.0), -1024.0);
+        for value in [3.0, 0.1, 1e-300, 12345.678] {
+            assert!((log2_from_bits(value) - f64::log2(value)).abs() < 0.09);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Estimating the size of a problem before solving it.
+
+use std::fmt::Display;
+
+use crate::{
+    abnormal_fraction::AbnormalFraction,
+    linear_programming::{Constraint, Variable},
+};
+
+/// A column is dense if it has entries in more than this many rows, and in more than
+/// [`DENSE_COLUMN_MIN_FRACTION`] of the rows.
+const DENSE_COLUMN_MIN_ROWS: usize = 10;
+const DENSE_COLUMN_MIN_FRACTION: f64 = 0.25;
+
+/// The size of a problem as the solver sees it, and structural properties that are worth a
+/// look before solving. Returned by
+/// [`Problem::size_report`](crate::linear_programming::Problem::size_report).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SizeReport {
+    /// Number of constraints.
+    pub rows: usize,
+    /// Number of variables of the problem. The solver adds one slack column per row.
+    pub structural_columns: usize,
+    /// Number of nonzero constraint coefficients.
+    pub nnz: usize,
+    /// Number of nonzeros in the constraint matrix, including one per slack column.
+    pub nnz_with_slacks: usize,
+    /// Estimated bytes for the constraint matrix, which the solver stores both by rows and by
+    /// columns. Only counts the fixed-size part of each coefficient; exact fractions with
+    /// large numerators or denominators need more.
+    pub matrix_bytes: usize,
+    /// Variables with entries in so many rows that they can make the factorisation of the
+    /// basis dense.
+    pub dense_columns: Vec<Variable>,
+    /// Constraints without coefficients.
+    pub empty_rows: Vec<usize>,
+    /// Variables that occur in no constraint.
+    pub empty_columns: Vec<Variable>,
+    /// Constraints without finite bounds, which constrain nothing.
+    pub free_rows: Vec<usize>,
+}
+
+impl SizeReport {
+    /// Number of columns of the constraint matrix, including the slack columns.
+    pub fn columns(&self) -> usize {
+        self.structural_columns + self.rows
+    }
+
+    /// Estimated bytes for an LU factorisation of a basis, if the factors have `fill_factor`
+    /// times as many nonzeros as the basis matrix. The basis consists of `rows` columns of
+    /// average density; the solver also keeps the transposed factors.
+    pub fn factorisation_bytes(&self, fill_factor: f64) -> usize {
+        if self.columns() == 0 {
+            return 0;
+        }
+        let basis_nnz = self.nnz_with_slacks as f64 * self.rows as f64 / self.columns() as f64;
+        (2.0 * fill_factor * basis_nnz * entry_bytes() as f64) as usize
+    }
+}
+
+impl Display for SizeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "rows: {}, columns: {} ({} structural), nnz: {} ({} with slacks), matrix: ~{} bytes",
+            self.rows,
+            self.columns(),
+            self.structural_columns,
+            self.nnz,
+            self.nnz_with_slacks,
+            self.matrix_bytes,
+        )?;
+        for (name, count) in [
+            ("dense columns", self.dense_columns.len()),
+            ("empty rows", self.empty_rows.len()),
+            ("empty columns", self.empty_columns.len()),
+            ("free rows", self.free_rows.len()),
+        ] {
+            if count > 0 {
+                write!(f, ", {}: {}", name, count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Bytes per stored coefficient: the value and its index.
+fn entry_bytes() -> usize {
+    std::mem::size_of::<AbnormalFraction>() + std::mem::size_of::<usize>()
+}
+
+pub(crate) fn size_report(num_vars: usize, constraints: &[Constraint]) -> SizeReport {
+    let rows = constraints.len();
+    let mut col_nnz = vec![0; num_vars];
+    let mut nnz = 0;
+    let mut empty_rows = vec![];
+    let mut free_rows = vec![];
+    for (row, constraint) in constraints.iter().enumerate() {
+        nnz += constraint.coeffs.nnz();
+        for &var in constraint.coeffs.indices() {
+            col_nnz[var] += 1;
+        }
+        if constraint.coeffs.nnz() == 0 {
+            empty_rows.push(row);
+        }
+        if constraint.min.is_infinite() && constraint.max.is_infinite() {
+            free_rows.push(row);
+        }
+    }
+
+    let is_dense = |count: usize| {
+        count > DENSE_COLUMN_MIN_ROWS && count as f64 > DENSE_COLUMN_MIN_FRACTION * rows as f64
+    };
+    let dense_columns = (0..num_vars)
+        .filter(|&var| is_dense(col_nnz[var]))
+        .map(Variable)
+        .collect();
+    let empty_columns = (0..num_vars)
+        .filter(|&var| col_nnz[var] == 0)
+        .map(Variable)
+        .collect();
+
+    let nnz_with_slacks = nnz + rows;
+    SizeReport {
+        rows,
+        structural_columns: num_vars,
+        nnz,
+        nnz_with_slacks,
+        matrix_bytes: 2 * nnz_with_slacks * entry_bytes(),
+        dense_columns,
+        empty_rows,
+        empty_columns,
+        free_rows,
+    }
+}
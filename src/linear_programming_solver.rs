@@ -1,14 +1,35 @@
-use ebi_arithmetic::{One, Round, Signed, Zero};
-use log::debug;
+#![deny(clippy::print_stdout, clippy::print_stderr)]
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use ebi_arithmetic::{MaybeExact, One, Round, Signed, Zero};
+use log::{Level, debug, log_enabled, trace, warn};
 use sprs::CompressedStorage;
 
 use crate::{
     abnormal_fraction::AbnormalFraction,
     f_ab, f0_ab, f1_ab,
-    linear_programming::{ComparisonOp, CsVec, Error},
+    linear_programming::{ComparisonOp, Constraint, CsVec, Error, ModelError},
+    linear_programming_basis::BasisStatus,
+    linear_programming_certificate::{InfeasibilityCertificate, PartialSolution, UnboundedRay},
     linear_programming_helpers::{resized_view, to_dense},
-    linear_programming_lu::{LUFactors, ScratchSpace, lu_factorise},
-    linear_programming_sparse::{ScatteredVec, SparseMat, SparseVec},
+    linear_programming_lu::{
+        LUFactors, LuScratch, ScratchSpace, SymbolicLU, UpdatableLU, lu_analyse_with_scratch,
+        lu_factorise_markowitz_with_scratch, lu_factorise_symbolic, lu_factorise_with_scratch,
+        lu_refactorise_with_scratch,
+    },
+    linear_programming_options::{
+        BasisUpdate, LuPivoting, Method, Pricing, RefactorPolicy, SolveOptions,
+    },
+    linear_programming_progress::{Phase, Reporter},
+    linear_programming_sparse::{Error as SparseError, ScatteredVec, SparseVec},
+    linear_programming_stats::{FactorStats, SolveStats, SolveTimings},
 };
 
 type CsMat = sprs::CsMatI<AbnormalFraction, usize>;
@@ -26,15 +47,34 @@ pub(crate) struct Solver {
 
     enable_primal_steepest_edge: bool,
     enable_dual_steepest_edge: bool,
-
-    is_primal_feasible: bool,
+    enable_devex: bool,
+    enable_perturbation: bool,
+    enable_bound_flipping: bool,
+    /// The tolerance of the first pass of the Harris ratio tests; `None` in exact arithmetic.
+    harris_tolerance: Option<AbnormalFraction>,
+    /// Whether the residuals of the tableau solves are checked; never in exact arithmetic.
+    check_residuals: bool,
+    method: Method,
+    refactor_policy: RefactorPolicy,
+    /// Vars with an artificial bound of the dual simplex, with their original bounds.
+    artificial_bounds: Vec<(usize, AbnormalFraction, AbnormalFraction)>,
+    /// The var bounds before they were perturbed, while phase 2 runs with perturbed bounds.
+    unperturbed_bounds: Option<(Vec<AbnormalFraction>, Vec<AbnormalFraction>)>,
+
+    pub(crate) is_primal_feasible: bool,
     is_dual_feasible: bool,
+    /// Set when [`Solver::refactorise_or_recover`] changed the basis, for the simplex loops to
+    /// start over from the new basis.
+    basis_recovered: bool,
 
     // Updated on each pivot
     /// For each var: whether it is basic/non-basic and the corresponding index.
     var_states: Vec<VarState>,
     basis_solver: BasisSolver,
 
+    /// For each constraint its slack var, if it has one: an equality row in which a var of its
+    /// own is basic from the start has none.
+    slack_vars: Vec<Option<usize>>,
     /// For each constraint the corresponding basic var.
     basic_vars: Vec<usize>,
     basic_var_vals: Vec<AbnormalFraction>,
@@ -49,8 +89,24 @@ pub(crate) struct Solver {
     nb_var_states: Vec<NonBasicVarState>,
     nb_var_is_fixed: Vec<bool>,
     primal_edge_sq_norms: Vec<AbnormalFraction>,
+    /// Devex reference weights, one per non-basic var.
+    devex_weights: Vec<AbnormalFraction>,
+    /// For each var: whether it belongs to the current Devex reference framework.
+    devex_reference: Vec<bool>,
+    /// Number of columns examined per iteration when choosing the entering var.
+    pricing_window: usize,
+    /// Column at which the next partial pricing window starts.
+    pricing_window_start: usize,
+    /// Minimum number of columns for which a full scan scores the columns in parallel.
+    #[cfg_attr(not(feature = "parallel"), allow(dead_code))]
+    parallel_pricing_threshold: usize,
 
     pub(crate) cur_obj_val: AbnormalFraction,
+    pub(crate) stats: SolveStats,
+    /// Calls the callback of [`SolveOptions::on_iteration`], if any.
+    pub(crate) reporter: Option<Reporter>,
+    /// The token of [`SolveOptions::cancel_token`], if any.
+    cancel: Option<Arc<AtomicBool>>,
 
     // Recomputed on each pivot
     col_coeffs: SparseVec,
@@ -73,10 +129,10 @@ struct NonBasicVarState {
 
 impl std::fmt::Debug for Solver {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Solver\n")?;
-        write!(
+        writeln!(f, "Solver")?;
+        writeln!(
             f,
-            "num_vars: {}, num_constraints: {}, is_primal_feasible: {}, is_dual_feasible: {}\n",
+            "num_vars: {}, num_constraints: {}, is_primal_feasible: {}, is_dual_feasible: {}",
             self.num_vars,
             self.num_constraints(),
             self.is_primal_feasible,
@@ -85,9 +141,9 @@ impl std::fmt::Debug for Solver {
         write!(f, "orig_obj_coeffs:\n{:?}\n", self.orig_obj_coeffs)?;
         write!(f, "orig_var_mins:\n{:?}\n", self.orig_var_mins)?;
         write!(f, "orig_var_maxs:\n{:?}\n", self.orig_var_maxs)?;
-        write!(f, "orig_constraints:\n")?;
+        writeln!(f, "orig_constraints:")?;
         for row in self.orig_constraints.outer_iterator() {
-            write!(f, "{:?}\n", to_dense(&row))?;
+            writeln!(f, "{:?}", to_dense(&row))?;
         }
         write!(f, "orig_rhs:\n{:?}\n", self.orig_rhs)?;
         write!(f, "basic_vars:\n{:?}\n", self.basic_vars)?;
@@ -101,7 +157,7 @@ impl std::fmt::Debug for Solver {
             "primal_edge_sq_norms:\n{:?}\n",
             self.primal_edge_sq_norms
         )?;
-        write!(f, "cur_obj_val: {:?}\n", self.cur_obj_val)?;
+        writeln!(f, "cur_obj_val: {:?}", self.cur_obj_val)?;
         Ok(())
     }
 }
@@ -111,9 +167,11 @@ impl Solver {
         obj_coeffs: &[AbnormalFraction],
         var_mins: &[AbnormalFraction],
         var_maxs: &[AbnormalFraction],
-        constraints: &[(CsVec, ComparisonOp, AbnormalFraction)],
+        constraints: &[Constraint],
+        options: &SolveOptions,
     ) -> Result<Self, Error> {
-        let enable_steepest_edge = true; // TODO: make user-settable.
+        let enable_steepest_edge = options.pricing == Pricing::SteepestEdge;
+        let enable_devex = options.pricing == Pricing::Devex;
 
         let num_vars = obj_coeffs.len();
 
@@ -132,16 +190,33 @@ impl Solver {
 
         let mut is_dual_feasible = true;
 
+        // The obj. coeffs below are those relative to the initial basis, in which the crash vars
+        // are basic in their equality rows and slack vars are basic in the other rows.
+        let crash_vars = crash_equality_rows(var_mins, var_maxs, constraints);
+        let mut is_crash_var = vec![false; num_vars];
+        for &var in crash_vars.iter().flatten() {
+            is_crash_var[var] = true;
+        }
+        let reduced_obj_coeffs = crash_obj_coeffs(obj_coeffs, constraints, &crash_vars);
+        let mut var_vals = vec![];
+
         for v in 0..num_vars {
             // choose initial variable values
 
             let min = &orig_var_mins[v];
             let max = &orig_var_maxs[v];
             if min > max {
-                return Err(Error::Infeasible);
+                return Err(Error::Infeasible(InfeasibilityCertificate::default()));
+            }
+
+            if is_crash_var[v] {
+                // basic in its equality row, set below
+                var_states.push(VarState::Basic(0));
+                var_vals.push(f0_ab!());
+                continue;
             }
 
-            // initially all user-created variables are non-basic
+            // initially all other user-created variables are non-basic
             var_states.push(VarState::NonBasic(nb_vars.len()));
             nb_vars.push(v);
 
@@ -151,11 +226,11 @@ impl Solver {
                 min.clone()
             } else if min.is_infinite() && max.is_infinite() {
                 // Free variable, if we are lucky and obj. coeff is zero, then dual-feasible.
-                if !obj_coeffs[v].is_zero() {
+                if !reduced_obj_coeffs[v].is_zero() {
                     is_dual_feasible = false;
                 }
                 f0_ab!()
-            } else if obj_coeffs[v].is_positive() {
+            } else if reduced_obj_coeffs[v].is_positive() {
                 // We need a finite value and prefer min for dual feasibility.
                 if min.is_finite() {
                     min.clone()
@@ -163,7 +238,7 @@ impl Solver {
                     is_dual_feasible = false;
                     max.clone()
                 }
-            } else if obj_coeffs[v].is_negative() {
+            } else if reduced_obj_coeffs[v].is_negative() {
                 // We need a finite value and prefer max for dual feasibility.
                 if max.is_finite() {
                     max.clone()
@@ -180,6 +255,7 @@ impl Solver {
             };
 
             nb_var_vals.push(init_val.clone());
+            var_vals.push(init_val.clone());
             obj_val += &init_val * &obj_coeffs[v];
 
             nb_var_states.push(NonBasicVarState {
@@ -191,40 +267,47 @@ impl Solver {
         let mut constraint_coeffs = vec![];
         let mut orig_rhs = vec![];
 
-        // Initially, all slack vars are basic.
+        // Initially, all slack vars and the crash vars of the equality rows are basic.
+        let mut slack_vars = vec![];
         let mut basic_vars = vec![];
         let mut basic_var_vals = vec![];
         let mut basic_var_mins = vec![];
         let mut basic_var_maxs = vec![];
 
-        for (coeffs, cmp_op, rhs) in constraints {
-            let rhs = rhs;
+        let lhs_val = |coeffs: &CsVec, var_vals: &[AbnormalFraction]| -> AbnormalFraction {
+            coeffs.iter().map(|(var, coeff)| coeff * &var_vals[var]).sum()
+        };
 
-            if coeffs.indices().is_empty() {
-                let is_tautological = match cmp_op {
-                    // ComparisonOp::Eq => 0.0 == rhs,
-                    // ComparisonOp::Le => 0.0 <= rhs,
-                    // ComparisonOp::Ge => 0.0 >= rhs,
-                    ComparisonOp::Eq => rhs.is_zero(),
-                    ComparisonOp::Le => rhs.is_not_negative(),
-                    ComparisonOp::Ge => rhs.is_not_positive(),
-                };
+        for (constraint, crash_var) in constraints.iter().zip(crash_vars) {
+            let coeffs = &constraint.coeffs;
 
-                if is_tautological {
+            if coeffs.indices().is_empty() {
+                if constraint.is_satisfied_by(&f0_ab!()) {
                     continue;
                 } else {
-                    return Err(Error::Infeasible);
+                    return Err(Error::Infeasible(InfeasibilityCertificate::default()));
                 }
             }
 
+            let (rhs, slack_var_min, slack_var_max) = constraint.slack();
             constraint_coeffs.push(coeffs.clone());
             orig_rhs.push(rhs.clone());
 
-            let (slack_var_min, slack_var_max) = match cmp_op {
-                ComparisonOp::Le => (f0_ab!(), AbnormalFraction::infinity()),
-                ComparisonOp::Ge => (AbnormalFraction::neg_infinity(), f0_ab!()),
-                ComparisonOp::Eq => (f0_ab!(), f0_ab!()),
-            };
+            if let Some(var) = crash_var {
+                // The crash vars of the rows before have their values already, and those of the
+                // rows after have no coeff. in this row.
+                let val = &(&rhs - &lhs_val(coeffs, &var_vals)) / coeffs.get(var).unwrap();
+                obj_val += &val * &obj_coeffs[var];
+                var_vals[var] = val.clone();
+
+                var_states[var] = VarState::Basic(basic_vars.len());
+                basic_vars.push(var);
+                basic_var_vals.push(val);
+                basic_var_mins.push(orig_var_mins[var].clone());
+                basic_var_maxs.push(orig_var_maxs[var].clone());
+                slack_vars.push(None);
+                continue;
+            }
 
             orig_var_mins.push(slack_var_min.clone());
             orig_var_maxs.push(slack_var_max.clone());
@@ -235,24 +318,30 @@ impl Solver {
             let cur_slack_var = var_states.len();
             var_states.push(VarState::Basic(basic_vars.len()));
             basic_vars.push(cur_slack_var);
+            slack_vars.push(Some(cur_slack_var));
+            // Set below, once the values of all crash vars are known.
+            basic_var_vals.push(f0_ab!());
+        }
 
-            let mut lhs_val = f0_ab!();
-            for (var, coeff) in coeffs.iter() {
-                lhs_val += coeff * &nb_var_vals[var];
+        for (row, slack_var) in slack_vars.iter().enumerate() {
+            if slack_var.is_some() {
+                basic_var_vals[row] = &orig_rhs[row] - &lhs_val(&constraint_coeffs[row], &var_vals);
             }
-            basic_var_vals.push(rhs - &lhs_val);
         }
 
         let num_constraints = constraint_coeffs.len();
-        let num_total_vars = num_vars + num_constraints;
+        let num_total_vars = var_states.len();
+        let has_crash_vars = num_total_vars < num_vars + num_constraints;
 
         let mut orig_obj_coeffs = obj_coeffs.to_vec();
         orig_obj_coeffs.resize(num_total_vars, f0_ab!());
 
         let mut orig_constraints = CsMat::empty(CompressedStorage::CSR, num_total_vars);
-        for (cur_slack_var, coeffs) in constraint_coeffs.into_iter().enumerate() {
+        for (coeffs, slack_var) in constraint_coeffs.into_iter().zip(&slack_vars) {
             let mut coeffs = into_resized(coeffs, num_total_vars);
-            coeffs.append(num_vars + cur_slack_var, f1_ab!());
+            if let Some(slack_var) = slack_var {
+                coeffs.append(*slack_var, f1_ab!());
+            }
             orig_constraints = orig_constraints.append_outer_csvec(coeffs.view());
         }
         let orig_constraints_csc = orig_constraints.to_csc();
@@ -296,10 +385,12 @@ impl Solver {
                 };
                 nb_var_obj_coeffs.push(coeff);
             } else {
-                nb_var_obj_coeffs.push(orig_obj_coeffs[var].clone());
+                nb_var_obj_coeffs.push(reduced_obj_coeffs[var].clone());
             }
 
-            if enable_primal_steepest_edge {
+            // With crash vars in the basis, the edges are no longer the columns themselves;
+            // their norms are computed once the basis is factorised.
+            if enable_primal_steepest_edge && !has_crash_vars {
                 primal_edge_sq_norms.push(col.squared_l2_norm() + f1_ab!());
             }
         }
@@ -310,24 +401,49 @@ impl Solver {
             obj_val
         };
 
-        let mut scratch = ScratchSpace::with_capacity(num_constraints);
-        let lu_factors = lu_factorise(
-            basic_vars.len(),
-            |c| {
-                orig_constraints_csc
-                    .outer_view(basic_vars[c])
-                    .unwrap()
-                    .into_raw_storage()
-            },
-            f_ab!(1, 10),
+        let mut scratch = LuScratch::with_capacity(num_constraints);
+        scratch.set_drop_tolerance(options.drop_tolerance.clone());
+        scratch.set_ordering(options.ordering.clone());
+        scratch.set_ordering_cache(options.ordering_cache.clone());
+        scratch.set_row_hints(options.row_hints.clone());
+        let lu_settings = LuSettings {
+            pivoting: options.lu_pivoting,
+            pivot_threshold: options.lu_pivot_threshold.clone(),
+            small_pivots: options.small_pivots,
+            dense_col_threshold: (options.dense_column_fraction)
+                .map(|fraction| (fraction * num_constraints as f64) as usize),
+            condition_limit: options.condition_limit.filter(|_| !f1_ab!().is_exact()),
+            coeff_bit_stats: options.coeff_bit_stats && f1_ab!().is_exact(),
+            partial_refactor: options.refactor_policy.partial_refactor,
+            basis_update: options.basis_update,
+            reuse_symbolic: options.reuse_symbolic
+                && options.lu_pivoting == LuPivoting::ColumnOrder
+                && options.dense_column_fraction.is_none(),
+        };
+        let lu_start = Instant::now();
+        let lu_factors = lu_settings.factorise(
+            &orig_constraints_csc,
+            &basic_vars,
             &mut scratch,
-        )
-        .unwrap();
-        let lu_factors_transp = lu_factors.transpose();
+            options.cancel.as_deref(),
+        )?;
+        let lu_time = lu_start.elapsed();
 
         let nb_var_is_fixed = vec![false; nb_vars.len()];
 
-        let res = Self {
+        let (devex_weights, devex_reference) = if enable_devex {
+            let mut reference = vec![false; num_total_vars];
+            for &var in &nb_vars {
+                reference[var] = true;
+            }
+            (vec![f1_ab!(); nb_vars.len()], reference)
+        } else {
+            (vec![], vec![])
+        };
+
+        let pricing_window = options.partial_pricing.window_size(nb_vars.len());
+
+        let mut res = Self {
             num_vars,
             orig_obj_coeffs,
             orig_var_mins,
@@ -337,16 +453,25 @@ impl Solver {
             orig_rhs,
             enable_primal_steepest_edge,
             enable_dual_steepest_edge,
+            enable_devex,
+            enable_perturbation: options.perturbation,
+            enable_bound_flipping: options.bound_flipping,
+            harris_tolerance: (!f1_ab!().is_exact()).then(|| options.harris_tolerance.clone()),
+            check_residuals: options.check_residuals && !f1_ab!().is_exact(),
+            method: options.method,
+            refactor_policy: RefactorPolicy {
+                max_coeff_bits: (options.refactor_policy.max_coeff_bits)
+                    .filter(|_| f1_ab!().is_exact()),
+                ..options.refactor_policy.clone()
+            },
+            artificial_bounds: vec![],
+            unperturbed_bounds: None,
             is_primal_feasible,
             is_dual_feasible,
+            basis_recovered: false,
             var_states,
-            basis_solver: BasisSolver {
-                lu_factors,
-                lu_factors_transp,
-                scratch,
-                eta_matrices: EtaMatrices::new(num_constraints),
-                rhs: ScatteredVec::empty(num_constraints),
-            },
+            basis_solver: BasisSolver::new(lu_factors, &basic_vars, scratch, lu_settings),
+            slack_vars,
             basic_vars,
             basic_var_vals,
             basic_var_mins,
@@ -358,12 +483,46 @@ impl Solver {
             nb_var_states,
             nb_var_is_fixed,
             primal_edge_sq_norms,
+            devex_weights,
+            devex_reference,
+            pricing_window,
+            pricing_window_start: 0,
+            parallel_pricing_threshold: options.parallel_pricing_threshold,
             cur_obj_val,
+            stats: SolveStats {
+                timings: options.timings.then(SolveTimings::default),
+                ..Default::default()
+            },
+            reporter: options.on_iteration.clone().map(Reporter::new),
+            cancel: options.cancel.clone(),
             col_coeffs: SparseVec::new(),
             sq_norms_update_helper,
             inv_basis_row_coeffs: SparseVec::new(),
             row_coeffs: ScatteredVec::empty(num_total_vars - num_constraints),
         };
+        let restore_start = Instant::now();
+        res.basis_solver
+            .restore_dense_cols(
+                &res.orig_constraints_csc,
+                &res.basic_vars,
+                options.cancel.as_deref(),
+            )?;
+        let lu_time = lu_time + restore_start.elapsed();
+        res.stats.record_lu_factorisation(
+            FactorStats {
+                basis_nnz: res.basis_nnz(),
+                time: lu_time,
+                ..res.basis_solver.factor_stats.clone()
+            },
+            res.basis_solver.lu_factors.dense_cols().len(),
+        );
+        res.check_condition();
+        if let Some(timings) = &mut res.stats.timings {
+            timings.lu_factorisation += lu_time;
+        }
+        if enable_primal_steepest_edge && has_crash_vars {
+            res.recalc_primal_sq_norms();
+        }
 
         debug!(
             "initialized solver: vars: {}, constraints: {}, primal feasible: {}, dual feasible: {}, nnz: {}",
@@ -384,23 +543,53 @@ impl Solver {
         }
     }
 
+    /// The simplex multipliers of the current basis, `y = c_B B⁻¹`: for each constraint, the
+    /// change of the objective per unit change of its right-hand side while the basis stays
+    /// the same.
+    pub(crate) fn duals(&self) -> Vec<AbnormalFraction> {
+        let mut duals = vec![f0_ab!(); self.num_constraints()];
+        for (r, &var) in self.basic_vars.iter().enumerate() {
+            duals[r] = self.orig_obj_coeffs[var].clone();
+        }
+        let mut scratch = ScratchSpace::with_capacity(duals.len());
+        (self.basis_solver.lu_factors).solve_transp_dense(&mut duals, &mut scratch);
+        duals
+    }
+
+    /// Reduced cost of a var for the given [`Solver::duals`], `c_j - yᵀA_j`, or `-yᵀA_j` with
+    /// `is_ray`, for the multipliers of an infeasibility certificate.
+    pub(crate) fn reduced_cost(
+        &self,
+        var: usize,
+        duals: &[AbnormalFraction],
+        is_ray: bool,
+    ) -> AbnormalFraction {
+        let col = self.orig_constraints_csc.outer_view(var).unwrap();
+        let dot_prod: AbnormalFraction = col.iter().map(|(r, val)| val * &duals[r]).sum();
+        if is_ray {
+            -dot_prod
+        } else {
+            &self.orig_obj_coeffs[var] - &dot_prod
+        }
+    }
+
     pub(crate) fn fix_var(&mut self, var: usize, val: AbnormalFraction) -> Result<(), Error> {
         if val < self.orig_var_mins[var] || val > self.orig_var_maxs[var] {
-            return Err(Error::Infeasible);
+            return Err(Error::Infeasible(InfeasibilityCertificate::default()));
         }
 
         let col = match self.var_states[var] {
             VarState::Basic(row) => {
                 // if var was basic, remove it.
-                self.calc_row_coeffs(row);
-                let pivot_info = self.choose_entering_col_dual(row, val)?;
-                self.calc_col_coeffs(pivot_info.col);
-                self.pivot(&pivot_info);
+                self.calc_row_coeffs(row)?;
+                let (pivot_info, _) = self.choose_entering_col_dual(row, val, false)?;
+                self.calc_col_coeffs(pivot_info.col)?;
+                self.pivot(&pivot_info)?;
                 pivot_info.col
             }
 
             VarState::NonBasic(col) => {
-                self.calc_col_coeffs(col);
+                self.calc_col_coeffs(col)?;
 
                 let diff = &val - &self.nb_var_vals[col];
                 for (r, coeff) in self.col_coeffs.iter() {
@@ -447,7 +636,7 @@ impl Solver {
 
     pub(crate) fn add_gomory_cut(&mut self, var: usize) -> Result<(), Error> {
         if let VarState::Basic(row) = self.var_states[var] {
-            self.calc_row_coeffs(row);
+            self.calc_row_coeffs(row)?;
 
             let mut cut_coeffs = SparseVec::new();
             for (col, coeff) in self.row_coeffs.iter() {
@@ -457,38 +646,466 @@ impl Solver {
 
             let cut_bound = &self.basic_var_vals[row].clone().floor() - &self.basic_var_vals[row];
             let num_total_vars = self.num_total_vars();
-            self.add_constraint(
+            self.add_constraint(Constraint::new(
                 cut_coeffs.into_csvec(num_total_vars),
                 ComparisonOp::Le,
                 cut_bound,
-            )
+            ))
         } else {
             panic!("var {:?} is not basic!", var);
         }
     }
 
+    /// The moves of non-basic vars that keep the objective at its optimum: for each non-basic
+    /// var with a zero reduced cost that is not fixed, `(col, increase)` for each direction in
+    /// which it can move.
+    pub(crate) fn zero_cost_moves(&self) -> Vec<(usize, bool)> {
+        let mut moves = vec![];
+        for col in 0..self.nb_vars.len() {
+            if !self.nb_var_obj_coeffs[col].is_zero() || self.nb_var_is_fixed[col] {
+                continue;
+            }
+            let state = &self.nb_var_states[col];
+            if !state.at_max {
+                moves.push((col, true));
+            }
+            if !state.at_min {
+                moves.push((col, false));
+            }
+        }
+        moves
+    }
+
+    /// Pivot the non-basic var of `col` into the basis, or to its other bound, moving it in
+    /// the given direction. Returns false, without changing the basis, if nothing limits the
+    /// move.
+    pub(crate) fn move_nonbasic(&mut self, col: usize, increase: bool) -> Result<bool, Error> {
+        let pivot_info = match self.primal_ratio_test(col, increase) {
+            Ok(pivot_info) => pivot_info,
+            Err(Error::Unbounded(_)) => return Ok(false),
+            Err(err) => return Err(err),
+        };
+        self.pivot(&pivot_info)?;
+        Ok(true)
+    }
+
     pub(crate) fn num_constraints(&self) -> usize {
         self.orig_constraints.rows()
     }
 
-    fn num_total_vars(&self) -> usize {
-        self.num_vars + self.num_constraints()
+    /// Number of vars including the slack vars, of which there is at most one per constraint.
+    pub(crate) fn num_total_vars(&self) -> usize {
+        self.orig_constraints.cols()
+    }
+
+    /// The status of every non-slack var, followed by that of the slack var of every row. For a
+    /// slack var, the status refers to the bounds of the slack var itself rather than to those
+    /// of its row. An equality row without a slack var has the status of a fixed slack var.
+    pub(crate) fn basis_statuses(&self) -> Vec<BasisStatus> {
+        let status = |var: usize| match self.var_states[var] {
+            VarState::Basic(_) => BasisStatus::Basic,
+            VarState::NonBasic(col) => {
+                let state = &self.nb_var_states[col];
+                if state.at_max && !state.at_min {
+                    BasisStatus::NonbasicUpper
+                } else {
+                    BasisStatus::NonbasicLower
+                }
+            }
+        };
+        let slack_statuses = (self.slack_vars.iter())
+            .map(|slack_var| slack_var.map_or(BasisStatus::NonbasicLower, status));
+        (0..self.num_vars).map(status).chain(slack_statuses).collect()
+    }
+
+    /// Replace the initial basis by the given statuses of the non-slack vars and of the slack
+    /// vars of the rows (see [`Solver::basis_statuses`]). Must be called before
+    /// [`Solver::initial_solve`].
+    ///
+    /// The statuses need not form a valid basis. A crash procedure pivots the vars that should
+    /// be basic into the basis one by one, replacing basic vars that should not be basic. A var
+    /// for which no such basic var is left, because there are too many basic vars or because
+    /// its column depends on the columns already in the basis, stays non-basic; a row for which
+    /// no var is left keeps its initial basic var.
+    pub(crate) fn warm_start(&mut self, statuses: &[BasisStatus]) -> Result<(), Error> {
+        assert_eq!(statuses.len(), self.num_vars + self.num_constraints());
+        let mut var_statuses = statuses[..self.num_vars].to_vec();
+        var_statuses.resize(self.num_total_vars(), BasisStatus::NonbasicLower);
+        for (row, slack_var) in self.slack_vars.iter().enumerate() {
+            if let Some(slack_var) = slack_var {
+                var_statuses[*slack_var] = statuses[self.num_vars + row];
+            }
+        }
+        let statuses = var_statuses;
+
+        for var in 0..self.num_total_vars() {
+            let col = match self.var_states[var] {
+                VarState::NonBasic(col) if statuses[var] == BasisStatus::Basic => col,
+                _ => continue,
+            };
+
+            self.calc_col_coeffs(col)?;
+            // Choose the largest coefficient for stability.
+            let mut best: Option<(usize, &AbnormalFraction)> = None;
+            for (r, coeff) in self.col_coeffs.iter() {
+                if statuses[self.basic_vars[r]] == BasisStatus::Basic || coeff.is_zero() {
+                    continue;
+                }
+                if best.is_none_or(|(_, best_coeff)| coeff.clone().abs() > best_coeff.clone().abs())
+                {
+                    best = Some((r, coeff));
+                }
+            }
+            let Some((row, _)) = best else {
+                continue;
+            };
+
+            let leaving_var = self.basic_vars[row];
+            self.basic_vars[row] = var;
+            self.var_states[var] = VarState::Basic(row);
+            self.basic_var_mins[row] = self.orig_var_mins[var].clone();
+            self.basic_var_maxs[row] = self.orig_var_maxs[var].clone();
+            self.nb_vars[col] = leaving_var;
+            self.var_states[leaving_var] = VarState::NonBasic(col);
+            let var_col = self.orig_constraints_csc.outer_view(var).unwrap();
+            if self.basis_solver.replace_col(row, var_col.iter()).is_err() {
+                self.refactorise_or_recover("singular update")?;
+            }
+        }
+
+        for (col, &var) in self.nb_vars.iter().enumerate() {
+            let min = &self.orig_var_mins[var];
+            let max = &self.orig_var_maxs[var];
+            let val = match statuses[var] {
+                BasisStatus::NonbasicUpper if max.is_finite() => max.clone(),
+                _ if min.is_finite() => min.clone(),
+                _ if max.is_finite() => max.clone(),
+                _ => f0_ab!(),
+            };
+            self.nb_var_states[col] = NonBasicVarState {
+                at_min: &val == min,
+                at_max: &val == max,
+            };
+            self.nb_var_vals[col] = val;
+        }
+
+        self.reset_basis_state()
+    }
+
+    /// Recompute the values of the basic vars, the obj. coeffs, the feasibility and the pricing
+    /// weights after the basis was changed other than by pivots.
+    fn reset_basis_state(&mut self) -> Result<(), Error> {
+        self.recalc_basic_var_vals()?;
+        self.recalc_obj_coeffs()?;
+        self.is_primal_feasible = self.calc_primal_infeasibility().0 == 0;
+        self.is_dual_feasible = self.calc_dual_infeasibility().0 == 0;
+
+        if !self.is_primal_feasible && !self.is_dual_feasible {
+            self.use_artificial_obj();
+        }
+
+        if self.enable_dual_steepest_edge {
+            self.dual_edge_sq_norms = vec![f1_ab!(); self.num_constraints()];
+        }
+        if self.enable_primal_steepest_edge {
+            self.recalc_primal_sq_norms();
+        }
+        if self.enable_devex {
+            self.reset_devex_weights();
+        }
+        Ok(())
+    }
+
+    /// Replace the obj. coeffs by an artificial objective for which the current basis is dual
+    /// feasible, as in [`Solver::try_new`], such that the dual simplex can restore primal
+    /// feasibility before the real objective is optimised.
+    fn use_artificial_obj(&mut self) {
+        for (obj_coeff, state) in self.nb_var_obj_coeffs.iter_mut().zip(&self.nb_var_states) {
+            *obj_coeff = if state.at_min && !state.at_max {
+                f1_ab!()
+            } else if state.at_max && !state.at_min {
+                -f1_ab!()
+            } else {
+                f0_ab!()
+            };
+        }
+        self.cur_obj_val = f0_ab!();
+        self.is_dual_feasible = false;
+    }
+
+    /// Make the basis dual feasible for the dual simplex with the real objective. Non-basic
+    /// vars with an obj. coeff of the wrong sign are moved to their other bound, which is a
+    /// bound flip for boxed vars. A var without such a bound gets a temporary artificial bound
+    /// far from the values in the problem, which is removed after the dual simplex.
+    fn make_dual_feasible(&mut self) -> Result<(), Error> {
+        self.recalc_obj_coeffs()?;
+
+        let mut largest_val = f1_ab!();
+        for val in self
+            .orig_var_mins
+            .iter()
+            .chain(&self.orig_var_maxs)
+            .chain(&self.orig_rhs)
+        {
+            if val.is_finite() {
+                let abs = val.clone().abs();
+                if abs > largest_val {
+                    largest_val = abs;
+                }
+            }
+        }
+        let artificial_bound = largest_val * f_ab!(1usize << 20);
+
+        for col in 0..self.nb_vars.len() {
+            let var = self.nb_vars[col];
+            let obj_coeff = &self.nb_var_obj_coeffs[col];
+            let state = &self.nb_var_states[col];
+            let to_min = if obj_coeff.is_positive() && !state.at_min {
+                true
+            } else if obj_coeff.is_negative() && !state.at_max {
+                false
+            } else {
+                continue;
+            };
+
+            if to_min && self.orig_var_mins[var].is_infinite() {
+                self.artificial_bounds.push((
+                    var,
+                    self.orig_var_mins[var].clone(),
+                    self.orig_var_maxs[var].clone(),
+                ));
+                self.orig_var_mins[var] = -artificial_bound.clone();
+            } else if !to_min && self.orig_var_maxs[var].is_infinite() {
+                self.artificial_bounds.push((
+                    var,
+                    self.orig_var_mins[var].clone(),
+                    self.orig_var_maxs[var].clone(),
+                ));
+                self.orig_var_maxs[var] = artificial_bound.clone();
+            }
+
+            let min = &self.orig_var_mins[var];
+            let max = &self.orig_var_maxs[var];
+            let val = if to_min { min.clone() } else { max.clone() };
+            self.nb_var_states[col] = NonBasicVarState {
+                at_min: &val == min,
+                at_max: &val == max,
+            };
+            self.nb_var_vals[col] = val;
+        }
+
+        self.recalc_basic_var_vals()?;
+        self.recalc_obj_coeffs()?;
+        self.is_dual_feasible = true;
+        self.is_primal_feasible = self.calc_primal_infeasibility().0 == 0;
+        debug!(
+            "dual simplex: made basis dual feasible, artificial bounds: {}",
+            self.artificial_bounds.len()
+        );
+        Ok(())
+    }
+
+    /// Restore the bounds replaced by [`Solver::make_dual_feasible`]. A non-basic var at an
+    /// artificial bound is left between its bounds, for the primal simplex to move.
+    fn remove_artificial_bounds(&mut self) {
+        for (var, min, max) in std::mem::take(&mut self.artificial_bounds) {
+            self.orig_var_mins[var] = min;
+            self.orig_var_maxs[var] = max;
+            match self.var_states[var] {
+                VarState::Basic(r) => {
+                    self.basic_var_mins[r] = self.orig_var_mins[var].clone();
+                    self.basic_var_maxs[r] = self.orig_var_maxs[var].clone();
+                }
+                VarState::NonBasic(col) => {
+                    let val = &self.nb_var_vals[col];
+                    self.nb_var_states[col] = NonBasicVarState {
+                        at_min: val == &self.orig_var_mins[var],
+                        at_max: val == &self.orig_var_maxs[var],
+                    };
+                    self.is_dual_feasible = false;
+                }
+            }
+        }
     }
 
     pub(crate) fn initial_solve(&mut self) -> Result<(), Error> {
+        if self.basic_vars.is_empty() {
+            // Without constraints, every var is optimal at the bound that its objective coeff
+            // prefers; if that bound is infinite, the objective is unbounded.
+            if !self.is_dual_feasible {
+                self.move_to_preferred_bounds()?;
+            }
+            debug!("solved without constraints, obj.: {}", self.cur_obj_val);
+            return Ok(());
+        }
+
+        self.solve_phases()?;
+
+        // Disable updates of primal sq. norms, because lengthy primal simplex runs
+        // are unlikely after the initial solve.
+        self.enable_primal_steepest_edge = false;
+
+        if log_enabled!(Level::Debug) {
+            debug!("solved, obj.: {}, {}", self.cur_obj_val, self.stats);
+        }
+        Ok(())
+    }
+
+    /// Without constraints: move every non-basic var to the bound that its objective coeff
+    /// prefers. try_new already puts them there, but a warm start keeps the bounds of the
+    /// basis, which may come from another objective.
+    fn move_to_preferred_bounds(&mut self) -> Result<(), Error> {
+        for (col, &var) in self.nb_vars.iter().enumerate() {
+            let obj_coeff = &self.orig_obj_coeffs[var];
+            let val = if obj_coeff.is_positive() {
+                &self.orig_var_mins[var]
+            } else if obj_coeff.is_negative() {
+                &self.orig_var_maxs[var]
+            } else {
+                continue;
+            };
+            if val.is_infinite() {
+                return Err(Error::Unbounded(self.unbounded_var_ray()));
+            }
+            self.nb_var_states[col] = NonBasicVarState {
+                at_min: val == &self.orig_var_mins[var],
+                at_max: val == &self.orig_var_maxs[var],
+            };
+            self.nb_var_vals[col] = val.clone();
+        }
+        self.recalc_obj_coeffs()?;
+        self.is_dual_feasible = true;
+        Ok(())
+    }
+
+    /// Whether the LU factors leave out entries below a drop tolerance.
+    pub(crate) fn drops_entries(&self) -> bool {
+        self.basis_solver.scratch.drop_tolerance().is_some()
+    }
+
+    /// Solve again from the current basis with factors that keep all entries, because the
+    /// solution with dropped entries violates the constraints.
+    pub(crate) fn solve_without_dropping(&mut self) -> Result<(), Error> {
+        warn!("solution with dropped LU entries violates the constraints, solving again");
+        self.basis_solver.disable_dropping();
+        self.refactorise("solving without a drop tolerance")?;
+        self.reset_basis_state()?;
+        self.solve_phases()
+    }
+
+    /// Solve from the current basis: restore primal feasibility, then optimise the objective.
+    /// The simplex loops call this again to start over when the basis was recovered from a
+    /// singularity (see [`Solver::refactorise_or_recover`]).
+    fn solve_phases(&mut self) -> Result<(), Error> {
+        self.basis_recovered = false;
+        if self.method == Method::DualSimplex && !self.is_dual_feasible {
+            self.make_dual_feasible()?;
+        }
+
         if !self.is_primal_feasible {
-            self.restore_feasibility()?;
+            match self.restore_feasibility() {
+                Err(Error::Infeasible(_)) if !self.artificial_bounds.is_empty() => {
+                    // Only the artificial bounds may make the problem infeasible, so start over
+                    // with an artificial objective instead.
+                    self.remove_artificial_bounds();
+                    self.use_artificial_obj();
+                    self.restore_feasibility()?;
+                }
+                res => res?,
+            }
         }
+        self.remove_artificial_bounds();
 
         if !self.is_dual_feasible {
-            self.recalc_obj_coeffs();
+            self.recalc_obj_coeffs()?;
+            if self.enable_perturbation {
+                self.perturb_bounds();
+            }
             self.optimize()?;
+            self.remove_perturbation()?;
         }
+        Ok(())
+    }
 
-        // Disable updates of primal sq. norms, because lengthy primal simplex runs
-        // are unlikely after the initial solve.
-        self.enable_primal_steepest_edge = false;
+    /// Widen the bounds of the basic vars by distinct tiny amounts. Degenerate basic vars then
+    /// no longer sit at their bounds, and ties in the ratio test become unlikely, which keeps
+    /// the primal simplex from stalling on degenerate problems.
+    fn perturb_bounds(&mut self) {
+        // Still perturbed if the phases started over after a recovery of the basis.
+        if self.unperturbed_bounds.is_some() {
+            return;
+        }
+        // The perturbations must be tiny compared to the bounds of the problem.
+        let mut largest_bound = f1_ab!();
+        for bound in self.basic_var_mins.iter().chain(&self.basic_var_maxs) {
+            if bound.is_finite() {
+                let abs = bound.clone().abs();
+                if abs > largest_bound {
+                    largest_bound = abs;
+                }
+            }
+        }
+        let base = largest_bound * f_ab!(1usize << 20);
+
+        self.unperturbed_bounds = Some((self.orig_var_mins.clone(), self.orig_var_maxs.clone()));
+        for (r, &var) in self.basic_vars.iter().enumerate() {
+            if self.orig_var_mins[var] == self.orig_var_maxs[var] {
+                continue;
+            }
+            let eps = f1_ab!() / (&base * &f_ab!(r + 1));
+            if self.orig_var_mins[var].is_finite() {
+                self.orig_var_mins[var] -= eps.clone();
+                self.basic_var_mins[r] = self.orig_var_mins[var].clone();
+            }
+            if self.orig_var_maxs[var].is_finite() {
+                self.orig_var_maxs[var] += eps;
+                self.basic_var_maxs[r] = self.orig_var_maxs[var].clone();
+            }
+        }
+        debug!("perturbed bounds of {} basic vars", self.basic_vars.len());
+    }
+
+    /// Restore the original bounds after [`Solver::perturb_bounds`]. Non-basic vars are moved
+    /// from their perturbed bounds to their original ones, and the dual simplex removes the
+    /// remaining primal infeasibilities, which keeps the basis optimal.
+    fn remove_perturbation(&mut self) -> Result<(), Error> {
+        let Some((mins, maxs)) = self.unperturbed_bounds.take() else {
+            return Ok(());
+        };
+        self.orig_var_mins = mins;
+        self.orig_var_maxs = maxs;
+        for (r, &var) in self.basic_vars.iter().enumerate() {
+            self.basic_var_mins[r] = self.orig_var_mins[var].clone();
+            self.basic_var_maxs[r] = self.orig_var_maxs[var].clone();
+        }
+
+        let mut is_moved = false;
+        for (col, &var) in self.nb_vars.iter().enumerate() {
+            let min = &self.orig_var_mins[var];
+            let max = &self.orig_var_maxs[var];
+            let val = &mut self.nb_var_vals[col];
+            if &*val < min {
+                *val = min.clone();
+            } else if &*val > max {
+                *val = max.clone();
+            } else {
+                continue;
+            }
+            self.nb_var_states[col] = NonBasicVarState {
+                at_min: &*val == min,
+                at_max: &*val == max,
+            };
+            is_moved = true;
+        }
+        if is_moved {
+            self.recalc_basic_var_vals()?;
+            self.recalc_obj_coeffs()?;
+        }
 
+        self.is_primal_feasible = self.calc_primal_infeasibility().0 == 0;
+        if !self.is_primal_feasible {
+            self.restore_feasibility()?;
+        }
         Ok(())
     }
 
@@ -502,8 +1119,16 @@ impl Solver {
                 );
             }
 
+            self.check_cancelled()?;
+            if self.basis_recovered {
+                return self.solve_phases();
+            }
             if let Some(pivot_info) = self.choose_pivot()? {
-                self.pivot(&pivot_info);
+                let (entering_var, leaving_var) = self.pivot_vars(&pivot_info);
+                self.pivot(&pivot_info)?;
+                self.stats.phase2_iterations += 1;
+                self.stats.record_pivot(entering_var, leaving_var);
+                self.report_iteration(Phase::Optimality, entering_var, leaving_var)?;
             } else {
                 debug!(
                     "found optimum in {} iterations, obj.: {}",
@@ -534,11 +1159,33 @@ impl Solver {
                 );
             }
 
-            if let Some((row, leaving_new_val)) = self.choose_pivot_row_dual() {
-                self.calc_row_coeffs(row);
-                let pivot_info = self.choose_entering_col_dual(row, leaving_new_val)?;
-                self.calc_col_coeffs(pivot_info.col);
-                self.pivot(&pivot_info);
+            self.check_cancelled()?;
+            if self.basis_recovered {
+                return self.solve_phases();
+            }
+            let pricing_start = self.start_timer();
+            let pivot_row = self.choose_pivot_row_dual();
+            self.stop_timer(pricing_start, |timings, time| timings.pricing += time);
+            if let Some((row, leaving_new_val)) = pivot_row {
+                self.calc_row_coeffs(row)?;
+                let ratio_test_start = self.start_timer();
+                let pivot_info = self.choose_entering_col_dual(row, leaving_new_val, true);
+                self.stop_timer(ratio_test_start, |timings, time| timings.ratio_test += time);
+                let (mut pivot_info, flips) = pivot_info?;
+                if !flips.is_empty() {
+                    self.flip_bounds(&flips);
+                    let elem = pivot_info.elem.as_ref().unwrap();
+                    pivot_info.entering_diff =
+                        &(&self.basic_var_vals[row] - &elem.leaving_new_val) / &elem.coeff;
+                    pivot_info.entering_new_val =
+                        &self.nb_var_vals[pivot_info.col] + &pivot_info.entering_diff;
+                }
+                self.calc_col_coeffs(pivot_info.col)?;
+                let (entering_var, leaving_var) = self.pivot_vars(&pivot_info);
+                self.pivot(&pivot_info)?;
+                self.stats.phase1_iterations += 1;
+                self.stats.record_pivot(entering_var, leaving_var);
+                self.report_iteration(Phase::Feasibility, entering_var, leaving_var)?;
             } else {
                 debug!(
                     "restored feasibility in {} iterations, {}: {}",
@@ -554,76 +1201,278 @@ impl Solver {
         Ok(())
     }
 
-    pub(crate) fn add_constraint(
-        &mut self,
-        mut coeffs: CsVec,
-        cmp_op: ComparisonOp,
-        rhs: AbnormalFraction,
-    ) -> Result<(), Error> {
-        assert!(self.is_primal_feasible);
-        assert!(self.is_dual_feasible);
-
-        if coeffs.indices().is_empty() {
-            let is_tautological = match cmp_op {
-                ComparisonOp::Eq => rhs.is_zero(),
-                ComparisonOp::Le => rhs.is_not_negative(),
-                ComparisonOp::Ge => rhs.is_not_positive(),
-            };
+    /// Start measuring the time of a part of the solver, if timings are collected.
+    fn start_timer(&self) -> Option<Instant> {
+        self.stats.timings.as_ref().map(|_| Instant::now())
+    }
 
-            if is_tautological {
-                return Ok(());
-            } else {
-                return Err(Error::Infeasible);
-            }
+    /// Add the time since `start` to the timing chosen by `add`.
+    fn stop_timer(&mut self, start: Option<Instant>, add: fn(&mut SolveTimings, Duration)) {
+        if let (Some(start), Some(timings)) = (start, &mut self.stats.timings) {
+            add(timings, start.elapsed());
         }
+    }
 
-        let slack_var = self.num_total_vars();
-        let (slack_var_min, slack_var_max) = match cmp_op {
-            ComparisonOp::Le => (f0_ab!(), AbnormalFraction::infinity()),
-            ComparisonOp::Ge => (AbnormalFraction::neg_infinity(), f0_ab!()),
-            ComparisonOp::Eq => (f0_ab!(), f0_ab!()),
-        };
+    /// Number of nonzeros in the basis matrix.
+    fn basis_nnz(&self) -> usize {
+        self.basic_vars
+            .iter()
+            .map(|&var| self.orig_constraints_csc.outer_view(var).unwrap().nnz())
+            .sum()
+    }
 
-        self.orig_obj_coeffs.push(f0_ab!());
-        self.orig_var_mins.push(slack_var_min.clone());
-        self.orig_var_maxs.push(slack_var_max.clone());
-        self.var_states.push(VarState::Basic(self.basic_vars.len()));
-        self.basic_vars.push(slack_var);
-        self.basic_var_mins.push(slack_var_min);
-        self.basic_var_maxs.push(slack_var_max);
+    /// Compute a fresh LU factorisation of the basis matrix and drop the updates. `reason` only
+    /// ends up in the log.
+    fn refactorise(&mut self, reason: &str) -> Result<(), Error> {
+        let updates = self.basis_solver.num_updates();
+        let update_dropped_entries = self.basis_solver.lu_factors.dropped_entries();
+        let start = Instant::now();
+        self.basis_solver.reset(
+            &self.orig_constraints_csc,
+            &self.basic_vars,
+            self.cancel.as_deref(),
+        )?;
+        let time = start.elapsed();
+        if let Some(timings) = &mut self.stats.timings {
+            timings.lu_factorisation += time;
+        }
+        let factor = FactorStats {
+            basis_nnz: self.basis_nnz(),
+            updates,
+            update_dropped_entries,
+            time,
+            ..self.basis_solver.factor_stats.clone()
+        };
+        let (basis_nnz, lu_nnz) = (factor.basis_nnz, factor.lu_nnz());
+        let dense_cols = self.basis_solver.lu_factors.dense_cols().len();
+        self.stats.record_lu_factorisation(factor, dense_cols);
+        self.check_condition();
+        debug!(
+            "LU factorisation ({}): basis nnz: {}, LU nnz: {}, fill-in: {:.2}, dense columns: {}",
+            reason,
+            basis_nnz,
+            lu_nnz,
+            lu_nnz as f64 / basis_nnz.max(1) as f64,
+            dense_cols,
+        );
+        Ok(())
+    }
 
-        let mut lhs_val = f0_ab!();
-        for (var, coeff) in coeffs.iter() {
-            let val = match self.var_states[var] {
-                VarState::Basic(idx) => &self.basic_var_vals[idx],
-                VarState::NonBasic(idx) => &self.nb_var_vals[idx],
+    /// Like [`Solver::refactorise`], but if the basis matrix is singular, replace the basic var
+    /// without a pivot by the slack var of the row without a pivot, and factorise again. The
+    /// values and obj. coeffs are then recomputed for the new basis, and the simplex loops
+    /// start over from it. Slack vars are never replaced, so this ends after at most one
+    /// recovery per row. A row without a slack var cannot be recovered.
+    fn refactorise_or_recover(&mut self, reason: &str) -> Result<(), Error> {
+        let mut num_recoveries = 0;
+        loop {
+            let err = match self.refactorise(reason) {
+                Ok(()) => break,
+                Err(err) => err,
             };
-            lhs_val += val * coeff;
+            let Error::SingularBasis {
+                basis_col: Some(basis_col),
+                row: Some(row),
+                ..
+            } = err
+            else {
+                return Err(err);
+            };
+            let leaving_var = self.basic_vars[basis_col];
+            let Some(slack_var) = self.slack_vars[row] else {
+                return Err(err);
+            };
+            let col = match self.var_states[slack_var] {
+                VarState::NonBasic(col) if leaving_var < self.num_vars => col,
+                _ => return Err(err),
+            };
+            warn!(
+                "singular basis: replacing basic var {} by the slack var of row {} ({})",
+                leaving_var, row, err
+            );
+
+            self.basic_vars[basis_col] = slack_var;
+            self.var_states[slack_var] = VarState::Basic(basis_col);
+            self.basic_var_mins[basis_col] = self.orig_var_mins[slack_var].clone();
+            self.basic_var_maxs[basis_col] = self.orig_var_maxs[slack_var].clone();
+            self.nb_vars[col] = leaving_var;
+            self.var_states[leaving_var] = VarState::NonBasic(col);
+            let min = &self.orig_var_mins[leaving_var];
+            let max = &self.orig_var_maxs[leaving_var];
+            let val = if min.is_finite() {
+                min.clone()
+            } else if max.is_finite() {
+                max.clone()
+            } else {
+                f0_ab!()
+            };
+            self.nb_var_states[col] = NonBasicVarState {
+                at_min: &val == min,
+                at_max: &val == max,
+            };
+            self.nb_var_vals[col] = val;
+            self.nb_var_is_fixed[col] = false;
+            num_recoveries += 1;
         }
-        self.basic_var_vals.push(&rhs - &lhs_val);
 
-        let new_num_total_vars = self.num_total_vars() + 1;
-        let mut new_orig_constraints = CsMat::empty(CompressedStorage::CSR, new_num_total_vars);
-        for row in self.orig_constraints.outer_iterator() {
-            new_orig_constraints =
-                new_orig_constraints.append_outer_csvec(resized_view(&row, new_num_total_vars));
+        if num_recoveries > 0 {
+            self.stats.recovery_count += num_recoveries;
+            self.reset_basis_state()?;
+            self.basis_recovered = true;
         }
-        coeffs = into_resized(coeffs, new_num_total_vars);
-        coeffs.append(slack_var, f1_ab!());
-        new_orig_constraints = new_orig_constraints.append_outer_csvec(coeffs.view());
+        Ok(())
+    }
+
+    /// Record the condition estimate of the last LU factorisation, and warn if it exceeds the
+    /// limit of [`SolveOptions::condition_limit`].
+    fn check_condition(&mut self) {
+        let (Some(estimate), Some(limit)) = (
+            self.basis_solver.condition_estimate,
+            self.basis_solver.lu_settings.condition_limit,
+        ) else {
+            return;
+        };
+        self.stats.record_condition_estimate(estimate);
+        if estimate > limit {
+            warn!(
+                "ill-conditioned basis: condition number estimate {:.3e} exceeds {:.3e}",
+                estimate, limit
+            );
+        }
+    }
+
+    fn check_cancelled(&self) -> Result<(), Error> {
+        match &self.cancel {
+            Some(cancel) if cancel.load(Ordering::Relaxed) => {
+                Err(Error::Interrupted(PartialSolution::default()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The var that enters the basis and the var that leaves it, if any.
+    fn pivot_vars(&self, pivot_info: &PivotInfo) -> (usize, Option<usize>) {
+        (
+            self.nb_vars[pivot_info.col],
+            pivot_info
+                .elem
+                .as_ref()
+                .map(|elem| self.basic_vars[elem.row]),
+        )
+    }
+
+    /// Log the iteration and pass it to the callback of [`SolveOptions::on_iteration`], if any.
+    fn report_iteration(
+        &self,
+        phase: Phase,
+        entering_var: usize,
+        leaving_var: Option<usize>,
+    ) -> Result<(), Error> {
+        trace!(
+            "iteration {} ({:?}): obj.: {}, entering var: {}, leaving var: {:?}",
+            self.stats.iterations(),
+            phase,
+            self.cur_obj_val,
+            entering_var,
+            leaving_var,
+        );
+
+        let Some(reporter) = &self.reporter else {
+            return Ok(());
+        };
+        let primal_infeasibility = match phase {
+            Phase::Feasibility => Some(self.calc_primal_infeasibility().1),
+            Phase::Optimality => None,
+        };
+        reporter.report(
+            self.stats.iterations(),
+            phase,
+            &self.cur_obj_val,
+            primal_infeasibility,
+            entering_var,
+            leaving_var,
+        )
+    }
+
+    /// Replace the objective by one with the given coefficients of the non-slack vars and
+    /// re-optimise. The current basis stays primal feasible, so only phase 2 is needed.
+    pub(crate) fn change_objective(
+        &mut self,
+        obj_coeffs: &[AbnormalFraction],
+    ) -> Result<(), Error> {
+        assert!(self.is_primal_feasible);
+        assert_eq!(obj_coeffs.len(), self.num_vars);
+
+        for (var, coeff) in self.orig_obj_coeffs.iter_mut().enumerate() {
+            *coeff = obj_coeffs.get(var).cloned().unwrap_or_else(|| f0_ab!());
+        }
+        self.recalc_obj_coeffs()?;
+        self.is_dual_feasible = self.calc_dual_infeasibility().0 == 0;
+        if !self.is_dual_feasible {
+            self.optimize()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn add_constraint(&mut self, constraint: Constraint) -> Result<(), Error> {
+        assert!(self.is_primal_feasible);
+        assert!(self.is_dual_feasible);
+
+        if constraint.coeffs.indices().is_empty() {
+            if constraint.is_satisfied_by(&f0_ab!()) {
+                return Ok(());
+            } else {
+                return Err(Error::Infeasible(InfeasibilityCertificate::default()));
+            }
+        }
+
+        let slack_var = self.num_total_vars();
+        let (rhs, slack_var_min, slack_var_max) = constraint.slack();
+        let mut coeffs = constraint.coeffs;
+
+        self.orig_obj_coeffs.push(f0_ab!());
+        self.orig_var_mins.push(slack_var_min.clone());
+        self.orig_var_maxs.push(slack_var_max.clone());
+        self.var_states.push(VarState::Basic(self.basic_vars.len()));
+        if self.enable_devex {
+            self.devex_reference.push(false);
+        }
+        self.slack_vars.push(Some(slack_var));
+        self.basic_vars.push(slack_var);
+        self.basic_var_mins.push(slack_var_min);
+        self.basic_var_maxs.push(slack_var_max);
+
+        let mut lhs_val = f0_ab!();
+        for (var, coeff) in coeffs.iter() {
+            let val = match self.var_states[var] {
+                VarState::Basic(idx) => &self.basic_var_vals[idx],
+                VarState::NonBasic(idx) => &self.nb_var_vals[idx],
+            };
+            lhs_val += val * coeff;
+        }
+        self.basic_var_vals.push(&rhs - &lhs_val);
+
+        let new_num_total_vars = self.num_total_vars() + 1;
+        let mut new_orig_constraints = CsMat::empty(CompressedStorage::CSR, new_num_total_vars);
+        for row in self.orig_constraints.outer_iterator() {
+            new_orig_constraints =
+                new_orig_constraints.append_outer_csvec(resized_view(&row, new_num_total_vars));
+        }
+        coeffs = into_resized(coeffs, new_num_total_vars);
+        coeffs.append(slack_var, f1_ab!());
+        new_orig_constraints = new_orig_constraints.append_outer_csvec(coeffs.view());
 
         self.orig_rhs.push(rhs.clone());
 
         self.orig_constraints = new_orig_constraints;
         self.orig_constraints_csc = self.orig_constraints.to_csc();
 
-        self.basis_solver
-            .reset(&self.orig_constraints_csc, &self.basic_vars);
+        self.refactorise("constraint added")?;
 
         if self.enable_primal_steepest_edge || self.enable_dual_steepest_edge {
             // existing tableau rows didn't change, so we calc the last row
             // and add its contribution to the sq. norms.
-            self.calc_row_coeffs(self.num_constraints() - 1);
+            self.calc_row_coeffs(self.num_constraints() - 1)?;
 
             if self.enable_primal_steepest_edge {
                 for (c, coeff) in self.row_coeffs.iter() {
@@ -667,8 +1516,11 @@ impl Solver {
         let mut num_vars = 0;
         let mut infeasibility = f0_ab!();
         for (obj_coeff, var_state) in self.nb_var_obj_coeffs.iter().zip(&self.nb_var_states) {
-            if !(var_state.at_min && obj_coeff.is_not_negative())
-                && !(var_state.at_max && obj_coeff.is_not_positive())
+            if obj_coeff.is_zero() {
+                continue;
+            }
+            if !((var_state.at_min && obj_coeff.is_not_negative())
+                || (var_state.at_max && obj_coeff.is_not_positive()))
             {
                 num_vars += 1;
                 infeasibility += obj_coeff.clone().abs();
@@ -678,19 +1530,42 @@ impl Solver {
     }
 
     /// Calculate current coeffs column for a single non-basic variable.
-    fn calc_col_coeffs(&mut self, c_var: usize) {
+    fn calc_col_coeffs(&mut self, c_var: usize) -> Result<(), Error> {
+        let start = self.start_timer();
         let var = self.nb_vars[c_var];
         let orig_col = self.orig_constraints_csc.outer_view(var).unwrap();
         self.basis_solver
             .solve(orig_col.iter())
             .to_sparse_vec(&mut self.col_coeffs);
+        if self.check_residuals {
+            let residual = self.col_residual(var);
+            if let Some(norm) = residual_too_large(&residual, orig_col.data()) {
+                self.refactorise_for_residual("column", norm)?;
+                let orig_col = self.orig_constraints_csc.outer_view(var).unwrap();
+                self.basis_solver
+                    .solve(orig_col.iter())
+                    .to_sparse_vec(&mut self.col_coeffs);
+            }
+        }
+        self.stop_timer(start, |timings, time| timings.ftran_btran += time);
+        Ok(())
     }
 
     /// Calculate current coeffs row for a single constraint (permuted according to nb_vars).
-    fn calc_row_coeffs(&mut self, r_constr: usize) {
+    fn calc_row_coeffs(&mut self, r_constr: usize) -> Result<(), Error> {
+        let start = self.start_timer();
         self.basis_solver
             .solve_transp(std::iter::once((r_constr, &f1_ab!())))
             .to_sparse_vec(&mut self.inv_basis_row_coeffs);
+        if self.check_residuals {
+            let residual = self.row_residual(r_constr);
+            if let Some(norm) = residual_too_large(&residual, &[f1_ab!()]) {
+                self.refactorise_for_residual("row", norm)?;
+                self.basis_solver
+                    .solve_transp(std::iter::once((r_constr, &f1_ab!())))
+                    .to_sparse_vec(&mut self.inv_basis_row_coeffs);
+            }
+        }
 
         self.row_coeffs.clear_and_resize(self.nb_vars.len());
         for (r, coeff) in self.inv_basis_row_coeffs.iter() {
@@ -700,47 +1575,84 @@ impl Solver {
                 }
             }
         }
+        self.stop_timer(start, |timings, time| timings.ftran_btran += time);
+        Ok(())
+    }
+
+    /// The residual B·x − a of the last column solve, where a is the column of `var` and x is
+    /// in `col_coeffs`.
+    fn col_residual(&self, var: usize) -> Vec<AbnormalFraction> {
+        let mut residual = vec![f0_ab!(); self.num_constraints()];
+        for (r, val) in self.orig_constraints_csc.outer_view(var).unwrap().iter() {
+            residual[r] -= val.clone();
+        }
+        for (c, coeff) in self.col_coeffs.iter() {
+            let col = self
+                .orig_constraints_csc
+                .outer_view(self.basic_vars[c])
+                .unwrap();
+            for (r, val) in col.iter() {
+                residual[r] += val * coeff;
+            }
+        }
+        residual
+    }
+
+    /// The residual Bᵀ·y − e of the last row solve, where e is the unit vector of `r_constr`
+    /// and y is in `inv_basis_row_coeffs`.
+    fn row_residual(&self, r_constr: usize) -> Vec<AbnormalFraction> {
+        let mut y = vec![f0_ab!(); self.num_constraints()];
+        for (r, coeff) in self.inv_basis_row_coeffs.iter() {
+            y[r] = coeff.clone();
+        }
+        let mut residual = vec![f0_ab!(); self.num_constraints()];
+        for (c, &var) in self.basic_vars.iter().enumerate() {
+            for (r, val) in self.orig_constraints_csc.outer_view(var).unwrap().iter() {
+                residual[c] += val * &y[r];
+            }
+        }
+        residual[r_constr] -= f1_ab!();
+        residual
+    }
+
+    /// Factorise the basis matrix anew because the residual of a tableau `kind` solve had the
+    /// norm `norm`.
+    fn refactorise_for_residual(
+        &mut self,
+        kind: &str,
+        norm: AbnormalFraction,
+    ) -> Result<(), Error> {
+        warn!(
+            "residual {} of a tableau {} solve after {} updates is too large, refactorising",
+            norm,
+            kind,
+            self.basis_solver.num_updates()
+        );
+        self.stats.residual_refactors += 1;
+        self.refactorise("residual check")
     }
 
     fn choose_pivot(&mut self) -> Result<Option<PivotInfo>, Error> {
+        let pricing_start = self.start_timer();
         let entering_c = {
-            let filtered_obj_coeffs = self
-                .nb_var_obj_coeffs
-                .iter()
-                .zip(&self.nb_var_states)
-                .enumerate()
-                .filter_map(|(col, (obj_coeff, var_state))| {
-                    // Choose only among non-basic vars that can be changed
-                    // with objective decreasing.
-                    if (var_state.at_min && obj_coeff.is_not_negative())
-                        || (var_state.at_max && obj_coeff.is_not_positive())
-                    {
-                        None
-                    } else {
-                        Some((col, obj_coeff))
-                    }
-                });
-
+            let num_cols = self.nb_vars.len();
             let mut best_col = None;
-            let mut best_score = AbnormalFraction::neg_infinity();
-            if self.enable_primal_steepest_edge {
-                for (col, obj_coeff) in filtered_obj_coeffs {
-                    let score = obj_coeff * &(obj_coeff / &self.primal_edge_sq_norms[col]);
-                    if score > best_score {
-                        best_col = Some(col);
-                        best_score = score;
-                    }
-                }
-            } else {
-                for (col, obj_coeff) in filtered_obj_coeffs {
-                    let score = obj_coeff.clone().abs();
-                    if score > best_score {
-                        best_col = Some(col);
-                        best_score = score;
-                    }
-                }
+            if self.pricing_window < num_cols {
+                // Partial pricing: only look at a window of columns, continuing where the
+                // previous window ended.
+                let start = self.pricing_window_start;
+                let window = (start..start + self.pricing_window).map(|c| c % num_cols);
+                best_col = self.choose_entering_col(window);
+                self.pricing_window_start = (start + self.pricing_window) % num_cols;
             }
 
+            if best_col.is_none() {
+                // Only a scan over all columns can establish optimality.
+                self.stats.full_scans += 1;
+                best_col = self.choose_entering_col_full();
+            }
+
+            self.stop_timer(pricing_start, |timings, time| timings.pricing += time);
             if let Some(col) = best_col {
                 col
             } else {
@@ -748,20 +1660,29 @@ impl Solver {
             }
         };
 
-        let entering_cur_val = self.nb_var_vals[entering_c].clone();
         // If true, entering variable will increase (because the objective function must decrease).
-        println!("something {}", self.nb_var_obj_coeffs[entering_c]);
         let entering_diff_sign = self.nb_var_obj_coeffs[entering_c].is_negative();
-        println!("diff sign {}", entering_diff_sign);
+        self.primal_ratio_test(entering_c, entering_diff_sign)
+            .map(Some)
+    }
+
+    /// Find the pivot that moves the non-basic var of `entering_c` in the given direction as
+    /// far as the bounds of the basic vars allow.
+    fn primal_ratio_test(
+        &mut self,
+        entering_c: usize,
+        entering_diff_sign: bool,
+    ) -> Result<PivotInfo, Error> {
+        let entering_cur_val = self.nb_var_vals[entering_c].clone();
         let entering_other_val = if entering_diff_sign {
             self.orig_var_maxs[self.nb_vars[entering_c]].clone()
         } else {
             self.orig_var_mins[self.nb_vars[entering_c]].clone()
         };
-        println!("entering_other_val {}", entering_other_val);
 
-        self.calc_col_coeffs(entering_c);
+        self.calc_col_coeffs(entering_c)?;
 
+        let ratio_test_start = self.start_timer();
         let get_leaving_var_step = |r: usize, coeff: &AbnormalFraction| -> AbnormalFraction {
             let val = &self.basic_var_vals[r];
             // leaving_diff = -entering_diff * coeff. From this we can determine
@@ -785,7 +1706,7 @@ impl Solver {
         // https://link.springer.com/content/pdf/10.1007/BF01589114.pdf
 
         // First, we determine the max change in entering variable so that basic variables
-        // remain feasible using relaxed bounds.
+        // remain feasible using bounds relaxed by the tolerance.
         let mut max_step = (&entering_other_val - &entering_cur_val).abs();
         for (r, coeff) in self.col_coeffs.iter() {
             let coeff_abs = coeff.clone().abs();
@@ -794,8 +1715,13 @@ impl Solver {
             }
 
             // By which amount can we change the entering variable so that the limit on this
-            // basic var is not violated. The var with the minimum such amount becomes leaving.
-            let cur_step = (get_leaving_var_step(r, coeff)) / coeff_abs;
+            // basic var is not violated by more than the tolerance. The var with the minimum
+            // such amount becomes leaving.
+            let mut leaving_var_step = get_leaving_var_step(r, coeff);
+            if let Some(tolerance) = &self.harris_tolerance {
+                leaving_var_step += tolerance.clone();
+            }
+            let cur_step = leaving_var_step / coeff_abs;
             if cur_step < max_step {
                 max_step = cur_step;
             }
@@ -815,8 +1741,9 @@ impl Solver {
                 continue;
             }
 
+            // A basic var without a bound in the direction it moves never leaves.
             let cur_step = &get_leaving_var_step(r, &coeff) / &coeff_abs;
-            if cur_step <= max_step && coeff_abs > pivot_coeff_abs {
+            if cur_step.is_finite() && cur_step <= max_step && coeff_abs > pivot_coeff_abs {
                 leaving_r = Some(r);
                 leaving_new_val = if (entering_diff_sign && coeff.is_negative())
                     || (!entering_diff_sign && coeff.is_positive())
@@ -830,13 +1757,23 @@ impl Solver {
             }
         }
 
-        if let Some(row) = leaving_r {
-            self.calc_row_coeffs(row);
+        self.stop_timer(ratio_test_start, |timings, time| timings.ratio_test += time);
 
-            let entering_diff = &(&self.basic_var_vals[row] - &leaving_new_val) / &pivot_coeff;
+        if let Some(row) = leaving_r {
+            self.calc_row_coeffs(row)?;
+
+            let mut entering_diff = &(&self.basic_var_vals[row] - &leaving_new_val) / &pivot_coeff;
+            // A leaving var that is already beyond its bound by less than the tolerance would
+            // move the entering var backwards; pivot without a step instead.
+            if self.harris_tolerance.is_some()
+                && !entering_diff.is_zero()
+                && entering_diff.is_positive() != entering_diff_sign
+            {
+                entering_diff = f0_ab!();
+            }
             let entering_new_val = &entering_cur_val + &entering_diff;
 
-            Ok(Some(PivotInfo {
+            Ok(PivotInfo {
                 col: entering_c,
                 entering_new_val,
                 entering_diff,
@@ -845,21 +1782,97 @@ impl Solver {
                     coeff: pivot_coeff.clone(),
                     leaving_new_val,
                 }),
-            }))
+            })
         } else {
             if entering_other_val.is_infinite() {
-                return Err(Error::Unbounded);
+                return Err(Error::Unbounded(self.unbounded_col_ray(entering_c, entering_diff_sign)));
             }
 
-            Ok(Some(PivotInfo {
+            Ok(PivotInfo {
                 col: entering_c,
                 entering_diff: &entering_other_val - &entering_cur_val,
                 entering_new_val: entering_other_val.clone(),
                 elem: None,
-            }))
+            })
         }
     }
 
+    /// The score of a col as the entering col, or `None` if changing its var cannot decrease
+    /// the objective.
+    fn entering_score(&self, col: usize) -> Option<AbnormalFraction> {
+        let obj_coeff = &self.nb_var_obj_coeffs[col];
+        let var_state = &self.nb_var_states[col];
+        // A free var with a zero obj. coeff is at neither bound, but changing it does not
+        // decrease the objective either.
+        if obj_coeff.is_zero()
+            || (var_state.at_min && obj_coeff.is_not_negative())
+            || (var_state.at_max && obj_coeff.is_not_positive())
+        {
+            return None;
+        }
+
+        let score = if self.enable_primal_steepest_edge {
+            obj_coeff * &(obj_coeff / &self.primal_edge_sq_norms[col])
+        } else if self.enable_devex {
+            obj_coeff * &(obj_coeff / &self.devex_weights[col])
+        } else {
+            obj_coeff.clone().abs()
+        };
+        Some(score)
+    }
+
+    /// Choose the col with the best score; among equal scores, the first one.
+    fn choose_entering_col(&mut self, cols: impl Iterator<Item = usize>) -> Option<usize> {
+        let mut num_evaluated = 0;
+        let mut best_col = None;
+        let mut best_score = AbnormalFraction::neg_infinity();
+        for col in cols {
+            num_evaluated += 1;
+            let Some(score) = self.entering_score(col) else {
+                continue;
+            };
+            if score > best_score {
+                best_col = Some(col);
+                best_score = score;
+            }
+        }
+
+        self.stats.reduced_cost_evaluations += num_evaluated;
+        best_col
+    }
+
+    /// Choose the entering col among all cols.
+    fn choose_entering_col_full(&mut self) -> Option<usize> {
+        let num_cols = self.nb_vars.len();
+        #[cfg(feature = "parallel")]
+        if num_cols >= self.parallel_pricing_threshold {
+            return self.choose_entering_col_parallel(num_cols);
+        }
+        self.choose_entering_col(0..num_cols)
+    }
+
+    /// Same as [`Solver::choose_entering_col`] over all cols, but scores the cols in parallel.
+    /// Ties are broken by the lowest col, so the choice does not depend on the scheduling.
+    #[cfg(feature = "parallel")]
+    fn choose_entering_col_parallel(&mut self, num_cols: usize) -> Option<usize> {
+        use rayon::prelude::*;
+
+        let this = &*self;
+        let best = (0..num_cols)
+            .into_par_iter()
+            .filter_map(|col| Some((col, this.entering_score(col)?)))
+            .reduce_with(|a, b| {
+                if b.1 > a.1 || (b.1 == a.1 && b.0 < a.0) {
+                    b
+                } else {
+                    a
+                }
+            });
+
+        self.stats.reduced_cost_evaluations += num_cols;
+        best.map(|(col, _)| col)
+    }
+
     fn choose_pivot_row_dual(&self) -> Option<(usize, AbnormalFraction)> {
         let infeasibilities = self
             .basic_var_vals
@@ -924,11 +1937,19 @@ impl Solver {
         })
     }
 
+    /// Choose the entering variable of the dual simplex for the leaving variable in `row`.
+    ///
+    /// With `long_step` (and bound flipping enabled), the step may pass the breakpoints of
+    /// boxed variables whose reduced costs change sign: these variables move to their other
+    /// bound instead of entering, as long as the leaving variable stays on the infeasible side
+    /// of its bound. The columns to flip are returned with the pivot; the entering value of the
+    /// pivot does not yet account for them.
     fn choose_entering_col_dual(
         &self,
         row: usize,
         leaving_new_val: AbnormalFraction,
-    ) -> Result<PivotInfo, Error> {
+        long_step: bool,
+    ) -> Result<(PivotInfo, Vec<usize>), Error> {
         // True if the new obj. coeff. must be nonnegative in a dual-feasible configuration.
         let leaving_diff_sign = leaving_new_val > self.basic_var_vals[row];
 
@@ -936,9 +1957,9 @@ impl Solver {
             obj_coeff: &AbnormalFraction,
             var_state: &NonBasicVarState,
         ) -> AbnormalFraction {
-            if var_state.at_min && obj_coeff.is_negative() {
-                return f0_ab!();
-            } else if var_state.at_max && obj_coeff.is_positive() {
+            if (var_state.at_min && obj_coeff.is_negative())
+                || (var_state.at_max && obj_coeff.is_positive())
+            {
                 return f0_ab!();
             }
             obj_coeff.clone()
@@ -960,6 +1981,80 @@ impl Solver {
             }
         };
 
+        if long_step && self.enable_bound_flipping {
+            // Bound-flipping ratio test. See e.g.
+            // Kostina, E. (2002). The long step rule in the bounded-variable dual simplex
+            // method: Numerical experiments. Mathematical Methods of Operations Research,
+            // 55(3), 413-429.
+            let mut breakpoints = vec![];
+            for (c, coeff) in self.row_coeffs.iter() {
+                let var_state = &self.nb_var_states[c];
+                if !is_eligible_var(coeff, var_state) {
+                    continue;
+                }
+                let obj_coeff = clamp_obj_coeff(&self.nb_var_obj_coeffs[c], var_state);
+                let coeff_abs = coeff.clone().abs();
+                breakpoints.push((obj_coeff.abs() / coeff_abs.clone(), c, coeff_abs));
+            }
+            breakpoints.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then(a.1.cmp(&b.1)));
+
+            // The rate at which the dual objective improves with the step. Passing the
+            // breakpoint of a boxed variable flips it to its other bound, which lowers the
+            // rate by the change it causes in the leaving variable.
+            let mut slope = (&self.basic_var_vals[row] - &leaving_new_val).abs();
+            let mut flips = vec![];
+            for (_, c, coeff_abs) in &breakpoints[..breakpoints.len().saturating_sub(1)] {
+                let var = self.nb_vars[*c];
+                let var_state = &self.nb_var_states[*c];
+                let range = &self.orig_var_maxs[var] - &self.orig_var_mins[var];
+                if !range.is_finite()
+                    || self.nb_var_is_fixed[*c]
+                    || var_state.at_min == var_state.at_max
+                {
+                    break;
+                }
+                let new_slope = &slope - &(coeff_abs * &range);
+                if !new_slope.is_positive() {
+                    break;
+                }
+                slope = new_slope;
+                flips.push(*c);
+            }
+
+            if !flips.is_empty() {
+                // Among the breakpoints at the step where the slope changes sign, enter the
+                // variable with the biggest pivot coefficient.
+                let (step, first_col, first_coeff_abs) = &breakpoints[flips.len()];
+                let mut col = *first_col;
+                let mut pivot_coeff_abs = first_coeff_abs;
+                for (ratio, c, coeff_abs) in &breakpoints[flips.len() + 1..] {
+                    if ratio != step {
+                        break;
+                    }
+                    if coeff_abs > pivot_coeff_abs {
+                        col = *c;
+                        pivot_coeff_abs = coeff_abs;
+                    }
+                }
+                let pivot_coeff = self.row_coeffs.get(col).clone();
+                let entering_diff = &(&self.basic_var_vals[row] - &leaving_new_val) / &pivot_coeff;
+                let entering_new_val = &self.nb_var_vals[col] + &entering_diff;
+                return Ok((
+                    PivotInfo {
+                        col,
+                        entering_new_val,
+                        entering_diff,
+                        elem: Some(PivotElem {
+                            row,
+                            leaving_new_val,
+                            coeff: pivot_coeff,
+                        }),
+                    },
+                    flips,
+                ));
+            }
+        }
+
         // Harris rule. See e.g.
         // Gill, P. E., Murray, W., Saunders, M. A., & Wright, M. H. (1989).
         // A practical anti-cycling procedure for linearly constrained optimization.
@@ -968,7 +2063,7 @@ impl Solver {
         // https://link.springer.com/content/pdf/10.1007/BF01589114.pdf
 
         // First, we determine the max step (change in the leaving variable obj. coeff that still
-        // leaves us with a dual-feasible state) using relaxed bounds.
+        // leaves us with a dual-feasible state) using bounds relaxed by the tolerance.
         let mut max_step = AbnormalFraction::infinity();
         for (c, coeff) in self.row_coeffs.iter() {
             let var_state = &self.nb_var_states[c];
@@ -976,8 +2071,11 @@ impl Solver {
                 continue;
             }
 
-            let obj_coeff = clamp_obj_coeff(&self.nb_var_obj_coeffs[c], var_state);
-            let cur_step = (obj_coeff.clone().abs()) / coeff.clone().abs();
+            let mut obj_coeff = clamp_obj_coeff(&self.nb_var_obj_coeffs[c], var_state).abs();
+            if let Some(tolerance) = &self.harris_tolerance {
+                obj_coeff += tolerance.clone();
+            }
+            let cur_step = obj_coeff / coeff.clone().abs();
             if cur_step < max_step {
                 max_step = cur_step;
             }
@@ -1015,22 +2113,107 @@ impl Solver {
             let entering_diff = &(&self.basic_var_vals[row] - &leaving_new_val) / &pivot_coeff;
             let entering_new_val = &self.nb_var_vals[col] + &entering_diff;
 
-            Ok(PivotInfo {
-                col,
-                entering_new_val,
-                entering_diff,
-                elem: Some(PivotElem {
-                    row,
-                    leaving_new_val,
-                    coeff: pivot_coeff,
-                }),
-            })
+            Ok((
+                PivotInfo {
+                    col,
+                    entering_new_val,
+                    entering_diff,
+                    elem: Some(PivotElem {
+                        row,
+                        leaving_new_val,
+                        coeff: pivot_coeff,
+                    }),
+                },
+                vec![],
+            ))
         } else {
-            Err(Error::Infeasible)
+            Err(Error::Infeasible(self.farkas_certificate(leaving_diff_sign)))
+        }
+    }
+
+    /// The multipliers of the rows that prove the problem infeasible when the basic var of the
+    /// row in `inv_basis_row_coeffs` cannot reach its bound: that row of the basis inverse
+    /// combines the rows into the basic var plus non-basic vars which, at the bounds they are
+    /// held at, already give it its extreme value. With the rows as `a·x + s = rhs`, negating
+    /// the combination when the var must increase turns this into the form of
+    /// [`InfeasibilityCertificate`].
+    fn farkas_certificate(&self, increase: bool) -> InfeasibilityCertificate {
+        let mut multipliers = vec![f0_ab!(); self.num_constraints()];
+        for (r, coeff) in self.inv_basis_row_coeffs.iter() {
+            multipliers[r] = if increase { -coeff.clone() } else { coeff.clone() };
         }
+        InfeasibilityCertificate::new(multipliers)
     }
 
-    fn pivot(&mut self, pivot_info: &PivotInfo) {
+    /// The direction in which the non-basic var of `col` moves without limit (as the last
+    /// `primal_ratio_test` found) and the basic vars move along: the structural vars of the
+    /// change per unit of the move.
+    fn unbounded_col_ray(&self, col: usize, increase: bool) -> UnboundedRay {
+        let sign = if increase { f1_ab!() } else { -f1_ab!() };
+        let mut direction = vec![f0_ab!(); self.num_vars];
+        let entering_var = self.nb_vars[col];
+        if entering_var < self.num_vars {
+            direction[entering_var] = sign.clone();
+        }
+        for (r, coeff) in self.col_coeffs.iter() {
+            let var = self.basic_vars[r];
+            if var < self.num_vars {
+                direction[var] = -(&sign * coeff);
+            }
+        }
+        UnboundedRay::new(direction)
+    }
+
+    /// Without constraints: the direction of a var whose objective coeff prefers an infinite
+    /// bound.
+    fn unbounded_var_ray(&self) -> UnboundedRay {
+        let mut direction = vec![f0_ab!(); self.num_vars];
+        for (var, dir) in direction.iter_mut().enumerate() {
+            let obj_coeff = &self.orig_obj_coeffs[var];
+            if obj_coeff.is_negative() && self.orig_var_maxs[var].is_infinite() {
+                *dir = f1_ab!();
+                break;
+            } else if obj_coeff.is_positive() && self.orig_var_mins[var].is_infinite() {
+                *dir = -f1_ab!();
+                break;
+            }
+        }
+        UnboundedRay::new(direction)
+    }
+
+    /// Move the non-basic variables in `cols` to their other bound and update the basic
+    /// variables with a single FTRAN.
+    fn flip_bounds(&mut self, cols: &[usize]) {
+        let mut rhs = ScatteredVec::empty(self.num_constraints());
+        for &c in cols {
+            let var = self.nb_vars[c];
+            let (at_min, at_max) = (self.nb_var_states[c].at_min, self.nb_var_states[c].at_max);
+            let new_val = if at_min {
+                self.orig_var_maxs[var].clone()
+            } else {
+                self.orig_var_mins[var].clone()
+            };
+            let diff = &new_val - &self.nb_var_vals[c];
+            self.cur_obj_val += &self.nb_var_obj_coeffs[c] * &diff;
+            for (r, coeff) in self.orig_constraints_csc.outer_view(var).unwrap().iter() {
+                *rhs.get_mut(r) += &diff * coeff;
+            }
+            self.nb_var_vals[c] = new_val;
+            self.nb_var_states[c] = NonBasicVarState {
+                at_min: at_max,
+                at_max: at_min,
+            };
+        }
+
+        let start = self.start_timer();
+        for (r, coeff) in self.basis_solver.solve(rhs.iter()).iter() {
+            self.basic_var_vals[r] -= coeff.clone();
+        }
+        self.stop_timer(start, |timings, time| timings.ftran_btran += time);
+        self.stats.dual_bound_flips += cols.len();
+    }
+
+    fn pivot(&mut self, pivot_info: &PivotInfo) -> Result<(), Error> {
         // TODO: periodically (say, every 1000 pivots) recalc basic vars and object coeffs
         // from scratch for numerical stability.
 
@@ -1038,6 +2221,12 @@ impl Solver {
 
         let entering_var = self.nb_vars[pivot_info.col];
 
+        if pivot_info.elem.is_none() {
+            self.stats.bound_flips += 1;
+        } else if pivot_info.entering_diff.is_zero() {
+            self.stats.degenerate_pivots += 1;
+        }
+
         if pivot_info.elem.is_none() {
             // "entering" var is still non-basic, it just changes value from one limit
             // to the other.
@@ -1048,7 +2237,7 @@ impl Solver {
             let var_state = &mut self.nb_var_states[pivot_info.col];
             var_state.at_min = pivot_info.entering_new_val == self.orig_var_mins[entering_var];
             var_state.at_max = pivot_info.entering_new_val == self.orig_var_maxs[entering_var];
-            return;
+            return Ok(());
         }
 
         let pivot_elem = pivot_info.elem.as_ref().unwrap();
@@ -1090,7 +2279,11 @@ impl Solver {
         }
 
         if self.enable_primal_steepest_edge {
-            self.update_primal_sq_norms(pivot_info.col, &pivot_coeff);
+            self.update_primal_sq_norms(pivot_info.col, pivot_coeff);
+        }
+
+        if self.enable_devex {
+            self.update_devex_weights(pivot_info.col, pivot_coeff);
         }
 
         // Update basis itself
@@ -1100,17 +2293,38 @@ impl Solver {
         self.nb_vars[pivot_info.col] = leaving_var;
         self.var_states[leaving_var] = VarState::NonBasic(pivot_info.col);
 
-        // A simple heuristic to choose when to recompute LU factorization.
         // Note: a possible failure mode is that the LU factorization accidentally
         // generates a lot of fill-in and doesn't get recomputed for a long time.
-        let eta_matrices_nnz = self.basis_solver.eta_matrices.coeff_cols.nnz();
-        if eta_matrices_nnz < self.basis_solver.lu_factors.nnz() {
-            self.basis_solver
-                .push_eta_matrix(&self.col_coeffs, pivot_elem.row, pivot_coeff);
+        let policy = &self.refactor_policy;
+        let basis_solver = &self.basis_solver;
+        let fill_ratio =
+            f_ab!(basis_solver.updates_nnz()) / f_ab!(basis_solver.factors_nnz().max(1));
+        if basis_solver.num_updates() + 1 >= policy.max_updates {
+            self.stats.refactors_max_updates += 1;
+            self.refactorise_or_recover("max. updates")?;
+        } else if fill_ratio >= policy.max_fill_ratio {
+            self.stats.refactors_max_fill += 1;
+            self.refactorise_or_recover("max. fill ratio")?;
         } else {
-            self.basis_solver
-                .reset(&self.orig_constraints_csc, &self.basic_vars);
+            let col = self.orig_constraints_csc.outer_view(entering_var).unwrap();
+            if self
+                .basis_solver
+                .replace_col(pivot_elem.row, col.iter())
+                .is_err()
+            {
+                self.refactorise_or_recover("singular update")?;
+            } else if let Some(max_bits) = self.refactor_policy.max_coeff_bits {
+                let max_coeff_bits = (self.basis_solver.lu_factors.last_update_coeffs())
+                    .map(|coeff| coeff.complexity())
+                    .max()
+                    .unwrap_or(0);
+                if max_coeff_bits > max_bits as u64 {
+                    self.stats.refactors_max_coeff_bits += 1;
+                    self.refactorise_or_recover("max. coeff. bits")?;
+                }
+            }
         }
+        Ok(())
     }
 
     fn update_primal_sq_norms(&mut self, entering_col: usize, pivot_coeff: &AbnormalFraction) {
@@ -1160,6 +2374,68 @@ impl Solver {
         }
     }
 
+    fn update_devex_weights(&mut self, entering_col: usize, pivot_coeff: &AbnormalFraction) {
+        // Devex reference weights. See
+        // Forrest, J. J., & Goldfarb, D. (1992).
+        // Steepest-edge simplex algorithms for linear programming.
+        // Mathematical programming, 57(1-3), 341-374.
+        //
+        // The weight of the entering column can be computed exactly from the pivot column,
+        // restricted to the reference framework. If the stored weight has drifted too far,
+        // we start a new reference framework consisting of the current non-basic vars.
+
+        let entering_var = self.nb_vars[entering_col];
+        let mut exact_weight = if self.devex_reference[entering_var] {
+            f1_ab!()
+        } else {
+            f0_ab!()
+        };
+        for (r, coeff) in self.col_coeffs.iter() {
+            if self.devex_reference[self.basic_vars[r]] {
+                exact_weight += coeff * coeff;
+            }
+        }
+
+        let stored_weight = &self.devex_weights[entering_col];
+        let reset_ratio = f_ab!(3);
+        if stored_weight > &(&reset_ratio * &exact_weight)
+            || exact_weight > &reset_ratio * stored_weight
+        {
+            self.reset_devex_weights();
+            return;
+        }
+
+        let entering_weight = exact_weight;
+        let pivot_coeff_sq = pivot_coeff * pivot_coeff;
+        for (c, r_coeff) in self.row_coeffs.iter() {
+            if c == entering_col {
+                let weight = &entering_weight / &pivot_coeff_sq;
+                self.devex_weights[c] = if weight > f1_ab!() { weight } else { f1_ab!() };
+            } else {
+                let weight = &(&(r_coeff * r_coeff) / &pivot_coeff_sq) * &entering_weight;
+                if weight > self.devex_weights[c] {
+                    self.devex_weights[c] = weight;
+                }
+            }
+        }
+    }
+
+    fn reset_devex_weights(&mut self) {
+        for is_reference in self.devex_reference.iter_mut() {
+            *is_reference = false;
+        }
+        for &var in &self.nb_vars {
+            // At this point the basis has not been updated yet, thus the leaving var
+            // is not in the new reference framework, which is fine as its weight is 1.
+            self.devex_reference[var] = true;
+        }
+        for weight in self.devex_weights.iter_mut() {
+            *weight = f1_ab!();
+        }
+        self.stats.devex_resets += 1;
+        debug!("devex: reset reference framework");
+    }
+
     fn update_dual_sq_norms(&mut self, leaving_row: usize, pivot_coeff: &AbnormalFraction) {
         // Computations for the dual steepest edge pivoting rule.
         // See the same reference (Forrest, Goldfarb).
@@ -1183,8 +2459,7 @@ impl Solver {
         }
     }
 
-    #[allow(dead_code)]
-    fn recalc_basic_var_vals(&mut self) {
+    fn recalc_basic_var_vals(&mut self) -> Result<(), Error> {
         let mut cur_vals = self.orig_rhs.clone();
         for (i, var) in self.nb_vars.iter().enumerate() {
             let val = &self.nb_var_vals[i];
@@ -1195,41 +2470,24 @@ impl Solver {
             }
         }
 
-        if self.basis_solver.eta_matrices.len() > 0 {
-            self.basis_solver
-                .reset(&self.orig_constraints_csc, &self.basic_vars);
+        if self.basis_solver.num_updates() > 0 {
+            self.refactorise("recomputing basic var values")?;
         }
 
-        self.basis_solver
-            .lu_factors
-            .solve_dense(&mut cur_vals, &mut self.basis_solver.scratch);
+        self.basis_solver.solve_dense(&mut cur_vals);
         self.basic_var_vals = cur_vals;
+        Ok(())
     }
 
-    fn recalc_obj_coeffs(&mut self) {
-        if self.basis_solver.eta_matrices.len() > 0 {
-            self.basis_solver
-                .reset(&self.orig_constraints_csc, &self.basic_vars);
+    fn recalc_obj_coeffs(&mut self) -> Result<(), Error> {
+        if self.basis_solver.num_updates() > 0 {
+            self.refactorise("recomputing objective coeffs")?;
         }
 
-        let multipliers = {
-            let mut rhs = vec![f0_ab!(); self.num_constraints()];
-            for (c, &var) in self.basic_vars.iter().enumerate() {
-                rhs[c] = self.orig_obj_coeffs[var].clone();
-            }
-            self.basis_solver
-                .lu_factors_transp
-                .solve_dense(&mut rhs, &mut self.basis_solver.scratch);
-            rhs
-        };
-
-        self.nb_var_obj_coeffs.clear();
-        for &var in &self.nb_vars {
-            let col = self.orig_constraints_csc.outer_view(var).unwrap();
-            let dot_prod: AbnormalFraction = col.iter().map(|(r, val)| val * &multipliers[r]).sum();
-            self.nb_var_obj_coeffs
-                .push(&self.orig_obj_coeffs[var] - &dot_prod);
-        }
+        let multipliers = self.duals();
+        self.nb_var_obj_coeffs = (self.nb_vars.iter())
+            .map(|&var| self.reduced_cost(var, &multipliers, false))
+            .collect();
 
         self.cur_obj_val = f0_ab!();
         for (r, &var) in self.basic_vars.iter().enumerate() {
@@ -1238,9 +2496,9 @@ impl Solver {
         for (c, &var) in self.nb_vars.iter().enumerate() {
             self.cur_obj_val += &self.orig_obj_coeffs[var] * &self.nb_var_vals[c];
         }
+        Ok(())
     }
 
-    #[allow(dead_code)]
     fn recalc_primal_sq_norms(&mut self) {
         self.primal_edge_sq_norms.clear();
         for &var in &self.nb_vars {
@@ -1273,48 +2531,370 @@ struct PivotElem {
 /// Stuff related to inversion of the basis matrix
 #[derive(Clone)]
 struct BasisSolver {
-    lu_factors: LUFactors,
-    lu_factors_transp: LUFactors,
-    scratch: ScratchSpace,
-    eta_matrices: EtaMatrices,
+    lu_factors: UpdatableLU,
+    scratch: LuScratch,
     rhs: ScatteredVec,
+    lu_settings: LuSettings,
+    /// Counters of the last LU factorisation, before the dense columns were restored. The
+    /// solver fills in the rest.
+    factor_stats: FactorStats,
+    /// Nonzeros of the LU factors after the dense columns were restored.
+    factors_nnz: usize,
+    /// The first updates restore the dense columns rather than record pivots.
+    num_dense_updates: usize,
+    /// Estimated condition number of the basis matrix at the last LU factorisation.
+    condition_estimate: Option<f64>,
+    /// The last LU factorisation and the basic variables that it factorised, kept for partial
+    /// refactorisations.
+    last_factors: Option<(LUFactors, Vec<usize>)>,
+    /// The symbolic part of the last full LU factorisation, kept for factorisations of
+    /// matrices with the same pattern.
+    symbolic: Option<SymbolicLU>,
+}
+
+/// How the basis matrix is factorised.
+#[derive(Clone)]
+struct LuSettings {
+    pivoting: LuPivoting,
+    pivot_threshold: AbnormalFraction,
+    /// Whether Markowitz pivoting prefers small pivots in exact arithmetic.
+    small_pivots: bool,
+    /// Basis columns with more nonzeros are left out of the LU factors.
+    dense_col_threshold: Option<usize>,
+    /// If set, the condition number of each factorised basis is estimated. Only in
+    /// approximate arithmetic.
+    condition_limit: Option<f64>,
+    /// Whether the sizes in bits of the coefficients of each factorisation are collected.
+    /// Only in exact arithmetic.
+    coeff_bit_stats: bool,
+    /// The largest fraction of changed basis columns for which a factorisation reuses the
+    /// previous factors. Only with [`LuPivoting::ColumnOrder`].
+    partial_refactor: Option<f64>,
+    /// How the factors are updated for the pivots between two factorisations.
+    basis_update: BasisUpdate,
+    /// Whether factorisations reuse the symbolic part of the last one if the pattern fits.
+    /// Only with [`LuPivoting::ColumnOrder`] and without dense columns.
+    reuse_symbolic: bool,
+}
+
+impl LuSettings {
+    fn factorise(
+        &self,
+        orig_constraints_csc: &CsMat,
+        basic_vars: &[usize],
+        scratch: &mut LuScratch,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<LUFactors, Error> {
+        let get_col = |c: usize| {
+            orig_constraints_csc
+                .outer_view(basic_vars[c])
+                .unwrap()
+                .into_raw_storage()
+        };
+        match self.pivoting {
+            LuPivoting::ColumnOrder => lu_factorise_with_scratch(
+                basic_vars.len(),
+                get_col,
+                self.pivot_threshold.clone(),
+                self.dense_col_threshold,
+                scratch,
+                cancel,
+            ),
+            LuPivoting::Markowitz => lu_factorise_markowitz_with_scratch(
+                basic_vars.len(),
+                get_col,
+                self.pivot_threshold.clone(),
+                self.dense_col_threshold,
+                self.small_pivots,
+                scratch,
+                cancel,
+            ),
+        }
+        .map_err(lu_error)
+    }
+
+    /// Factorise the basis matrix of `basic_vars`, reusing the factors `prev` of the basis
+    /// `prev_basic_vars` if few enough columns changed.
+    fn refactorise(
+        &self,
+        orig_constraints_csc: &CsMat,
+        basic_vars: &[usize],
+        prev: &LUFactors,
+        prev_basic_vars: &[usize],
+        scratch: &mut LuScratch,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<LUFactors, Error> {
+        let changed_cols = if prev_basic_vars.len() == basic_vars.len() {
+            (0..basic_vars.len())
+                .filter(|&c| basic_vars[c] != prev_basic_vars[c])
+                .collect::<Vec<_>>()
+        } else {
+            (0..basic_vars.len()).collect()
+        };
+        let max_changed = self.partial_refactor.unwrap_or(0.0) * basic_vars.len() as f64;
+        if changed_cols.len() as f64 > max_changed {
+            return self.factorise(orig_constraints_csc, basic_vars, scratch, cancel);
+        }
+        let get_col = |c: usize| {
+            orig_constraints_csc
+                .outer_view(basic_vars[c])
+                .unwrap()
+                .into_raw_storage()
+        };
+        lu_refactorise_with_scratch(
+            basic_vars.len(),
+            get_col,
+            self.pivot_threshold.clone(),
+            self.dense_col_threshold,
+            prev,
+            &changed_cols,
+            scratch,
+            cancel,
+        )
+        .map_err(lu_error)
+    }
+
+    /// Factorise the basis matrix of `basic_vars` with `symbolic` if the pattern fits, and
+    /// otherwise analyse it anew into `symbolic`.
+    fn factorise_symbolic(
+        &self,
+        orig_constraints_csc: &CsMat,
+        basic_vars: &[usize],
+        symbolic: &mut Option<SymbolicLU>,
+        scratch: &mut LuScratch,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<LUFactors, Error> {
+        let get_col = |c: usize| {
+            orig_constraints_csc
+                .outer_view(basic_vars[c])
+                .unwrap()
+                .into_raw_storage()
+        };
+        if let Some(prev) = symbolic.as_ref() {
+            match lu_factorise_symbolic(
+                prev,
+                get_col,
+                self.pivot_threshold.clone(),
+                scratch,
+                cancel,
+            ) {
+                Err(SparseError::SymbolicMismatch { step }) => {
+                    trace!("symbolic LU factorisation does not fit in step {}", step);
+                }
+                res => return res.map_err(lu_error),
+            }
+        }
+        let (lu_factors, analysed) = lu_analyse_with_scratch(
+            basic_vars.len(),
+            get_col,
+            self.pivot_threshold.clone(),
+            scratch,
+            cancel,
+        )
+        .map_err(lu_error)?;
+        *symbolic = Some(analysed);
+        Ok(lu_factors)
+    }
+
+    /// Whether factorisations are kept to reuse them in the next one.
+    fn keeps_factors(&self) -> bool {
+        self.partial_refactor.is_some() && self.pivoting == LuPivoting::ColumnOrder
+    }
+
+    /// The counters of the factorisation `lu_factors`, which `scratch` computed.
+    fn factor_stats(&self, lu_factors: &LUFactors, scratch: &LuScratch) -> FactorStats {
+        let coeff_bits = self.coeff_bit_stats.then(|| lu_factors.coeff_bits());
+        FactorStats {
+            lower_nnz: lu_factors.lower_nnz(),
+            upper_nnz: lu_factors.upper_nnz(),
+            rejected_pivots: scratch.rejected_pivots(),
+            dropped_entries: scratch.dropped_entries(),
+            unit_pivots: scratch.unit_pivots(),
+            reused_columns: scratch.reused_cols(),
+            ordering: scratch.ordering().clone(),
+            ordering_cache_hit: scratch.ordering_cache_hit(),
+            max_coeff_bits: coeff_bits.map(|(max, _)| max),
+            total_coeff_bits: coeff_bits.map(|(_, total)| total),
+            ..Default::default()
+        }
+    }
+}
+
+/// Make `lu_factors` updatable with `update`, with the drop tolerance of their factorisation.
+fn updatable_lu(lu_factors: LUFactors, scratch: &LuScratch, update: BasisUpdate) -> UpdatableLU {
+    let mut lu = UpdatableLU::new(lu_factors);
+    lu.set_drop_tolerance(scratch.drop_tolerance().cloned());
+    lu.set_update(update);
+    lu
+}
+
+fn lu_error(err: SparseError) -> Error {
+    match err {
+        SparseError::Interrupted => Error::Interrupted(PartialSolution::default()),
+        SparseError::InvalidOrdering { size, len } => ModelError::DimensionMismatch {
+            part: "column ordering",
+            expected: size,
+            found: len,
+        }
+        .into(),
+        SparseError::SymbolicMismatch { step } => Error::SingularBasis {
+            detail: format!(
+                "the basis matrix does not fit the symbolic LU factorisation in step {}",
+                step
+            ),
+            basis_col: None,
+            row: None,
+        },
+        SparseError::SingularMatrix { step, row, col } => Error::SingularBasis {
+            detail: match col {
+                Some(col) => format!(
+                    "no pivot left for basis column {} in step {} of the LU factorisation",
+                    col, step
+                ),
+                None => format!("no pivot left in step {} of the LU factorisation", step),
+            },
+            basis_col: col,
+            row,
+        },
+    }
 }
 
 impl BasisSolver {
-    fn push_eta_matrix(
+    fn new(
+        lu_factors: LUFactors,
+        basic_vars: &[usize],
+        scratch: LuScratch,
+        lu_settings: LuSettings,
+    ) -> BasisSolver {
+        let lu_nnz = lu_factors.nnz();
+        let size = lu_factors.size();
+        let condition_estimate = (lu_settings.condition_limit).map(|_| lu_factors.condest());
+        let factor_stats = lu_settings.factor_stats(&lu_factors, &scratch);
+        let last_factors =
+            (lu_settings.keeps_factors()).then(|| (lu_factors.clone(), basic_vars.to_vec()));
+        BasisSolver {
+            lu_factors: updatable_lu(lu_factors, &scratch, lu_settings.basis_update),
+            scratch,
+            rhs: ScatteredVec::empty(size),
+            lu_settings,
+            factor_stats,
+            factors_nnz: lu_nnz,
+            num_dense_updates: 0,
+            condition_estimate,
+            last_factors,
+            symbolic: None,
+        }
+    }
+
+    /// Replace the column of the basis matrix in `row` by `col` (a Forrest–Tomlin update of
+    /// the LU factors). On error, the basis has to be factorised anew.
+    fn replace_col<'a>(
         &mut self,
-        col_coeffs: &SparseVec,
-        r_leaving: usize,
-        pivot_coeff: &AbnormalFraction,
-    ) {
-        let coeffs = col_coeffs.iter().map(|(r, coeff)| {
-            let val = if r == r_leaving {
-                f1_ab!() - &f1_ab!() / pivot_coeff
-            } else {
-                coeff / pivot_coeff
-            };
-            (r, val)
-        });
-        self.eta_matrices.push(r_leaving, coeffs);
+        row: usize,
+        col: impl Iterator<Item = (usize, &'a AbnormalFraction)>,
+    ) -> Result<(), SparseError> {
+        self.rhs.set(col);
+        self.lu_factors
+            .replace_col(row, &self.rhs, self.scratch.space())
     }
 
-    fn reset(&mut self, orig_constraints_csc: &CsMat, basic_vars: &[usize]) {
-        self.scratch.clear_sparse(basic_vars.len());
-        self.eta_matrices.clear_and_resize(basic_vars.len());
+    fn reset(
+        &mut self,
+        orig_constraints_csc: &CsMat,
+        basic_vars: &[usize],
+        cancel: Option<&AtomicBool>,
+    ) -> Result<(), Error> {
+        self.scratch.space().clear_sparse(basic_vars.len());
         self.rhs.clear_and_resize(basic_vars.len());
-        self.lu_factors = lu_factorise(
-            basic_vars.len(),
-            |c| {
-                orig_constraints_csc
-                    .outer_view(basic_vars[c])
-                    .unwrap()
-                    .into_raw_storage()
-            },
-            f_ab!(1, 10),
-            &mut self.scratch,
-        )
-        .unwrap(); // TODO: When is singular basis matrix possible? Report as a proper error.
-        self.lu_factors_transp = self.lu_factors.transpose();
+        let lu_factors = match self.last_factors.take() {
+            Some((prev, prev_basic_vars)) => (self.lu_settings).refactorise(
+                orig_constraints_csc,
+                basic_vars,
+                &prev,
+                &prev_basic_vars,
+                &mut self.scratch,
+                cancel,
+            )?,
+            None if self.lu_settings.reuse_symbolic => (self.lu_settings).factorise_symbolic(
+                orig_constraints_csc,
+                basic_vars,
+                &mut self.symbolic,
+                &mut self.scratch,
+                cancel,
+            )?,
+            None => (self.lu_settings).factorise(
+                orig_constraints_csc,
+                basic_vars,
+                &mut self.scratch,
+                cancel,
+            )?,
+        };
+        self.set_factors(lu_factors, basic_vars);
+        self.restore_dense_cols(orig_constraints_csc, basic_vars, cancel)
+    }
+
+    fn set_factors(&mut self, lu_factors: LUFactors, basic_vars: &[usize]) {
+        if self.lu_settings.keeps_factors() {
+            self.last_factors = Some((lu_factors.clone(), basic_vars.to_vec()));
+        }
+        self.factor_stats = (self.lu_settings).factor_stats(&lu_factors, &self.scratch);
+        self.condition_estimate = (self.lu_settings.condition_limit).map(|_| lu_factors.condest());
+        self.lu_factors = updatable_lu(lu_factors, &self.scratch, self.lu_settings.basis_update);
+    }
+
+    /// The LU factors have unit columns in place of the dense basis columns. Put the dense
+    /// columns back with one update each, as if they had entered the basis by pivots.
+    fn restore_dense_cols(
+        &mut self,
+        orig_constraints_csc: &CsMat,
+        basic_vars: &[usize],
+        cancel: Option<&AtomicBool>,
+    ) -> Result<(), Error> {
+        let dense_cols = self.lu_factors.dense_cols().to_vec();
+        let restored = dense_cols.iter().all(|&c| {
+            let col = orig_constraints_csc.outer_view(basic_vars[c]).unwrap();
+            self.replace_col(c, col.iter()).is_ok()
+        });
+        if !restored {
+            // Putting the dense columns back one by one can pass through a singular matrix
+            // even if the basis is not; factorise them with the other columns instead.
+            let lu_settings = LuSettings {
+                dense_col_threshold: None,
+                ..self.lu_settings.clone()
+            };
+            let lu_factors =
+                lu_settings.factorise(orig_constraints_csc, basic_vars, &mut self.scratch, cancel)?;
+            self.set_factors(lu_factors, basic_vars);
+        }
+        self.num_dense_updates = self.lu_factors.num_updates();
+        self.factors_nnz = self.lu_factors.nnz();
+        Ok(())
+    }
+
+    /// Keep all entries in the factors from now on; takes effect at the next factorisation.
+    fn disable_dropping(&mut self) {
+        self.scratch.set_drop_tolerance(None);
+        self.lu_factors.set_drop_tolerance(None);
+        self.last_factors = None;
+    }
+
+    /// Number of pivots since the last factorisation.
+    fn num_updates(&self) -> usize {
+        self.lu_factors.num_updates() - self.num_dense_updates
+    }
+
+    /// Nonzeros that the updates of the pivots since the last factorisation added.
+    fn updates_nnz(&self) -> usize {
+        self.lu_factors.nnz().saturating_sub(self.factors_nnz)
+    }
+
+    /// Nonzeros of the factorisation, including the updates of the dense columns.
+    fn factors_nnz(&self) -> usize {
+        self.factors_nnz
+    }
+
+    fn solve_dense(&mut self, rhs: &mut [AbnormalFraction]) {
+        self.lu_factors.solve_dense(rhs, self.scratch.space());
     }
 
     fn solve<'a>(
@@ -1322,17 +2902,7 @@ impl BasisSolver {
         rhs: impl Iterator<Item = (usize, &'a AbnormalFraction)>,
     ) -> &ScatteredVec {
         self.rhs.set(rhs);
-        self.lu_factors.solve(&mut self.rhs, &mut self.scratch);
-
-        // apply eta matrices (Vanderbei p.139)
-        for idx in 0..self.eta_matrices.len() {
-            let r_leaving = self.eta_matrices.leaving_rows[idx];
-            let coeff = self.rhs.get(r_leaving).clone();
-            for (r, val) in self.eta_matrices.coeff_cols.col_iter(idx) {
-                *self.rhs.get_mut(r) -= &coeff * val;
-            }
-        }
-
+        self.lu_factors.solve(&mut self.rhs, self.scratch.space());
         &mut self.rhs
     }
 
@@ -1342,54 +2912,90 @@ impl BasisSolver {
         rhs: impl Iterator<Item = (usize, &'a AbnormalFraction)>,
     ) -> &ScatteredVec {
         self.rhs.set(rhs);
-        // apply eta matrices in reverse (Vanderbei p.139)
-        for idx in (0..self.eta_matrices.len()).rev() {
-            let mut coeff = f0_ab!();
-            // eta col `dot` rhs_transp
-            for (i, val) in self.eta_matrices.coeff_cols.col_iter(idx) {
-                coeff += val * self.rhs.get(i);
-            }
-            let r_leaving = self.eta_matrices.leaving_rows[idx];
-            *self.rhs.get_mut(r_leaving) -= coeff;
-        }
-
-        self.lu_factors_transp
-            .solve(&mut self.rhs, &mut self.scratch);
+        self.lu_factors
+            .solve_transp(&mut self.rhs, self.scratch.space());
         &mut self.rhs
     }
 }
 
-#[derive(Clone, Debug)]
-struct EtaMatrices {
-    leaving_rows: Vec<usize>,
-    coeff_cols: SparseMat,
+/// For each constraint, the var that is basic in it from the start instead of a slack var, if
+/// the constraint is an equality. A var qualifies if it is not fixed and has no coefficient in
+/// the rows chosen before, so that the initial basis is triangular; of those, the one with the
+/// largest coefficient is chosen. An equality row for which no var qualifies gets a slack var
+/// fixed at zero, which acts as the artificial var of phase 1: once it has left the basis, it
+/// never enters again.
+fn crash_equality_rows(
+    var_mins: &[AbnormalFraction],
+    var_maxs: &[AbnormalFraction],
+    constraints: &[Constraint],
+) -> Vec<Option<usize>> {
+    let mut is_blocked = vec![false; var_mins.len()];
+    (constraints.iter())
+        .map(|constraint| {
+            if !constraint.is_equality() {
+                return None;
+            }
+            let mut best: Option<(usize, AbnormalFraction)> = None;
+            for (var, coeff) in constraint.coeffs.iter() {
+                if is_blocked[var] || coeff.is_zero() || var_mins[var] == var_maxs[var] {
+                    continue;
+                }
+                let abs = coeff.clone().abs();
+                if best.as_ref().is_none_or(|(_, best_abs)| &abs > best_abs) {
+                    best = Some((var, abs));
+                }
+            }
+            let (var, _) = best?;
+            for &other_var in constraint.coeffs.indices() {
+                is_blocked[other_var] = true;
+            }
+            Some(var)
+        })
+        .collect()
 }
 
-impl EtaMatrices {
-    fn new(n_rows: usize) -> EtaMatrices {
-        EtaMatrices {
-            leaving_rows: vec![],
-            coeff_cols: SparseMat::new(n_rows),
+/// The obj. coeffs of the non-slack vars relative to the initial basis, in which the crash vars
+/// of [`crash_equality_rows`] are basic. The duals of the rows of slack vars are zero, so only
+/// the equality rows with crash vars are eliminated, last row first: the crash var of a row has
+/// no coeff. in the rows before it, so its obj. coeff is final once the rows after it are done.
+fn crash_obj_coeffs(
+    obj_coeffs: &[AbnormalFraction],
+    constraints: &[Constraint],
+    crash_vars: &[Option<usize>],
+) -> Vec<AbnormalFraction> {
+    let mut res = obj_coeffs.to_vec();
+    for (constraint, crash_var) in constraints.iter().zip(crash_vars).rev() {
+        let Some(var) = *crash_var else {
+            continue;
+        };
+        let dual = &res[var] / constraint.coeffs.get(var).unwrap();
+        for (other_var, coeff) in constraint.coeffs.iter() {
+            res[other_var] -= &dual * coeff;
         }
+        res[var] = f0_ab!();
     }
+    res
+}
 
-    fn len(&self) -> usize {
-        self.leaving_rows.len()
-    }
-
-    fn clear_and_resize(&mut self, n_rows: usize) {
-        self.leaving_rows.clear();
-        self.coeff_cols.clear_and_resize(n_rows);
-    }
-
-    fn push(
-        &mut self,
-        leaving_row: usize,
-        coeffs: impl Iterator<Item = (usize, AbnormalFraction)>,
-    ) {
-        self.leaving_rows.push(leaving_row);
-        self.coeff_cols.append_col(coeffs);
-    }
+/// The largest absolute entry of `residual`, if it exceeds the tolerance of
+/// [`SolveOptions::check_residuals`] for a right-hand side with the entries `rhs`.
+fn residual_too_large(
+    residual: &[AbnormalFraction],
+    rhs: &[AbnormalFraction],
+) -> Option<AbnormalFraction> {
+    let max_abs = |vals: &[AbnormalFraction]| {
+        let mut max = f0_ab!();
+        for val in vals {
+            let abs = val.clone().abs();
+            if abs > max {
+                max = abs;
+            }
+        }
+        max
+    };
+    let norm = max_abs(residual);
+    let tolerance = f_ab!(1, 1_000_000_000) * (f1_ab!() + max_abs(rhs));
+    (norm > tolerance).then_some(norm)
 }
 
 fn into_resized(vec: CsVec, len: usize) -> CsVec {
@@ -1418,6 +3024,125 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn singular_basis() {
+        assert!(matches!(
+            lu_error(SparseError::SingularMatrix {
+                step: 1,
+                row: Some(0),
+                col: Some(2),
+            }),
+            Error::SingularBasis {
+                basis_col: Some(2),
+                row: Some(0),
+                ..
+            }
+        ));
+
+        // The basis consists of the columns of x and y, which are equal.
+        let mut sol = Solver::try_new(
+            &[f0_ab!(), f0_ab!()],
+            &[f0_ab!(), f0_ab!()],
+            &[AbnormalFraction::infinity(), AbnormalFraction::infinity()],
+            &[
+                Constraint::new(to_sparse(&[f1_ab!(), f1_ab!()]), ComparisonOp::Le, f1_ab!()),
+                Constraint::new(to_sparse(&[f1_ab!(), f1_ab!()]), ComparisonOp::Le, f1_ab!()),
+            ],
+            &SolveOptions::default(),
+        )
+        .unwrap();
+        sol.basic_vars = vec![0, 1];
+        assert!(matches!(
+            sol.refactorise("test"),
+            Err(Error::SingularBasis { .. })
+        ));
+    }
+
+    #[test]
+    fn recover_singular_basis() {
+        // x and y have equal columns. Minimise -x - y - 2z subject to x + y + z <= 4 and
+        // x + y + 3z <= 6; the optimum is -5, at x + y = 3 and z = 1.
+        let mut sol = Solver::try_new(
+            &[-f1_ab!(), -f1_ab!(), -f_ab!(2)],
+            &[f0_ab!(), f0_ab!(), f0_ab!()],
+            &[
+                AbnormalFraction::infinity(),
+                AbnormalFraction::infinity(),
+                AbnormalFraction::infinity(),
+            ],
+            &[
+                Constraint::new(
+                    to_sparse(&[f1_ab!(), f1_ab!(), f1_ab!()]),
+                    ComparisonOp::Le,
+                    f_ab!(4),
+                ),
+                Constraint::new(
+                    to_sparse(&[f1_ab!(), f1_ab!(), f_ab!(3)]),
+                    ComparisonOp::Le,
+                    f_ab!(6),
+                ),
+            ],
+            &SolveOptions::default(),
+        )
+        .unwrap();
+
+        // Put x and y into the basis in place of the slack vars 3 and 4.
+        for (row, (var, slack_var)) in [(0, 3), (1, 4)].into_iter().enumerate() {
+            let VarState::NonBasic(col) = sol.var_states[var] else {
+                panic!("var {} is basic", var);
+            };
+            sol.basic_vars[row] = var;
+            sol.var_states[var] = VarState::Basic(row);
+            sol.basic_var_mins[row] = sol.orig_var_mins[var].clone();
+            sol.basic_var_maxs[row] = sol.orig_var_maxs[var].clone();
+            sol.nb_vars[col] = slack_var;
+            sol.var_states[slack_var] = VarState::NonBasic(col);
+        }
+
+        sol.refactorise_or_recover("test").unwrap();
+        assert_eq!(sol.stats.recovery_count, 1);
+        assert!(sol.basic_vars.iter().any(|&var| var >= sol.num_vars));
+
+        sol.initial_solve().unwrap();
+        assert_eq!(sol.cur_obj_val, -f_ab!(5));
+        assert_eq!(sol.stats.recovery_count, 1);
+    }
+
+    #[test]
+    fn residual_check() {
+        // Minimise -x - y subject to x + 2y <= 4 and 3x + y <= 6; the optimum is at x = 8/5,
+        // y = 6/5. With x fixed to 1, it is at y = 3/2.
+        let mut sol = Solver::try_new(
+            &[-f1_ab!(), -f1_ab!()],
+            &[f0_ab!(), f0_ab!()],
+            &[AbnormalFraction::infinity(), AbnormalFraction::infinity()],
+            &[
+                Constraint::new(to_sparse(&[f1_ab!(), f_ab!(2)]), ComparisonOp::Le, f_ab!(4)),
+                Constraint::new(to_sparse(&[f_ab!(3), f1_ab!()]), ComparisonOp::Le, f_ab!(6)),
+            ],
+            &SolveOptions::default().check_residuals(true),
+        )
+        .unwrap();
+        sol.initial_solve().unwrap();
+        if f1_ab!().is_exact() {
+            assert!(!sol.check_residuals);
+            return;
+        }
+        assert_eq!(sol.stats.residual_refactors, 0);
+
+        // The solves with the corrupted factors fail the check, which factorises the basis
+        // anew.
+        assert!(sol.basis_solver.num_updates() > 0);
+        sol.basis_solver.lu_factors.corrupt_last_update();
+        sol.fix_var(0, f1_ab!()).unwrap();
+        assert!(sol.stats.residual_refactors > 0);
+
+        let eps = f_ab!(1, 1_000_000_000);
+        assert!((sol.get_value(0) - &f1_ab!()).abs() <= eps);
+        assert!((sol.get_value(1) - &f_ab!(3, 2)).abs() <= eps);
+        assert!((&sol.cur_obj_val + &f_ab!(5, 2)).abs() <= eps);
+    }
+
     #[test]
     fn initialize() {
         let sol = Solver::try_new(
@@ -1425,11 +3150,12 @@ mod tests {
             &[AbnormalFraction::neg_infinity(), f_ab!(5)],
             &[f0_ab!(), AbnormalFraction::infinity()],
             &[
-                (to_sparse(&[f1_ab!(), f1_ab!()]), ComparisonOp::Le, f_ab!(6)),
-                (to_sparse(&[f1_ab!(), f_ab!(2)]), ComparisonOp::Le, f_ab!(8)),
-                (to_sparse(&[f1_ab!(), f1_ab!()]), ComparisonOp::Ge, f_ab!(2)),
-                (to_sparse(&[f0_ab!(), f1_ab!()]), ComparisonOp::Eq, f_ab!(3)),
+                Constraint::new(to_sparse(&[f1_ab!(), f1_ab!()]), ComparisonOp::Le, f_ab!(6)),
+                Constraint::new(to_sparse(&[f1_ab!(), f_ab!(2)]), ComparisonOp::Le, f_ab!(8)),
+                Constraint::new(to_sparse(&[f1_ab!(), f1_ab!()]), ComparisonOp::Ge, f_ab!(2)),
+                Constraint::new(to_sparse(&[f0_ab!(), f1_ab!()]), ComparisonOp::Eq, f_ab!(3)),
             ],
+            &SolveOptions::default(),
         )
         .unwrap();
 
@@ -1437,9 +3163,10 @@ mod tests {
         assert!(!sol.is_primal_feasible);
         assert!(!sol.is_dual_feasible);
 
+        // The equality row has no slack var: x1 is basic in it from the start.
         assert_eq!(
             &sol.orig_obj_coeffs,
-            &[f_ab!(2), f1_ab!(), f0_ab!(), f0_ab!(), f0_ab!(), f0_ab!()]
+            &[f_ab!(2), f1_ab!(), f0_ab!(), f0_ab!(), f0_ab!()]
         );
 
         assert_eq!(
@@ -1450,7 +3177,6 @@ mod tests {
                 f0_ab!(),
                 f0_ab!(),
                 AbnormalFraction::neg_infinity(),
-                f0_ab!(),
             ]
         );
         assert_eq!(
@@ -1461,34 +3187,34 @@ mod tests {
                 AbnormalFraction::infinity(),
                 AbnormalFraction::infinity(),
                 f0_ab!(),
-                f0_ab!()
             ]
         );
 
         let orig_constraints_ref = vec![
-            vec![f1_ab!(), f1_ab!(), f1_ab!(), f0_ab!(), f0_ab!(), f0_ab!()],
-            vec![f1_ab!(), f_ab!(2), f0_ab!(), f1_ab!(), f0_ab!(), f0_ab!()],
-            vec![f1_ab!(), f1_ab!(), f0_ab!(), f0_ab!(), f1_ab!(), f0_ab!()],
-            vec![f0_ab!(), f1_ab!(), f0_ab!(), f0_ab!(), f0_ab!(), f1_ab!()],
+            vec![f1_ab!(), f1_ab!(), f1_ab!(), f0_ab!(), f0_ab!()],
+            vec![f1_ab!(), f_ab!(2), f0_ab!(), f1_ab!(), f0_ab!()],
+            vec![f1_ab!(), f1_ab!(), f0_ab!(), f0_ab!(), f1_ab!()],
+            vec![f0_ab!(), f1_ab!(), f0_ab!(), f0_ab!(), f0_ab!()],
         ];
         assert_matrix_eq(&sol.orig_constraints, &orig_constraints_ref);
 
         assert_eq!(&sol.orig_rhs, &[f_ab!(6), f_ab!(8), f_ab!(2), f_ab!(3)]);
 
-        assert_eq!(&sol.basic_vars, &[2, 3, 4, 5]);
+        assert_eq!(&sol.slack_vars, &[Some(2), Some(3), Some(4), None]);
+        assert_eq!(&sol.basic_vars, &[2, 3, 4, 1]);
         assert_eq!(
             &sol.basic_var_vals,
-            &[f1_ab!(), -f_ab!(2), -f_ab!(3), -f_ab!(2)]
+            &[f_ab!(3), f_ab!(2), -f1_ab!(), f_ab!(3)]
         );
         assert_eq!(
             &sol.dual_edge_sq_norms,
             &[f1_ab!(), f1_ab!(), f1_ab!(), f1_ab!()]
         );
 
-        assert_eq!(&sol.nb_vars, &[0, 1]);
-        assert_eq!(&sol.nb_var_obj_coeffs, &[-f1_ab!(), f1_ab!()]);
-        assert_eq!(&sol.nb_var_vals, &[f0_ab!(), f_ab!(5)]);
-        assert_eq!(&sol.primal_edge_sq_norms, &[f_ab!(4), f_ab!(8)]);
+        assert_eq!(&sol.nb_vars, &[0]);
+        assert_eq!(&sol.nb_var_obj_coeffs, &[-f1_ab!()]);
+        assert_eq!(&sol.nb_var_vals, &[f0_ab!()]);
+        assert_eq!(&sol.primal_edge_sq_norms, &[f_ab!(4)]);
 
         assert_eq!(sol.cur_obj_val, f0_ab!());
     }
@@ -1500,17 +3226,18 @@ mod tests {
             &[AbnormalFraction::neg_infinity(), f_ab!(5)],
             &[f_ab!(20), AbnormalFraction::infinity()],
             &[
-                (
+                Constraint::new(
                     to_sparse(&[f1_ab!(), f1_ab!()]),
                     ComparisonOp::Le,
                     f_ab!(20),
                 ),
-                (
+                Constraint::new(
                     to_sparse(&[-f1_ab!(), f_ab!(4)]),
                     ComparisonOp::Le,
                     f_ab!(20),
                 ),
             ],
+            &SolveOptions::default(),
         )
         .unwrap();
         sol.initial_solve().unwrap();
@@ -1533,16 +3260,17 @@ mod tests {
                 AbnormalFraction::neg_infinity(),
             ],
             &[
-                (
+                Constraint::new(
                     to_sparse(&[f1_ab!(), f1_ab!()]),
                     ComparisonOp::Ge,
                     f_ab!(10),
                 ),
-                (to_sparse(&[f1_ab!(), f1_ab!()]), ComparisonOp::Le, f_ab!(5)),
+                Constraint::new(to_sparse(&[f1_ab!(), f1_ab!()]), ComparisonOp::Le, f_ab!(5)),
             ],
+            &SolveOptions::default(),
         );
         // .unwrap()
         // .initial_solve();
-        assert_eq!(infeasible.unwrap_err(), Error::Infeasible);
+        assert!(matches!(infeasible, Err(Error::Infeasible(_))));
     }
 }
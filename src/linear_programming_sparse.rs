@@ -60,6 +60,10 @@ impl ScatteredVec {
         self.values.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (usize, &AbnormalFraction)> {
         self.nonzero.iter().map(move |&i| (i, &self.values[i]))
     }
@@ -122,7 +126,6 @@ impl ScatteredVec {
         }
     }
 
-    #[cfg(test)]
     pub(crate) fn to_csvec(&self) -> CsVec<AbnormalFraction> {
         let mut indices = vec![];
         let mut data = vec![];
@@ -138,7 +141,7 @@ impl ScatteredVec {
 
 /// Unordered sparse matrix with elements stored by columns
 #[derive(Clone, Debug)]
-pub(crate) struct SparseMat {
+pub struct SparseMat {
     n_rows: usize,
     indptr: Vec<usize>,
     indices: Vec<usize>,
@@ -155,15 +158,15 @@ impl SparseMat {
         }
     }
 
-    pub(crate) fn rows(&self) -> usize {
+    pub fn rows(&self) -> usize {
         self.n_rows
     }
 
-    pub(crate) fn cols(&self) -> usize {
+    pub fn cols(&self) -> usize {
         self.indptr.len() - 1
     }
 
-    pub(crate) fn nnz(&self) -> usize {
+    pub fn nnz(&self) -> usize {
         self.data.len()
     }
 
@@ -184,7 +187,8 @@ impl SparseMat {
         self.indptr.push(self.indices.len())
     }
 
-    pub(crate) fn col_rows(&self, i_col: usize) -> &[usize] {
+    /// The rows of the nonzeros of a column, in the order in which they were stored.
+    pub fn col_rows(&self, i_col: usize) -> &[usize] {
         &self.indices[self.indptr[i_col]..self.indptr[i_col + 1]]
     }
 
@@ -192,14 +196,13 @@ impl SparseMat {
         &mut self.indices[self.indptr[i_col]..self.indptr[i_col + 1]]
     }
 
-    pub(crate) fn col_data(&self, i_col: usize) -> &[AbnormalFraction] {
+    /// The values of the nonzeros of a column, in the order of [`SparseMat::col_rows`].
+    pub fn col_data(&self, i_col: usize) -> &[AbnormalFraction] {
         &self.data[self.indptr[i_col]..self.indptr[i_col + 1]]
     }
 
-    pub(crate) fn col_iter(
-        &self,
-        i_col: usize,
-    ) -> impl Iterator<Item = (usize, &AbnormalFraction)> {
+    /// The rows and values of the nonzeros of a column.
+    pub fn col_iter(&self, i_col: usize) -> impl Iterator<Item = (usize, &AbnormalFraction)> {
         self.col_rows(i_col)
             .iter()
             .copied()
@@ -218,17 +221,15 @@ impl SparseMat {
         self.seal_column();
     }
 
-    pub(crate) fn into_csmat(self) -> CsMat<AbnormalFraction> {
-        CsMat::new_csc(
-            (self.cols(), self.n_rows),
-            self.indptr,
-            self.indices,
-            self.data,
+    /// A copy in the compressed sparse column format of sprs, with the rows of each column sorted.
+    pub fn to_csmat(&self) -> CsMat<AbnormalFraction> {
+        CsMat::new_from_unsorted_csc(
+            (self.n_rows, self.cols()),
+            self.indptr.clone(),
+            self.indices.clone(),
+            self.data.clone(),
         )
-    }
-
-    pub(crate) fn to_csmat(&self) -> CsMat<AbnormalFraction> {
-        self.clone().into_csmat()
+        .unwrap() // Cannot fail
     }
 
     pub(crate) fn transpose(&self) -> SparseMat {
@@ -271,24 +272,100 @@ impl SparseMat {
 
         out
     }
+
+    /// The fraction of the nonzeros off the diagonal whose mirror entry across the diagonal is
+    /// a nonzero too, from 0 for a triangular to 1 for a symmetric pattern; 1 if there are no
+    /// nonzeros off the diagonal. Only the pattern counts, not the values.
+    pub fn symmetry_ratio(&self) -> f64 {
+        symmetry_ratio(self.rows(), self.cols(), |c| self.col_rows(c))
+    }
+
+    /// The largest distance of a nonzero from the diagonal, that is the largest |r − c| over
+    /// the nonzeros (r, c); 0 for a diagonal matrix. With `perm`, the distance in the matrix
+    /// whose rows and columns are both permuted by `perm`, which must have an entry for every
+    /// row and column.
+    pub fn bandwidth(&self, perm: Option<&Perm>) -> usize {
+        let new = |i: usize| perm.map_or(i, |perm| perm.orig2new[i]);
+        (0..self.cols())
+            .flat_map(|c| {
+                self.col_rows(c)
+                    .iter()
+                    .map(move |&r| new(r).abs_diff(new(c)))
+            })
+            .max()
+            .unwrap_or(0)
+    }
 }
 
+/// [`SparseMat::symmetry_ratio`] of the pattern with `n_rows` rows whose columns `get_col`
+/// returns.
+pub(crate) fn symmetry_ratio<'a>(
+    n_rows: usize,
+    n_cols: usize,
+    get_col: impl Fn(usize) -> &'a [usize],
+) -> f64 {
+    // For each row the columns of its nonzeros.
+    let mut row_cols = vec![vec![]; n_rows];
+    for c in 0..n_cols {
+        for &r in get_col(c) {
+            row_cols[r].push(c);
+        }
+    }
+
+    let mut mark = vec![usize::MAX; n_rows.max(n_cols)];
+    let mut num_offdiag = 0;
+    let mut num_mirrored = 0;
+    for c in 0..n_cols {
+        // The mirror of (r, c) is in row c.
+        if let Some(cols) = row_cols.get(c) {
+            for &mirror_r in cols {
+                mark[mirror_r] = c;
+            }
+        }
+        for &r in get_col(c) {
+            if r != c {
+                num_offdiag += 1;
+                if mark[r] == c {
+                    num_mirrored += 1;
+                }
+            }
+        }
+    }
+
+    if num_offdiag == 0 {
+        1.0
+    } else {
+        num_mirrored as f64 / num_offdiag as f64
+    }
+}
+
+/// Triangular matrix with the diagonal stored separately.
 #[derive(Clone)]
-pub(crate) struct TriangleMat {
+pub struct TriangleMat {
     pub(crate) nondiag: SparseMat,
     /// Diag elements, None means all 1's
     pub(crate) diag: Option<Vec<AbnormalFraction>>,
 }
 
 impl TriangleMat {
-    pub(crate) fn rows(&self) -> usize {
+    pub fn rows(&self) -> usize {
         self.nondiag.rows()
     }
 
-    pub(crate) fn cols(&self) -> usize {
+    pub fn cols(&self) -> usize {
         self.nondiag.cols()
     }
 
+    /// The elements off the diagonal.
+    pub fn nondiag(&self) -> &SparseMat {
+        &self.nondiag
+    }
+
+    /// The diagonal elements, or `None` if they are all 1.
+    pub fn diag(&self) -> Option<&[AbnormalFraction]> {
+        self.diag.as_deref()
+    }
+
     pub(crate) fn transpose(&self) -> TriangleMat {
         TriangleMat {
             nondiag: self.nondiag.transpose(),
@@ -326,26 +403,96 @@ impl TriangleMat {
 
 impl std::fmt::Debug for TriangleMat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "nondiag:\n")?;
+        writeln!(f, "nondiag:")?;
         for row in self.nondiag.to_csmat().to_csr().outer_iterator() {
-            write!(f, "{:?}\n", to_dense(&row))?
+            writeln!(f, "{:?}", to_dense(&row))?
         }
-        write!(f, "diag: {:?}\n", self.diag)?;
+        writeln!(f, "diag: {:?}", self.diag)?;
         Ok(())
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Perm {
     pub(crate) orig2new: Vec<usize>,
     pub(crate) new2orig: Vec<usize>,
 }
 
+impl Perm {
+    /// The permutation that moves the original index `new2orig[i]` to `i`; `None` if
+    /// `new2orig` does not contain every index below its length exactly once.
+    pub fn from_new2orig(new2orig: Vec<usize>) -> Option<Perm> {
+        let mut orig2new = vec![usize::MAX; new2orig.len()];
+        for (new, &orig) in new2orig.iter().enumerate() {
+            if *orig2new.get(orig)? != usize::MAX {
+                return None;
+            }
+            orig2new[orig] = new;
+        }
+        Some(Perm { orig2new, new2orig })
+    }
+
+    /// The new index of each original index.
+    pub fn orig2new(&self) -> &[usize] {
+        &self.orig2new
+    }
+
+    /// The original index of each new index.
+    pub fn new2orig(&self) -> &[usize] {
+        &self.new2orig
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Error {
-    SingularMatrix,
+    /// The matrix is singular: no acceptable pivot was left in elimination step `step`,
+    /// counting from 0. `col` is the column of the matrix and `row` the row of the matrix
+    /// without a pivot, where the step determines them.
+    SingularMatrix {
+        step: usize,
+        row: Option<usize>,
+        col: Option<usize>,
+    },
+    Interrupted,
+    /// The matrix does not fit a symbolic factorisation: the pattern of the column of step
+    /// `step` differs, its pivot fails the threshold test, or it has fill that the symbolic
+    /// factorisation does not have.
+    SymbolicMismatch {
+        step: usize,
+    },
+    /// The column ordering has `len` entries instead of one for each of the `size` columns.
+    InvalidOrdering {
+        size: usize,
+        len: usize,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::SingularMatrix { step, .. } => {
+                write!(f, "matrix is singular at elimination step {}", step)
+            }
+            Error::Interrupted => "factorisation was interrupted".fmt(f),
+            Error::SymbolicMismatch { step } => write!(
+                f,
+                "matrix does not fit the symbolic factorisation at step {}",
+                step
+            ),
+            Error::InvalidOrdering { size, len } => write!(
+                f,
+                "column ordering has {} entries for {} columns",
+                len, size
+            ),
+        }
+    }
 }
 
+impl std::error::Error for Error {}
+
+/// The error of the sparse factorisations; the same type as [`Error`].
+pub type SparseError = Error;
+
 #[cfg(test)]
 mod tests {
 
@@ -372,4 +519,38 @@ mod tests {
             &[f_ab!(44, 10), f_ab!(11, 10), f_ab!(33, 10), f_ab!(22, 10)]
         );
     }
+
+    #[test]
+    fn symmetry_ratio() {
+        // Entries (1, 0) and (0, 1) mirror each other, (2, 1) does not.
+        let mut mat = SparseMat::new(3);
+        mat.append_col([(0, f_ab!(1)), (1, f_ab!(2))]);
+        mat.append_col([(0, f_ab!(3)), (2, f_ab!(4))]);
+        mat.append_col([(2, f_ab!(5))]);
+        assert_eq!(mat.symmetry_ratio(), 2.0 / 3.0);
+        assert_eq!(mat.transpose().symmetry_ratio(), 2.0 / 3.0);
+
+        let mut diag = SparseMat::new(2);
+        diag.append_col([(0, f_ab!(1))]);
+        diag.append_col([(1, f_ab!(1))]);
+        assert_eq!(diag.symmetry_ratio(), 1.0);
+        assert_eq!(diag.bandwidth(None), 0);
+    }
+
+    #[test]
+    fn bandwidth() {
+        // Nonzeros on the diagonal and in (2, 0).
+        let mut mat = SparseMat::new(3);
+        mat.append_col([(0, f_ab!(1)), (2, f_ab!(1))]);
+        mat.append_col([(1, f_ab!(1))]);
+        mat.append_col([(2, f_ab!(1))]);
+        assert_eq!(mat.bandwidth(None), 2);
+
+        // Swapping rows and columns 1 and 2 moves the nonzero next to the diagonal.
+        let perm = Perm {
+            orig2new: vec![0, 2, 1],
+            new2orig: vec![0, 2, 1],
+        };
+        assert_eq!(mat.bandwidth(Some(&perm)), 1);
+    }
 }
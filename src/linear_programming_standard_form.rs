@@ -0,0 +1,272 @@
+//! Converting a problem to the standard form min cᵀx subject to Ax = b, x ≥ 0.
+
+use ebi_arithmetic::{One, Zero};
+
+use crate::{abnormal_fraction::AbnormalFraction, f0_ab, f1_ab, linear_programming::Constraint};
+
+/// How a variable of the problem is expressed in the variables of a [`StandardForm`], which
+/// are all non-negative.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StandardVar {
+    /// The variable has a finite lower bound `offset`: it equals `offset + x[col]`. A finite
+    /// upper bound becomes a row of its own.
+    Shifted {
+        /// The column of the standard form.
+        col: usize,
+        /// The lower bound of the variable.
+        offset: AbnormalFraction,
+    },
+    /// The variable only has a finite upper bound `offset`: it equals `offset - x[col]`.
+    Negated {
+        /// The column of the standard form.
+        col: usize,
+        /// The upper bound of the variable.
+        offset: AbnormalFraction,
+    },
+    /// The variable is free: it equals `x[pos] - x[neg]`.
+    Split {
+        /// The column of the positive part.
+        pos: usize,
+        /// The column of the negative part.
+        neg: usize,
+    },
+}
+
+/// A problem in the standard form min cᵀx + `obj_offset` subject to Ax = b, x ≥ 0, returned by
+/// [`Problem::to_standard_form`](crate::linear_programming::Problem::to_standard_form).
+///
+/// The columns of `a` are, in this order: one per variable of the problem, one for the
+/// negative part of every free variable, and the slack columns. Each constraint gives one row,
+/// except that a range constraint gives two and a constraint without finite bounds none;
+/// after the constraints follows one row per finite upper bound of a [`StandardVar::Shifted`]
+/// variable.
+///
+/// The objective is always minimised: for a maximisation problem, `c` and `obj_offset` are
+/// those of the negated objective.
+#[derive(Clone, Debug)]
+pub struct StandardForm {
+    /// The constraint matrix, including the slack columns, in CSR storage.
+    pub a: sprs::CsMatI<AbnormalFraction, usize>,
+    /// The right-hand sides, one per row of `a`.
+    pub b: Vec<AbnormalFraction>,
+    /// The objective coefficients, one per column of `a`.
+    pub c: Vec<AbnormalFraction>,
+    /// The constant term of the objective that results from shifting variables.
+    pub obj_offset: AbnormalFraction,
+    /// For each variable of the problem: how it is expressed in the columns.
+    pub vars: Vec<StandardVar>,
+}
+
+impl StandardForm {
+    /// Number of variables of the standard form.
+    pub fn num_cols(&self) -> usize {
+        self.a.cols()
+    }
+
+    /// Number of equality constraints of the standard form.
+    pub fn num_rows(&self) -> usize {
+        self.a.rows()
+    }
+
+    /// The values of the variables of the problem, given the values of the variables of the
+    /// standard form.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `x_std` does not have one value per column.
+    pub fn recover_solution(&self, x_std: &[AbnormalFraction]) -> Vec<AbnormalFraction> {
+        assert_eq!(x_std.len(), self.num_cols());
+        self.vars
+            .iter()
+            .map(|var| match var {
+                StandardVar::Shifted { col, offset } => offset + &x_std[*col],
+                StandardVar::Negated { col, offset } => offset - &x_std[*col],
+                StandardVar::Split { pos, neg } => &x_std[*pos] - &x_std[*neg],
+            })
+            .collect()
+    }
+}
+
+/// The rows of the standard form under construction, in CSR storage.
+struct RowBuilder {
+    indptr: Vec<usize>,
+    indices: Vec<usize>,
+    data: Vec<AbnormalFraction>,
+    b: Vec<AbnormalFraction>,
+    num_slacks: usize,
+}
+
+impl RowBuilder {
+    /// Finish a row whose structural entries were pushed already, with a slack column of
+    /// coefficient `slack` if given.
+    fn finish_row(
+        &mut self,
+        num_structural: usize,
+        slack: Option<AbnormalFraction>,
+        rhs: AbnormalFraction,
+    ) {
+        if let Some(slack) = slack {
+            self.indices.push(num_structural + self.num_slacks);
+            self.data.push(slack);
+            self.num_slacks += 1;
+        }
+        self.indptr.push(self.indices.len());
+        self.b.push(rhs);
+    }
+}
+
+pub(crate) fn standard_form(
+    obj_coeffs: &[AbnormalFraction],
+    var_mins: &[AbnormalFraction],
+    var_maxs: &[AbnormalFraction],
+    constraints: &[Constraint],
+) -> StandardForm {
+    let num_vars = obj_coeffs.len();
+
+    let mut vars = Vec::with_capacity(num_vars);
+    let mut num_structural = num_vars;
+    for (col, (min, max)) in var_mins.iter().zip(var_maxs).enumerate() {
+        vars.push(if min.is_finite() {
+            StandardVar::Shifted {
+                col,
+                offset: min.clone(),
+            }
+        } else if max.is_finite() {
+            StandardVar::Negated {
+                col,
+                offset: max.clone(),
+            }
+        } else {
+            num_structural += 1;
+            StandardVar::Split {
+                pos: col,
+                neg: num_structural - 1,
+            }
+        });
+    }
+
+    let mut c = vec![f0_ab!(); num_structural];
+    let mut obj_offset = f0_ab!();
+    for (var, obj_coeff) in vars.iter().zip(obj_coeffs) {
+        match var {
+            StandardVar::Shifted { col, offset } => {
+                c[*col] = obj_coeff.clone();
+                obj_offset += obj_coeff * offset;
+            }
+            StandardVar::Negated { col, offset } => {
+                c[*col] = -obj_coeff.clone();
+                obj_offset += obj_coeff * offset;
+            }
+            StandardVar::Split { pos, neg } => {
+                c[*pos] = obj_coeff.clone();
+                c[*neg] = -obj_coeff.clone();
+            }
+        }
+    }
+
+    let mut rows = RowBuilder {
+        indptr: vec![0],
+        indices: vec![],
+        data: vec![],
+        b: vec![],
+        num_slacks: 0,
+    };
+    for constraint in constraints {
+        // The entries of the columns of the vars, and after them those of the negative parts,
+        // keep the indices of each row sorted.
+        let mut entries = Vec::with_capacity(constraint.coeffs.nnz());
+        let mut neg_entries = vec![];
+        let mut shift = f0_ab!();
+        for (var, coeff) in constraint.coeffs.iter() {
+            match &vars[var] {
+                StandardVar::Shifted { col, offset } => {
+                    entries.push((*col, coeff.clone()));
+                    shift += coeff * offset;
+                }
+                StandardVar::Negated { col, offset } => {
+                    entries.push((*col, -coeff.clone()));
+                    shift += coeff * offset;
+                }
+                StandardVar::Split { pos, neg } => {
+                    entries.push((*pos, coeff.clone()));
+                    neg_entries.push((*neg, -coeff.clone()));
+                }
+            }
+        }
+        entries.extend(neg_entries);
+
+        let mut bounded_rows = vec![];
+        if constraint.min == constraint.max {
+            bounded_rows.push((None, &constraint.min - &shift));
+        } else {
+            if constraint.min.is_finite() {
+                bounded_rows.push((Some(-f1_ab!()), &constraint.min - &shift));
+            }
+            if constraint.max.is_finite() {
+                bounded_rows.push((Some(f1_ab!()), &constraint.max - &shift));
+            }
+        }
+        for (slack, rhs) in bounded_rows {
+            for (col, coeff) in &entries {
+                rows.indices.push(*col);
+                rows.data.push(coeff.clone());
+            }
+            rows.finish_row(num_structural, slack, rhs);
+        }
+    }
+
+    // The upper bounds of shifted vars: x[col] + slack = max - min.
+    for (var, max) in vars.iter().zip(var_maxs).filter(|(_, max)| max.is_finite()) {
+        if let StandardVar::Shifted { col, offset } = var {
+            rows.indices.push(*col);
+            rows.data.push(f1_ab!());
+            rows.finish_row(num_structural, Some(f1_ab!()), max - offset);
+        }
+    }
+
+    let num_cols = num_structural + rows.num_slacks;
+    c.resize(num_cols, f0_ab!());
+    let a = sprs::CsMatI::new(
+        (rows.b.len(), num_cols),
+        rows.indptr,
+        rows.indices,
+        rows.data,
+    );
+
+    StandardForm {
+        a,
+        b: rows.b,
+        c,
+        obj_offset,
+        vars,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::f_ab;
+
+    #[test]
+    fn recover() {
+        let form = StandardForm {
+            a: sprs::CsMatI::zero((0, 4)),
+            b: vec![],
+            c: vec![f0_ab!(); 4],
+            obj_offset: f0_ab!(),
+            vars: vec![
+                StandardVar::Shifted {
+                    col: 0,
+                    offset: f_ab!(2),
+                },
+                StandardVar::Negated {
+                    col: 1,
+                    offset: f_ab!(5),
+                },
+                StandardVar::Split { pos: 2, neg: 3 },
+            ],
+        };
+        let values = form.recover_solution(&[f1_ab!(), f_ab!(3), f1_ab!(), f_ab!(4)]);
+        assert_eq!(values, vec![f_ab!(3), f_ab!(2), -f_ab!(3)]);
+    }
+}
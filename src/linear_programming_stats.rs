@@ -0,0 +1,262 @@
+use std::{fmt::Display, time::Duration};
+
+use crate::linear_programming_options::Ordering;
+
+/// Counters collected while solving a linear programming problem.
+///
+/// Phase 1 denotes the iterations that restore primal feasibility (dual simplex), phase 2 the
+/// iterations that restore optimality (primal simplex).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SolveStats {
+    /// Number of iterations spent restoring primal feasibility.
+    pub phase1_iterations: usize,
+    /// Number of iterations spent optimising the objective.
+    pub phase2_iterations: usize,
+    /// Number of times the Devex reference framework was reset.
+    pub devex_resets: usize,
+    /// Number of times the primal simplex examined all reduced costs to choose the entering
+    /// variable.
+    pub full_scans: usize,
+    /// Number of reduced costs examined by the primal simplex to choose entering variables.
+    pub reduced_cost_evaluations: usize,
+    /// Number of constraints removed by presolve.
+    pub presolve_removed_rows: usize,
+    /// Number of variables removed by presolve.
+    pub presolve_removed_vars: usize,
+    /// Number of iterations of the approximate solve of
+    /// [`Method::HybridExact`](crate::linear_programming::Method::HybridExact). These are not
+    /// included in the phase 1 and phase 2 counts.
+    pub approximate_iterations: usize,
+    /// Number of LU factorisations of the basis matrix, including the initial one.
+    pub lu_factorisations: usize,
+    /// Number of LU factorisations because the number of updates reached
+    /// [`RefactorPolicy::max_updates`](crate::linear_programming::RefactorPolicy::max_updates).
+    pub refactors_max_updates: usize,
+    /// Number of LU factorisations because the fill of the updates reached
+    /// [`RefactorPolicy::max_fill_ratio`](crate::linear_programming::RefactorPolicy::max_fill_ratio).
+    pub refactors_max_fill: usize,
+    /// Number of LU factorisations because an update had a coefficient larger than
+    /// [`RefactorPolicy::max_coeff_bits`](crate::linear_programming::RefactorPolicy::max_coeff_bits).
+    pub refactors_max_coeff_bits: usize,
+    /// Number of LU factorisations because the residual of a solve with the factors was too
+    /// large (see
+    /// [`SolveOptions::check_residuals`](crate::linear_programming::SolveOptions::check_residuals)).
+    pub residual_refactors: usize,
+    /// Number of LU factorisations that reused the column ordering of an earlier one (see
+    /// [`SolveOptions::ordering_cache`](crate::linear_programming::SolveOptions::ordering_cache)).
+    pub ordering_cache_hits: usize,
+    /// Number of LU factorisations that computed their column ordering although the ordering
+    /// cache was enabled.
+    pub ordering_cache_misses: usize,
+    /// Sum over all LU factorisations of the number of nonzeros in the basis matrix.
+    pub basis_nnz: usize,
+    /// Sum over all LU factorisations of the number of nonzeros in the L and U factors. Dense
+    /// columns that were left out of the factors (see
+    /// [`SolveOptions::dense_column_fraction`](crate::linear_programming::SolveOptions::dense_column_fraction))
+    /// do not count.
+    pub lu_nnz: usize,
+    /// Sum over all LU factorisations of the number of dense basis columns that were left out
+    /// of the factors and put back by updates of the factors.
+    pub dense_columns: usize,
+    /// The largest ratio of the nonzeros in the L and U factors to the nonzeros in the basis
+    /// matrix over all LU factorisations.
+    pub peak_fill_in: f64,
+    /// The largest estimate of the condition number of the basis matrix over all LU
+    /// factorisations, if enabled with
+    /// [`SolveOptions::condition_limit`](crate::linear_programming::SolveOptions::condition_limit).
+    pub max_condition_estimate: Option<f64>,
+    /// Number of basic variables that were replaced by slack variables because their columns
+    /// made the basis matrix singular.
+    pub recovery_count: usize,
+    /// The LU factorisations of the basis matrix in the order of the solve, including the
+    /// initial one.
+    pub factorisations: Vec<FactorStats>,
+    /// Number of pivots that did not change the value of the entering variable.
+    pub degenerate_pivots: usize,
+    /// Number of iterations of the primal simplex in which the entering variable moved from
+    /// one bound to the other without entering the basis.
+    pub bound_flips: usize,
+    /// Number of non-basic variables that the ratio test of the dual simplex moved from one
+    /// bound to the other (see
+    /// [`SolveOptions::bound_flipping`](crate::linear_programming::SolveOptions::bound_flipping)).
+    /// These flips are part of an iteration with a pivot.
+    pub dual_bound_flips: usize,
+    /// A hash of the sequence of entering and leaving variables of all iterations. Two solves
+    /// with equal hashes made the same pivots; with
+    /// [`SolveOptions::deterministic`](crate::linear_programming::SolveOptions::deterministic),
+    /// the hash is the same on every run and platform.
+    pub pivot_hash: u64,
+    /// Time spent in parts of the solver; only collected if enabled with
+    /// [`SolveOptions::timings`](crate::linear_programming::SolveOptions::timings).
+    pub timings: Option<SolveTimings>,
+}
+
+impl SolveStats {
+    /// Total number of simplex iterations.
+    pub fn iterations(&self) -> usize {
+        self.phase1_iterations + self.phase2_iterations
+    }
+
+    /// Ratio of the nonzeros in the L and U factors to the nonzeros in the basis matrix,
+    /// averaged over all LU factorisations. 1 means that the factorisations had no fill-in.
+    pub fn average_fill_in(&self) -> f64 {
+        if self.basis_nnz == 0 {
+            1.0
+        } else {
+            self.lu_nnz as f64 / self.basis_nnz as f64
+        }
+    }
+
+    /// Mix the vars of a pivot into [`SolveStats::pivot_hash`] with the FNV-1a step, byte by
+    /// byte. Unlike the hashers of the standard library, this gives the same hash on every
+    /// platform and Rust version.
+    pub(crate) fn record_pivot(&mut self, entering_var: usize, leaving_var: Option<usize>) {
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+        let leaving = leaving_var.map_or(0, |var| var as u64 + 1);
+        for word in [entering_var as u64, leaving] {
+            for byte in word.to_le_bytes() {
+                self.pivot_hash = (self.pivot_hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+            }
+        }
+    }
+
+    pub(crate) fn record_lu_factorisation(&mut self, factor: FactorStats, dense_columns: usize) {
+        self.lu_factorisations += 1;
+        self.dense_columns += dense_columns;
+        self.basis_nnz += factor.basis_nnz;
+        self.lu_nnz += factor.lu_nnz();
+        match factor.ordering_cache_hit {
+            Some(true) => self.ordering_cache_hits += 1,
+            Some(false) => self.ordering_cache_misses += 1,
+            None => {}
+        }
+        if factor.basis_nnz > 0 {
+            self.peak_fill_in = self.peak_fill_in.max(factor.fill_ratio());
+        }
+        self.factorisations.push(factor);
+    }
+
+    pub(crate) fn record_condition_estimate(&mut self, estimate: f64) {
+        self.max_condition_estimate = Some(
+            self.max_condition_estimate
+                .map_or(estimate, |max| max.max(estimate)),
+        );
+    }
+}
+
+impl Display for SolveStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "iterations: {} (phase 1: {}, phase 2: {}), LU factorisations: {} (fill-in avg. {:.2}, peak {:.2}), degenerate pivots: {}, bound flips: {} (dual: {})",
+            self.iterations(),
+            self.phase1_iterations,
+            self.phase2_iterations,
+            self.lu_factorisations,
+            self.average_fill_in(),
+            self.peak_fill_in,
+            self.degenerate_pivots,
+            self.bound_flips,
+            self.dual_bound_flips,
+        )?;
+        if let Some(timings) = &self.timings {
+            write!(f, ", {}", timings)?;
+        }
+        Ok(())
+    }
+}
+
+/// Counters of one LU factorisation of the basis matrix.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FactorStats {
+    /// Number of nonzeros in the basis matrix.
+    pub basis_nnz: usize,
+    /// Number of nonzeros in L, without its diagonal of ones.
+    pub lower_nnz: usize,
+    /// Number of nonzeros in U, including its diagonal.
+    pub upper_nnz: usize,
+    /// Number of candidate pivots that the threshold test rejected (see
+    /// [`SolveOptions::lu_pivot_threshold`](crate::linear_programming::SolveOptions::lu_pivot_threshold)).
+    pub rejected_pivots: usize,
+    /// Number of entries that the drop tolerance left out of the factors (see
+    /// [`SolveOptions::drop_tolerance`](crate::linear_programming::SolveOptions::drop_tolerance)).
+    pub dropped_entries: usize,
+    /// Number of entries that the drop tolerance left out of the updates of the previous
+    /// factors that this factorisation replaced.
+    pub update_dropped_entries: usize,
+    /// Number of pivots equal to 1 or -1 (see
+    /// [`SolveOptions::small_pivots`](crate::linear_programming::SolveOptions::small_pivots)).
+    pub unit_pivots: usize,
+    /// Number of updates of the previous factors, one per pivot, that this factorisation
+    /// replaced.
+    pub updates: usize,
+    /// Number of basis columns whose factors were taken over from the previous factorisation
+    /// (see
+    /// [`RefactorPolicy::partial_refactor`](crate::linear_programming::RefactorPolicy::partial_refactor)).
+    pub reused_columns: usize,
+    /// The order in which the columns were eliminated (see
+    /// [`SolveOptions::ordering`](crate::linear_programming::SolveOptions::ordering)); with
+    /// Markowitz pivoting and for partial refactorisations, the order that was configured.
+    pub ordering: Ordering,
+    /// Whether the column ordering was taken from the ordering cache (see
+    /// [`SolveOptions::ordering_cache`](crate::linear_programming::SolveOptions::ordering_cache));
+    /// `None` if the factorisation did not consult it.
+    pub ordering_cache_hit: Option<bool>,
+    /// Wall-clock time of the factorisation.
+    pub time: Duration,
+    /// The largest size in bits of a coefficient of the factors, counting its numerator and
+    /// its denominator; only collected in exact arithmetic and if enabled with
+    /// [`SolveOptions::coeff_bit_stats`](crate::linear_programming::SolveOptions::coeff_bit_stats).
+    pub max_coeff_bits: Option<u64>,
+    /// The sum of the sizes in bits of the coefficients of the factors, collected like
+    /// [`FactorStats::max_coeff_bits`].
+    pub total_coeff_bits: Option<u64>,
+}
+
+impl FactorStats {
+    /// Number of nonzeros in the L and U factors. Dense columns that were left out of the
+    /// factors do not count.
+    pub fn lu_nnz(&self) -> usize {
+        self.lower_nnz + self.upper_nnz
+    }
+
+    /// Number of nonzeros in the L and U factors beyond those of the basis matrix, to compare
+    /// the fill-in of the orderings. Dense columns that were left out of the factors do not
+    /// count.
+    pub fn fill_in(&self) -> usize {
+        self.lu_nnz().saturating_sub(self.basis_nnz)
+    }
+
+    /// Ratio of the nonzeros in the L and U factors to the nonzeros in the basis matrix. 1
+    /// means that the factorisation had no fill-in.
+    pub fn fill_ratio(&self) -> f64 {
+        if self.basis_nnz == 0 {
+            1.0
+        } else {
+            self.lu_nnz() as f64 / self.basis_nnz as f64
+        }
+    }
+}
+
+/// Wall-clock time spent in parts of the solver.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolveTimings {
+    /// Choosing the entering variable (phase 2) or the leaving variable (phase 1).
+    pub pricing: Duration,
+    /// Choosing the other variable of the pivot.
+    pub ratio_test: Duration,
+    /// Computing columns and rows of the tableau with the factorised basis (FTRAN and BTRAN).
+    pub ftran_btran: Duration,
+    /// Computing LU factorisations of the basis matrix.
+    pub lu_factorisation: Duration,
+}
+
+impl Display for SolveTimings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "time in pricing: {:?}, ratio test: {:?}, FTRAN/BTRAN: {:?}, LU factorisation: {:?}",
+            self.pricing, self.ratio_test, self.ftran_btran, self.lu_factorisation
+        )
+    }
+}
@@ -0,0 +1,165 @@
+//! Checking values of the variables against the constraints and bounds of a problem.
+
+use ebi_arithmetic::{Signed, Zero};
+
+use crate::{
+    abnormal_fraction::AbnormalFraction,
+    f0_ab,
+    linear_programming::{Constraint, Variable},
+};
+
+/// A way in which a solution fails to satisfy its problem, found by
+/// [`Problem::verify`](crate::linear_programming::Problem::verify). Amounts are positive and
+/// exceed the tolerance of the check.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolutionViolation {
+    /// The left-hand side of the constraint is below its lower bound by `amount`.
+    ConstraintBelowMin {
+        /// Position of the constraint in the order in which constraints were added.
+        constraint: usize,
+        /// By how much the bound is violated.
+        amount: AbnormalFraction,
+    },
+    /// The left-hand side of the constraint is above its upper bound by `amount`.
+    ConstraintAboveMax {
+        /// Position of the constraint in the order in which constraints were added.
+        constraint: usize,
+        /// By how much the bound is violated.
+        amount: AbnormalFraction,
+    },
+    /// The value of the variable is below its lower bound by `amount`.
+    VarBelowMin {
+        /// The variable.
+        var: Variable,
+        /// By how much the bound is violated.
+        amount: AbnormalFraction,
+    },
+    /// The value of the variable is above its upper bound by `amount`.
+    VarAboveMax {
+        /// The variable.
+        var: Variable,
+        /// By how much the bound is violated.
+        amount: AbnormalFraction,
+    },
+    /// The objective value reported by the solution differs from the value computed from the
+    /// values of the variables.
+    ObjectiveMismatch {
+        /// The objective value of the solution.
+        reported: AbnormalFraction,
+        /// The objective value computed from the values of the variables.
+        computed: AbnormalFraction,
+    },
+}
+
+impl std::fmt::Display for SolutionViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SolutionViolation::ConstraintBelowMin { constraint, amount } => write!(
+                f,
+                "constraint {} is below its lower bound by {}",
+                constraint, amount
+            ),
+            SolutionViolation::ConstraintAboveMax { constraint, amount } => write!(
+                f,
+                "constraint {} is above its upper bound by {}",
+                constraint, amount
+            ),
+            SolutionViolation::VarBelowMin { var, amount } => write!(
+                f,
+                "variable {} is below its lower bound by {}",
+                var.idx(),
+                amount
+            ),
+            SolutionViolation::VarAboveMax { var, amount } => write!(
+                f,
+                "variable {} is above its upper bound by {}",
+                var.idx(),
+                amount
+            ),
+            SolutionViolation::ObjectiveMismatch { reported, computed } => write!(
+                f,
+                "objective is {} but the variables give {}",
+                reported, computed
+            ),
+        }
+    }
+}
+
+/// The bound violations of `val`, as `(below min, above max)` amounts larger than `tolerance`.
+fn bound_violation(
+    val: &AbnormalFraction,
+    min: &AbnormalFraction,
+    max: &AbnormalFraction,
+    tolerance: &AbnormalFraction,
+) -> (Option<AbnormalFraction>, Option<AbnormalFraction>) {
+    let below = min - val;
+    let above = val - max;
+    (
+        (&below > tolerance).then_some(below),
+        (&above > tolerance).then_some(above),
+    )
+}
+
+/// Check the values of the variables against their bounds and the constraints. The left-hand
+/// sides are recomputed from the constraint coefficients.
+pub(crate) fn check_values(
+    values: &[AbnormalFraction],
+    var_mins: &[AbnormalFraction],
+    var_maxs: &[AbnormalFraction],
+    constraints: &[Constraint],
+    tolerance: &AbnormalFraction,
+) -> Vec<SolutionViolation> {
+    let mut violations = vec![];
+
+    for (var, val) in values.iter().enumerate() {
+        let (below, above) = bound_violation(val, &var_mins[var], &var_maxs[var], tolerance);
+        if let Some(amount) = below {
+            violations.push(SolutionViolation::VarBelowMin {
+                var: Variable(var),
+                amount,
+            });
+        }
+        if let Some(amount) = above {
+            violations.push(SolutionViolation::VarAboveMax {
+                var: Variable(var),
+                amount,
+            });
+        }
+    }
+
+    for (c, constraint) in constraints.iter().enumerate() {
+        let mut lhs = f0_ab!();
+        for (var, coeff) in constraint.coeffs.iter() {
+            if !values[var].is_zero() {
+                lhs += coeff * &values[var];
+            }
+        }
+        let (below, above) = bound_violation(&lhs, &constraint.min, &constraint.max, tolerance);
+        if let Some(amount) = below {
+            violations.push(SolutionViolation::ConstraintBelowMin {
+                constraint: c,
+                amount,
+            });
+        }
+        if let Some(amount) = above {
+            violations.push(SolutionViolation::ConstraintAboveMax {
+                constraint: c,
+                amount,
+            });
+        }
+    }
+
+    violations
+}
+
+/// Whether `a` and `b` differ by more than `tolerance`.
+pub(crate) fn differ(
+    a: &AbnormalFraction,
+    b: &AbnormalFraction,
+    tolerance: &AbnormalFraction,
+) -> bool {
+    if a.is_infinite() || b.is_infinite() {
+        return a != b;
+    }
+    &(a - b).abs() > tolerance
+}
@@ -1,4 +1,5 @@
 use super::network_simplex_value_type::{MulWithFloat, ToBigInt};
+use crate::error::Error;
 use core::convert::From;
 use ebi_arithmetic::exact::MaybeExact;
 use ebi_arithmetic::rand::rng;
@@ -30,6 +31,7 @@ pub enum ProblemType {
 /// Enum for representing the type of supply constraints in the network
 /// - `GEQ`: The supply constraints are treated as "greater than or equal to" constraints
 /// - `LEQ`: The supply constraints are treated as strict equality constraints
+///
 /// If the sum of the supplies is zero, both types are equivalent.
 #[derive(Debug, PartialEq)]
 pub enum SupplyType {
@@ -37,6 +39,37 @@ pub enum SupplyType {
     LEQ,
 }
 
+/// Enum for representing the errors of setting up the Network Simplex algorithm
+/// - `SizeMismatch`: The graph matrix has `rows` rows instead of one for each of the `nodes` nodes
+/// - `NotSquare`: Row `row` of the graph matrix has `len` columns instead of one for each node
+/// - `SelfLoop`: The graph matrix has an arc from the node to itself
+///
+/// [`NetworkSimplex::new`] returns them as [`Error::NetworkSimplex`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetworkSimplexError {
+    SizeMismatch { nodes: usize, rows: usize },
+    NotSquare { row: usize, len: usize },
+    SelfLoop(usize),
+}
+
+impl Display for NetworkSimplexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkSimplexError::SizeMismatch { nodes, rows } => {
+                write!(f, "graph matrix has {} rows for {} nodes", rows, nodes)
+            }
+            NetworkSimplexError::NotSquare { row, len } => {
+                write!(f, "row {} of graph matrix has {} columns", row, len)
+            }
+            NetworkSimplexError::SelfLoop(node) => {
+                write!(f, "graph has an arc from node {} to itself", node)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetworkSimplexError {}
+
 /// Enum for representing the state of an arc in the spanning tree representation
 /// - `Upper`: The flow on the arc is currently equal to its capacity
 /// - `Tree`: The arc is currently part of the spanning tree
@@ -162,7 +195,7 @@ const EPSILON: f64 = 1e-15;
 /// .into_iter()
 /// .map(|row| row.into_iter().map(|x| x.map(Exact64)).collect())
 /// .collect();
-/// let mut ns = NetworkSimplex::new(&graph_and_costs, &supply, false, false);
+/// let mut ns = NetworkSimplex::new(&graph_and_costs, &supply, false, false).unwrap();
 /// if ns.run(false) == ProblemType::Optimal {
 ///     result = ns.get_result().unwrap();
 /// }
@@ -234,7 +267,6 @@ where
         + Signed
         + PartialEq
         + PartialOrd
-        + ?Sized
         + Display
         + Debug
         + From<i32>
@@ -260,23 +292,39 @@ where
     ///
     /// # Returns
     /// A new instance of `NetworkSimplex` initialized with the given graph structure and parameters.
+    ///
+    /// # Errors
+    /// [`Error::NetworkSimplex`] with
+    /// - [`NetworkSimplexError::SizeMismatch`] if the matrix does not have a row for each node.
+    /// - [`NetworkSimplexError::NotSquare`] if a row of the matrix does not have a column for
+    ///   each node.
+    /// - [`NetworkSimplexError::SelfLoop`] if the matrix has an arc from a node to itself.
     pub fn new(
-        graph_and_costs: &Vec<Vec<Option<T>>>,
+        graph_and_costs: &[Vec<Option<T>>],
         supply: &Vec<T>,
         arc_mixing: bool,
         greater_eq_supply: bool,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         let node_num = supply.len();
 
         // Ensure that the graph dimensions match the number of nodes
-        assert!(
-            graph_and_costs.len() == node_num,
-            "Graph size and supply size mismatch"
-        );
+        if graph_and_costs.len() != node_num {
+            return Err(NetworkSimplexError::SizeMismatch {
+                nodes: node_num,
+                rows: graph_and_costs.len(),
+            }
+            .into());
+        }
 
         // Ensure that the graph is square (all rows must be the same size)
-        for row in graph_and_costs.iter() {
-            assert!(row.len() == node_num, "Graph matrix not square");
+        for (row, costs) in graph_and_costs.iter().enumerate() {
+            if costs.len() != node_num {
+                return Err(NetworkSimplexError::NotSquare {
+                    row,
+                    len: costs.len(),
+                }
+                .into());
+            }
         }
 
         let node_id: Vec<usize> = (0..node_num).collect();
@@ -286,11 +334,13 @@ where
         let mut source = vec![];
         let mut target = vec![];
         let mut cost = vec![];
-        for i in 0..node_num {
-            for j in 0..node_num {
-                if let Some(c) = &graph_and_costs[i][j] {
+        for (i, row) in graph_and_costs.iter().enumerate() {
+            for (j, c) in row.iter().enumerate() {
+                if let Some(c) = c {
                     // Could as well allow for self loops: cost>0 -> ignore, cost<0 -> Unbounded if connected to some supply
-                    assert!(i != j, "Tried to add arc from node to itself");
+                    if i == j {
+                        return Err(NetworkSimplexError::SelfLoop(i).into());
+                    }
                     source.push(i);
                     target.push(j);
                     cost.push((*c).clone());
@@ -390,7 +440,7 @@ where
             supply_type,
         };
 
-        ns
+        Ok(ns)
     }
 
     /// DEBUG function
@@ -432,7 +482,7 @@ where
         ));
 
         for i in 0..self.all_node_num {
-            if self.parent[i] != None {
+            if self.parent[i].is_some() {
                 let parent = self.parent[i].unwrap();
                 let direction = &self.predecessor_direction[i];
                 let flow = &self.flow[self.predecessor[i].unwrap()];
@@ -551,7 +601,7 @@ where
 
         self.problem_type = Some(ProblemType::Optimal);
         log::info!("Optimal solution found");
-        return ProblemType::Optimal;
+        ProblemType::Optimal
     }
 
     /// Internal function:
@@ -881,7 +931,7 @@ where
             self.u_in = second;
             self.v_in = first;
         }
-        return result != 0;
+        result != 0
     }
 
     /// Internal function:
@@ -889,7 +939,7 @@ where
     /// All potentials of the successors of u_in are updated
     fn update_potential(&mut self) {
         let mut sigma = -self.cost[self.in_arc].clone();
-        sigma *= &(self.predecessor_direction[self.u_in].value());
+        sigma *= self.predecessor_direction[self.u_in].value();
         sigma += &self.pi[self.v_in];
         sigma -= &self.pi[self.u_in];
 
@@ -978,8 +1028,7 @@ where
         // set up aticficial arcs (i, root node) for b_i >= 0, (root node, i) for b_i < 0
         if self.sum_supply == T::zero() {
             self.search_arc_num = self.arc_num;
-            let mut e = self.arc_num;
-            for u in 0..self.node_num {
+            for (u, e) in (0..self.node_num).zip(self.arc_num..) {
                 self.parent[u] = Some(self.root);
                 self.predecessor[u] = Some(e);
                 self.thread[u] = u + 1;
@@ -1002,7 +1051,6 @@ where
                     self.flow[e] = -self.supply[u].clone();
                     self.cost[e] = art_cost.clone();
                 }
-                e += 1;
             }
         } else if self.sum_supply > T::zero() {
             // LEQ supply constraints
@@ -1081,7 +1129,7 @@ where
             }
             self.all_arc_num = f;
         }
-        return true;
+        true
     }
 
     /// Internal function:
@@ -1269,7 +1317,7 @@ where
         };
         let last_successor_out = self.last_successor[self.u_out];
         let mut u = Some(self.v_in);
-        while u != None && self.last_successor[u.unwrap()] == self.v_in {
+        while u.is_some() && self.last_successor[u.unwrap()] == self.v_in {
             self.last_successor[u.unwrap()] = last_successor_out;
             u = self.parent[u.unwrap()];
         }
@@ -1277,7 +1325,7 @@ where
         // update last_successor from v_out towards the root
         if self.join != old_reverse_thread && self.v_in != old_reverse_thread {
             u = Some(self.v_out);
-            while u != None
+            while u.is_some()
                 && u != up_limit_out
                 && self.last_successor[u.unwrap()] == old_last_successor
             {
@@ -1286,7 +1334,7 @@ where
             }
         } else if last_successor_out != old_last_successor {
             u = Some(self.v_out);
-            while u != None
+            while u.is_some()
                 && u != up_limit_out
                 && self.last_successor[u.unwrap()] == old_last_successor
             {
@@ -1333,37 +1381,40 @@ where
     /// }
     /// ```
     pub fn get_result(&self) -> Option<T> {
-        if let Some(problem_type) = &self.problem_type {
-            if problem_type == &ProblemType::Optimal {
-                let flow_cost = self.flow.iter().zip(self.cost.iter());
-                let mut result = T::zero();
-                for (flow, cost) in flow_cost {
-                    let mut arc_result = flow.clone();
-                    arc_result *= cost;
-                    result += &arc_result;
-                }
-                return Some(result);
+        if self.problem_type == Some(ProblemType::Optimal) {
+            let flow_cost = self.flow.iter().zip(self.cost.iter());
+            let mut result = T::zero();
+            for (flow, cost) in flow_cost {
+                let mut arc_result = flow.clone();
+                arc_result *= cost;
+                result += &arc_result;
             }
+            return Some(result);
         }
 
-        return None;
+        None
     }
 
-    pub fn get_bigint_result(&self) -> Option<Integer> {
-        if let Some(problem_type) = &self.problem_type {
-            if problem_type == &ProblemType::Optimal {
-                let flow_cost = self.flow.iter().zip(self.cost.iter());
-                let mut result = Integer::zero();
-                for (flow, cost) in flow_cost {
-                    let mut arc_result = flow.to_big_int();
-                    arc_result *= cost.to_big_int();
-                    result += arc_result;
-                }
-                return Some(result);
+    /// Retrieves the total cost of the flow as an integer if the problem is optimal, like
+    /// [`NetworkSimplex::get_result`].
+    ///
+    /// # Errors
+    /// [`Error::Arithmetic`] with
+    /// [`ArithmeticError::NotAnInteger`](crate::error::ArithmeticError::NotAnInteger) if the
+    /// value type has no integer values.
+    pub fn get_bigint_result(&self) -> Result<Option<Integer>, Error> {
+        if self.problem_type == Some(ProblemType::Optimal) {
+            let flow_cost = self.flow.iter().zip(self.cost.iter());
+            let mut result = Integer::zero();
+            for (flow, cost) in flow_cost {
+                let mut arc_result = flow.to_big_int()?;
+                arc_result *= cost.to_big_int()?;
+                result += arc_result;
             }
+            return Ok(Some(result));
         }
 
-        None
+        Ok(None)
     }
 
     /// Retrieves the flow values of the network.
@@ -1382,9 +1433,9 @@ where
         self.cost.clone()
     }
 
-    /// Internal function: Retrieves the supply values of the network.
+    /// Internal function: Retrieves the largest cost of an arc, or zero if there are no arcs.
     fn find_max_cost(&self) -> T {
-        select_max(&self.cost).expect("Cost vector cannot be empty")
+        select_max(&self.cost).unwrap_or_else(T::zero)
     }
 }
 
@@ -1403,7 +1454,10 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::network_simplex::NetworkSimplex;
+    use crate::{
+        error::Error,
+        network_simplex::{NetworkSimplex, NetworkSimplexError, ProblemType},
+    };
     use ebi_arithmetic::malachite::Integer;
 
     #[test]
@@ -1417,9 +1471,10 @@ mod tests {
             vec![None, None, None, None, Some(2)],
             vec![None, None, Some(3), None, None],
         ];
-        let mut ns = NetworkSimplex::new(&graph_and_costs, &supply, true, false);
+        let mut ns = NetworkSimplex::new(&graph_and_costs, &supply, true, false).unwrap();
         _ = ns.run(false);
         assert_eq!(ns.get_result().unwrap(), 123);
+        assert_eq!(ns.get_bigint_result().unwrap(), Some(Integer::from(123)));
     }
 
     #[test]
@@ -1434,18 +1489,47 @@ mod tests {
             vec![None, None, Some(3), None, None],
         ]
         .into_iter()
-        .map(|row| {
-            row.into_iter()
-                .map(|x| x.map(|cost| Integer::from(cost)))
-                .collect()
-        })
+        .map(|row| row.into_iter().map(|x| x.map(Integer::from)).collect())
         .collect();
 
-        let mut ns = NetworkSimplex::new(&graph_and_costs, &supply, true, false);
+        let mut ns = NetworkSimplex::new(&graph_and_costs, &supply, true, false).unwrap();
         _ = ns.run(false);
         assert_eq!(ns.get_result().unwrap(), Integer::from(123));
     }
 
+    #[test]
+    fn network_simplex_errors() {
+        let error = |graph_and_costs: &[Vec<Option<i64>>], supply: Vec<i64>| {
+            let result = NetworkSimplex::new(graph_and_costs, &supply, false, false);
+            match result {
+                Err(Error::NetworkSimplex(error)) => Some(error),
+                _ => None,
+            }
+        };
+        let graph_and_costs: Vec<Vec<Option<i64>>> = vec![vec![None, Some(1)], vec![None]];
+        assert_eq!(
+            error(&graph_and_costs, vec![1, -1, 0]),
+            Some(NetworkSimplexError::SizeMismatch { nodes: 3, rows: 2 })
+        );
+        assert_eq!(
+            error(&graph_and_costs, vec![1, -1]),
+            Some(NetworkSimplexError::NotSquare { row: 1, len: 1 })
+        );
+        let graph_and_costs = vec![vec![Some(1i64), None], vec![None, None]];
+        assert_eq!(
+            error(&graph_and_costs, vec![1, -1]),
+            Some(NetworkSimplexError::SelfLoop(0))
+        );
+
+        // Without arcs, only a network without supplies is feasible.
+        let graph_and_costs: Vec<Vec<Option<i64>>> = vec![vec![None, None], vec![None, None]];
+        let mut ns = NetworkSimplex::new(&graph_and_costs, &vec![0, 0], false, false).unwrap();
+        assert_eq!(ns.run(false), ProblemType::Optimal);
+        assert_eq!(ns.get_result(), Some(0));
+        let mut ns = NetworkSimplex::new(&graph_and_costs, &vec![1, -1], false, false).unwrap();
+        assert_eq!(ns.run(false), ProblemType::Infeasible);
+    }
+
     #[test]
     fn network_simplex_float() {
         let supply: Vec<f64> = vec![20, 0, 0, -5, -14]
@@ -1464,7 +1548,7 @@ mod tests {
         .map(|row| row.into_iter().map(|x| x.map(|cost| cost.into())).collect())
         .collect();
 
-        let mut ns = NetworkSimplex::new(&graph_and_costs, &supply, true, false);
+        let mut ns = NetworkSimplex::new(&graph_and_costs, &supply, true, false).unwrap();
         _ = ns.run(false);
         let result = ns.get_result().unwrap();
         assert_eq!(result, 123.0);
@@ -1,9 +1,11 @@
 use ebi_arithmetic::{
-    Fraction,
+    Fraction, MaybeExact,
     fraction::fraction_f64::FractionF64,
     malachite::{Integer, Natural},
 };
 
+use crate::error::ArithmeticError;
+
 pub trait MulWithFloat {
     fn mul_with_float(self, rhs: &f64) -> Self;
 }
@@ -48,37 +50,38 @@ impl MulWithFloat for FractionF64 {
     }
 }
 
+/// Conversion of the values of the network simplex to integers, for types that have them.
 pub trait ToBigInt {
-    fn to_big_int(&self) -> Integer;
+    fn to_big_int(&self) -> Result<Integer, ArithmeticError>;
 }
 
 impl ToBigInt for f64 {
-    // this should never occur. it is necessary to make network simplex work on both integers and floats
-    fn to_big_int(&self) -> Integer {
-        panic!("Cannot multiply values of different types");
+    fn to_big_int(&self) -> Result<Integer, ArithmeticError> {
+        Err(ArithmeticError::NotAnInteger)
     }
 }
 
 impl ToBigInt for i64 {
-    fn to_big_int(&self) -> Integer {
-        Integer::from(*self)
+    fn to_big_int(&self) -> Result<Integer, ArithmeticError> {
+        Ok(Integer::from(*self))
     }
 }
 
 impl ToBigInt for i128 {
-    fn to_big_int(&self) -> Integer {
-        Integer::from(*self)
+    fn to_big_int(&self) -> Result<Integer, ArithmeticError> {
+        Ok(Integer::from(*self))
     }
 }
 
 impl ToBigInt for Integer {
-    fn to_big_int(&self) -> Integer {
-        self.clone()
+    fn to_big_int(&self) -> Result<Integer, ArithmeticError> {
+        Ok(self.clone())
     }
 }
 
 impl ToBigInt for Fraction {
-    fn to_big_int(&self) -> Integer {
-        panic!("Cannot multiply values of different types");
+    fn to_big_int(&self) -> Result<Integer, ArithmeticError> {
+        let exact = self.exact_ref().map_err(|_| ArithmeticError::NotExact)?;
+        Integer::try_from(exact).map_err(|_| ArithmeticError::NotAnInteger)
     }
 }
@@ -0,0 +1,519 @@
+//! Transportation problems, solved with the network simplex if they fit it, and with the
+//! linear programming solver otherwise.
+
+use std::fmt::Display;
+
+use ebi_arithmetic::{
+    Fraction, MaybeExact, One, Zero,
+    malachite::{Integer, Natural, base::num::arithmetic::traits::Lcm, rational::Rational},
+};
+
+use crate::{
+    abnormal_fraction::AbnormalFraction,
+    error::Error,
+    f1_ab,
+    linear_programming::{self, ComparisonOp, OptimisationDirection, Problem},
+    network_simplex::{NetworkSimplex, ProblemType},
+};
+
+/// How [`TransportationProblem::solve`] chooses its backend.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// The network simplex if the problem fits it (see
+    /// [`TransportationProblem::fits_network_simplex`]), the linear programming solver
+    /// otherwise.
+    #[default]
+    Auto,
+    /// The linear programming solver.
+    ForceLp,
+    /// The network simplex, or [`TransportError::NotANetwork`] if the problem does not fit it.
+    ForceNetworkSimplex,
+}
+
+/// The solver that solved a transportation problem.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Lp,
+    NetworkSimplex,
+}
+
+/// An error of [`TransportationProblem::solve`], which returns it as [`Error::Transport`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransportError {
+    /// The supplies do not add up to the demands.
+    Unbalanced,
+    /// No flow over the cells meets the supplies, the demands and the bounds of the cells.
+    Infeasible,
+    /// The problem has bounds on cells or values that are not exact, which the network
+    /// simplex does not support.
+    NotANetwork,
+    /// The linear programming solver failed.
+    Lp(linear_programming::Error),
+}
+
+impl Display for TransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransportError::Unbalanced => "supplies and demands are unbalanced".fmt(f),
+            TransportError::Infeasible => "transportation problem is infeasible".fmt(f),
+            TransportError::NotANetwork => {
+                "transportation problem does not fit the network simplex".fmt(f)
+            }
+            TransportError::Lp(error) => write!(f, "linear programming: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for TransportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TransportError::Lp(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// A cell of a transportation problem: the moves of goods from a supply to a demand.
+#[derive(Clone, Debug)]
+struct Cell {
+    supply: usize,
+    demand: usize,
+    cost: AbnormalFraction,
+    lower: AbnormalFraction,
+    capacity: Option<AbnormalFraction>,
+}
+
+/// A transportation problem: move the goods of the supplies to the demands over the cells,
+/// each of which joins a supply and a demand at a cost per unit of goods moved, at the
+/// smallest total cost. The supplies must add up to the demands.
+///
+/// ```
+/// # use ebi_optimisation::transportation::*;
+/// # use ebi_arithmetic::Fraction;
+/// let f = |n: usize| Fraction::from(n);
+/// let mut problem = TransportationProblem::new(vec![f(3), f(2)], vec![f(4), f(1)]);
+/// problem.add_cell(0, 0, f(1));
+/// problem.add_cell(0, 1, f(2));
+/// problem.add_cell(1, 0, f(3));
+/// problem.add_cell(1, 1, f(1));
+/// let solution = problem.solve(Strategy::Auto).unwrap();
+/// assert_eq!(solution.backend, Backend::NetworkSimplex);
+/// // 3 units over cell 0, 1 over cell 2 and 1 over cell 3.
+/// assert_eq!(solution.total_cost, f(7));
+/// ```
+#[derive(Clone, Debug)]
+pub struct TransportationProblem {
+    supplies: Vec<AbnormalFraction>,
+    demands: Vec<AbnormalFraction>,
+    cells: Vec<Cell>,
+}
+
+impl TransportationProblem {
+    pub fn new(supplies: Vec<Fraction>, demands: Vec<Fraction>) -> Self {
+        Self {
+            supplies: supplies.into_iter().map(AbnormalFraction::Normal).collect(),
+            demands: demands.into_iter().map(AbnormalFraction::Normal).collect(),
+            cells: vec![],
+        }
+    }
+
+    /// Add a cell from `supply` to `demand` of `cost` per unit, without bounds. Returns its
+    /// index, the position of its flow in [`TransportSolution::flows`].
+    ///
+    /// # Panics
+    ///
+    /// If `supply` or `demand` is out of range.
+    pub fn add_cell(&mut self, supply: usize, demand: usize, cost: Fraction) -> usize {
+        assert!(supply < self.supplies.len(), "no supply {}", supply);
+        assert!(demand < self.demands.len(), "no demand {}", demand);
+        self.cells.push(Cell {
+            supply,
+            demand,
+            cost: AbnormalFraction::Normal(cost),
+            lower: AbnormalFraction::zero(),
+            capacity: None,
+        });
+        self.cells.len() - 1
+    }
+
+    /// Bound the flow over the cell with index `cell` from below by `lower`, and from above by
+    /// `capacity` if it is given.
+    ///
+    /// # Panics
+    ///
+    /// If there is no cell with index `cell`.
+    pub fn set_bounds(&mut self, cell: usize, lower: Fraction, capacity: Option<Fraction>) {
+        let cell = &mut self.cells[cell];
+        cell.lower = AbnormalFraction::Normal(lower);
+        cell.capacity = capacity.map(AbnormalFraction::Normal);
+    }
+
+    pub fn num_cells(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Whether the network simplex can solve the problem: no cell has bounds, and all values
+    /// are exact, so that they are integers once scaled by the common denominators.
+    pub fn fits_network_simplex(&self) -> bool {
+        self.cells
+            .iter()
+            .all(|cell| cell.lower.is_zero() && cell.capacity.is_none())
+            && (self.supplies.iter())
+                .chain(&self.demands)
+                .chain(self.cells.iter().map(|cell| &cell.cost))
+                .all(|value| value.exact_ref().is_ok())
+    }
+
+    /// Solve the problem with the backend chosen by `strategy`. Both backends give the same
+    /// total cost, and an optimal flow, which may differ if several are optimal.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Transport`] with [`TransportError::Unbalanced`] if the supplies do not add up
+    /// to the demands, [`TransportError::Infeasible`] if no flow meets them and the bounds of
+    /// the cells, [`TransportError::NotANetwork`] if the network simplex is forced on a problem
+    /// that does not fit it, and [`TransportError::Lp`] if the linear programming solver fails
+    /// otherwise.
+    pub fn solve(&self, strategy: Strategy) -> Result<TransportSolution, Error> {
+        let total_supply = self.supplies.iter().cloned().sum::<AbnormalFraction>();
+        let total_demand = self.demands.iter().cloned().sum::<AbnormalFraction>();
+        if total_supply != total_demand {
+            return Err(TransportError::Unbalanced.into());
+        }
+        let (backend, flows) = match strategy {
+            Strategy::ForceLp => (Backend::Lp, self.solve_lp()?),
+            Strategy::ForceNetworkSimplex if !self.fits_network_simplex() => {
+                return Err(TransportError::NotANetwork.into());
+            }
+            Strategy::Auto if !self.fits_network_simplex() => (Backend::Lp, self.solve_lp()?),
+            Strategy::ForceNetworkSimplex | Strategy::Auto => {
+                (Backend::NetworkSimplex, self.solve_network_simplex()?)
+            }
+        };
+
+        let mut total_cost = AbnormalFraction::zero();
+        for (cell, flow) in self.cells.iter().zip(&flows) {
+            total_cost += &cell.cost * flow;
+        }
+        let (supply_potentials, demand_potentials) = self.potentials(&flows);
+        Ok(TransportSolution {
+            backend,
+            total_cost: normal(total_cost),
+            flows: flows.into_iter().map(normal).collect(),
+            supply_potentials: supply_potentials.into_iter().map(normal).collect(),
+            demand_potentials: demand_potentials.into_iter().map(normal).collect(),
+        })
+    }
+
+    /// An optimal flow per cell, by the linear programming solver.
+    fn solve_lp(&self) -> Result<Vec<AbnormalFraction>, TransportError> {
+        let mut problem = Problem::new(OptimisationDirection::Minimise);
+        let vars = (self.cells.iter())
+            .map(|cell| {
+                let capacity = cell.capacity.clone();
+                let bounds = (
+                    cell.lower.clone(),
+                    capacity.unwrap_or(AbnormalFraction::infinity()),
+                );
+                problem.add_var(cell.cost.clone(), bounds)
+            })
+            .collect::<Vec<_>>();
+        let mut supply_rows = vec![vec![]; self.supplies.len()];
+        let mut demand_rows = vec![vec![]; self.demands.len()];
+        for (cell, &var) in self.cells.iter().zip(&vars) {
+            supply_rows[cell.supply].push((var, f1_ab!()));
+            demand_rows[cell.demand].push((var, f1_ab!()));
+        }
+        let rows = (supply_rows.into_iter().zip(&self.supplies))
+            .chain(demand_rows.into_iter().zip(&self.demands));
+        for (row, amount) in rows {
+            problem
+                .add_constraint(row, ComparisonOp::Eq, amount.clone())
+                .map_err(TransportError::Lp)?;
+        }
+        let solution = problem.solve().map_err(|error| match error {
+            linear_programming::Error::Infeasible(_) => TransportError::Infeasible,
+            error => TransportError::Lp(error),
+        })?;
+        Ok(vars.iter().map(|&var| solution[var].clone()).collect())
+    }
+
+    /// An optimal flow per cell, by the network simplex over integers: the supplies and demands
+    /// are scaled by their common denominator, and the costs by theirs. Of several cells that
+    /// join the same supply and demand, only the first of the cheapest carries flow.
+    fn solve_network_simplex(&self) -> Result<Vec<AbnormalFraction>, TransportError> {
+        let (amounts, amount_scale) = scaled_integers(self.supplies.iter().chain(&self.demands));
+        let (costs, _) = scaled_integers(self.cells.iter().map(|cell| &cell.cost));
+        let m = self.supplies.len();
+        let n = m + self.demands.len();
+        let mut cheapest: Vec<Vec<Option<usize>>> = vec![vec![None; n]; n];
+        for (index, cell) in self.cells.iter().enumerate() {
+            let entry = &mut cheapest[cell.supply][m + cell.demand];
+            if entry.is_none_or(|other| cell.cost < self.cells[other].cost) {
+                *entry = Some(index);
+            }
+        }
+
+        let mut flows = vec![AbnormalFraction::zero(); self.cells.len()];
+        if n == 0 {
+            // The network simplex needs nodes.
+            return Ok(flows);
+        }
+        let graph_and_costs = (cheapest.iter())
+            .map(|row| {
+                (row.iter())
+                    .map(|cell| cell.map(|cell| costs[cell].clone()))
+                    .collect()
+            })
+            .collect::<Vec<Vec<Option<Integer>>>>();
+        let supply = (amounts.iter().enumerate())
+            .map(|(node, amount)| if node < m { amount.clone() } else { -amount })
+            .collect::<Vec<_>>();
+        let mut network_simplex =
+            NetworkSimplex::new(&graph_and_costs, &supply, false, false).unwrap(); // Cannot fail: the matrix is square and has no loops
+        if network_simplex.run(false) != ProblemType::Optimal {
+            // The costs per unit are finite and the flows bounded by the supplies.
+            return Err(TransportError::Infeasible);
+        }
+        // The arcs are in the order of the rows of the matrix, then of the columns.
+        let arcs = cheapest.iter().flatten().flatten();
+        let scale = Integer::from(amount_scale);
+        for (&cell, flow) in arcs.zip(network_simplex.get_flow()) {
+            let flow = Rational::from_integers(flow, scale.clone());
+            flows[cell] = AbnormalFraction::Normal(Fraction::try_to_exact(flow).unwrap()); // Cannot fail for exact values
+        }
+        Ok(flows)
+    }
+
+    /// Dual values of an optimal flow: a potential per supply and per demand, whose sum does
+    /// not exceed the cost of any cell that may carry more flow, and is at least that of any
+    /// cell that may carry less. They are the shortest distances in the residual network of
+    /// the flow, which has no cycle of negative cost as the flow is optimal, so both backends
+    /// give potentials of the same form.
+    fn potentials(
+        &self,
+        flows: &[AbnormalFraction],
+    ) -> (Vec<AbnormalFraction>, Vec<AbnormalFraction>) {
+        let m = self.supplies.len();
+        let n = m + self.demands.len();
+        // The arcs of the residual network: forward over the cells that may carry more flow,
+        // backward over those that may carry less.
+        let mut arcs = vec![];
+        for (cell, flow) in self.cells.iter().zip(flows) {
+            if cell
+                .capacity
+                .as_ref()
+                .is_none_or(|capacity| flow < capacity)
+            {
+                arcs.push((cell.supply, m + cell.demand, cell.cost.clone()));
+            }
+            if flow > &cell.lower {
+                arcs.push((m + cell.demand, cell.supply, -&cell.cost));
+            }
+        }
+        // Bellman-Ford from a virtual node with arcs of cost 0 to all nodes.
+        let mut distances = vec![AbnormalFraction::zero(); n];
+        for _ in 0..n {
+            let mut changed = false;
+            for (from, to, cost) in &arcs {
+                let distance = &distances[*from] + cost;
+                if distance < distances[*to] {
+                    distances[*to] = distance;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        let demand_potentials = distances.split_off(m);
+        let supply_potentials = distances.into_iter().map(|distance| -distance).collect();
+        (supply_potentials, demand_potentials)
+    }
+}
+
+/// A solution of a [`TransportationProblem`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransportSolution {
+    /// The solver that solved the problem.
+    pub backend: Backend,
+    pub total_cost: Fraction,
+    /// The flow per cell, in the order in which the cells were added.
+    pub flows: Vec<Fraction>,
+    /// The dual value of the constraint of each supply: with those of the demands, their sum
+    /// for a cell is at most its cost if it may carry more flow, and equal to it if it carries
+    /// flow strictly within its bounds. Without bounds on the cells, the supplies and demands
+    /// weighted by their potentials add up to the total cost.
+    pub supply_potentials: Vec<Fraction>,
+    /// The dual value of the constraint of each demand.
+    pub demand_potentials: Vec<Fraction>,
+}
+
+/// `values` as integers, once multiplied by the least common multiple of their denominators,
+/// and that multiple. The values must be exact.
+fn scaled_integers<'a>(
+    values: impl Iterator<Item = &'a AbnormalFraction> + Clone,
+) -> (Vec<Integer>, Natural) {
+    let exact = |value: &'a AbnormalFraction| value.exact_ref().unwrap(); // Cannot fail for a network
+    let mut scale = Natural::from(1u32);
+    for value in values.clone() {
+        scale = scale.lcm(exact(value).denominator_ref());
+    }
+    let factor = Rational::from(&scale);
+    let integers = values
+        .map(|value| Integer::try_from(exact(value) * &factor).unwrap()) // Cannot fail
+        .collect();
+    (integers, scale)
+}
+
+fn normal(value: AbnormalFraction) -> Fraction {
+    match value {
+        AbnormalFraction::Normal(value) => value,
+        _ => unreachable!("the values of a transportation problem are finite"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ebi_arithmetic::One;
+
+    use crate::rand::{Rng, SeedableRng, rngs::StdRng};
+
+    fn f(n: usize) -> Fraction {
+        Fraction::from(n)
+    }
+
+    /// A balanced problem of `m` supplies and `n` demands with random integer amounts, with all
+    /// cells at random costs in halves.
+    fn random_problem(rng: &mut StdRng, m: usize, n: usize) -> TransportationProblem {
+        let supplies = (0..m)
+            .map(|_| rng.random_range(0..20usize))
+            .collect::<Vec<_>>();
+        let mut demands = vec![0usize; n];
+        for _ in 0..supplies.iter().sum::<usize>() {
+            demands[rng.random_range(0..n)] += 1;
+        }
+        let mut problem = TransportationProblem::new(
+            supplies.into_iter().map(Fraction::from).collect(),
+            demands.into_iter().map(Fraction::from).collect(),
+        );
+        for i in 0..m {
+            for j in 0..n {
+                let cost = Fraction::from((rng.random_range(0..40usize), 2));
+                problem.add_cell(i, j, cost);
+            }
+        }
+        problem
+    }
+
+    /// Check that `solution` is a flow of `problem` whose potentials prove it optimal.
+    fn check(problem: &TransportationProblem, solution: &TransportSolution) {
+        let zero = AbnormalFraction::zero();
+        let mut supplied = vec![zero.clone(); problem.supplies.len()];
+        let mut demanded = vec![zero.clone(); problem.demands.len()];
+        let mut total = zero.clone();
+        for (cell, flow) in problem.cells.iter().zip(&solution.flows) {
+            let flow = AbnormalFraction::Normal(flow.clone());
+            assert!(flow >= zero);
+            supplied[cell.supply] += flow.clone();
+            demanded[cell.demand] += flow.clone();
+            total += &cell.cost * &flow;
+            let mut potential =
+                AbnormalFraction::Normal(solution.supply_potentials[cell.supply].clone());
+            potential += AbnormalFraction::Normal(solution.demand_potentials[cell.demand].clone());
+            assert!(potential <= cell.cost);
+        }
+        assert_eq!(supplied, problem.supplies);
+        assert_eq!(demanded, problem.demands);
+        assert_eq!(normal(total.clone()), solution.total_cost);
+
+        let mut dual = zero;
+        for (amount, potential) in problem.supplies.iter().zip(&solution.supply_potentials) {
+            dual += amount * &AbnormalFraction::Normal(potential.clone());
+        }
+        for (amount, potential) in problem.demands.iter().zip(&solution.demand_potentials) {
+            dual += amount * &AbnormalFraction::Normal(potential.clone());
+        }
+        assert_eq!(dual, total);
+    }
+
+    /// The error of the transportation module that `result` failed with, if any.
+    fn transport_error(result: Result<TransportSolution, Error>) -> Option<TransportError> {
+        match result {
+            Err(Error::Transport(error)) => Some(error),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn transportation_backends_agree() {
+        let mut rng = StdRng::seed_from_u64(716);
+        for _ in 0..50 {
+            let m = rng.random_range(1..6);
+            let n = rng.random_range(1..6);
+            let problem = random_problem(&mut rng, m, n);
+            assert!(problem.fits_network_simplex());
+            let lp = problem.solve(Strategy::ForceLp).unwrap();
+            let network = problem.solve(Strategy::ForceNetworkSimplex).unwrap();
+            assert_eq!(lp.backend, Backend::Lp);
+            assert_eq!(network.backend, Backend::NetworkSimplex);
+            assert_eq!(lp.total_cost, network.total_cost);
+            check(&problem, &lp);
+            check(&problem, &network);
+        }
+    }
+
+    #[test]
+    fn transportation_auto() {
+        let mut rng = StdRng::seed_from_u64(7160);
+        let mut problem = random_problem(&mut rng, 3, 4);
+        let auto = problem.solve(Strategy::Auto).unwrap();
+        assert_eq!(auto.backend, Backend::NetworkSimplex);
+
+        // A capacity does not fit the network simplex; the linear programming solver respects
+        // it.
+        let cell = (0..problem.num_cells())
+            .find(|&cell| auto.flows[cell] > f(1))
+            .unwrap();
+        problem.set_bounds(cell, Fraction::zero(), Some(Fraction::one()));
+        assert!(!problem.fits_network_simplex());
+        let bounded = problem.solve(Strategy::Auto).unwrap();
+        assert_eq!(bounded.backend, Backend::Lp);
+        assert!(bounded.flows[cell] <= Fraction::one());
+        assert!(bounded.total_cost >= auto.total_cost);
+        assert_eq!(
+            transport_error(problem.solve(Strategy::ForceNetworkSimplex)),
+            Some(TransportError::NotANetwork)
+        );
+    }
+
+    #[test]
+    fn transportation_errors() {
+        let mut problem = TransportationProblem::new(vec![f(2)], vec![f(3)]);
+        problem.add_cell(0, 0, f(1));
+        assert_eq!(
+            transport_error(problem.solve(Strategy::Auto)),
+            Some(TransportError::Unbalanced)
+        );
+
+        // The only cell carries at most 1 of the 2 units.
+        let mut problem = TransportationProblem::new(vec![f(2)], vec![f(2)]);
+        let cell = problem.add_cell(0, 0, f(1));
+        problem.set_bounds(cell, Fraction::zero(), Some(Fraction::one()));
+        assert_eq!(
+            transport_error(problem.solve(Strategy::Auto)),
+            Some(TransportError::Infeasible)
+        );
+
+        // No cell to the second demand.
+        let mut problem = TransportationProblem::new(vec![f(2)], vec![f(1), f(1)]);
+        problem.add_cell(0, 0, f(1));
+        for strategy in [Strategy::ForceLp, Strategy::ForceNetworkSimplex] {
+            assert_eq!(
+                transport_error(problem.solve(strategy)),
+                Some(TransportError::Infeasible)
+            );
+        }
+    }
+}